@@ -1,4 +1,7 @@
 pub mod develop;
 pub mod migrate;
+pub mod rebuild;
 pub mod seed;
 pub mod serve;
+pub mod verify_chain;
+pub mod worker;
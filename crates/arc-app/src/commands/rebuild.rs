@@ -0,0 +1,60 @@
+//! `arc rebuild` — rebuilds projections from the event store while the
+//! server keeps serving them.
+//!
+//! Each projection is rebuilt through
+//! [`ProjectionEngine::rebuild_projection`]: replayed into a shadow table
+//! (`users_view__next`), caught up to the head of the log and swapped in
+//! atomically, so `users_view` is never empty. The replaced table is kept
+//! as `users_view__prev` until the next rebuild; `--rollback` swaps it back
+//! in and catches it up.
+//!
+//! ```text
+//! arc rebuild [<projection>...] [--rollback]
+//! ```
+//!
+//! Without names every registered projection is rebuilt (or rolled back),
+//! one at a time.
+
+use crate::helpers::{config, es_stack};
+use arc_core::read_model_store::ReadModelStore;
+use arc_es_sqlite::SqliteReadModelStore;
+use std::io;
+use std::sync::Arc;
+
+pub async fn run(args: &[String]) -> io::Result<()> {
+    crate::check_database_health();
+
+    let db_url = config::database_url();
+    let event_store = es_stack::event_store(&db_url)
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to init event store: {e}")))?;
+    let read_model_store: Arc<dyn ReadModelStore> = Arc::new(
+        SqliteReadModelStore::new(&db_url)
+            .await
+            .map_err(|e| io::Error::other(format!("Failed to init read-model store: {e}")))?,
+    );
+    let engine = es_stack::projection_engine(&db_url, &event_store, read_model_store)
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to init projection engine: {e}")))?;
+
+    let rollback = args.iter().any(|a| a == "--rollback");
+    let mut names: Vec<String> = args
+        .iter()
+        .skip(2)
+        .filter(|a| !a.starts_with("--"))
+        .cloned()
+        .collect();
+    if names.is_empty() {
+        names = engine.projection_names();
+    }
+
+    for name in &names {
+        let (action, result) = if rollback {
+            ("roll back", engine.rollback_projection(name).await)
+        } else {
+            ("rebuild", engine.rebuild_projection(name).await)
+        };
+        result.map_err(|e| io::Error::other(format!("Failed to {action} {name}: {e}")))?;
+    }
+    Ok(())
+}
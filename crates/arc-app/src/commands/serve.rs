@@ -1,3 +1,4 @@
+use crate::helpers::mailer::LogMailer;
 use crate::helpers::rate_limit;
 use crate::http::middlewares::rate_limit_middleware::GlobalRateLimit;
 use crate::routes;
@@ -14,16 +15,26 @@ use std::io;
 use std::sync::Mutex;
 use tracing::{info, warn};
 
-use crate::domain::user::aggregate::UserAggregate;
-use crate::domain::user::projector::{UserProjector, USERS_VIEW};
 use arc_core::access_log::{AccessLogger, NoOpAccessLogger};
-use arc_core::command_bus::CommandBus;
 use arc_core::event_bus::{EventBus, InProcessEventBus};
-use arc_core::projection::{ProjectionEngine, ProjectionEngineHandler};
+use arc_core::idempotency::IdempotencyStore;
+use arc_core::outbox::OutboxRelay;
+use arc_core::projection::ProjectionEngineHandler;
 use arc_core::read_model_store::ReadModelStore;
 use arc_core::session::SessionStore;
-use arc_es_sqlite::{SqliteEventStore, SqliteReadModelStore, SqliteSessionStore};
+use arc_es_sqlite::{
+    SqliteIdempotencyStore, SqliteProcessStore, SqliteReadModelStore, SqliteScheduleStore,
+    SqliteSessionStore,
+};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn now_us() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as i64)
+        .unwrap_or(0)
+}
 
 /// Starts the Actix-Web HTTP server with all middleware, session management,
 /// rate limiting, compression, and route configuration.
@@ -75,26 +86,29 @@ pub async fn run(app_url: String, app_port: u16) -> io::Result<()> {
     // Set up Event Sourced CQRS
     let db_url = crate::helpers::config::database_url();
 
-    let sqlite_event_store = SqliteEventStore::new(&db_url)
+    let sqlite_event_store = crate::helpers::es_stack::event_store(&db_url)
         .await
         .expect("Failed to init event store");
 
     // Read-model store + projection engine. The engine subscribes to the
     // in-process event bus through a thin adapter so every committed event
-    // drives `UserProjector` synchronously into `users_view`. Step 3 will
-    // split this lane out as JetStream consumers move into a worker.
+    // drives `UserProjector` synchronously into `users_view`. `arc worker`
+    // can run the same engine out of process; both are safe together since
+    // projectors are idempotent and checkpoints only move forward.
     let read_model_store: Arc<dyn ReadModelStore> = Arc::new(
         SqliteReadModelStore::new(&db_url)
             .await
             .expect("Failed to init read-model store"),
     );
-    let mut projection_engine = ProjectionEngine::new(Box::new(sqlite_event_store.clone()));
-    projection_engine.register_projector(
-        Box::new(UserProjector::new()),
-        read_model_store.clone(),
-        USERS_VIEW,
+    let projection_engine = Arc::new(
+        crate::helpers::es_stack::projection_engine(
+            &db_url,
+            &sqlite_event_store,
+            read_model_store.clone(),
+        )
+        .await
+        .expect("Failed to init projection engine"),
     );
-    let projection_engine = Arc::new(projection_engine);
 
     let mut event_bus = InProcessEventBus::new();
     event_bus
@@ -104,26 +118,98 @@ pub async fn run(app_url: String, app_port: u16) -> io::Result<()> {
         .await
         .expect("Failed to subscribe ProjectionEngine to event bus");
 
-    // Backfill the read model from the event store on every start. Cheap on
-    // SQLite, idempotent under the version-gated upsert, and removes the need
-    // for a separate one-shot CLI when an operator is recovering from a
-    // truncated read model. Step 4's worker will own this for distributed
-    // deployments.
-    if let Err(e) = projection_engine.rebuild_all().await {
-        tracing::error!(error = ?e, "ProjectionEngine.rebuild_all failed at startup");
+    // Apply anything appended after each projection's checkpoint, e.g.
+    // events committed just before a crash but never published. A projection
+    // without a checkpoint replays from the start, which is idempotent under
+    // the version-gated upsert.
+    if let Err(e) = projection_engine.catch_up_all().await {
+        tracing::error!(error = ?e, "ProjectionEngine.catch_up_all failed at startup");
     } else {
-        info!("Projections rebuilt from event store");
+        info!("Projections caught up with event store");
     }
 
-    let command_bus =
-        CommandBus::<UserAggregate>::new(Box::new(sqlite_event_store.clone()), Box::new(event_bus));
-    let command_bus_data = web::Data::new(command_bus);
-    let read_model_store_data = web::Data::from(read_model_store);
+    let background_bus = event_bus.clone();
 
+    // Idempotency keys replay a request's original result for
+    // IDEMPOTENCY_TTL_SECS; records past that are dropped at startup.
+    let idempotency_store = SqliteIdempotencyStore::new(&db_url)
+        .await
+        .expect("Failed to init idempotency store");
+    match idempotency_store.prune_expired(now_us()).await {
+        Ok(n) => info!(pruned = n, "Expired idempotency keys pruned"),
+        Err(e) => tracing::warn!(error = %e, "Failed to prune expired idempotency keys"),
+    }
     // Default to NoOpAccessLogger for non-regulated deployments. Production
     // PHI/PCI deployments swap this for a JetStream- or DB-backed sink (Step 3+).
+    // Reads and authorization denials (commands and reads) both land here.
     let access_logger: Arc<dyn AccessLogger> = Arc::new(NoOpAccessLogger);
+
+    let command_bus = Arc::new(crate::helpers::es_stack::command_router(
+        &sqlite_event_store,
+        Box::new(event_bus),
+        Arc::new(idempotency_store),
+        read_model_store.clone(),
+        access_logger.clone(),
+    ));
+
+    // Process managers react to events in the background tier and dispatch
+    // their follow-up commands through the same router. Their deadlines are
+    // fired by a loop stopped alongside the relay.
+    let process_store = SqliteProcessStore::new(&db_url)
+        .await
+        .expect("Failed to init process store");
+    let onboarding = crate::helpers::es_stack::onboarding_runner(
+        &background_bus,
+        command_bus.clone(),
+        Arc::new(process_store),
+    )
+    .await
+    .expect("Failed to subscribe onboarding process to event bus");
+
+    // New and changed addresses are sent a verification token. No mail
+    // transport is wired yet, so `LogMailer` logs each message instead.
+    crate::helpers::es_stack::verification_mailer(&background_bus, Arc::new(LogMailer))
+        .await
+        .expect("Failed to subscribe verification mailer to event bus");
+    let (stop_timeouts, timeouts_stopped) = tokio::sync::watch::channel(false);
+    let timeouts_task =
+        tokio::spawn(async move { onboarding.run_timeouts(timeouts_stopped).await });
+
+    // Delayed commands (e.g. account deletion after its grace period) are
+    // kept in SQLite and dispatched through the router once due, so pending
+    // ones survive restarts.
+    let schedule_store = SqliteScheduleStore::new(&db_url)
+        .await
+        .expect("Failed to init schedule store");
+    let scheduler = Arc::new(crate::helpers::es_stack::command_scheduler(
+        command_bus.clone(),
+        Arc::new(schedule_store),
+    ));
+    let (stop_scheduler, scheduler_stopped) = tokio::sync::watch::channel(false);
+    let scheduler_task = {
+        let scheduler = scheduler.clone();
+        tokio::spawn(async move { scheduler.run(scheduler_stopped).await })
+    };
+
+    // The store queues every event in the outbox inside the append
+    // transaction. The relay redelivers whatever the inline publish could
+    // not, into the same handlers (`InProcessEventBus` clones share them).
+    // Started once every handler is subscribed so none misses a redelivery.
+    // Delivered entries are pruned once past `OUTBOX_RETENTION_SECS`.
+    let (stop_relay, relay_stopped) = tokio::sync::watch::channel(false);
+    let relay = OutboxRelay::new(
+        Arc::new(sqlite_event_store.clone()),
+        Arc::new(background_bus.clone()),
+    )
+    .with_retention(crate::helpers::config::outbox_retention());
+    let relay_task = tokio::spawn(async move { relay.run(relay_stopped).await });
+
+    let command_bus_data = web::Data::from(command_bus);
+    let retry_source = command_bus_data.clone();
+    let scheduler_data = web::Data::from(scheduler);
+    let read_model_store_data = web::Data::from(read_model_store);
     let access_logger_data = web::Data::from(access_logger);
+    let policies_data = web::Data::new(crate::helpers::es_stack::policies());
 
     // HIPAA-4 server-side JWT session registry.
     let session_store_impl = SqliteSessionStore::new(&db_url)
@@ -132,7 +218,7 @@ pub async fn run(app_url: String, app_port: u16) -> io::Result<()> {
     let session_store: Arc<dyn SessionStore> = Arc::new(session_store_impl);
     let session_store_data = web::Data::from(session_store);
 
-    HttpServer::new(move || {
+    let served = HttpServer::new(move || {
         // Build session middleware with proper cookie configuration
         let mut session_middleware =
             SessionMiddleware::builder(CookieSessionStore::default(), secret_key.clone())
@@ -167,13 +253,32 @@ pub async fn run(app_url: String, app_port: u16) -> io::Result<()> {
                 app_name: Mutex::from(env::var("APP_NAME").unwrap_or_else(|_| "".to_string())),
             }))
             .app_data(command_bus_data.clone())
+            .app_data(scheduler_data.clone())
             .app_data(read_model_store_data.clone())
             .app_data(access_logger_data.clone())
+            .app_data(policies_data.clone())
             .app_data(session_store_data.clone())
             .app_data(web::Data::new(ws_server.clone()))
             .configure(routes::config)
     })
     .bind((app_url, app_port))?
     .run()
-    .await
+    .await;
+
+    let _ = stop_scheduler.send(true);
+    let _ = scheduler_task.await;
+    let _ = stop_timeouts.send(true);
+    let _ = timeouts_task.await;
+    let _ = stop_relay.send(true);
+    let _ = relay_task.await;
+    // Let background-tier handlers finish what the relay handed them.
+    background_bus.shutdown().await;
+    let retries = retry_source.retry_stats();
+    info!(
+        retries = retries.retries,
+        recovered = retries.recovered,
+        exhausted = retries.exhausted,
+        "Command retries since startup"
+    );
+    served
 }
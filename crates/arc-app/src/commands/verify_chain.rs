@@ -0,0 +1,430 @@
+//! `arc verify-chain` — offline tamper audit of the event store.
+//!
+//! HIPAA-5 §164.312(c)(1). Streams the SQLite event store a page at a time
+//! with its stored signatures, recomputes each aggregate's chain and the
+//! global (insertion-order) chain through [`IntegrityChain::sign_event`],
+//! then prints a JSON report on stdout. Logs go to stderr so the report can be piped straight into `jq`.
+//!
+//! ```text
+//! arc verify-chain [--database <path>]
+//! ```
+//!
+//! Exit codes: `0` chain intact, `1` tampering detected, `2` the audit could
+//! not run (no `INTEGRITY_KEY`, missing or unreadable database).
+//!
+//! Intended to run nightly against a copy of the production database file.
+//! Rows carrying the genesis marker as a leading prefix are pre-chain legacy
+//! data: they are counted but cannot be verified.
+
+use crate::helpers::config;
+use arc_core::integrity::{EventSignature, IntegrityChain, IntegrityError};
+use arc_es_sqlite::{SignedEvent, SqliteEventStore};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::Path;
+use std::process::exit;
+use tracing::{error, info};
+
+/// Rows read from the store per query.
+const PAGE_SIZE: usize = 1000;
+
+const EXIT_TAMPERED: i32 = 1;
+const EXIT_ERROR: i32 = 2;
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainStatus {
+    Intact,
+    Tampered,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ChainKind {
+    /// Per-aggregate `signature` column.
+    Aggregate,
+    /// Insertion-order `global_signature` column.
+    Global,
+}
+
+/// Where a chain first fails, in global position order.
+#[derive(Debug, Serialize)]
+pub struct BrokenLink {
+    pub chain: ChainKind,
+    pub position: i64,
+    pub aggregate_type: String,
+    pub aggregate_id: String,
+    pub sequence: i64,
+    pub reason: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct TypeCounts {
+    pub aggregates: usize,
+    pub events: usize,
+    pub legacy_events: usize,
+}
+
+/// Machine-readable output of `arc verify-chain`.
+#[derive(Debug, Serialize)]
+pub struct ChainReport {
+    pub status: ChainStatus,
+    pub total_events: usize,
+    pub signed_events: usize,
+    pub legacy_events: usize,
+    pub aggregate_types: BTreeMap<String, TypeCounts>,
+    pub first_broken: Option<BrokenLink>,
+}
+
+/// Runs the audit and exits non-zero on tampering or failure.
+pub async fn run(args: &[String]) -> io::Result<()> {
+    let database_url = args
+        .iter()
+        .position(|a| a == "--database")
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+        .unwrap_or_else(config::database_url);
+
+    let Some(chain) = config::integrity_chain() else {
+        error!("INTEGRITY_KEY must be set to verify the event chain");
+        exit(EXIT_ERROR);
+    };
+
+    // Opening a missing SQLite file would silently create an empty one and
+    // report it intact.
+    if !Path::new(&database_url).exists() {
+        error!("Database file not found at: {}", database_url);
+        exit(EXIT_ERROR);
+    }
+
+    info!("Verifying event chain in {}", database_url);
+    let store = SqliteEventStore::new(&database_url)
+        .await
+        .unwrap_or_else(|e| {
+            error!(error = %e, "Failed to open event store");
+            exit(EXIT_ERROR);
+        });
+
+    let mut audit = ChainAudit::default();
+    loop {
+        let page = store
+            .stream_all_signed(audit.last_position(), PAGE_SIZE)
+            .await
+            .unwrap_or_else(|e| {
+                error!(error = %e, "Failed to read event store");
+                exit(EXIT_ERROR);
+            });
+        audit.feed(chain.as_ref(), &page);
+        if page.len() < PAGE_SIZE {
+            break;
+        }
+    }
+
+    let report = audit.finish();
+    let json = serde_json::to_string_pretty(&report).map_err(io::Error::other)?;
+    println!("{json}");
+
+    if report.status == ChainStatus::Tampered {
+        error!("Event chain is broken");
+        exit(EXIT_TAMPERED);
+    }
+    info!("Event chain intact ({} events)", report.total_events);
+    Ok(())
+}
+
+/// Where one aggregate's chain stands after the rows seen so far.
+#[derive(Default)]
+struct StreamHead {
+    /// Past the leading run of genesis-marked legacy rows.
+    signed: bool,
+    prev: Option<EventSignature>,
+    next_sequence: Option<i64>,
+}
+
+/// Both chains verified incrementally, so the store can be read a page at a
+/// time: only the previous signature of the global chain and of each
+/// aggregate is carried from one page to the next.
+#[derive(Default)]
+pub struct ChainAudit {
+    last_position: i64,
+    total_events: usize,
+    legacy_events: usize,
+    aggregate_types: BTreeMap<String, TypeCounts>,
+    streams: HashMap<String, StreamHead>,
+    global_signed: bool,
+    global_prev: Option<EventSignature>,
+    first_broken: Option<BrokenLink>,
+}
+
+impl ChainAudit {
+    /// Position of the last row fed, `0` before the first page.
+    pub fn last_position(&self) -> i64 {
+        self.last_position
+    }
+
+    /// Verify the next rows, which must follow the previous page in global
+    /// position order.
+    pub fn feed(&mut self, chain: &dyn IntegrityChain, rows: &[SignedEvent]) {
+        for row in rows {
+            self.last_position = row.position;
+            self.total_events += 1;
+
+            let counts = self
+                .aggregate_types
+                .entry(row.event.aggregate_type.clone())
+                .or_default();
+            counts.events += 1;
+            let head = self
+                .streams
+                .entry(row.event.aggregate_id.clone())
+                .or_insert_with(|| {
+                    counts.aggregates += 1;
+                    StreamHead::default()
+                });
+
+            if !head.signed && row.signature.is_genesis() {
+                counts.legacy_events += 1;
+                self.legacy_events += 1;
+            } else {
+                head.signed = true;
+                if let Err(reason) = verify_aggregate_link(chain, head, row) {
+                    record(&mut self.first_broken, ChainKind::Aggregate, row, reason);
+                }
+            }
+
+            if self.global_signed || !row.global_signature.is_genesis() {
+                self.global_signed = true;
+                if let Err(reason) =
+                    verify_link(chain, &mut self.global_prev, row, |r| &r.global_signature)
+                {
+                    record(&mut self.first_broken, ChainKind::Global, row, reason);
+                }
+            }
+        }
+    }
+
+    /// The report over every row fed so far.
+    pub fn finish(self) -> ChainReport {
+        ChainReport {
+            status: if self.first_broken.is_some() {
+                ChainStatus::Tampered
+            } else {
+                ChainStatus::Intact
+            },
+            total_events: self.total_events,
+            signed_events: self.total_events - self.legacy_events,
+            legacy_events: self.legacy_events,
+            aggregate_types: self.aggregate_types,
+            first_broken: self.first_broken,
+        }
+    }
+}
+
+/// Rows arrive in position order, so the first break recorded is the
+/// earliest; later ones are ignored.
+fn record(first: &mut Option<BrokenLink>, kind: ChainKind, row: &SignedEvent, reason: String) {
+    if first.is_none() {
+        *first = Some(link_at(kind, row, reason));
+    }
+}
+
+fn verify_aggregate_link(
+    chain: &dyn IntegrityChain,
+    head: &mut StreamHead,
+    row: &SignedEvent,
+) -> Result<(), String> {
+    if let Some(expected) = head.next_sequence {
+        if row.event.sequence != expected {
+            return Err(IntegrityError::OutOfOrder {
+                aggregate_id: row.event.aggregate_id.clone(),
+                expected,
+                sequence: row.event.sequence,
+            }
+            .to_string());
+        }
+    }
+    head.next_sequence = Some(row.event.sequence + 1);
+    verify_link(chain, &mut head.prev, row, |r| &r.signature)
+}
+
+/// Recompute one link of a chain from `prev` (genesis when `None`) and
+/// advance `prev` to the stored signature.
+fn verify_link(
+    chain: &dyn IntegrityChain,
+    prev: &mut Option<EventSignature>,
+    row: &SignedEvent,
+    stored: impl Fn(&SignedEvent) -> &EventSignature,
+) -> Result<(), String> {
+    let anchor = prev.clone().unwrap_or_else(EventSignature::genesis);
+    let claimed = stored(row);
+    *prev = Some(claimed.clone());
+    match chain.sign_event(&anchor, &row.event) {
+        Ok(computed) if &computed == claimed => Ok(()),
+        Ok(_) => Err(IntegrityError::BrokenAt {
+            aggregate_id: row.event.aggregate_id.clone(),
+            sequence: row.event.sequence,
+        }
+        .to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn link_at(chain: ChainKind, row: &SignedEvent, reason: String) -> BrokenLink {
+    BrokenLink {
+        chain,
+        position: row.position,
+        aggregate_type: row.event.aggregate_type.clone(),
+        aggregate_id: row.event.aggregate_id.clone(),
+        sequence: row.event.sequence,
+        reason,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arc_core::audit::AuditMetadata;
+    use arc_core::event::Event;
+    use arc_core::integrity::HmacSha256Chain;
+    use serde_json::json;
+
+    fn chain() -> HmacSha256Chain {
+        HmacSha256Chain::new(b"012345678901234567890123456789AB".to_vec()).unwrap()
+    }
+
+    fn build_report(chain: &dyn IntegrityChain, rows: &[SignedEvent]) -> ChainReport {
+        let mut audit = ChainAudit::default();
+        audit.feed(chain, rows);
+        audit.finish()
+    }
+
+    /// Sign `(aggregate_type, aggregate_id, sequence)` rows the way
+    /// `SqliteEventStore::append` does.
+    fn signed_rows(specs: &[(&str, &str, i64)]) -> Vec<SignedEvent> {
+        let chain = chain();
+        let mut heads: HashMap<String, EventSignature> = HashMap::new();
+        let mut global = EventSignature::genesis();
+        specs
+            .iter()
+            .enumerate()
+            .map(|(i, (ty, id, seq))| {
+                let event = Event::new(*ty, *id, *seq, "Touched", json!({ "n": seq }))
+                    .with_audit(AuditMetadata::test_default());
+                let head = heads
+                    .entry(id.to_string())
+                    .or_insert_with(EventSignature::genesis);
+                *head = chain.sign_event(head, &event).unwrap();
+                global = chain.sign_event(&global, &event).unwrap();
+                SignedEvent {
+                    position: i as i64 + 1,
+                    event,
+                    signature: head.clone(),
+                    global_signature: global.clone(),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_intact_store_reports_counts_per_type() {
+        let rows = signed_rows(&[
+            ("User", "u1", 1),
+            ("User", "u2", 1),
+            ("User", "u1", 2),
+            ("Order", "o1", 1),
+        ]);
+        let report = build_report(&chain(), &rows);
+        assert_eq!(report.status, ChainStatus::Intact);
+        assert!(report.first_broken.is_none());
+        assert_eq!(report.total_events, 4);
+        assert_eq!(report.signed_events, 4);
+        assert_eq!(report.aggregate_types["User"].aggregates, 2);
+        assert_eq!(report.aggregate_types["User"].events, 3);
+        assert_eq!(report.aggregate_types["Order"].events, 1);
+    }
+
+    #[test]
+    fn test_tampered_payload_reports_first_broken_row() {
+        let mut rows = signed_rows(&[("User", "u1", 1), ("User", "u1", 2), ("User", "u2", 1)]);
+        rows[1].event.payload = json!({ "n": 99 });
+        rows[2].event.payload = json!({ "n": 99 });
+
+        let report = build_report(&chain(), &rows);
+        assert_eq!(report.status, ChainStatus::Tampered);
+        let broken = report.first_broken.unwrap();
+        assert_eq!(broken.position, 2);
+        assert_eq!(broken.aggregate_id, "u1");
+        assert_eq!(broken.sequence, 2);
+    }
+
+    #[test]
+    fn test_deleted_aggregate_breaks_global_chain() {
+        let mut rows = signed_rows(&[("User", "u1", 1), ("User", "u2", 1), ("User", "u3", 1)]);
+        rows.remove(1);
+
+        let report = build_report(&chain(), &rows);
+        let broken = report.first_broken.unwrap();
+        assert_eq!(broken.chain, ChainKind::Global);
+        assert_eq!(broken.aggregate_id, "u3");
+    }
+
+    #[test]
+    fn test_legacy_prefix_is_counted_not_verified() {
+        let mut rows = signed_rows(&[("User", "u1", 1), ("User", "u1", 2)]);
+        let legacy = Event::new("User", "u0", 1, "Touched", json!({}))
+            .with_audit(AuditMetadata::test_default());
+        rows.insert(
+            0,
+            SignedEvent {
+                position: 0,
+                event: legacy,
+                signature: EventSignature::genesis(),
+                global_signature: EventSignature::genesis(),
+            },
+        );
+
+        let report = build_report(&chain(), &rows);
+        assert_eq!(report.status, ChainStatus::Intact);
+        assert_eq!(report.legacy_events, 1);
+        assert_eq!(report.signed_events, 2);
+        assert_eq!(report.aggregate_types["User"].legacy_events, 1);
+    }
+
+    #[test]
+    fn test_chains_carry_across_pages() {
+        let rows = signed_rows(&[
+            ("User", "u1", 1),
+            ("User", "u2", 1),
+            ("User", "u1", 2),
+            ("User", "u2", 2),
+        ]);
+        let mut audit = ChainAudit::default();
+        for page in rows.chunks(1) {
+            audit.feed(&chain(), page);
+        }
+        assert_eq!(audit.last_position(), 4);
+        let report = audit.finish();
+        assert_eq!(report.status, ChainStatus::Intact);
+        assert_eq!(report.total_events, 4);
+        assert_eq!(report.aggregate_types["User"].aggregates, 2);
+
+        let mut tampered = rows.clone();
+        tampered[2].event.payload = json!({ "n": 99 });
+        let mut audit = ChainAudit::default();
+        for page in tampered.chunks(2) {
+            audit.feed(&chain(), page);
+        }
+        let broken = audit.finish().first_broken.unwrap();
+        assert_eq!(broken.chain, ChainKind::Aggregate);
+        assert_eq!(broken.position, 3);
+    }
+
+    #[test]
+    fn test_report_serializes_snake_case() {
+        let report = build_report(&chain(), &[]);
+        let value = serde_json::to_value(&report).unwrap();
+        assert_eq!(value["status"], "intact");
+        assert!(value["first_broken"].is_null());
+    }
+}
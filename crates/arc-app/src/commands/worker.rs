@@ -0,0 +1,54 @@
+//! `arc worker` — runs the application's projections out of the web process.
+//!
+//! Tails the SQLite event store from each projection's checkpoint through an
+//! [`arc_worker::Worker`], serves `GET /health` on `WORKER_HEALTH_ADDR`
+//! (default `0.0.0.0:8081`) and exits cleanly on SIGTERM or Ctrl-C after
+//! finishing the poll in progress.
+//!
+//! ```text
+//! arc worker
+//! ```
+
+use crate::helpers::{config, es_stack};
+use arc_core::read_model_store::ReadModelStore;
+use arc_es_sqlite::SqliteReadModelStore;
+use arc_worker::{serve_health, shutdown_signal, ProjectionSource, Worker};
+use std::io;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::watch;
+use tracing::info;
+
+pub async fn run() -> io::Result<()> {
+    crate::check_database_health();
+
+    let db_url = config::database_url();
+    let event_store = es_stack::event_store(&db_url)
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to init event store: {e}")))?;
+    let read_model_store: Arc<dyn ReadModelStore> = Arc::new(
+        SqliteReadModelStore::new(&db_url)
+            .await
+            .map_err(|e| io::Error::other(format!("Failed to init read-model store: {e}")))?,
+    );
+    let engine = es_stack::projection_engine(&db_url, &event_store, read_model_store)
+        .await
+        .map_err(|e| io::Error::other(format!("Failed to init projection engine: {e}")))?;
+    info!(projections = ?engine.projection_names(), "Projection worker configured");
+
+    let worker = Worker::new()
+        .with_source(ProjectionSource::new(Arc::new(engine)))
+        .with_poll_interval(config::worker_poll_interval());
+
+    let (stop, stopped) = watch::channel(false);
+    let listener = TcpListener::bind(config::worker_health_addr()).await?;
+    let health_task = tokio::spawn(serve_health(listener, worker.health(), stopped.clone()));
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        let _ = stop.send(true);
+    });
+
+    worker.run(stopped).await;
+    let _ = health_task.await;
+    Ok(())
+}
@@ -1,11 +1,12 @@
 //! Seeds the default user (`jekyll@example.com`) by dispatching a
-//! `UserCommand::RegisterUser` through the `CommandBus`. The legacy
-//! direct-Diesel seeder has been retired alongside the `users` table.
+//! `UserCommand::RegisterUser` through the `CommandRouter`, verifies its
+//! email and makes it an admin. The legacy direct-Diesel seeder has been retired alongside the
+//! `users` table.
 
-use crate::domain::user::aggregate::UserAggregate;
 use crate::domain::user::commands::UserCommand;
+use crate::domain::user::ADMIN_ROLE;
 use crate::services::user_service::{lookup_aggregate_id_by_email_view, prepare_password};
-use arc_core::command_bus::{CommandBus, CommandContext};
+use arc_core::command_bus::{CommandContext, CommandRouter};
 use arc_core::read_model_store::ReadModelStore;
 use tracing::info;
 
@@ -14,25 +15,47 @@ pub const DEFAULT_USER_EMAIL: &str = "jekyll@example.com";
 pub const DEFAULT_USER_NAME: &str = "Jekyll";
 pub const DEFAULT_USER_PASSWORD: &str = "password";
 
-/// Seed the default user. Idempotent: if the projection already has a row
-/// for the email, returns the existing aggregate id without dispatching.
+/// Seed the default user with a verified email and the admin role.
+/// Idempotent: if the projection already has a row for the email, the
+/// existing aggregate is reused, and verifying or granting what it already
+/// has is a no-op. Verifying keeps the onboarding process from deleting the
+/// seeded admin once its events reach the server.
 pub async fn seed_default_user(
-    command_bus: &CommandBus<UserAggregate>,
+    command_bus: &CommandRouter,
     rm_store: &dyn ReadModelStore,
 ) -> Result<String, Box<dyn std::error::Error>> {
-    if let Some(id) = lookup_aggregate_id_by_email_view(rm_store, DEFAULT_USER_EMAIL).await {
-        info!(email = DEFAULT_USER_EMAIL, "User already seeded");
-        return Ok(id);
-    }
+    let id = match lookup_aggregate_id_by_email_view(rm_store, DEFAULT_USER_EMAIL).await {
+        Some(id) => {
+            info!(email = DEFAULT_USER_EMAIL, "User already seeded");
+            id
+        }
+        None => {
+            info!("Creating default user");
+            let id = uuid::Uuid::new_v4().to_string();
+            let cmd = UserCommand::RegisterUser {
+                id: id.clone(),
+                name: DEFAULT_USER_NAME.to_string(),
+                email: DEFAULT_USER_EMAIL.to_string(),
+                password_hash: prepare_password(DEFAULT_USER_PASSWORD),
+            };
+            command_bus.dispatch(cmd, CommandContext::system()).await?;
+            id
+        }
+    };
 
-    info!("Creating default user");
-    let id = uuid::Uuid::new_v4().to_string();
-    let cmd = UserCommand::RegisterUser {
+    let verify = UserCommand::VerifyEmail {
+        id: id.clone(),
+        token: None,
+    };
+    command_bus
+        .dispatch(verify, CommandContext::system())
+        .await?;
+    let grant = UserCommand::GrantRole {
         id: id.clone(),
-        name: DEFAULT_USER_NAME.to_string(),
-        email: DEFAULT_USER_EMAIL.to_string(),
-        password_hash: prepare_password(DEFAULT_USER_PASSWORD),
+        role: ADMIN_ROLE.to_string(),
     };
-    command_bus.dispatch(cmd, CommandContext::system()).await?;
+    command_bus
+        .dispatch(grant, CommandContext::system())
+        .await?;
     Ok(id)
 }
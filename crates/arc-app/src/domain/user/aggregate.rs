@@ -1,6 +1,9 @@
 use crate::domain::user::commands::UserCommand;
+use crate::domain::user::events::UserDomainEvent;
+use arc_core::domain_event::DomainEventError;
 use arc_core::{aggregate::Aggregate, event::Event};
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -13,24 +16,46 @@ pub enum UserAggregateError {
     AlreadyDeleted,
     #[error("invalid email format")]
     InvalidEmail,
+    #[error("failed to encode user event: {0}")]
+    Encode(#[from] DomainEventError),
 }
 
-#[derive(Default)]
+#[derive(Default, Serialize, Deserialize)]
 pub struct UserAggregate {
     pub id: Option<String>,
     pub name: Option<String>,
     pub email: Option<String>,
     pub password_hash: Option<String>,
+    /// Authorization roles (`"admin"`). Defaulted so snapshots taken before
+    /// roles existed still load.
+    #[serde(default)]
+    pub roles: Vec<String>,
+    #[serde(default)]
+    pub email_verified: bool,
+    #[serde(default)]
+    pub workspace_id: Option<String>,
     pub version: i64,
     pub exists: bool,
     pub deleted: bool,
 }
 
+impl UserAggregate {
+    /// The next event in `id`'s stream.
+    fn emit(&self, id: &str, event: UserDomainEvent) -> Result<Vec<Event>, UserAggregateError> {
+        Ok(vec![Event::from_domain(
+            Self::aggregate_type(),
+            id,
+            self.version + 1,
+            &event,
+        )?])
+    }
+}
+
 #[async_trait]
 impl Aggregate for UserAggregate {
     type Command = UserCommand;
     type Error = UserAggregateError;
-    type Event = ();
+    type Event = UserDomainEvent;
 
     fn aggregate_type() -> &'static str {
         "User"
@@ -42,10 +67,10 @@ impl Aggregate for UserAggregate {
     async fn handle<'a>(&'a self, cmd: Self::Command) -> Result<Vec<Event>, Self::Error> {
         match cmd {
             UserCommand::RegisterUser {
-                ref id,
-                ref name,
-                ref email,
-                ref password_hash,
+                id,
+                name,
+                email,
+                password_hash,
             } => {
                 if self.exists {
                     return Err(UserAggregateError::AlreadyExists);
@@ -53,101 +78,164 @@ impl Aggregate for UserAggregate {
                 if !email.contains('@') {
                     return Err(UserAggregateError::InvalidEmail);
                 }
-                Ok(vec![Event::new(
-                    "User",
-                    id,
-                    self.version + 1,
-                    "UserRegistered",
-                    serde_json::json!({ "id": id, "name": name,
-                                        "email": email, "password_hash": password_hash }),
-                )])
+                self.emit(
+                    &id,
+                    UserDomainEvent::UserRegistered {
+                        id: id.clone(),
+                        name,
+                        email,
+                        password_hash,
+                    },
+                )
             }
-            UserCommand::UpdateProfile { ref id, ref name } => {
+            UserCommand::UpdateProfile { id, name } => {
                 if !self.exists || self.deleted {
                     return Err(UserAggregateError::NotFound);
                 }
-                Ok(vec![Event::new(
-                    "User",
-                    id,
-                    self.version + 1,
-                    "ProfileUpdated",
-                    serde_json::json!({ "name": name }),
-                )])
+                self.emit(&id, UserDomainEvent::ProfileUpdated { name })
             }
-            UserCommand::ChangeEmail { ref id, ref email } => {
+            UserCommand::ChangeEmail { id, email } => {
                 if !self.exists || self.deleted {
                     return Err(UserAggregateError::NotFound);
                 }
                 if !email.contains('@') {
                     return Err(UserAggregateError::InvalidEmail);
                 }
-                Ok(vec![Event::new(
-                    "User",
-                    id,
-                    self.version + 1,
-                    "EmailChanged",
-                    serde_json::json!({ "email": email }),
-                )])
-            }
-            UserCommand::ChangePassword {
-                ref id,
-                ref password_hash,
-            } => {
+                self.emit(&id, UserDomainEvent::EmailChanged { email })
+            }
+            UserCommand::ChangePassword { id, password_hash } => {
                 if !self.exists || self.deleted {
                     return Err(UserAggregateError::NotFound);
                 }
-                Ok(vec![Event::new(
-                    "User",
-                    id,
-                    self.version + 1,
-                    "PasswordChanged",
-                    serde_json::json!({ "password_hash": password_hash }),
-                )])
+                self.emit(&id, UserDomainEvent::PasswordChanged { password_hash })
             }
-            UserCommand::DeleteUser { ref id } => {
+            UserCommand::DeleteUser { id } => {
                 if !self.exists {
                     return Err(UserAggregateError::NotFound);
                 }
                 if self.deleted {
                     return Err(UserAggregateError::AlreadyDeleted);
                 }
-                Ok(vec![Event::new(
-                    "User",
-                    id,
-                    self.version + 1,
-                    "UserDeleted",
-                    serde_json::json!({}),
-                )])
+                self.emit(&id, UserDomainEvent::UserDeleted)
+            }
+            // Granting a held role or revoking one not held is a no-op.
+            UserCommand::GrantRole { id, role } => {
+                if !self.exists || self.deleted {
+                    return Err(UserAggregateError::NotFound);
+                }
+                if self.roles.contains(&role) {
+                    return Ok(vec![]);
+                }
+                self.emit(&id, UserDomainEvent::RoleGranted { role })
+            }
+            UserCommand::RevokeRole { id, role } => {
+                if !self.exists || self.deleted {
+                    return Err(UserAggregateError::NotFound);
+                }
+                if !self.roles.contains(&role) {
+                    return Ok(vec![]);
+                }
+                self.emit(&id, UserDomainEvent::RoleRevoked { role })
+            }
+            // Re-verifying and re-provisioning are no-ops, so the onboarding
+            // process can repeat a step safely.
+            UserCommand::VerifyEmail { id, .. } => {
+                if !self.exists || self.deleted {
+                    return Err(UserAggregateError::NotFound);
+                }
+                if self.email_verified {
+                    return Ok(vec![]);
+                }
+                let email = self.email.clone().unwrap_or_default();
+                self.emit(&id, UserDomainEvent::EmailVerified { email })
+            }
+            UserCommand::ProvisionWorkspace { id, workspace_id } => {
+                if !self.exists || self.deleted {
+                    return Err(UserAggregateError::NotFound);
+                }
+                if self.workspace_id.is_some() {
+                    return Ok(vec![]);
+                }
+                self.emit(&id, UserDomainEvent::WorkspaceProvisioned { workspace_id })
             }
         }
     }
 
+    /// Only reached for events this process just emitted; loads go through
+    /// [`try_apply`](Aggregate::try_apply), which reports a payload that
+    /// does not decode. One that still gets here is logged and skipped.
     fn apply(&mut self, event: &Event) {
+        if let Err(e) = self.try_apply(event) {
+            tracing::error!(
+                aggregate_id = %event.aggregate_id,
+                event_id = %event.event_id,
+                error = %e,
+                "User event does not decode"
+            );
+        }
+    }
+
+    /// Events of other types are ignored. A `User` event whose payload does
+    /// not decode means the log and this code disagree; folding past it
+    /// would hand `handle` the wrong state, so the load fails instead.
+    fn try_apply(&mut self, event: &Event) -> Result<(), DomainEventError> {
+        let user_event = match event.decode::<UserDomainEvent>() {
+            Ok(user_event) => user_event,
+            Err(e) if e.is_unknown_event_type() => {
+                self.version = event.sequence;
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
         self.version = event.sequence;
-        match event.event_type.as_str() {
-            "UserRegistered" => {
-                self.id = Some(event.payload["id"].as_str().unwrap().to_string());
-                self.name = Some(event.payload["name"].as_str().unwrap().to_string());
-                self.email = Some(event.payload["email"].as_str().unwrap().to_string());
-                self.password_hash =
-                    Some(event.payload["password_hash"].as_str().unwrap().to_string());
+        match user_event {
+            UserDomainEvent::UserRegistered {
+                id,
+                name,
+                email,
+                password_hash,
+            } => {
+                self.id = Some(id);
+                self.name = Some(name);
+                self.email = Some(email);
+                self.password_hash = Some(password_hash);
                 self.exists = true;
             }
-            "ProfileUpdated" => {
-                self.name = Some(event.payload["name"].as_str().unwrap().to_string());
+            UserDomainEvent::ProfileUpdated { name } => {
+                self.name = Some(name);
             }
-            "EmailChanged" => {
-                self.email = Some(event.payload["email"].as_str().unwrap().to_string());
+            UserDomainEvent::EmailChanged { email } => {
+                self.email = Some(email);
+                self.email_verified = false;
             }
-            "PasswordChanged" => {
-                self.password_hash =
-                    Some(event.payload["password_hash"].as_str().unwrap().to_string());
+            UserDomainEvent::PasswordChanged { password_hash } => {
+                self.password_hash = Some(password_hash);
             }
-            "UserDeleted" => {
+            UserDomainEvent::UserDeleted => {
                 self.deleted = true;
             }
-            _ => {}
+            UserDomainEvent::RoleGranted { role } => {
+                self.roles.push(role);
+            }
+            UserDomainEvent::RoleRevoked { role } => {
+                self.roles.retain(|r| *r != role);
+            }
+            UserDomainEvent::EmailVerified { .. } => {
+                self.email_verified = true;
+            }
+            UserDomainEvent::WorkspaceProvisioned { workspace_id } => {
+                self.workspace_id = Some(workspace_id);
+            }
         }
+        Ok(())
+    }
+
+    fn to_snapshot(&self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    fn from_snapshot(state: &serde_json::Value) -> Option<Self> {
+        serde_json::from_value(state.clone()).ok()
     }
 }
 
@@ -155,75 +243,256 @@ impl Aggregate for UserAggregate {
 mod tests {
     use super::*;
     use crate::domain::user::commands::UserCommand;
+    use arc_core::audit::AuditMetadata;
+    use arc_core::command_bus::{CommandBusError, CommandContext, CommandRouter};
+    use arc_core::event_bus::InProcessEventBus;
+    use arc_core::event_store::{EventStore, EventStoreError, InMemoryEventStore, VersionCheck};
+    use arc_core::testing::AggregateFixture;
+
+    fn fixture() -> AggregateFixture<UserAggregate> {
+        AggregateFixture::new("uuid-123")
+    }
+
+    fn registered() -> UserDomainEvent {
+        UserDomainEvent::UserRegistered {
+            id: "uuid-123".into(),
+            name: "Ann".into(),
+            email: "a@e.c".into(),
+            password_hash: "pw".into(),
+        }
+    }
+
+    fn register() -> UserCommand {
+        UserCommand::RegisterUser {
+            id: "uuid-123".into(),
+            name: "Ann".into(),
+            email: "a@e.c".into(),
+            password_hash: "pw".into(),
+        }
+    }
 
     #[tokio::test]
     async fn test_create_user_emits_user_registered_event() {
-        let agg = UserAggregate::default();
-        let cmd = UserCommand::RegisterUser {
-            id: "uuid-123".to_string(),
-            name: "John Doe".to_string(),
-            email: "john@example.com".to_string(),
-            password_hash: "hash".to_string(),
-        };
+        fixture()
+            .when(register())
+            .await
+            .then_expect_events([registered()]);
+    }
 
-        let result = agg.handle(cmd).await;
-        assert!(result.is_ok());
+    #[tokio::test]
+    async fn test_create_user_rejects_invalid_email_and_duplicates() {
+        fixture()
+            .when(UserCommand::RegisterUser {
+                id: "uuid-123".into(),
+                name: "Ann".into(),
+                email: "not-an-email".into(),
+                password_hash: "pw".into(),
+            })
+            .await
+            .then_expect_error("invalid email format");
 
-        let events = result.unwrap();
-        assert_eq!(events.len(), 1);
-        let event = &events[0];
+        fixture()
+            .given([registered()])
+            .when(register())
+            .await
+            .then_expect_error("user already exists");
+    }
 
-        assert_eq!(event.event_type, "UserRegistered");
-        assert_eq!(event.aggregate_id, "uuid-123".to_string());
-        assert_eq!(event.payload["email"], "john@example.com");
+    #[tokio::test]
+    async fn test_update_profile_reflects_in_get() {
+        let agg = fixture()
+            .given([registered()])
+            .when(UserCommand::UpdateProfile {
+                id: "uuid-123".into(),
+                name: "New Name".into(),
+            })
+            .await
+            .then_expect_events([UserDomainEvent::ProfileUpdated {
+                name: "New Name".into(),
+            }])
+            .into_aggregate();
+        assert_eq!(agg.name.unwrap(), "New Name");
+        assert_eq!(agg.version, 2);
     }
 
     #[tokio::test]
-    async fn test_create_user_returns_conflict_on_duplicate_user() {
-        let agg = UserAggregate {
-            exists: true,
-            ..Default::default()
-        };
+    async fn test_deleted_user_cannot_be_changed_or_deleted_again() {
+        let deleted = || fixture().given([registered(), UserDomainEvent::UserDeleted]);
+        deleted()
+            .when(UserCommand::DeleteUser {
+                id: "uuid-123".into(),
+            })
+            .await
+            .then_expect_error_matching(|e| matches!(e, UserAggregateError::AlreadyDeleted));
+        deleted()
+            .when(UserCommand::ChangePassword {
+                id: "uuid-123".into(),
+                password_hash: "pw2".into(),
+            })
+            .await
+            .then_expect_error("user not found");
+    }
+
+    #[test]
+    fn test_snapshot_roundtrip_preserves_state() {
+        let agg = UserAggregate::from_events(
+            fixture()
+                .given([registered(), UserDomainEvent::UserDeleted])
+                .history()
+                .to_vec(),
+        );
 
-        let cmd = UserCommand::RegisterUser {
-            id: "uuid-123".to_string(),
-            name: "John Doe".to_string(),
-            email: "john@example.com".to_string(),
-            password_hash: "hash".to_string(),
+        let restored = UserAggregate::from_snapshot(&agg.to_snapshot().unwrap()).unwrap();
+        assert_eq!(restored.id.as_deref(), Some("uuid-123"));
+        assert_eq!(restored.email.as_deref(), Some("a@e.c"));
+        assert_eq!(restored.version(), 2);
+        assert!(restored.exists);
+        assert!(restored.deleted);
+    }
+
+    #[tokio::test]
+    async fn test_role_grants_and_revokes_are_idempotent() {
+        let grant = || UserCommand::GrantRole {
+            id: "uuid-123".into(),
+            role: "admin".into(),
+        };
+        let revoke = || UserCommand::RevokeRole {
+            id: "uuid-123".into(),
+            role: "admin".into(),
+        };
+        let granted = || UserDomainEvent::RoleGranted {
+            role: "admin".into(),
         };
 
-        let result = agg.handle(cmd).await;
-        assert!(result.is_err());
-        assert_eq!(result.unwrap_err().to_string(), "user already exists");
+        let agg = fixture()
+            .given([registered()])
+            .when(grant())
+            .await
+            .then_expect_events([granted()])
+            .into_aggregate();
+        assert_eq!(agg.roles, vec!["admin"]);
+        fixture()
+            .given([registered(), granted()])
+            .when(grant())
+            .await
+            .then_expect_no_events();
+
+        let agg = fixture()
+            .given([registered(), granted()])
+            .when(revoke())
+            .await
+            .then_expect_events([UserDomainEvent::RoleRevoked {
+                role: "admin".into(),
+            }])
+            .into_aggregate();
+        assert!(agg.roles.is_empty());
+        fixture()
+            .given([registered()])
+            .when(revoke())
+            .await
+            .then_expect_no_events();
     }
 
     #[tokio::test]
-    async fn test_update_profile_reflects_in_get() {
+    async fn test_email_change_resets_verification() {
+        let verify = || UserCommand::VerifyEmail {
+            id: "uuid-123".into(),
+            token: None,
+        };
+        let verified = || UserDomainEvent::EmailVerified {
+            email: "a@e.c".into(),
+        };
+
+        fixture()
+            .given([registered()])
+            .when(verify())
+            .await
+            .then_expect_events([verified()]);
+        fixture()
+            .given([registered(), verified()])
+            .when(verify())
+            .await
+            .then_expect_no_events();
+        fixture()
+            .given([
+                registered(),
+                verified(),
+                UserDomainEvent::EmailChanged {
+                    email: "b@e.c".into(),
+                },
+            ])
+            .when(verify())
+            .await
+            .then_expect_events([UserDomainEvent::EmailVerified {
+                email: "b@e.c".into(),
+            }]);
+    }
+
+    #[test]
+    fn test_apply_ignores_foreign_event_types() {
         let mut agg = UserAggregate::default();
+        agg.apply(&Event::new(
+            "User",
+            "uuid-123",
+            1,
+            "SomethingElse",
+            serde_json::json!({}),
+        ));
+        assert!(!agg.exists);
+        assert_eq!(agg.version, 1);
+    }
 
-        // setup state
-        let create_event = Event::new(
+    #[test]
+    fn test_try_apply_rejects_mismatched_payload() {
+        let mut agg = UserAggregate::default();
+        let err = agg
+            .try_apply(&Event::new(
+                "User",
+                "uuid-123",
+                1,
+                "UserRegistered",
+                serde_json::json!({ "id": "uuid-123", "name": "Ann" }),
+            ))
+            .unwrap_err();
+        assert!(matches!(err, DomainEventError::InvalidPayload { .. }));
+        assert!(!agg.exists);
+        assert_eq!(agg.version, 0);
+    }
+
+    #[tokio::test]
+    async fn test_undecodable_stored_event_fails_the_load() {
+        let store = InMemoryEventStore::new();
+        let mut corrupt = Event::new(
             "User",
             "uuid-123",
             1,
             "UserRegistered",
-            serde_json::json!({
-                "id": "uuid-123", "name": "Old", "email": "o@e.c", "password_hash": "pw"
-            }),
+            serde_json::json!({ "id": "uuid-123", "name": "Ann" }),
         );
-        agg.apply(&create_event);
-
-        let cmd = UserCommand::UpdateProfile {
-            id: "uuid-123".to_string(),
-            name: "New Name".to_string(),
-        };
-
-        let result = agg.handle(cmd).await.unwrap();
-        assert_eq!(result[0].event_type, "ProfileUpdated");
-        assert_eq!(result[0].payload["name"], "New Name");
+        corrupt.audit = AuditMetadata::test_default();
+        store
+            .append("uuid-123", VersionCheck::New, vec![corrupt])
+            .await
+            .unwrap();
+        let router = CommandRouter::new(Box::new(store), Box::new(InProcessEventBus::new()))
+            .register::<UserAggregate>();
 
-        agg.apply(&result[0]);
-        assert_eq!(agg.name.unwrap(), "New Name");
-        assert_eq!(agg.version, 2);
+        let err = router
+            .dispatch(
+                UserCommand::UpdateProfile {
+                    id: "uuid-123".into(),
+                    name: "New".into(),
+                },
+                CommandContext::system(),
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            CommandBusError::LoadFailed {
+                source: EventStoreError::SerializationError { .. },
+                ..
+            }
+        ));
     }
 }
@@ -23,6 +23,25 @@ pub enum UserCommand {
     DeleteUser {
         id: String,
     },
+    GrantRole {
+        id: String,
+        role: String,
+    },
+    RevokeRole {
+        id: String,
+        role: String,
+    },
+    /// `token` is the verification token emailed to the user; the system
+    /// actor and admins verify without one (see `middleware::VerificationToken`).
+    VerifyEmail {
+        id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token: Option<String>,
+    },
+    ProvisionWorkspace {
+        id: String,
+        workspace_id: String,
+    },
 }
 
 impl arc_core::aggregate::Command for UserCommand {
@@ -33,6 +52,112 @@ impl arc_core::aggregate::Command for UserCommand {
             Self::ChangeEmail { id, .. } => id,
             Self::ChangePassword { id, .. } => id,
             Self::DeleteUser { id } => id,
+            Self::GrantRole { id, .. } => id,
+            Self::RevokeRole { id, .. } => id,
+            Self::VerifyEmail { id, .. } => id,
+            Self::ProvisionWorkspace { id, .. } => id,
+        }
+    }
+
+    /// Actions named by the authorization policies in `helpers::es_stack`.
+    fn action(&self) -> &'static str {
+        match self {
+            Self::RegisterUser { .. } => "register",
+            Self::UpdateProfile { .. } => "update_profile",
+            Self::ChangeEmail { .. } => "change_email",
+            Self::ChangePassword { .. } => "change_password",
+            Self::DeleteUser { .. } => "delete",
+            Self::GrantRole { .. } | Self::RevokeRole { .. } => "manage_roles",
+            Self::VerifyEmail { .. } => "verify_email",
+            Self::ProvisionWorkspace { .. } => "provision_workspace",
+        }
+    }
+
+    /// Profile edits overwrite a field with the submitted value, so a race
+    /// with another edit is resolved by re-running against the newer state.
+    /// Registration, password changes and deletion surface the conflict.
+    fn retry_on_conflict(&self) -> Option<Self> {
+        match self {
+            Self::UpdateProfile { .. } | Self::ChangeEmail { .. } => Some(self.clone()),
+            Self::RegisterUser { .. }
+            | Self::ChangePassword { .. }
+            | Self::DeleteUser { .. }
+            | Self::GrantRole { .. }
+            | Self::RevokeRole { .. }
+            | Self::VerifyEmail { .. }
+            | Self::ProvisionWorkspace { .. } => None,
         }
     }
+
+    /// The serialized command, minus what differs on every attempt of the
+    /// same request: the id minted for a new user, the generated workspace
+    /// id and salted password hashes.
+    fn idempotency_fingerprint(&self) -> Option<serde_json::Value> {
+        let mut value = serde_json::to_value(self).ok()?;
+        let generated: &[&str] = match self {
+            Self::RegisterUser { .. } => &["id", "password_hash"],
+            Self::ChangePassword { .. } => &["password_hash"],
+            Self::ProvisionWorkspace { .. } => &["workspace_id"],
+            _ => &[],
+        };
+        if let Some(fields) = value
+            .as_object_mut()
+            .and_then(|tagged| tagged.values_mut().next())
+            .and_then(|fields| fields.as_object_mut())
+        {
+            for field in generated {
+                fields.remove(*field);
+            }
+        }
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arc_core::aggregate::Command;
+
+    #[test]
+    fn only_profile_edits_retry_on_conflict() {
+        let id = "u1".to_string();
+        let retried = |cmd: UserCommand| cmd.retry_on_conflict().is_some();
+        assert!(retried(UserCommand::UpdateProfile {
+            id: id.clone(),
+            name: "Alice".into(),
+        }));
+        assert!(retried(UserCommand::ChangeEmail {
+            id: id.clone(),
+            email: "a@b.c".into(),
+        }));
+        assert!(!retried(UserCommand::ChangePassword {
+            id: id.clone(),
+            password_hash: "$argon2$x".into(),
+        }));
+        assert!(!retried(UserCommand::DeleteUser { id }));
+    }
+
+    #[test]
+    fn fingerprint_ignores_generated_fields_only() {
+        let register = |id: &str, email: &str, hash: &str| UserCommand::RegisterUser {
+            id: id.into(),
+            name: "Alice".into(),
+            email: email.into(),
+            password_hash: hash.into(),
+        };
+        let fingerprint = |cmd: UserCommand| cmd.idempotency_fingerprint().unwrap();
+
+        assert_eq!(
+            fingerprint(register("u1", "a@example.com", "$argon2$1")),
+            fingerprint(register("u2", "a@example.com", "$argon2$2"))
+        );
+        assert_ne!(
+            fingerprint(register("u1", "a@example.com", "$argon2$1")),
+            fingerprint(register("u1", "b@example.com", "$argon2$1"))
+        );
+        assert_ne!(
+            fingerprint(UserCommand::DeleteUser { id: "u1".into() }),
+            fingerprint(UserCommand::DeleteUser { id: "u2".into() })
+        );
+    }
 }
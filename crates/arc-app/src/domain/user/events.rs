@@ -1,11 +1,11 @@
+use arc_core::domain_event::DomainEvent;
 use serde::{Deserialize, Serialize};
 
-/// Typed domain event payloads. Event payloads are stored on `Event::payload` as
-/// untyped JSON, but this enum documents the wire shape and will be used as a
-/// deserialization target by Step 2 projections that need typed access.
-#[allow(dead_code)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "snake_case")]
+/// Typed `User` event payloads. The variant name is the stored `event_type`
+/// and the variant's fields are the payload; `UserAggregate` emits and folds
+/// these, and `UserProjector` decodes them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
 pub enum UserDomainEvent {
     UserRegistered {
         id: String,
@@ -23,4 +23,78 @@ pub enum UserDomainEvent {
         password_hash: String,
     },
     UserDeleted,
+    RoleGranted {
+        role: String,
+    },
+    RoleRevoked {
+        role: String,
+    },
+    /// The address that was verified; a later `EmailChanged` un-verifies.
+    EmailVerified {
+        email: String,
+    },
+    WorkspaceProvisioned {
+        workspace_id: String,
+    },
+}
+
+impl DomainEvent for UserDomainEvent {
+    const EVENT_TYPES: &'static [&'static str] = &[
+        "UserRegistered",
+        "ProfileUpdated",
+        "EmailChanged",
+        "PasswordChanged",
+        "UserDeleted",
+        "RoleGranted",
+        "RoleRevoked",
+        "EmailVerified",
+        "WorkspaceProvisioned",
+    ];
+
+    fn event_type(&self) -> &'static str {
+        match self {
+            UserDomainEvent::UserRegistered { .. } => "UserRegistered",
+            UserDomainEvent::ProfileUpdated { .. } => "ProfileUpdated",
+            UserDomainEvent::EmailChanged { .. } => "EmailChanged",
+            UserDomainEvent::PasswordChanged { .. } => "PasswordChanged",
+            UserDomainEvent::UserDeleted => "UserDeleted",
+            UserDomainEvent::RoleGranted { .. } => "RoleGranted",
+            UserDomainEvent::RoleRevoked { .. } => "RoleRevoked",
+            UserDomainEvent::EmailVerified { .. } => "EmailVerified",
+            UserDomainEvent::WorkspaceProvisioned { .. } => "WorkspaceProvisioned",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn payloads_keep_the_stored_shape() {
+        let registered = UserDomainEvent::UserRegistered {
+            id: "u1".into(),
+            name: "Alice".into(),
+            email: "a@b.c".into(),
+            password_hash: "$argon2$x".into(),
+        };
+        assert_eq!(
+            registered.to_payload().unwrap(),
+            json!({"id":"u1","name":"Alice","email":"a@b.c","password_hash":"$argon2$x"})
+        );
+        assert_eq!(
+            UserDomainEvent::UserDeleted.to_payload().unwrap(),
+            json!({})
+        );
+
+        for name in UserDomainEvent::EVENT_TYPES {
+            let decoded = UserDomainEvent::from_payload(name, &json!({"unexpected": true}));
+            if *name == "UserDeleted" {
+                assert_eq!(decoded.unwrap(), UserDomainEvent::UserDeleted);
+            } else {
+                assert!(!decoded.unwrap_err().is_unknown_event_type());
+            }
+        }
+    }
 }
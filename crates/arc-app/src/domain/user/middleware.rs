@@ -0,0 +1,365 @@
+//! Command middleware for the `User` aggregate.
+//!
+//! `UniqueEmail` rejects `RegisterUser` and `ChangeEmail` commands whose
+//! address already belongs to another user in `users_view`. The check is
+//! best-effort — the projection can lag the event store — and the
+//! authoritative guard stays the `UNIQUE` index on `users_view.email`. A
+//! failed lookup fails the command rather than letting it through unchecked.
+//!
+//! `CurrentRoles` replaces the roles a command arrives with (copied into the
+//! JWT or cookie session at sign-in) with the ones `users_view` holds now, so
+//! a revoked role stops authorizing commands right away rather than when the
+//! token expires. It runs ahead of authorization.
+//!
+//! `VerificationToken` lets a user verify their own email only with the
+//! token emailed to them (see `domain::user::verification`), issued for
+//! their current address. The system actor and admins verify without one.
+
+use crate::domain::user::commands::UserCommand;
+use crate::domain::user::projector::roles_of;
+use crate::domain::user::projector::USERS_VIEW;
+use crate::domain::user::ADMIN_ROLE;
+use crate::helpers::jwt::decode_email_verification_token;
+use arc_core::audit::{ANONYMOUS_ACTOR, SYSTEM_ACTOR};
+use arc_core::authorization::Principal;
+use arc_core::command_bus::{CommandBusError, CommandBusResult, CommandContext};
+use arc_core::command_middleware::{CommandEnvelope, CommandMiddleware};
+use arc_core::read_model_store::ReadModelStore;
+use async_trait::async_trait;
+use serde_json::json;
+use std::sync::Arc;
+
+pub struct UniqueEmail {
+    read_model_store: Arc<dyn ReadModelStore>,
+}
+
+impl UniqueEmail {
+    pub fn new(read_model_store: Arc<dyn ReadModelStore>) -> Self {
+        Self { read_model_store }
+    }
+
+    /// Aggregate id of the user currently holding `email`, if any.
+    async fn owner_of(&self, email: &str) -> CommandBusResult<Option<String>> {
+        let rows = self
+            .read_model_store
+            .find_by(USERS_VIEW, "email", &json!(email))
+            .await
+            .map_err(|e| CommandBusError::other(format!("users_view lookup failed: {e}")))?;
+        Ok(rows
+            .first()
+            .and_then(|row| row.get("id"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string))
+    }
+}
+
+#[async_trait]
+impl CommandMiddleware for UniqueEmail {
+    fn name(&self) -> &str {
+        "unique_email"
+    }
+
+    async fn before(
+        &self,
+        command: &CommandEnvelope<'_>,
+        _context: &mut CommandContext,
+    ) -> CommandBusResult<()> {
+        let (id, email) = match command.downcast_ref::<UserCommand>() {
+            Some(UserCommand::RegisterUser { id, email, .. })
+            | Some(UserCommand::ChangeEmail { id, email }) => (id, email),
+            _ => return Ok(()),
+        };
+        match self.owner_of(email).await? {
+            Some(owner) if owner != *id => Err(CommandBusError::rejected(
+                command.command_type,
+                format!("email '{email}' is already registered"),
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub struct CurrentRoles {
+    read_model_store: Arc<dyn ReadModelStore>,
+}
+
+impl CurrentRoles {
+    pub fn new(read_model_store: Arc<dyn ReadModelStore>) -> Self {
+        Self { read_model_store }
+    }
+}
+
+#[async_trait]
+impl CommandMiddleware for CurrentRoles {
+    fn name(&self) -> &str {
+        "current_roles"
+    }
+
+    async fn before(
+        &self,
+        _command: &CommandEnvelope<'_>,
+        context: &mut CommandContext,
+    ) -> CommandBusResult<()> {
+        if context.actor_id == SYSTEM_ACTOR || context.actor_id == ANONYMOUS_ACTOR {
+            return Ok(());
+        }
+        let row = self
+            .read_model_store
+            .get(USERS_VIEW, &context.actor_id)
+            .await
+            .map_err(|e| CommandBusError::other(format!("users_view lookup failed: {e}")))?;
+        context.roles = row.as_ref().map(roles_of).unwrap_or_default();
+        Ok(())
+    }
+}
+
+pub struct VerificationToken {
+    read_model_store: Arc<dyn ReadModelStore>,
+}
+
+impl VerificationToken {
+    pub fn new(read_model_store: Arc<dyn ReadModelStore>) -> Self {
+        Self { read_model_store }
+    }
+
+    /// Current email of user `id` in `users_view`, if the user is there.
+    async fn email_of(&self, id: &str) -> CommandBusResult<Option<String>> {
+        let row = self
+            .read_model_store
+            .get(USERS_VIEW, id)
+            .await
+            .map_err(|e| CommandBusError::other(format!("users_view lookup failed: {e}")))?;
+        Ok(row
+            .and_then(|row| row.get("email").cloned())
+            .and_then(|v| v.as_str().map(str::to_string)))
+    }
+}
+
+#[async_trait]
+impl CommandMiddleware for VerificationToken {
+    fn name(&self) -> &str {
+        "verification_token"
+    }
+
+    async fn before(
+        &self,
+        command: &CommandEnvelope<'_>,
+        context: &mut CommandContext,
+    ) -> CommandBusResult<()> {
+        let Some(UserCommand::VerifyEmail { id, token }) = command.downcast_ref::<UserCommand>()
+        else {
+            return Ok(());
+        };
+        let principal = Principal::from_context(context);
+        if principal.actor_id == SYSTEM_ACTOR || principal.has_role(ADMIN_ROLE) {
+            return Ok(());
+        }
+        let reject = |message: &str| CommandBusError::rejected(command.command_type, message);
+        let token = token
+            .as_deref()
+            .ok_or_else(|| reject("a verification token is required"))?;
+        let claims = decode_email_verification_token(token)
+            .map_err(|_| reject("verification token is invalid or expired"))?;
+        if claims.sub != *id {
+            return Err(reject("verification token was issued to another user"));
+        }
+        // A missing user is left for the aggregate to report as not found.
+        match self.email_of(id).await? {
+            Some(email) if email != claims.email => Err(reject(
+                "verification token was issued for a previous email address",
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::jwt::create_email_verification_token;
+    use arc_core::read_model_store::{
+        InMemoryReadModelStore, ReadModelError, ReadModelResult, Row, Upsert,
+    };
+
+    async fn store_with(id: &str, email: &str) -> Arc<dyn ReadModelStore> {
+        let store = InMemoryReadModelStore::new();
+        store
+            .upsert(Upsert::new(
+                USERS_VIEW,
+                id,
+                json!({ "id": id, "email": email, "version": 1 }),
+            ))
+            .await
+            .unwrap();
+        Arc::new(store)
+    }
+
+    async fn check(middleware: &UniqueEmail, command: UserCommand) -> CommandBusResult<()> {
+        middleware
+            .before(
+                &CommandEnvelope::new("User", &command),
+                &mut CommandContext::system(),
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn rejects_an_email_held_by_another_user() {
+        let m = UniqueEmail::new(store_with("u1", "taken@example.com").await);
+
+        let register = UserCommand::RegisterUser {
+            id: "u2".into(),
+            name: "New".into(),
+            email: "taken@example.com".into(),
+            password_hash: String::new(),
+        };
+        let err = check(&m, register).await.unwrap_err();
+        assert!(matches!(err, CommandBusError::Rejected { .. }));
+
+        let change = UserCommand::ChangeEmail {
+            id: "u2".into(),
+            email: "taken@example.com".into(),
+        };
+        assert!(check(&m, change).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn allows_free_or_own_email_and_other_commands() {
+        let m = UniqueEmail::new(store_with("u1", "mine@example.com").await);
+
+        let own = UserCommand::ChangeEmail {
+            id: "u1".into(),
+            email: "mine@example.com".into(),
+        };
+        assert!(check(&m, own).await.is_ok());
+        let free = UserCommand::ChangeEmail {
+            id: "u1".into(),
+            email: "free@example.com".into(),
+        };
+        assert!(check(&m, free).await.is_ok());
+        let delete = UserCommand::DeleteUser { id: "u2".into() };
+        assert!(check(&m, delete).await.is_ok());
+    }
+
+    /// Read model whose every call fails, as when the database is down.
+    struct DownStore;
+
+    #[async_trait]
+    impl ReadModelStore for DownStore {
+        async fn upsert(&self, _op: Upsert) -> ReadModelResult<()> {
+            Err(ReadModelError::write_failed("down"))
+        }
+        async fn delete(&self, _table: &str, _key: &str) -> ReadModelResult<()> {
+            Err(ReadModelError::write_failed("down"))
+        }
+        async fn get(&self, _table: &str, _key: &str) -> ReadModelResult<Option<Row>> {
+            Err(ReadModelError::query_failed("down"))
+        }
+        async fn find_by(
+            &self,
+            _table: &str,
+            _field: &str,
+            _value: &serde_json::Value,
+        ) -> ReadModelResult<Vec<Row>> {
+            Err(ReadModelError::query_failed("down"))
+        }
+        async fn list(&self, _table: &str) -> ReadModelResult<Vec<Row>> {
+            Err(ReadModelError::query_failed("down"))
+        }
+        async fn truncate(&self, _table: &str) -> ReadModelResult<()> {
+            Err(ReadModelError::schema_failed("down"))
+        }
+    }
+
+    #[tokio::test]
+    async fn lookup_failure_fails_the_command() {
+        let m = UniqueEmail::new(Arc::new(DownStore));
+        let change = UserCommand::ChangeEmail {
+            id: "u1".into(),
+            email: "any@example.com".into(),
+        };
+        let err = check(&m, change).await.unwrap_err();
+        assert!(matches!(err, CommandBusError::Other { .. }));
+    }
+
+    #[tokio::test]
+    async fn current_roles_replace_the_ones_the_request_carried() {
+        let store = InMemoryReadModelStore::new();
+        store
+            .upsert(Upsert::new(
+                USERS_VIEW,
+                "u1",
+                json!({ "id": "u1", "email": "a@example.com", "roles": ["auditor"], "version": 2 }),
+            ))
+            .await
+            .unwrap();
+        let m = CurrentRoles::new(Arc::new(store));
+        let command = UserCommand::DeleteUser { id: "u2".into() };
+        let envelope = CommandEnvelope::new("User", &command);
+
+        // Signed in as an admin; the role has since been revoked.
+        let mut context = CommandContext::for_actor("u1").with_roles([ADMIN_ROLE]);
+        m.before(&envelope, &mut context).await.unwrap();
+        assert_eq!(context.roles, vec!["auditor".to_string()]);
+
+        let mut context = CommandContext::for_actor("gone").with_roles([ADMIN_ROLE]);
+        m.before(&envelope, &mut context).await.unwrap();
+        assert!(context.roles.is_empty());
+
+        let mut context = CommandContext::system();
+        m.before(&envelope, &mut context).await.unwrap();
+        assert_eq!(context.actor_id, SYSTEM_ACTOR);
+
+        let down = CurrentRoles::new(Arc::new(DownStore));
+        let mut context = CommandContext::for_actor("u1");
+        let err = down.before(&envelope, &mut context).await.unwrap_err();
+        assert!(matches!(err, CommandBusError::Other { .. }));
+    }
+
+    async fn verify(
+        middleware: &VerificationToken,
+        context: CommandContext,
+        token: Option<String>,
+    ) -> CommandBusResult<()> {
+        let command = UserCommand::VerifyEmail {
+            id: "u1".into(),
+            token,
+        };
+        middleware
+            .before(&CommandEnvelope::new("User", &command), &mut { context })
+            .await
+    }
+
+    #[tokio::test]
+    async fn users_verify_only_with_a_token_for_their_current_email() {
+        if std::env::var("JWT_SECRET").is_err() {
+            std::env::set_var("JWT_SECRET", "test-jwt-secret");
+        }
+        let m = VerificationToken::new(store_with("u1", "me@example.com").await);
+        let user = || CommandContext::for_actor("u1");
+        let token =
+            |id: &str, email: &str| Some(create_email_verification_token(id, email).unwrap());
+
+        assert!(verify(&m, user(), token("u1", "me@example.com"))
+            .await
+            .is_ok());
+
+        for bad in [
+            None,
+            Some("not-a-token".to_string()),
+            token("u2", "me@example.com"),
+            token("u1", "old@example.com"),
+        ] {
+            let err = verify(&m, user(), bad).await.unwrap_err();
+            assert!(matches!(err, CommandBusError::Rejected { .. }), "{err}");
+        }
+    }
+
+    #[tokio::test]
+    async fn system_and_admins_verify_without_a_token() {
+        let m = VerificationToken::new(store_with("u1", "me@example.com").await);
+        assert!(verify(&m, CommandContext::system(), None).await.is_ok());
+        let admin = CommandContext::for_actor("a1").with_roles([ADMIN_ROLE]);
+        assert!(verify(&m, admin, None).await.is_ok());
+    }
+}
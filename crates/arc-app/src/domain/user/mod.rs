@@ -1,4 +1,10 @@
 pub mod aggregate;
 pub mod commands;
 pub mod events;
+pub mod middleware;
+pub mod onboarding;
 pub mod projector;
+pub mod verification;
+
+/// Role granting every action on every `User` (see `es_stack::policies`).
+pub const ADMIN_ROLE: &str = "admin";
@@ -0,0 +1,253 @@
+//! `OnboardingProcess` — register → verify email → provision workspace.
+//!
+//! A `UserRegistered` event starts one instance per user (keyed by the user
+//! id, since verification arrives in a later request with its own
+//! correlation id). `EmailVerified` provisions the user's workspace and
+//! completes the instance. When a verification timeout is configured, a
+//! user who does not verify within it is deleted as compensation; without
+//! one the instance waits indefinitely. A user deleted in the meantime
+//! simply ends the instance.
+//!
+//! Driven by an [`arc_core::process_manager::ProcessRunner`] subscribed to
+//! the event bus in the background tier (see `es_stack::onboarding_runner`).
+
+use crate::domain::user::aggregate::UserAggregate;
+use crate::domain::user::commands::UserCommand;
+use crate::domain::user::events::UserDomainEvent;
+use arc_core::aggregate::Aggregate;
+use arc_core::event::Event;
+use arc_core::process_manager::{ProcessCommands, ProcessError, ProcessManager, Transition};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use uuid::Uuid;
+
+/// Process name, part of every instance key.
+pub const ONBOARDING_PROCESS: &str = "onboarding";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OnboardingState {
+    pub user_id: String,
+    pub email_verified: bool,
+    pub workspace_id: Option<String>,
+}
+
+pub struct OnboardingProcess {
+    verification_timeout: Option<Duration>,
+}
+
+impl OnboardingProcess {
+    /// Users have `verification_timeout` after registering to verify their
+    /// email before they are deleted; `None` never deletes them.
+    pub fn new(verification_timeout: Option<Duration>) -> Self {
+        Self {
+            verification_timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl ProcessManager for OnboardingProcess {
+    type State = OnboardingState;
+
+    fn name(&self) -> &'static str {
+        ONBOARDING_PROCESS
+    }
+
+    fn handles(&self) -> Vec<String> {
+        vec![
+            "UserRegistered".into(),
+            "EmailVerified".into(),
+            "UserDeleted".into(),
+        ]
+    }
+
+    fn starts(&self, event: &Event) -> bool {
+        event.event_type == "UserRegistered"
+    }
+
+    fn instance_id(&self, event: &Event) -> Option<String> {
+        (event.aggregate_type == UserAggregate::aggregate_type())
+            .then(|| event.aggregate_id.clone())
+    }
+
+    async fn react(
+        &self,
+        state: &mut OnboardingState,
+        event: &Event,
+        commands: &ProcessCommands,
+    ) -> Result<Transition, ProcessError> {
+        let user_event = event
+            .decode::<UserDomainEvent>()
+            .map_err(|e| ProcessError::failed(e.to_string()))?;
+        match user_event {
+            UserDomainEvent::UserRegistered { id, .. } => {
+                state.user_id = id;
+                Ok(match self.verification_timeout {
+                    Some(timeout) => Transition::AwaitWithin(timeout),
+                    None => Transition::Await,
+                })
+            }
+            UserDomainEvent::EmailVerified { .. } => {
+                state.email_verified = true;
+                let provisioned = commands
+                    .dispatch(UserCommand::ProvisionWorkspace {
+                        id: state.user_id.clone(),
+                        workspace_id: Uuid::new_v4().to_string(),
+                    })
+                    .await?;
+                // A replayed dispatch returns the original event, so the
+                // recorded id is the one that was actually provisioned.
+                for event in provisioned {
+                    if let Ok(UserDomainEvent::WorkspaceProvisioned { workspace_id }) =
+                        event.decode::<UserDomainEvent>()
+                    {
+                        state.workspace_id = Some(workspace_id);
+                    }
+                }
+                Ok(Transition::Complete)
+            }
+            _ => Ok(Transition::Complete),
+        }
+    }
+
+    /// Only an unverified registration is rolled back. A failed provisioning
+    /// leaves the verified user in place for an operator to look at.
+    async fn compensate(
+        &self,
+        state: &OnboardingState,
+        error: &ProcessError,
+        commands: &ProcessCommands,
+    ) -> Result<(), ProcessError> {
+        if !matches!(error, ProcessError::TimedOut) || state.email_verified {
+            return Ok(());
+        }
+        tracing::info!(user_id = %state.user_id, "Deleting user who never verified their email");
+        commands
+            .dispatch(UserCommand::DeleteUser {
+                id: state.user_id.clone(),
+            })
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::es_stack::{policies, register_aggregates};
+    use arc_core::authorization::AuthorizationMiddleware;
+    use arc_core::command_bus::{CommandContext, CommandRouter};
+    use arc_core::event_bus::InProcessEventBus;
+    use arc_core::event_store::InMemoryEventStore;
+    use arc_core::process_manager::{
+        InMemoryProcessStore, ProcessRunner, ProcessStatus, ProcessStore,
+    };
+    use std::sync::Arc;
+
+    type Harness = (
+        ProcessRunner<OnboardingProcess>,
+        Arc<CommandRouter>,
+        InMemoryProcessStore,
+    );
+
+    fn harness() -> Harness {
+        harness_with(Some(Duration::from_secs(60)))
+    }
+
+    fn harness_with(verification_timeout: Option<Duration>) -> Harness {
+        let router = Arc::new(register_aggregates(
+            CommandRouter::new(
+                Box::new(InMemoryEventStore::new()),
+                Box::new(InProcessEventBus::new()),
+            )
+            .with_middleware(Arc::new(AuthorizationMiddleware::new(Arc::new(policies())))),
+        ));
+        let store = InMemoryProcessStore::new();
+        let runner = ProcessRunner::new(
+            OnboardingProcess::new(verification_timeout),
+            Arc::new(store.clone()),
+            router.clone(),
+        );
+        (runner, router, store)
+    }
+
+    async fn dispatch(
+        runner: &ProcessRunner<OnboardingProcess>,
+        router: &CommandRouter,
+        command: UserCommand,
+    ) {
+        for event in router
+            .dispatch(command, CommandContext::system())
+            .await
+            .unwrap()
+        {
+            runner.handle_event(&event).await.unwrap();
+        }
+    }
+
+    fn register(id: &str) -> UserCommand {
+        UserCommand::RegisterUser {
+            id: id.into(),
+            name: "Alice".into(),
+            email: "alice@example.com".into(),
+            password_hash: "$argon2$x".into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn verified_user_gets_a_workspace() {
+        let (runner, router, store) = harness();
+        dispatch(&runner, &router, register("u1")).await;
+        dispatch(
+            &runner,
+            &router,
+            UserCommand::VerifyEmail {
+                id: "u1".into(),
+                token: None,
+            },
+        )
+        .await;
+
+        let instance = store.load(ONBOARDING_PROCESS, "u1").await.unwrap().unwrap();
+        assert_eq!(instance.status, ProcessStatus::Completed);
+        let workspace_id = instance.state["workspace_id"].as_str().unwrap();
+
+        let events = router.event_store().load("u1").await.unwrap();
+        let provisioned = events.last().unwrap();
+        assert_eq!(provisioned.event_type, "WorkspaceProvisioned");
+        assert_eq!(provisioned.payload["workspace_id"], workspace_id);
+    }
+
+    #[tokio::test]
+    async fn unverified_user_is_deleted_after_the_timeout() {
+        let (runner, router, store) = harness();
+        dispatch(&runner, &router, register("u1")).await;
+        let deadline = store
+            .load(ONBOARDING_PROCESS, "u1")
+            .await
+            .unwrap()
+            .unwrap()
+            .deadline_us
+            .unwrap();
+
+        assert_eq!(runner.fire_timeouts(deadline).await.unwrap(), 1);
+        let instance = store.load(ONBOARDING_PROCESS, "u1").await.unwrap().unwrap();
+        assert_eq!(instance.status, ProcessStatus::Compensated);
+        let events = router.event_store().load("u1").await.unwrap();
+        assert_eq!(events.last().unwrap().event_type, "UserDeleted");
+    }
+
+    #[tokio::test]
+    async fn without_a_timeout_unverified_users_are_kept() {
+        let (runner, router, store) = harness_with(None);
+        dispatch(&runner, &router, register("u1")).await;
+
+        let instance = store.load(ONBOARDING_PROCESS, "u1").await.unwrap().unwrap();
+        assert_eq!(instance.status, ProcessStatus::Running);
+        assert!(instance.deadline_us.is_none());
+        assert_eq!(runner.fire_timeouts(i64::MAX).await.unwrap(), 0);
+        let events = router.event_store().load("u1").await.unwrap();
+        assert_eq!(events.last().unwrap().event_type, "UserRegistered");
+    }
+}
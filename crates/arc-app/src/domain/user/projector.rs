@@ -11,11 +11,13 @@
 //! at startup via a thin [`EventHandler`] adapter that calls
 //! [`ProjectionEngine::process`](arc_core::projection::ProjectionEngine::process).
 
+use crate::domain::user::events::UserDomainEvent;
+use arc_core::domain_event::DomainEvent;
 use arc_core::event::Event;
 use arc_core::projection::{ProjectionError, ProjectionResult, Projector};
-use arc_core::read_model_store::{ReadModelStore, Upsert};
+use arc_core::read_model_store::{ReadModelIndex, ReadModelSchema, ReadModelStore, Upsert};
 use async_trait::async_trait;
-use serde_json::{json, Value};
+use serde_json::json;
 
 /// The shared read-model table name. Other modules (controllers, login lookup)
 /// reference this constant rather than hard-coding the string.
@@ -42,28 +44,41 @@ impl Projector for UserProjector {
     }
 
     fn handles(&self) -> Vec<String> {
-        vec![
-            "UserRegistered".to_string(),
-            "ProfileUpdated".to_string(),
-            "EmailChanged".to_string(),
-            "PasswordChanged".to_string(),
-            "UserDeleted".to_string(),
-        ]
+        UserDomainEvent::EVENT_TYPES
+            .iter()
+            .map(|t| t.to_string())
+            .collect()
+    }
+
+    /// Login resolves an email to one user, so `email` is unique.
+    fn schema(&self) -> Option<ReadModelSchema> {
+        Some(ReadModelSchema::new(USERS_VIEW).with_index(ReadModelIndex::unique_on(["email"])))
     }
 
     async fn apply(&self, event: &Event, store: &dyn ReadModelStore) -> ProjectionResult<()> {
         let id = &event.aggregate_id;
+        let user_event = event
+            .decode::<UserDomainEvent>()
+            .map_err(|e| project_err(self, event, e.to_string()))?;
 
         // For partial-update events we need the prior row so we can carry
         // forward fields the event doesn't touch. UserRegistered seeds the
         // row outright, so the lookup is skipped.
-        match event.event_type.as_str() {
-            "UserRegistered" => {
+        match user_event {
+            UserDomainEvent::UserRegistered {
+                name,
+                email,
+                password_hash,
+                ..
+            } => {
                 let row = json!({
                     "id": id,
-                    "name": payload_str(&event.payload, "name")?,
-                    "email": payload_str(&event.payload, "email")?,
-                    "password_hash": payload_str(&event.payload, "password_hash")?,
+                    "name": name,
+                    "email": email,
+                    "password_hash": password_hash,
+                    "roles": [],
+                    "email_verified": false,
+                    "workspace_id": null,
                     "version": event.sequence,
                 });
                 store
@@ -72,14 +87,20 @@ impl Projector for UserProjector {
                     .map_err(|e| project_err(self, event, e.to_string()))?;
             }
 
-            "UserDeleted" => {
+            UserDomainEvent::UserDeleted => {
                 store
                     .delete(USERS_VIEW, id)
                     .await
                     .map_err(|e| project_err(self, event, e.to_string()))?;
             }
 
-            "ProfileUpdated" | "EmailChanged" | "PasswordChanged" => {
+            update @ (UserDomainEvent::ProfileUpdated { .. }
+            | UserDomainEvent::EmailChanged { .. }
+            | UserDomainEvent::PasswordChanged { .. }
+            | UserDomainEvent::RoleGranted { .. }
+            | UserDomainEvent::RoleRevoked { .. }
+            | UserDomainEvent::EmailVerified { .. }
+            | UserDomainEvent::WorkspaceProvisioned { .. }) => {
                 let existing = store
                     .get(USERS_VIEW, id)
                     .await
@@ -97,15 +118,30 @@ impl Projector for UserProjector {
                     return Ok(());
                 };
 
-                match event.event_type.as_str() {
-                    "ProfileUpdated" => {
-                        row["name"] = json!(payload_str(&event.payload, "name")?);
+                match update {
+                    UserDomainEvent::ProfileUpdated { name } => row["name"] = json!(name),
+                    UserDomainEvent::EmailChanged { email } => {
+                        row["email"] = json!(email);
+                        row["email_verified"] = json!(false);
+                    }
+                    UserDomainEvent::PasswordChanged { password_hash } => {
+                        row["password_hash"] = json!(password_hash)
                     }
-                    "EmailChanged" => {
-                        row["email"] = json!(payload_str(&event.payload, "email")?);
+                    UserDomainEvent::RoleGranted { role } => {
+                        let mut roles = roles_of(&row);
+                        if !roles.contains(&role) {
+                            roles.push(role);
+                        }
+                        row["roles"] = json!(roles);
                     }
-                    "PasswordChanged" => {
-                        row["password_hash"] = json!(payload_str(&event.payload, "password_hash")?);
+                    UserDomainEvent::RoleRevoked { role } => {
+                        let mut roles = roles_of(&row);
+                        roles.retain(|r| *r != role);
+                        row["roles"] = json!(roles);
+                    }
+                    UserDomainEvent::EmailVerified { .. } => row["email_verified"] = json!(true),
+                    UserDomainEvent::WorkspaceProvisioned { workspace_id } => {
+                        row["workspace_id"] = json!(workspace_id)
                     }
                     _ => unreachable!(),
                 }
@@ -116,18 +152,24 @@ impl Projector for UserProjector {
                     .await
                     .map_err(|e| project_err(self, event, e.to_string()))?;
             }
-
-            _ => {}
         }
 
         Ok(())
     }
 }
 
-fn payload_str<'a>(payload: &'a Value, field: &str) -> ProjectionResult<&'a str> {
-    payload.get(field).and_then(Value::as_str).ok_or_else(|| {
-        ProjectionError::other(format!("event payload missing string field '{field}'"))
-    })
+/// The `roles` array of a `users_view` row; rows projected before roles
+/// existed have none.
+pub fn roles_of(row: &serde_json::Value) -> Vec<String> {
+    row.get("roles")
+        .and_then(|v| v.as_array())
+        .map(|roles| {
+            roles
+                .iter()
+                .filter_map(|r| r.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
 }
 
 fn project_err(p: &UserProjector, event: &Event, message: impl Into<String>) -> ProjectionError {
@@ -143,63 +185,58 @@ fn project_err(p: &UserProjector, event: &Event, message: impl Into<String>) ->
 mod tests {
     use super::*;
     use arc_core::audit::AuditMetadata;
-    use arc_core::read_model_store::InMemoryReadModelStore;
+    use arc_core::testing::ProjectorFixture;
 
-    fn ev(agg_id: &str, seq: i64, ty: &str, payload: Value) -> Event {
-        Event::new("User", agg_id, seq, ty, payload).with_audit(AuditMetadata::test_default())
+    fn fixture() -> ProjectorFixture<UserProjector> {
+        ProjectorFixture::new(UserProjector::new(), "User")
+    }
+
+    fn registered() -> UserDomainEvent {
+        UserDomainEvent::UserRegistered {
+            id: "u1".into(),
+            name: "Alice".into(),
+            email: "a@b.c".into(),
+            password_hash: "$argon2$x".into(),
+        }
+    }
+
+    fn renamed(name: &str) -> UserDomainEvent {
+        UserDomainEvent::ProfileUpdated { name: name.into() }
     }
 
     #[tokio::test]
     async fn registered_then_profile_updated_carries_email_forward() {
-        let store = InMemoryReadModelStore::new();
-        let p = UserProjector::new();
-
-        p.apply(
-            &ev(
+        fixture()
+            .given("u1", [registered(), renamed("Alice2")])
+            .await
+            .then_expect_row(
+                USERS_VIEW,
                 "u1",
-                1,
-                "UserRegistered",
-                json!({"id":"u1","name":"Alice","email":"a@b.c","password_hash":"$argon2$x"}),
-            ),
-            &store,
-        )
-        .await
-        .unwrap();
-
-        p.apply(
-            &ev("u1", 2, "ProfileUpdated", json!({"name":"Alice2"})),
-            &store,
-        )
-        .await
-        .unwrap();
-
-        let row = store.get(USERS_VIEW, "u1").await.unwrap().unwrap();
-        assert_eq!(row["name"], "Alice2");
-        assert_eq!(row["email"], "a@b.c");
-        assert_eq!(row["version"], 2);
+                json!({
+                    "id": "u1",
+                    "name": "Alice2",
+                    "email": "a@b.c",
+                    "password_hash": "$argon2$x",
+                    "roles": [],
+                    "email_verified": false,
+                    "workspace_id": null,
+                    "version": 2,
+                }),
+            )
+            .await
+            .then_expect_replay_converges()
+            .await;
     }
 
     #[tokio::test]
     async fn deleted_removes_row() {
-        let store = InMemoryReadModelStore::new();
-        let p = UserProjector::new();
-
-        p.apply(
-            &ev(
-                "u1",
-                1,
-                "UserRegistered",
-                json!({"id":"u1","name":"Alice","email":"a@b.c","password_hash":"$argon2$x"}),
-            ),
-            &store,
-        )
-        .await
-        .unwrap();
-        p.apply(&ev("u1", 2, "UserDeleted", json!({})), &store)
+        fixture()
+            .given("u1", [registered(), UserDomainEvent::UserDeleted])
             .await
-            .unwrap();
-
-        assert!(store.get(USERS_VIEW, "u1").await.unwrap().is_none());
+            .then_expect_no_row(USERS_VIEW, "u1")
+            .await
+            .then_expect_replay_converges()
+            .await;
     }
 
     #[tokio::test]
@@ -207,73 +244,136 @@ mod tests {
         // Pinned: the framework promises at-least-once semantics on the bus
         // and replay-from-zero on rebuild. Re-applying the same event must
         // not corrupt state.
-        let store = InMemoryReadModelStore::new();
-        let p = UserProjector::new();
-        let registered = ev(
-            "u1",
-            1,
-            "UserRegistered",
-            json!({"id":"u1","name":"Alice","email":"a@b.c","password_hash":"$argon2$x"}),
-        );
-        let updated = ev("u1", 2, "ProfileUpdated", json!({"name":"Alice2"}));
-
+        let mut f = fixture();
+        let registered = f.event("u1", registered());
+        let updated = f.event("u1", renamed("Alice2"));
         for _ in 0..3 {
-            p.apply(&registered, &store).await.unwrap();
-            p.apply(&updated, &store).await.unwrap();
+            f.deliver([&registered, &updated]).await;
         }
 
-        let row = store.get(USERS_VIEW, "u1").await.unwrap().unwrap();
+        let row = f.store().get(USERS_VIEW, "u1").await.unwrap().unwrap();
         assert_eq!(row["name"], "Alice2");
         assert_eq!(row["version"], 2);
+        f.then_expect_replay_converges().await;
     }
 
     #[tokio::test]
     async fn out_of_order_replay_does_not_regress() {
-        let store = InMemoryReadModelStore::new();
-        let p = UserProjector::new();
+        let mut f = fixture();
+        let registered = f.event("u1", registered());
+        let updated = f.event("u1", renamed("Alice2"));
+        let changed = f.event(
+            "u1",
+            UserDomainEvent::EmailChanged {
+                email: "new@b.c".into(),
+            },
+        );
+        // sequence 3 arrives before 2, which must not stomp the newer email
+        f.deliver([&registered, &changed, &updated]).await;
+
+        let row = f.store().get(USERS_VIEW, "u1").await.unwrap().unwrap();
+        assert_eq!(row["email"], "new@b.c");
+        assert_eq!(row["version"], 3);
+    }
 
-        p.apply(
-            &ev(
+    #[tokio::test]
+    async fn role_events_maintain_roles_array() {
+        let mut f = fixture();
+        // Rows projected before roles existed carry no `roles` key.
+        f.event("u1", registered());
+        f.store()
+            .upsert(Upsert::new(
+                USERS_VIEW,
                 "u1",
-                1,
-                "UserRegistered",
-                json!({"id":"u1","name":"Alice","email":"a@b.c","password_hash":"$argon2$x"}),
-            ),
-            &store,
-        )
-        .await
-        .unwrap();
-        // sequence 3 arrives first
-        p.apply(
-            &ev("u1", 3, "EmailChanged", json!({"email":"new@b.c"})),
-            &store,
+                json!({"id":"u1","name":"Alice","email":"a@b.c","version":1}),
+            ))
+            .await
+            .unwrap();
+        f.given(
+            "u1",
+            [
+                UserDomainEvent::RoleGranted {
+                    role: "admin".into(),
+                },
+                UserDomainEvent::RoleGranted {
+                    role: "auditor".into(),
+                },
+                UserDomainEvent::RoleRevoked {
+                    role: "admin".into(),
+                },
+            ],
         )
-        .await
-        .unwrap();
-        // sequence 2 arrives later — must not stomp newer email
-        p.apply(
-            &ev("u1", 2, "ProfileUpdated", json!({"name":"Alice2"})),
-            &store,
+        .await;
+
+        let row = f.store().get(USERS_VIEW, "u1").await.unwrap().unwrap();
+        assert_eq!(roles_of(&row), vec!["auditor"]);
+        assert_eq!(row["version"], 4);
+        f.then_expect_replay_converges().await;
+    }
+
+    #[tokio::test]
+    async fn onboarding_events_track_verification_and_workspace() {
+        let mut f = fixture();
+        f.given(
+            "u1",
+            [
+                registered(),
+                UserDomainEvent::EmailVerified {
+                    email: "a@b.c".into(),
+                },
+                UserDomainEvent::WorkspaceProvisioned {
+                    workspace_id: "ws-1".into(),
+                },
+            ],
         )
-        .await
-        .unwrap();
+        .await;
+        let row = f.store().get(USERS_VIEW, "u1").await.unwrap().unwrap();
+        assert_eq!(row["email_verified"], true);
+        assert_eq!(row["workspace_id"], "ws-1");
 
-        let row = store.get(USERS_VIEW, "u1").await.unwrap().unwrap();
-        assert_eq!(row["email"], "new@b.c");
-        assert_eq!(row["version"], 3);
+        f.given(
+            "u1",
+            [UserDomainEvent::EmailChanged {
+                email: "b@b.c".into(),
+            }],
+        )
+        .await;
+        let row = f.store().get(USERS_VIEW, "u1").await.unwrap().unwrap();
+        assert_eq!(row["email_verified"], false);
+        f.then_expect_replay_converges().await;
     }
 
     #[tokio::test]
     async fn update_without_prior_row_is_a_warn_skip() {
-        let store = InMemoryReadModelStore::new();
-        let p = UserProjector::new();
-        p.apply(
-            &ev("u-orphan", 5, "ProfileUpdated", json!({"name":"X"})),
-            &store,
+        let mut f = fixture();
+        f.event("u-orphan", registered());
+        let updated = f.event("u-orphan", renamed("X"));
+        f.deliver([&updated])
+            .await
+            .then_expect_no_row(USERS_VIEW, "u-orphan")
+            .await;
+    }
+
+    #[tokio::test]
+    async fn mismatched_payload_fails_with_event_context() {
+        let mut f = fixture();
+        let bad = Event::new(
+            "User",
+            "u1",
+            1,
+            "UserRegistered",
+            json!({"id":"u1","name":"Alice"}),
         )
-        .await
-        .unwrap();
-        assert!(store.get(USERS_VIEW, "u-orphan").await.unwrap().is_none());
+        .with_audit(AuditMetadata::test_default());
+
+        let err = f.try_deliver(&bad).await.unwrap_err();
+        assert!(matches!(
+            err,
+            ProjectionError::HandleFailed { ref event_type, ref event_id, .. }
+                if event_type == "UserRegistered" && *event_id == bad.event_id.to_string()
+        ));
+        assert!(err.to_string().contains("email"));
+        f.then_expect_no_row(USERS_VIEW, "u1").await;
     }
 }
 
@@ -285,7 +385,8 @@ mod tests {
 // `users_view` and replays the entire event log through `UserProjector`
 // against `SqliteReadModelStore`. Asserts deterministic convergence — the
 // promise that lets operators recover from a corrupted read model with
-// `rebuild_all`.
+// `rebuild_all`, or with `arc rebuild`, which rebuilds through
+// `rebuild_projection` into a shadow table and swaps it in.
 #[cfg(test)]
 mod replay_from_zero {
     use super::{UserProjector, USERS_VIEW};
@@ -296,7 +397,7 @@ mod replay_from_zero {
     use arc_core::event::Event;
     use arc_core::event_store::{EventStore, VersionCheck};
     use arc_core::projection::ProjectionEngine;
-    use arc_core::read_model_store::ReadModelStore;
+    use arc_core::read_model_store::{ReadModelStore, Upsert};
     use arc_es_sqlite::{SqliteEventStore, SqliteReadModelStore};
     use diesel_migrations::MigrationHarness;
     use serde_json::json;
@@ -389,5 +490,28 @@ mod replay_from_zero {
             rm_store.get(USERS_VIEW, "u2").await.unwrap().unwrap()["version"],
             1
         );
+
+        // Blue/green: the rebuilt table replaces `users_view` with its
+        // unique email index, and the old one stays as `users_view__prev`.
+        engine
+            .rebuild_projection("UserProjector")
+            .await
+            .expect("rebuild_projection");
+        let by_email = rm_store
+            .find_by(USERS_VIEW, "email", &json!("b@b.c"))
+            .await
+            .unwrap();
+        assert_eq!(by_email.len(), 1);
+        assert_eq!(by_email[0]["id"], "u2");
+        assert_eq!(
+            rm_store.list("users_view__prev").await.unwrap(),
+            rm_store.list(USERS_VIEW).await.unwrap()
+        );
+        let mut dup = rm_store.get(USERS_VIEW, "u2").await.unwrap().unwrap();
+        dup["id"] = json!("u3");
+        assert!(rm_store
+            .upsert(Upsert::new(USERS_VIEW, "u3", dup))
+            .await
+            .is_err());
     }
 }
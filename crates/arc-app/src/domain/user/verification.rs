@@ -0,0 +1,50 @@
+//! `VerificationMailer` — emails a user the token that verifies their
+//! address, on registration and again on every email change (which
+//! un-verifies). The user redeems it through `VerifyEmail`, checked by
+//! `middleware::VerificationToken`.
+//!
+//! Subscribed to the event bus in the background tier (see
+//! `es_stack::verification_mailer`), so a mail failure is retried and never
+//! fails the write that triggered it.
+
+use crate::domain::user::events::UserDomainEvent;
+use crate::helpers::jwt::create_email_verification_token;
+use crate::helpers::mailer::{Email, Mailer};
+use arc_core::event::Event;
+use arc_core::event_bus::EventHandler;
+use async_trait::async_trait;
+use std::error::Error;
+use std::sync::Arc;
+
+pub struct VerificationMailer {
+    mailer: Arc<dyn Mailer>,
+}
+
+impl VerificationMailer {
+    pub fn new(mailer: Arc<dyn Mailer>) -> Self {
+        Self { mailer }
+    }
+}
+
+#[async_trait]
+impl EventHandler for VerificationMailer {
+    fn handles(&self) -> Vec<String> {
+        vec!["UserRegistered".into(), "EmailChanged".into()]
+    }
+
+    async fn handle(&self, event: &Event) -> Result<(), Box<dyn Error + Send + Sync>> {
+        let email = match event.decode::<UserDomainEvent>()? {
+            UserDomainEvent::UserRegistered { email, .. }
+            | UserDomainEvent::EmailChanged { email } => email,
+            _ => return Ok(()),
+        };
+        let token = create_email_verification_token(&event.aggregate_id, &email)?;
+        self.mailer
+            .send(Email {
+                to: email,
+                subject: "Verify your email address".into(),
+                body: format!("Use this token to verify your email address:\n\n{token}"),
+            })
+            .await
+    }
+}
@@ -7,6 +7,9 @@
 //!   should respond 503; an audit gap on regulated data is unacceptable.
 //! - Everything else → [`FailurePolicy::FailOpenWarn`]. Failure is warned and
 //!   the read proceeds.
+//!
+//! Denied reads go through [`record_denial`]; the response is already a
+//! refusal, so a sink failure is only warned.
 
 use actix_web::HttpRequest;
 use arc_core::access_log::{AccessLogger, AccessedResource, FailurePolicy, Identity, PurposeOfUse};
@@ -77,3 +80,22 @@ pub async fn record_read(
         },
     }
 }
+
+/// Log a read refused by an authorization policy.
+pub async fn record_denial(
+    logger: &dyn AccessLogger,
+    req: &HttpRequest,
+    actor_id: impl Into<String>,
+    resource: AccessedResource,
+    purpose: PurposeOfUse,
+    reason: String,
+) {
+    let identity = identity_from(req, actor_id);
+    let correlation = correlation_from(req);
+    if let Err(e) = logger
+        .log_denial(identity, resource, purpose, reason, correlation)
+        .await
+    {
+        tracing::warn!(error = %e, "access log sink rejected denial");
+    }
+}
@@ -1,19 +1,24 @@
 //! Build a `CommandContext` from an Actix HTTP request.
 //!
-//! Pulls `source_ip`, `user_agent`, and an optional `X-Correlation-Id` header
-//! into the context so every event written by the request carries
-//! request-scoped audit metadata (HIPAA §164.312(b)).
+//! Pulls `source_ip`, `user_agent`, and the optional `X-Correlation-Id` and
+//! `Idempotency-Key` headers into the context so every event written by the request carries
+//! request-scoped audit metadata (HIPAA §164.312(b)). Roles from the JWT
+//! middleware's `Principal` ride along for authorization policies.
 
-use actix_web::HttpRequest;
+use actix_web::{HttpMessage, HttpRequest};
 use arc_core::audit::ANONYMOUS_ACTOR;
+use arc_core::authorization::Principal;
 use arc_core::command_bus::CommandContext;
 use uuid::Uuid;
 
 /// Build a `CommandContext` for an authenticated request.
 /// Pass the aggregate UUID resolved by the JWT middleware as `actor_id`.
+/// Cookie-session callers attach `SessionUser::roles` with `with_roles`.
 pub fn for_actor(req: &HttpRequest, actor_id: impl Into<String>) -> CommandContext {
+    let actor_id = actor_id.into();
     CommandContext {
-        actor_id: actor_id.into(),
+        roles: roles_from(req, &actor_id),
+        actor_id,
         session_id: None,
         source_ip: req
             .connection_info()
@@ -26,12 +31,33 @@ pub fn for_actor(req: &HttpRequest, actor_id: impl Into<String>) -> CommandConte
             .map(str::to_string),
         correlation_id: correlation_from(req),
         causation_id: None,
+        idempotency_key: idempotency_key_from(req),
     }
 }
 
 /// Build a `CommandContext` for an unauthenticated request (e.g. self-registration).
+///
+/// Every anonymous caller shares `ANONYMOUS_ACTOR`, so an `Idempotency-Key`
+/// is scoped to the caller's address to keep clients from replaying each
+/// other's results. Without an address the key is ignored.
 pub fn anonymous(req: &HttpRequest) -> CommandContext {
-    for_actor(req, ANONYMOUS_ACTOR)
+    let context = for_actor(req, ANONYMOUS_ACTOR);
+    match context.source_ip.clone() {
+        Some(ip) => context.with_idempotency_scope(&ip),
+        None => CommandContext {
+            idempotency_key: None,
+            ..context
+        },
+    }
+}
+
+/// Roles of the request's `Principal`, when it is the acting user.
+fn roles_from(req: &HttpRequest, actor_id: &str) -> Vec<String> {
+    req.extensions()
+        .get::<Principal>()
+        .filter(|p| p.actor_id == actor_id)
+        .map(|p| p.roles.clone())
+        .unwrap_or_default()
 }
 
 /// Read `X-Correlation-Id` from the incoming request, falling back to a fresh UUID.
@@ -42,3 +68,13 @@ fn correlation_from(req: &HttpRequest) -> Uuid {
         .and_then(|s| Uuid::parse_str(s).ok())
         .unwrap_or_else(Uuid::new_v4)
 }
+
+/// Read `Idempotency-Key` from the incoming request. Blank values are ignored.
+fn idempotency_key_from(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get("Idempotency-Key")
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
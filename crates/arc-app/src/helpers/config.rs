@@ -1,4 +1,9 @@
+use arc_core::command_bus::RetryPolicy;
+use arc_core::integrity::{HmacSha256Chain, IntegrityChain};
+use arc_core::snapshot::SnapshotPolicy;
 use std::env;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Default database file path used when DATABASE_URL is not set
 pub const DEFAULT_DATABASE_URL: &str = "database/database.sqlite";
@@ -6,6 +11,38 @@ pub const DEFAULT_DATABASE_URL: &str = "database/database.sqlite";
 /// Default database connection pool size
 pub const DEFAULT_POOL_LIMIT: u32 = 10;
 
+/// Default number of events between aggregate snapshots
+pub const DEFAULT_SNAPSHOT_EVERY: u64 = 100;
+
+/// Default attempts per command dispatch when an append hits a concurrency
+/// conflict, including the first
+pub const DEFAULT_COMMAND_RETRY_ATTEMPTS: u32 = 3;
+
+/// Default lifetime of a command idempotency key, in seconds (24 hours)
+pub const DEFAULT_IDEMPOTENCY_TTL_SECS: u64 = 24 * 3600;
+
+/// Default time a new user has to verify their email before onboarding
+/// deletes the account, in seconds. 0 turns the deadline off, so
+/// unverified accounts are kept
+pub const DEFAULT_EMAIL_VERIFICATION_TIMEOUT_SECS: u64 = 0;
+
+/// Default lifetime of an emailed verification token, in seconds (48 hours)
+pub const DEFAULT_EMAIL_VERIFICATION_TOKEN_TTL_SECS: u64 = 48 * 3600;
+
+/// Default time between a user requesting account deletion and the deletion
+/// running, in seconds (30 days)
+pub const DEFAULT_DELETION_GRACE_PERIOD_SECS: u64 = 30 * 24 * 3600;
+
+/// Default time delivered outbox entries are kept before the relay prunes
+/// them, in seconds (7 days). 0 keeps them forever
+pub const DEFAULT_OUTBOX_RETENTION_SECS: u64 = 7 * 24 * 3600;
+
+/// Default address of the `arc worker` health endpoint
+pub const DEFAULT_WORKER_HEALTH_ADDR: &str = "0.0.0.0:8081";
+
+/// Default pause between `arc worker` polls once it is idle, in milliseconds
+pub const DEFAULT_WORKER_POLL_INTERVAL_MS: u64 = 500;
+
 /// Get the database URL from environment or use default
 pub fn database_url() -> String {
     env::var("DATABASE_URL").unwrap_or_else(|_| DEFAULT_DATABASE_URL.to_string())
@@ -18,3 +55,104 @@ pub fn database_pool_limit() -> u32 {
         .parse()
         .expect("DATABASE_POOL_LIMIT must be a number")
 }
+
+/// Get the aggregate snapshot cadence from environment or use default.
+/// `SNAPSHOT_EVERY=0` disables snapshots.
+pub fn snapshot_policy() -> SnapshotPolicy {
+    let every = env::var("SNAPSHOT_EVERY")
+        .unwrap_or_else(|_| DEFAULT_SNAPSHOT_EVERY.to_string())
+        .parse()
+        .expect("SNAPSHOT_EVERY must be a number");
+    SnapshotPolicy::EveryNEvents(every)
+}
+
+/// Get the concurrency-conflict retry policy from environment or use
+/// default. `COMMAND_RETRY_ATTEMPTS=1` disables retries.
+pub fn command_retry_policy() -> RetryPolicy {
+    let attempts = env::var("COMMAND_RETRY_ATTEMPTS")
+        .unwrap_or_else(|_| DEFAULT_COMMAND_RETRY_ATTEMPTS.to_string())
+        .parse()
+        .expect("COMMAND_RETRY_ATTEMPTS must be a number");
+    RetryPolicy::attempts(attempts)
+}
+
+/// Get how long an `Idempotency-Key` replays its original result from
+/// environment or use default
+pub fn idempotency_ttl() -> Duration {
+    let secs = env::var("IDEMPOTENCY_TTL_SECS")
+        .unwrap_or_else(|_| DEFAULT_IDEMPOTENCY_TTL_SECS.to_string())
+        .parse()
+        .expect("IDEMPOTENCY_TTL_SECS must be a number");
+    Duration::from_secs(secs)
+}
+
+/// Get how long a new user has to verify their email from environment or
+/// use default. `None` when the deadline is off
+pub fn email_verification_timeout() -> Option<Duration> {
+    let secs = env::var("EMAIL_VERIFICATION_TIMEOUT_SECS")
+        .unwrap_or_else(|_| DEFAULT_EMAIL_VERIFICATION_TIMEOUT_SECS.to_string())
+        .parse()
+        .expect("EMAIL_VERIFICATION_TIMEOUT_SECS must be a number");
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Get how long an emailed verification token stays valid from environment
+/// or use default
+pub fn email_verification_token_ttl() -> Duration {
+    let secs = env::var("EMAIL_VERIFICATION_TOKEN_TTL_SECS")
+        .unwrap_or_else(|_| DEFAULT_EMAIL_VERIFICATION_TOKEN_TTL_SECS.to_string())
+        .parse()
+        .expect("EMAIL_VERIFICATION_TOKEN_TTL_SECS must be a number");
+    Duration::from_secs(secs)
+}
+
+/// Get how long delivered outbox entries are kept from environment or use
+/// default. `None` keeps them forever
+pub fn outbox_retention() -> Option<Duration> {
+    let secs = env::var("OUTBOX_RETENTION_SECS")
+        .unwrap_or_else(|_| DEFAULT_OUTBOX_RETENTION_SECS.to_string())
+        .parse()
+        .expect("OUTBOX_RETENTION_SECS must be a number");
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Get how long a requested account deletion waits before it runs from
+/// environment or use default
+pub fn deletion_grace_period() -> Duration {
+    let secs = env::var("DELETION_GRACE_PERIOD_SECS")
+        .unwrap_or_else(|_| DEFAULT_DELETION_GRACE_PERIOD_SECS.to_string())
+        .parse()
+        .expect("DELETION_GRACE_PERIOD_SECS must be a number");
+    Duration::from_secs(secs)
+}
+
+/// Build the event integrity chain from `INTEGRITY_KEY` (hex, at least 32
+/// bytes). Returns `None` when unset. Panics on a malformed key so a typo
+/// cannot silently turn signing off.
+pub fn integrity_chain() -> Option<Arc<dyn IntegrityChain>> {
+    let hex_key = env::var("INTEGRITY_KEY").ok()?;
+    let chain = HmacSha256Chain::from_hex(hex_key.trim())
+        .expect("INTEGRITY_KEY must be a hex-encoded key of at least 32 bytes");
+    Some(Arc::new(chain))
+}
+
+/// Whether aggregate loads verify the integrity chain (`INTEGRITY_VERIFY_ON_LOAD`).
+pub fn integrity_verify_on_load() -> bool {
+    env::var("INTEGRITY_VERIFY_ON_LOAD")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+}
+
+/// Address the `arc worker` health endpoint binds (`WORKER_HEALTH_ADDR`).
+pub fn worker_health_addr() -> String {
+    env::var("WORKER_HEALTH_ADDR").unwrap_or_else(|_| DEFAULT_WORKER_HEALTH_ADDR.to_string())
+}
+
+/// Pause between idle `arc worker` polls (`WORKER_POLL_INTERVAL_MS`).
+pub fn worker_poll_interval() -> Duration {
+    let ms = env::var("WORKER_POLL_INTERVAL_MS")
+        .unwrap_or_else(|_| DEFAULT_WORKER_POLL_INTERVAL_MS.to_string())
+        .parse()
+        .expect("WORKER_POLL_INTERVAL_MS must be a number");
+    Duration::from_millis(ms)
+}
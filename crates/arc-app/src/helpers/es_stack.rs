@@ -1,50 +1,259 @@
 //! Shared assembly of the event-sourced stack — `EventStore`, in-process
-//! `EventBus`, `ReadModelStore`, `ProjectionEngine`, and `CommandBus`.
+//! `EventBus`, `ReadModelStore`, `ProjectionEngine`, and the `CommandRouter`.
 //!
-//! Used by the runtime server (`commands::serve`), CLI utilities
-//! (`commands::migrate`, `commands::seed`), and integration tests so the
-//! exact same wiring drives every entry point.
+//! Used by the runtime server (`commands::serve`), the projection worker
+//! (`commands::worker`), CLI utilities (`commands::migrate`,
+//! `commands::seed`), and integration tests so the exact same wiring drives
+//! every entry point.
 
 use crate::domain::user::aggregate::UserAggregate;
+use crate::domain::user::commands::UserCommand;
+use crate::domain::user::middleware::{CurrentRoles, UniqueEmail, VerificationToken};
+use crate::domain::user::onboarding::OnboardingProcess;
 use crate::domain::user::projector::{UserProjector, USERS_VIEW};
-use arc_core::command_bus::CommandBus;
-use arc_core::event_bus::{EventBus, InProcessEventBus};
+use crate::domain::user::verification::VerificationMailer;
+use crate::domain::user::ADMIN_ROLE;
+use crate::helpers::config;
+use crate::helpers::mailer::Mailer;
+use arc_core::access_log::{AccessLogger, NoOpAccessLogger};
+use arc_core::authorization::{
+    AuthorizationMiddleware, PolicySet, PublicPolicy, RolePolicy, SelfPolicy, SystemPolicy,
+    WILDCARD,
+};
+use arc_core::command_bus::CommandRouter;
+use arc_core::command_middleware::{CommandMiddleware, TracingMiddleware};
+use arc_core::event::UpcasterRegistry;
+use arc_core::event_bus::{DeliveryMode, EventBus, EventBusResult, InProcessEventBus};
+use arc_core::event_store::UpcastingEventStore;
+use arc_core::idempotency::IdempotencyStore;
+use arc_core::process_manager::{ProcessRunner, ProcessStore};
 use arc_core::projection::{ProjectionEngine, ProjectionEngineHandler};
 use arc_core::read_model_store::ReadModelStore;
-use arc_es_sqlite::{SqliteEventStore, SqliteReadModelStore};
+use arc_core::scheduler::{CommandScheduler, ScheduleStore};
+use arc_es_sqlite::{
+    SqliteCheckpointStore, SqliteEventStore, SqliteIdempotencyStore, SqliteReadModelStore,
+};
 use std::sync::Arc;
 
 /// Bundle of constructed components — the parts external code keeps a
 /// handle on after wiring.
 pub struct EsStack {
-    pub command_bus: CommandBus<UserAggregate>,
+    pub command_bus: CommandRouter,
     pub read_model_store: Arc<dyn ReadModelStore>,
-    /// Held so callers that want to drive `rebuild_all()` can do so. CLI
-    /// utilities ignore it; the runtime server keeps a clone.
+    /// Held so callers that want to drive `rebuild_all()` or `catch_up()`
+    /// can do so. CLI utilities ignore it; the runtime server keeps a clone.
     #[allow(dead_code)]
     pub projection_engine: Arc<ProjectionEngine>,
 }
 
+/// The event store every entry point reads and writes through: SQLite,
+/// upcasting stored payloads to their current schema on the way out.
+pub type AppEventStore = UpcastingEventStore<SqliteEventStore>;
+
+/// Upcasters for every application event type. When a `DomainEvent`
+/// variant's `schema_version` is bumped, register the upcaster from the
+/// previous version here.
+pub fn upcasters() -> UpcasterRegistry {
+    UpcasterRegistry::new()
+}
+
+/// Open the SQLite event store with the transactional outbox enabled and
+/// the configured integrity chain (HIPAA-5, §164.312(c)(1)), wrapped in the
+/// application's [`upcasters`]. Signing is mandatory when
+/// `APP_ENV=production`; elsewhere a missing `INTEGRITY_KEY` only warns.
+pub async fn event_store(database_url: &str) -> Result<AppEventStore, Box<dyn std::error::Error>> {
+    let store = SqliteEventStore::new(database_url).await?.with_outbox();
+    let store = match config::integrity_chain() {
+        Some(chain) => {
+            tracing::info!("Event integrity chain enabled");
+            store
+                .with_integrity_chain(chain)
+                .verify_on_load(config::integrity_verify_on_load())
+        }
+        None if std::env::var("APP_ENV").as_deref() == Ok("production") => {
+            return Err("INTEGRITY_KEY must be set when APP_ENV=production".into());
+        }
+        None => {
+            tracing::warn!("INTEGRITY_KEY not set; events are stored unsigned");
+            store
+        }
+    };
+    Ok(UpcastingEventStore::new(store, upcasters()))
+}
+
+/// Register every application aggregate on `router`. A new aggregate is
+/// added here once, and the server, the CLI and the test stacks all route
+/// its commands.
+pub fn register_aggregates(router: CommandRouter) -> CommandRouter {
+    router.register::<UserAggregate>()
+}
+
+/// Authorization policies for commands (through [`command_middleware`])
+/// and reads (through the `Authz` extractor). Users register themselves and
+/// manage their own account; admins may do anything to any user. A user's
+/// own `verify_email` also needs the emailed token, checked by
+/// [`VerificationToken`] in [`command_middleware`].
+pub fn policies() -> PolicySet {
+    PolicySet::new()
+        .with_policy(SystemPolicy)
+        .with_policy(PublicPolicy::new("User", ["register"]))
+        .with_policy(SelfPolicy::new(
+            "User",
+            [
+                "read",
+                "update_profile",
+                "change_email",
+                "change_password",
+                "delete",
+                "verify_email",
+            ],
+        ))
+        .with_policy(RolePolicy::new(ADMIN_ROLE, "User", [WILDCARD]))
+}
+
+/// Middleware run around every command, outermost first. Checks that used
+/// to sit in front of `dispatch` in controllers belong here, so every entry
+/// point gets them. `CurrentRoles` loads the actor's own roles just ahead of
+/// authorization, and authorization runs before any other read-model lookup
+/// so a denied actor learns nothing from them.
+pub fn command_middleware(
+    read_model_store: Arc<dyn ReadModelStore>,
+    access_logger: Arc<dyn AccessLogger>,
+) -> Vec<Arc<dyn CommandMiddleware>> {
+    vec![
+        Arc::new(TracingMiddleware),
+        Arc::new(CurrentRoles::new(read_model_store.clone())),
+        Arc::new(
+            AuthorizationMiddleware::new(Arc::new(policies())).with_access_logger(access_logger),
+        ),
+        Arc::new(UniqueEmail::new(read_model_store.clone())),
+        Arc::new(VerificationToken::new(read_model_store)),
+    ]
+}
+
+/// Stack [`command_middleware`] onto `router`. Call before
+/// [`register_aggregates`].
+pub fn with_command_middleware(
+    router: CommandRouter,
+    read_model_store: Arc<dyn ReadModelStore>,
+    access_logger: Arc<dyn AccessLogger>,
+) -> CommandRouter {
+    command_middleware(read_model_store, access_logger)
+        .into_iter()
+        .fold(router, CommandRouter::with_middleware)
+}
+
+/// Command router over `event_store` publishing to `event_bus`, with the
+/// outbox, the configured snapshot and retry policies, `Idempotency-Key`
+/// replay through `idempotency` and the [`command_middleware`] applied to
+/// every aggregate, recording authorization denials to `access_logger`.
+pub fn command_router(
+    event_store: &AppEventStore,
+    event_bus: Box<dyn EventBus>,
+    idempotency: Arc<dyn IdempotencyStore>,
+    read_model_store: Arc<dyn ReadModelStore>,
+    access_logger: Arc<dyn AccessLogger>,
+) -> CommandRouter {
+    let router = CommandRouter::new(Box::new(event_store.clone()), event_bus)
+        .with_snapshot_policy(config::snapshot_policy())
+        .with_retry_policy(config::command_retry_policy())
+        .with_idempotency(idempotency, config::idempotency_ttl())
+        .with_outbox(Arc::new(event_store.clone()));
+    register_aggregates(with_command_middleware(
+        router,
+        read_model_store,
+        access_logger,
+    ))
+}
+
+/// Runner for the onboarding process, subscribed to `event_bus` in the
+/// background tier so a step never blocks or fails the write that triggered
+/// it. `router` must publish to the same bus (clones share handlers). The
+/// caller drives deadlines with [`ProcessRunner::run_timeouts`].
+pub async fn onboarding_runner(
+    event_bus: &InProcessEventBus,
+    router: Arc<CommandRouter>,
+    process_store: Arc<dyn ProcessStore>,
+) -> EventBusResult<ProcessRunner<OnboardingProcess>> {
+    let runner = ProcessRunner::new(
+        OnboardingProcess::new(config::email_verification_timeout()),
+        process_store,
+        router,
+    );
+    event_bus
+        .subscribe_with(Box::new(runner.clone()), DeliveryMode::Background)
+        .await?;
+    Ok(runner)
+}
+
+/// Subscribe the [`VerificationMailer`] to `event_bus` in the background
+/// tier, sending each new or changed address its verification token
+/// through `mailer`.
+pub async fn verification_mailer(
+    event_bus: &InProcessEventBus,
+    mailer: Arc<dyn Mailer>,
+) -> EventBusResult<()> {
+    event_bus
+        .subscribe_with(
+            Box::new(VerificationMailer::new(mailer)),
+            DeliveryMode::Background,
+        )
+        .await
+}
+
+/// Scheduler dispatching delayed commands through `router`. Every command
+/// type that may be scheduled is registered here; the names are persisted
+/// with pending jobs and must not change. The caller drives it with
+/// [`CommandScheduler::run`].
+pub fn command_scheduler(
+    router: Arc<CommandRouter>,
+    schedule_store: Arc<dyn ScheduleStore>,
+) -> CommandScheduler {
+    CommandScheduler::new(schedule_store, router).register::<UserCommand>("UserCommand")
+}
+
+/// Projection engine over `event_store` with every application projector
+/// registered and SQLite checkpoints attached, so the server and the worker
+/// maintain the same read models from the same positions. Each projector's
+/// declared table and indexes are created or reconciled before it returns.
+pub async fn projection_engine(
+    database_url: &str,
+    event_store: &AppEventStore,
+    read_model_store: Arc<dyn ReadModelStore>,
+) -> Result<ProjectionEngine, Box<dyn std::error::Error>> {
+    let checkpoints = Arc::new(SqliteCheckpointStore::new(database_url).await?);
+    let mut engine =
+        ProjectionEngine::new(Box::new(event_store.clone())).with_checkpoint_store(checkpoints);
+    engine.register_projector(Box::new(UserProjector::new()), read_model_store, USERS_VIEW);
+    engine.init_all().await?;
+    Ok(engine)
+}
+
 /// Build the production stack against a SQLite database URL. Subscribes the
 /// projector to the in-process bus so writes drive `users_view` synchronously.
 pub async fn build(database_url: &str) -> Result<EsStack, Box<dyn std::error::Error>> {
-    let event_store = SqliteEventStore::new(database_url).await?;
+    let event_store = event_store(database_url).await?;
     let read_model_store: Arc<dyn ReadModelStore> =
         Arc::new(SqliteReadModelStore::new(database_url).await?);
 
-    let mut engine = ProjectionEngine::new(Box::new(event_store.clone()));
-    engine.register_projector(
-        Box::new(UserProjector::new()),
-        read_model_store.clone(),
-        USERS_VIEW,
-    );
-    let engine = Arc::new(engine);
+    let engine =
+        Arc::new(projection_engine(database_url, &event_store, read_model_store.clone()).await?);
 
     let mut bus = InProcessEventBus::new();
     bus.subscribe(Box::new(ProjectionEngineHandler::new(engine.clone())))
         .await?;
 
-    let command_bus = CommandBus::<UserAggregate>::new(Box::new(event_store), Box::new(bus));
+    // No relay here: CLI runs are short-lived, and anything left pending is
+    // redelivered by the server's relay on its next start. CLI commands act
+    // as the system actor, so denials are not expected and go unrecorded.
+    let idempotency = Arc::new(SqliteIdempotencyStore::new(database_url).await?);
+    let command_bus = command_router(
+        &event_store,
+        Box::new(bus),
+        idempotency,
+        read_model_store.clone(),
+        Arc::new(NoOpAccessLogger),
+    );
 
     Ok(EsStack {
         command_bus,
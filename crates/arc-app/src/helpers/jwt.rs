@@ -14,6 +14,12 @@ use uuid::Uuid;
 /// `sub` holds the aggregate UUID; `jti` is the unique token id used by the
 /// server-side session registry (HIPAA-4) for revocation.
 ///
+/// `roles` carries the authorization roles the user held at sign-in; the
+/// JWT middleware puts them on the request's
+/// [`Principal`](arc_core::authorization::Principal). They go stale when a
+/// role is revoked, so authorization re-reads the current ones from
+/// `users_view`. Tokens minted before roles existed decode with none.
+///
 /// `jti` is `Option` for the rollout window — tokens minted before HIPAA-4
 /// landed have no `jti`. Acceptance of those is governed by the
 /// `JWT_GRANDFATHER_LEGACY` env flag, enforced in the JWT middleware.
@@ -26,6 +32,9 @@ pub struct Claims {
     /// JWT id (HIPAA-4 revocation key). `None` only for pre-HIPAA-4 tokens.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub jti: Option<Uuid>,
+    /// Authorization roles (`"admin"`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub roles: Vec<String>,
 }
 
 static JWT_SECRET: Lazy<Vec<u8>> = Lazy::new(|| {
@@ -51,10 +60,13 @@ pub fn get_jwt_expiry() -> u64 {
     *JWT_EXPIRY_HOURS
 }
 
-/// Mint a signed JWT for the given aggregate UUID. Returns the token and the
-/// `jti` so the caller can record the session in the server-side store
-/// before handing the token to the client.
-pub fn create_token(aggregate_id: &str) -> Result<(String, Uuid), jsonwebtoken::errors::Error> {
+/// Mint a signed JWT for the given aggregate UUID and roles. Returns the
+/// token and the `jti` so the caller can record the session in the
+/// server-side store before handing the token to the client.
+pub fn create_token(
+    aggregate_id: &str,
+    roles: &[String],
+) -> Result<(String, Uuid), jsonwebtoken::errors::Error> {
     let now_secs = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
@@ -65,6 +77,7 @@ pub fn create_token(aggregate_id: &str) -> Result<(String, Uuid), jsonwebtoken::
         sub: aggregate_id.to_string(),
         exp,
         jti: Some(jti),
+        roles: roles.to_vec(),
     };
     let token = encode(
         &Header::default(),
@@ -86,6 +99,61 @@ pub fn decode_token(token: &str) -> Result<Claims, Box<dyn Error + Send + Sync>>
     Ok(data.claims)
 }
 
+/// Claims of an email verification token: the user it was issued to and
+/// the address it verifies. Signed with a key derived from `JWT_SECRET`
+/// rather than the secret itself, so a verification token is never
+/// accepted as a session token or the other way round.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailVerificationClaims {
+    /// Subject: aggregate UUID.
+    pub sub: String,
+    /// The address being verified; a token for an old address is refused
+    /// once the user changes it.
+    pub email: String,
+    /// Expiration timestamp (seconds since UNIX epoch).
+    pub exp: usize,
+}
+
+fn email_verification_key() -> Vec<u8> {
+    [get_jwt_secret(), b":email-verification"].concat()
+}
+
+/// Mint the token emailed to a user to prove they own `email`. It expires
+/// after [`config::email_verification_token_ttl`](crate::helpers::config::email_verification_token_ttl).
+pub fn create_email_verification_token(
+    aggregate_id: &str,
+    email: &str,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let now_secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as usize;
+    let ttl = crate::helpers::config::email_verification_token_ttl().as_secs() as usize;
+    let claims = EmailVerificationClaims {
+        sub: aggregate_id.to_string(),
+        email: email.to_string(),
+        exp: now_secs + ttl,
+    };
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(&email_verification_key()),
+    )
+}
+
+/// Decode and signature-verify an email verification token.
+pub fn decode_email_verification_token(
+    token: &str,
+) -> Result<EmailVerificationClaims, jsonwebtoken::errors::Error> {
+    let validation = Validation::new(Algorithm::HS256);
+    let data: TokenData<EmailVerificationClaims> = decode(
+        token,
+        &DecodingKey::from_secret(&email_verification_key()),
+        &validation,
+    )?;
+    Ok(data.claims)
+}
+
 /// Backwards-compat shim used by tests. Returns just the aggregate UUID. New
 /// code should call [`decode_token`] and route through the session-aware
 /// middleware.
@@ -0,0 +1,54 @@
+//! Outgoing email. [`Mailer`] is the seam a real transport (SMTP, an email
+//! API) plugs into; until one is configured, [`LogMailer`] writes each
+//! message to the log so an operator can hand it on.
+
+use async_trait::async_trait;
+use std::error::Error;
+
+/// One outgoing message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: Email) -> Result<(), Box<dyn Error + Send + Sync>>;
+}
+
+/// Logs every message instead of delivering it.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LogMailer;
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, email: Email) -> Result<(), Box<dyn Error + Send + Sync>> {
+        tracing::info!(to = %email.to, subject = %email.subject, body = %email.body, "Email not delivered: no mail transport configured");
+        Ok(())
+    }
+}
+
+/// Keeps sent messages in memory for assertions.
+#[cfg(test)]
+#[derive(Debug, Clone, Default)]
+pub struct RecordingMailer {
+    sent: std::sync::Arc<std::sync::Mutex<Vec<Email>>>,
+}
+
+#[cfg(test)]
+impl RecordingMailer {
+    pub fn sent(&self) -> Vec<Email> {
+        self.sent.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl Mailer for RecordingMailer {
+    async fn send(&self, email: Email) -> Result<(), Box<dyn Error + Send + Sync>> {
+        self.sent.lock().unwrap().push(email);
+        Ok(())
+    }
+}
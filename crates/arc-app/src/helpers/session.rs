@@ -1,11 +1,11 @@
 //! Cookie-session-backed identity for the server-rendered admin UI.
 //!
 //! Sessions hold a [`SessionUser`] — a lightweight projection-backed POD
-//! carrying the `aggregate_id` UUID, name, and email. Reads from
+//! carrying the `aggregate_id` UUID, name, email, and roles. Reads from
 //! `users_view` (Step 2 projection); never touches the retired Diesel
 //! `users` table.
 
-use crate::domain::user::projector::USERS_VIEW;
+use crate::domain::user::projector::{roles_of, USERS_VIEW};
 use actix_session::Session;
 use arc_core::read_model_store::ReadModelStore;
 use serde::{Deserialize, Serialize};
@@ -20,6 +20,11 @@ pub struct SessionUser {
     pub id: String,
     pub name: String,
     pub email: String,
+    /// Authorization roles at sign-in, for display; commands are authorized
+    /// against the current ones in `users_view`. Cookies issued before roles
+    /// existed deserialize with none.
+    #[serde(default)]
+    pub roles: Vec<String>,
 }
 
 impl SessionUser {
@@ -30,6 +35,7 @@ impl SessionUser {
             id: row.get("id")?.as_str()?.to_string(),
             name: row.get("name")?.as_str()?.to_string(),
             email: row.get("email")?.as_str()?.to_string(),
+            roles: roles_of(row),
         })
     }
 
@@ -38,17 +38,18 @@ impl Drop for InMemoryTestGuard {
 
 #[cfg(test)]
 pub mod es {
-    //! Shared event-sourced test scaffolding. Builds a `CommandBus +
+    //! Shared event-sourced test scaffolding. Builds a `CommandRouter +
     //! ReadModelStore` pair backed by SQLite in-memory + the in-process
     //! event bus, with `UserProjector` synchronously subscribed so seeded
     //! commands land in `users_view` before `await` returns.
 
     use crate::database::seeders::create_users::seed_default_user;
-    use crate::domain::user::aggregate::UserAggregate;
     use crate::domain::user::projector::{UserProjector, USERS_VIEW};
     use crate::helpers::database::{get_connection, MIGRATIONS};
+    use crate::helpers::es_stack::{register_aggregates, with_command_middleware};
     use actix_web::web;
-    use arc_core::command_bus::CommandBus;
+    use arc_core::access_log::NoOpAccessLogger;
+    use arc_core::command_bus::CommandRouter;
     use arc_core::event_bus::{EventBus, InProcessEventBus};
     use arc_core::projection::{ProjectionEngine, ProjectionEngineHandler};
     use arc_core::read_model_store::{InMemoryReadModelStore, ReadModelStore};
@@ -58,7 +59,7 @@ pub mod es {
     use std::sync::Arc;
 
     pub struct EsTestStack {
-        pub command_bus: web::Data<CommandBus<UserAggregate>>,
+        pub command_bus: web::Data<CommandRouter>,
         pub read_model_store: web::Data<dyn ReadModelStore>,
         pub seeded_user_id: Option<String>,
     }
@@ -100,6 +101,7 @@ pub mod es {
             read_model_store.clone(),
             USERS_VIEW,
         );
+        engine.init_all().await.expect("init projections");
         let engine = Arc::new(engine);
 
         let mut bus = InProcessEventBus::new();
@@ -107,7 +109,11 @@ pub mod es {
             .await
             .expect("subscribe");
 
-        let command_bus = CommandBus::<UserAggregate>::new(Box::new(event_store), Box::new(bus));
+        let command_bus = register_aggregates(with_command_middleware(
+            CommandRouter::new(Box::new(event_store), Box::new(bus)),
+            read_model_store.clone(),
+            Arc::new(NoOpAccessLogger),
+        ));
 
         EsTestStack {
             command_bus: web::Data::new(command_bus),
@@ -1,4 +1,3 @@
-use crate::domain::user::aggregate::UserAggregate;
 use crate::domain::user::commands::UserCommand;
 use crate::helpers::audit_context;
 use crate::helpers::csrf::{get_csrf_token, validate_and_regenerate_csrf_token};
@@ -12,7 +11,7 @@ use crate::validation::user_validation::UpdateProfileForm;
 use crate::AppState;
 use actix_session::Session;
 use actix_web::{get, post, web, HttpRequest, HttpResponse, Responder};
-use arc_core::command_bus::CommandBus;
+use arc_core::command_bus::{CommandBusError, CommandRouter};
 use arc_core::read_model_store::ReadModelStore;
 use serde::{Deserialize, Serialize};
 use validator::Validate;
@@ -121,7 +120,7 @@ pub struct ProfileResponse {
     data: UserResponseData,
 }
 
-/// Handles profile update form submission. Routes through the `CommandBus`:
+/// Handles profile update form submission. Routes through the `CommandRouter`:
 /// emits `ProfileUpdated` and/or `EmailChanged` events as needed. Refreshes
 /// the cached `SessionUser` from the projection so subsequent requests see
 /// the new values without re-signing-in.
@@ -130,7 +129,7 @@ pub async fn profile_post(
     req: HttpRequest,
     form: web::Form<UserForm>,
     session: Session,
-    command_bus: web::Data<CommandBus<UserAggregate>>,
+    command_bus: web::Data<CommandRouter>,
     read_model_store: web::Data<dyn ReadModelStore>,
 ) -> impl Responder {
     if !validate_and_regenerate_csrf_token(&session, &form.csrf_token) {
@@ -162,10 +161,12 @@ pub async fn profile_post(
             id: user.id.clone(),
             name: new_name.clone(),
         };
-        if let Err(e) = command_bus
-            .dispatch(cmd, audit_context::for_actor(&req, user.id.clone()))
-            .await
-        {
+        // One form post can dispatch both profile commands; scope the
+        // request's idempotency key so each replays only its own result.
+        let ctx = audit_context::for_actor(&req, user.id.clone())
+            .with_roles(user.roles.clone())
+            .with_idempotency_scope("profile");
+        if let Err(e) = command_bus.dispatch(cmd, ctx).await {
             tracing::error!(error = ?e, "UpdateProfile dispatch failed");
             return HttpResponse::InternalServerError()
                 .json(serde_json::json!({"errors": {"server_error": "Failed to update user"}}));
@@ -177,10 +178,14 @@ pub async fn profile_post(
             id: user.id.clone(),
             email: new_email.clone(),
         };
-        if let Err(e) = command_bus
-            .dispatch(cmd, audit_context::for_actor(&req, user.id.clone()))
-            .await
-        {
+        let ctx = audit_context::for_actor(&req, user.id.clone())
+            .with_roles(user.roles.clone())
+            .with_idempotency_scope("email");
+        if let Err(e) = command_bus.dispatch(cmd, ctx).await {
+            if let CommandBusError::Rejected { message, .. } = &e {
+                return HttpResponse::UnprocessableEntity()
+                    .json(serde_json::json!({"errors": {"email": message}}));
+            }
             tracing::error!(error = ?e, "ChangeEmail dispatch failed");
             return HttpResponse::InternalServerError()
                 .json(serde_json::json!({"errors": {"server_error": "Failed to update user"}}));
@@ -207,7 +212,7 @@ pub async fn profile_password_post(
     req: HttpRequest,
     form: web::Form<PasswordForm>,
     session: Session,
-    command_bus: web::Data<CommandBus<UserAggregate>>,
+    command_bus: web::Data<CommandRouter>,
     read_model_store: web::Data<dyn ReadModelStore>,
 ) -> impl Responder {
     if !validate_and_regenerate_csrf_token(&session, &form.csrf_token) {
@@ -242,7 +247,10 @@ pub async fn profile_password_post(
     };
 
     if let Err(e) = command_bus
-        .dispatch(cmd, audit_context::for_actor(&req, user.id.clone()))
+        .dispatch(
+            cmd,
+            audit_context::for_actor(&req, user.id.clone()).with_roles(user.roles.clone()),
+        )
         .await
     {
         tracing::error!(error = ?e, "ChangePassword dispatch failed");
@@ -439,6 +447,50 @@ mod tests {
         assert_eq!(hits[0]["name"], "Hyde");
     }
 
+    #[serial]
+    #[actix_web::test]
+    async fn test_profile_rejects_email_of_another_user() {
+        let _guard = InMemoryTestGuard;
+        let stack = build_stack_with_default_user().await;
+        crate::services::user_service::create_user(
+            &stack.command_bus,
+            arc_core::command_bus::CommandContext::system(),
+            "Other".into(),
+            "other@example.com".into(),
+            "password",
+        )
+        .await
+        .unwrap();
+        let secret_key = Key::from(env::var("SECRET_KEY").unwrap().as_bytes());
+        let app = build_app!(stack.command_bus, stack.read_model_store, secret_key);
+
+        let cookie = login!(app, "jekyll@example.com", "password");
+        let req = test::TestRequest::get()
+            .cookie(cookie.clone())
+            .uri("/admin/profile")
+            .to_request();
+        let body = test::read_body(test::call_service(&app, req).await).await;
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        let csrf_token = extract_csrf_token(&body_str);
+
+        let req = test::TestRequest::post()
+            .cookie(cookie)
+            .uri("/admin/profile")
+            .set_form([
+                ("csrf_token", csrf_token.as_str()),
+                ("name", "Jekyll"),
+                ("email", "other@example.com"),
+            ])
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert!(body["errors"]["email"]
+            .as_str()
+            .unwrap()
+            .contains("already registered"));
+    }
+
     #[serial]
     #[actix_web::test]
     async fn test_profile_password() {
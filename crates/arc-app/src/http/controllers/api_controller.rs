@@ -1,21 +1,26 @@
 use crate::domain::user::aggregate::UserAggregate;
 use crate::domain::user::commands::UserCommand;
-use crate::domain::user::projector::USERS_VIEW;
+use crate::domain::user::projector::{roles_of, USERS_VIEW};
 use crate::helpers::access_log;
 use crate::helpers::audit_context;
+use crate::helpers::config;
 use crate::helpers::jwt::create_token;
 use crate::helpers::rate_limit::LoginRateLimiter;
 use crate::http::errors::AppError;
+use crate::http::extractors::Authz;
 use crate::services::user_service::{
-    create_user, validate_user_credentials_es, UserValidationResult,
+    create_user, user_roles, validate_user_credentials_es, UserValidationResult,
 };
 use actix_web::{
-    delete, get, patch, post, web, web::Json, HttpMessage, HttpRequest, HttpResponse, Responder,
-    ResponseError,
+    delete, get, patch, post, put, web, web::Json, HttpMessage, HttpRequest, HttpResponse,
+    Responder, ResponseError,
 };
 use arc_core::access_log::{AccessLogger, AccessedResource, PurposeOfUse, Sensitivity};
-use arc_core::command_bus::CommandBus;
+use arc_core::aggregate::Aggregate;
+use arc_core::authorization::Resource;
+use arc_core::command_bus::{CommandContext, CommandRouter};
 use arc_core::read_model_store::ReadModelStore;
+use arc_core::scheduler::CommandScheduler;
 use arc_core::session::{SessionRecord, SessionStore};
 use serde::Deserialize;
 use serde_json::json;
@@ -49,17 +54,28 @@ pub struct UpdateProfileRequest {
     name: String,
 }
 
+/// JSON body for `PATCH /users/{id}`. Omitted fields are left unchanged.
+#[derive(Deserialize)]
+pub struct UpdateUserRequest {
+    name: Option<String>,
+    email: Option<String>,
+}
+
+/// JSON body for `POST /users/{id}/verify-email`. Admins may omit it.
+#[derive(Deserialize)]
+pub struct VerifyEmailRequest {
+    token: String,
+}
+
 #[post("/register")]
 pub async fn register(
     http_req: HttpRequest,
     req: Json<RegisterRequest>,
-    command_bus: web::Data<CommandBus<UserAggregate>>,
-    read_model_store: web::Data<dyn ReadModelStore>,
+    command_bus: web::Data<CommandRouter>,
 ) -> impl Responder {
     let ctx = audit_context::anonymous(&http_req);
     match create_user(
         &command_bus,
-        read_model_store.as_ref(),
         ctx,
         req.name.clone(),
         req.email.clone(),
@@ -106,7 +122,8 @@ pub async fn login(
 
     match (result, aggregate_id) {
         (UserValidationResult::Valid, Some(agg_id)) => {
-            let (token, jti) = match create_token(&agg_id) {
+            let roles = user_roles(read_model_store.as_ref(), &agg_id).await;
+            let (token, jti) = match create_token(&agg_id, &roles) {
                 Ok(pair) => pair,
                 Err(_) => {
                     return HttpResponse::InternalServerError()
@@ -226,7 +243,7 @@ pub async fn profile(
 pub async fn update_profile(
     req: HttpRequest,
     body: Json<UpdateProfileRequest>,
-    command_bus: web::Data<CommandBus<UserAggregate>>,
+    command_bus: web::Data<CommandRouter>,
 ) -> impl Responder {
     let agg_id = match req.extensions().get::<String>() {
         Some(id) => id.clone(),
@@ -250,7 +267,7 @@ pub async fn update_profile(
 #[delete("/profile")]
 pub async fn delete_profile(
     req: HttpRequest,
-    command_bus: web::Data<CommandBus<UserAggregate>>,
+    command_bus: web::Data<CommandRouter>,
 ) -> impl Responder {
     let agg_id = match req.extensions().get::<String>() {
         Some(id) => id.clone(),
@@ -268,38 +285,267 @@ pub async fn delete_profile(
     }
 }
 
+/// Key of the pending deletion of `user_id`'s account.
+fn deletion_key(user_id: &str) -> String {
+    format!("delete-user:{user_id}")
+}
+
+/// `POST /api/v1/protected/profile/deletion` — request deletion of the
+/// caller's account. The deletion runs after `DELETION_GRACE_PERIOD_SECS`
+/// unless cancelled first; requesting again restarts the grace period.
+/// The job runs as the system actor, so the caller's right to delete the
+/// account is checked now.
+#[post("/profile/deletion")]
+pub async fn request_deletion(
+    req: HttpRequest,
+    authz: Authz,
+    scheduler: web::Data<CommandScheduler>,
+) -> impl Responder {
+    let Some(ctx) = actor_context(&req) else {
+        return HttpResponse::Unauthorized().json(json!({"error": "No authenticated user"}));
+    };
+    let id = ctx.actor_id.clone();
+    let resource = Resource::new(UserAggregate::aggregate_type(), &id);
+    if let Err(denied) = authz.require("delete", &resource).await {
+        return denied;
+    }
+
+    let grace_us = i64::try_from(config::deletion_grace_period().as_micros()).unwrap_or(i64::MAX);
+    let due_at_us = now_us().saturating_add(grace_us);
+    let cmd = UserCommand::DeleteUser { id: id.clone() };
+    match scheduler
+        .schedule(deletion_key(&id), &cmd, due_at_us, &ctx)
+        .await
+    {
+        Ok(job) => HttpResponse::Accepted().json(json!({ "deletion_due_at_us": job.due_at_us })),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to schedule account deletion");
+            HttpResponse::InternalServerError()
+                .json(json!({"error": "Failed to schedule deletion"}))
+        }
+    }
+}
+
+/// `DELETE /api/v1/protected/profile/deletion` — cancel the caller's
+/// pending account deletion. 404 when none is pending, including when the
+/// deletion is already running.
+#[delete("/profile/deletion")]
+pub async fn cancel_deletion(
+    req: HttpRequest,
+    scheduler: web::Data<CommandScheduler>,
+) -> impl Responder {
+    let Some(id) = req.extensions().get::<String>().cloned() else {
+        return HttpResponse::Unauthorized().json(json!({"error": "No authenticated user"}));
+    };
+    match scheduler.cancel(&deletion_key(&id)).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json(json!({"error": "No account deletion pending"})),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to cancel account deletion");
+            HttpResponse::InternalServerError().json(json!({"error": "Failed to cancel deletion"}))
+        }
+    }
+}
+
+/// `GET /api/v1/protected/users/{id}` — any user's profile, roles and
+/// onboarding status included, for principals the policies allow to `read`
+/// it (the user themselves and admins). Denials are recorded through the
+/// `AccessLogger`.
+#[get("/users/{id}")]
+pub async fn show_user(
+    req: HttpRequest,
+    path: web::Path<String>,
+    authz: Authz,
+    read_model_store: web::Data<dyn ReadModelStore>,
+    access_logger: web::Data<dyn AccessLogger>,
+) -> impl Responder {
+    let id = path.into_inner();
+    let resource = Resource::new(UserAggregate::aggregate_type(), &id);
+    if let Err(denied) = authz.require("read", &resource).await {
+        return denied;
+    }
+
+    let row = match read_model_store.get(USERS_VIEW, &id).await {
+        Ok(Some(r)) => r,
+        Ok(None) => return HttpResponse::NotFound().json(json!({"error": "User not found"})),
+        Err(e) => {
+            tracing::error!(error = ?e, "users_view read failed");
+            return HttpResponse::InternalServerError()
+                .json(json!({"error": "Failed to load user"}));
+        }
+    };
+
+    let accessed = AccessedResource::new("UserProfile", id, Sensitivity::Pii).with_fields([
+        "id",
+        "name",
+        "email",
+        "roles",
+        "email_verified",
+        "workspace_id",
+    ]);
+    let outcome = access_log::record_read(
+        access_logger.as_ref(),
+        &req,
+        authz.principal().actor_id.clone(),
+        accessed,
+        PurposeOfUse::Operations,
+    )
+    .await;
+
+    if outcome == access_log::RecordReadOutcome::FailHard {
+        return HttpResponse::ServiceUnavailable().json(json!({"error": "Audit sink unavailable"}));
+    }
+
+    HttpResponse::Ok().json(json!({
+        "id": row.get("id"),
+        "name": row.get("name"),
+        "email": row.get("email"),
+        "roles": roles_of(&row),
+        // Rows projected before onboarding existed carry neither field.
+        "email_verified": row.get("email_verified").and_then(|v| v.as_bool()).unwrap_or(false),
+        "workspace_id": row.get("workspace_id"),
+    }))
+}
+
+/// `PATCH /api/v1/protected/users/{id}` — edit another user's name and/or
+/// email. Authorization is left to the command bus, whose policies allow
+/// admins to update any user.
+#[patch("/users/{id}")]
+pub async fn update_user(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: Json<UpdateUserRequest>,
+    command_bus: web::Data<CommandRouter>,
+) -> impl Responder {
+    let Some(ctx) = actor_context(&req) else {
+        return HttpResponse::Unauthorized().json(json!({"error": "No authenticated user"}));
+    };
+    let id = path.into_inner();
+    let UpdateUserRequest { name, email } = body.into_inner();
+    if name.is_none() && email.is_none() {
+        return HttpResponse::BadRequest().json(json!({"error": "Nothing to update"}));
+    }
+
+    if let Some(name) = name {
+        let cmd = UserCommand::UpdateProfile {
+            id: id.clone(),
+            name,
+        };
+        if let Err(e) = command_bus
+            .dispatch(cmd, ctx.clone().with_idempotency_scope("profile"))
+            .await
+        {
+            return AppError::from(e).error_response();
+        }
+    }
+    if let Some(email) = email {
+        let cmd = UserCommand::ChangeEmail { id, email };
+        if let Err(e) = command_bus
+            .dispatch(cmd, ctx.with_idempotency_scope("email"))
+            .await
+        {
+            return AppError::from(e).error_response();
+        }
+    }
+
+    HttpResponse::NoContent().finish()
+}
+
+/// `PUT /api/v1/protected/users/{id}/roles/{role}` — grant a role. Granting
+/// a role already held succeeds without a new event.
+#[put("/users/{id}/roles/{role}")]
+pub async fn grant_role(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    command_bus: web::Data<CommandRouter>,
+) -> impl Responder {
+    let (id, role) = path.into_inner();
+    dispatch_user_command(&req, &command_bus, UserCommand::GrantRole { id, role }).await
+}
+
+/// `DELETE /api/v1/protected/users/{id}/roles/{role}` — revoke a role.
+#[delete("/users/{id}/roles/{role}")]
+pub async fn revoke_role(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    command_bus: web::Data<CommandRouter>,
+) -> impl Responder {
+    let (id, role) = path.into_inner();
+    dispatch_user_command(&req, &command_bus, UserCommand::RevokeRole { id, role }).await
+}
+
+/// `POST /api/v1/protected/users/{id}/verify-email` — mark the user's email
+/// verified, which lets the onboarding process provision their workspace.
+/// Users send the token emailed to them as `{"token": "..."}`; admins may
+/// verify without one.
+#[post("/users/{id}/verify-email")]
+pub async fn verify_email(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: Option<Json<VerifyEmailRequest>>,
+    command_bus: web::Data<CommandRouter>,
+) -> impl Responder {
+    let cmd = UserCommand::VerifyEmail {
+        id: path.into_inner(),
+        token: body.map(|b| b.into_inner().token),
+    };
+    dispatch_user_command(&req, &command_bus, cmd).await
+}
+
+async fn dispatch_user_command(
+    req: &HttpRequest,
+    command_bus: &CommandRouter,
+    cmd: UserCommand,
+) -> HttpResponse {
+    let Some(ctx) = actor_context(req) else {
+        return HttpResponse::Unauthorized().json(json!({"error": "No authenticated user"}));
+    };
+    match command_bus.dispatch(cmd, ctx).await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(e) => AppError::from(e).error_response(),
+    }
+}
+
+/// Command context for the JWT-authenticated actor, roles included.
+fn actor_context(req: &HttpRequest) -> Option<CommandContext> {
+    let actor_id = req.extensions().get::<String>().cloned()?;
+    Some(audit_context::for_actor(req, actor_id))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::domain::user::projector::UserProjector;
+    use crate::domain::user::verification::VerificationMailer;
     use crate::helpers::database::get_connection;
     use crate::helpers::database::MIGRATIONS;
+    use crate::helpers::es_stack;
     use crate::helpers::jwt::create_token;
+    use crate::helpers::mailer::RecordingMailer;
     use crate::helpers::rate_limit::{LoginRateLimiter, RateLimiter};
     use crate::helpers::test::InMemoryTestGuard;
     use crate::http::middlewares::jwt_middleware::JwtMiddleware;
     use actix_web::{http, test, App};
     use arc_core::access_log::{AccessLogger, NoOpAccessLogger};
-    use arc_core::event_bus::{EventBus, InProcessEventBus};
+    use arc_core::event_bus::{EventBus, EventHandler, InProcessEventBus};
     use arc_core::event_store::EventStore;
+    use arc_core::idempotency::{InMemoryIdempotencyStore, DEFAULT_IDEMPOTENCY_TTL};
     use arc_core::projection::{ProjectionEngine, ProjectionEngineHandler};
     use arc_core::read_model_store::{InMemoryReadModelStore, ReadModelStore};
+    use arc_core::scheduler::InMemoryScheduleStore;
     use arc_es_sqlite::SqliteEventStore;
     use diesel_migrations::MigrationHarness;
     use serial_test::serial;
     use std::env;
     use std::sync::Arc;
 
-    /// Build the (`CommandBus`, `ReadModelStore` `web::Data`) pair every API
+    /// Build the (`CommandRouter`, `ReadModelStore` `web::Data`) pair every API
     /// test needs. Wires an in-memory read model and subscribes
     /// `UserProjector` to the in-process bus so writes flow into `users_view`
     /// the same way they do at runtime.
     async fn build_setup(
         event_store: Box<dyn EventStore>,
-    ) -> (
-        web::Data<CommandBus<UserAggregate>>,
-        web::Data<dyn ReadModelStore>,
-    ) {
+    ) -> (web::Data<CommandRouter>, web::Data<dyn ReadModelStore>) {
         let read_model_store: Arc<dyn ReadModelStore> = Arc::new(InMemoryReadModelStore::new());
 
         let mut engine = ProjectionEngine::new(Box::new(InMemoryEventStoreShim));
@@ -315,7 +561,15 @@ mod tests {
             .await
             .expect("subscribe projection handler");
 
-        let command_bus = CommandBus::<UserAggregate>::new(event_store, Box::new(bus));
+        let router = CommandRouter::new(event_store, Box::new(bus)).with_idempotency(
+            Arc::new(InMemoryIdempotencyStore::new()),
+            DEFAULT_IDEMPOTENCY_TTL,
+        );
+        let command_bus = es_stack::register_aggregates(es_stack::with_command_middleware(
+            router,
+            read_model_store.clone(),
+            Arc::new(NoOpAccessLogger),
+        ));
         (
             web::Data::new(command_bus),
             web::Data::from(read_model_store),
@@ -550,7 +804,7 @@ mod tests {
         assert_eq!(resp.status(), http::StatusCode::CREATED);
         let body: serde_json::Value = test::read_body_json(resp).await;
         let agg_id = body["id"].as_str().unwrap().to_string();
-        let (token, _jti) = create_token(&agg_id).unwrap();
+        let (token, _jti) = create_token(&agg_id, &[]).unwrap();
 
         // Update profile
         let req = test::TestRequest::patch()
@@ -573,6 +827,248 @@ mod tests {
         assert_eq!(body["email"], "carol@example.com");
     }
 
+    #[serial]
+    #[actix_web::test]
+    async fn test_admin_edits_other_user_and_others_are_forbidden() {
+        let _guard = InMemoryTestGuard;
+        setup_test_env();
+        let _store = prepare_store_and_db().await;
+
+        let (command_bus_data, rm_data) = build_setup(Box::new(
+            SqliteEventStore::new("file::memory:?cache=shared")
+                .await
+                .unwrap(),
+        ))
+        .await;
+        let (recorder, logger) = recording_logger();
+
+        let app = test::init_service(
+            App::new()
+                .app_data(command_bus_data.clone())
+                .app_data(rm_data.clone())
+                .app_data(logger)
+                .app_data(web::Data::new(es_stack::policies()))
+                .service(
+                    web::scope("/api/v1").service(register).service(
+                        web::scope("/protected")
+                            .wrap(JwtMiddleware)
+                            .service(show_user)
+                            .service(update_user)
+                            .service(grant_role)
+                            .service(revoke_role)
+                            .service(verify_email),
+                    ),
+                ),
+        )
+        .await;
+
+        let mut ids = Vec::new();
+        for (name, email) in [
+            ("Erin", "erin@example.com"),
+            ("Finn", "finn@example.com"),
+            ("Gail", "gail@example.com"),
+        ] {
+            let req = test::TestRequest::post()
+                .uri("/api/v1/register")
+                .set_json(json!({ "name": name, "email": email, "password": "pw12345678" }))
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::CREATED);
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            ids.push(body["id"].as_str().unwrap().to_string());
+        }
+        let (erin, finn, gail) = (&ids[0], &ids[1], &ids[2]);
+        let users_uri = format!("/api/v1/protected/users/{finn}");
+
+        // A plain user can neither edit nor read another user.
+        let (token, _jti) = create_token(erin, &[]).unwrap();
+        let req = test::TestRequest::patch()
+            .uri(&users_uri)
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .set_json(json!({ "name": "Hijacked" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+        let req = test::TestRequest::get()
+            .uri(&users_uri)
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        let entries = recorder.entries().await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].actor.actor_id, *erin);
+        assert_eq!(entries[0].resource.identifier, *finn);
+        assert!(entries[0].denial.is_some());
+
+        // A forged `roles` claim is not enough: roles come from users_view.
+        let (admin, _jti) = create_token(erin, &["admin".to_string()]).unwrap();
+        let req = test::TestRequest::get()
+            .uri(&users_uri)
+            .insert_header(("Authorization", format!("Bearer {}", admin)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+
+        // An admin can edit, grant roles and read the result.
+        command_bus_data
+            .dispatch(
+                UserCommand::GrantRole {
+                    id: erin.clone(),
+                    role: "admin".into(),
+                },
+                CommandContext::system(),
+            )
+            .await
+            .unwrap();
+        let req = test::TestRequest::patch()
+            .uri(&users_uri)
+            .insert_header(("Authorization", format!("Bearer {}", admin)))
+            .set_json(json!({ "name": "Finn Edited", "email": "finn2@example.com" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NO_CONTENT);
+        let req = test::TestRequest::put()
+            .uri(&format!("{users_uri}/roles/auditor"))
+            .insert_header(("Authorization", format!("Bearer {}", admin)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NO_CONTENT);
+
+        let req = test::TestRequest::get()
+            .uri(&users_uri)
+            .insert_header(("Authorization", format!("Bearer {}", admin)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::OK);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["name"], "Finn Edited");
+        assert_eq!(body["email"], "finn2@example.com");
+        assert_eq!(body["roles"], json!(["auditor"]));
+        assert_eq!(body["email_verified"], false);
+
+        // Only an admin verifies an email on someone's behalf.
+        let (plain, _jti) = create_token(gail, &[]).unwrap();
+        let req = test::TestRequest::post()
+            .uri(&format!("{users_uri}/verify-email"))
+            .insert_header(("Authorization", format!("Bearer {}", plain)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+        let req = test::TestRequest::post()
+            .uri(&format!("{users_uri}/verify-email"))
+            .insert_header(("Authorization", format!("Bearer {}", admin)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NO_CONTENT);
+        let row = rm_data.get(USERS_VIEW, finn).await.unwrap().unwrap();
+        assert_eq!(row["email_verified"], true);
+
+        // Revoking the role takes effect before the token expires, for
+        // reads and commands alike.
+        let req = test::TestRequest::delete()
+            .uri(&format!("/api/v1/protected/users/{erin}/roles/admin"))
+            .insert_header(("Authorization", format!("Bearer {}", admin)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::NO_CONTENT);
+        let req = test::TestRequest::get()
+            .uri(&users_uri)
+            .insert_header(("Authorization", format!("Bearer {}", admin)))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+        let req = test::TestRequest::patch()
+            .uri(&users_uri)
+            .insert_header(("Authorization", format!("Bearer {}", admin)))
+            .set_json(json!({ "name": "Too Late" }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::FORBIDDEN);
+    }
+
+    #[serial]
+    #[actix_web::test]
+    async fn test_self_registered_user_verifies_with_emailed_token() {
+        let _guard = InMemoryTestGuard;
+        setup_test_env();
+        let store = prepare_store_and_db().await;
+
+        let (command_bus_data, rm_data) = build_setup(Box::new(
+            SqliteEventStore::new("file::memory:?cache=shared")
+                .await
+                .unwrap(),
+        ))
+        .await;
+
+        let app = test::init_service(
+            App::new()
+                .app_data(command_bus_data.clone())
+                .app_data(rm_data.clone())
+                .app_data(logger_data())
+                .app_data(web::Data::new(es_stack::policies()))
+                .service(
+                    web::scope("/api/v1").service(register).service(
+                        web::scope("/protected")
+                            .wrap(JwtMiddleware)
+                            .service(verify_email),
+                    ),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/v1/register")
+            .set_json(
+                json!({ "name": "Gina", "email": "gina@example.com", "password": "pw12345678" }),
+            )
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let gina = body["id"].as_str().unwrap().to_string();
+
+        // Deliver the registration to the verification mailer as the
+        // background bus would, and take the token from the email.
+        let mailer = RecordingMailer::default();
+        let registered = store.load(&gina).await.unwrap().remove(0);
+        VerificationMailer::new(Arc::new(mailer.clone()))
+            .handle(&registered)
+            .await
+            .unwrap();
+        let sent = mailer.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, "gina@example.com");
+        let emailed = sent[0].body.lines().last().unwrap().to_string();
+
+        let (session, _jti) = create_token(&gina, &[]).unwrap();
+        let verify = |token: Option<&str>| {
+            let req = test::TestRequest::post()
+                .uri(&format!("/api/v1/protected/users/{gina}/verify-email"))
+                .insert_header(("Authorization", format!("Bearer {}", session)));
+            match token {
+                Some(token) => req.set_json(json!({ "token": token })),
+                None => req,
+            }
+            .to_request()
+        };
+
+        // Without the emailed token, or with a session token in its place,
+        // the user cannot verify themselves.
+        for token in [None, Some(session.as_str())] {
+            let resp = test::call_service(&app, verify(token)).await;
+            assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        let row = rm_data.get(USERS_VIEW, &gina).await.unwrap().unwrap();
+        assert_eq!(row["email_verified"], false);
+
+        let resp = test::call_service(&app, verify(Some(&emailed))).await;
+        assert_eq!(resp.status(), http::StatusCode::NO_CONTENT);
+        let row = rm_data.get(USERS_VIEW, &gina).await.unwrap().unwrap();
+        assert_eq!(row["email_verified"], true);
+    }
+
     #[serial]
     #[actix_web::test]
     async fn test_delete_user_emits_deleted_and_returns_404() {
@@ -616,7 +1112,7 @@ mod tests {
         assert_eq!(resp.status(), http::StatusCode::CREATED);
         let body: serde_json::Value = test::read_body_json(resp).await;
         let agg_id = body["id"].as_str().unwrap().to_string();
-        let (token, _jti) = create_token(&agg_id).unwrap();
+        let (token, _jti) = create_token(&agg_id, &[]).unwrap();
 
         // DELETE profile
         let req = test::TestRequest::delete()
@@ -641,6 +1137,89 @@ mod tests {
         assert_eq!(events[1].event_type, "UserDeleted");
     }
 
+    #[serial]
+    #[actix_web::test]
+    async fn test_requested_deletion_runs_after_grace_period_unless_cancelled() {
+        let _guard = InMemoryTestGuard;
+        setup_test_env();
+        let store = prepare_store_and_db().await;
+
+        let (command_bus_data, rm_data) = build_setup(Box::new(
+            SqliteEventStore::new("file::memory:?cache=shared")
+                .await
+                .unwrap(),
+        ))
+        .await;
+        let scheduler = es_stack::command_scheduler(
+            command_bus_data.clone().into_inner(),
+            Arc::new(InMemoryScheduleStore::new()),
+        );
+        let scheduler_data = web::Data::new(scheduler);
+
+        let app = test::init_service(
+            App::new()
+                .app_data(command_bus_data.clone())
+                .app_data(scheduler_data.clone())
+                .app_data(rm_data.clone())
+                .app_data(logger_data())
+                .app_data(web::Data::new(es_stack::policies()))
+                .service(
+                    web::scope("/api/v1").service(register).service(
+                        web::scope("/protected")
+                            .wrap(JwtMiddleware)
+                            .service(request_deletion)
+                            .service(cancel_deletion),
+                    ),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/v1/register")
+            .set_json(json!({
+                "name": "Gus",
+                "email": "gus@example.com",
+                "password": "pw12345678"
+            }))
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_eq!(resp.status(), http::StatusCode::CREATED);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let agg_id = body["id"].as_str().unwrap().to_string();
+        let (token, _jti) = create_token(&agg_id, &[]).unwrap();
+        let deletion = |method: test::TestRequest| {
+            method
+                .uri("/api/v1/protected/profile/deletion")
+                .insert_header(("Authorization", format!("Bearer {}", token)))
+                .to_request()
+        };
+
+        // Requested, then cancelled within the grace period.
+        let resp = test::call_service(&app, deletion(test::TestRequest::post())).await;
+        assert_eq!(resp.status(), http::StatusCode::ACCEPTED);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        let due_at_us = body["deletion_due_at_us"].as_i64().unwrap();
+        assert!(due_at_us > now_us());
+        scheduler_data.run_due(due_at_us - 1).await.unwrap();
+        assert_eq!(store.load(&agg_id).await.unwrap().len(), 1);
+
+        let resp = test::call_service(&app, deletion(test::TestRequest::delete())).await;
+        assert_eq!(resp.status(), http::StatusCode::NO_CONTENT);
+        let resp = test::call_service(&app, deletion(test::TestRequest::delete())).await;
+        assert_eq!(resp.status(), http::StatusCode::NOT_FOUND);
+        scheduler_data.run_due(i64::MAX).await.unwrap();
+        assert_eq!(store.load(&agg_id).await.unwrap().len(), 1);
+
+        // Requested again and left to run.
+        let resp = test::call_service(&app, deletion(test::TestRequest::post())).await;
+        assert_eq!(resp.status(), http::StatusCode::ACCEPTED);
+        assert_eq!(scheduler_data.run_due(i64::MAX).await.unwrap(), 1);
+        let events = store.load(&agg_id).await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1].event_type, "UserDeleted");
+        assert_eq!(events[1].audit.actor_id, "system");
+    }
+
     #[serial]
     #[actix_web::test]
     async fn test_command_bus_failure_returns_422_no_event_emitted() {
@@ -767,7 +1346,7 @@ mod tests {
         let resp = test::call_service(&app, req).await;
         let body: serde_json::Value = test::read_body_json(resp).await;
         let agg_id = body["id"].as_str().unwrap().to_string();
-        let (token, _jti) = create_token(&agg_id).unwrap();
+        let (token, _jti) = create_token(&agg_id, &[]).unwrap();
 
         // Update profile while authenticated
         let req = test::TestRequest::patch()
@@ -825,7 +1404,7 @@ mod tests {
         let resp = test::call_service(&app, req).await;
         let body: serde_json::Value = test::read_body_json(resp).await;
         let agg_id = body["id"].as_str().unwrap().to_string();
-        let (token, _jti) = create_token(&agg_id).unwrap();
+        let (token, _jti) = create_token(&agg_id, &[]).unwrap();
 
         // GET profile
         let req = test::TestRequest::get()
@@ -979,7 +1558,7 @@ mod tests {
         .await;
 
         // JWT for an aggregate that doesn't exist.
-        let (token, _jti) = create_token("does-not-exist-uuid").unwrap();
+        let (token, _jti) = create_token("does-not-exist-uuid", &[]).unwrap();
         let req = test::TestRequest::get()
             .uri("/api/v1/protected/profile")
             .insert_header(("Authorization", format!("Bearer {}", token)))
@@ -1032,4 +1611,115 @@ mod tests {
         let events = store.load(&agg_id).await.unwrap();
         assert_eq!(events[0].audit.correlation_id, supplied_corr);
     }
+
+    #[serial]
+    #[actix_web::test]
+    async fn test_register_with_repeated_idempotency_key_replays_original_user() {
+        let _guard = InMemoryTestGuard;
+        setup_test_env();
+        let store = prepare_store_and_db().await;
+
+        let (command_bus_data, rm_data) = build_setup(Box::new(
+            SqliteEventStore::new("file::memory:?cache=shared")
+                .await
+                .unwrap(),
+        ))
+        .await;
+        let app = test::init_service(
+            App::new()
+                .app_data(command_bus_data.clone())
+                .app_data(rm_data.clone())
+                .app_data(logger_data())
+                .service(web::scope("/api/v1").service(register)),
+        )
+        .await;
+
+        let body = json!({"name": "Ivy", "email": "ivy@example.com", "password": "pw12345678"});
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let req = test::TestRequest::post()
+                .uri("/api/v1/register")
+                .peer_addr("10.0.0.1:40000".parse().unwrap())
+                .insert_header(("Idempotency-Key", "register-ivy-1"))
+                .set_json(&body)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(resp.status(), http::StatusCode::CREATED);
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            ids.push(body["id"].as_str().unwrap().to_string());
+        }
+        assert_eq!(ids[0], ids[1], "replay must return the original user id");
+
+        let registered = store
+            .stream_all(0)
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.payload["email"].as_str() == Some("ivy@example.com"))
+            .count();
+        assert_eq!(registered, 1, "replay must not emit a second event");
+
+        // Without the key the duplicate email is still rejected.
+        let req = test::TestRequest::post()
+            .uri("/api/v1/register")
+            .set_json(&body)
+            .to_request();
+        let resp = test::call_service(&app, req).await;
+        assert_ne!(resp.status(), http::StatusCode::CREATED);
+    }
+
+    #[serial]
+    #[actix_web::test]
+    async fn test_anonymous_idempotency_keys_do_not_cross_clients_or_requests() {
+        let _guard = InMemoryTestGuard;
+        setup_test_env();
+        let _store = prepare_store_and_db().await;
+
+        let (command_bus_data, rm_data) = build_setup(Box::new(
+            SqliteEventStore::new("file::memory:?cache=shared")
+                .await
+                .unwrap(),
+        ))
+        .await;
+        let app = test::init_service(
+            App::new()
+                .app_data(command_bus_data.clone())
+                .app_data(rm_data.clone())
+                .app_data(logger_data())
+                .service(web::scope("/api/v1").service(register)),
+        )
+        .await;
+
+        let register_as = |addr: &str, name: &str, email: &str| {
+            test::TestRequest::post()
+                .uri("/api/v1/register")
+                .peer_addr(addr.parse().unwrap())
+                .insert_header(("Idempotency-Key", "register-1"))
+                .set_json(json!({ "name": name, "email": email, "password": "pw12345678" }))
+                .to_request()
+        };
+
+        // Two clients picking the same key each register their own user.
+        let mut ids = Vec::new();
+        for (addr, name, email) in [
+            ("10.0.0.1:40000", "Jo", "jo@example.com"),
+            ("10.0.0.2:40000", "Kim", "kim@example.com"),
+        ] {
+            let resp = test::call_service(&app, register_as(addr, name, email)).await;
+            assert_eq!(resp.status(), http::StatusCode::CREATED);
+            let body: serde_json::Value = test::read_body_json(resp).await;
+            ids.push(body["id"].as_str().unwrap().to_string());
+        }
+        assert_ne!(ids[0], ids[1]);
+
+        // One client reusing its key for a different registration is refused.
+        let resp = test::call_service(
+            &app,
+            register_as("10.0.0.1:40000", "Lee", "lee@example.com"),
+        )
+        .await;
+        assert_eq!(resp.status(), http::StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["error"], "IdempotencyKeyReused");
+    }
 }
@@ -8,9 +8,8 @@
 //! Mounted from `routes::config` only when `APP_ENV=e2e`. Production builds
 //! never expose these endpoints.
 
-use crate::domain::user::aggregate::UserAggregate;
 use actix_web::{get, web, HttpResponse, Responder};
-use arc_core::command_bus::CommandBus;
+use arc_core::command_bus::CommandRouter;
 use serde_json::json;
 
 /// `GET /__diag__/events/{aggregate_id}`
@@ -21,7 +20,7 @@ use serde_json::json;
 #[get("/events/{aggregate_id}")]
 pub async fn list_events(
     path: web::Path<String>,
-    command_bus: web::Data<CommandBus<UserAggregate>>,
+    command_bus: web::Data<CommandRouter>,
 ) -> impl Responder {
     let aggregate_id = path.into_inner();
     match command_bus.event_store().load(&aggregate_id).await {
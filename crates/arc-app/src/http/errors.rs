@@ -19,6 +19,17 @@ impl ResponseError for AppError {
                         "message": message
                     }))
                 }
+                CommandBusError::Forbidden { reason, .. } => {
+                    HttpResponse::Forbidden().json(serde_json::json!({
+                        "error": "Forbidden",
+                        "message": reason
+                    }))
+                }
+                CommandBusError::Rejected { message, .. } => HttpResponse::UnprocessableEntity()
+                    .json(serde_json::json!({
+                        "error": "CommandRejected",
+                        "message": message
+                    })),
                 CommandBusError::AppendFailed { source, .. } => match source {
                     EventStoreError::ConcurrencyConflict { .. } => {
                         HttpResponse::Conflict().json(serde_json::json!({
@@ -43,6 +54,17 @@ impl ResponseError for AppError {
                         "message": "Audit metadata for this request is missing or malformed."
                     }))
                 }
+                CommandBusError::IdempotencyInFlight { .. } => {
+                    HttpResponse::Conflict().json(serde_json::json!({
+                        "error": "IdempotencyInFlight",
+                        "message": "A request with this Idempotency-Key is still being processed."
+                    }))
+                }
+                CommandBusError::IdempotencyKeyReused { .. } => HttpResponse::UnprocessableEntity()
+                    .json(serde_json::json!({
+                        "error": "IdempotencyKeyReused",
+                        "message": "This Idempotency-Key was already used for a different request."
+                    })),
                 _ => HttpResponse::InternalServerError().json(serde_json::json!({
                     "error": "InternalServerError",
                     "message": "An unexpected error occurred."
@@ -77,6 +99,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rejected_maps_to_unprocessable_entity() {
+        let err = AppError::CommandFailed(CommandBusError::rejected("UserCommand", "email taken"));
+        assert_eq!(
+            err.error_response().status(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
+
+    #[test]
+    fn test_forbidden_maps_to_forbidden() {
+        let err = AppError::CommandFailed(CommandBusError::forbidden(
+            "u-2",
+            "update_profile",
+            "User 'u-1'",
+            "no policy allows 'update_profile' on User",
+        ));
+        assert_eq!(err.error_response().status(), StatusCode::FORBIDDEN);
+    }
+
     #[test]
     fn test_concurrency_conflict_maps_to_conflict() {
         let err = AppError::CommandFailed(CommandBusError::AppendFailed {
@@ -89,4 +131,21 @@ mod tests {
         });
         assert_eq!(err.error_response().status(), StatusCode::CONFLICT);
     }
+
+    #[test]
+    fn test_idempotency_errors_map_to_conflict_and_unprocessable_entity() {
+        let err = AppError::CommandFailed(CommandBusError::IdempotencyInFlight {
+            key: "k1".to_string(),
+        });
+        assert_eq!(err.error_response().status(), StatusCode::CONFLICT);
+
+        let err = AppError::CommandFailed(CommandBusError::IdempotencyKeyReused {
+            key: "k1".to_string(),
+            aggregate_type: "User".to_string(),
+        });
+        assert_eq!(
+            err.error_response().status(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+    }
 }
@@ -0,0 +1,105 @@
+//! Request extractors shared by controllers.
+//!
+//! [`Authz`] evaluates the application's [`PolicySet`] for the request's
+//! [`Principal`] before a handler reads data the command bus never sees.
+//! Commands need no extractor: the `AuthorizationMiddleware` on the command
+//! bus applies the same policies to every dispatch.
+
+use crate::helpers::access_log;
+use crate::services::user_service::user_roles;
+use actix_web::dev::Payload;
+use actix_web::error::InternalError;
+use actix_web::{web, Error, FromRequest, HttpMessage, HttpRequest, HttpResponse};
+use arc_core::access_log::{AccessLogger, AccessedResource, PurposeOfUse, Sensitivity};
+use arc_core::authorization::{Decision, PolicySet, Principal, Resource};
+use arc_core::read_model_store::ReadModelStore;
+use futures_util::future::LocalBoxFuture;
+use serde_json::json;
+use std::future::ready;
+
+/// The authenticated [`Principal`] (inserted by the JWT middleware) plus the
+/// policies to check it against. When a `ReadModelStore` is registered as
+/// app data the principal's roles are re-read from `users_view`, so the
+/// token's sign-in copy is not trusted; an unreadable row leaves none. Rejects with 401 when no principal is
+/// present and 500 when no `PolicySet` is registered as app data.
+pub struct Authz {
+    req: HttpRequest,
+    principal: Principal,
+    policies: web::Data<PolicySet>,
+}
+
+impl Authz {
+    pub fn principal(&self) -> &Principal {
+        &self.principal
+    }
+
+    /// `Ok` when the policies allow `action` on `resource`. Otherwise the
+    /// denial is recorded through the `AccessLogger` (when one is registered)
+    /// and the 403 response to return is handed back.
+    pub async fn require(&self, action: &str, resource: &Resource) -> Result<(), HttpResponse> {
+        let reason = match self.policies.evaluate(&self.principal, action, resource) {
+            Decision::Allow => return Ok(()),
+            Decision::Deny(reason) => reason,
+        };
+
+        if let Some(logger) = self.req.app_data::<web::Data<dyn AccessLogger>>() {
+            let accessed =
+                AccessedResource::new(&resource.kind, &resource.id, Sensitivity::Internal)
+                    .with_fields([action]);
+            access_log::record_denial(
+                logger.as_ref(),
+                &self.req,
+                self.principal.actor_id.clone(),
+                accessed,
+                PurposeOfUse::UserInitiated,
+                reason.clone(),
+            )
+            .await;
+        }
+
+        Err(HttpResponse::Forbidden().json(json!({
+            "error": "Forbidden",
+            "message": reason
+        })))
+    }
+}
+
+impl FromRequest for Authz {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let Some(mut principal) = req.extensions().get::<Principal>().cloned() else {
+            let resp = HttpResponse::Unauthorized().json(json!({"error": "No authenticated user"}));
+            return Box::pin(ready(Err(InternalError::from_response(
+                "no principal",
+                resp,
+            )
+            .into())));
+        };
+        let Some(policies) = req.app_data::<web::Data<PolicySet>>().cloned() else {
+            tracing::error!("Authz extractor used without a PolicySet in app data");
+            let resp = HttpResponse::InternalServerError()
+                .json(json!({"error": "Authorization is not configured"}));
+            return Box::pin(ready(Err(InternalError::from_response(
+                "no policies",
+                resp,
+            )
+            .into())));
+        };
+        let read_model_store = req.app_data::<web::Data<dyn ReadModelStore>>().cloned();
+        let req = req.clone();
+        Box::pin(async move {
+            // The token's `roles` claim is a copy from sign-in; a revoked
+            // role must stop authorizing now, not when the token expires.
+            if let Some(store) = read_model_store {
+                principal.roles = user_roles(store.as_ref(), &principal.actor_id).await;
+            }
+            Ok(Authz {
+                req,
+                principal,
+                policies,
+            })
+        })
+    }
+}
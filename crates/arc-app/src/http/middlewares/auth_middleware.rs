@@ -109,6 +109,7 @@ mod tests {
                                         id: agg_id,
                                         name: "Jekyll".into(),
                                         email: "jekyll@example.com".into(),
+                                        roles: vec![],
                                     },
                                 );
                                 HttpResponse::Ok().finish()
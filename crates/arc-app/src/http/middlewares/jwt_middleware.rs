@@ -4,7 +4,8 @@
 //! 1. Decode and signature-verify the bearer token.
 //! 2. Check `jti` against the server-side [`SessionStore`]. Revoked / unknown
 //!    → 401. Store unavailable → **fail closed** with 503.
-//! 3. Insert `(actor_id, jti)` into request extensions for handlers.
+//! 3. Insert `(actor_id, jti)` and the token's [`Principal`] (actor id plus
+//!    `roles` claim) into request extensions for handlers.
 //!
 //! Tokens minted before HIPAA-4 landed have no `jti`. Set
 //! `JWT_GRANDFATHER_LEGACY=true` to accept them during rollout; defaults to
@@ -14,6 +15,7 @@ use crate::helpers::jwt::decode_token;
 use actix_web::body::EitherBody;
 use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
 use actix_web::{Error, HttpMessage, HttpResponse};
+use arc_core::authorization::Principal;
 use arc_core::session::{SessionStore, SessionStoreError};
 use futures_util::future::LocalBoxFuture;
 use std::future::{ready, Ready};
@@ -129,6 +131,7 @@ where
 
         let actor_id = claims.sub.clone();
         let jti_opt = claims.jti;
+        let principal = Principal::new(claims.sub).with_roles(claims.roles);
 
         // Pull the session store out of app data; if absent, the deployment
         // hasn't wired HIPAA-4 yet — fall back to legacy behavior (skip
@@ -139,9 +142,12 @@ where
             .app_data::<actix_web::web::Data<dyn SessionStore>>()
             .cloned();
 
-        let fut = self
-            .service
-            .call(req_with_extensions(req, actor_id.clone(), jti_opt));
+        let fut = self.service.call(req_with_extensions(
+            req,
+            actor_id.clone(),
+            jti_opt,
+            principal,
+        ));
 
         if let Some(store) = store_opt {
             let jti = match jti_opt {
@@ -196,11 +202,17 @@ where
     }
 }
 
-/// Insert actor_id and jti into request extensions before forwarding.
-fn req_with_extensions(req: ServiceRequest, actor_id: String, jti: Option<Uuid>) -> ServiceRequest {
+/// Insert actor_id, jti and principal into request extensions before forwarding.
+fn req_with_extensions(
+    req: ServiceRequest,
+    actor_id: String,
+    jti: Option<Uuid>,
+    principal: Principal,
+) -> ServiceRequest {
     {
         let mut ext = req.extensions_mut();
         ext.insert(actor_id);
+        ext.insert(principal);
         if let Some(j) = jti {
             ext.insert(j);
         }
@@ -4,6 +4,7 @@ use std::process::exit;
 use std::sync::Mutex;
 use std::{env, fs};
 use tracing::{debug, error, info};
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 mod routes;
@@ -24,6 +25,7 @@ mod http {
     }
 
     pub mod errors;
+    pub mod extractors;
 }
 
 mod database {
@@ -43,6 +45,7 @@ mod helpers {
     pub mod es_stack;
     pub mod general;
     pub mod jwt;
+    pub mod mailer;
     pub mod rate_limit;
     pub mod session;
     pub mod template;
@@ -111,13 +114,20 @@ pub fn check_database_health() {
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    // `verify-chain` prints a JSON report on stdout; keep its logs on stderr.
+    let log_writer = if env::args().nth(1).as_deref() == Some("verify-chain") {
+        BoxMakeWriter::new(std::io::stderr)
+    } else {
+        BoxMakeWriter::new(std::io::stdout)
+    };
+
     // Initialize tracing subscriber with environment-based filtering
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "arc=info,actix_web=info".into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(tracing_subscriber::fmt::layer().with_writer(log_writer))
         .init();
 
     check_app_health();
@@ -147,7 +157,10 @@ async fn main() -> std::io::Result<()> {
             commands::develop::run_development().await
         }
         "migrate" => commands::migrate::run(&args).await,
+        "rebuild" => commands::rebuild::run(&args).await,
         "seed" => commands::seed::run().await,
+        "verify-chain" => commands::verify_chain::run(&args).await,
+        "worker" => commands::worker::run().await,
         _ => {
             error!("Unknown command: {}", command);
             Ok(())
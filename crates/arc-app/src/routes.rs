@@ -1,5 +1,6 @@
 use crate::http::controllers::api_controller::{
-    delete_profile, login, logout, profile, register, update_profile,
+    cancel_deletion, delete_profile, grant_role, login, logout, profile, register,
+    request_deletion, revoke_role, show_user, update_profile, update_user, verify_email,
 };
 use crate::http::controllers::diag_controller::{diag_health, list_events};
 use crate::http::controllers::{admin_controller, auth_controller, home_controller};
@@ -11,6 +12,7 @@ use crate::websocket;
 use actix_files as fs;
 use actix_web::http::header::{CACHE_CONTROL, ETAG, IF_NONE_MATCH};
 use actix_web::{get, web, Error, HttpRequest, HttpResponse, Responder};
+use arc_core::command_bus::CommandRouter;
 
 /// Serves static files from the `dist/` directory with ETag-based conditional
 /// requests and tiered Cache-Control headers (immutable for hashed assets).
@@ -76,13 +78,18 @@ pub async fn static_file(req: HttpRequest) -> Result<HttpResponse, Error> {
 }
 
 /// Health check endpoint for monitoring and load balancers.
-/// Returns 200 OK with JSON status when the application is running.
+/// Returns 200 OK with JSON status when the application is running, plus the
+/// command bus's concurrency-conflict retry counters when one is registered.
 #[get("/health")]
-pub async fn health() -> impl Responder {
-    HttpResponse::Ok().json(serde_json::json!({
+pub async fn health(command_bus: Option<web::Data<CommandRouter>>) -> impl Responder {
+    let mut body = serde_json::json!({
         "status": "healthy",
         "version": env!("CARGO_PKG_VERSION")
-    }))
+    });
+    if let Some(command_bus) = command_bus {
+        body["command_retries"] = serde_json::json!(command_bus.retry_stats());
+    }
+    HttpResponse::Ok().json(body)
 }
 
 /// Registers all application routes: health check, auth, admin, API (v1 + legacy), WebSocket, and static files.
@@ -109,7 +116,15 @@ pub fn config(cfg: &mut web::ServiceConfig) {
                         .service(profile)
                         .service(update_profile)
                         .service(delete_profile)
-                        .service(logout),
+                        .service(request_deletion)
+                        .service(cancel_deletion)
+                        .service(logout)
+                        // Policy-checked access to any user (admins)
+                        .service(show_user)
+                        .service(update_user)
+                        .service(grant_role)
+                        .service(revoke_role)
+                        .service(verify_email),
                 ),
         )
         // Backwards-compatible API routes (will be deprecated)
@@ -154,6 +169,8 @@ mod tests {
 
     use super::*;
     use actix_web::{http, test, App};
+    use arc_core::event_bus::InProcessEventBus;
+    use arc_core::event_store::InMemoryEventStore;
 
     #[actix_web::test]
     async fn test_static_file_ok() {
@@ -172,6 +189,28 @@ mod tests {
         assert_eq!(resp.status(), http::StatusCode::OK);
     }
 
+    #[actix_web::test]
+    async fn test_health_reports_command_retries() {
+        let app = test::init_service(App::new().service(health)).await;
+        let req = test::TestRequest::get().uri("/health").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["status"], "healthy");
+        assert!(body.get("command_retries").is_none());
+
+        let router = CommandRouter::new(
+            Box::new(InMemoryEventStore::new()),
+            Box::new(InProcessEventBus::new()),
+        );
+        let app =
+            test::init_service(App::new().app_data(web::Data::new(router)).service(health)).await;
+        let req = test::TestRequest::get().uri("/health").to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(
+            body["command_retries"],
+            serde_json::json!({ "retries": 0, "recovered": 0, "exhausted": 0 })
+        );
+    }
+
     #[actix_web::test]
     async fn test_static_file_not_found() {
         let app = test::init_service(App::new().service(static_file)).await;
@@ -18,5 +18,8 @@ diesel::table! {
         timestamp_utc_us -> BigInt,
         causation_id -> Nullable<Text>,
         correlation_id -> Text,
+        signature -> Text,
+        global_signature -> Text,
+        schema_version -> Integer,
     }
 }
@@ -1,8 +1,7 @@
-use crate::domain::user::aggregate::UserAggregate;
 use crate::domain::user::commands::UserCommand;
-use crate::domain::user::projector::USERS_VIEW;
+use crate::domain::user::projector::{roles_of, USERS_VIEW};
 use crate::http::errors::AppError;
-use arc_core::command_bus::{CommandBus, CommandContext};
+use arc_core::command_bus::{CommandContext, CommandRouter};
 use arc_core::read_model_store::ReadModelStore;
 use argon2::password_hash::rand_core::OsRng;
 use argon2::password_hash::SaltString;
@@ -77,6 +76,15 @@ pub async fn lookup_aggregate_id_by_email_view(
     }
 }
 
+/// Roles held by a user per the `users_view` projection; none when the row
+/// is missing or unreadable.
+pub async fn user_roles(read_model_store: &dyn ReadModelStore, aggregate_id: &str) -> Vec<String> {
+    match read_model_store.get(USERS_VIEW, aggregate_id).await {
+        Ok(Some(row)) => roles_of(&row),
+        _ => Vec::new(),
+    }
+}
+
 async fn find_user_by_email(
     store: &dyn ReadModelStore,
     user_email: &str,
@@ -105,30 +113,15 @@ pub fn prepare_password(password_string: &str) -> String {
 /// The `UserProjector` (subscribed to the event bus) writes `users_view` so
 /// subsequent email→id lookups and login attempts find the new user.
 ///
-/// Pre-checking for an existing email is best-effort — the authoritative
-/// guard is the `UNIQUE` index on `users_view.email`. If the read model is
-/// behind, the projector's upsert will fail with a unique-constraint error
-/// and surface as a command-handling failure.
+/// A taken email is rejected by the `UniqueEmail` command middleware; a
+/// request repeating an `Idempotency-Key` gets the original user's id back.
 pub async fn create_user(
-    command_bus: &CommandBus<UserAggregate>,
-    read_model_store: &dyn ReadModelStore,
+    command_bus: &CommandRouter,
     ctx: CommandContext,
     user_name: String,
     user_email: String,
     user_password: &str,
 ) -> Result<String, AppError> {
-    if lookup_aggregate_id_by_email_view(read_model_store, &user_email)
-        .await
-        .is_some()
-    {
-        return Err(AppError::CommandFailed(
-            arc_core::command_bus::CommandBusError::handle_failed(
-                "<unassigned>",
-                format!("email '{}' is already registered", user_email),
-            ),
-        ));
-    }
-
     let aggregate_id = uuid::Uuid::new_v4().to_string();
     let password_hash = prepare_password(user_password);
 
@@ -139,7 +132,12 @@ pub async fn create_user(
         password_hash,
     };
 
-    command_bus.dispatch(cmd, ctx).await?;
+    let events = command_bus.dispatch(cmd, ctx).await?;
 
-    Ok(aggregate_id)
+    // A replayed idempotency key returns the events of the first request,
+    // which name the user that was actually created.
+    Ok(events
+        .first()
+        .map(|e| e.aggregate_id.clone())
+        .unwrap_or(aggregate_id))
 }
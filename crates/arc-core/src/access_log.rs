@@ -23,6 +23,9 @@
 //! 3. Calls `logger.log_access(actor, resource, purpose).await`.
 //! 4. Returns the data to the client.
 //!
+//! Refused reads and commands go through [`AccessLogger::log_denial`], so
+//! the same sink answers "who tried to look at what, and was turned away".
+//!
 //! Default implementations in tests and non-regulated apps use
 //! [`NoOpAccessLogger`] which validates inputs but discards them. Real
 //! deployments wire a JetStream- or DB-backed implementation (Step 3+).
@@ -176,7 +179,8 @@ impl AccessedResource {
     }
 }
 
-/// Single record produced for every successful `log_access` call.
+/// Single record produced for every successful `log_access` call, and for
+/// every `log_denial` call (with `denial` set).
 ///
 /// Sinks serialize this into their target medium; tests inspect it directly.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -187,6 +191,9 @@ pub struct AccessLogEntry {
     pub purpose: PurposeOfUse,
     pub timestamp_utc_us: i64,
     pub correlation_id: Option<Uuid>,
+    /// Why access was refused. `None` for granted reads.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub denial: Option<String>,
 }
 
 impl AccessLogEntry {
@@ -219,8 +226,15 @@ impl AccessLogEntry {
             purpose,
             timestamp_utc_us: now_us(),
             correlation_id,
+            denial: None,
         })
     }
+
+    /// Mark the entry as a refused access.
+    pub fn with_denial(mut self, reason: impl Into<String>) -> Self {
+        self.denial = Some(reason.into());
+        self
+    }
 }
 
 /// Sink for read-access audit events.
@@ -246,6 +260,29 @@ pub trait AccessLogger: Send + Sync {
         purpose: PurposeOfUse,
         correlation_id: Option<Uuid>,
     ) -> Result<(), AccessLogError>;
+
+    /// Log an access that an authorization policy refused. The default
+    /// validates the entry and emits it as a `tracing::warn!`; sinks that
+    /// persist reads should persist denials too.
+    async fn log_denial(
+        &self,
+        actor: Identity,
+        resource: AccessedResource,
+        purpose: PurposeOfUse,
+        reason: String,
+        correlation_id: Option<Uuid>,
+    ) -> Result<(), AccessLogError> {
+        let entry =
+            AccessLogEntry::new(actor, resource, purpose, correlation_id)?.with_denial(reason);
+        tracing::warn!(
+            actor_id = %entry.actor.actor_id,
+            kind = %entry.resource.kind,
+            identifier = %entry.resource.identifier,
+            reason = entry.denial.as_deref().unwrap_or_default(),
+            "Access denied"
+        );
+        Ok(())
+    }
 }
 
 /// Validates inputs and discards the entry. Default for test apps and any
@@ -279,8 +316,8 @@ mod recording {
     use std::sync::Arc;
     use tokio::sync::Mutex;
 
-    /// In-memory `AccessLogger` that records every successful entry.
-    /// Use `entries()` to assert in tests.
+    /// In-memory `AccessLogger` that records every successful entry,
+    /// denials included. Use `entries()` to assert in tests.
     #[derive(Clone, Default)]
     pub struct RecordingAccessLogger {
         entries: Arc<Mutex<Vec<AccessLogEntry>>>,
@@ -309,6 +346,20 @@ mod recording {
             self.entries.lock().await.push(entry);
             Ok(())
         }
+
+        async fn log_denial(
+            &self,
+            actor: Identity,
+            resource: AccessedResource,
+            purpose: PurposeOfUse,
+            reason: String,
+            correlation_id: Option<Uuid>,
+        ) -> Result<(), AccessLogError> {
+            let entry =
+                AccessLogEntry::new(actor, resource, purpose, correlation_id)?.with_denial(reason);
+            self.entries.lock().await.push(entry);
+            Ok(())
+        }
     }
 }
 
@@ -403,6 +454,34 @@ mod tests {
         assert_eq!(entries[1].correlation_id, None);
     }
 
+    #[tokio::test]
+    async fn test_recording_logger_captures_denials() {
+        let logger = RecordingAccessLogger::new();
+        logger
+            .log_denial(
+                Identity::new("bob"),
+                ok_resource(),
+                PurposeOfUse::UserInitiated,
+                "not an admin".into(),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let entries = logger.entries().await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].denial.as_deref(), Some("not an admin"));
+
+        let json = serde_json::to_value(&entries[0]).unwrap();
+        assert_eq!(json["denial"], "not an admin");
+        let granted =
+            AccessLogEntry::new(ok_actor(), ok_resource(), PurposeOfUse::Other, None).unwrap();
+        assert!(serde_json::to_value(&granted)
+            .unwrap()
+            .get("denial")
+            .is_none());
+    }
+
     #[test]
     fn test_sensitivity_regulated_classification() {
         assert!(Sensitivity::Phi.is_regulated());
@@ -549,6 +549,7 @@
 //! }
 //! ```
 
+use crate::domain_event::DomainEventError;
 use crate::event::Event;
 use async_trait::async_trait;
 use std::error::Error;
@@ -622,6 +623,43 @@ pub trait Command: Send + Sync {
     /// assert_eq!(command.aggregate_id(), "user-123");
     /// ```
     fn aggregate_id(&self) -> &str;
+
+    /// Name of what this command does to its aggregate (`"update_profile"`,
+    /// `"delete"`), as seen by authorization policies. Defaults to
+    /// `"dispatch"`, which only wildcard policies match.
+    fn action(&self) -> &'static str {
+        "dispatch"
+    }
+
+    /// A copy of this command for the command bus to re-run after its
+    /// append lost a race (`EventStoreError::ConcurrencyConflict`), or `None`
+    /// to surface the conflict to the caller.
+    ///
+    /// A retry reloads the aggregate and calls `handle()` again, so it is
+    /// only safe for commands whose outcome `handle()` decides from the
+    /// current state alone, such as "set the display name to X". Commands
+    /// that encode what the caller saw before dispatching ("increment from
+    /// 3") must keep the default. The bus retries only when its
+    /// `RetryPolicy` allows more than one attempt.
+    fn retry_on_conflict(&self) -> Option<Self>
+    where
+        Self: Sized,
+    {
+        None
+    }
+
+    /// What an idempotency key is bound to when this command carries one.
+    /// The command bus stores a hash of it with the key, and a repeat of the
+    /// key with a different fingerprint fails with
+    /// `CommandBusError::IdempotencyKeyReused` instead of replaying another
+    /// request's result.
+    ///
+    /// Leave out anything generated per attempt (fresh ids, salted password
+    /// hashes) so a genuine retry still matches. Defaults to `None`, which
+    /// only checks that the key was used for the same aggregate type.
+    fn idempotency_fingerprint(&self) -> Option<serde_json::Value> {
+        None
+    }
 }
 
 /// Trait for domain aggregates in event sourcing.
@@ -825,25 +863,45 @@ pub trait Aggregate: Send + Sync + Default {
     ///
     /// # Example
     ///
+    /// With a [`DomainEvent`](crate::domain_event::DomainEvent) enum, decode
+    /// the payload and match on the variant:
+    ///
     /// ```rust,ignore
     /// fn apply(&mut self, event: &Event) {
     ///     self.version = event.sequence;
     ///
-    ///     match event.event_type.as_str() {
-    ///         "UserCreated" => {
-    ///             self.id = Some(event.payload["id"].as_str().unwrap().to_string());
-    ///             self.name = Some(event.payload["name"].as_str().unwrap().to_string());
+    ///     match event.decode::<UserEvent>() {
+    ///         Ok(UserEvent::UserCreated { id, name, .. }) => {
+    ///             self.id = Some(id);
+    ///             self.name = Some(name);
     ///             self.created = true;
     ///         }
-    ///         "ProfileUpdated" => {
-    ///             self.name = Some(event.payload["name"].as_str().unwrap().to_string());
+    ///         Ok(UserEvent::ProfileUpdated { name }) => {
+    ///             self.name = Some(name);
     ///         }
-    ///         _ => {} // Unknown event types are ignored
+    ///         Err(e) if e.is_unknown_event_type() => {} // Not ours, ignored
+    ///         Err(e) => tracing::error!(event_id = %event.event_id, error = %e, "Corrupt event"),
     ///     }
     /// }
     /// ```
+    ///
+    /// A payload that does not decode cannot be reported from here; report
+    /// it from [`try_apply`](Self::try_apply) instead.
     fn apply(&mut self, event: &Event);
 
+    /// Fallible [`apply`](Self::apply), used by the command bus whenever it
+    /// loads an aggregate. An error fails the dispatch with
+    /// `CommandBusError::LoadFailed` instead of handing `handle` state
+    /// folded past an event it could not read.
+    ///
+    /// Override it when stored payloads are decoded into a
+    /// [`DomainEvent`](crate::domain_event::DomainEvent), returning the
+    /// decode error; the default applies the event and succeeds.
+    fn try_apply(&mut self, event: &Event) -> Result<(), DomainEventError> {
+        self.apply(event);
+        Ok(())
+    }
+
     /// Reconstruct aggregate from its event stream.
     ///
     /// This method has a default implementation that:
@@ -922,6 +980,81 @@ pub trait Aggregate: Send + Sync + Default {
         }
         aggregate
     }
+
+    /// Version of the shape produced by [`to_snapshot`](Aggregate::to_snapshot).
+    ///
+    /// Bump this whenever the serialized state changes incompatibly. The
+    /// `CommandBus` ignores stored snapshots whose schema version differs and
+    /// falls back to a full replay, so old snapshots never need migrating.
+    fn snapshot_schema_version() -> u32 {
+        1
+    }
+
+    /// Serialize current state for a [`Snapshot`](crate::snapshot::Snapshot).
+    ///
+    /// Returns `None` (the default) for aggregates that don't support
+    /// snapshots; the `CommandBus` then always replays from events. The
+    /// state must include everything `apply` has accumulated, including
+    /// `version()`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use arc_core::aggregate::{Aggregate, Command};
+    /// # use arc_core::event::Event;
+    /// # use serde::{Deserialize, Serialize};
+    /// # use thiserror::Error;
+    /// #
+    /// # struct DummyCommand;
+    /// # impl Command for DummyCommand {
+    /// #     fn aggregate_id(&self) -> &str { "dummy" }
+    /// # }
+    /// # #[derive(Debug, Error)]
+    /// # #[error("dummy error")]
+    /// # struct DummyError;
+    /// #
+    /// #[derive(Default, Serialize, Deserialize)]
+    /// struct CounterAggregate {
+    ///     value: i64,
+    ///     version: i64,
+    /// }
+    ///
+    /// # #[async_trait::async_trait]
+    /// impl Aggregate for CounterAggregate {
+    /// #     type Command = DummyCommand;
+    /// #     type Event = ();
+    /// #     type Error = DummyError;
+    /// #     fn aggregate_type() -> &'static str { "Counter" }
+    /// #     fn version(&self) -> i64 { self.version }
+    /// #     async fn handle(&self, _: Self::Command) -> Result<Vec<Event>, Self::Error> { Ok(vec![]) }
+    /// #     fn apply(&mut self, _: &Event) {}
+    ///     // ...
+    ///     fn to_snapshot(&self) -> Option<serde_json::Value> {
+    ///         serde_json::to_value(self).ok()
+    ///     }
+    ///
+    ///     fn from_snapshot(state: &serde_json::Value) -> Option<Self> {
+    ///         serde_json::from_value(state.clone()).ok()
+    ///     }
+    /// }
+    ///
+    /// let agg = CounterAggregate { value: 7, version: 3 };
+    /// let restored = CounterAggregate::from_snapshot(&agg.to_snapshot().unwrap()).unwrap();
+    /// assert_eq!(restored.value, 7);
+    /// assert_eq!(restored.version(), 3);
+    /// ```
+    fn to_snapshot(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Restore state from a snapshot produced by [`to_snapshot`](Aggregate::to_snapshot).
+    ///
+    /// Returning `None` makes the `CommandBus` discard the snapshot and
+    /// replay from events instead, so a state blob that no longer
+    /// deserializes is never fatal.
+    fn from_snapshot(_state: &serde_json::Value) -> Option<Self> {
+        None
+    }
 }
 
 #[cfg(test)]
@@ -1317,4 +1450,12 @@ mod tests {
         assert!(!aggregate.created);
         assert_eq!(aggregate.value, 0);
     }
+
+    #[test]
+    fn test_snapshot_hooks_default_to_opt_out() {
+        let aggregate = CounterAggregate::default();
+        assert_eq!(CounterAggregate::snapshot_schema_version(), 1);
+        assert!(aggregate.to_snapshot().is_none());
+        assert!(CounterAggregate::from_snapshot(&serde_json::json!({})).is_none());
+    }
 }
@@ -0,0 +1,531 @@
+//! # Authorization
+//!
+//! Policy checks answering "may this actor perform this action on this
+//! resource?" for both sides of the application:
+//!
+//! - **Commands** — [`AuthorizationMiddleware`] evaluates every dispatch
+//!   before the aggregate is loaded. The action is
+//!   [`Command::action`](crate::aggregate::Command::action), the resource is
+//!   the target aggregate (`aggregate_type`, `aggregate_id`).
+//! - **Reads** — controllers call [`PolicySet::evaluate`] directly (the web
+//!   crate wraps that in an extractor) before touching a read model.
+//!
+//! ## Evaluation
+//!
+//! A [`PolicySet`] asks every [`Policy`] in turn. Each one allows, denies, or
+//! abstains (`None`). Any deny wins; otherwise any allow grants access;
+//! when every policy abstains the request is denied. Deny-by-default means a
+//! new command or read is unreachable until a policy names it.
+//!
+//! Denials carry a reason and are recorded through
+//! [`AccessLogger::log_denial`] so refused attempts land in the same sink as
+//! audited reads.
+//!
+//! ```rust,ignore
+//! let policies = PolicySet::new()
+//!     .with_policy(SystemPolicy)
+//!     .with_policy(PublicPolicy::new("User", ["register"]))
+//!     .with_policy(SelfPolicy::new("User", ["read", "update_profile"]))
+//!     .with_policy(RolePolicy::new("admin", "User", ["*"]));
+//! ```
+
+use crate::access_log::{AccessLogger, AccessedResource, Identity, PurposeOfUse, Sensitivity};
+use crate::audit::SYSTEM_ACTOR;
+use crate::command_bus::{CommandBusError, CommandBusResult, CommandContext};
+use crate::command_middleware::{CommandEnvelope, CommandMiddleware};
+use async_trait::async_trait;
+use std::fmt;
+use std::sync::Arc;
+
+/// Matches every resource kind or every action.
+pub const WILDCARD: &str = "*";
+
+/// Who is asking: the actor id (same vocabulary as
+/// [`CommandContext::actor_id`]) and the roles it holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    pub actor_id: String,
+    pub roles: Vec<String>,
+}
+
+impl Principal {
+    pub fn new(actor_id: impl Into<String>) -> Self {
+        Self {
+            actor_id: actor_id.into(),
+            roles: Vec::new(),
+        }
+    }
+
+    pub fn with_roles<I, S>(mut self, roles: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.roles = roles.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The principal a command is dispatched on behalf of.
+    pub fn from_context(context: &CommandContext) -> Self {
+        Self {
+            actor_id: context.actor_id.clone(),
+            roles: context.roles.clone(),
+        }
+    }
+
+    pub fn has_role(&self, role: &str) -> bool {
+        self.roles.iter().any(|r| r == role)
+    }
+}
+
+/// What is being acted on: an aggregate type or read-model kind, and the id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resource {
+    pub kind: String,
+    pub id: String,
+}
+
+impl Resource {
+    pub fn new(kind: impl Into<String>, id: impl Into<String>) -> Self {
+        Self {
+            kind: kind.into(),
+            id: id.into(),
+        }
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} '{}'", self.kind, self.id)
+    }
+}
+
+/// Outcome of an authorization check.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decision {
+    Allow,
+    /// Refused, with a reason suitable for logs and API error bodies.
+    Deny(String),
+}
+
+impl Decision {
+    pub fn is_allowed(&self) -> bool {
+        matches!(self, Decision::Allow)
+    }
+}
+
+/// One authorization question.
+#[derive(Debug, Clone, Copy)]
+pub struct AccessRequest<'a> {
+    pub principal: &'a Principal,
+    pub action: &'a str,
+    pub resource: &'a Resource,
+}
+
+/// A single rule. Return `None` to abstain when the rule does not apply.
+pub trait Policy: Send + Sync {
+    /// Name for logs and `Debug` output.
+    fn name(&self) -> &str;
+
+    fn evaluate(&self, request: &AccessRequest<'_>) -> Option<Decision>;
+}
+
+/// Ordered collection of policies, evaluated deny-wins, deny-by-default.
+#[derive(Clone, Default)]
+pub struct PolicySet {
+    policies: Vec<Arc<dyn Policy>>,
+}
+
+impl fmt::Debug for PolicySet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.policies.iter().map(|p| p.name()))
+            .finish()
+    }
+}
+
+impl PolicySet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_policy(mut self, policy: impl Policy + 'static) -> Self {
+        self.policies.push(Arc::new(policy));
+        self
+    }
+
+    pub fn evaluate(&self, principal: &Principal, action: &str, resource: &Resource) -> Decision {
+        let request = AccessRequest {
+            principal,
+            action,
+            resource,
+        };
+        let mut allowed = false;
+        for policy in &self.policies {
+            match policy.evaluate(&request) {
+                Some(Decision::Deny(reason)) => return Decision::Deny(reason),
+                Some(Decision::Allow) => allowed = true,
+                None => {}
+            }
+        }
+        if allowed {
+            Decision::Allow
+        } else {
+            Decision::Deny(format!("no policy allows '{action}' on {}", resource.kind))
+        }
+    }
+}
+
+fn matches(pattern: &str, value: &str) -> bool {
+    pattern == WILDCARD || pattern == value
+}
+
+fn collect<I, S>(actions: I) -> Vec<String>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    actions.into_iter().map(Into::into).collect()
+}
+
+/// Target of a stock policy: a resource kind and the actions it covers.
+#[derive(Debug, Clone)]
+struct Scope {
+    kind: String,
+    actions: Vec<String>,
+}
+
+impl Scope {
+    fn covers(&self, request: &AccessRequest<'_>) -> bool {
+        matches(&self.kind, &request.resource.kind)
+            && self.actions.iter().any(|a| matches(a, request.action))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Stock policies
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Allows everything for the `"system"` actor (seeders, sagas, cron).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemPolicy;
+
+impl Policy for SystemPolicy {
+    fn name(&self) -> &str {
+        "system"
+    }
+
+    fn evaluate(&self, request: &AccessRequest<'_>) -> Option<Decision> {
+        (request.principal.actor_id == SYSTEM_ACTOR).then_some(Decision::Allow)
+    }
+}
+
+/// Allows `actions` on `kind` for anyone, anonymous included.
+#[derive(Debug, Clone)]
+pub struct PublicPolicy {
+    scope: Scope,
+}
+
+impl PublicPolicy {
+    pub fn new<I, S>(kind: impl Into<String>, actions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            scope: Scope {
+                kind: kind.into(),
+                actions: collect(actions),
+            },
+        }
+    }
+}
+
+impl Policy for PublicPolicy {
+    fn name(&self) -> &str {
+        "public"
+    }
+
+    fn evaluate(&self, request: &AccessRequest<'_>) -> Option<Decision> {
+        self.scope.covers(request).then_some(Decision::Allow)
+    }
+}
+
+/// Allows `actions` on `kind` when the resource is the actor itself — a
+/// user acting on their own `User` aggregate.
+#[derive(Debug, Clone)]
+pub struct SelfPolicy {
+    scope: Scope,
+}
+
+impl SelfPolicy {
+    pub fn new<I, S>(kind: impl Into<String>, actions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            scope: Scope {
+                kind: kind.into(),
+                actions: collect(actions),
+            },
+        }
+    }
+}
+
+impl Policy for SelfPolicy {
+    fn name(&self) -> &str {
+        "self"
+    }
+
+    fn evaluate(&self, request: &AccessRequest<'_>) -> Option<Decision> {
+        (self.scope.covers(request) && request.principal.actor_id == request.resource.id)
+            .then_some(Decision::Allow)
+    }
+}
+
+/// Allows `actions` on `kind` for principals holding `role`.
+#[derive(Debug, Clone)]
+pub struct RolePolicy {
+    role: String,
+    scope: Scope,
+}
+
+impl RolePolicy {
+    pub fn new<I, S>(role: impl Into<String>, kind: impl Into<String>, actions: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            role: role.into(),
+            scope: Scope {
+                kind: kind.into(),
+                actions: collect(actions),
+            },
+        }
+    }
+}
+
+impl Policy for RolePolicy {
+    fn name(&self) -> &str {
+        &self.role
+    }
+
+    fn evaluate(&self, request: &AccessRequest<'_>) -> Option<Decision> {
+        (self.scope.covers(request) && request.principal.has_role(&self.role))
+            .then_some(Decision::Allow)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Command bus hook
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Evaluates a [`PolicySet`] before every dispatch and rejects denied
+/// commands with [`CommandBusError::Forbidden`]. Denials are recorded through
+/// the access logger, when one is configured, as `Internal` resources with
+/// purpose `UserInitiated`.
+#[derive(Clone)]
+pub struct AuthorizationMiddleware {
+    policies: Arc<PolicySet>,
+    access_logger: Option<Arc<dyn AccessLogger>>,
+}
+
+impl AuthorizationMiddleware {
+    pub fn new(policies: Arc<PolicySet>) -> Self {
+        Self {
+            policies,
+            access_logger: None,
+        }
+    }
+
+    pub fn with_access_logger(mut self, access_logger: Arc<dyn AccessLogger>) -> Self {
+        self.access_logger = Some(access_logger);
+        self
+    }
+}
+
+#[async_trait]
+impl CommandMiddleware for AuthorizationMiddleware {
+    fn name(&self) -> &str {
+        "authorization"
+    }
+
+    async fn before(
+        &self,
+        command: &CommandEnvelope<'_>,
+        context: &mut CommandContext,
+    ) -> CommandBusResult<()> {
+        let principal = Principal::from_context(context);
+        let resource = Resource::new(command.aggregate_type, command.aggregate_id);
+        let reason = match self
+            .policies
+            .evaluate(&principal, command.action, &resource)
+        {
+            Decision::Allow => return Ok(()),
+            Decision::Deny(reason) => reason,
+        };
+
+        if let Some(logger) = &self.access_logger {
+            let actor = Identity {
+                actor_id: context.actor_id.clone(),
+                session_id: context.session_id.clone(),
+                source_ip: context.source_ip.clone(),
+                user_agent: context.user_agent.clone(),
+            };
+            let accessed =
+                AccessedResource::new(&resource.kind, &resource.id, Sensitivity::Internal)
+                    .with_fields([command.action]);
+            if let Err(e) = logger
+                .log_denial(
+                    actor,
+                    accessed,
+                    PurposeOfUse::UserInitiated,
+                    reason.clone(),
+                    Some(context.correlation_id),
+                )
+                .await
+            {
+                tracing::warn!(error = %e, "Failed to record authorization denial");
+            }
+        }
+
+        Err(CommandBusError::forbidden(
+            &context.actor_id,
+            command.action,
+            resource.to_string(),
+            reason,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::access_log::RecordingAccessLogger;
+    use crate::aggregate::Command;
+
+    fn users() -> PolicySet {
+        PolicySet::new()
+            .with_policy(SystemPolicy)
+            .with_policy(PublicPolicy::new("User", ["register"]))
+            .with_policy(SelfPolicy::new("User", ["update_profile"]))
+            .with_policy(RolePolicy::new("admin", "User", [WILDCARD]))
+    }
+
+    #[test]
+    fn test_self_role_public_and_system_allow() {
+        let policies = users();
+        let alice = Principal::new("alice");
+        let admin = Principal::new("root").with_roles(["admin"]);
+
+        assert!(policies
+            .evaluate(&alice, "update_profile", &Resource::new("User", "alice"))
+            .is_allowed());
+        assert!(policies
+            .evaluate(&admin, "delete", &Resource::new("User", "alice"))
+            .is_allowed());
+        assert!(policies
+            .evaluate(
+                &Principal::new("anonymous"),
+                "register",
+                &Resource::new("User", "new")
+            )
+            .is_allowed());
+        assert!(policies
+            .evaluate(
+                &Principal::new(SYSTEM_ACTOR),
+                "delete",
+                &Resource::new("Order", "o1")
+            )
+            .is_allowed());
+    }
+
+    #[test]
+    fn test_denies_by_default_with_reason() {
+        let decision = users().evaluate(
+            &Principal::new("alice"),
+            "update_profile",
+            &Resource::new("User", "bob"),
+        );
+        assert_eq!(
+            decision,
+            Decision::Deny("no policy allows 'update_profile' on User".into())
+        );
+    }
+
+    #[test]
+    fn test_any_deny_overrides_allows() {
+        struct Suspended;
+        impl Policy for Suspended {
+            fn name(&self) -> &str {
+                "suspended"
+            }
+            fn evaluate(&self, request: &AccessRequest<'_>) -> Option<Decision> {
+                (request.principal.actor_id == "alice")
+                    .then(|| Decision::Deny("account suspended".into()))
+            }
+        }
+
+        let policies = users().with_policy(Suspended);
+        let decision = policies.evaluate(
+            &Principal::new("alice"),
+            "update_profile",
+            &Resource::new("User", "alice"),
+        );
+        assert_eq!(decision, Decision::Deny("account suspended".into()));
+    }
+
+    struct Rename {
+        id: String,
+    }
+
+    impl Command for Rename {
+        fn aggregate_id(&self) -> &str {
+            &self.id
+        }
+
+        fn action(&self) -> &'static str {
+            "update_profile"
+        }
+    }
+
+    #[tokio::test]
+    async fn test_middleware_rejects_and_logs_denials() {
+        let logger = RecordingAccessLogger::new();
+        let middleware = AuthorizationMiddleware::new(Arc::new(users()))
+            .with_access_logger(Arc::new(logger.clone()));
+        let command = Rename { id: "bob".into() };
+        let envelope = CommandEnvelope::new("User", &command);
+
+        let mut own = CommandContext::for_actor("bob");
+        assert!(middleware.before(&envelope, &mut own).await.is_ok());
+
+        let mut other = CommandContext::for_actor("alice");
+        let err = middleware.before(&envelope, &mut other).await.unwrap_err();
+        match err {
+            CommandBusError::Forbidden {
+                actor_id,
+                action,
+                resource,
+                ..
+            } => {
+                assert_eq!(actor_id, "alice");
+                assert_eq!(action, "update_profile");
+                assert_eq!(resource, "User 'bob'");
+            }
+            other => panic!("expected Forbidden, got {other:?}"),
+        }
+
+        let mut admin = CommandContext::for_actor("alice").with_roles(["admin"]);
+        assert!(middleware.before(&envelope, &mut admin).await.is_ok());
+
+        let entries = logger.entries().await;
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].actor.actor_id, "alice");
+        assert_eq!(entries[0].resource.identifier, "bob");
+        assert_eq!(entries[0].correlation_id, Some(other.correlation_id));
+        assert!(entries[0].denial.is_some());
+    }
+}
@@ -4,8 +4,12 @@
 //!
 //! ## Flow
 //!
-//! 1. Load events from `EventStore` for the target aggregate
-//! 2. Reconstruct aggregate state via `Aggregate::from_events()`
+//! 1. Load events from `EventStore` for the target aggregate (or, with a
+//!    [`SnapshotPolicy`] enabled, the latest snapshot plus the events after it)
+//! 2. Reconstruct aggregate state by folding each event through
+//!    `Aggregate::try_apply()` (or `Aggregate::from_snapshot()` followed by
+//!    `try_apply()` for the tail); an event that does not decode fails the
+//!    dispatch with [`CommandBusError::LoadFailed`]
 //! 3. Handle command through `Aggregate::handle()` to produce new events
 //!    (events leave `handle()` with `audit = AuditMetadata::pending()`)
 //! 4. **Stamp** each event with a fully-validated [`AuditMetadata`] derived from
@@ -13,6 +17,60 @@
 //! 5. Append events to `EventStore` with optimistic concurrency check; the
 //!    store re-validates audit (defense-in-depth)
 //! 6. Publish events to `EventBus` for projections and side effects
+//! 7. If the snapshot policy says the aggregate is due, save a fresh snapshot
+//!
+//! ## Outbox
+//!
+//! Without an [`Outbox`], a failed publish returns
+//! [`CommandBusError::PublishFailed`] even though the events are already
+//! committed, and nothing redelivers them. With
+//! [`with_outbox`](CommandBus::with_outbox) the store has queued the events
+//! in the append transaction: a successful publish marks them delivered, a
+//! failed one is logged and left to the [`OutboxRelay`](crate::outbox::OutboxRelay),
+//! and `dispatch` succeeds.
+//!
+//! ## Retries
+//!
+//! Step 5 fails with [`EventStoreError::ConcurrencyConflict`] when another
+//! writer appended to the aggregate first. With a [`RetryPolicy`] allowing
+//! more than one attempt, commands that opt in through
+//! [`Command::retry_on_conflict`] go back to step 1 after a jittered
+//! backoff; everything else returns the conflict (HTTP 409 in the app).
+//! [`RetryStats`] counts retries taken, recoveries and exhausted retries.
+//!
+//! ## Idempotency
+//!
+//! With [`with_idempotency`](CommandBus::with_idempotency), a dispatch whose
+//! [`CommandContext::idempotency_key`] is set reserves the key in an
+//! [`IdempotencyStore`] before step 1 and records the produced event ids
+//! after step 6. Repeating the key returns those events, loaded back from
+//! the store, without running the command; a failed dispatch releases the
+//! key so the client can retry it. The record also keeps a hash of the
+//! command's [`Command::idempotency_fingerprint`], and a repeat carrying a
+//! different command is refused with
+//! [`CommandBusError::IdempotencyKeyReused`].
+//!
+//! ## Middleware
+//!
+//! [`with_middleware`](CommandBus::with_middleware) stacks
+//! [`CommandMiddleware`] around steps 1–7: `before` hooks can adjust the
+//! context or reject the command, `after` / `on_error` hooks observe the
+//! outcome. See [`crate::command_middleware`].
+//!
+//! ## Snapshots
+//!
+//! Snapshots are a read-side cache for step 1–2. A snapshot is only used when
+//! its aggregate type, schema version and version all line up with what the
+//! aggregate expects; anything else (including a store error) falls back to a
+//! full replay. Saving a snapshot after publish is best-effort: a failure is
+//! logged and the command still succeeds, since the events are already durable.
+//!
+//! ## Routing
+//!
+//! A [`CommandBus`] serves a single aggregate type. [`CommandRouter`] holds
+//! one bus per registered aggregate over a shared store and bus, and routes
+//! each command by its Rust type, so an application with several aggregates
+//! wires (and injects) a single router.
 //!
 //! ## Audit invariant
 //!
@@ -22,10 +80,25 @@
 
 use crate::aggregate::{Aggregate, Command};
 use crate::audit::{AuditError, AuditMetadata, SYSTEM_ACTOR};
+use crate::command_middleware::{CommandEnvelope, CommandMiddleware, MiddlewareStack};
+use crate::domain_event::DomainEventError;
 use crate::event::Event;
 use crate::event_bus::{EventBus, EventBusError};
 use crate::event_store::{EventStore, EventStoreError, VersionCheck};
+use crate::idempotency::{
+    IdempotencyError, IdempotencyRecord, IdempotencyStatus, IdempotencyStore, IdempotentOutcome,
+};
+use crate::outbox::Outbox;
+use crate::snapshot::{Snapshot, SnapshotPolicy};
+use async_trait::async_trait;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::any::{type_name, Any, TypeId};
+use std::collections::HashMap;
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -54,6 +127,15 @@ pub struct CommandContext {
 
     /// Optional event id that triggered this command (saga / projection follow-up).
     pub causation_id: Option<Uuid>,
+
+    /// Client-supplied key (`Idempotency-Key` header). A bus with an
+    /// [`IdempotencyStore`] returns the original result for a key it has
+    /// already completed instead of running the command again.
+    pub idempotency_key: Option<String>,
+
+    /// Roles the actor holds (JWT `roles` claim), for authorization
+    /// policies. Not part of the audit stamp.
+    pub roles: Vec<String>,
 }
 
 impl CommandContext {
@@ -67,6 +149,8 @@ impl CommandContext {
             user_agent: None,
             correlation_id: Uuid::new_v4(),
             causation_id: None,
+            idempotency_key: None,
+            roles: Vec::new(),
         }
     }
 
@@ -85,7 +169,37 @@ impl CommandContext {
             user_agent: None,
             correlation_id: triggering.audit.correlation_id,
             causation_id: Some(triggering.event_id),
+            idempotency_key: None,
+            roles: Vec::new(),
+        }
+    }
+
+    /// Attach the client's idempotency key. Only takes effect on a bus
+    /// configured with an idempotency store.
+    pub fn with_idempotency_key(mut self, key: impl Into<String>) -> Self {
+        self.idempotency_key = Some(key.into());
+        self
+    }
+
+    /// Attach the roles the actor holds.
+    pub fn with_roles<I, S>(mut self, roles: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.roles = roles.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Narrow the idempotency key to one of several commands dispatched for
+    /// the same request (`"{key}/{scope}"`), so each command replays its own
+    /// result. A context without a key is returned unchanged.
+    pub fn with_idempotency_scope(mut self, scope: &str) -> Self {
+        if let Some(key) = &mut self.idempotency_key {
+            key.push('/');
+            key.push_str(scope);
         }
+        self
     }
 
     /// Convert into the [`AuditMetadata`] that will stamp produced events.
@@ -149,6 +263,36 @@ pub enum CommandBusError {
         source: AuditError,
     },
 
+    #[error("No aggregate registered for command '{command_type}'")]
+    NoRoute { command_type: String },
+
+    #[error("Actor '{actor_id}' may not '{action}' {resource}: {reason}")]
+    Forbidden {
+        actor_id: String,
+        action: String,
+        resource: String,
+        reason: String,
+    },
+
+    #[error("Command '{command_type}' rejected: {message}")]
+    Rejected {
+        command_type: String,
+        message: String,
+    },
+
+    #[error("A request with idempotency key '{key}' is still being processed")]
+    IdempotencyInFlight { key: String },
+
+    #[error("Idempotency key '{key}' was already used for a '{aggregate_type}' command")]
+    IdempotencyKeyReused { key: String, aggregate_type: String },
+
+    #[error("Idempotency store failed for key '{key}': {source}")]
+    IdempotencyFailed {
+        key: String,
+        #[source]
+        source: IdempotencyError,
+    },
+
     #[error("Command bus error: {message}")]
     Other { message: String },
 }
@@ -161,58 +305,526 @@ impl CommandBusError {
         }
     }
 
+    pub fn no_route(command_type: impl Into<String>) -> Self {
+        CommandBusError::NoRoute {
+            command_type: command_type.into(),
+        }
+    }
+
+    pub fn forbidden(
+        actor_id: impl Into<String>,
+        action: impl Into<String>,
+        resource: impl Into<String>,
+        reason: impl Into<String>,
+    ) -> Self {
+        CommandBusError::Forbidden {
+            actor_id: actor_id.into(),
+            action: action.into(),
+            resource: resource.into(),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn rejected(command_type: impl Into<String>, message: impl Into<String>) -> Self {
+        CommandBusError::Rejected {
+            command_type: command_type.into(),
+            message: message.into(),
+        }
+    }
+
     pub fn other(message: impl Into<String>) -> Self {
         CommandBusError::Other {
             message: message.into(),
         }
     }
+
+    /// Whether dispatching the same command again may succeed: storage and
+    /// delivery failures, and idempotency bookkeeping that has not settled.
+    /// Rejections, denials and routing errors are permanent.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            CommandBusError::LoadFailed { .. }
+                | CommandBusError::AppendFailed { .. }
+                | CommandBusError::PublishFailed { .. }
+                | CommandBusError::IdempotencyInFlight { .. }
+                | CommandBusError::IdempotencyFailed { .. }
+        )
+    }
 }
 
 pub type CommandBusResult<T> = Result<T, CommandBusError>;
 
+/// How [`CommandBus::dispatch`] retries a command whose append failed with
+/// [`EventStoreError::ConcurrencyConflict`].
+///
+/// Only commands that opt in through [`Command::retry_on_conflict`] are
+/// retried. Each retry reloads the aggregate and re-runs `handle()` after a
+/// jittered backoff, so racing writers spread out instead of colliding again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts per dispatch, including the first. `1` (the default) never
+    /// retries.
+    pub max_attempts: u32,
+    /// Upper bound of the delay before the first retry; doubled after each
+    /// further conflict.
+    pub initial_backoff: Duration,
+    /// Upper bound for any retry delay.
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(250),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Up to `max_attempts` attempts per dispatch with the default backoff.
+    pub fn attempts(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    /// Whether the policy allows any retry at all.
+    pub fn is_enabled(&self) -> bool {
+        self.max_attempts > 1
+    }
+
+    /// Upper bound of the delay before retry number `retry` (1-based).
+    fn backoff_cap(&self, retry: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(retry.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+
+    /// Delay before retry number `retry`: uniform between half the cap and
+    /// the cap.
+    fn backoff(&self, retry: u32) -> Duration {
+        let cap = self.backoff_cap(retry);
+        let unit = (Uuid::new_v4().as_u128() as u64) as f64 / u64::MAX as f64;
+        cap / 2 + (cap / 2).mul_f64(unit)
+    }
+}
+
+/// Counters of concurrency-conflict retries, as reported by
+/// [`CommandBus::retry_stats`] and [`CommandRouter::retry_stats`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct RetryStats {
+    /// Retries taken after a conflict
+    pub retries: u64,
+    /// Dispatches that succeeded after at least one retry
+    pub recovered: u64,
+    /// Dispatches that still conflicted on their last allowed attempt
+    pub exhausted: u64,
+}
+
+#[derive(Debug, Default)]
+struct RetryMetrics {
+    retries: AtomicU64,
+    recovered: AtomicU64,
+    exhausted: AtomicU64,
+}
+
+impl RetryMetrics {
+    fn stats(&self) -> RetryStats {
+        RetryStats {
+            retries: self.retries.load(Ordering::Relaxed),
+            recovered: self.recovered.load(Ordering::Relaxed),
+            exhausted: self.exhausted.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Idempotency store plus record lifetime, shared by a router's buses.
+#[derive(Clone)]
+struct Idempotency {
+    store: Arc<dyn IdempotencyStore>,
+    ttl: Duration,
+}
+
+impl Idempotency {
+    fn failed(key: &str, source: IdempotencyError) -> CommandBusError {
+        CommandBusError::IdempotencyFailed {
+            key: key.to_string(),
+            source,
+        }
+    }
+
+    async fn lookup(
+        &self,
+        context: &CommandContext,
+        key: &str,
+    ) -> CommandBusResult<Option<IdempotentOutcome>> {
+        self.store
+            .lookup(&context.actor_id, key, crate::audit::now_us())
+            .await
+            .map_err(|e| Self::failed(key, e))
+    }
+
+    async fn begin(
+        &self,
+        context: &CommandContext,
+        key: &str,
+    ) -> CommandBusResult<IdempotencyStatus> {
+        let ttl_us = i64::try_from(self.ttl.as_micros()).unwrap_or(i64::MAX);
+        let record =
+            IdempotencyRecord::reserve(&context.actor_id, key, crate::audit::now_us(), ttl_us);
+        self.store
+            .begin(record)
+            .await
+            .map_err(|e| Self::failed(key, e))
+    }
+
+    /// Record or release the reservation once the dispatch is over. Both
+    /// are best-effort: the events are already committed (or were never
+    /// written), so a store failure here is logged, not returned.
+    async fn finish(
+        &self,
+        context: &CommandContext,
+        key: &str,
+        aggregate_type: &str,
+        aggregate_id: &str,
+        fingerprint: Option<String>,
+        result: &CommandBusResult<Vec<Event>>,
+    ) {
+        let actor_id = context.actor_id.as_str();
+        let finished = match result {
+            Ok(events) => {
+                let outcome = IdempotentOutcome {
+                    aggregate_type: aggregate_type.to_string(),
+                    aggregate_id: aggregate_id.to_string(),
+                    event_ids: events.iter().map(|e| e.event_id).collect(),
+                    fingerprint,
+                };
+                self.store.complete(actor_id, key, outcome).await
+            }
+            Err(_) => self.store.release(actor_id, key).await,
+        };
+        if let Err(e) = finished {
+            tracing::warn!(key, aggregate_id, error = %e, "Failed to update idempotency record");
+        }
+    }
+}
+
+/// SHA-256 (hex) of `command`'s
+/// [`idempotency_fingerprint`](Command::idempotency_fingerprint), if it has
+/// one.
+fn fingerprint_of<C: Command>(command: &C) -> Option<String> {
+    let value = command.idempotency_fingerprint()?;
+    let bytes = serde_json::to_vec(&value).ok()?;
+    Some(format!("{:x}", Sha256::digest(bytes)))
+}
+
+/// Load the events a completed dispatch produced, in their original order.
+async fn replay_outcome(
+    event_store: &dyn EventStore,
+    outcome: &IdempotentOutcome,
+) -> CommandBusResult<Vec<Event>> {
+    let mut events = event_store
+        .load(&outcome.aggregate_id)
+        .await
+        .map_err(|source| CommandBusError::LoadFailed {
+            aggregate_id: outcome.aggregate_id.clone(),
+            source,
+        })?;
+    events.retain(|e| outcome.event_ids.contains(&e.event_id));
+    events.sort_by_key(|e| outcome.event_ids.iter().position(|id| *id == e.event_id));
+    Ok(events)
+}
+
 /// Command bus for dispatching commands to aggregates.
 pub struct CommandBus<A: Aggregate> {
-    event_store: Box<dyn EventStore>,
-    event_bus: Box<dyn EventBus>,
+    event_store: Arc<dyn EventStore>,
+    event_bus: Arc<dyn EventBus>,
+    snapshot_policy: SnapshotPolicy,
+    retry_policy: RetryPolicy,
+    retry_metrics: Arc<RetryMetrics>,
+    idempotency: Option<Idempotency>,
+    middleware: MiddlewareStack,
+    outbox: Option<Arc<dyn Outbox>>,
     _phantom: PhantomData<A>,
 }
 
 impl<A: Aggregate> CommandBus<A> {
     pub fn new(event_store: Box<dyn EventStore>, event_bus: Box<dyn EventBus>) -> Self {
+        Self::from_shared(Arc::from(event_store), Arc::from(event_bus))
+    }
+
+    /// Build a bus over a store and bus that other `CommandBus`es also use,
+    /// as [`CommandRouter`] does for each registered aggregate.
+    pub fn from_shared(event_store: Arc<dyn EventStore>, event_bus: Arc<dyn EventBus>) -> Self {
         Self {
             event_store,
             event_bus,
+            snapshot_policy: SnapshotPolicy::default(),
+            retry_policy: RetryPolicy::default(),
+            retry_metrics: Arc::default(),
+            idempotency: None,
+            middleware: MiddlewareStack::default(),
+            outbox: None,
             _phantom: PhantomData,
         }
     }
 
+    /// Mark published events delivered in `outbox` and tolerate publish
+    /// failures, leaving redelivery to an
+    /// [`OutboxRelay`](crate::outbox::OutboxRelay).
+    ///
+    /// `outbox` must be backed by the same store as `event_store`, with
+    /// outbox writes enabled; otherwise events that fail to publish are
+    /// silently dropped.
+    pub fn with_outbox(mut self, outbox: Arc<dyn Outbox>) -> Self {
+        self.outbox = Some(outbox);
+        self
+    }
+
+    /// Set the snapshot cadence. Defaults to [`SnapshotPolicy::Never`].
+    ///
+    /// Only takes effect for aggregates that implement
+    /// `Aggregate::to_snapshot` / `from_snapshot`.
+    pub fn with_snapshot_policy(mut self, policy: SnapshotPolicy) -> Self {
+        self.snapshot_policy = policy;
+        self
+    }
+
+    pub fn snapshot_policy(&self) -> SnapshotPolicy {
+        self.snapshot_policy
+    }
+
+    /// Retry commands that opt in through [`Command::retry_on_conflict`]
+    /// when their append hits a concurrency conflict. Defaults to no retries.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+
+    /// Retries taken so far by this bus.
+    pub fn retry_stats(&self) -> RetryStats {
+        self.retry_metrics.stats()
+    }
+
+    /// Make dispatches that carry [`CommandContext::idempotency_key`]
+    /// idempotent: the first run's produced events are recorded under the
+    /// key for `ttl`, and a repeat returns them without running the command.
+    pub fn with_idempotency(mut self, store: Arc<dyn IdempotencyStore>, ttl: Duration) -> Self {
+        self.idempotency = Some(Idempotency { store, ttl });
+        self
+    }
+
+    /// Append `middleware` to the stack run around every dispatch. `before`
+    /// hooks run in the order middleware was added, `after` / `on_error`
+    /// hooks in reverse.
+    pub fn with_middleware(mut self, middleware: Arc<dyn CommandMiddleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// The events already produced under `context`'s idempotency key, if a
+    /// dispatch with that key has completed. For handlers that run checks
+    /// before dispatching which a repeated request would fail. Having no
+    /// command to compare, this checks only the aggregate type, not the
+    /// fingerprint.
+    pub async fn replay(&self, context: &CommandContext) -> CommandBusResult<Option<Vec<Event>>> {
+        let (Some(idempotency), Some(key)) = (&self.idempotency, &context.idempotency_key) else {
+            return Ok(None);
+        };
+        match idempotency.lookup(context, key).await? {
+            Some(outcome) => Ok(Some(self.replay_checked(key, &outcome, None).await?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Replay `outcome` if the key was used for this aggregate type and, when
+    /// both sides have one, the same command `fingerprint`.
+    async fn replay_checked(
+        &self,
+        key: &str,
+        outcome: &IdempotentOutcome,
+        fingerprint: Option<&str>,
+    ) -> CommandBusResult<Vec<Event>> {
+        let fingerprint_differs = matches!(
+            (outcome.fingerprint.as_deref(), fingerprint),
+            (Some(recorded), Some(current)) if recorded != current
+        );
+        if outcome.aggregate_type != A::aggregate_type() || fingerprint_differs {
+            return Err(CommandBusError::IdempotencyKeyReused {
+                key: key.to_string(),
+                aggregate_type: outcome.aggregate_type.clone(),
+            });
+        }
+        replay_outcome(self.event_store.as_ref(), outcome).await
+    }
+
     /// Dispatch a command with its request-scoped [`CommandContext`].
     ///
     /// Steps: load → reconstruct → handle → **stamp audit** → append → publish.
     /// The aggregate's `handle()` returns events with placeholder audit; this
     /// method overwrites it with a single validated [`AuditMetadata`] per
     /// dispatch (all events from one command share the same audit stamp).
+    ///
+    /// If the append fails with a concurrency conflict and both the
+    /// [`RetryPolicy`] and the command allow it, the whole sequence runs
+    /// again against the freshly loaded aggregate.
+    ///
+    /// With [`with_idempotency`](Self::with_idempotency) and a context that
+    /// carries a key, a key that already completed returns the events it
+    /// produced then, and a key still being processed fails with
+    /// [`CommandBusError::IdempotencyInFlight`]. A completed key whose
+    /// command had a different
+    /// [`idempotency_fingerprint`](Command::idempotency_fingerprint) fails
+    /// with [`CommandBusError::IdempotencyKeyReused`].
+    ///
+    /// Middleware added with [`with_middleware`](Self::with_middleware) runs
+    /// around everything but an idempotent replay.
     pub async fn dispatch(
         &self,
         command: A::Command,
         context: CommandContext,
-    ) -> CommandBusResult<Vec<Event>> {
+    ) -> CommandBusResult<Vec<Event>>
+    where
+        A::Command: 'static,
+    {
+        let (Some(idempotency), Some(key)) = (&self.idempotency, &context.idempotency_key) else {
+            return self.dispatch_with_middleware(command, context).await;
+        };
+        let fingerprint = fingerprint_of(&command);
+        match idempotency.begin(&context, key).await? {
+            IdempotencyStatus::Completed(outcome) => {
+                tracing::info!(key, aggregate_id = %outcome.aggregate_id, "Replaying idempotent command");
+                return self
+                    .replay_checked(key, &outcome, fingerprint.as_deref())
+                    .await;
+            }
+            IdempotencyStatus::InFlight => {
+                return Err(CommandBusError::IdempotencyInFlight { key: key.clone() });
+            }
+            IdempotencyStatus::Started => {}
+        }
         let aggregate_id = command.aggregate_id().to_string();
+        let result = self
+            .dispatch_with_middleware(command, context.clone())
+            .await;
+        idempotency
+            .finish(
+                &context,
+                key,
+                A::aggregate_type(),
+                &aggregate_id,
+                fingerprint,
+                &result,
+            )
+            .await;
+        result
+    }
 
-        // Step 1: Load existing events
-        let events = self
-            .event_store
-            .load(&aggregate_id)
-            .await
-            .map_err(|source| CommandBusError::LoadFailed {
-                aggregate_id: aggregate_id.clone(),
-                source,
-            })?;
+    /// [`dispatch_with_retries`](Self::dispatch_with_retries) inside the
+    /// middleware stack.
+    async fn dispatch_with_middleware(
+        &self,
+        command: A::Command,
+        mut context: CommandContext,
+    ) -> CommandBusResult<Vec<Event>>
+    where
+        A::Command: 'static,
+    {
+        if self.middleware.is_empty() {
+            return self.dispatch_with_retries(command, &context).await;
+        }
+        let aggregate_id = command.aggregate_id().to_string();
+        let action = command.action();
+        self.middleware
+            .before(
+                &CommandEnvelope::new(A::aggregate_type(), &command),
+                &mut context,
+            )
+            .await?;
+        let result = self.dispatch_with_retries(command, &context).await;
+        let envelope =
+            CommandEnvelope::consumed::<A::Command>(A::aggregate_type(), &aggregate_id, action);
+        self.middleware.finish(&envelope, &context, &result).await;
+        result
+    }
 
-        let current_version = events.last().map(|e| e.sequence).unwrap_or(0);
+    /// [`dispatch_once`](Self::dispatch_once) under the retry policy.
+    async fn dispatch_with_retries(
+        &self,
+        command: A::Command,
+        context: &CommandContext,
+    ) -> CommandBusResult<Vec<Event>> {
+        let mut command = command;
+        let mut retries = 0;
+        loop {
+            let retry = if retries + 1 < self.retry_policy.max_attempts {
+                command.retry_on_conflict()
+            } else {
+                None
+            };
+            let result = self.dispatch_once(command, context).await;
+            let conflicted = matches!(
+                result,
+                Err(CommandBusError::AppendFailed {
+                    source: EventStoreError::ConcurrencyConflict { .. },
+                    ..
+                })
+            );
+            match retry {
+                Some(next) if conflicted => {
+                    retries += 1;
+                    self.retry_metrics.retries.fetch_add(1, Ordering::Relaxed);
+                    let delay = self.retry_policy.backoff(retries);
+                    tracing::info!(
+                        aggregate_id = next.aggregate_id(),
+                        retry = retries,
+                        delay_ms = delay.as_millis() as u64,
+                        "Concurrency conflict; retrying command"
+                    );
+                    tokio::time::sleep(delay).await;
+                    command = next;
+                }
+                _ => {
+                    if retries > 0 && result.is_ok() {
+                        self.retry_metrics.recovered.fetch_add(1, Ordering::Relaxed);
+                    } else if retries > 0 && conflicted {
+                        self.retry_metrics.exhausted.fetch_add(1, Ordering::Relaxed);
+                        let stats = self.retry_metrics.stats();
+                        tracing::warn!(
+                            retries = stats.retries,
+                            recovered = stats.recovered,
+                            exhausted = stats.exhausted,
+                            "Concurrency conflict persisted after every retry"
+                        );
+                    }
+                    return result;
+                }
+            }
+        }
+    }
+
+    /// One load → handle → append → publish pass of [`dispatch`](Self::dispatch).
+    async fn dispatch_once(
+        &self,
+        command: A::Command,
+        context: &CommandContext,
+    ) -> CommandBusResult<Vec<Event>> {
+        let aggregate_id = command.aggregate_id().to_string();
 
-        // Step 2: Reconstruct
-        let aggregate = A::from_events(events);
+        // Steps 1–2: Load and reconstruct
+        let (mut aggregate, current_version, snapshot_version) =
+            self.load_aggregate(&aggregate_id).await?;
 
         // Step 3: Handle
         let new_events = aggregate
@@ -243,8 +855,11 @@ impl<A: Aggregate> CommandBus<A> {
             VersionCheck::Expected(current_version)
         };
 
-        self.event_store
-            .append(&aggregate_id, version_check, new_events.clone())
+        // Stored events carry their `GlobalPosition`, which projections
+        // record as their checkpoint.
+        let new_events = self
+            .event_store
+            .append_returning(&aggregate_id, version_check, new_events)
             .await
             .map_err(|source| CommandBusError::AppendFailed {
                 aggregate_id: aggregate_id.clone(),
@@ -252,17 +867,144 @@ impl<A: Aggregate> CommandBus<A> {
             })?;
 
         // Step 6: Publish
-        self.event_bus
-            .publish(new_events.clone())
-            .await
-            .map_err(|source| CommandBusError::PublishFailed {
-                aggregate_id: aggregate_id.clone(),
-                source,
-            })?;
+        self.publish(&aggregate_id, &new_events).await?;
+
+        // Step 7: Snapshot (best-effort)
+        let new_version = new_events
+            .last()
+            .map(|e| e.sequence)
+            .unwrap_or(current_version);
+        if self
+            .snapshot_policy
+            .should_snapshot(snapshot_version, new_version)
+        {
+            for event in &new_events {
+                aggregate.apply(event);
+            }
+            self.save_snapshot(&aggregate_id, &aggregate).await;
+        }
 
         Ok(new_events)
     }
 
+    /// Publish committed events. With an outbox, a failure is left for the
+    /// relay and marking delivered is best-effort: a missed mark only costs
+    /// a duplicate delivery.
+    async fn publish(&self, aggregate_id: &str, events: &[Event]) -> CommandBusResult<()> {
+        let result = self.event_bus.publish(events.to_vec()).await;
+        let Some(outbox) = &self.outbox else {
+            return result.map_err(|source| CommandBusError::PublishFailed {
+                aggregate_id: aggregate_id.to_string(),
+                source,
+            });
+        };
+        match result {
+            Ok(()) => {
+                let positions: Vec<_> = events.iter().filter_map(|e| e.position).collect();
+                if let Err(e) = outbox.mark_delivered(&positions).await {
+                    tracing::warn!(
+                        aggregate_id,
+                        error = %e,
+                        "Failed to mark events delivered; the relay will redeliver them"
+                    );
+                }
+            }
+            Err(e) => tracing::warn!(
+                aggregate_id,
+                error = %e,
+                "Publish failed; events stay in the outbox for the relay"
+            ),
+        }
+        Ok(())
+    }
+
+    /// Rebuild the aggregate, returning `(aggregate, current_version,
+    /// snapshot_version)`. `snapshot_version` is 0 when no snapshot was used.
+    async fn load_aggregate(&self, aggregate_id: &str) -> CommandBusResult<(A, i64, i64)> {
+        let load_failed = |source| CommandBusError::LoadFailed {
+            aggregate_id: aggregate_id.to_string(),
+            source,
+        };
+        let decode_failed = |e: DomainEventError| {
+            load_failed(EventStoreError::serialization(format!(
+                "stored event does not decode: {e}"
+            )))
+        };
+
+        if let Some(snapshot) = self.usable_snapshot(aggregate_id).await {
+            if let Some(mut aggregate) = A::from_snapshot(&snapshot.state) {
+                if aggregate.version() == snapshot.version {
+                    let tail = self
+                        .event_store
+                        .load_from(aggregate_id, snapshot.version + 1)
+                        .await
+                        .map_err(load_failed)?;
+                    let current_version =
+                        tail.last().map(|e| e.sequence).unwrap_or(snapshot.version);
+                    for event in &tail {
+                        aggregate.try_apply(event).map_err(decode_failed)?;
+                    }
+                    return Ok((aggregate, current_version, snapshot.version));
+                }
+            }
+            tracing::warn!(
+                aggregate_id,
+                version = snapshot.version,
+                "Discarding snapshot that does not restore; replaying from events"
+            );
+        }
+
+        let events = self
+            .event_store
+            .load(aggregate_id)
+            .await
+            .map_err(load_failed)?;
+        let current_version = events.last().map(|e| e.sequence).unwrap_or(0);
+        let mut aggregate = A::default();
+        for event in &events {
+            aggregate.try_apply(event).map_err(decode_failed)?;
+        }
+        Ok((aggregate, current_version, 0))
+    }
+
+    /// Latest snapshot if the policy is enabled and the snapshot matches this
+    /// aggregate's type and schema version. Store errors are logged, not
+    /// surfaced: a missing snapshot only costs a full replay.
+    async fn usable_snapshot(&self, aggregate_id: &str) -> Option<Snapshot> {
+        if !self.snapshot_policy.is_enabled() {
+            return None;
+        }
+        let snapshot = match self.event_store.load_snapshot(aggregate_id).await {
+            Ok(snapshot) => snapshot?,
+            Err(e) => {
+                tracing::warn!(aggregate_id, error = %e, "Failed to load snapshot");
+                return None;
+            }
+        };
+        if snapshot.aggregate_type != A::aggregate_type()
+            || snapshot.schema_version != A::snapshot_schema_version()
+        {
+            return None;
+        }
+        Some(snapshot)
+    }
+
+    async fn save_snapshot(&self, aggregate_id: &str, aggregate: &A) {
+        let Some(state) = aggregate.to_snapshot() else {
+            return;
+        };
+        let snapshot = Snapshot::new(
+            A::aggregate_type(),
+            aggregate_id,
+            aggregate.version(),
+            A::snapshot_schema_version(),
+            state,
+        );
+        if let Err(e) = self.event_store.save_snapshot(&snapshot).await {
+            tracing::warn!(aggregate_id, error = %e, "Failed to save snapshot");
+        }
+    }
+
     pub fn event_store(&self) -> &dyn EventStore {
         self.event_store.as_ref()
     }
@@ -272,47 +1014,302 @@ impl<A: Aggregate> CommandBus<A> {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::aggregate::Aggregate;
-    use crate::event::Event;
-    use crate::event_bus::{EventHandler, InProcessEventBus};
-    use crate::event_store::{
-        EventStore, EventStoreError, EventStoreResult, InMemoryEventStore, VersionCheck,
-    };
-    use async_trait::async_trait;
-    use serde_json::json;
-    use std::sync::Arc;
-    use tokio::sync::Mutex as TokioMutex;
+/// Type-erased dispatch of one command type, implemented by every
+/// `CommandBus<A>` for `A::Command`.
+#[async_trait]
+trait Route<C>: Send + Sync {
+    async fn dispatch(&self, command: C, context: CommandContext) -> CommandBusResult<Vec<Event>>;
+}
 
-    #[derive(Debug, Clone, PartialEq)]
-    struct CounterCommand {
-        id: String,
-        increment: i64,
+#[async_trait]
+impl<A> Route<A::Command> for CommandBus<A>
+where
+    A: Aggregate + 'static,
+    A::Command: 'static,
+{
+    async fn dispatch(
+        &self,
+        command: A::Command,
+        context: CommandContext,
+    ) -> CommandBusResult<Vec<Event>> {
+        CommandBus::dispatch(self, command, context).await
     }
+}
 
-    impl Command for CounterCommand {
-        fn aggregate_id(&self) -> &str {
-            &self.id
+struct RegisteredRoute {
+    aggregate_type: &'static str,
+    /// A `Box<dyn Route<C>>` for the command type this entry is keyed by.
+    route: Box<dyn Any + Send + Sync>,
+}
+
+/// Dispatches commands for every registered aggregate over one shared
+/// `EventStore` and `EventBus`.
+///
+/// Each [`register`](CommandRouter::register)ed aggregate gets a
+/// [`CommandBus`] built from the router's store, bus, outbox and snapshot
+/// policy, keyed by its command type. [`dispatch`](CommandRouter::dispatch)
+/// picks the bus from the command's type, so one router serves every
+/// aggregate and can be shared as a single `web::Data<CommandRouter>`.
+///
+/// ```rust,ignore
+/// let router = CommandRouter::new(Box::new(store.clone()), Box::new(bus))
+///     .with_outbox(Arc::new(store))
+///     .register::<UserAggregate>()
+///     .register::<TaskAggregate>();
+///
+/// router.dispatch(UserCommand::DeleteUser { id }, ctx.clone()).await?;
+/// router.dispatch(TaskCommand::Close { id: task_id }, ctx).await?;
+/// ```
+pub struct CommandRouter {
+    event_store: Arc<dyn EventStore>,
+    event_bus: Arc<dyn EventBus>,
+    snapshot_policy: SnapshotPolicy,
+    retry_policy: RetryPolicy,
+    retry_metrics: Arc<RetryMetrics>,
+    idempotency: Option<Idempotency>,
+    middleware: MiddlewareStack,
+    outbox: Option<Arc<dyn Outbox>>,
+    routes: HashMap<TypeId, RegisteredRoute>,
+}
+
+impl CommandRouter {
+    pub fn new(event_store: Box<dyn EventStore>, event_bus: Box<dyn EventBus>) -> Self {
+        Self {
+            event_store: Arc::from(event_store),
+            event_bus: Arc::from(event_bus),
+            snapshot_policy: SnapshotPolicy::default(),
+            retry_policy: RetryPolicy::default(),
+            retry_metrics: Arc::default(),
+            idempotency: None,
+            middleware: MiddlewareStack::default(),
+            outbox: None,
+            routes: HashMap::new(),
         }
     }
 
-    #[derive(Debug, Clone, Default)]
-    struct CounterAggregate {
-        id: Option<String>,
-        value: i64,
-        version: i64,
+    /// See [`CommandBus::with_outbox`]. Must be called before any
+    /// aggregate is registered.
+    ///
+    /// # Panics
+    ///
+    /// If an aggregate has already been registered.
+    pub fn with_outbox(mut self, outbox: Arc<dyn Outbox>) -> Self {
+        self.assert_no_routes("with_outbox");
+        self.outbox = Some(outbox);
+        self
     }
 
-    #[derive(Debug, thiserror::Error)]
-    enum CounterError {
-        #[error("Negative increment not allowed")]
-        NegativeIncrement,
+    /// See [`CommandBus::with_snapshot_policy`]. Applies to every aggregate
+    /// and must be called before any aggregate is registered.
+    ///
+    /// # Panics
+    ///
+    /// If an aggregate has already been registered.
+    pub fn with_snapshot_policy(mut self, policy: SnapshotPolicy) -> Self {
+        self.assert_no_routes("with_snapshot_policy");
+        self.snapshot_policy = policy;
+        self
     }
 
-    #[async_trait]
-    impl Aggregate for CounterAggregate {
+    pub fn snapshot_policy(&self) -> SnapshotPolicy {
+        self.snapshot_policy
+    }
+
+    /// See [`CommandBus::with_retry_policy`]. Applies to every aggregate
+    /// and must be called before any aggregate is registered.
+    ///
+    /// # Panics
+    ///
+    /// If an aggregate has already been registered.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.assert_no_routes("with_retry_policy");
+        self.retry_policy = policy;
+        self
+    }
+
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy
+    }
+
+    /// Retries taken so far, summed over every registered aggregate.
+    pub fn retry_stats(&self) -> RetryStats {
+        self.retry_metrics.stats()
+    }
+
+    /// See [`CommandBus::with_idempotency`]. One store serves every
+    /// aggregate; must be called before any aggregate is registered.
+    ///
+    /// # Panics
+    ///
+    /// If an aggregate has already been registered.
+    pub fn with_idempotency(mut self, store: Arc<dyn IdempotencyStore>, ttl: Duration) -> Self {
+        self.assert_no_routes("with_idempotency");
+        self.idempotency = Some(Idempotency { store, ttl });
+        self
+    }
+
+    /// See [`CommandBus::with_middleware`]. The stack runs around commands
+    /// for every aggregate; must be called before any aggregate is
+    /// registered.
+    ///
+    /// # Panics
+    ///
+    /// If an aggregate has already been registered.
+    pub fn with_middleware(mut self, middleware: Arc<dyn CommandMiddleware>) -> Self {
+        self.assert_no_routes("with_middleware");
+        self.middleware.push(middleware);
+        self
+    }
+
+    /// See [`CommandBus::replay`].
+    pub async fn replay(&self, context: &CommandContext) -> CommandBusResult<Option<Vec<Event>>> {
+        let (Some(idempotency), Some(key)) = (&self.idempotency, &context.idempotency_key) else {
+            return Ok(None);
+        };
+        match idempotency.lookup(context, key).await? {
+            Some(outcome) => Ok(Some(
+                replay_outcome(self.event_store.as_ref(), &outcome).await?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Route `A::Command` to a [`CommandBus<A>`] over the shared store and bus.
+    ///
+    /// # Panics
+    ///
+    /// If another registered aggregate already handles `A::Command`: a
+    /// command type has exactly one owner.
+    pub fn register<A>(mut self) -> Self
+    where
+        A: Aggregate + 'static,
+        A::Command: 'static,
+    {
+        let mut bus =
+            CommandBus::<A>::from_shared(self.event_store.clone(), self.event_bus.clone())
+                .with_snapshot_policy(self.snapshot_policy)
+                .with_retry_policy(self.retry_policy);
+        bus.retry_metrics = self.retry_metrics.clone();
+        bus.idempotency = self.idempotency.clone();
+        bus.middleware = self.middleware.clone();
+        if let Some(outbox) = &self.outbox {
+            bus = bus.with_outbox(outbox.clone());
+        }
+        let route: Box<dyn Route<A::Command>> = Box::new(bus);
+        let registered = RegisteredRoute {
+            aggregate_type: A::aggregate_type(),
+            route: Box::new(route),
+        };
+        if let Some(existing) = self.routes.insert(TypeId::of::<A::Command>(), registered) {
+            panic!(
+                "Command '{}' is already routed to aggregate '{}'",
+                type_name::<A::Command>(),
+                existing.aggregate_type
+            );
+        }
+        self
+    }
+
+    /// Whether some registered aggregate handles commands of type `C`.
+    pub fn has_route<C: Command + 'static>(&self) -> bool {
+        self.routes.contains_key(&TypeId::of::<C>())
+    }
+
+    /// Aggregate types of every registered aggregate, sorted.
+    pub fn aggregate_types(&self) -> Vec<&'static str> {
+        let mut types: Vec<_> = self.routes.values().map(|r| r.aggregate_type).collect();
+        types.sort_unstable();
+        types
+    }
+
+    /// Dispatch `command` through the [`CommandBus`] of the aggregate that
+    /// registered its type. See [`CommandBus::dispatch`].
+    ///
+    /// Fails with [`CommandBusError::NoRoute`] if no aggregate handles `C`.
+    pub async fn dispatch<C: Command + 'static>(
+        &self,
+        command: C,
+        context: CommandContext,
+    ) -> CommandBusResult<Vec<Event>> {
+        let route = self
+            .routes
+            .get(&TypeId::of::<C>())
+            .and_then(|r| r.route.downcast_ref::<Box<dyn Route<C>>>())
+            .ok_or_else(|| CommandBusError::no_route(type_name::<C>()))?;
+        route.dispatch(command, context).await
+    }
+
+    pub fn event_store(&self) -> &dyn EventStore {
+        self.event_store.as_ref()
+    }
+
+    pub fn event_bus(&self) -> &dyn EventBus {
+        self.event_bus.as_ref()
+    }
+
+    fn assert_no_routes(&self, setter: &str) {
+        assert!(
+            self.routes.is_empty(),
+            "CommandRouter::{setter} must be called before registering aggregates ({:?})",
+            self.aggregate_types()
+        );
+    }
+}
+
+impl std::fmt::Debug for CommandRouter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CommandRouter")
+            .field("aggregate_types", &self.aggregate_types())
+            .field("snapshot_policy", &self.snapshot_policy)
+            .field("retry_policy", &self.retry_policy)
+            .field("idempotency", &self.idempotency.is_some())
+            .field("middleware", &self.middleware.names())
+            .field("outbox", &self.outbox.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::Aggregate;
+    use crate::event::Event;
+    use crate::event_bus::{EventHandler, InProcessEventBus};
+    use crate::event_store::{
+        EventStore, EventStoreError, EventStoreResult, InMemoryEventStore, VersionCheck,
+    };
+    use async_trait::async_trait;
+    use serde_json::json;
+    use std::sync::Arc;
+    use tokio::sync::Mutex as TokioMutex;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct CounterCommand {
+        id: String,
+        increment: i64,
+    }
+
+    impl Command for CounterCommand {
+        fn aggregate_id(&self) -> &str {
+            &self.id
+        }
+    }
+
+    #[derive(Debug, Clone, Default)]
+    struct CounterAggregate {
+        id: Option<String>,
+        value: i64,
+        version: i64,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    enum CounterError {
+        #[error("Negative increment not allowed")]
+        NegativeIncrement,
+    }
+
+    #[async_trait]
+    impl Aggregate for CounterAggregate {
         type Command = CounterCommand;
         type Event = ();
         type Error = CounterError;
@@ -345,6 +1342,18 @@ mod tests {
                 self.version = event.sequence;
             }
         }
+
+        fn to_snapshot(&self) -> Option<serde_json::Value> {
+            Some(json!({ "id": self.id, "value": self.value, "version": self.version }))
+        }
+
+        fn from_snapshot(state: &serde_json::Value) -> Option<Self> {
+            Some(Self {
+                id: state["id"].as_str().map(String::from),
+                value: state["value"].as_i64()?,
+                version: state["version"].as_i64()?,
+            })
+        }
     }
 
     fn ctx() -> CommandContext {
@@ -456,16 +1465,19 @@ mod tests {
             Box::new(InMemoryEventStore::new()),
             Box::new(event_bus),
         );
-        bus.dispatch(
-            CounterCommand {
-                id: "c1".into(),
-                increment: 5,
-            },
-            ctx(),
-        )
-        .await
-        .unwrap();
+        let events = bus
+            .dispatch(
+                CounterCommand {
+                    id: "c1".into(),
+                    increment: 5,
+                },
+                ctx(),
+            )
+            .await
+            .unwrap();
         assert_eq!(published.lock().await.len(), 1);
+        // Published events are the stored ones, positions included.
+        assert_eq!(events[0].position, Some(crate::event::GlobalPosition(1)));
     }
 
     #[tokio::test]
@@ -612,6 +1624,8 @@ mod tests {
             user_agent: Some("test-agent".into()),
             correlation_id: Uuid::new_v4(),
             causation_id: None,
+            idempotency_key: None,
+            roles: Vec::new(),
         };
         let corr = ctx.correlation_id;
         let events = bus
@@ -645,6 +1659,8 @@ mod tests {
             user_agent: None,
             correlation_id: Uuid::new_v4(),
             causation_id: None,
+            idempotency_key: None,
+            roles: Vec::new(),
         };
         let err = bus
             .dispatch(
@@ -740,6 +1756,153 @@ mod tests {
         assert_eq!(follow[0].audit.causation_id, Some(triggers[0].event_id));
     }
 
+    fn increment(id: &str, increment: i64) -> CounterCommand {
+        CounterCommand {
+            id: id.into(),
+            increment,
+        }
+    }
+
+    fn snapshotting_bus(store: &InMemoryEventStore, every: u64) -> CommandBus<CounterAggregate> {
+        CommandBus::<CounterAggregate>::new(
+            Box::new(store.clone()),
+            Box::new(InProcessEventBus::new()),
+        )
+        .with_snapshot_policy(SnapshotPolicy::EveryNEvents(every))
+    }
+
+    #[tokio::test]
+    async fn test_default_policy_never_writes_snapshots() {
+        let store = InMemoryEventStore::new();
+        let bus = CommandBus::<CounterAggregate>::new(
+            Box::new(store.clone()),
+            Box::new(InProcessEventBus::new()),
+        );
+        assert_eq!(bus.snapshot_policy(), SnapshotPolicy::Never);
+        for _ in 0..5 {
+            bus.dispatch(increment("c1", 1), ctx()).await.unwrap();
+        }
+        assert!(store.load_snapshot("c1").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_written_at_cadence() {
+        let store = InMemoryEventStore::new();
+        let bus = snapshotting_bus(&store, 3);
+
+        bus.dispatch(increment("c1", 1), ctx()).await.unwrap();
+        bus.dispatch(increment("c1", 2), ctx()).await.unwrap();
+        assert!(store.load_snapshot("c1").await.unwrap().is_none());
+
+        bus.dispatch(increment("c1", 4), ctx()).await.unwrap();
+        let snap = store.load_snapshot("c1").await.unwrap().unwrap();
+        assert_eq!(snap.aggregate_type, "Counter");
+        assert_eq!(snap.version, 3);
+        assert_eq!(snap.schema_version, 1);
+        assert_eq!(snap.state["value"], 7);
+
+        // Next boundary is measured from the stored snapshot.
+        bus.dispatch(increment("c1", 1), ctx()).await.unwrap();
+        bus.dispatch(increment("c1", 1), ctx()).await.unwrap();
+        assert_eq!(store.load_snapshot("c1").await.unwrap().unwrap().version, 3);
+        bus.dispatch(increment("c1", 1), ctx()).await.unwrap();
+        assert_eq!(store.load_snapshot("c1").await.unwrap().unwrap().version, 6);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_restores_from_snapshot_and_applies_tail() {
+        let store = InMemoryEventStore::new();
+        let bus = snapshotting_bus(&store, 1);
+        bus.dispatch(increment("c1", 1), ctx()).await.unwrap();
+        bus.dispatch(increment("c1", 1), ctx()).await.unwrap();
+
+        // Overwrite the snapshot with a deliberately divergent value. If the
+        // bus restores from it, the next snapshot carries the divergence.
+        store
+            .save_snapshot(&Snapshot::new(
+                "Counter",
+                "c1",
+                1,
+                1,
+                json!({ "id": "c1", "value": 100, "version": 1 }),
+            ))
+            .await
+            .unwrap();
+
+        let events = bus.dispatch(increment("c1", 5), ctx()).await.unwrap();
+        assert_eq!(events[0].sequence, 3);
+        let snap = store.load_snapshot("c1").await.unwrap().unwrap();
+        assert_eq!(snap.version, 3);
+        // 100 (snapshot @1) + 1 (tail event 2) + 5 (new event 3)
+        assert_eq!(snap.state["value"], 106);
+    }
+
+    #[tokio::test]
+    async fn test_dispatch_ignores_snapshot_with_other_schema_version() {
+        let store = InMemoryEventStore::new();
+        let bus = snapshotting_bus(&store, 1);
+        bus.dispatch(increment("c1", 2), ctx()).await.unwrap();
+
+        store
+            .save_snapshot(&Snapshot::new(
+                "Counter",
+                "c1",
+                1,
+                99,
+                json!({ "id": "c1", "value": 100, "version": 1 }),
+            ))
+            .await
+            .unwrap();
+
+        bus.dispatch(increment("c1", 3), ctx()).await.unwrap();
+        let snap = store.load_snapshot("c1").await.unwrap().unwrap();
+        assert_eq!(snap.schema_version, 1);
+        assert_eq!(snap.state["value"], 5);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_save_failure_does_not_fail_dispatch() {
+        struct FailingSnapshots(InMemoryEventStore);
+        #[async_trait]
+        impl EventStore for FailingSnapshots {
+            async fn append(
+                &self,
+                aggregate_id: &str,
+                version_check: VersionCheck,
+                events: Vec<Event>,
+            ) -> EventStoreResult<()> {
+                self.0.append(aggregate_id, version_check, events).await
+            }
+            async fn load(&self, id: &str) -> EventStoreResult<Vec<Event>> {
+                self.0.load(id).await
+            }
+            async fn load_from(&self, id: &str, from: i64) -> EventStoreResult<Vec<Event>> {
+                self.0.load_from(id, from).await
+            }
+            async fn stream_all(&self, from: i64) -> EventStoreResult<Vec<Event>> {
+                self.0.stream_all(from).await
+            }
+            async fn get_version(&self, id: &str) -> EventStoreResult<i64> {
+                self.0.get_version(id).await
+            }
+            async fn save_snapshot(&self, _: &Snapshot) -> EventStoreResult<()> {
+                Err(EventStoreError::database("snapshots unavailable"))
+            }
+            async fn load_snapshot(&self, _: &str) -> EventStoreResult<Option<Snapshot>> {
+                Err(EventStoreError::database("snapshots unavailable"))
+            }
+        }
+
+        let bus = CommandBus::<CounterAggregate>::new(
+            Box::new(FailingSnapshots(InMemoryEventStore::new())),
+            Box::new(InProcessEventBus::new()),
+        )
+        .with_snapshot_policy(SnapshotPolicy::EveryNEvents(1));
+        bus.dispatch(increment("c1", 1), ctx()).await.unwrap();
+        let events = bus.dispatch(increment("c1", 1), ctx()).await.unwrap();
+        assert_eq!(events[0].sequence, 2);
+    }
+
     #[test]
     fn test_error_messages() {
         let e = CommandBusError::handle_failed("user-123", "Invalid email");
@@ -747,4 +1910,767 @@ mod tests {
         assert!(e.to_string().contains("Invalid email"));
         assert!(CommandBusError::other("X").to_string().contains("X"));
     }
+    /// In-process bus whose only handler fails every `CounterIncremented`.
+    async fn failing_event_bus() -> InProcessEventBus {
+        struct Down;
+        #[async_trait]
+        impl EventHandler for Down {
+            fn handles(&self) -> Vec<String> {
+                vec!["CounterIncremented".to_string()]
+            }
+            async fn handle(
+                &self,
+                _event: &Event,
+            ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+                Err("projection store down".into())
+            }
+        }
+        let mut bus = InProcessEventBus::new();
+        bus.subscribe(Box::new(Down)).await.unwrap();
+        bus
+    }
+
+    #[tokio::test]
+    async fn test_publish_failure_without_outbox_is_an_error() {
+        let bus = CommandBus::<CounterAggregate>::new(
+            Box::new(InMemoryEventStore::new()),
+            Box::new(failing_event_bus().await),
+        );
+        let err = bus.dispatch(increment("c1", 1), ctx()).await.unwrap_err();
+        assert!(matches!(err, CommandBusError::PublishFailed { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_publish_failure_with_outbox_leaves_events_pending() {
+        use crate::outbox::Outbox;
+
+        let store = InMemoryEventStore::new().with_outbox();
+        let bus = CommandBus::<CounterAggregate>::new(
+            Box::new(store.clone()),
+            Box::new(failing_event_bus().await),
+        )
+        .with_outbox(Arc::new(store.clone()));
+
+        let events = bus.dispatch(increment("c1", 1), ctx()).await.unwrap();
+        assert_eq!(events.len(), 1);
+        let status = store.status().await.unwrap();
+        assert_eq!(status.pending, 1);
+        assert_eq!(status.oldest_pending, events[0].position);
+    }
+
+    #[tokio::test]
+    async fn test_successful_publish_marks_outbox_delivered() {
+        use crate::outbox::Outbox;
+
+        let store = InMemoryEventStore::new().with_outbox();
+        let bus = CommandBus::<CounterAggregate>::new(
+            Box::new(store.clone()),
+            Box::new(InProcessEventBus::new()),
+        )
+        .with_outbox(Arc::new(store.clone()));
+
+        bus.dispatch(increment("c1", 1), ctx()).await.unwrap();
+        bus.dispatch(increment("c1", 2), ctx()).await.unwrap();
+        let status = store.status().await.unwrap();
+        assert_eq!(status.pending, 0);
+        assert_eq!(status.delivered, 2);
+    }
+
+    #[derive(Debug, Clone)]
+    struct RenameNote {
+        id: String,
+        title: String,
+    }
+
+    /// Renames are last-writer-wins, so they are safe to retry.
+    impl Command for RenameNote {
+        fn aggregate_id(&self) -> &str {
+            &self.id
+        }
+
+        fn retry_on_conflict(&self) -> Option<Self> {
+            Some(self.clone())
+        }
+
+        fn idempotency_fingerprint(&self) -> Option<serde_json::Value> {
+            Some(json!({ "id": self.id, "title": self.title }))
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct NoteAggregate {
+        version: i64,
+    }
+
+    #[async_trait]
+    impl Aggregate for NoteAggregate {
+        type Command = RenameNote;
+        type Event = ();
+        type Error = CounterError;
+
+        fn aggregate_type() -> &'static str {
+            "Note"
+        }
+
+        fn version(&self) -> i64 {
+            self.version
+        }
+
+        async fn handle(&self, command: Self::Command) -> Result<Vec<Event>, Self::Error> {
+            Ok(vec![Event::new(
+                "Note",
+                &command.id,
+                self.version + 1,
+                "NoteRenamed",
+                json!({ "title": command.title }),
+            )])
+        }
+
+        fn apply(&mut self, event: &Event) {
+            self.version = event.sequence;
+        }
+    }
+
+    fn rename(id: &str, title: &str) -> RenameNote {
+        RenameNote {
+            id: id.into(),
+            title: title.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_router_dispatches_each_command_to_its_aggregate() {
+        let store = InMemoryEventStore::new();
+        let router =
+            CommandRouter::new(Box::new(store.clone()), Box::new(InProcessEventBus::new()))
+                .register::<CounterAggregate>()
+                .register::<NoteAggregate>();
+        assert_eq!(router.aggregate_types(), vec!["Counter", "Note"]);
+        assert!(router.has_route::<RenameNote>());
+
+        router.dispatch(increment("c1", 2), ctx()).await.unwrap();
+        let events = router.dispatch(rename("n1", "todo"), ctx()).await.unwrap();
+        assert_eq!(events[0].aggregate_type, "Note");
+        let events = router.dispatch(rename("n1", "done"), ctx()).await.unwrap();
+        assert_eq!(events[0].sequence, 2);
+
+        assert_eq!(store.load("c1").await.unwrap().len(), 1);
+        assert_eq!(store.load("n1").await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_router_rejects_unregistered_command() {
+        let router = CommandRouter::new(
+            Box::new(InMemoryEventStore::new()),
+            Box::new(InProcessEventBus::new()),
+        )
+        .register::<CounterAggregate>();
+        assert!(!router.has_route::<RenameNote>());
+
+        let err = router.dispatch(rename("n1", "x"), ctx()).await.unwrap_err();
+        assert!(matches!(err, CommandBusError::NoRoute { .. }));
+        assert!(err.to_string().contains("RenameNote"));
+    }
+
+    #[tokio::test]
+    async fn test_router_applies_shared_outbox_and_policy() {
+        use crate::outbox::Outbox;
+
+        let store = InMemoryEventStore::new().with_outbox();
+        let router =
+            CommandRouter::new(Box::new(store.clone()), Box::new(failing_event_bus().await))
+                .with_outbox(Arc::new(store.clone()))
+                .with_snapshot_policy(SnapshotPolicy::EveryNEvents(1))
+                .register::<CounterAggregate>()
+                .register::<NoteAggregate>();
+
+        router.dispatch(increment("c1", 1), ctx()).await.unwrap();
+        router.dispatch(rename("n1", "x"), ctx()).await.unwrap();
+        let status = store.status().await.unwrap();
+        assert_eq!(status.pending, 1);
+        assert_eq!(status.delivered, 1);
+        assert!(store.load_snapshot("c1").await.unwrap().is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "already routed to aggregate 'Counter'")]
+    fn test_router_rejects_duplicate_command_owner() {
+        #[derive(Debug, Default)]
+        struct OtherCounter(CounterAggregate);
+
+        #[async_trait]
+        impl Aggregate for OtherCounter {
+            type Command = CounterCommand;
+            type Event = ();
+            type Error = CounterError;
+
+            fn aggregate_type() -> &'static str {
+                "OtherCounter"
+            }
+
+            fn version(&self) -> i64 {
+                self.0.version
+            }
+
+            async fn handle(&self, command: Self::Command) -> Result<Vec<Event>, Self::Error> {
+                self.0.handle(command).await
+            }
+
+            fn apply(&mut self, event: &Event) {
+                self.0.apply(event)
+            }
+        }
+
+        let _ = CommandRouter::new(
+            Box::new(InMemoryEventStore::new()),
+            Box::new(InProcessEventBus::new()),
+        )
+        .register::<CounterAggregate>()
+        .register::<OtherCounter>();
+    }
+
+    #[test]
+    #[should_panic(expected = "before registering aggregates")]
+    fn test_router_setters_must_precede_register() {
+        let _ = CommandRouter::new(
+            Box::new(InMemoryEventStore::new()),
+            Box::new(InProcessEventBus::new()),
+        )
+        .register::<CounterAggregate>()
+        .with_snapshot_policy(SnapshotPolicy::EveryNEvents(5));
+    }
+
+    /// Store where another writer appends to the stream right before each of
+    /// the next `races` version-checked appends, so they conflict.
+    struct RacingStore {
+        inner: InMemoryEventStore,
+        races: std::sync::atomic::AtomicU32,
+    }
+
+    impl RacingStore {
+        fn new(inner: InMemoryEventStore, races: u32) -> Self {
+            Self {
+                inner,
+                races: races.into(),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl EventStore for RacingStore {
+        async fn append(
+            &self,
+            aggregate_id: &str,
+            version_check: VersionCheck,
+            events: Vec<Event>,
+        ) -> EventStoreResult<()> {
+            if let Some(expected) = version_check.version() {
+                let racing = self
+                    .races
+                    .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                    .is_ok();
+                if racing {
+                    let winner = Event::new(
+                        &events[0].aggregate_type,
+                        aggregate_id,
+                        expected + 1,
+                        &events[0].event_type,
+                        events[0].payload.clone(),
+                    )
+                    .with_audit(AuditMetadata::test_default());
+                    self.inner
+                        .append(aggregate_id, version_check, vec![winner])
+                        .await?;
+                }
+            }
+            self.inner.append(aggregate_id, version_check, events).await
+        }
+        async fn load(&self, aggregate_id: &str) -> EventStoreResult<Vec<Event>> {
+            self.inner.load(aggregate_id).await
+        }
+        async fn load_from(&self, aggregate_id: &str, from: i64) -> EventStoreResult<Vec<Event>> {
+            self.inner.load_from(aggregate_id, from).await
+        }
+        async fn stream_all(&self, from_position: i64) -> EventStoreResult<Vec<Event>> {
+            self.inner.stream_all(from_position).await
+        }
+        async fn get_version(&self, aggregate_id: &str) -> EventStoreResult<i64> {
+            self.inner.get_version(aggregate_id).await
+        }
+    }
+
+    fn fast_retries(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(2),
+        }
+    }
+
+    /// A racing store over a note that already has one event.
+    async fn racing_note_store(races: u32) -> (InMemoryEventStore, RacingStore) {
+        let inner = InMemoryEventStore::new();
+        CommandBus::<NoteAggregate>::new(
+            Box::new(inner.clone()),
+            Box::new(InProcessEventBus::new()),
+        )
+        .dispatch(rename("n1", "first"), ctx())
+        .await
+        .unwrap();
+        (inner.clone(), RacingStore::new(inner, races))
+    }
+
+    fn is_conflict(err: &CommandBusError) -> bool {
+        matches!(
+            err,
+            CommandBusError::AppendFailed {
+                source: EventStoreError::ConcurrencyConflict { .. },
+                ..
+            }
+        )
+    }
+
+    #[tokio::test]
+    async fn test_retryable_command_recovers_from_conflicts() {
+        let (inner, racing) = racing_note_store(2).await;
+        let bus =
+            CommandBus::<NoteAggregate>::new(Box::new(racing), Box::new(InProcessEventBus::new()))
+                .with_retry_policy(fast_retries(3));
+
+        let events = bus.dispatch(rename("n1", "mine"), ctx()).await.unwrap();
+        assert_eq!(events[0].sequence, 4);
+        assert_eq!(events[0].audit.actor_id, "test-actor");
+        assert_eq!(inner.load("n1").await.unwrap().len(), 4);
+        assert_eq!(
+            bus.retry_stats(),
+            RetryStats {
+                retries: 2,
+                recovered: 1,
+                exhausted: 0
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retries_stop_at_max_attempts() {
+        let (_, racing) = racing_note_store(5).await;
+        let bus =
+            CommandBus::<NoteAggregate>::new(Box::new(racing), Box::new(InProcessEventBus::new()))
+                .with_retry_policy(fast_retries(3));
+
+        let err = bus.dispatch(rename("n1", "mine"), ctx()).await.unwrap_err();
+        assert!(is_conflict(&err));
+        assert_eq!(bus.retry_stats().retries, 2);
+        assert_eq!(bus.retry_stats().exhausted, 1);
+    }
+
+    #[tokio::test]
+    async fn test_conflict_is_not_retried_without_opt_in() {
+        let inner = InMemoryEventStore::new();
+        let bus = CommandBus::<CounterAggregate>::new(
+            Box::new(RacingStore::new(inner.clone(), 0)),
+            Box::new(InProcessEventBus::new()),
+        )
+        .with_retry_policy(fast_retries(3));
+        bus.dispatch(increment("c1", 1), ctx()).await.unwrap();
+
+        let bus = CommandBus::<CounterAggregate>::new(
+            Box::new(RacingStore::new(inner, 1)),
+            Box::new(InProcessEventBus::new()),
+        )
+        .with_retry_policy(fast_retries(3));
+        let err = bus.dispatch(increment("c1", 1), ctx()).await.unwrap_err();
+        assert!(is_conflict(&err));
+        assert_eq!(bus.retry_stats(), RetryStats::default());
+    }
+
+    #[tokio::test]
+    async fn test_default_policy_never_retries() {
+        let (_, racing) = racing_note_store(1).await;
+        let bus =
+            CommandBus::<NoteAggregate>::new(Box::new(racing), Box::new(InProcessEventBus::new()));
+        assert!(!bus.retry_policy().is_enabled());
+
+        let err = bus.dispatch(rename("n1", "mine"), ctx()).await.unwrap_err();
+        assert!(is_conflict(&err));
+        assert_eq!(bus.retry_stats().retries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_router_shares_retry_stats_across_aggregates() {
+        let (_, racing) = racing_note_store(1).await;
+        let router = CommandRouter::new(Box::new(racing), Box::new(InProcessEventBus::new()))
+            .with_retry_policy(fast_retries(2))
+            .register::<CounterAggregate>()
+            .register::<NoteAggregate>();
+
+        router.dispatch(rename("n1", "mine"), ctx()).await.unwrap();
+        router.dispatch(increment("c1", 1), ctx()).await.unwrap();
+        assert_eq!(router.retry_stats().retries, 1);
+        assert_eq!(router.retry_stats().recovered, 1);
+    }
+
+    #[test]
+    fn test_retry_backoff_is_jittered_below_a_doubling_cap() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(350),
+        };
+        assert_eq!(policy.backoff_cap(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff_cap(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff_cap(3), Duration::from_millis(350));
+        assert_eq!(policy.backoff_cap(64), Duration::from_millis(350));
+        for retry in 1..=4 {
+            let delay = policy.backoff(retry);
+            let cap = policy.backoff_cap(retry);
+            assert!(
+                delay >= cap / 2 && delay <= cap,
+                "{delay:?} outside {cap:?}"
+            );
+        }
+        assert!(RetryPolicy::attempts(3).is_enabled());
+        assert!(!RetryPolicy::default().is_enabled());
+    }
+
+    mod idempotency {
+        use super::*;
+        use crate::idempotency::{IdempotencyStore, InMemoryIdempotencyStore};
+
+        const TTL: Duration = Duration::from_secs(60);
+
+        fn keyed(key: &str) -> CommandContext {
+            ctx().with_idempotency_key(key)
+        }
+
+        fn idempotent_bus(
+            store: &InMemoryEventStore,
+            keys: &InMemoryIdempotencyStore,
+        ) -> CommandBus<CounterAggregate> {
+            CommandBus::<CounterAggregate>::new(
+                Box::new(store.clone()),
+                Box::new(InProcessEventBus::new()),
+            )
+            .with_idempotency(Arc::new(keys.clone()), TTL)
+        }
+
+        #[tokio::test]
+        async fn test_repeated_key_returns_original_events() {
+            let store = InMemoryEventStore::new();
+            let bus = idempotent_bus(&store, &InMemoryIdempotencyStore::new());
+            assert_eq!(bus.replay(&keyed("k1")).await.unwrap(), None);
+
+            let first = bus.dispatch(increment("c1", 5), keyed("k1")).await.unwrap();
+            let again = bus.dispatch(increment("c1", 5), keyed("k1")).await.unwrap();
+            assert_eq!(again, first);
+            assert_eq!(store.load("c1").await.unwrap().len(), 1);
+            assert_eq!(bus.replay(&keyed("k1")).await.unwrap(), Some(first));
+
+            bus.dispatch(increment("c1", 5), keyed("k2")).await.unwrap();
+            bus.dispatch(increment("c1", 5), ctx()).await.unwrap();
+            assert_eq!(store.load("c1").await.unwrap().len(), 3);
+        }
+
+        #[tokio::test]
+        async fn test_key_in_flight_is_rejected() {
+            let keys = InMemoryIdempotencyStore::new();
+            let now = crate::audit::now_us();
+            keys.begin(IdempotencyRecord::reserve(
+                "test-actor",
+                "k1",
+                now,
+                60_000_000,
+            ))
+            .await
+            .unwrap();
+            let bus = idempotent_bus(&InMemoryEventStore::new(), &keys);
+
+            let err = bus
+                .dispatch(increment("c1", 1), keyed("k1"))
+                .await
+                .unwrap_err();
+            assert!(matches!(err, CommandBusError::IdempotencyInFlight { ref key } if key == "k1"));
+        }
+
+        #[tokio::test]
+        async fn test_failed_dispatch_releases_key() {
+            let store = InMemoryEventStore::new();
+            let bus = idempotent_bus(&store, &InMemoryIdempotencyStore::new());
+
+            let err = bus
+                .dispatch(increment("c1", -1), keyed("k1"))
+                .await
+                .unwrap_err();
+            assert!(matches!(err, CommandBusError::HandleFailed { .. }));
+            let events = bus.dispatch(increment("c1", 2), keyed("k1")).await.unwrap();
+            assert_eq!(events.len(), 1);
+        }
+
+        #[tokio::test]
+        async fn test_scoped_keys_replay_separately() {
+            let store = InMemoryEventStore::new();
+            let bus = idempotent_bus(&store, &InMemoryIdempotencyStore::new());
+            let request = keyed("k1");
+
+            for _ in 0..2 {
+                bus.dispatch(
+                    increment("c1", 1),
+                    request.clone().with_idempotency_scope("a"),
+                )
+                .await
+                .unwrap();
+                bus.dispatch(
+                    increment("c1", 1),
+                    request.clone().with_idempotency_scope("b"),
+                )
+                .await
+                .unwrap();
+            }
+            assert_eq!(store.load("c1").await.unwrap().len(), 2);
+            assert_eq!(ctx().with_idempotency_scope("a").idempotency_key, None);
+        }
+
+        #[tokio::test]
+        async fn test_key_reused_for_another_aggregate_is_rejected() {
+            let router = CommandRouter::new(
+                Box::new(InMemoryEventStore::new()),
+                Box::new(InProcessEventBus::new()),
+            )
+            .with_idempotency(Arc::new(InMemoryIdempotencyStore::new()), TTL)
+            .register::<CounterAggregate>()
+            .register::<NoteAggregate>();
+
+            let first = router
+                .dispatch(increment("c1", 1), keyed("k1"))
+                .await
+                .unwrap();
+            assert_eq!(router.replay(&keyed("k1")).await.unwrap(), Some(first));
+            let err = router
+                .dispatch(rename("n1", "x"), keyed("k1"))
+                .await
+                .unwrap_err();
+            assert!(matches!(
+                err,
+                CommandBusError::IdempotencyKeyReused { ref aggregate_type, .. } if aggregate_type == "Counter"
+            ));
+        }
+
+        #[tokio::test]
+        async fn test_key_reused_for_a_different_command_is_rejected() {
+            let store = InMemoryEventStore::new();
+            let bus = CommandBus::<NoteAggregate>::new(
+                Box::new(store.clone()),
+                Box::new(InProcessEventBus::new()),
+            )
+            .with_idempotency(Arc::new(InMemoryIdempotencyStore::new()), TTL);
+
+            let first = bus.dispatch(rename("n1", "a"), keyed("k1")).await.unwrap();
+            let err = bus
+                .dispatch(rename("n1", "b"), keyed("k1"))
+                .await
+                .unwrap_err();
+            assert!(matches!(
+                err,
+                CommandBusError::IdempotencyKeyReused { ref key, .. } if key == "k1"
+            ));
+            let again = bus.dispatch(rename("n1", "a"), keyed("k1")).await.unwrap();
+            assert_eq!(again, first);
+            assert_eq!(store.load("n1").await.unwrap().len(), 1);
+        }
+    }
+
+    mod middleware {
+        use super::*;
+        use crate::command_middleware::{CommandMiddleware, MetricsMiddleware};
+        use crate::idempotency::InMemoryIdempotencyStore;
+        use std::sync::Mutex as StdMutex;
+
+        /// Records every hook it sees; rejects increments above `limit`.
+        struct Recorder {
+            name: &'static str,
+            log: Arc<StdMutex<Vec<String>>>,
+            limit: Option<i64>,
+        }
+
+        impl Recorder {
+            fn new(name: &'static str, log: &Arc<StdMutex<Vec<String>>>) -> Self {
+                Self {
+                    name,
+                    log: log.clone(),
+                    limit: None,
+                }
+            }
+
+            fn rejecting_above(mut self, limit: i64) -> Self {
+                self.limit = Some(limit);
+                self
+            }
+
+            fn push(&self, hook: &str) {
+                self.log
+                    .lock()
+                    .unwrap()
+                    .push(format!("{}:{hook}", self.name));
+            }
+        }
+
+        #[async_trait]
+        impl CommandMiddleware for Recorder {
+            fn name(&self) -> &str {
+                self.name
+            }
+
+            async fn before(
+                &self,
+                command: &CommandEnvelope<'_>,
+                context: &mut CommandContext,
+            ) -> CommandBusResult<()> {
+                self.push("before");
+                context.session_id = Some(format!("via-{}", self.name));
+                let increment = command
+                    .downcast_ref::<CounterCommand>()
+                    .map_or(0, |c| c.increment);
+                match self.limit {
+                    Some(limit) if increment > limit => Err(CommandBusError::rejected(
+                        command.command_type,
+                        format!("increment above {limit}"),
+                    )),
+                    _ => Ok(()),
+                }
+            }
+
+            async fn after(
+                &self,
+                command: &CommandEnvelope<'_>,
+                _context: &CommandContext,
+                events: &[Event],
+            ) {
+                assert!(command.downcast_ref::<CounterCommand>().is_none());
+                self.push(&format!("after({})", events.len()));
+            }
+
+            async fn on_error(
+                &self,
+                _command: &CommandEnvelope<'_>,
+                _context: &CommandContext,
+                _error: &CommandBusError,
+            ) {
+                self.push("error");
+            }
+        }
+
+        fn log() -> Arc<StdMutex<Vec<String>>> {
+            Arc::new(StdMutex::new(Vec::new()))
+        }
+
+        fn entries(log: &Arc<StdMutex<Vec<String>>>) -> Vec<String> {
+            std::mem::take(&mut *log.lock().unwrap())
+        }
+
+        fn bus_with(
+            store: &InMemoryEventStore,
+            layers: Vec<Arc<dyn CommandMiddleware>>,
+        ) -> CommandBus<CounterAggregate> {
+            layers.into_iter().fold(
+                CommandBus::<CounterAggregate>::new(
+                    Box::new(store.clone()),
+                    Box::new(InProcessEventBus::new()),
+                ),
+                |bus, m| bus.with_middleware(m),
+            )
+        }
+
+        #[tokio::test]
+        async fn test_hooks_run_in_onion_order_and_can_enrich_context() {
+            let log = log();
+            let store = InMemoryEventStore::new();
+            let bus = bus_with(
+                &store,
+                vec![
+                    Arc::new(Recorder::new("a", &log)),
+                    Arc::new(Recorder::new("b", &log)),
+                ],
+            );
+
+            bus.dispatch(increment("c1", 1), ctx()).await.unwrap();
+            assert_eq!(
+                entries(&log),
+                ["a:before", "b:before", "b:after(1)", "a:after(1)"]
+            );
+            let events = store.load("c1").await.unwrap();
+            assert_eq!(events[0].audit.actor_session_id.as_deref(), Some("via-b"));
+        }
+
+        #[tokio::test]
+        async fn test_rejection_stops_dispatch_before_load() {
+            let log = log();
+            let store = InMemoryEventStore::new();
+            let bus = bus_with(
+                &store,
+                vec![
+                    Arc::new(Recorder::new("a", &log)),
+                    Arc::new(Recorder::new("b", &log).rejecting_above(10)),
+                    Arc::new(Recorder::new("c", &log)),
+                ],
+            );
+
+            let err = bus.dispatch(increment("c1", 11), ctx()).await.unwrap_err();
+            assert!(matches!(
+                err,
+                CommandBusError::Rejected { ref message, .. } if message == "increment above 10"
+            ));
+            assert_eq!(entries(&log), ["a:before", "b:before", "a:error"]);
+            assert!(store.load("c1").await.unwrap().is_empty());
+        }
+
+        #[tokio::test]
+        async fn test_handle_failure_reaches_on_error_and_metrics() {
+            let log = log();
+            let metrics = MetricsMiddleware::new();
+            let bus = bus_with(
+                &InMemoryEventStore::new(),
+                vec![
+                    Arc::new(metrics.clone()),
+                    Arc::new(Recorder::new("a", &log)),
+                ],
+            );
+
+            bus.dispatch(increment("c1", 1), ctx()).await.unwrap();
+            bus.dispatch(increment("c1", 2), ctx()).await.unwrap();
+            bus.dispatch(increment("c1", -1), ctx()).await.unwrap_err();
+            assert_eq!(entries(&log)[4..], ["a:before", "a:error"]);
+
+            let counts = metrics.snapshot()[type_name::<CounterCommand>()];
+            assert_eq!((counts.succeeded, counts.failed, counts.events), (2, 1, 2));
+        }
+
+        #[tokio::test]
+        async fn test_router_runs_stack_for_every_aggregate_but_not_replays() {
+            let log = log();
+            let router = CommandRouter::new(
+                Box::new(InMemoryEventStore::new()),
+                Box::new(InProcessEventBus::new()),
+            )
+            .with_idempotency(
+                Arc::new(InMemoryIdempotencyStore::new()),
+                Duration::from_secs(60),
+            )
+            .with_middleware(Arc::new(Recorder::new("a", &log)))
+            .register::<CounterAggregate>()
+            .register::<NoteAggregate>();
+
+            router.dispatch(rename("n1", "x"), ctx()).await.unwrap();
+            let keyed = ctx().with_idempotency_key("k1");
+            router
+                .dispatch(increment("c1", 1), keyed.clone())
+                .await
+                .unwrap();
+            router.dispatch(increment("c1", 1), keyed).await.unwrap();
+            assert_eq!(
+                entries(&log),
+                ["a:before", "a:after(1)", "a:before", "a:after(1)"]
+            );
+            assert!(format!("{router:?}").contains(r#"middleware: ["a"]"#));
+        }
+    }
 }
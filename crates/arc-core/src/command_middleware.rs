@@ -0,0 +1,317 @@
+//! # Command Middleware
+//!
+//! Hook points around [`CommandBus::dispatch`](crate::command_bus::CommandBus::dispatch)
+//! for cross-cutting concerns that would otherwise be re-implemented in front
+//! of every dispatch call: authorization, validation against read models,
+//! tracing, metrics, audit enrichment.
+//!
+//! ## Pipeline
+//!
+//! Middleware is stacked in registration order, onion-style:
+//!
+//! 1. [`before`](CommandMiddleware::before) runs first-to-last. It may adjust
+//!    the [`CommandContext`] or reject the command by returning an error; a
+//!    rejection stops the pipeline before the aggregate is loaded.
+//! 2. The bus runs load → handle → stamp → append → publish (with retries).
+//! 3. [`after`](CommandMiddleware::after) (on success) or
+//!    [`on_error`](CommandMiddleware::on_error) (on failure) runs
+//!    last-to-first, for every middleware whose `before` completed.
+//!
+//! `after` and `on_error` observe; they cannot change the result.
+//!
+//! A dispatch answered from an idempotency key replays the original result
+//! without entering the pipeline: the first run already passed it.
+//!
+//! ## Inspecting the command
+//!
+//! Middleware is not generic over the command type, so one stack serves every
+//! aggregate behind a [`CommandRouter`](crate::command_bus::CommandRouter).
+//! [`CommandEnvelope`] names the aggregate and command types and hands out
+//! the concrete command through [`CommandEnvelope::downcast_ref`] (in
+//! `before` only; by `after` the aggregate has consumed it):
+//!
+//! ```rust,ignore
+//! async fn before(&self, cmd: &CommandEnvelope<'_>, ctx: &mut CommandContext) -> CommandBusResult<()> {
+//!     if let Some(UserCommand::ChangeEmail { email, .. }) = cmd.downcast_ref::<UserCommand>() {
+//!         // ...
+//!     }
+//!     Ok(())
+//! }
+//! ```
+
+use crate::aggregate::Command;
+use crate::command_bus::{CommandBusError, CommandBusResult, CommandContext};
+use crate::event::Event;
+use async_trait::async_trait;
+use serde::Serialize;
+use std::any::{type_name, Any};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// A command on its way through the middleware pipeline.
+pub struct CommandEnvelope<'a> {
+    /// `Aggregate::aggregate_type()` of the aggregate handling the command.
+    pub aggregate_type: &'static str,
+    /// `Command::aggregate_id()`.
+    pub aggregate_id: &'a str,
+    /// Rust type name of the command, e.g. `my_app::UserCommand`.
+    pub command_type: &'static str,
+    /// `Command::action()`.
+    pub action: &'static str,
+    command: Option<&'a (dyn Any + Send + Sync)>,
+}
+
+impl<'a> CommandEnvelope<'a> {
+    pub fn new<C: Command + 'static>(aggregate_type: &'static str, command: &'a C) -> Self {
+        Self {
+            aggregate_type,
+            aggregate_id: command.aggregate_id(),
+            command_type: type_name::<C>(),
+            action: command.action(),
+            command: Some(command),
+        }
+    }
+
+    /// The envelope `after` and `on_error` see: the same names, but the
+    /// command itself has been consumed by `Aggregate::handle`.
+    pub(crate) fn consumed<C: 'static>(
+        aggregate_type: &'static str,
+        aggregate_id: &'a str,
+        action: &'static str,
+    ) -> Self {
+        Self {
+            aggregate_type,
+            aggregate_id,
+            command_type: type_name::<C>(),
+            action,
+            command: None,
+        }
+    }
+
+    /// The command as its concrete type, or `None` if it is another type.
+    /// Always `None` in [`after`](CommandMiddleware::after) and
+    /// [`on_error`](CommandMiddleware::on_error).
+    pub fn downcast_ref<C: 'static>(&self) -> Option<&'a C> {
+        self.command.and_then(|c| c.downcast_ref::<C>())
+    }
+}
+
+/// Interceptor around command dispatch. Every method defaults to a no-op,
+/// so an implementation overrides only the hooks it needs.
+#[async_trait]
+pub trait CommandMiddleware: Send + Sync {
+    /// Name for logs and `Debug` output.
+    fn name(&self) -> &str;
+
+    /// Runs before the aggregate is loaded. Return an error (typically
+    /// [`CommandBusError::rejected`]) to stop the dispatch.
+    async fn before(
+        &self,
+        _command: &CommandEnvelope<'_>,
+        _context: &mut CommandContext,
+    ) -> CommandBusResult<()> {
+        Ok(())
+    }
+
+    /// Runs after the events were appended and published.
+    async fn after(
+        &self,
+        _command: &CommandEnvelope<'_>,
+        _context: &CommandContext,
+        _events: &[Event],
+    ) {
+    }
+
+    /// Runs when the dispatch failed, including a rejection by a middleware
+    /// later in the stack.
+    async fn on_error(
+        &self,
+        _command: &CommandEnvelope<'_>,
+        _context: &CommandContext,
+        _error: &CommandBusError,
+    ) {
+    }
+}
+
+/// Ordered middleware stack shared by a bus or router.
+#[derive(Clone, Default)]
+pub(crate) struct MiddlewareStack {
+    layers: Vec<Arc<dyn CommandMiddleware>>,
+}
+
+impl MiddlewareStack {
+    pub(crate) fn push(&mut self, middleware: Arc<dyn CommandMiddleware>) {
+        self.layers.push(middleware);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    pub(crate) fn names(&self) -> Vec<&str> {
+        self.layers.iter().map(|m| m.name()).collect()
+    }
+
+    /// Run every `before`. On a rejection, the layers that already passed
+    /// see it through `on_error` and the error is returned.
+    pub(crate) async fn before(
+        &self,
+        command: &CommandEnvelope<'_>,
+        context: &mut CommandContext,
+    ) -> CommandBusResult<()> {
+        for (i, layer) in self.layers.iter().enumerate() {
+            if let Err(e) = layer.before(command, context).await {
+                tracing::info!(
+                    middleware = layer.name(),
+                    command_type = command.command_type,
+                    aggregate_id = command.aggregate_id,
+                    error = %e,
+                    "Command rejected by middleware"
+                );
+                for passed in self.layers[..i].iter().rev() {
+                    passed.on_error(command, context, &e).await;
+                }
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Run `after` or `on_error` for every layer, last to first.
+    pub(crate) async fn finish(
+        &self,
+        command: &CommandEnvelope<'_>,
+        context: &CommandContext,
+        result: &CommandBusResult<Vec<Event>>,
+    ) {
+        for layer in self.layers.iter().rev() {
+            match result {
+                Ok(events) => layer.after(command, context, events).await,
+                Err(e) => layer.on_error(command, context, e).await,
+            }
+        }
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Stock middleware
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Logs every dispatch: `debug` on entry, `info` with the event count on
+/// success, `warn` with the error on failure.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TracingMiddleware;
+
+#[async_trait]
+impl CommandMiddleware for TracingMiddleware {
+    fn name(&self) -> &str {
+        "tracing"
+    }
+
+    async fn before(
+        &self,
+        command: &CommandEnvelope<'_>,
+        context: &mut CommandContext,
+    ) -> CommandBusResult<()> {
+        tracing::debug!(
+            aggregate_type = command.aggregate_type,
+            aggregate_id = command.aggregate_id,
+            command_type = command.command_type,
+            actor_id = %context.actor_id,
+            correlation_id = %context.correlation_id,
+            "Dispatching command"
+        );
+        Ok(())
+    }
+
+    async fn after(
+        &self,
+        command: &CommandEnvelope<'_>,
+        context: &CommandContext,
+        events: &[Event],
+    ) {
+        tracing::info!(
+            aggregate_type = command.aggregate_type,
+            aggregate_id = command.aggregate_id,
+            command_type = command.command_type,
+            correlation_id = %context.correlation_id,
+            events = events.len(),
+            "Command dispatched"
+        );
+    }
+
+    async fn on_error(
+        &self,
+        command: &CommandEnvelope<'_>,
+        context: &CommandContext,
+        error: &CommandBusError,
+    ) {
+        tracing::warn!(
+            aggregate_type = command.aggregate_type,
+            aggregate_id = command.aggregate_id,
+            command_type = command.command_type,
+            correlation_id = %context.correlation_id,
+            error = %error,
+            "Command failed"
+        );
+    }
+}
+
+/// Per-command-type dispatch counters reported by [`MetricsMiddleware`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CommandCounts {
+    pub succeeded: u64,
+    pub failed: u64,
+    /// Events produced by successful dispatches.
+    pub events: u64,
+}
+
+/// Counts dispatch outcomes per command type, for `/diag`-style endpoints.
+#[derive(Clone, Default)]
+pub struct MetricsMiddleware {
+    counts: Arc<Mutex<BTreeMap<&'static str, CommandCounts>>>,
+}
+
+impl MetricsMiddleware {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Counters so far, keyed by command type name.
+    pub fn snapshot(&self) -> BTreeMap<&'static str, CommandCounts> {
+        self.counts.lock().expect("metrics lock poisoned").clone()
+    }
+
+    fn record(&self, command_type: &'static str, update: impl FnOnce(&mut CommandCounts)) {
+        let mut counts = self.counts.lock().expect("metrics lock poisoned");
+        update(counts.entry(command_type).or_default());
+    }
+}
+
+#[async_trait]
+impl CommandMiddleware for MetricsMiddleware {
+    fn name(&self) -> &str {
+        "metrics"
+    }
+
+    async fn after(
+        &self,
+        command: &CommandEnvelope<'_>,
+        _context: &CommandContext,
+        events: &[Event],
+    ) {
+        self.record(command.command_type, |c| {
+            c.succeeded += 1;
+            c.events += events.len() as u64;
+        });
+    }
+
+    async fn on_error(
+        &self,
+        command: &CommandEnvelope<'_>,
+        _context: &CommandContext,
+        _error: &CommandBusError,
+    ) {
+        self.record(command.command_type, |c| c.failed += 1);
+    }
+}
@@ -0,0 +1,260 @@
+//! # Domain Event Module
+//!
+//! Typed views of [`Event`](crate::event::Event) payloads.
+//!
+//! Stores and buses move events around as `event_type` + untyped JSON
+//! `payload`. A [`DomainEvent`] is the enum an aggregate actually thinks in:
+//! one variant per event type, with the variant's fields as the payload.
+//! [`Event::from_domain`](crate::event::Event::from_domain) encodes a variant
+//! and [`Event::decode`](crate::event::Event::decode) decodes one back, so
+//! aggregates and projectors match on variants instead of picking fields out
+//! of JSON, and a payload that doesn't fit its type is a [`DomainEventError`]
+//! instead of a panic or a silently missing field.
+//!
+//! ## Wire shape
+//!
+//! The default codec relies on serde's internally tagged representation with
+//! the tag named [`EVENT_TYPE_TAG`]. The variant name is the `event_type`;
+//! the tag itself is not stored in the payload, so a variant's payload is
+//! exactly its fields:
+//!
+//! ```rust
+//! use arc_core::domain_event::DomainEvent;
+//! use arc_core::event::Event;
+//! use serde::{Deserialize, Serialize};
+//!
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! #[serde(tag = "type")]
+//! enum AccountEvent {
+//!     Opened { owner: String },
+//!     Closed,
+//! }
+//!
+//! impl DomainEvent for AccountEvent {
+//!     const EVENT_TYPES: &'static [&'static str] = &["Opened", "Closed"];
+//!
+//!     fn event_type(&self) -> &'static str {
+//!         match self {
+//!             AccountEvent::Opened { .. } => "Opened",
+//!             AccountEvent::Closed => "Closed",
+//!         }
+//!     }
+//! }
+//!
+//! let opened = AccountEvent::Opened { owner: "alice".into() };
+//! let event = Event::from_domain("Account", "acc-1", 1, &opened).unwrap();
+//! assert_eq!(event.event_type, "Opened");
+//! assert_eq!(event.payload, serde_json::json!({ "owner": "alice" }));
+//! assert_eq!(event.decode::<AccountEvent>().unwrap(), opened);
+//! ```
+//!
+//! Enums with a different wire shape override
+//! [`to_payload`](DomainEvent::to_payload) and
+//! [`from_payload`](DomainEvent::from_payload).
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+
+/// Serde tag field the default codec expects (`#[serde(tag = "type")]`).
+pub const EVENT_TYPE_TAG: &str = "type";
+
+/// Errors encoding or decoding a [`DomainEvent`].
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum DomainEventError {
+    /// The event's `event_type` is not one of the target's `EVENT_TYPES`.
+    #[error("Unknown event type '{event_type}' for {target}")]
+    UnknownEventType {
+        event_type: String,
+        target: &'static str,
+    },
+
+    /// The event type is known but its payload does not fit the variant.
+    #[error("Invalid '{event_type}' payload: {message}")]
+    InvalidPayload { event_type: String, message: String },
+
+    /// A variant could not be turned into a payload object.
+    #[error("Failed to encode '{event_type}': {message}")]
+    EncodeFailed { event_type: String, message: String },
+}
+
+impl DomainEventError {
+    pub fn unknown_event_type(event_type: impl Into<String>, target: &'static str) -> Self {
+        DomainEventError::UnknownEventType {
+            event_type: event_type.into(),
+            target,
+        }
+    }
+
+    pub fn invalid_payload(event_type: impl Into<String>, message: impl Into<String>) -> Self {
+        DomainEventError::InvalidPayload {
+            event_type: event_type.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn encode_failed(event_type: impl Into<String>, message: impl Into<String>) -> Self {
+        DomainEventError::EncodeFailed {
+            event_type: event_type.into(),
+            message: message.into(),
+        }
+    }
+
+    /// `true` for [`UnknownEventType`](DomainEventError::UnknownEventType):
+    /// the event belongs to someone else, as opposed to being malformed.
+    pub fn is_unknown_event_type(&self) -> bool {
+        matches!(self, DomainEventError::UnknownEventType { .. })
+    }
+}
+
+/// A typed domain event: an enum whose variants are the event types of one
+/// aggregate.
+///
+/// Implementors list their event type names in
+/// [`EVENT_TYPES`](DomainEvent::EVENT_TYPES) and name each variant in
+/// [`event_type`](DomainEvent::event_type). The payload codec defaults to
+/// serde; see the [module docs](self) for the expected representation.
+pub trait DomainEvent: Serialize + DeserializeOwned + Send + Sync {
+    /// Every `event_type` this enum decodes. Projectors can return these
+    /// from `Projector::handles`.
+    const EVENT_TYPES: &'static [&'static str];
+
+    /// The `event_type` stored for this variant (convention: past tense,
+    /// PascalCase).
+    fn event_type(&self) -> &'static str;
+
+    /// Version of this variant's payload shape, stored as
+    /// `Event::schema_version`. Starts at 1; bump it when a field is renamed,
+    /// removed or changes meaning, and register an
+    /// [`Upcaster`](crate::event::Upcaster) from the previous version.
+    fn schema_version(&self) -> u32 {
+        1
+    }
+
+    /// Encode the variant's fields as a JSON object, without the tag.
+    fn to_payload(&self) -> Result<Value, DomainEventError> {
+        let event_type = self.event_type();
+        let value = serde_json::to_value(self)
+            .map_err(|e| DomainEventError::encode_failed(event_type, e.to_string()))?;
+        let Value::Object(mut fields) = value else {
+            return Err(DomainEventError::encode_failed(
+                event_type,
+                format!("expected a JSON object tagged by '{EVENT_TYPE_TAG}', got {value}"),
+            ));
+        };
+        match fields.remove(EVENT_TYPE_TAG) {
+            Some(Value::String(tag)) if tag == event_type => Ok(Value::Object(fields)),
+            tag => Err(DomainEventError::encode_failed(
+                event_type,
+                format!("serde tag '{EVENT_TYPE_TAG}' is {tag:?}, expected \"{event_type}\""),
+            )),
+        }
+    }
+
+    /// Decode `payload` as the variant named `event_type`.
+    fn from_payload(event_type: &str, payload: &Value) -> Result<Self, DomainEventError> {
+        if !Self::EVENT_TYPES.contains(&event_type) {
+            return Err(DomainEventError::unknown_event_type(
+                event_type,
+                std::any::type_name::<Self>(),
+            ));
+        }
+        let Value::Object(fields) = payload else {
+            return Err(DomainEventError::invalid_payload(
+                event_type,
+                format!("expected a JSON object, got {payload}"),
+            ));
+        };
+        let mut tagged = fields.clone();
+        tagged.insert(EVENT_TYPE_TAG.to_string(), Value::from(event_type));
+        serde_json::from_value(Value::Object(tagged))
+            .map_err(|e| DomainEventError::invalid_payload(event_type, e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use serde_json::json;
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(tag = "type")]
+    enum CounterEvent {
+        Created { id: String },
+        Incremented { amount: i32 },
+        Reset,
+    }
+
+    impl DomainEvent for CounterEvent {
+        const EVENT_TYPES: &'static [&'static str] = &["Created", "Incremented", "Reset"];
+
+        fn event_type(&self) -> &'static str {
+            match self {
+                CounterEvent::Created { .. } => "Created",
+                CounterEvent::Incremented { .. } => "Incremented",
+                CounterEvent::Reset => "Reset",
+            }
+        }
+    }
+
+    #[test]
+    fn test_payload_roundtrip_omits_tag() {
+        for event in [
+            CounterEvent::Created { id: "c1".into() },
+            CounterEvent::Incremented { amount: 3 },
+            CounterEvent::Reset,
+        ] {
+            let payload = event.to_payload().unwrap();
+            assert!(payload.get(EVENT_TYPE_TAG).is_none());
+            let back = CounterEvent::from_payload(event.event_type(), &payload).unwrap();
+            assert_eq!(back, event);
+        }
+        assert_eq!(CounterEvent::Reset.to_payload().unwrap(), json!({}));
+        assert_eq!(CounterEvent::Reset.schema_version(), 1);
+    }
+
+    #[test]
+    fn test_unknown_event_type_is_distinguished() {
+        let err = CounterEvent::from_payload("Deleted", &json!({})).unwrap_err();
+        assert!(err.is_unknown_event_type());
+        assert!(err.to_string().contains("Deleted"));
+    }
+
+    #[test]
+    fn test_mismatched_payload_is_invalid() {
+        let err =
+            CounterEvent::from_payload("Incremented", &json!({ "amount": "three" })).unwrap_err();
+        assert!(matches!(
+            err,
+            DomainEventError::InvalidPayload { ref event_type, .. } if event_type == "Incremented"
+        ));
+
+        let err = CounterEvent::from_payload("Created", &json!({})).unwrap_err();
+        assert!(err.to_string().contains("id"));
+
+        let err = CounterEvent::from_payload("Reset", &json!([1, 2])).unwrap_err();
+        assert!(!err.is_unknown_event_type());
+    }
+
+    #[test]
+    fn test_wrong_event_type_name_fails_to_encode() {
+        #[derive(Debug, Serialize, Deserialize)]
+        #[serde(tag = "type")]
+        enum Misnamed {
+            Opened,
+        }
+
+        impl DomainEvent for Misnamed {
+            const EVENT_TYPES: &'static [&'static str] = &["AccountOpened"];
+
+            fn event_type(&self) -> &'static str {
+                "AccountOpened"
+            }
+        }
+
+        let err = Misnamed::Opened.to_payload().unwrap_err();
+        assert!(matches!(err, DomainEventError::EncodeFailed { .. }));
+    }
+}
@@ -17,14 +17,61 @@
 //! - **Immutable**: Once persisted, events cannot be changed.
 //! - **Serializable**: All events can be stored as JSON.
 //! - **Self-describing**: Events contain all metadata needed to understand them.
-//! - **Ordered**: Events have a sequence number within their aggregate.
+//! - **Ordered**: Events have a sequence number within their aggregate, and
+//!   once persisted a [`GlobalPosition`] in the store-wide log.
 //! - **Audited**: Every persisted event carries `who/when/where/why` audit data.
+//! - **Versioned**: Each event records the `schema_version` of its payload
+//!   shape. Old shapes are migrated on read by an [`UpcasterRegistry`], never
+//!   rewritten in the store.
+//!
+//! ## Upcasting
+//!
+//! Stored payloads are frozen. When a payload shape changes, bump the
+//! variant's [`DomainEvent::schema_version`] and register an [`Upcaster`]
+//! that rewrites the previous version into the new one. Upcasters are keyed
+//! by `(event_type, from_version)` and chain, so a version 1 event passes
+//! through the 1 → 2 and 2 → 3 upcasters on its way to version 3. Wrap the
+//! store in [`UpcastingEventStore`](crate::event_store::UpcastingEventStore)
+//! and aggregates and projectors only ever see the current shape.
 
 use crate::audit::AuditMetadata;
+use crate::domain_event::{DomainEvent, DomainEventError};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
 use uuid::Uuid;
 
+/// Position of an event in the store-wide log.
+///
+/// Assigned by the [`EventStore`](crate::event_store::EventStore) on append:
+/// strictly increasing in commit order, starting at 1, possibly with gaps.
+/// Use it as a resumable cursor for `stream_all_paged` / `stream_all_from`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct GlobalPosition(pub i64);
+
+impl GlobalPosition {
+    /// Cursor that reads from the very beginning of the log.
+    pub const START: GlobalPosition = GlobalPosition(0);
+
+    pub fn value(self) -> i64 {
+        self.0
+    }
+
+    /// Cursor for the event after this one (streams are `from`-inclusive).
+    pub fn next(self) -> Self {
+        GlobalPosition(self.0 + 1)
+    }
+}
+
+impl std::fmt::Display for GlobalPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 /// Core event type representing an immutable domain event.
 ///
 /// # Fields
@@ -35,8 +82,10 @@ use uuid::Uuid;
 /// - `sequence`: Sequential number within the aggregate stream (starts at 1)
 /// - `event_type`: Type of event (e.g., "UserRegistered")
 /// - `payload`: Event data as JSON (flexible, evolvable schema)
+/// - `schema_version`: Version of the payload shape (starts at 1)
 /// - `audit`: HIPAA audit metadata. `pending()` until the bus stamps it.
 /// - `timestamp`: When the event occurred (milliseconds since UNIX epoch)
+/// - `position`: Store-wide [`GlobalPosition`]; `None` until loaded from a store
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Event {
     /// Unique identifier for this event
@@ -58,6 +107,12 @@ pub struct Event {
     /// Event payload as JSON
     pub payload: serde_json::Value,
 
+    /// Version of `payload`'s shape for this `event_type`. 1 unless the
+    /// event was built from a [`DomainEvent`] with a later version, or
+    /// upcast on read.
+    #[serde(default = "initial_schema_version")]
+    pub schema_version: u32,
+
     /// HIPAA audit metadata. `AuditMetadata::pending()` until the
     /// `CommandBus` overwrites it before `append`. `EventStore::append`
     /// implementations call `audit.validate()` and reject pending values.
@@ -66,10 +121,16 @@ pub struct Event {
     /// Wall-clock timestamp (milliseconds since UNIX epoch). For HIPAA
     /// audit-quality time, use `audit.timestamp_utc_us` (microsecond precision).
     pub timestamp: u64,
+
+    /// Store-wide position. `None` on freshly created events; set by the
+    /// store on every load / stream. Not part of the integrity signature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<GlobalPosition>,
 }
 
 impl Event {
-    /// Create a new event with `audit = AuditMetadata::pending()`.
+    /// Create a new event with `audit = AuditMetadata::pending()` and
+    /// `schema_version` 1.
     ///
     /// Aggregates call this from `handle()` and the `CommandBus` overwrites the
     /// audit field with a request-scoped value before persisting. The
@@ -105,14 +166,49 @@ impl Event {
             sequence,
             event_type: event_type.into(),
             payload,
+            schema_version: initial_schema_version(),
             audit: AuditMetadata::pending(),
             timestamp: SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .expect("Time went backwards")
                 .as_millis() as u64,
+            position: None,
         }
     }
 
+    /// Create a pending-audit event from a typed [`DomainEvent`], taking
+    /// `event_type`, `payload` and `schema_version` from the variant.
+    ///
+    /// Fails only if the variant does not encode to a JSON object tagged
+    /// with its own `event_type()`, i.e. on a mistake in the enum itself.
+    pub fn from_domain<E: DomainEvent>(
+        aggregate_type: impl Into<String>,
+        aggregate_id: impl Into<String>,
+        sequence: i64,
+        event: &E,
+    ) -> Result<Self, DomainEventError> {
+        let mut stored = Self::new(
+            aggregate_type,
+            aggregate_id,
+            sequence,
+            event.event_type(),
+            event.to_payload()?,
+        );
+        stored.schema_version = event.schema_version();
+        Ok(stored)
+    }
+
+    /// Decode `event_type` + `payload` into a typed [`DomainEvent`]. Read
+    /// events through an upcasting store so `payload` is in the current
+    /// shape.
+    ///
+    /// Returns `DomainEventError::UnknownEventType` when `E` has no such
+    /// event type and `DomainEventError::InvalidPayload` when the payload
+    /// does not fit the variant.
+    pub fn decode<E: DomainEvent>(&self) -> Result<E, DomainEventError> {
+        E::from_payload(&self.event_type, &self.payload)
+    }
+
     /// Replace `audit` with a fully-stamped value. Used by `CommandBus`
     /// before calling `EventStore::append`.
     pub fn with_audit(mut self, audit: AuditMetadata) -> Self {
@@ -131,6 +227,117 @@ impl Event {
     }
 }
 
+fn initial_schema_version() -> u32 {
+    1
+}
+
+/// Rewrites one event type's payload from one schema version to the next.
+///
+/// Implemented for plain closures, so most upcasters are registered inline:
+///
+/// ```rust
+/// use arc_core::event::UpcasterRegistry;
+/// use serde_json::json;
+///
+/// // ProfileUpdated v1 `{ name }` -> v2 `{ first_name, last_name }`
+/// let upcasters = UpcasterRegistry::new().with_upcaster(
+///     "ProfileUpdated",
+///     1,
+///     |mut payload: serde_json::Value| {
+///         let name = payload["name"].take();
+///         let name = name.as_str().ok_or("name is not a string")?;
+///         let (first, last) = name.split_once(' ').unwrap_or((name, ""));
+///         Ok(json!({ "first_name": first, "last_name": last }))
+///     },
+/// );
+/// # assert!(!upcasters.is_empty());
+/// ```
+pub trait Upcaster: Send + Sync {
+    /// Turn a payload at `from_version` into one at `from_version + 1`.
+    /// An `Err` fails the read with [`UpcastError`].
+    fn upcast(&self, payload: serde_json::Value) -> Result<serde_json::Value, String>;
+}
+
+impl<F> Upcaster for F
+where
+    F: Fn(serde_json::Value) -> Result<serde_json::Value, String> + Send + Sync,
+{
+    fn upcast(&self, payload: serde_json::Value) -> Result<serde_json::Value, String> {
+        self(payload)
+    }
+}
+
+/// An upcaster rejected a stored payload.
+#[derive(Debug, Error, Clone, PartialEq)]
+#[error("Failed to upcast {event_type} v{from_version} (event_id: {event_id}): {message}")]
+pub struct UpcastError {
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub from_version: u32,
+    pub message: String,
+}
+
+/// Upcasters keyed by `(event_type, from_version)`.
+///
+/// Cheap to clone; registration happens once at startup.
+#[derive(Clone, Default)]
+pub struct UpcasterRegistry {
+    upcasters: HashMap<(String, u32), Arc<dyn Upcaster>>,
+}
+
+impl UpcasterRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `upcaster` for `event_type` payloads at `from_version`.
+    /// Registering the same key twice replaces the earlier upcaster.
+    pub fn with_upcaster(
+        mut self,
+        event_type: impl Into<String>,
+        from_version: u32,
+        upcaster: impl Upcaster + 'static,
+    ) -> Self {
+        self.upcasters
+            .insert((event_type.into(), from_version), Arc::new(upcaster));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.upcasters.is_empty()
+    }
+
+    /// Apply every upcaster that matches, in version order, until none is
+    /// registered for the event's `(event_type, schema_version)`. Events
+    /// already at the latest version are returned unchanged.
+    pub fn upcast(&self, mut event: Event) -> Result<Event, UpcastError> {
+        while let Some(upcaster) = self
+            .upcasters
+            .get(&(event.event_type.clone(), event.schema_version))
+        {
+            let payload = std::mem::take(&mut event.payload);
+            event.payload = upcaster.upcast(payload).map_err(|message| UpcastError {
+                event_id: event.event_id,
+                event_type: event.event_type.clone(),
+                from_version: event.schema_version,
+                message,
+            })?;
+            event.schema_version += 1;
+        }
+        Ok(event)
+    }
+}
+
+impl std::fmt::Debug for UpcasterRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut keys: Vec<_> = self.upcasters.keys().collect();
+        keys.sort();
+        f.debug_struct("UpcasterRegistry")
+            .field("upcasters", &keys)
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -200,4 +407,131 @@ mod tests {
         let event2 = Event::new("User", "user-1", 1, "UserCreated", json!({}));
         assert_ne!(event1.event_id, event2.event_id);
     }
+
+    #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+    #[serde(tag = "type")]
+    enum UserEvent {
+        UserCreated { name: String },
+        UserDeleted,
+    }
+
+    impl DomainEvent for UserEvent {
+        const EVENT_TYPES: &'static [&'static str] = &["UserCreated", "UserDeleted"];
+
+        fn event_type(&self) -> &'static str {
+            match self {
+                UserEvent::UserCreated { .. } => "UserCreated",
+                UserEvent::UserDeleted => "UserDeleted",
+            }
+        }
+
+        fn schema_version(&self) -> u32 {
+            match self {
+                UserEvent::UserCreated { .. } => 2,
+                UserEvent::UserDeleted => 1,
+            }
+        }
+    }
+
+    #[test]
+    fn test_from_domain_then_decode_roundtrips() {
+        let created = UserEvent::UserCreated { name: "Ann".into() };
+        let event = Event::from_domain("User", "user-1", 1, &created).unwrap();
+        assert_eq!(event.event_type, "UserCreated");
+        assert_eq!(event.payload, json!({ "name": "Ann" }));
+        assert_eq!(event.schema_version, 2);
+        assert!(event.audit.is_pending());
+        assert_eq!(event.decode::<UserEvent>().unwrap(), created);
+
+        let deleted = Event::from_domain("User", "user-1", 2, &UserEvent::UserDeleted).unwrap();
+        assert_eq!(deleted.payload, json!({}));
+        assert_eq!(deleted.schema_version, 1);
+        assert_eq!(
+            deleted.decode::<UserEvent>().unwrap(),
+            UserEvent::UserDeleted
+        );
+    }
+
+    #[test]
+    fn test_decode_reports_mismatches() {
+        let hand_built = Event::new("User", "user-1", 1, "UserCreated", json!({ "name": 7 }));
+        assert!(matches!(
+            hand_built.decode::<UserEvent>(),
+            Err(DomainEventError::InvalidPayload { .. })
+        ));
+
+        let foreign = Event::new("Order", "order-1", 1, "OrderPlaced", json!({}));
+        assert!(foreign
+            .decode::<UserEvent>()
+            .unwrap_err()
+            .is_unknown_event_type());
+    }
+
+    fn split_name(mut payload: serde_json::Value) -> Result<serde_json::Value, String> {
+        let name = payload["name"].take();
+        let name = name.as_str().ok_or("name is not a string")?;
+        let (first, last) = name.split_once(' ').unwrap_or((name, ""));
+        Ok(json!({ "first_name": first, "last_name": last }))
+    }
+
+    #[test]
+    fn test_upcasters_chain_to_latest_version() {
+        let upcasters = UpcasterRegistry::new()
+            .with_upcaster("ProfileUpdated", 1, split_name)
+            .with_upcaster("ProfileUpdated", 2, |mut payload: serde_json::Value| {
+                payload["display_name"] = payload["first_name"].clone();
+                Ok(payload)
+            });
+
+        let v1 = Event::new(
+            "User",
+            "u1",
+            2,
+            "ProfileUpdated",
+            json!({"name": "Ada Lovelace"}),
+        );
+        let upcast = upcasters.upcast(v1.clone()).unwrap();
+        assert_eq!(upcast.schema_version, 3);
+        assert_eq!(
+            upcast.payload,
+            json!({"first_name": "Ada", "last_name": "Lovelace", "display_name": "Ada"})
+        );
+        assert_eq!(upcast.event_id, v1.event_id);
+
+        // Already current, or not registered at all: untouched.
+        assert_eq!(upcasters.upcast(upcast.clone()).unwrap(), upcast);
+        let other = Event::new("User", "u1", 1, "UserDeleted", json!({}));
+        assert_eq!(upcasters.upcast(other.clone()).unwrap(), other);
+    }
+
+    #[test]
+    fn test_upcast_failure_names_the_event() {
+        let upcasters = UpcasterRegistry::new().with_upcaster("ProfileUpdated", 1, split_name);
+        let bad = Event::new("User", "u1", 2, "ProfileUpdated", json!({"name": 7}));
+        let err = upcasters.upcast(bad.clone()).unwrap_err();
+        assert_eq!(err.event_id, bad.event_id);
+        assert_eq!(err.from_version, 1);
+        assert!(err.to_string().contains("name is not a string"));
+    }
+
+    #[test]
+    fn test_schema_version_defaults_for_legacy_json() {
+        let mut value =
+            serde_json::to_value(Event::new("User", "u1", 1, "UserCreated", json!({}))).unwrap();
+        value.as_object_mut().unwrap().remove("schema_version");
+        let legacy: Event = serde_json::from_value(value).unwrap();
+        assert_eq!(legacy.schema_version, 1);
+    }
+
+    #[test]
+    fn test_position_unset_until_stored_and_skipped_in_json() {
+        let mut event = Event::new("User", "user-1", 1, "UserCreated", json!({}));
+        assert!(event.position.is_none());
+        assert!(!event.to_json().unwrap().contains("position"));
+
+        event.position = Some(GlobalPosition(42));
+        let back = Event::from_json(&event.to_json().unwrap()).unwrap();
+        assert_eq!(back.position, Some(GlobalPosition(42)));
+        assert_eq!(GlobalPosition(42).next(), GlobalPosition(43));
+    }
 }
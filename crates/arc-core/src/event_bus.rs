@@ -5,7 +5,10 @@
 //! ## Design Principles
 //!
 //! - **Decoupled**: Publishers don't know about subscribers
-//! - **Synchronous**: InProcessEventBus handles events synchronously in order
+//! - **Two tiers**: InProcessEventBus runs [`DeliveryMode::Inline`] handlers
+//!   synchronously in order (projectors); [`DeliveryMode::Background`]
+//!   handlers (email, webhooks) get their own bounded queue and retry loop
+//!   off the write path
 //! - **Type-safe**: Event handlers declare which event types they handle
 //! - **Extensible**: Multiple handlers can subscribe to the same events
 //!
@@ -57,8 +60,10 @@
 use crate::event::Event;
 use async_trait::async_trait;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
 
 /// Errors that can occur during event bus operations.
 #[derive(Debug, Error)]
@@ -183,13 +188,14 @@ pub trait EventHandler: Send + Sync {
     /// # Returns
     ///
     /// - `Ok(())` if the event was handled successfully
-    /// - `Err(...)` if handling failed (error will be propagated to publisher)
+    /// - `Err(...)` if handling failed
     ///
     /// # Error Handling
     ///
-    /// If an error is returned, it will stop event processing for subsequent
-    /// handlers. Consider logging errors and returning Ok(()) if you want
-    /// to allow other handlers to continue.
+    /// For an [`DeliveryMode::Inline`] handler, an error stops processing
+    /// for subsequent handlers and is propagated to the publisher. For a
+    /// [`DeliveryMode::Background`] handler, the event is retried with
+    /// backoff and the publisher never sees the error.
     async fn handle(&self, event: &Event) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
 }
 
@@ -277,20 +283,86 @@ pub trait EventBus: Send + Sync {
     async fn subscribe(&mut self, handler: Box<dyn EventHandler>) -> EventBusResult<()>;
 }
 
-/// In-process, synchronous event bus implementation.
+/// How an [`InProcessEventBus`] delivers events to a handler.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeliveryMode {
+    /// Awaited inside `publish`, in subscription order. A failure aborts
+    /// the publish and reaches the caller. For projectors that must be
+    /// current when the write returns.
+    #[default]
+    Inline,
+    /// Queued and handled by a dedicated background task, retried with
+    /// backoff. Failures are logged, never returned to the publisher. For
+    /// side effects (email, webhooks) that must not block or fail writes.
+    Background,
+}
+
+/// Tuning for the background tier of an [`InProcessEventBus`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackgroundConfig {
+    /// Events buffered per background handler. When a queue is full,
+    /// `publish` waits for room (backpressure) rather than dropping events.
+    pub queue_capacity: usize,
+    /// Attempts per event, including the first. The event is dropped with an
+    /// error log once they are exhausted.
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled after each failed attempt.
+    pub initial_backoff: Duration,
+    /// Upper bound for the retry delay.
+    pub max_backoff: Duration,
+}
+
+impl Default for BackgroundConfig {
+    fn default() -> Self {
+        Self {
+            queue_capacity: 1024,
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
+
+impl BackgroundConfig {
+    /// Delay after failed attempt number `attempt` (1-based).
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// A background handler's queue and the task draining it.
+struct BackgroundSubscription {
+    handles: Vec<String>,
+    sender: mpsc::Sender<Event>,
+    worker: JoinHandle<()>,
+}
+
+/// In-process event bus with an inline and a background handler tier.
 ///
-/// This is the default event bus implementation that delivers events
-/// synchronously to all registered handlers in the same process.
+/// Handlers registered through [`EventBus::subscribe`] (or
+/// [`subscribe_with`](Self::subscribe_with) and [`DeliveryMode::Inline`])
+/// are delivered synchronously to all registered handlers in the same
+/// process. [`DeliveryMode::Background`] handlers each get a bounded queue
+/// and a tokio task, so a slow or failing side effect is isolated from the
+/// publisher and from every other handler.
 ///
 /// # Thread Safety
 ///
-/// Uses Arc<Mutex<>> internally for thread-safe handler management.
+/// Uses Arc<Mutex<>> internally for thread-safe handler management. Clones
+/// share handlers and background queues.
 ///
 /// # Performance
 ///
-/// - Synchronous delivery means handlers block the publisher
-/// - Handlers are called sequentially in subscription order
-/// - For high-throughput scenarios, consider async/queue-based implementations
+/// - Inline delivery means handlers block the publisher
+/// - Inline handlers are called sequentially in subscription order
+/// - Background handlers are only enqueued after every inline handler has
+///   succeeded, so a publish that fails (and is later redelivered) does not
+///   reach them twice
 ///
 /// # Example
 ///
@@ -326,6 +398,8 @@ pub trait EventBus: Send + Sync {
 #[derive(Clone)]
 pub struct InProcessEventBus {
     handlers: Arc<Mutex<Vec<Box<dyn EventHandler>>>>,
+    background: Arc<Mutex<Vec<BackgroundSubscription>>>,
+    background_config: BackgroundConfig,
 }
 
 impl InProcessEventBus {
@@ -341,6 +415,54 @@ impl InProcessEventBus {
     pub fn new() -> Self {
         Self {
             handlers: Arc::new(Mutex::new(Vec::new())),
+            background: Arc::new(Mutex::new(Vec::new())),
+            background_config: BackgroundConfig::default(),
+        }
+    }
+
+    /// Queue size and retry policy for handlers subscribed afterwards with
+    /// [`DeliveryMode::Background`].
+    pub fn with_background_config(mut self, config: BackgroundConfig) -> Self {
+        self.background_config = config;
+        self
+    }
+
+    /// Subscribe `handler` in the given tier. Must be called from within a
+    /// tokio runtime when `mode` is [`DeliveryMode::Background`], since it
+    /// spawns the handler's worker task.
+    pub async fn subscribe_with(
+        &self,
+        handler: Box<dyn EventHandler>,
+        mode: DeliveryMode,
+    ) -> EventBusResult<()> {
+        match mode {
+            DeliveryMode::Inline => self.handlers.lock().await.push(handler),
+            DeliveryMode::Background => {
+                let config = self.background_config;
+                let (sender, receiver) = mpsc::channel(config.queue_capacity.max(1));
+                let handles = handler.handles();
+                let worker = tokio::spawn(run_background(handler.into(), receiver, config));
+                self.background.lock().await.push(BackgroundSubscription {
+                    handles,
+                    sender,
+                    worker,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Stop accepting background work, let every background handler finish
+    /// its queue (retries included), and wait for the workers to exit.
+    /// Inline handlers keep working; later background deliveries are
+    /// dropped because no subscription remains.
+    pub async fn shutdown(&self) {
+        let subscriptions = std::mem::take(&mut *self.background.lock().await);
+        for subscription in subscriptions {
+            drop(subscription.sender);
+            if let Err(e) = subscription.worker.await {
+                tracing::error!(error = %e, "Background event handler task panicked");
+            }
         }
     }
 
@@ -360,7 +482,46 @@ impl InProcessEventBus {
     /// # }
     /// ```
     pub async fn handler_count(&self) -> usize {
-        self.handlers.lock().await.len()
+        self.handlers.lock().await.len() + self.background.lock().await.len()
+    }
+}
+
+/// Drain one background handler's queue, retrying each event with backoff.
+async fn run_background(
+    handler: Arc<dyn EventHandler>,
+    mut receiver: mpsc::Receiver<Event>,
+    config: BackgroundConfig,
+) {
+    while let Some(event) = receiver.recv().await {
+        let mut attempt = 1;
+        loop {
+            match handler.handle(&event).await {
+                Ok(()) => break,
+                Err(e) if attempt < config.max_attempts.max(1) => {
+                    let delay = config.backoff(attempt);
+                    tracing::warn!(
+                        event_type = %event.event_type,
+                        event_id = %event.event_id,
+                        attempt,
+                        error = %e,
+                        "Background event handler failed; retrying in {:?}",
+                        delay
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                Err(e) => {
+                    tracing::error!(
+                        event_type = %event.event_type,
+                        event_id = %event.event_id,
+                        attempts = attempt,
+                        error = %e,
+                        "Background event handler gave up on event"
+                    );
+                    break;
+                }
+            }
+        }
     }
 }
 
@@ -392,14 +553,34 @@ impl EventBus for InProcessEventBus {
                 }
             }
         }
+        drop(handlers);
+
+        // Clone the senders out so a full queue never holds the lock.
+        let background: Vec<(Vec<String>, mpsc::Sender<Event>)> = self
+            .background
+            .lock()
+            .await
+            .iter()
+            .map(|b| (b.handles.clone(), b.sender.clone()))
+            .collect();
+        for event in &events {
+            for (handles, sender) in &background {
+                if handles.contains(&event.event_type) && sender.send(event.clone()).await.is_err()
+                {
+                    tracing::warn!(
+                        event_type = %event.event_type,
+                        event_id = %event.event_id,
+                        "Background event handler stopped; event not queued"
+                    );
+                }
+            }
+        }
 
         Ok(())
     }
 
     async fn subscribe(&mut self, handler: Box<dyn EventHandler>) -> EventBusResult<()> {
-        let mut handlers = self.handlers.lock().await;
-        handlers.push(handler);
-        Ok(())
+        self.subscribe_with(handler, DeliveryMode::Inline).await
     }
 }
 
@@ -734,4 +915,169 @@ mod tests {
         let error = EventBusError::subscription_failed("Handler invalid");
         assert!(error.to_string().contains("Handler invalid"));
     }
+
+    /// Fails the first `fail` calls, then counts successes.
+    struct FlakyHandler {
+        fail: std::sync::atomic::AtomicU32,
+        calls: Arc<std::sync::atomic::AtomicU32>,
+        handled: Arc<TokioMutex<usize>>,
+    }
+
+    impl FlakyHandler {
+        fn new(
+            fail: u32,
+        ) -> (
+            Self,
+            Arc<std::sync::atomic::AtomicU32>,
+            Arc<TokioMutex<usize>>,
+        ) {
+            let calls = Arc::new(std::sync::atomic::AtomicU32::new(0));
+            let handled = Arc::new(TokioMutex::new(0));
+            let handler = Self {
+                fail: std::sync::atomic::AtomicU32::new(fail),
+                calls: calls.clone(),
+                handled: handled.clone(),
+            };
+            (handler, calls, handled)
+        }
+    }
+
+    #[async_trait]
+    impl EventHandler for FlakyHandler {
+        fn handles(&self) -> Vec<String> {
+            vec!["UserCreated".to_string()]
+        }
+
+        async fn handle(
+            &self,
+            _event: &Event,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            use std::sync::atomic::Ordering;
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self
+                .fail
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                return Err("webhook down".into());
+            }
+            *self.handled.lock().await += 1;
+            Ok(())
+        }
+    }
+
+    fn fast_retries(max_attempts: u32) -> BackgroundConfig {
+        BackgroundConfig {
+            queue_capacity: 8,
+            max_attempts,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        let config = BackgroundConfig {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(350),
+            ..BackgroundConfig::default()
+        };
+        assert_eq!(config.backoff(1), Duration::from_millis(100));
+        assert_eq!(config.backoff(2), Duration::from_millis(200));
+        assert_eq!(config.backoff(3), Duration::from_millis(350));
+        assert_eq!(config.backoff(64), Duration::from_millis(350));
+    }
+
+    #[tokio::test]
+    async fn test_background_failure_does_not_fail_publish() {
+        let bus = InProcessEventBus::new().with_background_config(fast_retries(2));
+        let inline = CountingHandler::new(vec!["UserCreated".to_string()]);
+        let inline_count = inline.count.clone();
+        let (flaky, calls, handled) = FlakyHandler::new(u32::MAX);
+        bus.subscribe_with(Box::new(inline), DeliveryMode::Inline)
+            .await
+            .unwrap();
+        bus.subscribe_with(Box::new(flaky), DeliveryMode::Background)
+            .await
+            .unwrap();
+        assert_eq!(bus.handler_count().await, 2);
+
+        let event = Event::new("User", "user-1", 1, "UserCreated", json!({}));
+        bus.publish(vec![event]).await.unwrap();
+        assert_eq!(*inline_count.lock().await, 1);
+
+        bus.shutdown().await;
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert_eq!(*handled.lock().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_background_handler_retries_until_success() {
+        let bus = InProcessEventBus::new().with_background_config(fast_retries(5));
+        let (flaky, calls, handled) = FlakyHandler::new(2);
+        bus.subscribe_with(Box::new(flaky), DeliveryMode::Background)
+            .await
+            .unwrap();
+
+        let events = vec![
+            Event::new("User", "user-1", 1, "UserCreated", json!({})),
+            Event::new("User", "user-2", 1, "UserCreated", json!({})),
+        ];
+        bus.publish(events).await.unwrap();
+        bus.shutdown().await;
+
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 4);
+        assert_eq!(*handled.lock().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_background_handlers_are_isolated() {
+        let bus = InProcessEventBus::new().with_background_config(fast_retries(1));
+        let healthy = CountingHandler::new(vec!["UserCreated".to_string()]);
+        let healthy_count = healthy.count.clone();
+        bus.subscribe_with(
+            Box::new(FailingHandler {
+                fail_on: "UserCreated".to_string(),
+            }),
+            DeliveryMode::Background,
+        )
+        .await
+        .unwrap();
+        bus.subscribe_with(Box::new(healthy), DeliveryMode::Background)
+            .await
+            .unwrap();
+
+        for seq in 1..=3 {
+            let event = Event::new("User", "user-1", seq, "UserCreated", json!({}));
+            bus.publish(vec![event]).await.unwrap();
+        }
+        bus.shutdown().await;
+
+        assert_eq!(*healthy_count.lock().await, 3);
+        assert_eq!(bus.handler_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_inline_failure_skips_background_tier() {
+        let bus = InProcessEventBus::new();
+        let background = CountingHandler::new(vec!["UserCreated".to_string()]);
+        let background_count = background.count.clone();
+        bus.subscribe_with(
+            Box::new(FailingHandler {
+                fail_on: "UserCreated".to_string(),
+            }),
+            DeliveryMode::Inline,
+        )
+        .await
+        .unwrap();
+        bus.subscribe_with(Box::new(background), DeliveryMode::Background)
+            .await
+            .unwrap();
+
+        let event = Event::new("User", "user-1", 1, "UserCreated", json!({}));
+        assert!(bus.publish(vec![event]).await.is_err());
+        bus.shutdown().await;
+
+        assert_eq!(*background_count.lock().await, 0);
+    }
 }
@@ -6,10 +6,15 @@
 //!
 //! - **Append-only**: events can only be added, never modified or deleted
 //! - **Optimistic concurrency**: version-based conflict detection
-//! - **Stream-based**: events can be loaded by aggregate or streamed globally
+//! - **Stream-based**: events can be loaded by aggregate or streamed globally,
+//!   in bounded pages keyed by [`GlobalPosition`]
 //! - **Audited**: every event must carry valid [`AuditMetadata`](crate::audit::AuditMetadata)
 //!   when appended (HIPAA §164.312(b))
 //! - **Pluggable**: multiple implementations (SQLite, Postgres, in-memory)
+//! - **Snapshot cache**: stores may keep the latest [`Snapshot`] per aggregate
+//!   beside the stream; snapshots are never a substitute for events
+//! - **Upcast on read**: [`UpcastingEventStore`] wraps any store and migrates
+//!   old payload shapes as they are loaded; stored rows never change
 //!
 //! ## HIPAA defense-in-depth
 //!
@@ -18,9 +23,16 @@
 //! durable boundary — it must not trust upstream.
 
 use crate::audit::AuditError;
-use crate::event::Event;
+use crate::event::{Event, GlobalPosition, UpcastError, UpcasterRegistry};
+use crate::integrity::IntegrityError;
+use crate::outbox::{Outbox, OutboxEntry, OutboxStatus};
+use crate::snapshot::Snapshot;
 use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+use std::collections::HashSet;
+use std::sync::Arc;
 use thiserror::Error;
+use uuid::Uuid;
 
 /// Version check strategy for optimistic concurrency control.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -78,6 +90,21 @@ pub enum EventStoreError {
         source: AuditError,
     },
 
+    /// A verifying load recomputed the HMAC chain and it did not match
+    /// (HIPAA §164.312(c)(1)). Never retried; surfaces as tampering.
+    #[error("Integrity check failed: {source}")]
+    IntegrityViolation {
+        #[source]
+        source: IntegrityError,
+    },
+
+    /// A stored payload could not be migrated to its current schema.
+    #[error("Upcast failed: {source}")]
+    UpcastFailed {
+        #[source]
+        source: UpcastError,
+    },
+
     #[error("Database error: {message}")]
     DatabaseError { message: String },
 
@@ -110,6 +137,14 @@ impl EventStoreError {
         }
     }
 
+    pub fn integrity(source: IntegrityError) -> Self {
+        EventStoreError::IntegrityViolation { source }
+    }
+
+    pub fn upcast(source: UpcastError) -> Self {
+        EventStoreError::UpcastFailed { source }
+    }
+
     pub fn invalid_audit(
         aggregate_id: impl Into<String>,
         event_index: usize,
@@ -154,6 +189,31 @@ pub trait EventStore: Send + Sync {
         events: Vec<Event>,
     ) -> EventStoreResult<()>;
 
+    /// [`append`](Self::append), returning the persisted events with
+    /// `position` set so publishers can hand subscribers a resumable cursor.
+    ///
+    /// The default appends, then reads the batch back with `load_from`.
+    /// Stores that learn positions during the insert override it to skip
+    /// the extra read.
+    async fn append_returning(
+        &self,
+        aggregate_id: &str,
+        version_check: VersionCheck,
+        events: Vec<Event>,
+    ) -> EventStoreResult<Vec<Event>> {
+        let Some(first_sequence) = events.first().map(|e| e.sequence) else {
+            return Ok(Vec::new());
+        };
+        let ids: HashSet<Uuid> = events.iter().map(|e| e.event_id).collect();
+        self.append(aggregate_id, version_check, events).await?;
+        Ok(self
+            .load_from(aggregate_id, first_sequence)
+            .await?
+            .into_iter()
+            .filter(|e| ids.contains(&e.event_id))
+            .collect())
+    }
+
     async fn load(&self, aggregate_id: &str) -> EventStoreResult<Vec<Event>>;
 
     async fn load_from(
@@ -162,9 +222,216 @@ pub trait EventStore: Send + Sync {
         from_sequence: i64,
     ) -> EventStoreResult<Vec<Event>>;
 
+    /// Every event with position `>= from_position`, in position order.
+    ///
+    /// Materializes the whole tail in memory. Prefer [`stream_all_paged`] or
+    /// [`stream_all_from`] for anything that walks the full history.
+    ///
+    /// [`stream_all_paged`]: EventStore::stream_all_paged
+    /// [`stream_all_from`]: EventStore::stream_all_from
     async fn stream_all(&self, from_position: i64) -> EventStoreResult<Vec<Event>>;
 
+    /// At most `limit` events with position `>= from`, in position order.
+    /// Loaded events carry `position`, so the next page starts at
+    /// `last.position.next()`.
+    ///
+    /// The default delegates to `stream_all` and truncates, which is correct
+    /// but unbounded; real stores override it with a `LIMIT` query.
+    async fn stream_all_paged(
+        &self,
+        from: GlobalPosition,
+        limit: usize,
+    ) -> EventStoreResult<Vec<Event>> {
+        let mut events = self.stream_all(from.value()).await?;
+        events.truncate(limit);
+        Ok(events)
+    }
+
+    /// Async stream over every event with position `>= from`, fetched
+    /// `page_size` events at a time so memory stays bounded by one page.
+    ///
+    /// Ends after the first short page. A store error is yielded once and
+    /// ends the stream.
+    fn stream_all_from(
+        &self,
+        from: GlobalPosition,
+        page_size: usize,
+    ) -> BoxStream<'_, EventStoreResult<Event>> {
+        let page_size = page_size.max(1);
+        stream::unfold(Some(from), move |cursor| async move {
+            let from = cursor?;
+            match self.stream_all_paged(from, page_size).await {
+                Ok(page) => {
+                    let next = if page.len() < page_size {
+                        None
+                    } else {
+                        // Stores that don't stamp positions are treated as
+                        // dense, 1 per event.
+                        Some(
+                            page.last()
+                                .and_then(|e| e.position)
+                                .map(GlobalPosition::next)
+                                .unwrap_or(GlobalPosition(from.value() + page.len() as i64)),
+                        )
+                    };
+                    Some((stream::iter(page.into_iter().map(Ok)).left_stream(), next))
+                }
+                Err(e) => Some((stream::once(async { Err(e) }).right_stream(), None)),
+            }
+        })
+        .flatten()
+        .boxed()
+    }
+
     async fn get_version(&self, aggregate_id: &str) -> EventStoreResult<i64>;
+
+    /// Persist `snapshot`, replacing any earlier snapshot for the same
+    /// aggregate. Stores keep only the latest one.
+    ///
+    /// The default discards the snapshot, which is always correct: the
+    /// `CommandBus` then rebuilds from events.
+    async fn save_snapshot(&self, _snapshot: &Snapshot) -> EventStoreResult<()> {
+        Ok(())
+    }
+
+    /// Latest snapshot for `aggregate_id`, if any. The default never has one.
+    async fn load_snapshot(&self, _aggregate_id: &str) -> EventStoreResult<Option<Snapshot>> {
+        Ok(None)
+    }
+}
+
+/// [`EventStore`] decorator that runs every loaded event through an
+/// [`UpcasterRegistry`].
+///
+/// Writes and snapshots pass straight through. Every read path (`load`,
+/// `load_from`, `stream_all`, `stream_all_paged`, and through it
+/// `stream_all_from`, plus the batch returned by `append_returning`) yields
+/// events in their latest schema version. A payload an upcaster rejects
+/// fails the read with [`EventStoreError::UpcastFailed`].
+///
+/// Integrity verification, where the inner store does it, runs on the stored
+/// payload before upcasting. When the inner store is also an [`Outbox`], so
+/// is the decorator, and relayed events are upcast the same way.
+#[derive(Clone)]
+pub struct UpcastingEventStore<S> {
+    inner: S,
+    upcasters: Arc<UpcasterRegistry>,
+}
+
+impl<S: EventStore> UpcastingEventStore<S> {
+    pub fn new(inner: S, upcasters: UpcasterRegistry) -> Self {
+        Self {
+            inner,
+            upcasters: Arc::new(upcasters),
+        }
+    }
+
+    /// The wrapped store, for store-specific operations.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    fn upcast_all(&self, events: Vec<Event>) -> EventStoreResult<Vec<Event>> {
+        events
+            .into_iter()
+            .map(|e| self.upcasters.upcast(e).map_err(EventStoreError::upcast))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl<S: EventStore> EventStore for UpcastingEventStore<S> {
+    async fn append(
+        &self,
+        aggregate_id: &str,
+        version_check: VersionCheck,
+        events: Vec<Event>,
+    ) -> EventStoreResult<()> {
+        self.inner.append(aggregate_id, version_check, events).await
+    }
+
+    async fn append_returning(
+        &self,
+        aggregate_id: &str,
+        version_check: VersionCheck,
+        events: Vec<Event>,
+    ) -> EventStoreResult<Vec<Event>> {
+        let stored = self
+            .inner
+            .append_returning(aggregate_id, version_check, events)
+            .await?;
+        self.upcast_all(stored)
+    }
+
+    async fn load(&self, aggregate_id: &str) -> EventStoreResult<Vec<Event>> {
+        self.upcast_all(self.inner.load(aggregate_id).await?)
+    }
+
+    async fn load_from(
+        &self,
+        aggregate_id: &str,
+        from_sequence: i64,
+    ) -> EventStoreResult<Vec<Event>> {
+        self.upcast_all(self.inner.load_from(aggregate_id, from_sequence).await?)
+    }
+
+    async fn stream_all(&self, from_position: i64) -> EventStoreResult<Vec<Event>> {
+        self.upcast_all(self.inner.stream_all(from_position).await?)
+    }
+
+    async fn stream_all_paged(
+        &self,
+        from: GlobalPosition,
+        limit: usize,
+    ) -> EventStoreResult<Vec<Event>> {
+        self.upcast_all(self.inner.stream_all_paged(from, limit).await?)
+    }
+
+    async fn get_version(&self, aggregate_id: &str) -> EventStoreResult<i64> {
+        self.inner.get_version(aggregate_id).await
+    }
+
+    async fn save_snapshot(&self, snapshot: &Snapshot) -> EventStoreResult<()> {
+        self.inner.save_snapshot(snapshot).await
+    }
+
+    async fn load_snapshot(&self, aggregate_id: &str) -> EventStoreResult<Option<Snapshot>> {
+        self.inner.load_snapshot(aggregate_id).await
+    }
+}
+
+#[async_trait]
+impl<S: EventStore + Outbox> Outbox for UpcastingEventStore<S> {
+    async fn fetch_pending(&self, limit: usize) -> EventStoreResult<Vec<OutboxEntry>> {
+        self.inner
+            .fetch_pending(limit)
+            .await?
+            .into_iter()
+            .map(|mut entry| {
+                entry.event = self
+                    .upcasters
+                    .upcast(entry.event)
+                    .map_err(EventStoreError::upcast)?;
+                Ok(entry)
+            })
+            .collect()
+    }
+
+    async fn mark_delivered(&self, positions: &[GlobalPosition]) -> EventStoreResult<()> {
+        self.inner.mark_delivered(positions).await
+    }
+
+    async fn mark_failed(&self, position: GlobalPosition, error: &str) -> EventStoreResult<()> {
+        self.inner.mark_failed(position, error).await
+    }
+
+    async fn status(&self) -> EventStoreResult<OutboxStatus> {
+        self.inner.status().await
+    }
+
+    async fn prune_delivered(&self, delivered_before_us: i64) -> EventStoreResult<usize> {
+        self.inner.prune_delivered(delivered_before_us).await
+    }
 }
 
 // ─────────────────────────────────────────────────────────────────────────────
@@ -174,9 +441,18 @@ pub trait EventStore: Send + Sync {
 #[cfg(any(test, feature = "test-utils"))]
 mod in_memory {
     use super::*;
+    use crate::outbox::{Outbox, OutboxEntry, OutboxStatus};
+    use std::collections::{BTreeMap, HashMap};
     use std::sync::Arc;
     use tokio::sync::Mutex as TokioMutex;
 
+    #[derive(Debug, Clone, Default)]
+    struct OutboxRow {
+        attempts: u32,
+        last_error: Option<String>,
+        delivered_at_us: Option<i64>,
+    }
+
     /// In-memory event store. Available to downstream crates via the
     /// `test-utils` feature flag.
     ///
@@ -185,12 +461,29 @@ mod in_memory {
     #[derive(Clone, Default)]
     pub struct InMemoryEventStore {
         events: Arc<TokioMutex<Vec<Event>>>,
+        snapshots: Arc<TokioMutex<HashMap<String, Snapshot>>>,
+        outbox: Option<Arc<TokioMutex<BTreeMap<GlobalPosition, OutboxRow>>>>,
     }
 
     impl InMemoryEventStore {
         pub fn new() -> Self {
             Self::default()
         }
+
+        /// Queue every appended event in an [`Outbox`], like an
+        /// outbox-enabled production store.
+        pub fn with_outbox(mut self) -> Self {
+            self.outbox = Some(Arc::default());
+            self
+        }
+
+        fn outbox_rows(
+            &self,
+        ) -> EventStoreResult<&Arc<TokioMutex<BTreeMap<GlobalPosition, OutboxRow>>>> {
+            self.outbox
+                .as_ref()
+                .ok_or_else(|| EventStoreError::other("outbox not enabled on this store"))
+        }
     }
 
     #[async_trait]
@@ -201,6 +494,17 @@ mod in_memory {
             version_check: VersionCheck,
             events: Vec<Event>,
         ) -> EventStoreResult<()> {
+            self.append_returning(aggregate_id, version_check, events)
+                .await
+                .map(drop)
+        }
+
+        async fn append_returning(
+            &self,
+            aggregate_id: &str,
+            version_check: VersionCheck,
+            events: Vec<Event>,
+        ) -> EventStoreResult<Vec<Event>> {
             validate_audit_batch(aggregate_id, &events)?;
 
             let mut store = self.events.lock().await;
@@ -222,8 +526,26 @@ mod in_memory {
                 }
             }
 
-            store.extend(events);
-            Ok(())
+            let base = store.len() as i64;
+            let stored: Vec<Event> = events
+                .into_iter()
+                .enumerate()
+                .map(|(i, mut e)| {
+                    e.position = Some(GlobalPosition(base + i as i64 + 1));
+                    e
+                })
+                .collect();
+            store.extend(stored.iter().cloned());
+            // Queued under the events lock, so append and enqueue are atomic.
+            if let Some(outbox) = &self.outbox {
+                let mut outbox = outbox.lock().await;
+                for event in &stored {
+                    if let Some(position) = event.position {
+                        outbox.insert(position, OutboxRow::default());
+                    }
+                }
+            }
+            Ok(stored)
         }
 
         async fn load(&self, aggregate_id: &str) -> EventStoreResult<Vec<Event>> {
@@ -249,8 +571,19 @@ mod in_memory {
         }
 
         async fn stream_all(&self, from_position: i64) -> EventStoreResult<Vec<Event>> {
+            self.stream_all_paged(GlobalPosition(from_position), usize::MAX)
+                .await
+        }
+
+        async fn stream_all_paged(
+            &self,
+            from: GlobalPosition,
+            limit: usize,
+        ) -> EventStoreResult<Vec<Event>> {
             let store = self.events.lock().await;
-            Ok(store.iter().skip(from_position as usize).cloned().collect())
+            // Positions are dense and 1-based: position p lives at index p - 1.
+            let start = (from.value().max(1) - 1) as usize;
+            Ok(store.iter().skip(start).take(limit).cloned().collect())
         }
 
         async fn get_version(&self, aggregate_id: &str) -> EventStoreResult<i64> {
@@ -262,6 +595,93 @@ mod in_memory {
                 .max()
                 .unwrap_or(0))
         }
+
+        async fn save_snapshot(&self, snapshot: &Snapshot) -> EventStoreResult<()> {
+            self.snapshots
+                .lock()
+                .await
+                .insert(snapshot.aggregate_id.clone(), snapshot.clone());
+            Ok(())
+        }
+
+        async fn load_snapshot(&self, aggregate_id: &str) -> EventStoreResult<Option<Snapshot>> {
+            Ok(self.snapshots.lock().await.get(aggregate_id).cloned())
+        }
+    }
+
+    #[async_trait]
+    impl Outbox for InMemoryEventStore {
+        async fn fetch_pending(&self, limit: usize) -> EventStoreResult<Vec<OutboxEntry>> {
+            let pending: Vec<(GlobalPosition, OutboxRow)> = self
+                .outbox_rows()?
+                .lock()
+                .await
+                .iter()
+                .filter(|(_, row)| row.delivered_at_us.is_none())
+                .take(limit)
+                .map(|(position, row)| (*position, row.clone()))
+                .collect();
+            let events = self.events.lock().await;
+            Ok(pending
+                .into_iter()
+                .filter_map(|(position, row)| {
+                    // Positions are dense and 1-based: position p lives at index p - 1.
+                    let event = events.get((position.value() - 1) as usize)?.clone();
+                    Some(OutboxEntry {
+                        position,
+                        event,
+                        attempts: row.attempts,
+                        last_error: row.last_error,
+                    })
+                })
+                .collect())
+        }
+
+        async fn mark_delivered(&self, positions: &[GlobalPosition]) -> EventStoreResult<()> {
+            let mut rows = self.outbox_rows()?.lock().await;
+            let now = crate::audit::now_us();
+            for position in positions {
+                if let Some(row) = rows.get_mut(position) {
+                    row.delivered_at_us.get_or_insert(now);
+                }
+            }
+            Ok(())
+        }
+
+        async fn mark_failed(&self, position: GlobalPosition, error: &str) -> EventStoreResult<()> {
+            let mut rows = self.outbox_rows()?.lock().await;
+            if let Some(row) = rows.get_mut(&position) {
+                row.attempts += 1;
+                row.last_error = Some(error.to_string());
+            }
+            Ok(())
+        }
+
+        async fn status(&self) -> EventStoreResult<OutboxStatus> {
+            let rows = self.outbox_rows()?.lock().await;
+            let mut status = OutboxStatus::default();
+            for (position, row) in rows.iter() {
+                if row.delivered_at_us.is_some() {
+                    status.delivered += 1;
+                    continue;
+                }
+                status.pending += 1;
+                if row.attempts > 0 {
+                    status.failing += 1;
+                }
+                status.oldest_pending.get_or_insert(*position);
+            }
+            Ok(status)
+        }
+
+        async fn prune_delivered(&self, delivered_before_us: i64) -> EventStoreResult<usize> {
+            let mut rows = self.outbox_rows()?.lock().await;
+            let before = rows.len();
+            rows.retain(
+                |_, row| !matches!(row.delivered_at_us, Some(at) if at < delivered_before_us),
+            );
+            Ok(before - rows.len())
+        }
     }
 }
 
@@ -306,6 +726,13 @@ mod tests {
         assert!(EventStoreError::serialization("Y")
             .to_string()
             .contains("Y"));
+
+        let broken = EventStoreError::integrity(IntegrityError::BrokenAt {
+            aggregate_id: "user-9".into(),
+            sequence: 4,
+        });
+        assert!(broken.to_string().contains("Integrity check failed"));
+        assert!(broken.to_string().contains("user-9"));
     }
 
     #[test]
@@ -349,4 +776,210 @@ mod tests {
         let loaded = store.load("u1").await.unwrap();
         assert_eq!(loaded.len(), 1);
     }
+
+    #[tokio::test]
+    async fn test_in_memory_store_keeps_latest_snapshot() {
+        let store = InMemoryEventStore::new();
+        assert!(store.load_snapshot("u1").await.unwrap().is_none());
+
+        store
+            .save_snapshot(&Snapshot::new("User", "u1", 10, 1, json!({"v": 10})))
+            .await
+            .unwrap();
+        store
+            .save_snapshot(&Snapshot::new("User", "u1", 20, 1, json!({"v": 20})))
+            .await
+            .unwrap();
+
+        let snap = store.load_snapshot("u1").await.unwrap().unwrap();
+        assert_eq!(snap.version, 20);
+        assert_eq!(snap.state, json!({"v": 20}));
+        assert!(store.load_snapshot("u2").await.unwrap().is_none());
+    }
+
+    async fn seed_store(count: i64) -> InMemoryEventStore {
+        let store = InMemoryEventStore::new();
+        for seq in 1..=count {
+            let e = Event::new("User", "u1", seq, "X", json!({ "n": seq }))
+                .with_audit(AuditMetadata::test_default());
+            store
+                .append("u1", VersionCheck::Expected(seq - 1), vec![e])
+                .await
+                .unwrap();
+        }
+        store
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_assigns_global_positions() {
+        let store = seed_store(3).await;
+        let all = store.stream_all(0).await.unwrap();
+        let positions: Vec<_> = all.iter().map(|e| e.position.unwrap().value()).collect();
+        assert_eq!(positions, vec![1, 2, 3]);
+        assert_eq!(store.stream_all(2).await.unwrap().len(), 2);
+        assert_eq!(
+            store.load("u1").await.unwrap()[0].position,
+            Some(GlobalPosition(1))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_all_paged_resumes_from_cursor() {
+        let store = seed_store(5).await;
+        let first = store
+            .stream_all_paged(GlobalPosition::START, 2)
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 2);
+        let next = first.last().unwrap().position.unwrap().next();
+        let second = store.stream_all_paged(next, 2).await.unwrap();
+        assert_eq!(second[0].sequence, 3);
+        let rest = store.stream_all_paged(GlobalPosition(5), 2).await.unwrap();
+        assert_eq!(rest.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stream_all_from_walks_every_page() {
+        let store = seed_store(7).await;
+        let events: Vec<Event> = store
+            .stream_all_from(GlobalPosition::START, 3)
+            .map(|r| r.unwrap())
+            .collect()
+            .await;
+        let sequences: Vec<i64> = events.iter().map(|e| e.sequence).collect();
+        assert_eq!(sequences, (1..=7).collect::<Vec<_>>());
+
+        let tail: Vec<_> = store.stream_all_from(GlobalPosition(6), 3).collect().await;
+        assert_eq!(tail.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stream_all_from_yields_store_error_once() {
+        struct Failing;
+        #[async_trait]
+        impl EventStore for Failing {
+            async fn append(
+                &self,
+                _: &str,
+                _: VersionCheck,
+                _: Vec<Event>,
+            ) -> EventStoreResult<()> {
+                Ok(())
+            }
+            async fn load(&self, _: &str) -> EventStoreResult<Vec<Event>> {
+                Ok(vec![])
+            }
+            async fn load_from(&self, _: &str, _: i64) -> EventStoreResult<Vec<Event>> {
+                Ok(vec![])
+            }
+            async fn stream_all(&self, _: i64) -> EventStoreResult<Vec<Event>> {
+                Err(EventStoreError::database("down"))
+            }
+            async fn get_version(&self, _: &str) -> EventStoreResult<i64> {
+                Ok(0)
+            }
+        }
+        let results: Vec<_> = Failing
+            .stream_all_from(GlobalPosition::START, 10)
+            .collect()
+            .await;
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+    }
+
+    fn rename_n(mut payload: serde_json::Value) -> Result<serde_json::Value, String> {
+        let n = payload["n"].take();
+        if n.is_null() {
+            return Err("missing n".into());
+        }
+        Ok(json!({ "count": n }))
+    }
+
+    #[tokio::test]
+    async fn test_upcasting_store_upcasts_every_read_path() {
+        let store = UpcastingEventStore::new(
+            seed_store(3).await,
+            UpcasterRegistry::new().with_upcaster("X", 1, rename_n),
+        );
+
+        let loaded = store.load("u1").await.unwrap();
+        assert!(loaded.iter().all(|e| e.schema_version == 2));
+        assert_eq!(loaded[0].payload, json!({ "count": 1 }));
+        assert_eq!(
+            store.load_from("u1", 3).await.unwrap()[0].payload["count"],
+            3
+        );
+        assert_eq!(store.stream_all(0).await.unwrap().len(), 3);
+        let page = store.stream_all_paged(GlobalPosition(2), 1).await.unwrap();
+        assert_eq!(page[0].payload, json!({ "count": 2 }));
+        let streamed: Vec<_> = store
+            .stream_all_from(GlobalPosition::START, 2)
+            .collect()
+            .await;
+        assert!(streamed
+            .iter()
+            .all(|e| e.as_ref().unwrap().schema_version == 2));
+
+        let appended = store
+            .append_returning(
+                "u1",
+                VersionCheck::Expected(3),
+                vec![Event::new("User", "u1", 4, "X", json!({ "n": 4 }))
+                    .with_audit(AuditMetadata::test_default())],
+            )
+            .await
+            .unwrap();
+        assert_eq!(appended[0].payload, json!({ "count": 4 }));
+
+        // The stored rows keep their original shape.
+        let raw = store.inner().load("u1").await.unwrap();
+        assert_eq!(raw[3].payload, json!({ "n": 4 }));
+        assert_eq!(raw[3].schema_version, 1);
+        assert_eq!(store.get_version("u1").await.unwrap(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_upcasting_store_upcasts_relayed_outbox_entries() {
+        let inner = InMemoryEventStore::new().with_outbox();
+        inner
+            .append(
+                "u1",
+                VersionCheck::New,
+                vec![Event::new("User", "u1", 1, "X", json!({ "n": 1 }))
+                    .with_audit(AuditMetadata::test_default())],
+            )
+            .await
+            .unwrap();
+        let store = UpcastingEventStore::new(
+            inner,
+            UpcasterRegistry::new().with_upcaster("X", 1, rename_n),
+        );
+
+        let pending = store.fetch_pending(10).await.unwrap();
+        assert_eq!(pending[0].event.payload, json!({ "count": 1 }));
+        store.mark_delivered(&[pending[0].position]).await.unwrap();
+        assert_eq!(store.status().await.unwrap().pending, 0);
+    }
+
+    #[tokio::test]
+    async fn test_upcasting_store_reports_rejected_payload() {
+        let inner = InMemoryEventStore::new();
+        inner
+            .append(
+                "u1",
+                VersionCheck::New,
+                vec![Event::new("User", "u1", 1, "X", json!({}))
+                    .with_audit(AuditMetadata::test_default())],
+            )
+            .await
+            .unwrap();
+        let store = UpcastingEventStore::new(
+            inner,
+            UpcasterRegistry::new().with_upcaster("X", 1, rename_n),
+        );
+
+        let err = store.load("u1").await.unwrap_err();
+        assert!(matches!(err, EventStoreError::UpcastFailed { .. }));
+        assert!(err.to_string().contains("missing n"));
+    }
 }
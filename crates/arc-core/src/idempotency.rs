@@ -0,0 +1,383 @@
+//! # Idempotency Store
+//!
+//! Remembers which events a command produced under a client-supplied
+//! idempotency key, so a retried request (the same `Idempotency-Key` header
+//! sent twice) gets the original result instead of running the command
+//! again. [`CommandBus::with_idempotency`](crate::command_bus::CommandBus::with_idempotency)
+//! consults the store for every dispatch whose
+//! [`CommandContext`](crate::command_bus::CommandContext) carries a key.
+//!
+//! ## Lifecycle
+//!
+//! 1. [`begin`](IdempotencyStore::begin) reserves `(actor_id, key)` before
+//!    the command runs. A concurrent request with the same key sees the
+//!    reservation as [`IdempotencyStatus::InFlight`] instead of running the
+//!    command a second time.
+//! 2. [`complete`](IdempotencyStore::complete) records the produced event
+//!    ids once they are committed; later requests see
+//!    [`IdempotencyStatus::Completed`] until the record expires.
+//! 3. [`release`](IdempotencyStore::release) drops the reservation when the
+//!    command fails, so the client can retry with the same key.
+//!
+//! A reservation that is never completed or released (the process died mid
+//! dispatch) is taken over once it is older than [`IN_FLIGHT_LEASE_US`].
+//!
+//! Keys are scoped to the actor: two users sending the same key never see
+//! each other's results. Within one actor, a key is bound to the command's
+//! fingerprint, so reusing it for a different request is refused rather
+//! than answered with the first request's result.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use thiserror::Error;
+use uuid::Uuid;
+
+/// Default lifetime of a completed record: 24 hours.
+pub const DEFAULT_IDEMPOTENCY_TTL: Duration = Duration::from_secs(24 * 3600);
+
+/// How long a reservation blocks other requests with the same key before it
+/// is considered abandoned: 60 seconds.
+pub const IN_FLIGHT_LEASE_US: i64 = 60 * 1_000_000;
+
+/// Errors emitted by [`IdempotencyStore`] implementations.
+#[derive(Debug, Error)]
+pub enum IdempotencyError {
+    #[error("idempotency store sink failure: {0}")]
+    Sink(String),
+    #[error("idempotency store validation failure: {0}")]
+    Validation(String),
+}
+
+/// Outcome of a completed command, as recorded under its key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdempotentOutcome {
+    pub aggregate_type: String,
+    pub aggregate_id: String,
+    /// Ids of the events the command produced, in order. Empty when the
+    /// command was a no-op.
+    pub event_ids: Vec<Uuid>,
+    /// SHA-256 (hex) of the command's
+    /// [`idempotency_fingerprint`](crate::aggregate::Command::idempotency_fingerprint);
+    /// `None` for commands without one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+}
+
+/// Persistent record of one idempotency key.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdempotencyRecord {
+    pub actor_id: String,
+    pub key: String,
+    pub created_at_us: i64,
+    pub expires_at_us: i64,
+    /// `None` while the command is still running.
+    pub outcome: Option<IdempotentOutcome>,
+}
+
+impl IdempotencyRecord {
+    /// A fresh reservation for `key`, valid for `ttl_us`.
+    pub fn reserve(
+        actor_id: impl Into<String>,
+        key: impl Into<String>,
+        now_us: i64,
+        ttl_us: i64,
+    ) -> Self {
+        Self {
+            actor_id: actor_id.into(),
+            key: key.into(),
+            created_at_us: now_us,
+            expires_at_us: now_us.saturating_add(ttl_us),
+            outcome: None,
+        }
+    }
+
+    /// Whether the record still guards its key at `now_us`: a completed
+    /// record until it expires, a reservation until its lease runs out.
+    pub fn is_live_at(&self, now_us: i64) -> bool {
+        if self.expires_at_us <= now_us {
+            return false;
+        }
+        self.outcome.is_some() || self.created_at_us.saturating_add(IN_FLIGHT_LEASE_US) > now_us
+    }
+
+    /// Shared input checks for [`IdempotencyStore::begin`].
+    pub fn validate(&self) -> Result<(), IdempotencyError> {
+        if self.actor_id.trim().is_empty() {
+            return Err(IdempotencyError::Validation("actor_id empty".into()));
+        }
+        if self.key.trim().is_empty() {
+            return Err(IdempotencyError::Validation("key empty".into()));
+        }
+        if self.expires_at_us <= self.created_at_us {
+            return Err(IdempotencyError::Validation(
+                "expires_at_us must be > created_at_us".into(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Answer of [`IdempotencyStore::begin`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum IdempotencyStatus {
+    /// The key was free (or its previous record had lapsed) and is now
+    /// reserved for the caller.
+    Started,
+    /// Another request holds the key and has not finished yet.
+    InFlight,
+    /// A previous request with this key completed with this outcome.
+    Completed(IdempotentOutcome),
+}
+
+/// Key → produced events registry behind idempotent command dispatch.
+///
+/// Implementations:
+/// - [`InMemoryIdempotencyStore`] — `Arc<Mutex<HashMap>>`, behind
+///   `test-utils`
+/// - `SqliteIdempotencyStore` — in `arc-es-sqlite`, durable
+#[async_trait]
+pub trait IdempotencyStore: Send + Sync {
+    /// Reserve `record`'s key unless a live record already holds it. Must be
+    /// atomic: of two concurrent calls for the same key, at most one gets
+    /// [`IdempotencyStatus::Started`].
+    async fn begin(&self, record: IdempotencyRecord)
+        -> Result<IdempotencyStatus, IdempotencyError>;
+
+    /// Outcome recorded under `key`, if a completed record is still live at
+    /// `now_us`. Does not reserve anything.
+    async fn lookup(
+        &self,
+        actor_id: &str,
+        key: &str,
+        now_us: i64,
+    ) -> Result<Option<IdempotentOutcome>, IdempotencyError>;
+
+    /// Record the outcome of the reserved key.
+    async fn complete(
+        &self,
+        actor_id: &str,
+        key: &str,
+        outcome: IdempotentOutcome,
+    ) -> Result<(), IdempotencyError>;
+
+    /// Drop an uncompleted reservation. Completed records are left alone.
+    async fn release(&self, actor_id: &str, key: &str) -> Result<(), IdempotencyError>;
+
+    /// Hard-delete records past their `expires_at_us`. Returns rows removed.
+    async fn prune_expired(&self, now_us: i64) -> Result<usize, IdempotencyError>;
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// In-memory implementation. Public behind the `test-utils` feature so
+// downstream tests can use it.
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(any(test, feature = "test-utils"))]
+mod in_memory {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use tokio::sync::Mutex;
+
+    #[derive(Clone, Default)]
+    pub struct InMemoryIdempotencyStore {
+        inner: Arc<Mutex<HashMap<(String, String), IdempotencyRecord>>>,
+    }
+
+    impl InMemoryIdempotencyStore {
+        pub fn new() -> Self {
+            Self::default()
+        }
+    }
+
+    #[async_trait]
+    impl IdempotencyStore for InMemoryIdempotencyStore {
+        async fn begin(
+            &self,
+            record: IdempotencyRecord,
+        ) -> Result<IdempotencyStatus, IdempotencyError> {
+            record.validate()?;
+            let mut g = self.inner.lock().await;
+            let slot = (record.actor_id.clone(), record.key.clone());
+            if let Some(existing) = g.get(&slot) {
+                if existing.is_live_at(record.created_at_us) {
+                    return Ok(match &existing.outcome {
+                        Some(outcome) => IdempotencyStatus::Completed(outcome.clone()),
+                        None => IdempotencyStatus::InFlight,
+                    });
+                }
+            }
+            g.insert(slot, record);
+            Ok(IdempotencyStatus::Started)
+        }
+
+        async fn lookup(
+            &self,
+            actor_id: &str,
+            key: &str,
+            now_us: i64,
+        ) -> Result<Option<IdempotentOutcome>, IdempotencyError> {
+            let g = self.inner.lock().await;
+            Ok(g.get(&(actor_id.to_string(), key.to_string()))
+                .filter(|r| r.is_live_at(now_us))
+                .and_then(|r| r.outcome.clone()))
+        }
+
+        async fn complete(
+            &self,
+            actor_id: &str,
+            key: &str,
+            outcome: IdempotentOutcome,
+        ) -> Result<(), IdempotencyError> {
+            let mut g = self.inner.lock().await;
+            match g.get_mut(&(actor_id.to_string(), key.to_string())) {
+                Some(r) => {
+                    r.outcome = Some(outcome);
+                    Ok(())
+                }
+                None => Err(IdempotencyError::Validation(format!(
+                    "no reservation for key '{key}'"
+                ))),
+            }
+        }
+
+        async fn release(&self, actor_id: &str, key: &str) -> Result<(), IdempotencyError> {
+            let mut g = self.inner.lock().await;
+            let slot = (actor_id.to_string(), key.to_string());
+            if g.get(&slot).is_some_and(|r| r.outcome.is_none()) {
+                g.remove(&slot);
+            }
+            Ok(())
+        }
+
+        async fn prune_expired(&self, now_us: i64) -> Result<usize, IdempotencyError> {
+            let mut g = self.inner.lock().await;
+            let before = g.len();
+            g.retain(|_, r| r.expires_at_us > now_us);
+            Ok(before - g.len())
+        }
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+pub use in_memory::InMemoryIdempotencyStore;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000_000_000;
+    const DAY_US: i64 = 24 * 3600 * 1_000_000;
+
+    fn outcome() -> IdempotentOutcome {
+        IdempotentOutcome {
+            aggregate_type: "User".into(),
+            aggregate_id: "u1".into(),
+            event_ids: vec![Uuid::new_v4()],
+            fingerprint: Some("ab12".into()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_begin_complete_then_replay() {
+        let s = InMemoryIdempotencyStore::new();
+        let r = IdempotencyRecord::reserve("alice", "k1", NOW, DAY_US);
+        assert_eq!(
+            s.begin(r.clone()).await.unwrap(),
+            IdempotencyStatus::Started
+        );
+        assert_eq!(s.lookup("alice", "k1", NOW).await.unwrap(), None);
+        let done = outcome();
+        s.complete("alice", "k1", done.clone()).await.unwrap();
+        assert_eq!(
+            s.lookup("alice", "k1", NOW).await.unwrap(),
+            Some(done.clone())
+        );
+
+        let again = IdempotencyRecord::reserve("alice", "k1", NOW + 1_000, DAY_US);
+        assert_eq!(
+            s.begin(again).await.unwrap(),
+            IdempotencyStatus::Completed(done)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reservation_blocks_until_released() {
+        let s = InMemoryIdempotencyStore::new();
+        let r = IdempotencyRecord::reserve("alice", "k1", NOW, DAY_US);
+        s.begin(r.clone()).await.unwrap();
+        assert_eq!(
+            s.begin(r.clone()).await.unwrap(),
+            IdempotencyStatus::InFlight
+        );
+
+        s.release("alice", "k1").await.unwrap();
+        assert_eq!(s.begin(r).await.unwrap(), IdempotencyStatus::Started);
+    }
+
+    #[tokio::test]
+    async fn test_keys_are_scoped_to_the_actor() {
+        let s = InMemoryIdempotencyStore::new();
+        s.begin(IdempotencyRecord::reserve("alice", "k1", NOW, DAY_US))
+            .await
+            .unwrap();
+        let bob = IdempotencyRecord::reserve("bob", "k1", NOW, DAY_US);
+        assert_eq!(s.begin(bob).await.unwrap(), IdempotencyStatus::Started);
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_reservation_and_expired_record_are_taken_over() {
+        let s = InMemoryIdempotencyStore::new();
+        s.begin(IdempotencyRecord::reserve("alice", "k1", NOW, DAY_US))
+            .await
+            .unwrap();
+        let later = IdempotencyRecord::reserve("alice", "k1", NOW + IN_FLIGHT_LEASE_US, DAY_US);
+        assert_eq!(s.begin(later).await.unwrap(), IdempotencyStatus::Started);
+
+        s.complete("alice", "k1", outcome()).await.unwrap();
+        let expired = IdempotencyRecord::reserve("alice", "k1", NOW + 2 * DAY_US, DAY_US);
+        assert_eq!(s.begin(expired).await.unwrap(), IdempotencyStatus::Started);
+    }
+
+    #[tokio::test]
+    async fn test_release_keeps_completed_records() {
+        let s = InMemoryIdempotencyStore::new();
+        let r = IdempotencyRecord::reserve("alice", "k1", NOW, DAY_US);
+        s.begin(r.clone()).await.unwrap();
+        s.complete("alice", "k1", outcome()).await.unwrap();
+        s.release("alice", "k1").await.unwrap();
+        assert!(matches!(
+            s.begin(r).await.unwrap(),
+            IdempotencyStatus::Completed(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_prune_expired_and_validation() {
+        let s = InMemoryIdempotencyStore::new();
+        s.begin(IdempotencyRecord::reserve(
+            "a",
+            "old",
+            NOW - 2 * DAY_US,
+            DAY_US,
+        ))
+        .await
+        .unwrap();
+        s.begin(IdempotencyRecord::reserve("a", "new", NOW, DAY_US))
+            .await
+            .unwrap();
+        assert_eq!(s.prune_expired(NOW).await.unwrap(), 1);
+
+        let err = s
+            .begin(IdempotencyRecord::reserve("a", " ", NOW, DAY_US))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, IdempotencyError::Validation(_)));
+        let err = s
+            .begin(IdempotencyRecord::reserve("a", "k", NOW, 0))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, IdempotencyError::Validation(_)));
+    }
+}
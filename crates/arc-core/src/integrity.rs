@@ -110,7 +110,15 @@ fn canonical_bytes(event: &Event) -> Vec<u8> {
         &event.payload,
         event.timestamp,
     );
-    serde_json::to_vec(&signable).expect("serde always succeeds for tuple of primitive refs")
+    // `schema_version` decides how the payload is upcast on read, so it is
+    // signed too, but only past version 1: every event written before the
+    // column existed is version 1 and keeps its original signature.
+    if event.schema_version == 1 {
+        serde_json::to_vec(&signable)
+    } else {
+        serde_json::to_vec(&(signable, event.schema_version))
+    }
+    .expect("serde always succeeds for tuple of primitive refs")
 }
 
 /// HMAC-SHA256-keyed [`IntegrityChain`].
@@ -368,6 +376,17 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_schema_version_past_one_is_signed() {
+        let chain = HmacSha256Chain::new(key()).unwrap();
+        let v1 = event(1, "Created", json!({"x": 1}));
+        let mut v2 = v1.clone();
+        v2.schema_version = 2;
+        let s1 = chain.sign_event(&EventSignature::genesis(), &v1).unwrap();
+        let s2 = chain.sign_event(&EventSignature::genesis(), &v2).unwrap();
+        assert_ne!(s1, s2);
+    }
+
     #[test]
     fn test_hex_helpers_roundtrip() {
         let bytes = vec![0x00, 0xff, 0xab, 0xcd];
@@ -7,9 +7,19 @@
 //!
 //! - Event store trait definitions
 //! - Aggregate trait definitions
+//! - Typed domain events decoded from event payloads
 //! - Command and event bus traits
+//! - Command middleware hooks around dispatch
+//! - Authorization policies for commands and reads
 //! - Projector, projection, and projection engine traits
 //! - Read model store trait
+//! - Backend-neutral read model queries: filters, ordering, paging
+//! - Aggregate snapshots and snapshot cadence policy
+//! - Transactional outbox and relay for at-least-once publishing
+//! - Idempotency keys that replay a command's original result
+//! - Process managers (sagas) with durable state, timeouts and compensation
+//! - Durable scheduled commands with cancellation by key
+//! - Given/When/Then test fixtures (`test-utils` feature)
 //!
 
 // Re-export commonly used types
@@ -20,14 +30,25 @@ pub use uuid::Uuid;
 pub mod access_log;
 pub mod aggregate;
 pub mod audit;
+pub mod authorization;
 pub mod command_bus;
+pub mod command_middleware;
+pub mod domain_event;
 pub mod event;
 pub mod event_bus;
 pub mod event_store;
+pub mod idempotency;
 pub mod integrity;
+pub mod outbox;
+pub mod process_manager;
 pub mod projection;
+pub mod read_model_query;
 pub mod read_model_store;
+pub mod scheduler;
 pub mod session;
+pub mod snapshot;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
 
 #[cfg(test)]
 mod tests {
@@ -0,0 +1,395 @@
+//! # Outbox Module
+//!
+//! Transactional outbox between [`EventStore::append`](crate::event_store::EventStore::append)
+//! and [`EventBus::publish`](crate::event_bus::EventBus::publish).
+//!
+//! An outbox-enabled store writes one outbox entry per event in the same
+//! transaction as the append, so "persisted" implies "queued for delivery".
+//! The [`CommandBus`](crate::command_bus::CommandBus) still publishes inline
+//! and marks the entries delivered on success; when publish fails the entries
+//! stay pending and the [`OutboxRelay`] redelivers them.
+//!
+//! ## Guarantees
+//!
+//! - **At-least-once**: an event is marked delivered only after a publish
+//!   returned `Ok`. A crash between publish and mark redelivers it, so
+//!   handlers must be idempotent (projectors already are).
+//! - **Ordered**: entries are relayed in [`GlobalPosition`] order and the
+//!   relay stops at the first failure, so a later event is never delivered
+//!   ahead of an earlier pending one.
+//! - **Observable**: every entry records attempts, the last error, and when
+//!   it was delivered; [`Outbox::status`] summarizes the backlog.
+//! - **Bounded**: delivered entries are kept for the relay's retention window
+//!   and then pruned, so the table does not grow with the event log.
+
+use crate::event::{Event, GlobalPosition};
+use crate::event_bus::EventBus;
+use crate::event_store::EventStoreResult;
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// A pending outbox entry together with the event it delivers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutboxEntry {
+    /// Position of the event; also the entry's key.
+    pub position: GlobalPosition,
+
+    /// The stored event, `position` included
+    pub event: Event,
+
+    /// Failed delivery attempts so far
+    pub attempts: u32,
+
+    /// Error from the most recent failed attempt
+    pub last_error: Option<String>,
+}
+
+/// Snapshot of the outbox backlog.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutboxStatus {
+    /// Entries not yet delivered
+    pub pending: u64,
+
+    /// Pending entries with at least one failed attempt
+    pub failing: u64,
+
+    /// Delivered entries still retained
+    pub delivered: u64,
+
+    /// Position of the oldest pending entry; the relay's next target
+    pub oldest_pending: Option<GlobalPosition>,
+}
+
+/// Delivery bookkeeping for events written by an outbox-enabled store.
+#[async_trait]
+pub trait Outbox: Send + Sync {
+    /// Up to `limit` undelivered entries, oldest first.
+    async fn fetch_pending(&self, limit: usize) -> EventStoreResult<Vec<OutboxEntry>>;
+
+    /// Mark entries delivered. Unknown or already delivered positions are
+    /// ignored.
+    async fn mark_delivered(&self, positions: &[GlobalPosition]) -> EventStoreResult<()>;
+
+    /// Record a failed delivery attempt for `position`.
+    async fn mark_failed(&self, position: GlobalPosition, error: &str) -> EventStoreResult<()>;
+
+    /// Current backlog counts.
+    async fn status(&self) -> EventStoreResult<OutboxStatus>;
+
+    /// Delete delivered entries older than `delivered_before_us`. Returns
+    /// the number removed. Pending entries are never pruned.
+    async fn prune_delivered(&self, delivered_before_us: i64) -> EventStoreResult<usize>;
+}
+
+/// Default number of entries the relay fetches per batch.
+pub const DEFAULT_RELAY_BATCH_SIZE: usize = 100;
+
+/// Default pause between polls once the outbox is drained.
+pub const DEFAULT_RELAY_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Default time delivered entries are kept before the relay prunes them.
+pub const DEFAULT_OUTBOX_RETENTION: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// Default pause between prunes of delivered entries.
+pub const DEFAULT_RELAY_PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Drains an [`Outbox`] into an [`EventBus`].
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let relay = OutboxRelay::new(Arc::new(event_store.clone()), Arc::new(bus.clone()));
+/// let (stop, stopped) = tokio::sync::watch::channel(false);
+/// tokio::spawn(async move { relay.run(stopped).await });
+/// // ...
+/// stop.send(true)?;
+/// ```
+pub struct OutboxRelay {
+    outbox: Arc<dyn Outbox>,
+    event_bus: Arc<dyn EventBus>,
+    batch_size: usize,
+    poll_interval: Duration,
+    retention: Option<Duration>,
+    prune_interval: Duration,
+}
+
+impl OutboxRelay {
+    pub fn new(outbox: Arc<dyn Outbox>, event_bus: Arc<dyn EventBus>) -> Self {
+        Self {
+            outbox,
+            event_bus,
+            batch_size: DEFAULT_RELAY_BATCH_SIZE,
+            poll_interval: DEFAULT_RELAY_POLL_INTERVAL,
+            retention: Some(DEFAULT_OUTBOX_RETENTION),
+            prune_interval: DEFAULT_RELAY_PRUNE_INTERVAL,
+        }
+    }
+
+    /// Entries fetched per batch. Clamped to at least 1.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Pause between polls when the outbox is empty or delivery failed.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// How long delivered entries are kept; `None` keeps them forever.
+    pub fn with_retention(mut self, retention: Option<Duration>) -> Self {
+        self.retention = retention;
+        self
+    }
+
+    /// Pause between prunes while [`run`](Self::run) is relaying.
+    pub fn with_prune_interval(mut self, prune_interval: Duration) -> Self {
+        self.prune_interval = prune_interval;
+        self
+    }
+
+    /// Delete entries delivered longer than the retention window ago.
+    /// Returns how many were removed.
+    pub async fn prune_once(&self) -> EventStoreResult<usize> {
+        let Some(retention) = self.retention else {
+            return Ok(0);
+        };
+        let retention_us = i64::try_from(retention.as_micros()).unwrap_or(i64::MAX);
+        self.outbox
+            .prune_delivered(crate::audit::now_us().saturating_sub(retention_us))
+            .await
+    }
+
+    /// Deliver one batch in position order, stopping at the first failure.
+    /// Returns how many entries were delivered.
+    pub async fn relay_once(&self) -> EventStoreResult<usize> {
+        let entries = self.outbox.fetch_pending(self.batch_size).await?;
+        let mut delivered = 0;
+        for entry in entries {
+            if let Err(e) = self.event_bus.publish(vec![entry.event]).await {
+                tracing::warn!(
+                    position = %entry.position,
+                    attempts = entry.attempts + 1,
+                    error = %e,
+                    "Outbox delivery failed; will retry"
+                );
+                self.outbox
+                    .mark_failed(entry.position, &e.to_string())
+                    .await?;
+                break;
+            }
+            self.outbox.mark_delivered(&[entry.position]).await?;
+            delivered += 1;
+        }
+        Ok(delivered)
+    }
+
+    /// Relay until `shutdown` turns `true` (or its sender is dropped).
+    ///
+    /// Drains full batches back to back and sleeps `poll_interval` once the
+    /// outbox is empty or a delivery fails. Store errors are logged and
+    /// retried on the next poll. Prunes delivered entries on start and then
+    /// every `prune_interval`.
+    pub async fn run(&self, mut shutdown: watch::Receiver<bool>) {
+        tracing::info!("Outbox relay started");
+        let mut last_prune: Option<Instant> = None;
+        while !*shutdown.borrow() {
+            if last_prune.is_none_or(|at| at.elapsed() >= self.prune_interval) {
+                last_prune = Some(Instant::now());
+                match self.prune_once().await {
+                    Ok(0) => {}
+                    Ok(n) => tracing::info!(pruned = n, "Pruned delivered outbox entries"),
+                    Err(e) => tracing::error!(error = %e, "Outbox prune failed"),
+                }
+            }
+            let idle = match self.relay_once().await {
+                Ok(n) => n < self.batch_size,
+                Err(e) => {
+                    tracing::error!(error = %e, "Outbox relay poll failed");
+                    true
+                }
+            };
+            if idle {
+                tokio::select! {
+                    _ = tokio::time::sleep(self.poll_interval) => {}
+                    changed = shutdown.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        tracing::info!("Outbox relay stopped");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::AuditMetadata;
+    use crate::event_bus::{EventBusError, EventBusResult, EventHandler};
+    use crate::event_store::{EventStore, InMemoryEventStore, VersionCheck};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::Mutex;
+
+    /// Bus that records delivered positions and fails the first `fail` calls.
+    struct FlakyBus {
+        fail: AtomicUsize,
+        delivered: Mutex<Vec<GlobalPosition>>,
+    }
+
+    impl FlakyBus {
+        fn new(fail: usize) -> Arc<Self> {
+            Arc::new(Self {
+                fail: AtomicUsize::new(fail),
+                delivered: Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl EventBus for FlakyBus {
+        async fn publish(&self, events: Vec<Event>) -> EventBusResult<()> {
+            if self
+                .fail
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                return Err(EventBusError::other("bus down"));
+            }
+            let mut delivered = self.delivered.lock().await;
+            delivered.extend(events.iter().filter_map(|e| e.position));
+            Ok(())
+        }
+
+        async fn subscribe(&mut self, _handler: Box<dyn EventHandler>) -> EventBusResult<()> {
+            Ok(())
+        }
+    }
+
+    async fn store_with_pending(n: i64) -> Arc<InMemoryEventStore> {
+        let store = Arc::new(InMemoryEventStore::new().with_outbox());
+        for seq in 1..=n {
+            let event = Event::new("User", "u1", seq, "Touched", json!({}))
+                .with_audit(AuditMetadata::test_default());
+            store
+                .append("u1", VersionCheck::Expected(seq - 1), vec![event])
+                .await
+                .unwrap();
+        }
+        store
+    }
+
+    #[tokio::test]
+    async fn test_relay_delivers_pending_in_order() {
+        let store = store_with_pending(3).await;
+        let bus = FlakyBus::new(0);
+        let relay = OutboxRelay::new(store.clone(), bus.clone());
+
+        assert_eq!(relay.relay_once().await.unwrap(), 3);
+        assert_eq!(
+            *bus.delivered.lock().await,
+            vec![GlobalPosition(1), GlobalPosition(2), GlobalPosition(3)]
+        );
+        let status = store.status().await.unwrap();
+        assert_eq!(status.pending, 0);
+        assert_eq!(status.delivered, 3);
+        assert_eq!(relay.relay_once().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_relay_stops_at_failure_and_retries_later() {
+        let store = store_with_pending(2).await;
+        let bus = FlakyBus::new(1);
+        let relay = OutboxRelay::new(store.clone(), bus.clone());
+
+        assert_eq!(relay.relay_once().await.unwrap(), 0);
+        let status = store.status().await.unwrap();
+        assert_eq!(status.pending, 2);
+        assert_eq!(status.failing, 1);
+        assert_eq!(status.oldest_pending, Some(GlobalPosition(1)));
+        let pending = store.fetch_pending(10).await.unwrap();
+        assert_eq!(pending[0].attempts, 1);
+        assert_eq!(
+            pending[0].last_error.as_deref(),
+            Some("Event bus error: bus down")
+        );
+
+        assert_eq!(relay.relay_once().await.unwrap(), 2);
+        assert_eq!(store.status().await.unwrap().pending, 0);
+    }
+
+    #[tokio::test]
+    async fn test_run_drains_then_stops_on_shutdown() {
+        let store = store_with_pending(5).await;
+        let bus = FlakyBus::new(0);
+        let relay = OutboxRelay::new(store.clone(), bus.clone())
+            .with_batch_size(2)
+            .with_poll_interval(Duration::from_millis(10));
+        let (stop, stopped) = watch::channel(false);
+
+        let task = tokio::spawn(async move { relay.run(stopped).await });
+        while store.status().await.unwrap().pending > 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        stop.send(true).unwrap();
+        task.await.unwrap();
+
+        assert_eq!(bus.delivered.lock().await.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_prune_removes_delivered_past_retention() {
+        let store = store_with_pending(2).await;
+        let relay = OutboxRelay::new(store.clone(), FlakyBus::new(0));
+        relay.relay_once().await.unwrap();
+
+        assert_eq!(relay.prune_once().await.unwrap(), 0);
+        let keep = OutboxRelay::new(store.clone(), FlakyBus::new(0)).with_retention(None);
+        assert_eq!(keep.prune_once().await.unwrap(), 0);
+
+        tokio::time::sleep(Duration::from_millis(2)).await;
+        let prune = OutboxRelay::new(store.clone(), FlakyBus::new(0))
+            .with_retention(Some(Duration::from_millis(1)));
+        assert_eq!(prune.prune_once().await.unwrap(), 2);
+        assert_eq!(store.status().await.unwrap().delivered, 0);
+    }
+
+    #[tokio::test]
+    async fn test_run_prunes_on_start() {
+        let store = store_with_pending(1).await;
+        let bus = FlakyBus::new(0);
+        OutboxRelay::new(store.clone(), bus.clone())
+            .relay_once()
+            .await
+            .unwrap();
+        let queued = Event::new("User", "u1", 2, "Touched", json!({}))
+            .with_audit(AuditMetadata::test_default());
+        store
+            .append("u1", VersionCheck::Expected(1), vec![queued])
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(2)).await;
+
+        let relay = OutboxRelay::new(store.clone(), bus.clone())
+            .with_retention(Some(Duration::from_millis(1)))
+            .with_poll_interval(Duration::from_millis(10));
+        let (stop, stopped) = watch::channel(false);
+        let task = tokio::spawn(async move { relay.run(stopped).await });
+        while store.status().await.unwrap().pending > 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        stop.send(true).unwrap();
+        task.await.unwrap();
+
+        // The entry delivered before start is gone; the one the run just
+        // delivered is still inside the retention window.
+        let status = store.status().await.unwrap();
+        assert_eq!(status.delivered, 1);
+    }
+}
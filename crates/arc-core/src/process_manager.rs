@@ -0,0 +1,1008 @@
+//! # Process Managers
+//!
+//! A process manager (saga) coordinates several aggregates over time: it
+//! listens to events, keeps durable per-instance state, and dispatches the
+//! follow-up commands each step needs. Onboarding is the canonical case —
+//! `UserRegistered` starts an instance, `EmailVerified` moves it on, and a
+//! user who never verifies is rolled back once the deadline passes.
+//!
+//! ## Moving parts
+//!
+//! - [`ProcessManager`] — the workflow: which events start an instance,
+//!   how each event advances its `State`, what to do on timeout and how to
+//!   compensate after a failure.
+//! - [`ProcessStore`] — persists one [`ProcessInstance`] per
+//!   `(process_name, instance_id)` with optimistic versioning.
+//! - [`ProcessRunner`] — drives a manager. It is an [`EventHandler`], meant
+//!   to be subscribed with [`DeliveryMode::Background`](crate::event_bus::DeliveryMode::Background)
+//!   so a slow step never blocks the write that triggered it, and its
+//!   [`run_timeouts`](ProcessRunner::run_timeouts) loop fires deadlines.
+//!
+//! ## Correlation
+//!
+//! Instances are keyed by the triggering event's `correlation_id` unless the
+//! manager overrides [`ProcessManager::instance_id`] (onboarding keys by the
+//! user id, because verification arrives in a later request). Commands a
+//! step dispatches go out with [`CommandContext::caused_by`] the event, so
+//! the whole chain shares one correlation id.
+//!
+//! ## Failure handling
+//!
+//! - A **transient** error ([`ProcessError::is_transient`]: store outages,
+//!   append conflicts) discards the step and is returned to the bus, which
+//!   redelivers the event.
+//! - Any other error (a command the aggregate rejects, a timeout) runs
+//!   [`ProcessManager::compensate`] and finishes the instance as
+//!   [`ProcessStatus::Compensated`], or [`ProcessStatus::Failed`] when the
+//!   compensation itself fails.
+//!
+//! Every command a step dispatches carries an idempotency key derived from
+//! the instance and the trigger, so when a step is re-run after a crash a
+//! router configured with an idempotency store replays the earlier result
+//! instead of executing the command twice.
+
+use crate::aggregate::Command;
+use crate::audit::SYSTEM_ACTOR;
+use crate::command_bus::{CommandBusError, CommandContext, CommandRouter};
+use crate::event::Event;
+use crate::event_bus::EventHandler;
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// Default number of due instances [`ProcessRunner::fire_timeouts`] handles
+/// per call.
+pub const DEFAULT_TIMEOUT_BATCH_SIZE: usize = 100;
+
+/// Default pause between deadline checks in [`ProcessRunner::run_timeouts`].
+pub const DEFAULT_TIMEOUT_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Errors emitted by [`ProcessStore`] implementations.
+#[derive(Debug, Error)]
+pub enum ProcessStoreError {
+    #[error("process store sink failure: {0}")]
+    Sink(String),
+    /// The instance changed since it was loaded; reload and retry.
+    #[error("process instance {process_name}/{instance_id} was modified concurrently")]
+    Conflict {
+        process_name: String,
+        instance_id: String,
+    },
+    #[error("process store validation failure: {0}")]
+    Validation(String),
+}
+
+impl ProcessStoreError {
+    pub fn conflict(process_name: impl Into<String>, instance_id: impl Into<String>) -> Self {
+        ProcessStoreError::Conflict {
+            process_name: process_name.into(),
+            instance_id: instance_id.into(),
+        }
+    }
+}
+
+/// Errors raised while a process step runs.
+#[derive(Debug, Error)]
+pub enum ProcessError {
+    #[error("process command failed: {0}")]
+    Command(#[from] CommandBusError),
+    #[error("process timed out")]
+    TimedOut,
+    #[error("process failed: {0}")]
+    Failed(String),
+    #[error("process state does not decode: {0}")]
+    State(#[from] serde_json::Error),
+    #[error(transparent)]
+    Store(#[from] ProcessStoreError),
+}
+
+impl ProcessError {
+    pub fn failed(message: impl Into<String>) -> Self {
+        ProcessError::Failed(message.into())
+    }
+
+    /// Whether retrying the same step may succeed. Transient errors are
+    /// returned to the event bus for redelivery; every other error triggers
+    /// compensation.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            ProcessError::Command(e) => e.is_transient(),
+            ProcessError::Store(e) => !matches!(e, ProcessStoreError::Validation(_)),
+            ProcessError::TimedOut | ProcessError::Failed(_) | ProcessError::State(_) => false,
+        }
+    }
+}
+
+/// Lifecycle of a [`ProcessInstance`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessStatus {
+    /// Waiting for its next event or deadline.
+    Running,
+    /// Reached [`Transition::Complete`].
+    Completed,
+    /// Failed and was compensated.
+    Compensated,
+    /// Failed and compensation failed too; needs an operator.
+    Failed,
+}
+
+impl ProcessStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProcessStatus::Running => "running",
+            ProcessStatus::Completed => "completed",
+            ProcessStatus::Compensated => "compensated",
+            ProcessStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "running" => Some(ProcessStatus::Running),
+            "completed" => Some(ProcessStatus::Completed),
+            "compensated" => Some(ProcessStatus::Compensated),
+            "failed" => Some(ProcessStatus::Failed),
+            _ => None,
+        }
+    }
+
+    /// Finished instances ignore further events and deadlines.
+    pub fn is_finished(&self) -> bool {
+        *self != ProcessStatus::Running
+    }
+}
+
+/// Persistent state of one run of a process.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessInstance {
+    pub process_name: String,
+    pub instance_id: String,
+    /// Correlation id of the event that started the instance. Commands
+    /// dispatched on timeout carry it.
+    pub correlation_id: Uuid,
+    pub status: ProcessStatus,
+    /// The manager's `State`, serialized.
+    pub state: serde_json::Value,
+    /// Starts at 1 and increases by one with every save.
+    pub version: i64,
+    /// When [`ProcessManager::on_timeout`] fires. `None` while no deadline
+    /// is armed.
+    pub deadline_us: Option<i64>,
+    /// Events already applied, so a redelivered event is not handled twice.
+    pub handled_event_ids: Vec<Uuid>,
+    /// Why the instance was compensated or failed.
+    pub failure: Option<String>,
+    pub created_at_us: i64,
+    pub updated_at_us: i64,
+}
+
+impl ProcessInstance {
+    /// A new, not yet saved instance (`version` 0; the first save stores 1).
+    pub fn start(
+        process_name: impl Into<String>,
+        instance_id: impl Into<String>,
+        correlation_id: Uuid,
+        now_us: i64,
+    ) -> Self {
+        Self {
+            process_name: process_name.into(),
+            instance_id: instance_id.into(),
+            correlation_id,
+            status: ProcessStatus::Running,
+            state: serde_json::Value::Null,
+            version: 0,
+            deadline_us: None,
+            handled_event_ids: Vec::new(),
+            failure: None,
+            created_at_us: now_us,
+            updated_at_us: now_us,
+        }
+    }
+
+    /// Whether a running instance's deadline has passed at `now_us`.
+    pub fn is_due_at(&self, now_us: i64) -> bool {
+        self.status == ProcessStatus::Running && self.deadline_us.is_some_and(|d| d <= now_us)
+    }
+
+    /// Shared input checks for [`ProcessStore::save`].
+    pub fn validate(&self) -> Result<(), ProcessStoreError> {
+        if self.process_name.trim().is_empty() {
+            return Err(ProcessStoreError::Validation("process_name empty".into()));
+        }
+        if self.instance_id.trim().is_empty() {
+            return Err(ProcessStoreError::Validation("instance_id empty".into()));
+        }
+        if self.version < 1 {
+            return Err(ProcessStoreError::Validation("version must be >= 1".into()));
+        }
+        Ok(())
+    }
+}
+
+/// Durable home of [`ProcessInstance`]s.
+///
+/// Implementations:
+/// - [`InMemoryProcessStore`] — `Arc<Mutex<HashMap>>`, behind `test-utils`
+/// - `SqliteProcessStore` — in `arc-es-sqlite`, durable
+#[async_trait]
+pub trait ProcessStore: Send + Sync {
+    async fn load(
+        &self,
+        process_name: &str,
+        instance_id: &str,
+    ) -> Result<Option<ProcessInstance>, ProcessStoreError>;
+
+    /// Insert (`version == 1`) or update the instance. An update succeeds
+    /// only while the stored row is at `version - 1`; otherwise it fails with
+    /// [`ProcessStoreError::Conflict`] and nothing is written.
+    async fn save(&self, instance: &ProcessInstance) -> Result<(), ProcessStoreError>;
+
+    /// Up to `limit` running instances of `process_name` whose deadline is
+    /// at or before `now_us`, earliest deadline first.
+    async fn due(
+        &self,
+        process_name: &str,
+        now_us: i64,
+        limit: usize,
+    ) -> Result<Vec<ProcessInstance>, ProcessStoreError>;
+}
+
+/// What a step asks the runner to do with the instance next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// Keep running and wait for the next event. Clears any deadline.
+    Await,
+    /// Keep running; call [`ProcessManager::on_timeout`] if nothing else
+    /// moves the instance on within the duration. Replaces any deadline.
+    AwaitWithin(Duration),
+    /// The process is done.
+    Complete,
+}
+
+/// Dispatches a step's commands through the router, on behalf of the
+/// process.
+///
+/// Each command gets the step's [`CommandContext`] with an idempotency key
+/// of `process:{name}:{instance}:{trigger}/{n}`, where `n` counts the
+/// commands dispatched so far in the step.
+pub struct ProcessCommands {
+    router: Arc<CommandRouter>,
+    context: CommandContext,
+    dispatched: AtomicU32,
+}
+
+impl ProcessCommands {
+    fn new(router: Arc<CommandRouter>, context: CommandContext, key: String) -> Self {
+        Self {
+            router,
+            context: context.with_idempotency_key(key),
+            dispatched: AtomicU32::new(0),
+        }
+    }
+
+    /// The context commands are dispatched with (before scoping the key).
+    pub fn context(&self) -> &CommandContext {
+        &self.context
+    }
+
+    pub async fn dispatch<C: Command + 'static>(
+        &self,
+        command: C,
+    ) -> Result<Vec<Event>, ProcessError> {
+        let n = self.dispatched.fetch_add(1, Ordering::Relaxed);
+        let context = self.context.clone().with_idempotency_scope(&n.to_string());
+        Ok(self.router.dispatch(command, context).await?)
+    }
+}
+
+/// A long-running workflow driven by events.
+///
+/// ```rust,ignore
+/// struct Onboarding;
+///
+/// #[async_trait]
+/// impl ProcessManager for Onboarding {
+///     type State = OnboardingState;
+///
+///     fn name(&self) -> &'static str { "onboarding" }
+///     fn handles(&self) -> Vec<String> { vec!["UserRegistered".into(), "EmailVerified".into()] }
+///     fn starts(&self, event: &Event) -> bool { event.event_type == "UserRegistered" }
+///     fn instance_id(&self, event: &Event) -> Option<String> { Some(event.aggregate_id.clone()) }
+///
+///     async fn react(&self, state: &mut OnboardingState, event: &Event, commands: &ProcessCommands)
+///         -> Result<Transition, ProcessError>
+///     {
+///         match event.event_type.as_str() {
+///             "UserRegistered" => Ok(Transition::AwaitWithin(Duration::from_secs(86_400))),
+///             _ => {
+///                 commands.dispatch(ProvisionWorkspace { .. }).await?;
+///                 Ok(Transition::Complete)
+///             }
+///         }
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait ProcessManager: Send + Sync + 'static {
+    /// Per-instance state, persisted as JSON between steps. A new instance
+    /// starts from `Default`.
+    type State: Serialize + DeserializeOwned + Default + Send + Sync;
+
+    /// Stable name, part of the instance key. Renaming orphans instances.
+    fn name(&self) -> &'static str;
+
+    /// Event types delivered to [`react`](Self::react).
+    fn handles(&self) -> Vec<String>;
+
+    /// Whether `event` starts a new instance when none exists for its id.
+    /// Events for an unknown instance that do not start one are ignored.
+    fn starts(&self, event: &Event) -> bool;
+
+    /// The instance `event` belongs to, or `None` to ignore it. Defaults to
+    /// the event's correlation id.
+    fn instance_id(&self, event: &Event) -> Option<String> {
+        Some(event.audit.correlation_id.to_string())
+    }
+
+    /// Advance `state` with `event`, dispatching any follow-up commands.
+    /// After a transient error the state changes are discarded and the step
+    /// runs again; after any other error they are kept for
+    /// [`compensate`](Self::compensate).
+    async fn react(
+        &self,
+        state: &mut Self::State,
+        event: &Event,
+        commands: &ProcessCommands,
+    ) -> Result<Transition, ProcessError>;
+
+    /// Called once an [`Transition::AwaitWithin`] deadline passes. Defaults
+    /// to failing with [`ProcessError::TimedOut`], which compensates.
+    async fn on_timeout(
+        &self,
+        _state: &mut Self::State,
+        _commands: &ProcessCommands,
+    ) -> Result<Transition, ProcessError> {
+        Err(ProcessError::TimedOut)
+    }
+
+    /// Undo what the instance has done so far after `error`. Defaults to
+    /// doing nothing.
+    async fn compensate(
+        &self,
+        _state: &Self::State,
+        _error: &ProcessError,
+        _commands: &ProcessCommands,
+    ) -> Result<(), ProcessError> {
+        Ok(())
+    }
+}
+
+/// Drives a [`ProcessManager`]: loads the instance an event belongs to, runs
+/// the step, dispatches through the [`CommandRouter`] and saves the result.
+///
+/// ```rust,ignore
+/// let runner = ProcessRunner::new(Onboarding::default(), Arc::new(store), router.clone());
+/// bus.subscribe_with(Box::new(runner.clone()), DeliveryMode::Background).await?;
+/// let (stop, stopped) = tokio::sync::watch::channel(false);
+/// tokio::spawn(async move { runner.run_timeouts(stopped).await });
+/// ```
+pub struct ProcessRunner<P: ProcessManager> {
+    process: Arc<P>,
+    store: Arc<dyn ProcessStore>,
+    router: Arc<CommandRouter>,
+    timeout_batch_size: usize,
+    timeout_poll_interval: Duration,
+}
+
+impl<P: ProcessManager> Clone for ProcessRunner<P> {
+    fn clone(&self) -> Self {
+        Self {
+            process: self.process.clone(),
+            store: self.store.clone(),
+            router: self.router.clone(),
+            timeout_batch_size: self.timeout_batch_size,
+            timeout_poll_interval: self.timeout_poll_interval,
+        }
+    }
+}
+
+impl<P: ProcessManager> ProcessRunner<P> {
+    pub fn new(process: P, store: Arc<dyn ProcessStore>, router: Arc<CommandRouter>) -> Self {
+        Self {
+            process: Arc::new(process),
+            store,
+            router,
+            timeout_batch_size: DEFAULT_TIMEOUT_BATCH_SIZE,
+            timeout_poll_interval: DEFAULT_TIMEOUT_POLL_INTERVAL,
+        }
+    }
+
+    /// Due instances handled per [`fire_timeouts`](Self::fire_timeouts)
+    /// call. Clamped to at least 1.
+    pub fn with_timeout_batch_size(mut self, batch_size: usize) -> Self {
+        self.timeout_batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Pause between deadline checks in [`run_timeouts`](Self::run_timeouts).
+    pub fn with_timeout_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.timeout_poll_interval = poll_interval;
+        self
+    }
+
+    pub fn process(&self) -> &P {
+        &self.process
+    }
+
+    /// Run the step `event` triggers, if any. Returns an error only when the
+    /// step should be retried (see [`ProcessError::is_transient`]).
+    pub async fn handle_event(&self, event: &Event) -> Result<(), ProcessError> {
+        let name = self.process.name();
+        let Some(instance_id) = self.process.instance_id(event) else {
+            return Ok(());
+        };
+        let now_us = crate::audit::now_us();
+        let mut instance = match self.store.load(name, &instance_id).await? {
+            Some(instance) => instance,
+            None if self.process.starts(event) => {
+                ProcessInstance::start(name, &instance_id, event.audit.correlation_id, now_us)
+            }
+            None => return Ok(()),
+        };
+        if instance.status.is_finished() || instance.handled_event_ids.contains(&event.event_id) {
+            return Ok(());
+        }
+
+        let commands = ProcessCommands::new(
+            self.router.clone(),
+            CommandContext::caused_by(SYSTEM_ACTOR, event),
+            format!("process:{name}:{instance_id}:{}", event.event_id),
+        );
+        instance.handled_event_ids.push(event.event_id);
+        let mut state = match self.decode_state(&instance) {
+            Ok(state) => state,
+            Err(e) => return self.abandon(instance, e.into(), now_us).await,
+        };
+        let result = self.process.react(&mut state, event, &commands).await;
+        self.settle(instance, state, result, &commands, now_us)
+            .await
+    }
+
+    /// Fire [`ProcessManager::on_timeout`] for up to one batch of instances
+    /// whose deadline has passed at `now_us`. Returns how many were handled.
+    /// Instances whose step fails transiently stay due for the next call.
+    pub async fn fire_timeouts(&self, now_us: i64) -> Result<usize, ProcessError> {
+        let name = self.process.name();
+        let due = self
+            .store
+            .due(name, now_us, self.timeout_batch_size)
+            .await?;
+        let mut fired = 0;
+        for instance in due {
+            let instance_id = instance.instance_id.clone();
+            let mut context = CommandContext::system();
+            context.correlation_id = instance.correlation_id;
+            let commands = ProcessCommands::new(
+                self.router.clone(),
+                context,
+                format!(
+                    "process:{name}:{instance_id}:timeout:{}",
+                    instance.deadline_us.unwrap_or_default()
+                ),
+            );
+            let result = match self.decode_state(&instance) {
+                Ok(mut state) => {
+                    let result = self.process.on_timeout(&mut state, &commands).await;
+                    self.settle(instance, state, result, &commands, now_us)
+                        .await
+                }
+                Err(e) => self.abandon(instance, e.into(), now_us).await,
+            };
+            match result {
+                Ok(()) => fired += 1,
+                Err(e) => tracing::warn!(
+                    process = name,
+                    instance_id = %instance_id,
+                    error = %e,
+                    "Process timeout failed; will retry"
+                ),
+            }
+        }
+        Ok(fired)
+    }
+
+    /// Fire timeouts every `timeout_poll_interval` until `shutdown` turns
+    /// `true` (or its sender is dropped). Store errors are logged and
+    /// retried on the next tick.
+    pub async fn run_timeouts(&self, mut shutdown: watch::Receiver<bool>) {
+        let name = self.process.name();
+        tracing::info!(process = name, "Process timeout loop started");
+        while !*shutdown.borrow() {
+            if let Err(e) = self.fire_timeouts(crate::audit::now_us()).await {
+                tracing::error!(process = name, error = %e, "Process timeout poll failed");
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(self.timeout_poll_interval) => {}
+                changed = shutdown.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+        tracing::info!(process = name, "Process timeout loop stopped");
+    }
+
+    /// A new instance starts from `State::default()`.
+    fn decode_state(&self, instance: &ProcessInstance) -> Result<P::State, serde_json::Error> {
+        if instance.version == 0 {
+            return Ok(P::State::default());
+        }
+        serde_json::from_value(instance.state.clone())
+    }
+
+    /// Apply a step's outcome to `instance` and save it. Transient errors
+    /// are returned without saving, so the step runs again; others are
+    /// compensated. State changes made before a permanent error are kept
+    /// and handed to [`ProcessManager::compensate`].
+    async fn settle(
+        &self,
+        mut instance: ProcessInstance,
+        state: P::State,
+        result: Result<Transition, ProcessError>,
+        commands: &ProcessCommands,
+        now_us: i64,
+    ) -> Result<(), ProcessError> {
+        match result {
+            Ok(Transition::Await) => instance.deadline_us = None,
+            Ok(Transition::AwaitWithin(timeout)) => {
+                let timeout_us = i64::try_from(timeout.as_micros()).unwrap_or(i64::MAX);
+                instance.deadline_us = Some(now_us.saturating_add(timeout_us));
+            }
+            Ok(Transition::Complete) => {
+                instance.status = ProcessStatus::Completed;
+                instance.deadline_us = None;
+            }
+            Err(e) if e.is_transient() => return Err(e),
+            Err(e) => {
+                tracing::warn!(
+                    process = self.process.name(),
+                    instance_id = %instance.instance_id,
+                    error = %e,
+                    "Process step failed; compensating"
+                );
+                instance.deadline_us = None;
+                match self.process.compensate(&state, &e, commands).await {
+                    Ok(()) => {
+                        instance.status = ProcessStatus::Compensated;
+                        instance.failure = Some(e.to_string());
+                    }
+                    Err(ce) if ce.is_transient() => return Err(ce),
+                    Err(ce) => {
+                        tracing::error!(
+                            process = self.process.name(),
+                            instance_id = %instance.instance_id,
+                            error = %ce,
+                            "Process compensation failed"
+                        );
+                        instance.status = ProcessStatus::Failed;
+                        instance.failure = Some(format!("{e}; compensation failed: {ce}"));
+                    }
+                }
+            }
+        }
+        instance.state = serde_json::to_value(&state)?;
+        self.save(instance, now_us).await
+    }
+
+    /// Mark an instance whose state no longer decodes as failed. Nothing is
+    /// compensated: there is no state to compensate from.
+    async fn abandon(
+        &self,
+        mut instance: ProcessInstance,
+        error: ProcessError,
+        now_us: i64,
+    ) -> Result<(), ProcessError> {
+        tracing::error!(
+            process = self.process.name(),
+            instance_id = %instance.instance_id,
+            error = %error,
+            "Process instance abandoned"
+        );
+        instance.status = ProcessStatus::Failed;
+        instance.deadline_us = None;
+        instance.failure = Some(error.to_string());
+        self.save(instance, now_us).await
+    }
+
+    async fn save(&self, mut instance: ProcessInstance, now_us: i64) -> Result<(), ProcessError> {
+        instance.version += 1;
+        instance.updated_at_us = now_us;
+        self.store.save(&instance).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<P: ProcessManager> EventHandler for ProcessRunner<P> {
+    fn handles(&self) -> Vec<String> {
+        self.process.handles()
+    }
+
+    async fn handle(&self, event: &Event) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        Ok(self.handle_event(event).await?)
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// In-memory implementation. Public behind the `test-utils` feature so
+// downstream tests can use it.
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(any(test, feature = "test-utils"))]
+mod in_memory {
+    use super::*;
+    use std::collections::HashMap;
+    use tokio::sync::Mutex;
+
+    #[derive(Clone, Default)]
+    pub struct InMemoryProcessStore {
+        inner: Arc<Mutex<HashMap<(String, String), ProcessInstance>>>,
+    }
+
+    impl InMemoryProcessStore {
+        pub fn new() -> Self {
+            Self::default()
+        }
+    }
+
+    #[async_trait]
+    impl ProcessStore for InMemoryProcessStore {
+        async fn load(
+            &self,
+            process_name: &str,
+            instance_id: &str,
+        ) -> Result<Option<ProcessInstance>, ProcessStoreError> {
+            let g = self.inner.lock().await;
+            Ok(g.get(&(process_name.to_string(), instance_id.to_string()))
+                .cloned())
+        }
+
+        async fn save(&self, instance: &ProcessInstance) -> Result<(), ProcessStoreError> {
+            instance.validate()?;
+            let mut g = self.inner.lock().await;
+            let slot = (instance.process_name.clone(), instance.instance_id.clone());
+            let stored = g.get(&slot).map_or(0, |i| i.version);
+            if stored != instance.version - 1 {
+                return Err(ProcessStoreError::conflict(
+                    &instance.process_name,
+                    &instance.instance_id,
+                ));
+            }
+            g.insert(slot, instance.clone());
+            Ok(())
+        }
+
+        async fn due(
+            &self,
+            process_name: &str,
+            now_us: i64,
+            limit: usize,
+        ) -> Result<Vec<ProcessInstance>, ProcessStoreError> {
+            let g = self.inner.lock().await;
+            let mut due: Vec<_> = g
+                .values()
+                .filter(|i| i.process_name == process_name && i.is_due_at(now_us))
+                .cloned()
+                .collect();
+            due.sort_by_key(|i| i.deadline_us);
+            due.truncate(limit);
+            Ok(due)
+        }
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+pub use in_memory::InMemoryProcessStore;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::Aggregate;
+    use crate::event_bus::InProcessEventBus;
+    use crate::event_store::InMemoryEventStore;
+    use crate::idempotency::{InMemoryIdempotencyStore, DEFAULT_IDEMPOTENCY_TTL};
+    use serde_json::json;
+
+    const NOW: i64 = 1_700_000_000_000_000;
+
+    /// Emits one event of type `emit`, or rejects the command when `emit`
+    /// is `"reject"`.
+    #[derive(Debug, Clone)]
+    struct Emit {
+        id: String,
+        emit: &'static str,
+    }
+
+    impl Command for Emit {
+        fn aggregate_id(&self) -> &str {
+            &self.id
+        }
+    }
+
+    #[derive(Default)]
+    struct Probe {
+        version: i64,
+    }
+
+    #[derive(Debug, Error)]
+    #[error("rejected")]
+    struct Rejected;
+
+    #[async_trait]
+    impl Aggregate for Probe {
+        type Command = Emit;
+        type Event = ();
+        type Error = Rejected;
+
+        fn aggregate_type() -> &'static str {
+            "Probe"
+        }
+
+        fn version(&self) -> i64 {
+            self.version
+        }
+
+        async fn handle(&self, command: Emit) -> Result<Vec<Event>, Rejected> {
+            if command.emit == "reject" {
+                return Err(Rejected);
+            }
+            Ok(vec![Event::new(
+                "Probe",
+                &command.id,
+                self.version + 1,
+                command.emit,
+                json!({}),
+            )])
+        }
+
+        fn apply(&mut self, event: &Event) {
+            self.version = event.sequence;
+        }
+    }
+
+    #[derive(Default, Serialize, Deserialize)]
+    struct FlowState {
+        probe_id: String,
+        steps: u32,
+    }
+
+    /// `Opened` starts a flow that waits a second for `Confirmed` (emits
+    /// `Finished`) or `Broken` (dispatches a rejected command). Compensation
+    /// emits `Undone`.
+    struct Flow;
+
+    #[async_trait]
+    impl ProcessManager for Flow {
+        type State = FlowState;
+
+        fn name(&self) -> &'static str {
+            "flow"
+        }
+
+        fn handles(&self) -> Vec<String> {
+            vec!["Opened".into(), "Confirmed".into(), "Broken".into()]
+        }
+
+        fn starts(&self, event: &Event) -> bool {
+            event.event_type == "Opened"
+        }
+
+        async fn react(
+            &self,
+            state: &mut FlowState,
+            event: &Event,
+            commands: &ProcessCommands,
+        ) -> Result<Transition, ProcessError> {
+            state.steps += 1;
+            match event.event_type.as_str() {
+                "Opened" => {
+                    state.probe_id = event.aggregate_id.clone();
+                    Ok(Transition::AwaitWithin(Duration::from_secs(1)))
+                }
+                "Confirmed" => {
+                    commands
+                        .dispatch(Emit {
+                            id: state.probe_id.clone(),
+                            emit: "Finished",
+                        })
+                        .await?;
+                    Ok(Transition::Complete)
+                }
+                _ => {
+                    commands
+                        .dispatch(Emit {
+                            id: state.probe_id.clone(),
+                            emit: "reject",
+                        })
+                        .await?;
+                    Ok(Transition::Await)
+                }
+            }
+        }
+
+        async fn compensate(
+            &self,
+            state: &FlowState,
+            _error: &ProcessError,
+            commands: &ProcessCommands,
+        ) -> Result<(), ProcessError> {
+            commands
+                .dispatch(Emit {
+                    id: state.probe_id.clone(),
+                    emit: "Undone",
+                })
+                .await?;
+            Ok(())
+        }
+    }
+
+    struct Harness {
+        runner: ProcessRunner<Flow>,
+        router: Arc<CommandRouter>,
+        store: InMemoryProcessStore,
+        context: CommandContext,
+    }
+
+    fn harness() -> Harness {
+        let store = InMemoryProcessStore::new();
+        let router = Arc::new(
+            CommandRouter::new(
+                Box::new(InMemoryEventStore::new()),
+                Box::new(InProcessEventBus::new()),
+            )
+            .with_idempotency(
+                Arc::new(InMemoryIdempotencyStore::new()),
+                DEFAULT_IDEMPOTENCY_TTL,
+            )
+            .register::<Probe>(),
+        );
+        Harness {
+            runner: ProcessRunner::new(Flow, Arc::new(store.clone()), router.clone()),
+            router,
+            store,
+            context: CommandContext::for_actor("alice"),
+        }
+    }
+
+    impl Harness {
+        /// Dispatch `emit` on probe `p1` as alice and feed the event to the
+        /// runner.
+        async fn trigger(&self, emit: &'static str) -> Event {
+            let event = self
+                .router
+                .dispatch(
+                    Emit {
+                        id: "p1".into(),
+                        emit,
+                    },
+                    self.context.clone(),
+                )
+                .await
+                .unwrap()
+                .remove(0);
+            self.runner.handle_event(&event).await.unwrap();
+            event
+        }
+
+        async fn instance(&self) -> ProcessInstance {
+            let id = self.context.correlation_id.to_string();
+            self.store.load("flow", &id).await.unwrap().unwrap()
+        }
+
+        async fn probe_events(&self) -> Vec<String> {
+            let events = self.router.event_store().load("p1").await.unwrap();
+            events.into_iter().map(|e| e.event_type).collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_instance_follows_events_to_completion() {
+        let h = harness();
+        h.trigger("Opened").await;
+        let instance = h.instance().await;
+        assert_eq!(instance.status, ProcessStatus::Running);
+        assert_eq!(instance.version, 1);
+        assert!(instance.deadline_us.is_some());
+        assert_eq!(instance.state["probe_id"], "p1");
+
+        h.trigger("Confirmed").await;
+        let instance = h.instance().await;
+        assert_eq!(instance.status, ProcessStatus::Completed);
+        assert_eq!(instance.deadline_us, None);
+        assert_eq!(instance.state["steps"], 2);
+        assert_eq!(h.probe_events().await, ["Opened", "Confirmed", "Finished"]);
+
+        let finished = h.router.event_store().load("p1").await.unwrap().remove(2);
+        assert_eq!(finished.audit.actor_id, SYSTEM_ACTOR);
+        assert_eq!(finished.audit.correlation_id, h.context.correlation_id);
+    }
+
+    #[tokio::test]
+    async fn test_redelivered_and_unrelated_events_are_ignored() {
+        let h = harness();
+        h.trigger("Confirmed").await;
+        assert!(h
+            .store
+            .load("flow", &h.context.correlation_id.to_string())
+            .await
+            .unwrap()
+            .is_none());
+
+        let opened = h.trigger("Opened").await;
+        h.runner.handle_event(&opened).await.unwrap();
+        let instance = h.instance().await;
+        assert_eq!(instance.version, 1);
+        assert_eq!(instance.state["steps"], 1);
+        assert_eq!(instance.handled_event_ids, vec![opened.event_id]);
+    }
+
+    #[tokio::test]
+    async fn test_rejected_command_is_compensated() {
+        let h = harness();
+        h.trigger("Opened").await;
+        h.trigger("Broken").await;
+        let instance = h.instance().await;
+        assert_eq!(instance.status, ProcessStatus::Compensated);
+        assert!(instance.failure.unwrap().contains("rejected"));
+        assert_eq!(h.probe_events().await, ["Opened", "Broken", "Undone"]);
+    }
+
+    #[tokio::test]
+    async fn test_timeouts_fire_once_the_deadline_passes() {
+        let h = harness();
+        h.trigger("Opened").await;
+        let deadline = h.instance().await.deadline_us.unwrap();
+
+        assert_eq!(h.runner.fire_timeouts(deadline - 1).await.unwrap(), 0);
+        assert_eq!(h.runner.fire_timeouts(deadline).await.unwrap(), 1);
+        let instance = h.instance().await;
+        assert_eq!(instance.status, ProcessStatus::Compensated);
+        assert_eq!(instance.failure.as_deref(), Some("process timed out"));
+        assert_eq!(h.probe_events().await, ["Opened", "Undone"]);
+
+        let undone = h.router.event_store().load("p1").await.unwrap().remove(1);
+        assert_eq!(undone.audit.correlation_id, h.context.correlation_id);
+        assert_eq!(h.runner.fire_timeouts(deadline).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_store_rejects_stale_saves() {
+        let store = InMemoryProcessStore::new();
+        let mut instance = ProcessInstance::start("flow", "i1", Uuid::new_v4(), NOW);
+        assert!(matches!(
+            store.save(&instance).await.unwrap_err(),
+            ProcessStoreError::Validation(_)
+        ));
+
+        instance.version = 1;
+        instance.deadline_us = Some(NOW);
+        store.save(&instance).await.unwrap();
+        assert!(matches!(
+            store.save(&instance).await.unwrap_err(),
+            ProcessStoreError::Conflict { .. }
+        ));
+        assert!(ProcessError::from(ProcessStoreError::conflict("flow", "i1")).is_transient());
+
+        assert_eq!(store.due("flow", NOW - 1, 10).await.unwrap().len(), 0);
+        assert_eq!(store.due("flow", NOW, 10).await.unwrap().len(), 1);
+        assert_eq!(store.due("other", NOW, 10).await.unwrap().len(), 0);
+    }
+}
@@ -15,9 +15,16 @@
 //!   (read model store) and orchestration (projection engine)
 //! - **Stateless projectors**: Projectors take `&self`, not `&mut self`. All mutable
 //!   state lives in the `ReadModelStore` via interior mutability.
-//! - **Rebuildable**: Projections can be rebuilt from scratch by replaying events
+//! - **Rebuildable**: Projections can be rebuilt from scratch by replaying events,
+//!   into a shadow table that is swapped in when complete where the projector
+//!   declares its schema ([`ProjectionEngine::rebuild_projection`])
+//! - **Resumable**: With a [`CheckpointStore`], each projection records the last
+//!   global position it applied and [`ProjectionEngine::catch_up`] resumes there
 //! - **Idempotent**: Handling the same event multiple times should be safe
 //! - **Composable**: One projector per read model concern; swap backends freely
+//! - **Self-describing**: A projector declares its table and indexes with
+//!   [`Projector::schema`]; [`ProjectionEngine::init_all`] applies them, so a
+//!   new projection needs no migration
 //!
 //! ## Example
 //!
@@ -59,11 +66,12 @@
 //! engine.process(&event).await?;
 //! ```
 
-use crate::event::Event;
+use crate::event::{Event, GlobalPosition};
 use crate::event_bus::EventHandler;
 use crate::event_store::EventStore;
-use crate::read_model_store::ReadModelStore;
+use crate::read_model_store::{ReadModelSchema, ReadModelStore, TableRedirect, WriteBuffer};
 use async_trait::async_trait;
+use futures::StreamExt;
 use std::sync::Arc;
 use thiserror::Error;
 
@@ -101,6 +109,10 @@ pub enum ProjectionError {
     #[error("Read model store error in projection '{name}': {message}")]
     ReadModelError { name: String, message: String },
 
+    /// Checkpoint store error
+    #[error("Checkpoint error for projection '{name}': {message}")]
+    CheckpointFailed { name: String, message: String },
+
     /// Other errors
     #[error("Projection error: {message}")]
     Other { message: String },
@@ -138,6 +150,14 @@ impl ProjectionError {
         }
     }
 
+    /// Create a checkpoint failed error.
+    pub fn checkpoint_failed(name: impl Into<String>, message: impl Into<String>) -> Self {
+        ProjectionError::CheckpointFailed {
+            name: name.into(),
+            message: message.into(),
+        }
+    }
+
     /// Create a read model error.
     pub fn read_model_error(name: impl Into<String>, message: impl Into<String>) -> Self {
         ProjectionError::ReadModelError {
@@ -220,15 +240,40 @@ pub trait Projector: Send + Sync {
     /// must produce the same result.
     async fn apply(&self, event: &Event, store: &dyn ReadModelStore) -> ProjectionResult<()>;
 
-    /// Initialize the read model schema (CREATE TABLE IF NOT EXISTS, etc.).
+    /// The table this projector writes and the indexes its readers rely on.
     ///
-    /// Called once when the projector is first registered and before rebuilds.
-    /// Default implementation does nothing (for stores that don't need schema setup).
+    /// Applied with [`ReadModelStore::ensure_schema`] before [`init`](Self::init),
+    /// at startup and before rebuilds. `None` (the default) leaves the table
+    /// to migrations.
+    fn schema(&self) -> Option<ReadModelSchema> {
+        None
+    }
+
+    /// Prepare the read model beyond its declared [`schema`](Self::schema),
+    /// e.g. seed reference rows.
+    ///
+    /// Called at startup and before rebuilds, after the schema is applied.
+    /// Default implementation does nothing.
     async fn init(&self, _store: &dyn ReadModelStore) -> ProjectionResult<()> {
         Ok(())
     }
 }
 
+/// Apply `projector`'s declared schema to `store`, then run its
+/// [`init`](Projector::init).
+pub async fn init_projector(
+    projector: &dyn Projector,
+    store: &dyn ReadModelStore,
+) -> ProjectionResult<()> {
+    if let Some(schema) = projector.schema() {
+        store
+            .ensure_schema(&schema)
+            .await
+            .map_err(|e| ProjectionError::read_model_error(projector.name(), e.to_string()))?;
+    }
+    projector.init(store).await
+}
+
 // ---------------------------------------------------------------------------
 // Projection trait — the composed read model unit
 // ---------------------------------------------------------------------------
@@ -253,12 +298,54 @@ pub trait Projection: Send + Sync {
     /// Event types this projection handles (delegates to the projector).
     fn handles(&self) -> Vec<String>;
 
+    /// Prepare the read model's storage. Idempotent; called at startup and
+    /// before rebuilds. Default does nothing.
+    async fn init(&self) -> ProjectionResult<()> {
+        Ok(())
+    }
+
     /// Handle a single event by applying it through the projector to the store.
     async fn handle(&self, event: &Event) -> ProjectionResult<()>;
 
+    /// Handle the events this projection is interested in from `events`, in
+    /// order. Rebuilds feed pages through here; implementations should make
+    /// the page's writes land together. The default handles them one by one.
+    async fn handle_batch(&self, events: &[Event]) -> ProjectionResult<()> {
+        let handles = self.handles();
+        for event in events.iter().filter(|e| handles.contains(&e.event_type)) {
+            self.handle(event).await?;
+        }
+        Ok(())
+    }
+
     /// Clear all read model state for this projection.
     async fn clear(&self) -> ProjectionResult<()>;
 
+    /// A copy of this projection writing to an empty shadow table beside
+    /// the live one, for blue/green rebuilds. `None` (the default) means the
+    /// projection can only be rebuilt in place.
+    fn shadow(&self) -> Option<Box<dyn Projection>> {
+        None
+    }
+
+    /// Atomically make the table filled through [`shadow`](Self::shadow)
+    /// live, keeping the replaced one for [`restore_retired`](Self::restore_retired).
+    async fn promote_shadow(&self) -> ProjectionResult<()> {
+        Err(ProjectionError::other(format!(
+            "{} has no shadow table",
+            self.name()
+        )))
+    }
+
+    /// Swap the table replaced by the last
+    /// [`promote_shadow`](Self::promote_shadow) back in.
+    async fn restore_retired(&self) -> ProjectionResult<()> {
+        Err(ProjectionError::other(format!(
+            "{} has no retired table",
+            self.name()
+        )))
+    }
+
     /// Rebuild from a set of events: clear, then replay matching events.
     async fn rebuild(&self, events: Vec<Event>) -> ProjectionResult<()> {
         self.clear().await?;
@@ -290,7 +377,7 @@ pub trait Projection: Send + Sync {
 /// engine.register(Box::new(projection));
 /// ```
 pub struct ProjectionUnit {
-    projector: Box<dyn Projector>,
+    projector: Arc<dyn Projector>,
     store: Arc<dyn ReadModelStore>,
     /// Table/collection name used for `clear()` (truncate target).
     table: String,
@@ -310,11 +397,36 @@ impl ProjectionUnit {
         table: impl Into<String>,
     ) -> Self {
         Self {
-            projector,
+            projector: projector.into(),
             store,
             table: table.into(),
         }
     }
+
+    /// Swap `incoming` in for the live table, leaving it as `outgoing`.
+    async fn swap(&self, incoming: &str, outgoing: &str) -> ProjectionResult<()> {
+        let schema = self.projector.schema().ok_or_else(|| {
+            ProjectionError::other(format!(
+                "{} declares no schema to swap tables with",
+                self.projector.name()
+            ))
+        })?;
+        self.store
+            .swap_tables(&schema, incoming, outgoing)
+            .await
+            .map_err(|e| ProjectionError::read_model_error(self.projector.name(), e.to_string()))
+    }
+}
+
+/// Table a blue/green rebuild of `table` fills before swapping it in.
+pub fn shadow_table(table: &str) -> String {
+    format!("{table}__next")
+}
+
+/// Table the previous contents of `table` are kept in after a blue/green
+/// rebuild swaps, until the next one.
+pub fn retired_table(table: &str) -> String {
+    format!("{table}__prev")
 }
 
 #[async_trait]
@@ -327,18 +439,138 @@ impl Projection for ProjectionUnit {
         self.projector.handles()
     }
 
+    async fn init(&self) -> ProjectionResult<()> {
+        if let Some(schema) = self.projector.schema() {
+            if schema.table != self.table {
+                return Err(ProjectionError::other(format!(
+                    "{} declares table {} but is registered for {}",
+                    self.projector.name(),
+                    schema.table,
+                    self.table
+                )));
+            }
+        }
+        init_projector(self.projector.as_ref(), self.store.as_ref()).await
+    }
+
     async fn handle(&self, event: &Event) -> ProjectionResult<()> {
         self.projector.apply(event, self.store.as_ref()).await
     }
 
+    /// Runs the projector against a [`WriteBuffer`] and commits the whole
+    /// page with one [`apply_batch`](ReadModelStore::apply_batch): one
+    /// transaction instead of one per row.
+    async fn handle_batch(&self, events: &[Event]) -> ProjectionResult<()> {
+        let buffer = WriteBuffer::new(self.store.as_ref());
+        let handles = self.projector.handles();
+        for event in events.iter().filter(|e| handles.contains(&e.event_type)) {
+            self.projector.apply(event, &buffer).await?;
+        }
+        buffer
+            .commit()
+            .await
+            .map_err(|e| ProjectionError::read_model_error(self.projector.name(), e.to_string()))
+    }
+
     async fn clear(&self) -> ProjectionResult<()> {
         self.store
             .truncate(&self.table)
             .await
             .map_err(|e| ProjectionError::clear_failed(self.projector.name(), e.to_string()))
     }
+
+    /// The same projector over a [`TableRedirect`] to
+    /// [`shadow_table`]. Needs a declared schema, so the shadow gets the
+    /// live table's indexes, and a store that can swap tables.
+    fn shadow(&self) -> Option<Box<dyn Projection>> {
+        if self.projector.schema().is_none() || !self.store.supports_table_swap() {
+            return None;
+        }
+        let store = TableRedirect::new(self.store.clone(), &self.table, shadow_table(&self.table));
+        Some(Box::new(ProjectionUnit {
+            projector: self.projector.clone(),
+            store: Arc::new(store),
+            table: self.table.clone(),
+        }))
+    }
+
+    async fn promote_shadow(&self) -> ProjectionResult<()> {
+        self.swap(&shadow_table(&self.table), &retired_table(&self.table))
+            .await
+    }
+
+    async fn restore_retired(&self) -> ProjectionResult<()> {
+        self.swap(&retired_table(&self.table), &shadow_table(&self.table))
+            .await
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Checkpoints — last applied global position per projection
+// ---------------------------------------------------------------------------
+
+/// Durable record of how far each projection has read the global log.
+///
+/// The [`ProjectionEngine`] saves a checkpoint as
+/// [`catch_up`](ProjectionEngine::catch_up) and rebuilds read the log in
+/// order, and `catch_up` resumes from it. Live [`process`](ProjectionEngine::process)
+/// never saves one: it may see position N+1 before N, and a checkpoint past
+/// an unapplied event would make `catch_up` skip it. Checkpoints only ever
+/// move forward: `save` with a position at or below the stored one is a
+/// no-op, which keeps them from rewinding but not from skipping.
+#[async_trait]
+pub trait CheckpointStore: Send + Sync {
+    /// Last position `projection` has applied, or `None` if it never ran.
+    async fn load(&self, projection: &str) -> ProjectionResult<Option<GlobalPosition>>;
+
+    /// Record that `projection` has applied everything up to `position`.
+    async fn save(&self, projection: &str, position: GlobalPosition) -> ProjectionResult<()>;
+
+    /// Forget the checkpoint, e.g. before a rebuild clears the read model.
+    async fn reset(&self, projection: &str) -> ProjectionResult<()>;
 }
 
+#[cfg(any(test, feature = "test-utils"))]
+mod in_memory {
+    use super::*;
+    use std::collections::HashMap;
+    use tokio::sync::Mutex;
+
+    /// [`CheckpointStore`] kept in a `HashMap`. Lost on restart.
+    #[derive(Clone, Default)]
+    pub struct InMemoryCheckpointStore {
+        positions: Arc<Mutex<HashMap<String, GlobalPosition>>>,
+    }
+
+    impl InMemoryCheckpointStore {
+        pub fn new() -> Self {
+            Self::default()
+        }
+    }
+
+    #[async_trait]
+    impl CheckpointStore for InMemoryCheckpointStore {
+        async fn load(&self, projection: &str) -> ProjectionResult<Option<GlobalPosition>> {
+            Ok(self.positions.lock().await.get(projection).copied())
+        }
+
+        async fn save(&self, projection: &str, position: GlobalPosition) -> ProjectionResult<()> {
+            let mut positions = self.positions.lock().await;
+            let entry = positions.entry(projection.to_string()).or_insert(position);
+            *entry = (*entry).max(position);
+            Ok(())
+        }
+
+        async fn reset(&self, projection: &str) -> ProjectionResult<()> {
+            self.positions.lock().await.remove(projection);
+            Ok(())
+        }
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+pub use in_memory::InMemoryCheckpointStore;
+
 // ---------------------------------------------------------------------------
 // ProjectionEngine — orchestrates multiple projections
 // ---------------------------------------------------------------------------
@@ -349,6 +581,8 @@ impl Projection for ProjectionUnit {
 /// - Registers fully composed [`Projection`] instances
 /// - Routes events to interested projections
 /// - Rebuilds projections from the event store
+/// - Checkpoints progress and catches up from it (see
+///   [`with_checkpoint_store`](Self::with_checkpoint_store))
 /// - Provides convenience registration via [`register_projector`](Self::register_projector)
 ///
 /// # Example
@@ -372,17 +606,39 @@ impl Projection for ProjectionUnit {
 pub struct ProjectionEngine {
     projections: Vec<Box<dyn Projection>>,
     event_store: Box<dyn EventStore>,
+    rebuild_page_size: usize,
+    checkpoints: Option<Arc<dyn CheckpointStore>>,
 }
 
+/// Events fetched per page while rebuilding.
+pub const DEFAULT_REBUILD_PAGE_SIZE: usize = 1000;
+
 impl ProjectionEngine {
     /// Create a new projection engine.
     pub fn new(event_store: Box<dyn EventStore>) -> Self {
         Self {
             projections: Vec::new(),
             event_store,
+            rebuild_page_size: DEFAULT_REBUILD_PAGE_SIZE,
+            checkpoints: None,
         }
     }
 
+    /// Record each projection's last applied [`GlobalPosition`] in `store`,
+    /// enabling [`catch_up`](Self::catch_up). Without one the engine keeps
+    /// no record and only full rebuilds can repair a missed event.
+    pub fn with_checkpoint_store(mut self, store: Arc<dyn CheckpointStore>) -> Self {
+        self.checkpoints = Some(store);
+        self
+    }
+
+    /// Set how many events a rebuild fetches from the store per page.
+    /// Bounds rebuild memory regardless of history size. Clamped to at least 1.
+    pub fn with_rebuild_page_size(mut self, page_size: usize) -> Self {
+        self.rebuild_page_size = page_size.max(1);
+        self
+    }
+
     /// Register a fully composed projection.
     pub fn register(&mut self, projection: Box<dyn Projection>) {
         tracing::info!("Registering projection: {}", projection.name());
@@ -400,9 +656,21 @@ impl ProjectionEngine {
         self.register(Box::new(unit));
     }
 
+    /// [`init`](Projection::init) every registered projection: create
+    /// declared tables and reconcile their indexes. Call once at startup,
+    /// before processing events; rebuilds repeat it for their targets.
+    pub async fn init_all(&self) -> ProjectionResult<()> {
+        for projection in &self.projections {
+            projection.init().await?;
+        }
+        Ok(())
+    }
+
     /// Process a single event through all interested projections.
     ///
     /// Routes the event to projections whose `handles()` includes the event type.
+    /// Checkpoints are left alone, since live events may arrive out of order;
+    /// [`catch_up`](Self::catch_up) advances them from the log.
     pub async fn process(&self, event: &Event) -> ProjectionResult<()> {
         for projection in &self.projections {
             if projection.handles().contains(&event.event_type) {
@@ -434,57 +702,298 @@ impl ProjectionEngine {
         Ok(())
     }
 
-    /// Rebuild all registered projections from the event store.
+    /// Rebuild all registered projections from the event store, in place.
+    ///
+    /// Clears every projection, then streams the log once in pages of
+    /// `rebuild_page_size`, so the full history is never held in memory.
     pub async fn rebuild_all(&self) -> ProjectionResult<()> {
         tracing::info!("Rebuilding all projections");
 
-        let events = self
-            .event_store
-            .stream_all(0)
-            .await
-            .map_err(|e| ProjectionError::EventStoreError(e.to_string()))?;
+        let targets: Vec<&dyn Projection> = self.projections.iter().map(|p| p.as_ref()).collect();
+        self.replay(&targets).await?;
 
-        tracing::info!("Loaded {} events for rebuild", events.len());
+        for projection in &targets {
+            tracing::info!("Rebuilt projection: {}", projection.name());
+        }
+        Ok(())
+    }
 
-        for projection in &self.projections {
-            tracing::info!("Rebuilding projection: {}", projection.name());
+    /// Rebuild a specific projection by name, without taking its read
+    /// model offline.
+    ///
+    /// When the projection offers a [`shadow`](Projection::shadow) the
+    /// rebuild is blue/green: the shadow table is filled from the start of
+    /// the log and caught up to the head while the live table keeps serving
+    /// reads and taking new events, then
+    /// [`promote_shadow`](Projection::promote_shadow) swaps it in atomically
+    /// and the events the old table took in the meantime are applied to it.
+    /// The old table is kept for [`rollback_projection`](Self::rollback_projection).
+    /// A failure before the swap leaves the live table untouched.
+    ///
+    /// Other projections are cleared and replayed in place, as by
+    /// [`rebuild_all`](Self::rebuild_all).
+    pub async fn rebuild_projection(&self, name: &str) -> ProjectionResult<()> {
+        tracing::info!("Rebuilding projection: {}", name);
+
+        let projection = self.projection(name)?;
+        match projection.shadow() {
+            Some(shadow) => self.rebuild_in_shadow(projection, shadow.as_ref()).await?,
+            None => {
+                tracing::warn!(
+                    "Projection {} has no shadow table; rebuilding in place",
+                    name
+                );
+                self.replay(&[projection]).await?
+            }
+        }
+
+        tracing::info!("Rebuilt projection: {}", name);
+        Ok(())
+    }
+
+    /// Swap the table the last blue/green rebuild of `name` replaced back
+    /// in, then bring it up to date. The rebuilt table becomes the shadow
+    /// and is discarded by the next rebuild.
+    ///
+    /// The restored table is fed from the checkpoint it had at the swap.
+    /// Without a checkpoint store, or if it had none, the whole log is
+    /// replayed; projectors are idempotent, so that only applies what it
+    /// missed.
+    pub async fn rollback_projection(&self, name: &str) -> ProjectionResult<()> {
+        let projection = self.projection(name)?;
+        let retired = retired_checkpoint(name);
+        let swapped_at = match &self.checkpoints {
+            Some(checkpoints) => checkpoints.load(&retired).await?,
+            None => None,
+        };
+        projection.restore_retired().await?;
+
+        let (read, last) = self.feed(&[projection], resume_after(swapped_at)).await?;
+        if let Some(position) = last {
+            self.save_checkpoint(projection, position).await?;
+        }
+        if let Some(checkpoints) = &self.checkpoints {
+            checkpoints.reset(&retired).await?;
+        }
+
+        tracing::info!("Rolled back projection {} ({} events read)", name, read);
+        Ok(())
+    }
+
+    async fn rebuild_in_shadow(
+        &self,
+        projection: &dyn Projection,
+        shadow: &dyn Projection,
+    ) -> ProjectionResult<()> {
+        let name = projection.name();
+        let failed = |e: ProjectionError| ProjectionError::rebuild_failed(name, e.to_string());
+        shadow.init().await.map_err(failed)?;
+        shadow.clear().await.map_err(failed)?;
+
+        // The live projection keeps taking events while the shadow fills.
+        // Read on until a pass comes back short of a page; whatever is still
+        // behind is applied right after the swap.
+        let mut last = None;
+        let mut replayed = 0usize;
+        loop {
+            let (read, position) = self.feed(&[shadow], resume_after(last)).await?;
+            replayed += read;
+            last = position.or(last);
+            if read < self.rebuild_page_size {
+                break;
+            }
+        }
+
+        // The outgoing table stops taking events at the swap. Keep its
+        // checkpoint so a rollback only feeds it what came after; read
+        // before the swap, it can only lag, never skip an event.
+        if let Some(checkpoints) = &self.checkpoints {
+            let retired = retired_checkpoint(name);
+            checkpoints.reset(&retired).await?;
+            if let Some(position) = checkpoints.load(name).await? {
+                checkpoints.save(&retired, position).await?;
+            }
+        }
+        projection.promote_shadow().await.map_err(failed)?;
+        tracing::info!("Swapped in rebuilt table for projection {}", name);
+
+        // Events the old table took between the last pass and the swap.
+        let (read, position) = self.feed(&[projection], resume_after(last)).await?;
+        if let Some(position) = position.or(last) {
+            self.save_checkpoint(projection, position).await?;
+        }
+
+        tracing::info!(
+            "Replayed {} events for rebuild, {} more after the swap",
+            replayed,
+            read
+        );
+        Ok(())
+    }
 
+    /// Clear `targets`, then feed them every matching event from the start
+    /// of the log, one page at a time.
+    async fn replay(&self, targets: &[&dyn Projection]) -> ProjectionResult<()> {
+        for projection in targets {
+            // Reset first: a rebuild that dies after `clear` must not leave a
+            // checkpoint that lets `catch_up` skip the emptied history.
+            if let Some(checkpoints) = &self.checkpoints {
+                checkpoints.reset(projection.name()).await?;
+            }
             projection
-                .rebuild(events.clone())
+                .init()
                 .await
                 .map_err(|e| ProjectionError::rebuild_failed(projection.name(), e.to_string()))?;
+            projection
+                .clear()
+                .await
+                .map_err(|e| ProjectionError::rebuild_failed(projection.name(), e.to_string()))?;
+        }
 
-            tracing::info!("Rebuilt projection: {}", projection.name());
+        let (replayed, last) = self.feed(targets, GlobalPosition::START).await?;
+
+        if let Some(position) = last {
+            for projection in targets {
+                self.save_checkpoint(*projection, position).await?;
+            }
         }
 
+        tracing::info!("Replayed {} events for rebuild", replayed);
         Ok(())
     }
 
-    /// Rebuild a specific projection by name.
-    pub async fn rebuild_projection(&self, name: &str) -> ProjectionResult<()> {
-        tracing::info!("Rebuilding projection: {}", name);
+    /// Feed `targets` every event from `from` to the head of the log. Each
+    /// projection takes a page through [`Projection::handle_batch`], so its
+    /// writes are committed per page rather than per row. Returns the
+    /// number of events read and the position of the last one.
+    async fn feed(
+        &self,
+        targets: &[&dyn Projection],
+        from: GlobalPosition,
+    ) -> ProjectionResult<(usize, Option<GlobalPosition>)> {
+        let mut events = self
+            .event_store
+            .stream_all_from(from, self.rebuild_page_size);
+        let mut read = 0usize;
+        let mut last = None;
+        let mut page = Vec::with_capacity(self.rebuild_page_size);
+        while let Some(event) = events.next().await {
+            let event = event.map_err(|e| ProjectionError::EventStoreError(e.to_string()))?;
+            last = event.position.or(last);
+            page.push(event);
+            if page.len() == self.rebuild_page_size {
+                read += Self::replay_page(targets, &mut page).await?;
+            }
+        }
+        read += Self::replay_page(targets, &mut page).await?;
+        Ok((read, last))
+    }
 
-        let projection = self
-            .projections
-            .iter()
-            .find(|p| p.name() == name)
-            .ok_or_else(|| ProjectionError::other(format!("Projection not found: {}", name)))?;
+    /// Hand `page` to every target, then empty it. Returns the number of
+    /// events in the page.
+    async fn replay_page(
+        targets: &[&dyn Projection],
+        page: &mut Vec<Event>,
+    ) -> ProjectionResult<usize> {
+        if page.is_empty() {
+            return Ok(0);
+        }
+        for projection in targets {
+            projection
+                .handle_batch(page)
+                .await
+                .map_err(|e| ProjectionError::rebuild_failed(projection.name(), e.to_string()))?;
+        }
+        let replayed = page.len();
+        page.clear();
+        Ok(replayed)
+    }
 
-        let events = self
+    /// Apply every event after `name`'s checkpoint, e.g. on startup after a
+    /// crash between append and publish. Returns the number of events read.
+    ///
+    /// A projection with no checkpoint yet replays from the start of the
+    /// log; projectors are idempotent, so that is safe on a populated read
+    /// model. Requires [`with_checkpoint_store`](Self::with_checkpoint_store).
+    pub async fn catch_up(&self, name: &str) -> ProjectionResult<usize> {
+        let projection = self.projection(name)?;
+        let Some(checkpoints) = &self.checkpoints else {
+            return Err(ProjectionError::checkpoint_failed(
+                name,
+                "no checkpoint store configured",
+            ));
+        };
+
+        let from = resume_after(checkpoints.load(name).await?);
+        tracing::debug!("Catching up projection {} from position {}", name, from);
+
+        let handles = projection.handles();
+        let mut events = self
             .event_store
-            .stream_all(0)
-            .await
-            .map_err(|e| ProjectionError::EventStoreError(e.to_string()))?;
+            .stream_all_from(from, self.rebuild_page_size);
+        let mut read = 0usize;
+        let mut last = None;
+        while let Some(event) = events.next().await {
+            let event = event.map_err(|e| ProjectionError::EventStoreError(e.to_string()))?;
+            if handles.contains(&event.event_type) {
+                projection.handle(&event).await.map_err(|e| {
+                    ProjectionError::handle_failed(
+                        name,
+                        &event.event_type,
+                        event.event_id.to_string(),
+                        e.to_string(),
+                    )
+                })?;
+            }
+            read += 1;
+            last = event.position.or(last);
+            // Persist progress once per page so a long catch-up that fails
+            // midway does not start over.
+            if read.is_multiple_of(self.rebuild_page_size) {
+                if let Some(position) = last {
+                    self.save_checkpoint(projection, position).await?;
+                }
+            }
+        }
+        if let Some(position) = last {
+            self.save_checkpoint(projection, position).await?;
+        }
 
-        projection
-            .rebuild(events)
-            .await
-            .map_err(|e| ProjectionError::rebuild_failed(name, e.to_string()))?;
+        // Polled continuously by `arc-worker`; only report catch-ups that did work.
+        if read > 0 {
+            tracing::info!("Projection {} caught up ({} events read)", name, read);
+        }
+        Ok(read)
+    }
 
-        tracing::info!("Rebuilt projection: {}", name);
+    /// [`catch_up`](Self::catch_up) every registered projection, in
+    /// registration order.
+    pub async fn catch_up_all(&self) -> ProjectionResult<()> {
+        for projection in &self.projections {
+            self.catch_up(projection.name()).await?;
+        }
         Ok(())
     }
 
+    fn projection(&self, name: &str) -> ProjectionResult<&dyn Projection> {
+        self.projections
+            .iter()
+            .find(|p| p.name() == name)
+            .map(|p| p.as_ref())
+            .ok_or_else(|| ProjectionError::other(format!("Projection not found: {}", name)))
+    }
+
+    async fn save_checkpoint(
+        &self,
+        projection: &dyn Projection,
+        position: GlobalPosition,
+    ) -> ProjectionResult<()> {
+        match &self.checkpoints {
+            Some(checkpoints) => checkpoints.save(projection.name(), position).await,
+            None => Ok(()),
+        }
+    }
+
     /// Get number of registered projections.
     pub fn projection_count(&self) -> usize {
         self.projections.len()
@@ -509,6 +1018,18 @@ impl ProjectionEngine {
     }
 }
 
+/// Checkpoint key for the table a blue/green rebuild of `projection`
+/// retired: where that table stood when it was swapped out.
+fn retired_checkpoint(projection: &str) -> String {
+    format!("{projection}__prev")
+}
+
+/// First position to read after having applied up to `last`.
+fn resume_after(last: Option<GlobalPosition>) -> GlobalPosition {
+    last.map(GlobalPosition::next)
+        .unwrap_or(GlobalPosition::START)
+}
+
 // ---------------------------------------------------------------------------
 // EventBus adapter — drive the engine from an in-process bus
 // ---------------------------------------------------------------------------
@@ -554,8 +1075,10 @@ impl EventHandler for ProjectionEngineHandler {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::event_store::{EventStore, EventStoreResult, VersionCheck};
-    use crate::read_model_store::InMemoryReadModelStore;
+    use crate::audit::AuditMetadata;
+    use crate::event_store::{EventStore, EventStoreResult, InMemoryEventStore, VersionCheck};
+    use crate::read_model_store::{InMemoryReadModelStore, ReadModelResult, Row, Upsert, WriteOp};
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::{Arc, Mutex};
 
     // -----------------------------------------------------------------------
@@ -640,6 +1163,7 @@ mod tests {
     struct MockProjector {
         name: String,
         handles_types: Vec<String>,
+        schema: Option<ReadModelSchema>,
     }
 
     impl MockProjector {
@@ -647,8 +1171,14 @@ mod tests {
             Self {
                 name: name.to_string(),
                 handles_types: handles,
+                schema: None,
             }
         }
+
+        fn with_schema(mut self, schema: ReadModelSchema) -> Self {
+            self.schema = Some(schema);
+            self
+        }
     }
 
     #[async_trait]
@@ -661,8 +1191,11 @@ mod tests {
             self.handles_types.clone()
         }
 
+        fn schema(&self) -> Option<ReadModelSchema> {
+            self.schema.clone()
+        }
+
         async fn apply(&self, event: &Event, store: &dyn ReadModelStore) -> ProjectionResult<()> {
-            use crate::read_model_store::Upsert;
             store
                 .upsert(Upsert::new(
                     "test_table",
@@ -798,6 +1331,362 @@ mod tests {
         assert_eq!(rm_store.get_rows("test_table").len(), 2);
     }
 
+    /// In-memory store holding one `UserCreated` per user, positions `1..=n`.
+    async fn store_with_users(n: usize) -> InMemoryEventStore {
+        let event_store = InMemoryEventStore::new();
+        for i in 1..=n {
+            let event = Event::new(
+                "User",
+                format!("user-{i}"),
+                1,
+                "UserCreated",
+                serde_json::json!({}),
+            )
+            .with_audit(AuditMetadata::test_default());
+            event_store
+                .append(&format!("user-{i}"), VersionCheck::New, vec![event])
+                .await
+                .unwrap();
+        }
+        event_store
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_all_pages_through_history() {
+        let event_store = store_with_users(5).await;
+
+        let mut engine = ProjectionEngine::new(Box::new(event_store)).with_rebuild_page_size(2);
+        let rm_store = Arc::new(InMemoryReadModelStore::new());
+        engine.register(make_projection(
+            "Test",
+            vec!["UserCreated".to_string()],
+            rm_store.clone(),
+        ));
+
+        // Stale row from before the rebuild must be cleared.
+        rm_store
+            .upsert(Upsert::new("test_table", "stale", serde_json::json!({})))
+            .await
+            .unwrap();
+
+        engine.rebuild_all().await.unwrap();
+        assert_eq!(rm_store.get_rows("test_table").len(), 5);
+
+        engine.rebuild_projection("Test").await.unwrap();
+        assert_eq!(rm_store.get_rows("test_table").len(), 5);
+    }
+
+    /// Counts how writes reach the store.
+    #[derive(Default)]
+    struct BatchCountingStore {
+        inner: InMemoryReadModelStore,
+        upserts: AtomicUsize,
+        batches: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ReadModelStore for BatchCountingStore {
+        async fn upsert(&self, op: Upsert) -> ReadModelResult<()> {
+            self.upserts.fetch_add(1, Ordering::SeqCst);
+            self.inner.upsert(op).await
+        }
+        async fn apply_batch(&self, ops: Vec<WriteOp>) -> ReadModelResult<()> {
+            self.batches.fetch_add(1, Ordering::SeqCst);
+            self.inner.apply_batch(ops).await
+        }
+        async fn delete(&self, table: &str, key: &str) -> ReadModelResult<()> {
+            self.inner.delete(table, key).await
+        }
+        async fn get(&self, table: &str, key: &str) -> ReadModelResult<Option<Row>> {
+            self.inner.get(table, key).await
+        }
+        async fn find_by(
+            &self,
+            table: &str,
+            field: &str,
+            value: &serde_json::Value,
+        ) -> ReadModelResult<Vec<Row>> {
+            self.inner.find_by(table, field, value).await
+        }
+        async fn list(&self, table: &str) -> ReadModelResult<Vec<Row>> {
+            self.inner.list(table).await
+        }
+        async fn truncate(&self, table: &str) -> ReadModelResult<()> {
+            self.inner.truncate(table).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_commits_one_batch_per_page() {
+        let event_store = store_with_users(5).await;
+        let mut engine = ProjectionEngine::new(Box::new(event_store)).with_rebuild_page_size(2);
+        let rm_store = Arc::new(BatchCountingStore::default());
+        engine.register_projector(
+            Box::new(MockProjector::new("Test", vec!["UserCreated".to_string()])),
+            rm_store.clone(),
+            "test_table",
+        );
+
+        engine.rebuild_all().await.unwrap();
+        assert_eq!(rm_store.inner.get_rows("test_table").len(), 5);
+        assert_eq!(rm_store.upserts.load(Ordering::SeqCst), 0);
+        assert_eq!(rm_store.batches.load(Ordering::SeqCst), 3);
+    }
+
+    /// Appends one more `UserCreated` right before swapping tables, as if
+    /// the live projection took an event between the last pass over the
+    /// log and the swap.
+    struct SwapRaceStore {
+        inner: InMemoryReadModelStore,
+        events: InMemoryEventStore,
+    }
+
+    #[async_trait]
+    impl ReadModelStore for SwapRaceStore {
+        async fn upsert(&self, op: Upsert) -> ReadModelResult<()> {
+            self.inner.upsert(op).await
+        }
+        async fn apply_batch(&self, ops: Vec<WriteOp>) -> ReadModelResult<()> {
+            self.inner.apply_batch(ops).await
+        }
+        async fn delete(&self, table: &str, key: &str) -> ReadModelResult<()> {
+            self.inner.delete(table, key).await
+        }
+        async fn get(&self, table: &str, key: &str) -> ReadModelResult<Option<Row>> {
+            self.inner.get(table, key).await
+        }
+        async fn find_by(
+            &self,
+            table: &str,
+            field: &str,
+            value: &serde_json::Value,
+        ) -> ReadModelResult<Vec<Row>> {
+            self.inner.find_by(table, field, value).await
+        }
+        async fn list(&self, table: &str) -> ReadModelResult<Vec<Row>> {
+            self.inner.list(table).await
+        }
+        async fn truncate(&self, table: &str) -> ReadModelResult<()> {
+            self.inner.truncate(table).await
+        }
+        async fn ensure_schema(&self, schema: &ReadModelSchema) -> ReadModelResult<()> {
+            self.inner.ensure_schema(schema).await
+        }
+        fn supports_table_swap(&self) -> bool {
+            true
+        }
+        async fn swap_tables(
+            &self,
+            schema: &ReadModelSchema,
+            incoming: &str,
+            outgoing: &str,
+        ) -> ReadModelResult<()> {
+            let id = format!(
+                "user-{}",
+                self.events.stream_all(0).await.unwrap().len() + 1
+            );
+            let event = Event::new("User", &id, 1, "UserCreated", serde_json::json!({}))
+                .with_audit(AuditMetadata::test_default());
+            self.events
+                .append(&id, VersionCheck::New, vec![event])
+                .await
+                .unwrap();
+            self.inner.swap_tables(schema, incoming, outgoing).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_projection_swaps_in_shadow_and_keeps_old_table() {
+        let event_store = store_with_users(5).await;
+        let checkpoints = Arc::new(InMemoryCheckpointStore::new());
+        let rm_store = Arc::new(SwapRaceStore {
+            inner: InMemoryReadModelStore::new(),
+            events: event_store.clone(),
+        });
+        let mut engine = ProjectionEngine::new(Box::new(event_store))
+            .with_rebuild_page_size(2)
+            .with_checkpoint_store(checkpoints.clone());
+        engine.register_projector(
+            Box::new(
+                MockProjector::new("Test", vec!["UserCreated".to_string()])
+                    .with_schema(ReadModelSchema::new("test_table")),
+            ),
+            rm_store.clone(),
+            "test_table",
+        );
+        engine.init_all().await.unwrap();
+        rm_store
+            .upsert(Upsert::new(
+                "test_table",
+                "stale",
+                serde_json::json!({"id": "stale", "version": 1}),
+            ))
+            .await
+            .unwrap();
+
+        engine.rebuild_projection("Test").await.unwrap();
+        let live = rm_store.inner.get_rows("test_table");
+        assert_eq!(live.len(), 6, "the event raced with the swap is applied");
+        assert!(live.iter().all(|row| row["id"] != "stale"));
+        assert_eq!(
+            rm_store.inner.get_rows("test_table__prev"),
+            vec![serde_json::json!({"id": "stale", "version": 1})]
+        );
+        assert_eq!(
+            checkpoints.load("Test").await.unwrap(),
+            Some(GlobalPosition(6))
+        );
+
+        // Rolling back swaps too, racing a seventh event: the old table
+        // comes back with its stale row plus every event.
+        engine.rollback_projection("Test").await.unwrap();
+        assert_eq!(rm_store.inner.get_rows("test_table").len(), 8);
+        assert_eq!(rm_store.inner.get_rows("test_table__next").len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_rollback_feeds_retired_table_from_its_swap_checkpoint() {
+        let event_store = store_with_users(3).await;
+        let checkpoints = Arc::new(InMemoryCheckpointStore::new());
+        let rm_store = Arc::new(InMemoryReadModelStore::new());
+        let mut engine = ProjectionEngine::new(Box::new(event_store.clone()))
+            .with_checkpoint_store(checkpoints.clone());
+        engine.register_projector(
+            Box::new(
+                MockProjector::new("Test", vec!["UserCreated".to_string()])
+                    .with_schema(ReadModelSchema::new("test_table")),
+            ),
+            rm_store.clone(),
+            "test_table",
+        );
+        engine.init_all().await.unwrap();
+        engine.catch_up("Test").await.unwrap();
+
+        engine.rebuild_projection("Test").await.unwrap();
+        assert_eq!(
+            checkpoints.load("Test__prev").await.unwrap(),
+            Some(GlobalPosition(3))
+        );
+
+        // A fourth event reaches only the rebuilt table. Emptying the
+        // retired one shows what the rollback replays into it.
+        let event = Event::new("User", "user-4", 1, "UserCreated", serde_json::json!({}))
+            .with_audit(AuditMetadata::test_default());
+        let stored = event_store
+            .append_returning("user-4", VersionCheck::New, vec![event])
+            .await
+            .unwrap();
+        engine.process(&stored[0]).await.unwrap();
+        for row in rm_store.get_rows("test_table__prev") {
+            let key = row["id"].as_str().unwrap();
+            rm_store.delete("test_table__prev", key).await.unwrap();
+        }
+
+        engine.rollback_projection("Test").await.unwrap();
+        let live = rm_store.get_rows("test_table");
+        assert_eq!(live.len(), 1, "only events after the swap are replayed");
+        assert_eq!(live[0]["id"], stored[0].event_id.to_string());
+        assert_eq!(
+            checkpoints.load("Test").await.unwrap(),
+            Some(GlobalPosition(4))
+        );
+        assert_eq!(checkpoints.load("Test__prev").await.unwrap(), None);
+    }
+
+    fn checkpointed_engine(
+        event_store: InMemoryEventStore,
+        checkpoints: Arc<InMemoryCheckpointStore>,
+        rm_store: Arc<InMemoryReadModelStore>,
+    ) -> ProjectionEngine {
+        let mut engine = ProjectionEngine::new(Box::new(event_store))
+            .with_rebuild_page_size(2)
+            .with_checkpoint_store(checkpoints);
+        engine.register(make_projection(
+            "Test",
+            vec!["UserCreated".to_string()],
+            rm_store,
+        ));
+        engine
+    }
+
+    #[tokio::test]
+    async fn test_out_of_order_process_does_not_skip_events_on_catch_up() {
+        let event_store = store_with_users(2).await;
+        let stored = event_store.stream_all(0).await.unwrap();
+        let checkpoints = Arc::new(InMemoryCheckpointStore::new());
+        let rm_store = Arc::new(InMemoryReadModelStore::new());
+        let engine = checkpointed_engine(event_store, checkpoints.clone(), rm_store.clone());
+
+        // Position 2 is published first; position 1 never arrives.
+        engine.process(&stored[1]).await.unwrap();
+        assert_eq!(rm_store.get_rows("test_table").len(), 1);
+        assert_eq!(checkpoints.load("Test").await.unwrap(), None);
+
+        assert_eq!(engine.catch_up("Test").await.unwrap(), 2);
+        assert_eq!(rm_store.get_rows("test_table").len(), 2);
+        assert_eq!(
+            checkpoints.load("Test").await.unwrap(),
+            Some(GlobalPosition(2))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_resumes_after_checkpoint() {
+        let event_store = store_with_users(5).await;
+        let first = event_store.stream_all(0).await.unwrap().remove(0);
+        let checkpoints = Arc::new(InMemoryCheckpointStore::new());
+        let rm_store = Arc::new(InMemoryReadModelStore::new());
+        let engine = checkpointed_engine(event_store, checkpoints.clone(), rm_store.clone());
+
+        // Only the first event was caught up before the "crash".
+        engine.process(&first).await.unwrap();
+        checkpoints
+            .save("Test", first.position.unwrap())
+            .await
+            .unwrap();
+        assert_eq!(rm_store.get_rows("test_table").len(), 1);
+
+        assert_eq!(engine.catch_up("Test").await.unwrap(), 4);
+        assert_eq!(rm_store.get_rows("test_table").len(), 5);
+        assert_eq!(
+            checkpoints.load("Test").await.unwrap(),
+            Some(GlobalPosition(5))
+        );
+
+        // Nothing new: a second catch-up reads nothing.
+        assert_eq!(engine.catch_up("Test").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_resets_then_advances_checkpoint() {
+        let checkpoints = Arc::new(InMemoryCheckpointStore::new());
+        checkpoints.save("Test", GlobalPosition(99)).await.unwrap();
+        let engine = checkpointed_engine(
+            store_with_users(3).await,
+            checkpoints.clone(),
+            Arc::new(InMemoryReadModelStore::new()),
+        );
+
+        engine.rebuild_projection("Test").await.unwrap();
+        assert_eq!(
+            checkpoints.load("Test").await.unwrap(),
+            Some(GlobalPosition(3))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_requires_checkpoint_store() {
+        let mut engine = ProjectionEngine::new(Box::new(InMemoryEventStore::new()));
+        engine.register(make_projection(
+            "Test",
+            vec!["UserCreated".to_string()],
+            Arc::new(InMemoryReadModelStore::new()),
+        ));
+        let err = engine.catch_up("Test").await.unwrap_err();
+        assert!(matches!(err, ProjectionError::CheckpointFailed { .. }));
+        assert!(engine.catch_up("Missing").await.is_err());
+    }
+
     #[tokio::test]
     async fn test_multiple_projections() {
         let store = Box::new(MockEventStore::new());
@@ -855,6 +1744,48 @@ mod tests {
         projector.init(&store).await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_init_all_applies_declared_schemas() {
+        use crate::read_model_store::{ReadModelError, ReadModelIndex, Upsert};
+
+        let rm_store = Arc::new(InMemoryReadModelStore::new());
+        let mut engine = ProjectionEngine::new(Box::new(MockEventStore::new()));
+        engine.register_projector(
+            Box::new(
+                MockProjector::new("Test", vec![]).with_schema(
+                    ReadModelSchema::new("test_table")
+                        .with_index(ReadModelIndex::unique_on(["event_type"])),
+                ),
+            ),
+            rm_store.clone(),
+            "test_table",
+        );
+        engine.init_all().await.unwrap();
+
+        let row = |id: &str| serde_json::json!({"id": id, "event_type": "Same", "version": 1});
+        rm_store
+            .upsert(Upsert::new("test_table", "a", row("a")))
+            .await
+            .unwrap();
+        let err = rm_store
+            .upsert(Upsert::new("test_table", "b", row("b")))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ReadModelError::WriteFailed { .. }));
+
+        // A schema for another table than the one registered is a wiring bug.
+        let mut engine = ProjectionEngine::new(Box::new(MockEventStore::new()));
+        engine.register_projector(
+            Box::new(
+                MockProjector::new("Test", vec![]).with_schema(ReadModelSchema::new("other_table")),
+            ),
+            rm_store,
+            "test_table",
+        );
+        let err = engine.init_all().await.unwrap_err();
+        assert!(err.to_string().contains("other_table"), "{err}");
+    }
+
     #[tokio::test]
     async fn test_register_projector_convenience() {
         let event_store = Box::new(MockEventStore::new());
@@ -0,0 +1,485 @@
+//! # Read Model Queries
+//!
+//! Backend-neutral filters, ordering and paging for
+//! [`ReadModelStore::query`](crate::read_model_store::ReadModelStore::query)
+//! and [`count`](crate::read_model_store::ReadModelStore::count). A [`Query`]
+//! describes intent over a table's top-level row fields; each backend
+//! translates it to its own dialect (SQLite via `json_extract`), and stores
+//! without a native translation fall back to [`Query::evaluate`] over
+//! `list()`.
+//!
+//! ## Semantics
+//!
+//! Every backend must agree on these, so the in-memory store and the SQL
+//! stores return the same pages for the same data:
+//!
+//! - Values order as SQLite orders them: `null` (or a missing field) first,
+//!   then numbers (with `false`/`true` as `0`/`1`), then strings compared
+//!   bytewise.
+//! - [`Filter::eq`] with `null` matches missing fields; [`Filter::ne`] is its
+//!   exact negation. [`lt`](Filter::lt), [`gt`](Filter::gt),
+//!   [`one_of`](Filter::one_of) and [`prefix`](Filter::prefix) never match a
+//!   missing field; `prefix` matches strings only.
+//! - Rows are ordered by each [`OrderBy`] in turn, then by primary key
+//!   ascending, so every ordering is total and pages never overlap.
+//!
+//! ## Paging
+//!
+//! [`with_limit`](Query::with_limit) and [`with_offset`](Query::with_offset)
+//! page by position. For long or changing tables, page by key instead: a
+//! full [`Page`] carries a [`Cursor`] for the last row, and
+//! [`with_cursor`](Query::with_cursor) resumes strictly after it.
+
+use crate::read_model_store::{ReadModelError, ReadModelResult, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// Comparison applied to one field of a row.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    Eq(Value),
+    Ne(Value),
+    Lt(Value),
+    Gt(Value),
+    In(Vec<Value>),
+    Prefix(String),
+}
+
+/// A [`Predicate`] on a named top-level field.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    pub field: String,
+    pub predicate: Predicate,
+}
+
+impl Filter {
+    pub fn new(field: impl Into<String>, predicate: Predicate) -> Self {
+        Self {
+            field: field.into(),
+            predicate,
+        }
+    }
+
+    pub fn eq(field: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::new(field, Predicate::Eq(value.into()))
+    }
+
+    pub fn ne(field: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::new(field, Predicate::Ne(value.into()))
+    }
+
+    pub fn lt(field: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::new(field, Predicate::Lt(value.into()))
+    }
+
+    pub fn gt(field: impl Into<String>, value: impl Into<Value>) -> Self {
+        Self::new(field, Predicate::Gt(value.into()))
+    }
+
+    pub fn one_of<V: Into<Value>>(
+        field: impl Into<String>,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        Self::new(
+            field,
+            Predicate::In(values.into_iter().map(Into::into).collect()),
+        )
+    }
+
+    pub fn prefix(field: impl Into<String>, prefix: impl Into<String>) -> Self {
+        Self::new(field, Predicate::Prefix(prefix.into()))
+    }
+
+    /// Whether `row` satisfies this filter.
+    pub fn matches(&self, row: &Row) -> bool {
+        let actual = field_value(row, &self.field);
+        match &self.predicate {
+            Predicate::Eq(v) => compare_values(actual, v) == Ordering::Equal,
+            Predicate::Ne(v) => compare_values(actual, v) != Ordering::Equal,
+            Predicate::Lt(v) => !actual.is_null() && compare_values(actual, v) == Ordering::Less,
+            Predicate::Gt(v) => !actual.is_null() && compare_values(actual, v) == Ordering::Greater,
+            Predicate::In(vs) => {
+                !actual.is_null()
+                    && vs
+                        .iter()
+                        .any(|v| compare_values(actual, v) == Ordering::Equal)
+            }
+            Predicate::Prefix(p) => actual.as_str().is_some_and(|s| s.starts_with(p.as_str())),
+        }
+    }
+
+    fn validate(&self) -> ReadModelResult<()> {
+        let primitive = |v: &Value, allow_null: bool| match v {
+            Value::String(_) | Value::Number(_) | Value::Bool(_) => Ok(()),
+            Value::Null if allow_null => Ok(()),
+            other => Err(ReadModelError::query_failed(format!(
+                "filter on '{}' only accepts primitive values, got: {other}",
+                self.field
+            ))),
+        };
+        match &self.predicate {
+            Predicate::Eq(v) | Predicate::Ne(v) => primitive(v, true),
+            Predicate::Lt(v) | Predicate::Gt(v) => primitive(v, false),
+            Predicate::In(vs) => vs.iter().try_for_each(|v| primitive(v, false)),
+            Predicate::Prefix(_) => Ok(()),
+        }
+    }
+}
+
+/// Sort direction of an [`OrderBy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    Asc,
+    Desc,
+}
+
+/// One sort key of a [`Query`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderBy {
+    pub field: String,
+    pub direction: Direction,
+}
+
+/// Position of a row in a query's ordering: its sort-field values, in
+/// [`Query::order`] order, and its primary key. Serializable so it can be
+/// handed to a client as a page token.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    pub values: Vec<Value>,
+    pub key: String,
+}
+
+/// Filters, ordering and paging for one table. Build with the `with_*`
+/// methods; an empty query matches every row in primary key order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    /// All must match.
+    pub filters: Vec<Filter>,
+    pub order: Vec<OrderBy>,
+    pub limit: Option<usize>,
+    pub offset: usize,
+    /// Only rows strictly after this position, applied before `offset`.
+    pub after: Option<Cursor>,
+}
+
+impl Query {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_filter(mut self, filter: Filter) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    pub fn with_order(mut self, field: impl Into<String>, direction: Direction) -> Self {
+        self.order.push(OrderBy {
+            field: field.into(),
+            direction,
+        });
+        self
+    }
+
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn with_offset(mut self, offset: usize) -> Self {
+        self.offset = offset;
+        self
+    }
+
+    pub fn with_cursor(mut self, cursor: Cursor) -> Self {
+        self.after = Some(cursor);
+        self
+    }
+
+    /// Reject filter values no backend can compare and cursors that do not
+    /// fit the ordering. Backends call this before translating.
+    pub fn validate(&self) -> ReadModelResult<()> {
+        self.filters.iter().try_for_each(Filter::validate)?;
+        if let Some(cursor) = &self.after {
+            if cursor.values.len() != self.order.len() {
+                return Err(ReadModelError::query_failed(format!(
+                    "cursor has {} sort values, query orders by {} fields",
+                    cursor.values.len(),
+                    self.order.len()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether `row` passes every filter.
+    pub fn matches(&self, row: &Row) -> bool {
+        self.filters.iter().all(|f| f.matches(row))
+    }
+
+    /// The cursor for `row`, stored under `key`, in this query's ordering.
+    pub fn cursor_for(&self, key: impl Into<String>, row: &Row) -> Cursor {
+        Cursor {
+            values: self
+                .order
+                .iter()
+                .map(|o| field_value(row, &o.field).clone())
+                .collect(),
+            key: key.into(),
+        }
+    }
+
+    /// Compare two `(key, row)` entries in this query's ordering.
+    pub fn compare(&self, a: (&str, &Row), b: (&str, &Row)) -> Ordering {
+        let a = self.cursor_for(a.0, a.1);
+        self.compare_to_cursor(&a, b.0, b.1)
+    }
+
+    /// Run the query over `(key, row)` pairs in memory. The in-memory store
+    /// uses this, and so does the default [`ReadModelStore::query`] for
+    /// backends without a native translation.
+    ///
+    /// [`ReadModelStore::query`]: crate::read_model_store::ReadModelStore::query
+    pub fn evaluate(&self, rows: impl IntoIterator<Item = (String, Row)>) -> ReadModelResult<Page> {
+        self.validate()?;
+        let mut hits: Vec<(String, Row)> = rows
+            .into_iter()
+            .filter(|(key, row)| {
+                self.matches(row)
+                    && self.after.as_ref().is_none_or(|after| {
+                        self.compare_to_cursor(after, key, row) == Ordering::Less
+                    })
+            })
+            .collect();
+        hits.sort_by(|a, b| self.compare((&a.0, &a.1), (&b.0, &b.1)));
+        let hits = hits
+            .into_iter()
+            .skip(self.offset)
+            .take(self.limit.unwrap_or(usize::MAX));
+        Ok(self.page(hits.collect()))
+    }
+
+    /// Number of `rows` passing every filter; ordering and paging are ignored.
+    pub fn count_matching<'a>(
+        &self,
+        rows: impl IntoIterator<Item = &'a Row>,
+    ) -> ReadModelResult<u64> {
+        self.validate()?;
+        Ok(rows.into_iter().filter(|row| self.matches(row)).count() as u64)
+    }
+
+    /// Assemble a page from the ordered `(key, row)` hits of this query,
+    /// setting [`Page::next`] when the page is full.
+    pub fn page(&self, hits: Vec<(String, Row)>) -> Page {
+        let next = match (self.limit, hits.last()) {
+            (Some(limit), Some((key, row))) if hits.len() == limit => {
+                Some(self.cursor_for(key.clone(), row))
+            }
+            _ => None,
+        };
+        Page {
+            rows: hits.into_iter().map(|(_, row)| row).collect(),
+            next,
+        }
+    }
+
+    fn compare_to_cursor(&self, cursor: &Cursor, key: &str, row: &Row) -> Ordering {
+        self.order
+            .iter()
+            .zip(&cursor.values)
+            .map(|(o, value)| {
+                let ord = compare_values(value, field_value(row, &o.field));
+                match o.direction {
+                    Direction::Asc => ord,
+                    Direction::Desc => ord.reverse(),
+                }
+            })
+            .find(|ord| ord.is_ne())
+            .unwrap_or_else(|| cursor.key.as_str().cmp(key))
+    }
+}
+
+/// One page of [`ReadModelStore::query`](crate::read_model_store::ReadModelStore::query)
+/// results.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    pub rows: Vec<Row>,
+    /// Position of the last row when the page is full (`rows.len()` equals
+    /// the limit); pass it to [`Query::with_cursor`] for the next page.
+    /// `None` once the results are exhausted or when no limit was set.
+    pub next: Option<Cursor>,
+}
+
+const NULL: Value = Value::Null;
+
+fn field_value<'a>(row: &'a Row, field: &str) -> &'a Value {
+    row.get(field).unwrap_or(&NULL)
+}
+
+/// SQLite's ordering of the values `json_extract` yields: NULL, then
+/// numbers (booleans are 0/1), then text. Arrays and objects come back from
+/// `json_extract` as JSON text, so they compare as their serialization.
+pub fn compare_values(a: &Value, b: &Value) -> Ordering {
+    fn rank(v: &Value) -> u8 {
+        match v {
+            Value::Null => 0,
+            Value::Bool(_) | Value::Number(_) => 1,
+            _ => 2,
+        }
+    }
+    fn number(v: &Value) -> Option<(Option<i64>, f64)> {
+        match v {
+            Value::Bool(b) => Some((Some(*b as i64), *b as i64 as f64)),
+            Value::Number(n) => Some((n.as_i64(), n.as_f64().unwrap_or(f64::NAN))),
+            _ => None,
+        }
+    }
+    fn text(v: &Value) -> std::borrow::Cow<'_, str> {
+        match v {
+            Value::String(s) => s.as_str().into(),
+            other => other.to_string().into(),
+        }
+    }
+
+    match (rank(a), rank(b)) {
+        (0, 0) => Ordering::Equal,
+        (1, 1) => match (number(a), number(b)) {
+            (Some((Some(x), _)), Some((Some(y), _))) => x.cmp(&y),
+            (Some((_, x)), Some((_, y))) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+            _ => Ordering::Equal,
+        },
+        (2, 2) => text(a).as_bytes().cmp(text(b).as_bytes()),
+        (ra, rb) => ra.cmp(&rb),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn users() -> Vec<(String, Row)> {
+        [
+            json!({"id": "u1", "name": "Carol", "age": 41, "active": true}),
+            json!({"id": "u2", "name": "alice", "age": 29, "active": false}),
+            json!({"id": "u3", "name": "Bob", "age": 35, "active": true, "nickname": "bobby"}),
+            json!({"id": "u4", "name": "Alina", "active": true}),
+        ]
+        .into_iter()
+        .map(|row| (row["id"].as_str().unwrap().to_string(), row))
+        .collect()
+    }
+
+    fn ids(page: &Page) -> Vec<&str> {
+        page.rows
+            .iter()
+            .map(|r| r["id"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_filters_combine_and_follow_sqlite_semantics() {
+        let q = |f: Filter| Query::new().with_filter(f).evaluate(users()).unwrap();
+
+        assert_eq!(ids(&q(Filter::eq("active", true))), ["u1", "u3", "u4"]);
+        // `true` compares as 1, like json_extract's output.
+        assert_eq!(ids(&q(Filter::eq("active", 1))), ["u1", "u3", "u4"]);
+        assert_eq!(
+            ids(&q(Filter::eq("nickname", Value::Null))),
+            ["u1", "u2", "u4"]
+        );
+        assert_eq!(ids(&q(Filter::ne("nickname", Value::Null))), ["u3"]);
+        // Missing fields never satisfy a range comparison.
+        assert_eq!(ids(&q(Filter::lt("age", 35))), ["u2"]);
+        assert_eq!(ids(&q(Filter::gt("age", 30))), ["u1", "u3"]);
+        assert_eq!(
+            ids(&q(Filter::one_of("name", ["Bob", "Alina"]))),
+            ["u3", "u4"]
+        );
+        // Prefix is case-sensitive.
+        assert_eq!(ids(&q(Filter::prefix("name", "Al"))), ["u4"]);
+
+        let both = Query::new()
+            .with_filter(Filter::eq("active", true))
+            .with_filter(Filter::gt("age", 30));
+        assert_eq!(ids(&both.evaluate(users()).unwrap()), ["u1", "u3"]);
+        assert_eq!(
+            both.count_matching(users().iter().map(|(_, r)| r)).unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_order_puts_nulls_first_and_breaks_ties_by_key() {
+        let by_age = Query::new().with_order("age", Direction::Asc);
+        assert_eq!(
+            ids(&by_age.evaluate(users()).unwrap()),
+            ["u4", "u2", "u3", "u1"]
+        );
+        let by_age_desc = Query::new().with_order("age", Direction::Desc);
+        assert_eq!(
+            ids(&by_age_desc.evaluate(users()).unwrap()),
+            ["u1", "u3", "u2", "u4"]
+        );
+
+        // Bytewise: uppercase sorts before lowercase.
+        let by_name = Query::new().with_order("name", Direction::Asc);
+        assert_eq!(
+            ids(&by_name.evaluate(users()).unwrap()),
+            ["u4", "u3", "u1", "u2"]
+        );
+
+        let by_active = Query::new().with_order("active", Direction::Desc);
+        assert_eq!(
+            ids(&by_active.evaluate(users()).unwrap()),
+            ["u1", "u3", "u4", "u2"]
+        );
+    }
+
+    #[test]
+    fn test_cursor_pages_cover_every_row_once() {
+        for direction in [Direction::Asc, Direction::Desc] {
+            let query = Query::new().with_order("age", direction).with_limit(3);
+            let all = Query::new().with_order("age", direction);
+            let expected: Vec<String> = ids(&all.evaluate(users()).unwrap())
+                .into_iter()
+                .map(String::from)
+                .collect();
+
+            let first = query.evaluate(users()).unwrap();
+            assert_eq!(first.rows.len(), 3);
+            let next = first.next.clone().expect("full page has a cursor");
+            let second = query.clone().with_cursor(next).evaluate(users()).unwrap();
+            assert_eq!(second.rows.len(), 1);
+            assert!(second.next.is_none());
+
+            let seen: Vec<&str> = ids(&first).into_iter().chain(ids(&second)).collect();
+            assert_eq!(seen, expected, "{direction:?}");
+        }
+
+        let offset = Query::new().with_limit(2).with_offset(3);
+        let page = offset.evaluate(users()).unwrap();
+        assert_eq!(ids(&page), ["u4"]);
+        assert!(page.next.is_none());
+    }
+
+    #[test]
+    fn test_validate_rejects_unusable_values_and_cursors() {
+        let bad = [
+            Query::new().with_filter(Filter::eq("tags", json!(["a"]))),
+            Query::new().with_filter(Filter::lt("age", Value::Null)),
+            Query::new().with_filter(Filter::one_of("age", [json!({"n": 1})])),
+            Query::new().with_cursor(Cursor {
+                values: vec![json!(1)],
+                key: "u1".into(),
+            }),
+        ];
+        for query in bad {
+            let err = query.evaluate(users()).unwrap_err();
+            assert!(
+                matches!(err, ReadModelError::QueryFailed { .. }),
+                "{query:?}"
+            );
+        }
+    }
+}
@@ -16,22 +16,38 @@
 //!   primary key. The gate (`existing.version < incoming.version`) makes
 //!   projectors idempotent under duplicate or out-of-order delivery.
 //! - [`delete`](ReadModelStore::delete) — remove a row by primary key.
+//! - [`apply_batch`](ReadModelStore::apply_batch) — several upserts and
+//!   deletes, possibly across tables, as one atomic unit. [`WriteBuffer`]
+//!   collects a projector's writes into such a batch.
 //! - [`get`](ReadModelStore::get) — fetch one row by primary key.
 //! - [`find_by`](ReadModelStore::find_by) — fetch rows where a single field
 //!   equals a value (covers email lookups, secondary index reads).
 //! - [`list`](ReadModelStore::list) — fetch all rows in a table.
+//! - [`query`](ReadModelStore::query) / [`count`](ReadModelStore::count) —
+//!   filtered, ordered, paged reads described by a backend-neutral
+//!   [`Query`] (see [`crate::read_model_query`]).
 //! - [`truncate`](ReadModelStore::truncate) — wipe a table during projection
 //!   rebuild.
+//! - [`ensure_schema`](ReadModelStore::ensure_schema) — create a table and
+//!   reconcile the secondary indexes a projector declares in its
+//!   [`ReadModelSchema`].
+//! - [`swap_tables`](ReadModelStore::swap_tables) — atomically replace a
+//!   table with one built beside it, for blue/green rebuilds.
+//!
+//! [`TableRedirect`] points a projector written against one table at
+//! another, such as the shadow table of such a rebuild.
 //!
 //! ## Implementations
 //!
 //! - [`InMemoryReadModelStore`] — built-in, for testing and ephemeral state.
 //! - `SqliteReadModelStore` (in `arc-es-sqlite`) — production SQLite.
-//! - Postgres / dqlite backends — planned.
+//! - `PostgresReadModelStore` (in `arc-es-postgres`) — JSONB `data` column.
+//! - dqlite backend — planned.
 
+use crate::read_model_query::{compare_values, Page, Query};
 use async_trait::async_trait;
-use std::collections::HashMap;
-use std::sync::Mutex;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
 
 /// A row stored or returned by a read model query, represented as a JSON object.
@@ -114,6 +130,117 @@ impl Upsert {
     }
 }
 
+/// One write in a [`ReadModelStore::apply_batch`].
+#[derive(Debug, Clone)]
+pub enum WriteOp {
+    /// Version-gated insert-or-replace, as [`ReadModelStore::upsert`].
+    Upsert(Upsert),
+    /// Remove a row by primary key, as [`ReadModelStore::delete`].
+    Delete { table: String, key: String },
+}
+
+impl WriteOp {
+    pub fn delete(table: impl Into<String>, key: impl Into<String>) -> Self {
+        WriteOp::Delete {
+            table: table.into(),
+            key: key.into(),
+        }
+    }
+
+    /// The table this op writes to.
+    pub fn table(&self) -> &str {
+        match self {
+            WriteOp::Upsert(op) => &op.table,
+            WriteOp::Delete { table, .. } => table,
+        }
+    }
+}
+
+impl From<Upsert> for WriteOp {
+    fn from(op: Upsert) -> Self {
+        WriteOp::Upsert(op)
+    }
+}
+
+/// Secondary index over top-level JSON fields of a projection table.
+///
+/// Named `idx_{table}_{field}_{field}…`, so the same declaration always maps
+/// to the same index and a store can tell its own indexes from hand-written
+/// ones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadModelIndex {
+    pub fields: Vec<String>,
+    pub unique: bool,
+}
+
+impl ReadModelIndex {
+    /// A non-unique index on `fields`, in order.
+    pub fn on<F: Into<String>>(fields: impl IntoIterator<Item = F>) -> Self {
+        Self {
+            fields: fields.into_iter().map(Into::into).collect(),
+            unique: false,
+        }
+    }
+
+    /// A unique index on `fields`. Rows where any of them is null or
+    /// missing are exempt, as in SQL.
+    pub fn unique_on<F: Into<String>>(fields: impl IntoIterator<Item = F>) -> Self {
+        Self {
+            unique: true,
+            ..Self::on(fields)
+        }
+    }
+
+    /// The index's name on `table`.
+    pub fn name(&self, table: &str) -> String {
+        format!("idx_{table}_{}", self.fields.join("_"))
+    }
+}
+
+/// A projector's table and the indexes it reads through, declared with
+/// [`Projector::schema`](crate::projection::Projector::schema) and applied
+/// by [`ReadModelStore::ensure_schema`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadModelSchema {
+    pub table: String,
+    pub indexes: Vec<ReadModelIndex>,
+}
+
+impl ReadModelSchema {
+    pub fn new(table: impl Into<String>) -> Self {
+        Self {
+            table: table.into(),
+            indexes: Vec::new(),
+        }
+    }
+
+    pub fn with_index(mut self, index: ReadModelIndex) -> Self {
+        self.indexes.push(index);
+        self
+    }
+
+    /// Reject schemas no backend can apply: no fields, or an index declared
+    /// twice.
+    pub fn validate(&self) -> ReadModelResult<()> {
+        let mut names = std::collections::HashSet::new();
+        for index in &self.indexes {
+            if index.fields.is_empty() {
+                return Err(ReadModelError::schema_failed(format!(
+                    "index on {} declares no fields",
+                    self.table
+                )));
+            }
+            if !names.insert(index.name(&self.table)) {
+                return Err(ReadModelError::schema_failed(format!(
+                    "index {} declared twice",
+                    index.name(&self.table)
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// Backend-agnostic storage for projection read models.
 ///
 /// Implementations must be `Send + Sync`. Multiple projectors may share a
@@ -133,6 +260,24 @@ pub trait ReadModelStore: Send + Sync {
     /// Delete a row by primary key. No-op if missing.
     async fn delete(&self, table: &str, key: &str) -> ReadModelResult<()>;
 
+    /// Apply `ops` in order as one unit: every write lands or none does.
+    /// Each upsert is version-gated exactly as [`upsert`](Self::upsert);
+    /// a gated-out upsert is not a failure.
+    ///
+    /// Lets a projector that maintains several rows or tables from one
+    /// event never leave them half-updated, and lets rebuilds commit a page
+    /// of events at a time. The default applies the ops one by one and is
+    /// **not** atomic; backends with transactions override it.
+    async fn apply_batch(&self, ops: Vec<WriteOp>) -> ReadModelResult<()> {
+        for op in ops {
+            match op {
+                WriteOp::Upsert(op) => self.upsert(op).await?,
+                WriteOp::Delete { table, key } => self.delete(&table, &key).await?,
+            }
+        }
+        Ok(())
+    }
+
     /// Fetch a single row by primary key.
     async fn get(&self, table: &str, key: &str) -> ReadModelResult<Option<Row>>;
 
@@ -149,23 +294,84 @@ pub trait ReadModelStore: Send + Sync {
     /// Fetch every row in a table.
     async fn list(&self, table: &str) -> ReadModelResult<Vec<Row>>;
 
+    /// Fetch one page of rows matching `query`, in its order.
+    ///
+    /// The default evaluates the query in memory over [`list`](Self::list),
+    /// keying rows by their `id` field; backends with a query language
+    /// should translate it instead.
+    async fn query(&self, table: &str, query: &Query) -> ReadModelResult<Page> {
+        query.validate()?;
+        let rows = self.list(table).await?;
+        query.evaluate(rows.into_iter().map(|row| {
+            let key = row.get("id").and_then(|v| v.as_str()).unwrap_or_default();
+            (key.to_string(), row)
+        }))
+    }
+
+    /// Count the rows matching `query`'s filters, ignoring its ordering and
+    /// paging. Pairs with [`query`](Self::query) for "page 2 of 7" listings.
+    async fn count(&self, table: &str, query: &Query) -> ReadModelResult<u64> {
+        query.validate()?;
+        query.count_matching(&self.list(table).await?)
+    }
+
     /// Wipe a table. Used during projection rebuild before replay.
     async fn truncate(&self, table: &str) -> ReadModelResult<()>;
+
+    /// Create `schema.table` if it is missing and bring its indexes in line
+    /// with the declaration: create missing ones, rebuild changed ones and
+    /// drop `idx_{table}_*` indexes no longer declared. Idempotent; run at
+    /// startup and before rebuilds.
+    ///
+    /// The default does nothing, for backends whose tables and indexes are
+    /// owned by migrations.
+    async fn ensure_schema(&self, _schema: &ReadModelSchema) -> ReadModelResult<()> {
+        Ok(())
+    }
+
+    /// Whether [`swap_tables`](Self::swap_tables) is implemented. Rebuilds
+    /// of projections over stores without it clear the live table instead.
+    fn supports_table_swap(&self) -> bool {
+        false
+    }
+
+    /// Atomically put `incoming` in place of `schema.table`. The current
+    /// table is renamed to `outgoing`, replacing any table of that name, and
+    /// `incoming` takes its name and `schema`'s indexes. A reader sees either
+    /// the old rows or the new ones, never a missing or empty table.
+    ///
+    /// `outgoing` keeps its rows but not its declared indexes. Fails without
+    /// changing anything if `incoming` does not exist.
+    async fn swap_tables(
+        &self,
+        schema: &ReadModelSchema,
+        _incoming: &str,
+        _outgoing: &str,
+    ) -> ReadModelResult<()> {
+        Err(ReadModelError::other(format!(
+            "cannot swap {}: the store does not support table swaps",
+            schema.table
+        )))
+    }
 }
 
 /// In-memory read model store for testing.
 ///
 /// Stores rows keyed by primary key in a per-table `HashMap`. Implements the
 /// version gate exactly the way SQLite does, so tests written against this
-/// store exercise the same idempotency semantics as production.
+/// store exercise the same idempotency semantics as production. Unique
+/// indexes from [`ensure_schema`](ReadModelStore::ensure_schema) are
+/// enforced on upsert; other indexes are accepted and ignored.
 pub struct InMemoryReadModelStore {
     tables: Mutex<HashMap<String, HashMap<String, Row>>>,
+    schemas: Mutex<HashMap<String, ReadModelSchema>>,
 }
 
 impl InMemoryReadModelStore {
     pub fn new() -> Self {
         Self {
             tables: Mutex::new(HashMap::new()),
+            schemas: Mutex::new(HashMap::new()),
         }
     }
 
@@ -179,6 +385,20 @@ impl InMemoryReadModelStore {
             .unwrap_or_default()
     }
 
+    /// Every non-empty table's rows by primary key, sorted (test helper).
+    pub fn tables(&self) -> BTreeMap<String, BTreeMap<String, Row>> {
+        self.tables
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, rows)| !rows.is_empty())
+            .map(|(name, rows)| {
+                let rows = rows.iter().map(|(k, r)| (k.clone(), r.clone())).collect();
+                (name.clone(), rows)
+            })
+            .collect()
+    }
+
     /// Total row count across all tables (test helper).
     pub fn total_rows(&self) -> usize {
         self.tables.lock().unwrap().values().map(|m| m.len()).sum()
@@ -195,26 +415,97 @@ fn row_version(row: &Row) -> i64 {
     row.get("version").and_then(|v| v.as_i64()).unwrap_or(0)
 }
 
-#[async_trait]
-impl ReadModelStore for InMemoryReadModelStore {
-    async fn upsert(&self, op: Upsert) -> ReadModelResult<()> {
-        let mut tables = self.tables.lock().unwrap();
-        let table = tables.entry(op.table).or_default();
+impl InMemoryReadModelStore {
+    /// Apply one op to `tables`, enforcing the version gate and declared
+    /// unique indexes.
+    fn write(
+        &self,
+        tables: &mut HashMap<String, HashMap<String, Row>>,
+        op: WriteOp,
+    ) -> ReadModelResult<()> {
+        let op = match op {
+            WriteOp::Upsert(op) => op,
+            WriteOp::Delete { table, key } => {
+                if let Some(t) = tables.get_mut(&table) {
+                    t.remove(&key);
+                }
+                return Ok(());
+            }
+        };
+        let table = tables.entry(op.table.clone()).or_default();
         let incoming_version = row_version(&op.row);
         let should_write = match table.get(&op.key) {
             Some(existing) => row_version(existing) < incoming_version,
             None => true,
         };
         if should_write {
+            if let Some(schema) = self.schemas.lock().unwrap().get(&op.table) {
+                if let Some(index) = unique_violation(schema, table, &op.key, &op.row) {
+                    return Err(ReadModelError::write_failed(format!(
+                        "UNIQUE constraint failed: {index}"
+                    )));
+                }
+            }
             table.insert(op.key, op.row);
         }
         Ok(())
     }
+}
+
+/// The first unique index `row`, stored under `key`, would violate.
+fn unique_violation(
+    schema: &ReadModelSchema,
+    table: &HashMap<String, Row>,
+    key: &str,
+    row: &Row,
+) -> Option<String> {
+    let values = |r: &Row, index: &ReadModelIndex| -> Option<Vec<serde_json::Value>> {
+        index
+            .fields
+            .iter()
+            .map(|f| r.get(f).filter(|v| !v.is_null()).cloned())
+            .collect()
+    };
+    schema
+        .indexes
+        .iter()
+        .filter(|index| index.unique)
+        .find(|index| {
+            let Some(incoming) = values(row, index) else {
+                return false;
+            };
+            table.iter().any(|(k, existing)| {
+                k != key
+                    && values(existing, index).is_some_and(|v| {
+                        v.iter()
+                            .zip(&incoming)
+                            .all(|(a, b)| compare_values(a, b) == std::cmp::Ordering::Equal)
+                    })
+            })
+        })
+        .map(|index| index.name(&schema.table))
+}
+
+#[async_trait]
+impl ReadModelStore for InMemoryReadModelStore {
+    async fn upsert(&self, op: Upsert) -> ReadModelResult<()> {
+        let mut tables = self.tables.lock().unwrap();
+        self.write(&mut tables, WriteOp::Upsert(op))
+    }
 
     async fn delete(&self, table: &str, key: &str) -> ReadModelResult<()> {
-        if let Some(t) = self.tables.lock().unwrap().get_mut(table) {
-            t.remove(key);
+        let mut tables = self.tables.lock().unwrap();
+        self.write(&mut tables, WriteOp::delete(table, key))
+    }
+
+    async fn apply_batch(&self, ops: Vec<WriteOp>) -> ReadModelResult<()> {
+        let mut tables = self.tables.lock().unwrap();
+        // Write into a copy and swap it in only if every op succeeds.
+        let mut staged = tables.clone();
+        for op in ops {
+            self.write(&mut staged, op)?;
         }
+        *tables = staged;
         Ok(())
     }
 
@@ -251,10 +542,373 @@ impl ReadModelStore for InMemoryReadModelStore {
         Ok(self.get_rows(table))
     }
 
+    async fn query(&self, table: &str, query: &Query) -> ReadModelResult<Page> {
+        let rows: Vec<(String, Row)> = self
+            .tables
+            .lock()
+            .unwrap()
+            .get(table)
+            .map(|t| t.iter().map(|(k, r)| (k.clone(), r.clone())).collect())
+            .unwrap_or_default();
+        query.evaluate(rows)
+    }
+
+    async fn count(&self, table: &str, query: &Query) -> ReadModelResult<u64> {
+        let tables = self.tables.lock().unwrap();
+        query.count_matching(tables.get(table).into_iter().flat_map(|t| t.values()))
+    }
+
     async fn truncate(&self, table: &str) -> ReadModelResult<()> {
         self.tables.lock().unwrap().remove(table);
         Ok(())
     }
+
+    async fn ensure_schema(&self, schema: &ReadModelSchema) -> ReadModelResult<()> {
+        schema.validate()?;
+        let tables = self.tables.lock().unwrap();
+        if let Some(rows) = tables.get(&schema.table) {
+            // Like CREATE UNIQUE INDEX over existing rows.
+            for (key, row) in rows {
+                if let Some(index) = unique_violation(schema, rows, key, row) {
+                    return Err(ReadModelError::schema_failed(format!(
+                        "UNIQUE constraint failed: {index}"
+                    )));
+                }
+            }
+        }
+        self.schemas
+            .lock()
+            .unwrap()
+            .insert(schema.table.clone(), schema.clone());
+        Ok(())
+    }
+
+    fn supports_table_swap(&self) -> bool {
+        true
+    }
+
+    async fn swap_tables(
+        &self,
+        schema: &ReadModelSchema,
+        incoming: &str,
+        outgoing: &str,
+    ) -> ReadModelResult<()> {
+        schema.validate()?;
+        let mut tables = self.tables.lock().unwrap();
+        let mut schemas = self.schemas.lock().unwrap();
+        // Truncated tables have no entry; a declared schema still marks
+        // the table as existing.
+        if !tables.contains_key(incoming) && !schemas.contains_key(incoming) {
+            return Err(ReadModelError::schema_failed(format!(
+                "no table {incoming} to swap in"
+            )));
+        }
+        if let Some(rows) = tables.get(incoming) {
+            for (key, row) in rows {
+                if let Some(index) = unique_violation(schema, rows, key, row) {
+                    return Err(ReadModelError::schema_failed(format!(
+                        "UNIQUE constraint failed: {index}"
+                    )));
+                }
+            }
+        }
+        let rows = tables.remove(incoming).unwrap_or_default();
+        schemas.remove(incoming);
+        schemas.remove(outgoing);
+        match tables.remove(&schema.table) {
+            Some(current) => tables.insert(outgoing.to_string(), current),
+            None => tables.remove(outgoing),
+        };
+        tables.insert(schema.table.clone(), rows);
+        schemas.insert(schema.table.clone(), schema.clone());
+        Ok(())
+    }
+}
+
+/// A [`ReadModelStore`] that holds writes back and hands them to the
+/// underlying store as one [`apply_batch`](ReadModelStore::apply_batch) on
+/// [`commit`](Self::commit).
+///
+/// Give it to a projector in place of the real store to make everything the
+/// projector writes, across any number of events, land atomically. Reads by
+/// primary key see the pending writes, with the version gate applied, so
+/// projectors that read a row back before updating it behave as they do
+/// against the store itself. Other reads (`find_by`, `list`, `query`,
+/// `count`) and `truncate` commit the pending writes first. Writes still
+/// pending when the buffer is dropped are discarded.
+pub struct WriteBuffer<'a> {
+    inner: &'a dyn ReadModelStore,
+    pending: Mutex<PendingWrites>,
+}
+
+#[derive(Default)]
+struct PendingWrites {
+    ops: Vec<WriteOp>,
+    /// Row each touched `(table, key)` will hold after the batch; `None`
+    /// once deleted.
+    rows: HashMap<(String, String), Option<Row>>,
+}
+
+impl<'a> WriteBuffer<'a> {
+    pub fn new(inner: &'a dyn ReadModelStore) -> Self {
+        Self {
+            inner,
+            pending: Mutex::new(PendingWrites::default()),
+        }
+    }
+
+    /// Number of writes waiting for [`commit`](Self::commit).
+    pub fn len(&self) -> usize {
+        self.pending.lock().unwrap().ops.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Apply the pending writes to the underlying store as one batch. On
+    /// error nothing was written and the pending writes are gone.
+    pub async fn commit(&self) -> ReadModelResult<()> {
+        let ops = {
+            let mut pending = self.pending.lock().unwrap();
+            pending.rows.clear();
+            std::mem::take(&mut pending.ops)
+        };
+        if ops.is_empty() {
+            return Ok(());
+        }
+        self.inner.apply_batch(ops).await
+    }
+
+    async fn visible(&self, table: &str, key: &str) -> ReadModelResult<Option<Row>> {
+        let buffered = self
+            .pending
+            .lock()
+            .unwrap()
+            .rows
+            .get(&(table.to_string(), key.to_string()))
+            .cloned();
+        match buffered {
+            Some(row) => Ok(row),
+            None => self.inner.get(table, key).await,
+        }
+    }
+
+    async fn push(&self, op: WriteOp) -> ReadModelResult<()> {
+        let (slot, row) = match &op {
+            WriteOp::Upsert(upsert) => {
+                // Gate here too, so reads through the buffer match what the
+                // store will hold after the batch.
+                let current = self.visible(&upsert.table, &upsert.key).await?;
+                if current.is_some_and(|row| row_version(&row) >= row_version(&upsert.row)) {
+                    return Ok(());
+                }
+                (
+                    (upsert.table.clone(), upsert.key.clone()),
+                    Some(upsert.row.clone()),
+                )
+            }
+            WriteOp::Delete { table, key } => ((table.clone(), key.clone()), None),
+        };
+        let mut pending = self.pending.lock().unwrap();
+        pending.rows.insert(slot, row);
+        pending.ops.push(op);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ReadModelStore for WriteBuffer<'_> {
+    async fn upsert(&self, op: Upsert) -> ReadModelResult<()> {
+        self.push(WriteOp::Upsert(op)).await
+    }
+
+    async fn delete(&self, table: &str, key: &str) -> ReadModelResult<()> {
+        self.push(WriteOp::delete(table, key)).await
+    }
+
+    async fn apply_batch(&self, ops: Vec<WriteOp>) -> ReadModelResult<()> {
+        // Joins the buffer's own batch, which is atomic as a whole.
+        for op in ops {
+            self.push(op).await?;
+        }
+        Ok(())
+    }
+
+    async fn get(&self, table: &str, key: &str) -> ReadModelResult<Option<Row>> {
+        self.visible(table, key).await
+    }
+
+    async fn find_by(
+        &self,
+        table: &str,
+        field: &str,
+        value: &serde_json::Value,
+    ) -> ReadModelResult<Vec<Row>> {
+        self.commit().await?;
+        self.inner.find_by(table, field, value).await
+    }
+
+    async fn list(&self, table: &str) -> ReadModelResult<Vec<Row>> {
+        self.commit().await?;
+        self.inner.list(table).await
+    }
+
+    async fn query(&self, table: &str, query: &Query) -> ReadModelResult<Page> {
+        self.commit().await?;
+        self.inner.query(table, query).await
+    }
+
+    async fn count(&self, table: &str, query: &Query) -> ReadModelResult<u64> {
+        self.commit().await?;
+        self.inner.count(table, query).await
+    }
+
+    async fn truncate(&self, table: &str) -> ReadModelResult<()> {
+        self.commit().await?;
+        self.inner.truncate(table).await
+    }
+
+    async fn ensure_schema(&self, schema: &ReadModelSchema) -> ReadModelResult<()> {
+        self.inner.ensure_schema(schema).await
+    }
+
+    fn supports_table_swap(&self) -> bool {
+        self.inner.supports_table_swap()
+    }
+
+    async fn swap_tables(
+        &self,
+        schema: &ReadModelSchema,
+        incoming: &str,
+        outgoing: &str,
+    ) -> ReadModelResult<()> {
+        self.commit().await?;
+        self.inner.swap_tables(schema, incoming, outgoing).await
+    }
+}
+
+/// A [`ReadModelStore`] that sends every operation on table `from` to table
+/// `to` instead, and passes other tables through.
+///
+/// Lets a projector that names its table in code write somewhere else:
+/// blue/green rebuilds run the projector against a `TableRedirect` to fill
+/// a shadow table while the live one keeps serving reads. Declared schemas
+/// for `from` are applied to `to`, with index names following the table.
+pub struct TableRedirect {
+    inner: Arc<dyn ReadModelStore>,
+    from: String,
+    to: String,
+}
+
+impl TableRedirect {
+    pub fn new(
+        inner: Arc<dyn ReadModelStore>,
+        from: impl Into<String>,
+        to: impl Into<String>,
+    ) -> Self {
+        Self {
+            inner,
+            from: from.into(),
+            to: to.into(),
+        }
+    }
+
+    fn table<'t>(&'t self, table: &'t str) -> &'t str {
+        if table == self.from {
+            &self.to
+        } else {
+            table
+        }
+    }
+
+    fn redirect(&self, op: WriteOp) -> WriteOp {
+        match op {
+            WriteOp::Upsert(op) => WriteOp::Upsert(Upsert {
+                table: self.table(&op.table).to_string(),
+                ..op
+            }),
+            WriteOp::Delete { table, key } => WriteOp::delete(self.table(&table), key),
+        }
+    }
+
+    fn redirect_schema(&self, schema: &ReadModelSchema) -> ReadModelSchema {
+        ReadModelSchema {
+            table: self.table(&schema.table).to_string(),
+            indexes: schema.indexes.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl ReadModelStore for TableRedirect {
+    async fn upsert(&self, op: Upsert) -> ReadModelResult<()> {
+        let table = self.table(&op.table).to_string();
+        self.inner.upsert(Upsert { table, ..op }).await
+    }
+
+    async fn delete(&self, table: &str, key: &str) -> ReadModelResult<()> {
+        self.inner.delete(self.table(table), key).await
+    }
+
+    async fn apply_batch(&self, ops: Vec<WriteOp>) -> ReadModelResult<()> {
+        let ops = ops.into_iter().map(|op| self.redirect(op)).collect();
+        self.inner.apply_batch(ops).await
+    }
+
+    async fn get(&self, table: &str, key: &str) -> ReadModelResult<Option<Row>> {
+        self.inner.get(self.table(table), key).await
+    }
+
+    async fn find_by(
+        &self,
+        table: &str,
+        field: &str,
+        value: &serde_json::Value,
+    ) -> ReadModelResult<Vec<Row>> {
+        self.inner.find_by(self.table(table), field, value).await
+    }
+
+    async fn list(&self, table: &str) -> ReadModelResult<Vec<Row>> {
+        self.inner.list(self.table(table)).await
+    }
+
+    async fn query(&self, table: &str, query: &Query) -> ReadModelResult<Page> {
+        self.inner.query(self.table(table), query).await
+    }
+
+    async fn count(&self, table: &str, query: &Query) -> ReadModelResult<u64> {
+        self.inner.count(self.table(table), query).await
+    }
+
+    async fn truncate(&self, table: &str) -> ReadModelResult<()> {
+        self.inner.truncate(self.table(table)).await
+    }
+
+    async fn ensure_schema(&self, schema: &ReadModelSchema) -> ReadModelResult<()> {
+        self.inner
+            .ensure_schema(&self.redirect_schema(schema))
+            .await
+    }
+
+    fn supports_table_swap(&self) -> bool {
+        self.inner.supports_table_swap()
+    }
+
+    async fn swap_tables(
+        &self,
+        schema: &ReadModelSchema,
+        incoming: &str,
+        outgoing: &str,
+    ) -> ReadModelResult<()> {
+        self.inner
+            .swap_tables(
+                &self.redirect_schema(schema),
+                self.table(incoming),
+                self.table(outgoing),
+            )
+            .await
+    }
 }
 
 #[cfg(test)]
@@ -341,6 +995,237 @@ mod tests {
         assert_eq!(hits[0]["id"], "u1");
     }
 
+    #[tokio::test]
+    async fn test_query_pages_by_key_and_counts() {
+        use crate::read_model_query::{Direction, Filter, Query};
+
+        let store = InMemoryReadModelStore::new();
+        for (id, name) in [("u1", "Carol"), ("u2", "Alice"), ("u3", "Bob")] {
+            store
+                .upsert(Upsert::new("users_view", id, row(id, name, 1)))
+                .await
+                .unwrap();
+        }
+
+        let query = Query::new()
+            .with_filter(Filter::ne("name", "Bob"))
+            .with_order("name", Direction::Asc)
+            .with_limit(1);
+        let first = store.query("users_view", &query).await.unwrap();
+        assert_eq!(first.rows[0]["name"], "Alice");
+        let cursor = first.next.unwrap();
+        assert_eq!(cursor.key, "u2");
+        let second = store
+            .query("users_view", &query.clone().with_cursor(cursor))
+            .await
+            .unwrap();
+        assert_eq!(second.rows[0]["name"], "Carol");
+
+        assert_eq!(store.count("users_view", &query).await.unwrap(), 2);
+        assert_eq!(store.count("missing", &query).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_ensure_schema_enforces_unique_indexes() {
+        let store = InMemoryReadModelStore::new();
+        let user = |id: &str, email: serde_json::Value, version: i64| json!({"id": id, "email": email, "version": version});
+        store
+            .upsert(Upsert::new(
+                "users_view",
+                "u1",
+                user("u1", json!("a@b.c"), 1),
+            ))
+            .await
+            .unwrap();
+        let schema = ReadModelSchema::new("users_view")
+            .with_index(ReadModelIndex::unique_on(["email"]))
+            .with_index(ReadModelIndex::on(["name"]));
+        store.ensure_schema(&schema).await.unwrap();
+
+        let err = store
+            .upsert(Upsert::new(
+                "users_view",
+                "u2",
+                user("u2", json!("a@b.c"), 1),
+            ))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ReadModelError::WriteFailed { ref message } if message.contains("idx_users_view_email")),
+            "expected unique violation, got {err:?}"
+        );
+        // The row itself may keep its value; nulls never collide.
+        store
+            .upsert(Upsert::new(
+                "users_view",
+                "u1",
+                user("u1", json!("a@b.c"), 2),
+            ))
+            .await
+            .unwrap();
+        for id in ["u3", "u4"] {
+            store
+                .upsert(Upsert::new("users_view", id, user(id, json!(null), 1)))
+                .await
+                .unwrap();
+        }
+
+        // Declaring a unique index over rows that already collide fails.
+        let names = ReadModelSchema::new("users_view")
+            .with_index(ReadModelIndex::unique_on(["email"]))
+            .with_index(ReadModelIndex::unique_on(["version"]));
+        let err = store.ensure_schema(&names).await.unwrap_err();
+        assert!(matches!(err, ReadModelError::SchemaFailed { .. }));
+
+        let twice = ReadModelSchema::new("users_view")
+            .with_index(ReadModelIndex::on(["email"]))
+            .with_index(ReadModelIndex::unique_on(["email"]));
+        assert!(store.ensure_schema(&twice).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_apply_batch_is_all_or_nothing() {
+        let store = InMemoryReadModelStore::new();
+        store
+            .ensure_schema(
+                &ReadModelSchema::new("users_view").with_index(ReadModelIndex::unique_on(["name"])),
+            )
+            .await
+            .unwrap();
+        store
+            .upsert(Upsert::new("users_view", "u1", row("u1", "Alice", 1)))
+            .await
+            .unwrap();
+
+        store
+            .apply_batch(vec![
+                Upsert::new("users_view", "u2", row("u2", "Bob", 1)).into(),
+                Upsert::new("members_view", "m1", row("m1", "Bob", 1)).into(),
+                // Gated out, not an error.
+                Upsert::new("users_view", "u1", row("u1", "Stale", 1)).into(),
+                WriteOp::delete("users_view", "u1"),
+            ])
+            .await
+            .unwrap();
+        assert!(store.get("users_view", "u1").await.unwrap().is_none());
+        assert_eq!(store.total_rows(), 2);
+
+        // The second op violates the unique index: the first is undone.
+        let err = store
+            .apply_batch(vec![
+                Upsert::new("members_view", "m2", row("m2", "Carol", 1)).into(),
+                Upsert::new("users_view", "u3", row("u3", "Bob", 1)).into(),
+            ])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ReadModelError::WriteFailed { .. }));
+        assert!(store.get("members_view", "m2").await.unwrap().is_none());
+        assert_eq!(store.total_rows(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_write_buffer_reads_its_own_writes_and_commits_once() {
+        let store = InMemoryReadModelStore::new();
+        store
+            .upsert(Upsert::new("users_view", "u1", row("u1", "Alice", 1)))
+            .await
+            .unwrap();
+
+        let buffer = WriteBuffer::new(&store);
+        buffer
+            .upsert(Upsert::new("users_view", "u1", row("u1", "Alice2", 2)))
+            .await
+            .unwrap();
+        buffer
+            .upsert(Upsert::new("users_view", "u1", row("u1", "Stale", 2)))
+            .await
+            .unwrap();
+        buffer
+            .upsert(Upsert::new("users_view", "u2", row("u2", "Bob", 1)))
+            .await
+            .unwrap();
+        buffer.delete("users_view", "u2").await.unwrap();
+
+        // Pending: visible through the buffer only, gate applied.
+        assert_eq!(buffer.len(), 3);
+        let seen = buffer.get("users_view", "u1").await.unwrap().unwrap();
+        assert_eq!(seen["name"], "Alice2");
+        assert!(buffer.get("users_view", "u2").await.unwrap().is_none());
+        let stored = store.get("users_view", "u1").await.unwrap().unwrap();
+        assert_eq!(stored["name"], "Alice");
+
+        buffer.commit().await.unwrap();
+        assert!(buffer.is_empty());
+        let stored = store.get("users_view", "u1").await.unwrap().unwrap();
+        assert_eq!(stored["name"], "Alice2");
+        assert_eq!(store.total_rows(), 1);
+
+        // Scans commit first, so they never miss a pending write.
+        buffer
+            .upsert(Upsert::new("users_view", "u3", row("u3", "Carol", 1)))
+            .await
+            .unwrap();
+        assert_eq!(buffer.list("users_view").await.unwrap().len(), 2);
+        assert!(buffer.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_swap_tables_puts_redirected_build_live_and_keeps_old() {
+        let store = Arc::new(InMemoryReadModelStore::new());
+        let schema =
+            ReadModelSchema::new("users_view").with_index(ReadModelIndex::unique_on(["name"]));
+        store.ensure_schema(&schema).await.unwrap();
+        store
+            .upsert(Upsert::new("users_view", "u1", row("u1", "Stale", 1)))
+            .await
+            .unwrap();
+
+        // Written as `users_view`, stored in the shadow table.
+        let shadow = TableRedirect::new(store.clone(), "users_view", "users_view__next");
+        shadow.ensure_schema(&schema).await.unwrap();
+        shadow
+            .upsert(Upsert::new("users_view", "u1", row("u1", "Alice", 1)))
+            .await
+            .unwrap();
+        assert_eq!(
+            shadow.get("users_view", "u1").await.unwrap().unwrap()["name"],
+            "Alice"
+        );
+        assert_eq!(store.get_rows("users_view")[0]["name"], "Stale");
+
+        let err = store
+            .swap_tables(&schema, "users_view__missing", "users_view__prev")
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ReadModelError::SchemaFailed { .. }),
+            "{err:?}"
+        );
+        assert_eq!(store.get_rows("users_view")[0]["name"], "Stale");
+
+        store
+            .swap_tables(&schema, "users_view__next", "users_view__prev")
+            .await
+            .unwrap();
+        assert_eq!(store.get_rows("users_view")[0]["name"], "Alice");
+        assert_eq!(store.get_rows("users_view__prev")[0]["name"], "Stale");
+        assert!(store.get_rows("users_view__next").is_empty());
+        // The schema moved with the name.
+        let err = store
+            .upsert(Upsert::new("users_view", "u2", row("u2", "Alice", 1)))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ReadModelError::WriteFailed { .. }), "{err:?}");
+
+        // And back again.
+        store
+            .swap_tables(&schema, "users_view__prev", "users_view__next")
+            .await
+            .unwrap();
+        assert_eq!(store.get_rows("users_view")[0]["name"], "Stale");
+        assert_eq!(store.get_rows("users_view__next")[0]["name"], "Alice");
+    }
+
     #[tokio::test]
     async fn test_delete_and_truncate() {
         let store = InMemoryReadModelStore::new();
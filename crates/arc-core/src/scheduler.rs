@@ -0,0 +1,916 @@
+//! # Command Scheduler
+//!
+//! Dispatches a command later: "delete this user in 30 days unless the
+//! deletion is cancelled", "send a reminder tomorrow". A
+//! [`CommandScheduler`] serializes the command into a [`ScheduledJob`] kept
+//! in a durable [`ScheduleStore`], so pending jobs survive restarts, and its
+//! [`run`](CommandScheduler::run) loop dispatches each one through the
+//! [`CommandRouter`] once it is due.
+//!
+//! ## Keys
+//!
+//! Every job has a caller-chosen key (`"delete-user:{id}"`). Scheduling
+//! under a key replaces whatever the key held, so rescheduling needs no
+//! cancel first, and [`cancel`](CommandScheduler::cancel) takes the key.
+//!
+//! ## Execution
+//!
+//! Due jobs are first claimed ([`JobStatus::Running`]), so a cancel that
+//! arrives while one is dispatching finds nothing pending and cannot be
+//! undone by the run's outcome. A claim older than the claim timeout is
+//! taken over, so jobs held by a scheduler that died still run.
+//!
+//! Due jobs run as the system actor, with the correlation and causation ids
+//! of the request that scheduled them. Each dispatch carries an idempotency
+//! key unique to the scheduling, so when a router configured with an
+//! idempotency store re-runs a job (the process died before recording the
+//! outcome) it replays the first result. Transient failures
+//! ([`CommandBusError::is_transient`]) are retried with backoff up to
+//! `max_attempts`; any other failure marks the job [`JobStatus::Failed`].
+
+use crate::aggregate::Command;
+use crate::audit::now_us;
+use crate::command_bus::{CommandBusError, CommandContext, CommandRouter};
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::any::TypeId;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// Default number of due jobs [`CommandScheduler::run_due`] dispatches per
+/// call.
+pub const DEFAULT_SCHEDULER_BATCH_SIZE: usize = 100;
+
+/// Default pause between polls once no job is due.
+pub const DEFAULT_SCHEDULER_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Default attempts per job, including the first.
+pub const DEFAULT_SCHEDULER_MAX_ATTEMPTS: u32 = 5;
+
+/// Default delay before the first retry of a transiently failed job; doubled
+/// after each further failure.
+pub const DEFAULT_SCHEDULER_RETRY_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Default age after which a claimed job is considered abandoned and can be
+/// claimed again.
+pub const DEFAULT_SCHEDULER_CLAIM_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Errors emitted by the scheduler and [`ScheduleStore`] implementations.
+#[derive(Debug, Error)]
+pub enum SchedulerError {
+    #[error("schedule store sink failure: {0}")]
+    Sink(String),
+    #[error("schedule store validation failure: {0}")]
+    Validation(String),
+    /// The command type was never [`register`](CommandScheduler::register)ed.
+    #[error("no scheduler route for command type '{command_type}'")]
+    UnknownCommand { command_type: String },
+    #[error("scheduled command payload does not (de)serialize: {0}")]
+    Payload(#[from] serde_json::Error),
+}
+
+impl SchedulerError {
+    pub fn unknown_command(command_type: impl Into<String>) -> Self {
+        SchedulerError::UnknownCommand {
+            command_type: command_type.into(),
+        }
+    }
+}
+
+/// Lifecycle of a [`ScheduledJob`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for `due_at_us` (or for a retry).
+    Pending,
+    /// Claimed by a scheduler and being dispatched; no longer cancellable.
+    Running,
+    /// Dispatched successfully.
+    Done,
+    /// Cancelled before it ran.
+    Cancelled,
+    /// Rejected, or out of attempts; see `last_error`.
+    Failed,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Running => "running",
+            JobStatus::Done => "done",
+            JobStatus::Cancelled => "cancelled",
+            JobStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(JobStatus::Pending),
+            "running" => Some(JobStatus::Running),
+            "done" => Some(JobStatus::Done),
+            "cancelled" => Some(JobStatus::Cancelled),
+            "failed" => Some(JobStatus::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// A command waiting to be dispatched, as persisted.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduledJob {
+    /// Caller-chosen key; at most one job per key.
+    pub key: String,
+    /// Identifies this scheduling of the key. A job rescheduled under the
+    /// same key gets a new id, so a stale run cannot overwrite it.
+    pub job_id: Uuid,
+    /// Name the command type was [`register`](CommandScheduler::register)ed
+    /// under.
+    pub command_type: String,
+    /// The serialized command.
+    pub payload: serde_json::Value,
+    /// Actor who scheduled the job. The job itself runs as the system actor.
+    pub scheduled_by: String,
+    pub correlation_id: Uuid,
+    pub causation_id: Option<Uuid>,
+    pub due_at_us: i64,
+    pub status: JobStatus,
+    /// Dispatch attempts so far.
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub created_at_us: i64,
+    pub updated_at_us: i64,
+}
+
+impl ScheduledJob {
+    /// Whether the job should run at `now_us`.
+    pub fn is_due_at(&self, now_us: i64) -> bool {
+        self.status == JobStatus::Pending && self.due_at_us <= now_us
+    }
+
+    /// Whether [`ScheduleStore::claim_due`] may claim the job at `now_us`:
+    /// it is due, or its claim was taken at or before `stale_before_us`.
+    pub fn is_claimable_at(&self, now_us: i64, stale_before_us: i64) -> bool {
+        self.is_due_at(now_us)
+            || (self.status == JobStatus::Running && self.updated_at_us <= stale_before_us)
+    }
+
+    /// Shared input checks for [`ScheduleStore::schedule`].
+    pub fn validate(&self) -> Result<(), SchedulerError> {
+        if self.key.trim().is_empty() {
+            return Err(SchedulerError::Validation("key empty".into()));
+        }
+        if self.command_type.trim().is_empty() {
+            return Err(SchedulerError::Validation("command_type empty".into()));
+        }
+        if self.scheduled_by.trim().is_empty() {
+            return Err(SchedulerError::Validation("scheduled_by empty".into()));
+        }
+        Ok(())
+    }
+}
+
+/// Durable home of [`ScheduledJob`]s.
+///
+/// Implementations:
+/// - [`InMemoryScheduleStore`] — `Arc<Mutex<HashMap>>`, behind `test-utils`
+/// - `SqliteScheduleStore` — in `arc-es-sqlite`, durable
+#[async_trait]
+pub trait ScheduleStore: Send + Sync {
+    /// Store `job`, replacing any job under the same key.
+    async fn schedule(&self, job: &ScheduledJob) -> Result<(), SchedulerError>;
+
+    async fn get(&self, key: &str) -> Result<Option<ScheduledJob>, SchedulerError>;
+
+    /// Cancel the pending job under `key`. Returns `false` when there is
+    /// none (never scheduled, running, already run, failed or cancelled).
+    async fn cancel(&self, key: &str, now_us: i64) -> Result<bool, SchedulerError>;
+
+    /// Claim up to `limit` jobs, earliest due first: pending jobs due at or
+    /// before `now_us`, and running jobs claimed at or before
+    /// `stale_before_us`. Claimed jobs are returned, and stored,
+    /// [`JobStatus::Running`] with `updated_at_us = now_us`. A job is handed
+    /// to one caller only, even when several claim at once.
+    async fn claim_due(
+        &self,
+        now_us: i64,
+        stale_before_us: i64,
+        limit: usize,
+    ) -> Result<Vec<ScheduledJob>, SchedulerError>;
+
+    /// Save the outcome of running `job`. Only touches the row while it
+    /// still holds `job.job_id` and is running; returns `false` when the key
+    /// has been rescheduled since.
+    async fn record(&self, job: &ScheduledJob) -> Result<bool, SchedulerError>;
+}
+
+type Dispatch = Arc<
+    dyn Fn(
+            Arc<CommandRouter>,
+            serde_json::Value,
+            CommandContext,
+        ) -> BoxFuture<'static, Result<(), JobError>>
+        + Send
+        + Sync,
+>;
+
+/// Why a job's dispatch failed.
+enum JobError {
+    Payload(serde_json::Error),
+    Command(CommandBusError),
+}
+
+/// Schedules commands and dispatches them through a [`CommandRouter`] when
+/// due.
+///
+/// ```rust,ignore
+/// let scheduler = CommandScheduler::new(Arc::new(store), router.clone())
+///     .register::<UserCommand>("UserCommand");
+///
+/// let due = now_us() + 30 * 24 * 3600 * 1_000_000;
+/// scheduler.schedule(format!("delete-user:{id}"), &UserCommand::DeleteUser { id }, due, &ctx).await?;
+/// scheduler.cancel(&format!("delete-user:{id}")).await?;
+///
+/// let (stop, stopped) = tokio::sync::watch::channel(false);
+/// tokio::spawn(async move { scheduler.run(stopped).await });
+/// ```
+#[derive(Clone)]
+pub struct CommandScheduler {
+    store: Arc<dyn ScheduleStore>,
+    router: Arc<CommandRouter>,
+    names: HashMap<TypeId, &'static str>,
+    routes: HashMap<&'static str, Dispatch>,
+    batch_size: usize,
+    poll_interval: Duration,
+    max_attempts: u32,
+    retry_backoff: Duration,
+    claim_timeout: Duration,
+}
+
+impl CommandScheduler {
+    pub fn new(store: Arc<dyn ScheduleStore>, router: Arc<CommandRouter>) -> Self {
+        Self {
+            store,
+            router,
+            names: HashMap::new(),
+            routes: HashMap::new(),
+            batch_size: DEFAULT_SCHEDULER_BATCH_SIZE,
+            poll_interval: DEFAULT_SCHEDULER_POLL_INTERVAL,
+            max_attempts: DEFAULT_SCHEDULER_MAX_ATTEMPTS,
+            retry_backoff: DEFAULT_SCHEDULER_RETRY_BACKOFF,
+            claim_timeout: DEFAULT_SCHEDULER_CLAIM_TIMEOUT,
+        }
+    }
+
+    /// Allow commands of type `C` to be scheduled, stored under
+    /// `command_type`. The name is persisted with every job, so it must stay
+    /// stable for as long as jobs may be pending.
+    ///
+    /// # Panics
+    ///
+    /// If `command_type` or `C` is already registered.
+    pub fn register<C>(mut self, command_type: &'static str) -> Self
+    where
+        C: Command + Serialize + DeserializeOwned + 'static,
+    {
+        assert!(
+            self.names.insert(TypeId::of::<C>(), command_type).is_none(),
+            "{} is already registered with the scheduler",
+            std::any::type_name::<C>()
+        );
+        let dispatch: Dispatch = Arc::new(|router, payload, context| {
+            Box::pin(async move {
+                let command: C = serde_json::from_value(payload).map_err(JobError::Payload)?;
+                router
+                    .dispatch(command, context)
+                    .await
+                    .map(|_| ())
+                    .map_err(JobError::Command)
+            })
+        });
+        assert!(
+            self.routes.insert(command_type, dispatch).is_none(),
+            "scheduler command type '{command_type}' is already registered"
+        );
+        self
+    }
+
+    /// Due jobs dispatched per [`run_due`](Self::run_due) call. Clamped to
+    /// at least 1.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Pause between polls when no job is due.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Attempts per job, including the first (clamped to at least 1), and
+    /// the delay before the first retry, doubled after each further failure.
+    pub fn with_retry(mut self, max_attempts: u32, backoff: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.retry_backoff = backoff;
+        self
+    }
+
+    /// Age after which a claimed job that never recorded an outcome is
+    /// claimed again. Keep it well above the longest dispatch.
+    pub fn with_claim_timeout(mut self, claim_timeout: Duration) -> Self {
+        self.claim_timeout = claim_timeout;
+        self
+    }
+
+    /// Schedule `command` to be dispatched at `due_at_us`, replacing any job
+    /// under `key`. `context` is the scheduling request's: its actor is
+    /// recorded as `scheduled_by` and its correlation and causation ids
+    /// carry over to the dispatch.
+    pub async fn schedule<C>(
+        &self,
+        key: impl Into<String>,
+        command: &C,
+        due_at_us: i64,
+        context: &CommandContext,
+    ) -> Result<ScheduledJob, SchedulerError>
+    where
+        C: Command + Serialize + 'static,
+    {
+        let command_type = self
+            .names
+            .get(&TypeId::of::<C>())
+            .ok_or_else(|| SchedulerError::unknown_command(std::any::type_name::<C>()))?;
+        let now_us = now_us();
+        let job = ScheduledJob {
+            key: key.into(),
+            job_id: Uuid::new_v4(),
+            command_type: command_type.to_string(),
+            payload: serde_json::to_value(command)?,
+            scheduled_by: context.actor_id.clone(),
+            correlation_id: context.correlation_id,
+            causation_id: context.causation_id,
+            due_at_us,
+            status: JobStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            created_at_us: now_us,
+            updated_at_us: now_us,
+        };
+        job.validate()?;
+        self.store.schedule(&job).await?;
+        Ok(job)
+    }
+
+    /// Cancel the pending job under `key`. Returns `false` if nothing was
+    /// pending, including when the job is already being dispatched.
+    pub async fn cancel(&self, key: &str) -> Result<bool, SchedulerError> {
+        self.store.cancel(key, now_us()).await
+    }
+
+    pub async fn get(&self, key: &str) -> Result<Option<ScheduledJob>, SchedulerError> {
+        self.store.get(key).await
+    }
+
+    /// Claim and dispatch up to one batch of jobs due at `now_us`. Returns
+    /// how many were attempted.
+    pub async fn run_due(&self, now_us: i64) -> Result<usize, SchedulerError> {
+        let timeout_us = i64::try_from(self.claim_timeout.as_micros()).unwrap_or(i64::MAX);
+        let due = self
+            .store
+            .claim_due(now_us, now_us.saturating_sub(timeout_us), self.batch_size)
+            .await?;
+        let attempted = due.len();
+        for job in due {
+            self.execute(job, now_us).await?;
+        }
+        Ok(attempted)
+    }
+
+    /// Dispatch due jobs until `shutdown` turns `true` (or its sender is
+    /// dropped). Drains full batches back to back and sleeps
+    /// `poll_interval` otherwise. Store errors are logged and retried on the
+    /// next poll.
+    pub async fn run(&self, mut shutdown: watch::Receiver<bool>) {
+        tracing::info!("Command scheduler started");
+        while !*shutdown.borrow() {
+            let idle = match self.run_due(now_us()).await {
+                Ok(n) => n < self.batch_size,
+                Err(e) => {
+                    tracing::error!(error = %e, "Command scheduler poll failed");
+                    true
+                }
+            };
+            if idle {
+                tokio::select! {
+                    _ = tokio::time::sleep(self.poll_interval) => {}
+                    changed = shutdown.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        tracing::info!("Command scheduler stopped");
+    }
+
+    async fn execute(&self, mut job: ScheduledJob, now_us: i64) -> Result<(), SchedulerError> {
+        let mut context = CommandContext::system()
+            .with_idempotency_key(format!("scheduled:{}:{}", job.key, job.job_id));
+        context.correlation_id = job.correlation_id;
+        context.causation_id = job.causation_id;
+
+        job.attempts += 1;
+        job.updated_at_us = now_us;
+        let result = match self.routes.get(job.command_type.as_str()) {
+            Some(dispatch) => dispatch(self.router.clone(), job.payload.clone(), context).await,
+            None => Err(JobError::Command(CommandBusError::no_route(
+                job.command_type.clone(),
+            ))),
+        };
+        match result {
+            Ok(()) => {
+                job.status = JobStatus::Done;
+                job.last_error = None;
+            }
+            Err(JobError::Command(e)) if e.is_transient() && job.attempts < self.max_attempts => {
+                let factor = 1u32.checked_shl(job.attempts - 1).unwrap_or(u32::MAX);
+                let backoff = self.retry_backoff.saturating_mul(factor);
+                let backoff_us = i64::try_from(backoff.as_micros()).unwrap_or(i64::MAX);
+                tracing::warn!(
+                    key = %job.key,
+                    attempts = job.attempts,
+                    error = %e,
+                    "Scheduled command failed; will retry"
+                );
+                job.status = JobStatus::Pending;
+                job.due_at_us = now_us.saturating_add(backoff_us);
+                job.last_error = Some(e.to_string());
+            }
+            Err(e) => {
+                let message = match e {
+                    JobError::Payload(e) => SchedulerError::Payload(e).to_string(),
+                    JobError::Command(e) => e.to_string(),
+                };
+                tracing::error!(key = %job.key, error = %message, "Scheduled command failed");
+                job.status = JobStatus::Failed;
+                job.last_error = Some(message);
+            }
+        }
+        if !self.store.record(&job).await? {
+            tracing::info!(key = %job.key, "Scheduled job was replaced while it ran");
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for CommandScheduler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut command_types: Vec<_> = self.routes.keys().collect();
+        command_types.sort_unstable();
+        f.debug_struct("CommandScheduler")
+            .field("command_types", &command_types)
+            .field("batch_size", &self.batch_size)
+            .field("poll_interval", &self.poll_interval)
+            .field("max_attempts", &self.max_attempts)
+            .field("retry_backoff", &self.retry_backoff)
+            .field("claim_timeout", &self.claim_timeout)
+            .finish()
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// In-memory implementation. Public behind the `test-utils` feature so
+// downstream tests can use it.
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(any(test, feature = "test-utils"))]
+mod in_memory {
+    use super::*;
+    use tokio::sync::Mutex;
+
+    #[derive(Clone, Default)]
+    pub struct InMemoryScheduleStore {
+        inner: Arc<Mutex<HashMap<String, ScheduledJob>>>,
+    }
+
+    impl InMemoryScheduleStore {
+        pub fn new() -> Self {
+            Self::default()
+        }
+    }
+
+    #[async_trait]
+    impl ScheduleStore for InMemoryScheduleStore {
+        async fn schedule(&self, job: &ScheduledJob) -> Result<(), SchedulerError> {
+            job.validate()?;
+            self.inner.lock().await.insert(job.key.clone(), job.clone());
+            Ok(())
+        }
+
+        async fn get(&self, key: &str) -> Result<Option<ScheduledJob>, SchedulerError> {
+            Ok(self.inner.lock().await.get(key).cloned())
+        }
+
+        async fn cancel(&self, key: &str, now_us: i64) -> Result<bool, SchedulerError> {
+            let mut g = self.inner.lock().await;
+            match g.get_mut(key) {
+                Some(job) if job.status == JobStatus::Pending => {
+                    job.status = JobStatus::Cancelled;
+                    job.updated_at_us = now_us;
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        }
+
+        async fn claim_due(
+            &self,
+            now_us: i64,
+            stale_before_us: i64,
+            limit: usize,
+        ) -> Result<Vec<ScheduledJob>, SchedulerError> {
+            let mut g = self.inner.lock().await;
+            let mut due: Vec<_> = g
+                .values_mut()
+                .filter(|j| j.is_claimable_at(now_us, stale_before_us))
+                .collect();
+            due.sort_by_key(|j| j.due_at_us);
+            due.truncate(limit);
+            Ok(due
+                .into_iter()
+                .map(|job| {
+                    job.status = JobStatus::Running;
+                    job.updated_at_us = now_us;
+                    job.clone()
+                })
+                .collect())
+        }
+
+        async fn record(&self, job: &ScheduledJob) -> Result<bool, SchedulerError> {
+            let mut g = self.inner.lock().await;
+            match g.get_mut(&job.key) {
+                Some(stored)
+                    if stored.job_id == job.job_id && stored.status == JobStatus::Running =>
+                {
+                    *stored = job.clone();
+                    Ok(true)
+                }
+                _ => Ok(false),
+            }
+        }
+    }
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+pub use in_memory::InMemoryScheduleStore;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::Aggregate;
+    use crate::command_middleware::{CommandEnvelope, CommandMiddleware};
+    use crate::event::Event;
+    use crate::event_bus::InProcessEventBus;
+    use crate::event_store::{EventStoreError, InMemoryEventStore};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Appends a `Pinged` event, or fails the way `mode` says.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    struct Ping {
+        id: String,
+        mode: String,
+    }
+
+    impl Command for Ping {
+        fn aggregate_id(&self) -> &str {
+            &self.id
+        }
+    }
+
+    #[derive(Default)]
+    struct Pinger {
+        version: i64,
+    }
+
+    #[derive(Debug, Error)]
+    #[error("refused")]
+    struct Refused;
+
+    #[async_trait]
+    impl Aggregate for Pinger {
+        type Command = Ping;
+        type Event = ();
+        type Error = Refused;
+
+        fn aggregate_type() -> &'static str {
+            "Pinger"
+        }
+
+        fn version(&self) -> i64 {
+            self.version
+        }
+
+        async fn handle(&self, command: Ping) -> Result<Vec<Event>, Refused> {
+            if command.mode == "refuse" {
+                return Err(Refused);
+            }
+            Ok(vec![Event::new(
+                "Pinger",
+                &command.id,
+                self.version + 1,
+                "Pinged",
+                json!({}),
+            )])
+        }
+
+        fn apply(&mut self, event: &Event) {
+            self.version = event.sequence;
+        }
+    }
+
+    fn scheduler() -> (CommandScheduler, InMemoryScheduleStore, Arc<CommandRouter>) {
+        let store = InMemoryScheduleStore::new();
+        let router = Arc::new(
+            CommandRouter::new(
+                Box::new(InMemoryEventStore::new()),
+                Box::new(InProcessEventBus::new()),
+            )
+            .register::<Pinger>(),
+        );
+        let scheduler = CommandScheduler::new(Arc::new(store.clone()), router.clone())
+            .register::<Ping>("Ping")
+            .with_retry(2, Duration::from_secs(1));
+        (scheduler, store, router)
+    }
+
+    fn ping(mode: &str) -> Ping {
+        Ping {
+            id: "p1".into(),
+            mode: mode.into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_job_runs_once_due_as_system_in_the_request_correlation() {
+        let (scheduler, _store, router) = scheduler();
+        let ctx = CommandContext::for_actor("alice");
+        let job = scheduler
+            .schedule("ping:p1", &ping("ok"), 1_000, &ctx)
+            .await
+            .unwrap();
+        assert_eq!(job.scheduled_by, "alice");
+
+        assert_eq!(scheduler.run_due(999).await.unwrap(), 0);
+        assert_eq!(scheduler.run_due(1_000).await.unwrap(), 1);
+        let job = scheduler.get("ping:p1").await.unwrap().unwrap();
+        assert_eq!(job.status, JobStatus::Done);
+        assert_eq!(job.attempts, 1);
+        assert_eq!(scheduler.run_due(2_000).await.unwrap(), 0);
+
+        let events = router.event_store().load("p1").await.unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].audit.actor_id, "system");
+        assert_eq!(events[0].audit.correlation_id, ctx.correlation_id);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_and_replaced_jobs() {
+        let (scheduler, store, router) = scheduler();
+        let ctx = CommandContext::system();
+        scheduler
+            .schedule("ping:p1", &ping("ok"), 1_000, &ctx)
+            .await
+            .unwrap();
+        assert!(scheduler.cancel("ping:p1").await.unwrap());
+        assert!(!scheduler.cancel("ping:p1").await.unwrap());
+        assert_eq!(scheduler.run_due(5_000).await.unwrap(), 0);
+        assert!(router.event_store().load("p1").await.unwrap().is_empty());
+
+        // Rescheduling replaces the job; a stale run cannot overwrite it.
+        let first = scheduler
+            .schedule("ping:p1", &ping("ok"), 1_000, &ctx)
+            .await
+            .unwrap();
+        let second = scheduler
+            .schedule("ping:p1", &ping("ok"), 9_000, &ctx)
+            .await
+            .unwrap();
+        assert_eq!(scheduler.run_due(5_000).await.unwrap(), 0);
+        assert!(!store.record(&first).await.unwrap());
+        assert_eq!(store.get("ping:p1").await.unwrap(), Some(second));
+    }
+
+    #[tokio::test]
+    async fn test_rejected_command_fails_the_job() {
+        let (scheduler, _store, _router) = scheduler();
+        scheduler
+            .schedule("ping:p1", &ping("refuse"), 0, &CommandContext::system())
+            .await
+            .unwrap();
+        scheduler.run_due(0).await.unwrap();
+        let job = scheduler.get("ping:p1").await.unwrap().unwrap();
+        assert_eq!(job.status, JobStatus::Failed);
+        assert!(job.last_error.unwrap().contains("refused"));
+    }
+
+    /// Fails every dispatch with a store outage while `down` is set.
+    struct Outage {
+        down: AtomicBool,
+    }
+
+    #[async_trait]
+    impl CommandMiddleware for Outage {
+        fn name(&self) -> &str {
+            "outage"
+        }
+
+        async fn before(
+            &self,
+            command: &CommandEnvelope<'_>,
+            _context: &mut CommandContext,
+        ) -> Result<(), CommandBusError> {
+            if self.down.load(Ordering::SeqCst) {
+                return Err(CommandBusError::LoadFailed {
+                    aggregate_id: command.aggregate_id.to_string(),
+                    source: EventStoreError::DatabaseError {
+                        message: "connection refused".into(),
+                    },
+                });
+            }
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_transient_failures_retry_with_backoff_then_fail() {
+        let outage = Arc::new(Outage { down: true.into() });
+        let router = Arc::new(
+            CommandRouter::new(
+                Box::new(InMemoryEventStore::new()),
+                Box::new(InProcessEventBus::new()),
+            )
+            .with_middleware(outage.clone())
+            .register::<Pinger>(),
+        );
+        let scheduler = CommandScheduler::new(Arc::new(InMemoryScheduleStore::new()), router)
+            .register::<Ping>("Ping")
+            .with_retry(3, Duration::from_micros(10));
+        let ctx = CommandContext::system();
+        scheduler.schedule("a", &ping("ok"), 0, &ctx).await.unwrap();
+
+        scheduler.run_due(100).await.unwrap();
+        let a = scheduler.get("a").await.unwrap().unwrap();
+        assert_eq!(
+            (a.status, a.attempts, a.due_at_us),
+            (JobStatus::Pending, 1, 110)
+        );
+        assert!(a.last_error.unwrap().contains("connection refused"));
+
+        // The backoff doubles.
+        assert_eq!(scheduler.run_due(109).await.unwrap(), 0);
+        scheduler.run_due(110).await.unwrap();
+        let a = scheduler.get("a").await.unwrap().unwrap();
+        assert_eq!(
+            (a.status, a.attempts, a.due_at_us),
+            (JobStatus::Pending, 2, 130)
+        );
+
+        // The retry after the outage goes through.
+        outage.down.store(false, Ordering::SeqCst);
+        scheduler.run_due(130).await.unwrap();
+        let a = scheduler.get("a").await.unwrap().unwrap();
+        assert_eq!(
+            (a.status, a.attempts, a.last_error),
+            (JobStatus::Done, 3, None)
+        );
+
+        // Out of attempts.
+        outage.down.store(true, Ordering::SeqCst);
+        scheduler
+            .schedule("b", &ping("ok"), 200, &ctx)
+            .await
+            .unwrap();
+        for now_us in [200, 210, 230] {
+            scheduler.run_due(now_us).await.unwrap();
+        }
+        let b = scheduler.get("b").await.unwrap().unwrap();
+        assert_eq!((b.status, b.attempts), (JobStatus::Failed, 3));
+    }
+
+    /// Cancels `key` from inside the dispatch, as a `DELETE` racing the
+    /// scheduler would, and keeps what the cancel returned.
+    struct CancelDuringDispatch {
+        store: InMemoryScheduleStore,
+        key: &'static str,
+        outcome: tokio::sync::Mutex<Option<bool>>,
+    }
+
+    #[async_trait]
+    impl CommandMiddleware for CancelDuringDispatch {
+        fn name(&self) -> &str {
+            "cancel_during_dispatch"
+        }
+
+        async fn before(
+            &self,
+            _command: &CommandEnvelope<'_>,
+            _context: &mut CommandContext,
+        ) -> Result<(), CommandBusError> {
+            let cancelled = self.store.cancel(self.key, 1_000).await.unwrap();
+            *self.outcome.lock().await = Some(cancelled);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancel_between_claim_and_record_cannot_be_overwritten() {
+        let store = InMemoryScheduleStore::new();
+        let racer = Arc::new(CancelDuringDispatch {
+            store: store.clone(),
+            key: "ping:p1",
+            outcome: Default::default(),
+        });
+        let router = Arc::new(
+            CommandRouter::new(
+                Box::new(InMemoryEventStore::new()),
+                Box::new(InProcessEventBus::new()),
+            )
+            .with_middleware(racer.clone())
+            .register::<Pinger>(),
+        );
+        let scheduler =
+            CommandScheduler::new(Arc::new(store.clone()), router).register::<Ping>("Ping");
+        scheduler
+            .schedule("ping:p1", &ping("ok"), 0, &CommandContext::system())
+            .await
+            .unwrap();
+
+        scheduler.run_due(1_000).await.unwrap();
+        // The job was already claimed: the cancel reports nothing pending
+        // rather than a cancellation the run then overwrites.
+        assert_eq!(*racer.outcome.lock().await, Some(false));
+        let job = scheduler.get("ping:p1").await.unwrap().unwrap();
+        assert_eq!(job.status, JobStatus::Done);
+
+        // A cancel between claim and record leaves the claimed job alone,
+        // and the outcome is recorded.
+        let job = scheduler
+            .schedule("ping:p2", &ping("ok"), 0, &CommandContext::system())
+            .await
+            .unwrap();
+        let claimed = store.claim_due(1_000, 0, 10).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].status, JobStatus::Running);
+        assert!(!scheduler.cancel("ping:p2").await.unwrap());
+        let mut done = claimed[0].clone();
+        done.status = JobStatus::Done;
+        assert!(store.record(&done).await.unwrap());
+        assert_eq!(done.job_id, job.job_id);
+        assert_eq!(
+            store.get("ping:p2").await.unwrap().unwrap().status,
+            JobStatus::Done
+        );
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_claim_is_taken_over() {
+        let (scheduler, store, router) = scheduler();
+        let scheduler = scheduler.with_claim_timeout(Duration::from_micros(100));
+        scheduler
+            .schedule("ping:p1", &ping("ok"), 0, &CommandContext::system())
+            .await
+            .unwrap();
+
+        // Claimed at 100 by a scheduler that died before recording.
+        assert_eq!(store.claim_due(100, 0, 10).await.unwrap().len(), 1);
+        assert_eq!(scheduler.run_due(199).await.unwrap(), 0);
+        assert_eq!(scheduler.run_due(200).await.unwrap(), 1);
+        let job = scheduler.get("ping:p1").await.unwrap().unwrap();
+        assert_eq!((job.status, job.attempts), (JobStatus::Done, 1));
+        assert_eq!(router.event_store().load("p1").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unregistered_command_cannot_be_scheduled() {
+        let store = InMemoryScheduleStore::new();
+        let router = Arc::new(CommandRouter::new(
+            Box::new(InMemoryEventStore::new()),
+            Box::new(InProcessEventBus::new()),
+        ));
+        let scheduler = CommandScheduler::new(Arc::new(store), router);
+        let err = scheduler
+            .schedule("k", &ping("ok"), 0, &CommandContext::system())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SchedulerError::UnknownCommand { .. }));
+    }
+}
@@ -56,7 +56,8 @@ impl SessionRecord {
 /// - [`InMemorySessionStore`] — `Arc<Mutex<HashMap>>`, ships in this crate
 ///   behind `test-utils` and is also a viable single-node production option
 /// - `SqliteSessionStore` — in `arc-es-sqlite`, durable
-/// - Future: Postgres, Redis
+/// - `PostgresSessionStore` — in `arc-es-postgres`, durable
+/// - Future: Redis
 #[async_trait]
 pub trait SessionStore: Send + Sync {
     /// Record a freshly-minted token. Called by the login controller before
@@ -0,0 +1,139 @@
+//! # Snapshot Module
+//!
+//! Point-in-time captures of aggregate state, used by the
+//! [`CommandBus`](crate::command_bus::CommandBus) to skip replaying the full
+//! event stream on every command.
+//!
+//! ## Design Principles
+//!
+//! - **Optional**: aggregates opt in by overriding
+//!   [`Aggregate::to_snapshot`](crate::aggregate::Aggregate::to_snapshot) /
+//!   [`Aggregate::from_snapshot`](crate::aggregate::Aggregate::from_snapshot).
+//!   Aggregates that don't are always rebuilt from events.
+//! - **Disposable**: snapshots are a cache, never the source of truth. Losing
+//!   or discarding one only costs a full replay; events stay authoritative.
+//! - **Versioned**: each snapshot records the aggregate's
+//!   `snapshot_schema_version()`. When the aggregate's state shape changes,
+//!   bumping that number invalidates every older snapshot instead of
+//!   deserializing it into the wrong shape.
+//! - **Latest only**: stores keep one snapshot per aggregate; saving replaces it.
+
+use serde::{Deserialize, Serialize};
+
+/// Serialized aggregate state as of `version`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Snapshot {
+    /// Aggregate type (e.g., "User"). Checked on load so a snapshot is never
+    /// restored into a different aggregate that shares an id.
+    pub aggregate_type: String,
+
+    /// Aggregate instance identifier
+    pub aggregate_id: String,
+
+    /// Sequence of the last event folded into `state`
+    pub version: i64,
+
+    /// `Aggregate::snapshot_schema_version()` at the time the snapshot was taken
+    pub schema_version: u32,
+
+    /// Aggregate state as produced by `Aggregate::to_snapshot()`
+    pub state: serde_json::Value,
+
+    /// When the snapshot was taken (microseconds since UNIX epoch)
+    pub taken_at_us: i64,
+}
+
+impl Snapshot {
+    /// Build a snapshot stamped with the current time.
+    pub fn new(
+        aggregate_type: impl Into<String>,
+        aggregate_id: impl Into<String>,
+        version: i64,
+        schema_version: u32,
+        state: serde_json::Value,
+    ) -> Self {
+        Self {
+            aggregate_type: aggregate_type.into(),
+            aggregate_id: aggregate_id.into(),
+            version,
+            schema_version,
+            state,
+            taken_at_us: crate::audit::now_us(),
+        }
+    }
+}
+
+/// How often the `CommandBus` writes a fresh snapshot.
+///
+/// The cadence is measured from the last stored snapshot, not from absolute
+/// sequence numbers, so an aggregate that predates snapshot support gets one
+/// on its next command instead of waiting for the next multiple of `N`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SnapshotPolicy {
+    /// Never read or write snapshots. Every dispatch replays the full stream.
+    #[default]
+    Never,
+    /// Write a snapshot once at least `N` events have been appended since the
+    /// last one. `EveryNEvents(0)` behaves like `Never`.
+    EveryNEvents(u64),
+}
+
+impl SnapshotPolicy {
+    /// Whether the bus should consult snapshots at all.
+    pub fn is_enabled(&self) -> bool {
+        matches!(self, SnapshotPolicy::EveryNEvents(n) if *n > 0)
+    }
+
+    /// Whether an aggregate now at `current_version`, last snapshotted at
+    /// `snapshot_version` (0 if never), is due for a new snapshot.
+    pub fn should_snapshot(&self, snapshot_version: i64, current_version: i64) -> bool {
+        match self {
+            SnapshotPolicy::Never => false,
+            SnapshotPolicy::EveryNEvents(0) => false,
+            SnapshotPolicy::EveryNEvents(n) => {
+                current_version.saturating_sub(snapshot_version) >= *n as i64
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_policy_never_is_default_and_disabled() {
+        let policy = SnapshotPolicy::default();
+        assert_eq!(policy, SnapshotPolicy::Never);
+        assert!(!policy.is_enabled());
+        assert!(!policy.should_snapshot(0, 1_000));
+    }
+
+    #[test]
+    fn test_policy_every_n_measures_from_last_snapshot() {
+        let policy = SnapshotPolicy::EveryNEvents(10);
+        assert!(policy.is_enabled());
+        assert!(!policy.should_snapshot(0, 9));
+        assert!(policy.should_snapshot(0, 10));
+        assert!(!policy.should_snapshot(10, 19));
+        assert!(policy.should_snapshot(10, 20));
+        // Legacy aggregate far past the first boundary snapshots immediately.
+        assert!(policy.should_snapshot(0, 537));
+    }
+
+    #[test]
+    fn test_policy_every_zero_is_disabled() {
+        let policy = SnapshotPolicy::EveryNEvents(0);
+        assert!(!policy.is_enabled());
+        assert!(!policy.should_snapshot(0, 100));
+    }
+
+    #[test]
+    fn test_snapshot_new_stamps_time() {
+        let snap = Snapshot::new("User", "u1", 5, 1, json!({"name": "A"}));
+        assert_eq!(snap.version, 5);
+        assert_eq!(snap.schema_version, 1);
+        assert!(snap.taken_at_us > 0);
+    }
+}
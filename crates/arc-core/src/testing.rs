@@ -0,0 +1,818 @@
+//! # Test Fixtures
+//!
+//! Given/When/Then helpers for testing domain code without an event store.
+//! Available to downstream crates behind the `test-utils` feature.
+//!
+//! [`AggregateFixture`] folds a history of typed events into an aggregate,
+//! hands it a command and checks what came back:
+//!
+//! ```rust,ignore
+//! AggregateFixture::<UserAggregate>::new("u1")
+//!     .given([UserDomainEvent::UserRegistered { id: "u1".into(), /* … */ }])
+//!     .when(UserCommand::DeleteUser { id: "u1".into() })
+//!     .await
+//!     .then_expect_events([UserDomainEvent::UserDeleted]);
+//! ```
+//!
+//! [`ProjectorFixture`] feeds events to a projector over an
+//! [`InMemoryReadModelStore`] — in order, duplicated or shuffled — and checks
+//! the resulting rows:
+//!
+//! ```rust,ignore
+//! let mut fixture = ProjectorFixture::new(UserProjector::new(), "User");
+//! let registered = fixture.event("u1", UserDomainEvent::UserRegistered { /* … */ });
+//! let renamed = fixture.event("u1", UserDomainEvent::ProfileUpdated { name: "Al".into() });
+//! fixture.deliver([&renamed, &registered, &renamed]).await;
+//! fixture.then_expect_row(USERS_VIEW, "u1", json!({ /* … */ })).await;
+//! fixture.then_expect_replay_converges().await;
+//! ```
+//!
+//! Events are numbered 1, 2, … per aggregate and stamped with
+//! [`AuditMetadata::test_default`], so tests state only event payloads. The
+//! `then_*` assertions panic with a per-field diff of the payloads or rows
+//! that differ.
+
+use crate::aggregate::Aggregate;
+use crate::audit::AuditMetadata;
+use crate::domain_event::DomainEvent;
+use crate::event::{Event, GlobalPosition};
+use crate::projection::{init_projector, ProjectionResult, Projector};
+use crate::read_model_store::{InMemoryReadModelStore, ReadModelStore, Row};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+/// Given/When/Then harness for one aggregate instance.
+pub struct AggregateFixture<A: Aggregate> {
+    aggregate_id: String,
+    history: Vec<Event>,
+    aggregate: A,
+}
+
+impl<A: Aggregate> AggregateFixture<A> {
+    /// A fixture for the aggregate `aggregate_id`, with no history.
+    pub fn new(aggregate_id: impl Into<String>) -> Self {
+        Self {
+            aggregate_id: aggregate_id.into(),
+            history: Vec::new(),
+            aggregate: A::default(),
+        }
+    }
+
+    /// Append typed events to the history and apply them, numbered after
+    /// the events already given.
+    ///
+    /// # Panics
+    ///
+    /// If an event does not encode (a mistake in the event enum).
+    pub fn given<E, I>(self, events: I) -> Self
+    where
+        E: DomainEvent,
+        I: IntoIterator<Item = E>,
+    {
+        let aggregate_id = self.aggregate_id.clone();
+        let events = events.into_iter().map(|event| {
+            Event::from_domain(A::aggregate_type(), &aggregate_id, 0, &event).unwrap_or_else(|e| {
+                panic!("given event {} does not encode: {e}", event.event_type())
+            })
+        });
+        self.given_events(events)
+    }
+
+    /// Append already-built events to the history and apply them. Their
+    /// aggregate type, id and sequence are overwritten to continue this
+    /// fixture's stream; audit metadata is stamped when still pending.
+    pub fn given_events<I>(mut self, events: I) -> Self
+    where
+        I: IntoIterator<Item = Event>,
+    {
+        for mut event in events {
+            event.aggregate_type = A::aggregate_type().to_string();
+            event.aggregate_id = self.aggregate_id.clone();
+            event.sequence = self.history.len() as i64 + 1;
+            if event.audit.is_pending() {
+                event.audit = AuditMetadata::test_default();
+            }
+            self.aggregate.apply(&event);
+            self.history.push(event);
+        }
+        self
+    }
+
+    /// The events given so far, as stored.
+    pub fn history(&self) -> &[Event] {
+        &self.history
+    }
+
+    /// Handle `command` against the state built from the history.
+    pub async fn when(self, command: A::Command) -> AggregateOutcome<A> {
+        let result = self.aggregate.handle(command).await;
+        AggregateOutcome {
+            aggregate_id: self.aggregate_id,
+            next_sequence: self.history.len() as i64 + 1,
+            aggregate: self.aggregate,
+            result,
+        }
+    }
+}
+
+/// What an [`AggregateFixture`] command produced.
+pub struct AggregateOutcome<A: Aggregate> {
+    aggregate_id: String,
+    next_sequence: i64,
+    aggregate: A,
+    result: Result<Vec<Event>, A::Error>,
+}
+
+impl<A: Aggregate> AggregateOutcome<A> {
+    /// Assert the command emitted exactly `expected`, in order, numbered
+    /// right after the history and addressed to this aggregate.
+    #[track_caller]
+    pub fn then_expect_events<E, I>(self, expected: I) -> Self
+    where
+        E: DomainEvent,
+        I: IntoIterator<Item = E>,
+    {
+        let expected: Vec<(String, Value)> = expected
+            .into_iter()
+            .map(|event| {
+                let payload = event.to_payload().unwrap_or_else(|e| {
+                    panic!("expected event {} does not encode: {e}", event.event_type())
+                });
+                (event.event_type().to_string(), payload)
+            })
+            .collect();
+        self.check_events(&expected);
+        self
+    }
+
+    /// Assert the command succeeded without emitting anything.
+    #[track_caller]
+    pub fn then_expect_no_events(self) -> Self {
+        self.check_events(&[]);
+        self
+    }
+
+    /// Assert the command was rejected with an error displaying as
+    /// `message`.
+    #[track_caller]
+    pub fn then_expect_error(self, message: &str) -> Self {
+        match &self.result {
+            Err(e) => assert_eq!(e.to_string(), message, "unexpected error"),
+            Ok(events) => panic!(
+                "expected error '{message}', got {} event(s): {:?}",
+                events.len(),
+                event_types(events)
+            ),
+        }
+        self
+    }
+
+    /// Assert the command was rejected with an error satisfying
+    /// `predicate`, e.g. `|e| matches!(e, UserAggregateError::NotFound)`.
+    #[track_caller]
+    pub fn then_expect_error_matching(self, predicate: impl FnOnce(&A::Error) -> bool) -> Self {
+        match &self.result {
+            Err(e) => assert!(predicate(e), "error did not match: {e}"),
+            Ok(events) => panic!(
+                "expected an error, got {} event(s): {:?}",
+                events.len(),
+                event_types(events)
+            ),
+        }
+        self
+    }
+
+    /// The emitted events, or the error.
+    pub fn result(&self) -> Result<&[Event], &A::Error> {
+        self.result.as_deref()
+    }
+
+    /// The aggregate with the history and any emitted events applied.
+    pub fn into_aggregate(mut self) -> A {
+        if let Ok(events) = &self.result {
+            for event in events {
+                self.aggregate.apply(event);
+            }
+        }
+        self.aggregate
+    }
+
+    #[track_caller]
+    fn check_events(&self, expected: &[(String, Value)]) {
+        let actual = match &self.result {
+            Ok(events) => events,
+            Err(e) => panic!("expected {} event(s), got error: {e}", expected.len()),
+        };
+        assert_eq!(
+            event_types(actual),
+            expected.iter().map(|(t, _)| t.as_str()).collect::<Vec<_>>(),
+            "emitted event types differ"
+        );
+
+        let mut report = String::new();
+        for (i, (event, (event_type, payload))) in actual.iter().zip(expected).enumerate() {
+            let sequence = self.next_sequence + i as i64;
+            if event.sequence != sequence {
+                let _ = writeln!(
+                    report,
+                    "event {i} ({event_type}): sequence {} != {sequence}",
+                    event.sequence
+                );
+            }
+            if event.aggregate_type != A::aggregate_type()
+                || event.aggregate_id != self.aggregate_id
+            {
+                let _ = writeln!(
+                    report,
+                    "event {i} ({event_type}): addressed to {}/{}, not {}/{}",
+                    event.aggregate_type,
+                    event.aggregate_id,
+                    A::aggregate_type(),
+                    self.aggregate_id
+                );
+            }
+            let mut diffs = Vec::new();
+            diff_json("", payload, &event.payload, &mut diffs);
+            for diff in diffs {
+                let _ = writeln!(report, "event {i} ({event_type}): {diff}");
+            }
+        }
+        assert!(report.is_empty(), "emitted events differ:\n{report}");
+    }
+}
+
+/// Harness feeding events to one [`Projector`] over an
+/// [`InMemoryReadModelStore`].
+///
+/// Every event built by [`event`](Self::event) is also recorded, in build
+/// order, as the fixture's stream: what an event store would hold. Delivery
+/// is separate, so a test can hand the projector that stream duplicated or
+/// out of order, the way at-least-once buses do.
+pub struct ProjectorFixture<P: Projector> {
+    projector: P,
+    aggregate_type: String,
+    store: InMemoryReadModelStore,
+    initialized: bool,
+    sequences: HashMap<String, i64>,
+    stream: Vec<Event>,
+}
+
+impl<P: Projector> ProjectorFixture<P> {
+    /// A fixture for `projector`, building events of `aggregate_type`.
+    pub fn new(projector: P, aggregate_type: impl Into<String>) -> Self {
+        Self {
+            projector,
+            aggregate_type: aggregate_type.into(),
+            store: InMemoryReadModelStore::new(),
+            initialized: false,
+            sequences: HashMap::new(),
+            stream: Vec::new(),
+        }
+    }
+
+    /// Build the next event of `aggregate_id`'s stream and record it,
+    /// without delivering it.
+    ///
+    /// # Panics
+    ///
+    /// If `event` does not encode (a mistake in the event enum).
+    pub fn event<E: DomainEvent>(&mut self, aggregate_id: &str, event: E) -> Event {
+        let sequence = self.sequences.entry(aggregate_id.to_string()).or_default();
+        *sequence += 1;
+        let mut stored = Event::from_domain(&self.aggregate_type, aggregate_id, *sequence, &event)
+            .unwrap_or_else(|e| panic!("event {} does not encode: {e}", event.event_type()))
+            .with_audit(AuditMetadata::test_default());
+        stored.position = Some(GlobalPosition(self.stream.len() as i64 + 1));
+        self.stream.push(stored.clone());
+        stored
+    }
+
+    /// Build `events` for `aggregate_id` and deliver them in order.
+    pub async fn given<E, I>(&mut self, aggregate_id: &str, events: I) -> &mut Self
+    where
+        E: DomainEvent,
+        I: IntoIterator<Item = E>,
+    {
+        let built: Vec<Event> = events
+            .into_iter()
+            .map(|event| self.event(aggregate_id, event))
+            .collect();
+        self.deliver(&built).await
+    }
+
+    /// Deliver `events` to the projector in the order given, skipping event
+    /// types it does not handle, as the projection engine does.
+    ///
+    /// # Panics
+    ///
+    /// If the projector fails on an event.
+    pub async fn deliver<'a, I>(&mut self, events: I) -> &mut Self
+    where
+        I: IntoIterator<Item = &'a Event>,
+    {
+        for event in events {
+            if let Err(e) = self.try_deliver(event).await {
+                panic!(
+                    "{} failed on {}: {e}",
+                    self.projector.name(),
+                    event.event_type
+                );
+            }
+        }
+        self
+    }
+
+    /// Deliver one event, returning the projector's error instead of
+    /// panicking.
+    pub async fn try_deliver(&mut self, event: &Event) -> ProjectionResult<()> {
+        if !self.initialized {
+            init_projector(&self.projector, &self.store).await?;
+            self.initialized = true;
+        }
+        if !self.projector.handles().contains(&event.event_type) {
+            return Ok(());
+        }
+        self.projector.apply(event, &self.store).await
+    }
+
+    /// The events built so far, in build order.
+    pub fn stream(&self) -> &[Event] {
+        &self.stream
+    }
+
+    /// The store the projector writes to.
+    pub fn store(&self) -> &InMemoryReadModelStore {
+        &self.store
+    }
+
+    /// Assert `table` holds `expected` under `key`.
+    pub async fn then_expect_row(&self, table: &str, key: &str, expected: Value) -> &Self {
+        let Some(actual) = self.row(table, key).await else {
+            panic!("{table}/{key}: expected a row, found none");
+        };
+        assert_rows_match(&format!("{table}/{key}"), &expected, &actual);
+        self
+    }
+
+    /// Assert `table` holds no row under `key`.
+    pub async fn then_expect_no_row(&self, table: &str, key: &str) -> &Self {
+        if let Some(row) = self.row(table, key).await {
+            panic!("{table}/{key}: expected no row, found {row}");
+        }
+        self
+    }
+
+    /// Assert `table` holds exactly the `(key, row)` pairs in `expected`.
+    #[track_caller]
+    pub fn then_expect_table<'k, I>(&self, table: &str, expected: I) -> &Self
+    where
+        I: IntoIterator<Item = (&'k str, Value)>,
+    {
+        let expected: BTreeMap<String, Row> = expected
+            .into_iter()
+            .map(|(key, row)| (key.to_string(), row))
+            .collect();
+        let actual = self.store.tables().remove(table).unwrap_or_default();
+        let mut report = String::new();
+        diff_tables(table, &expected, &actual, &mut report);
+        assert!(report.is_empty(), "{table} differs:\n{report}");
+        self
+    }
+
+    /// Assert that replaying the stream into an empty store and then
+    /// replaying it again yields identical tables, the promise the version
+    /// gate on [`ReadModelStore::upsert`] makes for rebuilds and redelivery.
+    pub async fn then_expect_replay_converges(&self) -> &Self {
+        let replay = InMemoryReadModelStore::new();
+        if let Err(e) = init_projector(&self.projector, &replay).await {
+            panic!("{} init failed: {e}", self.projector.name());
+        }
+        let handles = self.projector.handles();
+        let mut passes = Vec::new();
+        for _ in 0..2 {
+            for event in &self.stream {
+                if !handles.contains(&event.event_type) {
+                    continue;
+                }
+                if let Err(e) = self.projector.apply(event, &replay).await {
+                    panic!(
+                        "{} failed replaying {}: {e}",
+                        self.projector.name(),
+                        event.event_type
+                    );
+                }
+            }
+            passes.push(replay.tables());
+        }
+
+        let (once, twice) = (&passes[0], &passes[1]);
+        let mut report = String::new();
+        let mut names: Vec<&String> = once.keys().chain(twice.keys()).collect();
+        names.sort_unstable();
+        names.dedup();
+        let empty = BTreeMap::new();
+        for name in names {
+            let first = once.get(name).unwrap_or(&empty);
+            let second = twice.get(name).unwrap_or(&empty);
+            diff_tables(name, first, second, &mut report);
+        }
+        assert!(
+            report.is_empty(),
+            "replaying the stream a second time changed the read model \
+             (expected: after one replay, got: after two):\n{report}"
+        );
+        self
+    }
+
+    async fn row(&self, table: &str, key: &str) -> Option<Row> {
+        self.store
+            .get(table, key)
+            .await
+            .unwrap_or_else(|e| panic!("{table}/{key}: read failed: {e}"))
+    }
+}
+
+#[track_caller]
+fn assert_rows_match(label: &str, expected: &Value, actual: &Value) {
+    let mut diffs = Vec::new();
+    diff_json("", expected, actual, &mut diffs);
+    assert!(
+        diffs.is_empty(),
+        "{label} differs:\n{}",
+        diffs
+            .iter()
+            .map(|d| format!("{label}: {d}\n"))
+            .collect::<String>()
+    );
+}
+
+/// Append a line per missing, unexpected or differing row.
+fn diff_tables(
+    table: &str,
+    expected: &BTreeMap<String, Row>,
+    actual: &BTreeMap<String, Row>,
+    report: &mut String,
+) {
+    let mut keys: Vec<&String> = expected.keys().chain(actual.keys()).collect();
+    keys.sort_unstable();
+    keys.dedup();
+    for key in keys {
+        match (expected.get(key), actual.get(key)) {
+            (Some(e), Some(a)) => {
+                let mut diffs = Vec::new();
+                diff_json("", e, a, &mut diffs);
+                for diff in diffs {
+                    let _ = writeln!(report, "{table}/{key}: {diff}");
+                }
+            }
+            (Some(_), None) => {
+                let _ = writeln!(report, "{table}/{key}: expected a row, missing");
+            }
+            (None, Some(a)) => {
+                let _ = writeln!(report, "{table}/{key}: unexpected row {a}");
+            }
+            (None, None) => {}
+        }
+    }
+}
+
+fn event_types(events: &[Event]) -> Vec<&str> {
+    events.iter().map(|e| e.event_type.as_str()).collect()
+}
+
+/// Collect `path: expected … actual …` lines for every leaf where `actual`
+/// differs from `expected`.
+fn diff_json(path: &str, expected: &Value, actual: &Value, out: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(e), Value::Object(a)) => {
+            let mut keys: Vec<&String> = e.keys().chain(a.keys()).collect();
+            keys.sort_unstable();
+            keys.dedup();
+            for key in keys {
+                let path = format!("{path}.{key}");
+                match (e.get(key), a.get(key)) {
+                    (Some(e), Some(a)) => diff_json(&path, e, a, out),
+                    (Some(e), None) => out.push(format!("{path}: expected {e}, missing")),
+                    (None, Some(a)) => out.push(format!("{path}: unexpected {a}")),
+                    (None, None) => {}
+                }
+            }
+        }
+        (Value::Array(e), Value::Array(a)) if e.len() == a.len() => {
+            for (i, (e, a)) in e.iter().zip(a).enumerate() {
+                diff_json(&format!("{path}[{i}]"), e, a, out);
+            }
+        }
+        _ if expected != actual => {
+            let path = if path.is_empty() { "." } else { path };
+            out.push(format!("{path}: expected {expected}, got {actual}"));
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aggregate::Command;
+    use crate::read_model_store::Upsert;
+    use async_trait::async_trait;
+    use serde::{Deserialize, Serialize};
+    use serde_json::json;
+    use thiserror::Error;
+
+    #[derive(Debug)]
+    enum LampCommand {
+        Install { id: String, watts: u32 },
+        Switch { id: String, on: bool },
+    }
+
+    impl Command for LampCommand {
+        fn aggregate_id(&self) -> &str {
+            match self {
+                LampCommand::Install { id, .. } | LampCommand::Switch { id, .. } => id,
+            }
+        }
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    #[serde(tag = "type")]
+    enum LampEvent {
+        Installed { watts: u32 },
+        SwitchedOn,
+        SwitchedOff,
+    }
+
+    impl DomainEvent for LampEvent {
+        const EVENT_TYPES: &'static [&'static str] = &["Installed", "SwitchedOn", "SwitchedOff"];
+
+        fn event_type(&self) -> &'static str {
+            match self {
+                LampEvent::Installed { .. } => "Installed",
+                LampEvent::SwitchedOn => "SwitchedOn",
+                LampEvent::SwitchedOff => "SwitchedOff",
+            }
+        }
+    }
+
+    #[derive(Debug, Error)]
+    enum LampError {
+        #[error("lamp not installed")]
+        NotInstalled,
+    }
+
+    #[derive(Default)]
+    struct Lamp {
+        version: i64,
+        installed: bool,
+        on: bool,
+    }
+
+    #[async_trait]
+    impl Aggregate for Lamp {
+        type Command = LampCommand;
+        type Event = LampEvent;
+        type Error = LampError;
+
+        fn aggregate_type() -> &'static str {
+            "Lamp"
+        }
+
+        fn version(&self) -> i64 {
+            self.version
+        }
+
+        async fn handle(&self, command: LampCommand) -> Result<Vec<Event>, LampError> {
+            let (id, event) = match command {
+                LampCommand::Install { id, watts } => (id, LampEvent::Installed { watts }),
+                LampCommand::Switch { .. } if !self.installed => {
+                    return Err(LampError::NotInstalled)
+                }
+                LampCommand::Switch { on, .. } if on == self.on => return Ok(vec![]),
+                LampCommand::Switch { id, on: true } => (id, LampEvent::SwitchedOn),
+                LampCommand::Switch { id, on: false } => (id, LampEvent::SwitchedOff),
+            };
+            Ok(vec![Event::from_domain(
+                "Lamp",
+                id,
+                self.version + 1,
+                &event,
+            )
+            .unwrap()])
+        }
+
+        fn apply(&mut self, event: &Event) {
+            self.version = event.sequence;
+            match event.decode::<LampEvent>() {
+                Ok(LampEvent::Installed { .. }) => self.installed = true,
+                Ok(LampEvent::SwitchedOn) => self.on = true,
+                Ok(LampEvent::SwitchedOff) => self.on = false,
+                Err(_) => {}
+            }
+        }
+    }
+
+    fn switch(on: bool) -> LampCommand {
+        LampCommand::Switch {
+            id: "l1".into(),
+            on,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_events_follow_the_numbered_history() {
+        let fixture = AggregateFixture::<Lamp>::new("l1")
+            .given([LampEvent::Installed { watts: 40 }])
+            .given([LampEvent::SwitchedOn]);
+        let sequences: Vec<_> = fixture.history().iter().map(|e| e.sequence).collect();
+        assert_eq!(sequences, [1, 2]);
+        assert!(fixture.history().iter().all(|e| !e.audit.is_pending()));
+
+        let lamp = fixture
+            .when(switch(false))
+            .await
+            .then_expect_events([LampEvent::SwitchedOff])
+            .into_aggregate();
+        assert_eq!(lamp.version, 3);
+        assert!(!lamp.on);
+    }
+
+    #[tokio::test]
+    async fn test_no_events_and_errors() {
+        AggregateFixture::<Lamp>::new("l1")
+            .given([LampEvent::Installed { watts: 40 }])
+            .when(switch(false))
+            .await
+            .then_expect_no_events();
+
+        AggregateFixture::<Lamp>::new("l1")
+            .when(switch(true))
+            .await
+            .then_expect_error("lamp not installed")
+            .then_expect_error_matching(|e| matches!(e, LampError::NotInstalled));
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "event 0 (Installed): .watts: expected 60, got 40")]
+    async fn test_payload_mismatch_reports_the_field() {
+        AggregateFixture::<Lamp>::new("l1")
+            .when(LampCommand::Install {
+                id: "l1".into(),
+                watts: 40,
+            })
+            .await
+            .then_expect_events([LampEvent::Installed { watts: 60 }]);
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "addressed to Lamp/l2, not Lamp/l1")]
+    async fn test_events_for_another_aggregate_are_reported() {
+        AggregateFixture::<Lamp>::new("l1")
+            .when(LampCommand::Install {
+                id: "l2".into(),
+                watts: 40,
+            })
+            .await
+            .then_expect_events([LampEvent::Installed { watts: 40 }]);
+    }
+
+    /// Projects lamps into `lamps`, version-gated on the event sequence.
+    /// With `counting`, also tallies switches in a row that ignores the
+    /// sequence, which a replay double-counts.
+    struct LampProjector {
+        counting: bool,
+    }
+
+    #[async_trait]
+    impl Projector for LampProjector {
+        fn name(&self) -> &str {
+            "LampProjector"
+        }
+
+        fn handles(&self) -> Vec<String> {
+            vec!["Installed".into(), "SwitchedOn".into()]
+        }
+
+        async fn apply(&self, event: &Event, store: &dyn ReadModelStore) -> ProjectionResult<()> {
+            let id = &event.aggregate_id;
+            let row = match event.decode::<LampEvent>().unwrap() {
+                LampEvent::Installed { watts } => {
+                    json!({ "id": id, "watts": watts, "on": false, "version": event.sequence })
+                }
+                _ => {
+                    let Some(mut row) = store.get("lamps", id).await.unwrap() else {
+                        return Ok(());
+                    };
+                    row["on"] = json!(true);
+                    row["version"] = json!(event.sequence);
+                    if self.counting {
+                        let count = store.get("switches", "all").await.unwrap();
+                        let n = count.map_or(0, |c| c["n"].as_i64().unwrap()) + 1;
+                        store
+                            .upsert(Upsert::new(
+                                "switches",
+                                "all",
+                                json!({ "n": n, "version": n }),
+                            ))
+                            .await
+                            .unwrap();
+                    }
+                    row
+                }
+            };
+            store.upsert(Upsert::new("lamps", id, row)).await.unwrap();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_projector_fixture_with_redelivery_and_reordering() {
+        let mut fixture = ProjectorFixture::new(LampProjector { counting: false }, "Lamp");
+        fixture
+            .given("l1", [LampEvent::Installed { watts: 40 }])
+            .await;
+        let installed = fixture.event("l2", LampEvent::Installed { watts: 60 });
+        let on = fixture.event("l2", LampEvent::SwitchedOn);
+        let off = fixture.event("l2", LampEvent::SwitchedOff);
+        assert_eq!((installed.sequence, on.sequence, off.sequence), (1, 2, 3));
+        assert_eq!(fixture.stream().len(), 4);
+
+        // `SwitchedOff` is not handled; the late `Installed` is gated out.
+        fixture
+            .deliver([&installed, &on, &on, &off, &installed])
+            .await;
+        fixture
+            .then_expect_row(
+                "lamps",
+                "l2",
+                json!({ "id": "l2", "watts": 60, "on": true, "version": 2 }),
+            )
+            .await
+            .then_expect_no_row("lamps", "l3")
+            .await
+            .then_expect_table(
+                "lamps",
+                [
+                    (
+                        "l1",
+                        json!({ "id": "l1", "watts": 40, "on": false, "version": 1 }),
+                    ),
+                    (
+                        "l2",
+                        json!({ "id": "l2", "watts": 60, "on": true, "version": 2 }),
+                    ),
+                ],
+            )
+            .then_expect_replay_converges()
+            .await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "lamps/l1: .watts: expected 60, got 40")]
+    async fn test_projector_fixture_reports_row_differences() {
+        let mut fixture = ProjectorFixture::new(LampProjector { counting: false }, "Lamp");
+        fixture
+            .given("l1", [LampEvent::Installed { watts: 40 }])
+            .await
+            .then_expect_row(
+                "lamps",
+                "l1",
+                json!({ "id": "l1", "watts": 60, "on": false, "version": 1 }),
+            )
+            .await;
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "switches/all: .n: expected 1, got 2")]
+    async fn test_projector_fixture_catches_replay_divergence() {
+        let mut fixture = ProjectorFixture::new(LampProjector { counting: true }, "Lamp");
+        fixture
+            .given(
+                "l1",
+                [LampEvent::Installed { watts: 40 }, LampEvent::SwitchedOn],
+            )
+            .await
+            .then_expect_replay_converges()
+            .await;
+    }
+
+    #[test]
+    fn test_diff_json_lists_every_differing_leaf() {
+        let mut diffs = Vec::new();
+        diff_json(
+            "",
+            &json!({ "a": 1, "b": { "c": [1, 2] }, "gone": true }),
+            &json!({ "a": 1, "b": { "c": [1, 3] }, "extra": null }),
+            &mut diffs,
+        );
+        assert_eq!(
+            diffs,
+            [
+                ".b.c[1]: expected 2, got 3",
+                ".extra: unexpected null",
+                ".gone: expected true, missing",
+            ]
+        );
+    }
+}
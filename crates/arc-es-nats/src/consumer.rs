@@ -0,0 +1,305 @@
+//! Durable JetStream pull consumer that drives an [`EventHandler`].
+//!
+//! The consumer filters on `events.*.<event_type>` for every type the
+//! handler declares and acks a message only after `handle` returned `Ok`.
+//! A failed event is nak'd with a delay and redelivered, up to
+//! [`DEFAULT_MAX_DELIVER`] times; a payload that is not an [`Event`] is
+//! terminated so it cannot block the consumer. Delivery is at-least-once,
+//! so handlers must be idempotent (projectors already are).
+
+use crate::{filter_subject_for, NatsEventBus};
+use arc_core::event::Event;
+use arc_core::event_bus::{EventBusError, EventBusResult, EventHandler};
+use async_nats::jetstream::consumer::{pull, AckPolicy, DeliverPolicy, PullConsumer};
+use async_nats::jetstream::AckKind;
+use futures::StreamExt;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Default number of messages requested per pull.
+pub const DEFAULT_CONSUMER_BATCH_SIZE: usize = 100;
+
+/// Default time a pull waits for messages before returning what it has.
+pub const DEFAULT_FETCH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Default delay before a nak'd event is redelivered.
+pub const DEFAULT_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+/// Deliveries per event before JetStream stops redelivering it.
+pub const DEFAULT_MAX_DELIVER: i64 = 10;
+
+/// Pulls events for one handler from a durable JetStream consumer.
+pub struct NatsConsumer {
+    consumer: PullConsumer,
+    handler: Arc<dyn EventHandler>,
+    batch_size: usize,
+    fetch_timeout: Duration,
+    retry_delay: Duration,
+}
+
+impl NatsConsumer {
+    /// Create or reuse the durable consumer `durable_name` on the bus's
+    /// stream. A new consumer starts at the beginning of the stream; an
+    /// existing one resumes after its last acked message and keeps the
+    /// filter it was created with.
+    pub async fn new(
+        bus: &NatsEventBus,
+        durable_name: &str,
+        handler: Arc<dyn EventHandler>,
+    ) -> EventBusResult<Self> {
+        let filter_subjects: Vec<String> = handler
+            .handles()
+            .iter()
+            .map(|t| filter_subject_for(t))
+            .collect();
+        if filter_subjects.is_empty() {
+            return Err(EventBusError::subscription_failed(format!(
+                "Consumer '{durable_name}' handles no event types"
+            )));
+        }
+
+        let stream = bus
+            .jetstream()
+            .get_stream(bus.stream_name())
+            .await
+            .map_err(|e| {
+                EventBusError::subscription_failed(format!(
+                    "Failed to open stream '{}': {e}",
+                    bus.stream_name()
+                ))
+            })?;
+        let consumer = stream
+            .get_or_create_consumer(
+                durable_name,
+                pull::Config {
+                    durable_name: Some(durable_name.to_string()),
+                    filter_subjects,
+                    deliver_policy: DeliverPolicy::All,
+                    ack_policy: AckPolicy::Explicit,
+                    max_deliver: DEFAULT_MAX_DELIVER,
+                    ..Default::default()
+                },
+            )
+            .await
+            .map_err(|e| {
+                EventBusError::subscription_failed(format!(
+                    "Failed to create consumer '{durable_name}': {e}"
+                ))
+            })?;
+
+        Ok(Self {
+            consumer,
+            handler,
+            batch_size: DEFAULT_CONSUMER_BATCH_SIZE,
+            fetch_timeout: DEFAULT_FETCH_TIMEOUT,
+            retry_delay: DEFAULT_RETRY_DELAY,
+        })
+    }
+
+    /// Messages requested per pull. Clamped to at least 1.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// How long a pull waits for messages. Also bounds how quickly
+    /// [`run`](Self::run) notices shutdown.
+    pub fn with_fetch_timeout(mut self, fetch_timeout: Duration) -> Self {
+        self.fetch_timeout = fetch_timeout;
+        self
+    }
+
+    /// Delay before a failed event is redelivered.
+    pub fn with_retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Pull one batch and hand each event to the handler. Returns how many
+    /// events were handled (and acked).
+    pub async fn process_batch(&self) -> EventBusResult<usize> {
+        let mut messages = self
+            .consumer
+            .batch()
+            .max_messages(self.batch_size)
+            .expires(self.fetch_timeout)
+            .messages()
+            .await
+            .map_err(|e| EventBusError::other(format!("Failed to pull from NATS: {e}")))?;
+
+        let mut handled = 0;
+        while let Some(message) = messages.next().await {
+            let message = message
+                .map_err(|e| EventBusError::other(format!("Failed to receive from NATS: {e}")))?;
+            let ack = match serde_json::from_slice::<Event>(&message.payload) {
+                Err(e) => {
+                    tracing::error!(
+                        subject = %message.subject,
+                        error = %e,
+                        "Dropping NATS message that is not an event"
+                    );
+                    AckKind::Term
+                }
+                Ok(event) => match self.handler.handle(&event).await {
+                    Ok(()) => {
+                        handled += 1;
+                        AckKind::Ack
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            event_type = %event.event_type,
+                            event_id = %event.event_id,
+                            error = %e,
+                            "NATS consumer handler failed; event will be redelivered"
+                        );
+                        AckKind::Nak(Some(self.retry_delay))
+                    }
+                },
+            };
+            message
+                .ack_with(ack)
+                .await
+                .map_err(|e| EventBusError::other(format!("Failed to ack NATS message: {e}")))?;
+        }
+        Ok(handled)
+    }
+
+    /// Consume until `shutdown` turns `true` (or its sender is dropped).
+    ///
+    /// Each pull long-polls for up to the fetch timeout, so the loop never
+    /// spins; errors are logged and retried after the retry delay.
+    pub async fn run(&self, mut shutdown: watch::Receiver<bool>) {
+        tracing::info!("NATS consumer started");
+        while !*shutdown.borrow() && shutdown.has_changed().is_ok() {
+            if let Err(e) = self.process_batch().await {
+                tracing::error!(error = %e, "NATS consumer pull failed");
+                tokio::select! {
+                    _ = tokio::time::sleep(self.retry_delay) => {}
+                    changed = shutdown.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        tracing::info!("NATS consumer stopped");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{user_event, CountingHandler, NatsServer};
+    use arc_core::event_bus::EventBus;
+    use std::sync::atomic::Ordering;
+
+    fn fast(consumer: NatsConsumer) -> NatsConsumer {
+        consumer
+            .with_fetch_timeout(Duration::from_millis(200))
+            .with_retry_delay(Duration::from_millis(50))
+    }
+
+    #[tokio::test]
+    #[ignore = "needs nats-server; run with `make test-nats`"]
+    async fn test_consumer_handles_only_declared_types() {
+        let server = NatsServer::spawn().await;
+        let bus = server.bus().await;
+        bus.publish(vec![
+            user_event("u1", 1, "UserCreated"),
+            user_event("u1", 2, "UserUpdated"),
+            user_event("u2", 1, "UserCreated"),
+        ])
+        .await
+        .unwrap();
+
+        let (handler, handled) = CountingHandler::new(&["UserCreated"], 0);
+        let consumer = fast(
+            NatsConsumer::new(&bus, "users", Arc::new(handler))
+                .await
+                .unwrap(),
+        );
+
+        assert_eq!(consumer.process_batch().await.unwrap(), 2);
+        assert_eq!(handled.load(Ordering::SeqCst), 2);
+        assert_eq!(consumer.process_batch().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs nats-server; run with `make test-nats`"]
+    async fn test_failed_event_is_redelivered() {
+        let server = NatsServer::spawn().await;
+        let bus = server.bus().await;
+        bus.publish(vec![user_event("u1", 1, "UserCreated")])
+            .await
+            .unwrap();
+
+        let (handler, handled) = CountingHandler::new(&["UserCreated"], 1);
+        let consumer = fast(
+            NatsConsumer::new(&bus, "users", Arc::new(handler))
+                .await
+                .unwrap(),
+        );
+
+        assert_eq!(consumer.process_batch().await.unwrap(), 0);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(consumer.process_batch().await.unwrap(), 1);
+        assert_eq!(handled.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs nats-server; run with `make test-nats`"]
+    async fn test_durable_consumer_resumes_after_last_ack() {
+        let server = NatsServer::spawn().await;
+        let bus = server.bus().await;
+        bus.publish(vec![user_event("u1", 1, "UserCreated")])
+            .await
+            .unwrap();
+
+        let (first, _) = CountingHandler::new(&["UserCreated"], 0);
+        let consumer = fast(
+            NatsConsumer::new(&bus, "users", Arc::new(first))
+                .await
+                .unwrap(),
+        );
+        assert_eq!(consumer.process_batch().await.unwrap(), 1);
+        drop(consumer);
+
+        bus.publish(vec![user_event("u2", 1, "UserCreated")])
+            .await
+            .unwrap();
+        let (second, handled) = CountingHandler::new(&["UserCreated"], 0);
+        let consumer = fast(
+            NatsConsumer::new(&bus, "users", Arc::new(second))
+                .await
+                .unwrap(),
+        );
+        assert_eq!(consumer.process_batch().await.unwrap(), 1);
+        assert_eq!(handled.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs nats-server; run with `make test-nats`"]
+    async fn test_run_stops_on_shutdown() {
+        let server = NatsServer::spawn().await;
+        let bus = server.bus().await;
+        let (handler, handled) = CountingHandler::new(&["UserCreated"], 0);
+        let consumer = fast(
+            NatsConsumer::new(&bus, "users", Arc::new(handler))
+                .await
+                .unwrap(),
+        );
+        let (stop, stopped) = watch::channel(false);
+        let task = tokio::spawn(async move { consumer.run(stopped).await });
+
+        bus.publish(vec![user_event("u1", 1, "UserCreated")])
+            .await
+            .unwrap();
+        while handled.load(Ordering::SeqCst) == 0 {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        stop.send(true).unwrap();
+        task.await.unwrap();
+    }
+}
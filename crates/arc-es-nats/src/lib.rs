@@ -0,0 +1,365 @@
+//! # arc-es-nats
+//!
+//! NATS JetStream implementation of [`EventBus`] for arc event sourcing.
+//!
+//! [`NatsEventBus`] publishes every event to
+//! `events.<aggregate_type>.<event_type>` on a JetStream stream, both types
+//! in lowercase snake_case (`events.user.user_registered`), with the
+//! event's `event_id` as the `Nats-Msg-Id` header so that a republish (for
+//! example an outbox redelivery) inside the stream's duplicate window is
+//! stored once. [`NatsConsumer`] drives any [`EventHandler`] from a durable
+//! pull consumer, which lets projections and side effects run out of
+//! process and resume where they stopped.
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! use arc_es_nats::{NatsConsumer, NatsEventBus};
+//!
+//! let bus = NatsEventBus::connect("nats://localhost:4222").await?;
+//! bus.publish(events).await?;
+//!
+//! let consumer = NatsConsumer::new(&bus, "users-projection", Arc::new(handler)).await?;
+//! let (stop, stopped) = tokio::sync::watch::channel(false);
+//! tokio::spawn(async move { consumer.run(stopped).await });
+//! ```
+
+mod consumer;
+
+pub use consumer::{
+    NatsConsumer, DEFAULT_CONSUMER_BATCH_SIZE, DEFAULT_FETCH_TIMEOUT, DEFAULT_MAX_DELIVER,
+    DEFAULT_RETRY_DELAY,
+};
+
+use arc_core::event::Event;
+use arc_core::event_bus::{EventBus, EventBusError, EventBusResult, EventHandler};
+use async_nats::header::{HeaderMap, NATS_MESSAGE_ID};
+use async_nats::jetstream::{self, stream};
+use async_trait::async_trait;
+use std::time::Duration;
+
+/// Stream that [`NatsEventBus::connect`] creates or reuses.
+pub const DEFAULT_STREAM: &str = "ARC_EVENTS";
+
+/// Root subject token; events land on `events.<aggregate_type>.<event_type>`,
+/// e.g. `events.user.user_registered`.
+pub const SUBJECT_ROOT: &str = "events";
+
+/// Window in which JetStream drops a message whose `Nats-Msg-Id` it has
+/// already stored.
+pub const DEFAULT_DUPLICATE_WINDOW: Duration = Duration::from_secs(120);
+
+/// Subject an event is published on.
+pub fn subject_for(event: &Event) -> String {
+    format!(
+        "{}.{}.{}",
+        SUBJECT_ROOT,
+        subject_token(&event.aggregate_type),
+        subject_token(&event.event_type)
+    )
+}
+
+/// Filter subject matching `event_type` for any aggregate type.
+pub fn filter_subject_for(event_type: &str) -> String {
+    format!("{}.*.{}", SUBJECT_ROOT, subject_token(event_type))
+}
+
+/// Lowercase snake_case subject token: `UserRegistered` → `user_registered`,
+/// `HTTPRequest` → `http_request`. NATS subject tokens cannot contain
+/// separators, wildcards or whitespace, so those become `_` as well and
+/// every aggregate and event type maps to a single token.
+fn subject_token(value: &str) -> String {
+    let chars: Vec<char> = value.chars().collect();
+    let mut token = String::with_capacity(value.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if matches!(c, '.' | '*' | '>') || c.is_whitespace() {
+            token.push('_');
+            continue;
+        }
+        if c.is_uppercase() {
+            let word_starts = match i.checked_sub(1).map(|p| chars[p]) {
+                Some(prev) if prev.is_lowercase() || prev.is_ascii_digit() => true,
+                Some(prev) if prev.is_uppercase() => {
+                    chars.get(i + 1).is_some_and(|next| next.is_lowercase())
+                }
+                _ => false,
+            };
+            if word_starts {
+                token.push('_');
+            }
+            token.extend(c.to_lowercase());
+        } else {
+            token.push(c);
+        }
+    }
+    token
+}
+
+/// [`EventBus`] that publishes to a NATS JetStream stream.
+#[derive(Clone)]
+pub struct NatsEventBus {
+    jetstream: jetstream::Context,
+    stream: String,
+}
+
+impl NatsEventBus {
+    /// Connect to `url` and ensure [`DEFAULT_STREAM`] exists.
+    pub async fn connect(url: &str) -> EventBusResult<Self> {
+        let client = async_nats::connect(url)
+            .await
+            .map_err(|e| EventBusError::other(format!("Failed to connect to NATS: {e}")))?;
+        Self::with_client(client, DEFAULT_STREAM).await
+    }
+
+    /// Use an existing connection and ensure `stream` exists, capturing
+    /// `events.>`.
+    pub async fn with_client(client: async_nats::Client, stream: &str) -> EventBusResult<Self> {
+        let jetstream = jetstream::new(client);
+        jetstream
+            .get_or_create_stream(stream::Config {
+                name: stream.to_string(),
+                subjects: vec![format!("{SUBJECT_ROOT}.>")],
+                duplicate_window: DEFAULT_DUPLICATE_WINDOW,
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| {
+                EventBusError::other(format!("Failed to create stream '{stream}': {e}"))
+            })?;
+        Ok(Self {
+            jetstream,
+            stream: stream.to_string(),
+        })
+    }
+
+    /// Name of the JetStream stream events are stored in.
+    pub fn stream_name(&self) -> &str {
+        &self.stream
+    }
+
+    /// The underlying JetStream context.
+    pub fn jetstream(&self) -> &jetstream::Context {
+        &self.jetstream
+    }
+}
+
+#[async_trait]
+impl EventBus for NatsEventBus {
+    /// Publish events in order, waiting for each JetStream ack so that
+    /// `Ok` means every event is stored in the stream.
+    async fn publish(&self, events: Vec<Event>) -> EventBusResult<()> {
+        for event in &events {
+            let payload = serde_json::to_vec(event).map_err(|e| {
+                EventBusError::other(format!("Failed to serialize event {}: {e}", event.event_id))
+            })?;
+            let mut headers = HeaderMap::new();
+            headers.insert(NATS_MESSAGE_ID, event.event_id.to_string().as_str());
+
+            let publish_failed = |e: &dyn std::fmt::Display| {
+                EventBusError::other(format!(
+                    "Failed to publish event {} to NATS: {e}",
+                    event.event_id
+                ))
+            };
+            self.jetstream
+                .publish_with_headers(subject_for(event), headers, payload.into())
+                .await
+                .map_err(|e| publish_failed(&e))?
+                .await
+                .map_err(|e| publish_failed(&e))?;
+        }
+        Ok(())
+    }
+
+    /// JetStream delivery needs a durable consumer name, which this trait
+    /// method cannot carry. Use [`NatsConsumer::new`] instead.
+    async fn subscribe(&mut self, _handler: Box<dyn EventHandler>) -> EventBusResult<()> {
+        Err(EventBusError::subscription_failed(
+            "NatsEventBus handlers are driven by a durable NatsConsumer; use NatsConsumer::new",
+        ))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use arc_core::audit::AuditMetadata;
+    use serde_json::json;
+    use std::net::TcpListener;
+    use std::path::PathBuf;
+    use std::process::{Child, Command, Stdio};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// A `nats-server -js` on a free local port, killed on drop. Tests that
+    /// need one are `#[ignore]`d, so a plain `cargo test` lists them as
+    /// ignored; run them with `cargo test -p arc-es-nats -- --ignored`
+    /// (`make test-nats`). They fail when the binary is missing (set
+    /// `NATS_SERVER_BIN` or put `nats-server` on `PATH`).
+    pub(crate) struct NatsServer {
+        child: Child,
+        store_dir: PathBuf,
+        pub url: String,
+    }
+
+    impl NatsServer {
+        pub async fn spawn() -> Self {
+            let bin = std::env::var("NATS_SERVER_BIN").unwrap_or_else(|_| "nats-server".into());
+            let port = TcpListener::bind("127.0.0.1:0")
+                .and_then(|l| l.local_addr())
+                .expect("no free local port")
+                .port();
+            let store_dir = std::env::temp_dir().join(format!("arc-nats-{}", uuid::Uuid::new_v4()));
+            let child = Command::new(&bin)
+                .args(["-js", "-a", "127.0.0.1", "-p", &port.to_string(), "-sd"])
+                .arg(&store_dir)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .spawn()
+                .unwrap_or_else(|e| {
+                    panic!("cannot start `{bin}` ({e}); install nats-server or set NATS_SERVER_BIN")
+                });
+            let server = Self {
+                child,
+                store_dir,
+                url: format!("nats://127.0.0.1:{port}"),
+            };
+            for _ in 0..50 {
+                if async_nats::connect(&server.url).await.is_ok() {
+                    return server;
+                }
+                tokio::time::sleep(Duration::from_millis(100)).await;
+            }
+            panic!("nats-server did not start on {}", server.url);
+        }
+
+        pub async fn bus(&self) -> NatsEventBus {
+            NatsEventBus::connect(&self.url).await.unwrap()
+        }
+    }
+
+    impl Drop for NatsServer {
+        fn drop(&mut self) {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+            let _ = std::fs::remove_dir_all(&self.store_dir);
+        }
+    }
+
+    /// Counts handled events after failing the first `fail` calls.
+    pub(crate) struct CountingHandler {
+        types: Vec<String>,
+        fail: AtomicUsize,
+        handled: Arc<AtomicUsize>,
+    }
+
+    impl CountingHandler {
+        pub fn new(types: &[&str], fail: usize) -> (Self, Arc<AtomicUsize>) {
+            let handled = Arc::new(AtomicUsize::new(0));
+            let handler = Self {
+                types: types.iter().map(|t| t.to_string()).collect(),
+                fail: AtomicUsize::new(fail),
+                handled: handled.clone(),
+            };
+            (handler, handled)
+        }
+    }
+
+    #[async_trait]
+    impl EventHandler for CountingHandler {
+        fn handles(&self) -> Vec<String> {
+            self.types.clone()
+        }
+
+        async fn handle(
+            &self,
+            _event: &Event,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            if self
+                .fail
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+                .is_ok()
+            {
+                return Err("handler down".into());
+            }
+            self.handled.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    pub(crate) fn user_event(id: &str, seq: i64, event_type: &str) -> Event {
+        Event::new("User", id, seq, event_type, json!({ "n": seq }))
+            .with_audit(AuditMetadata::test_default())
+    }
+
+    #[test]
+    fn test_subject_for_event() {
+        let event = user_event("u1", 1, "UserRegistered");
+        assert_eq!(subject_for(&event), "events.user.user_registered");
+        assert_eq!(
+            filter_subject_for("UserRegistered"),
+            "events.*.user_registered"
+        );
+    }
+
+    #[test]
+    fn test_subject_tokens_are_snake_case() {
+        for (value, token) in [
+            ("User", "user"),
+            ("EmailVerified", "email_verified"),
+            ("HTTPRequestSent", "http_request_sent"),
+            ("Step2Done", "step2_done"),
+            ("already_snake", "already_snake"),
+        ] {
+            assert_eq!(subject_token(value), token);
+        }
+    }
+
+    #[test]
+    fn test_subject_tokens_are_sanitized() {
+        let event = Event::new("billing.Invoice", "i1", 1, "Paid >*", json!({}));
+        assert_eq!(subject_for(&event), "events.billing_invoice.paid___");
+    }
+
+    #[tokio::test]
+    #[ignore = "needs nats-server; run with `make test-nats`"]
+    async fn test_publish_stores_on_event_subject() {
+        let server = NatsServer::spawn().await;
+        let bus = server.bus().await;
+        let event = user_event("u1", 1, "UserCreated");
+        bus.publish(vec![event.clone()]).await.unwrap();
+
+        let stream = bus.jetstream().get_stream(DEFAULT_STREAM).await.unwrap();
+        let message = stream
+            .get_last_raw_message_by_subject("events.user.user_created")
+            .await
+            .unwrap();
+        let stored: Event = serde_json::from_slice(&message.payload).unwrap();
+        assert_eq!(stored.event_id, event.event_id);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs nats-server; run with `make test-nats`"]
+    async fn test_republish_is_deduplicated_by_event_id() {
+        let server = NatsServer::spawn().await;
+        let bus = server.bus().await;
+        let event = user_event("u1", 1, "UserCreated");
+        bus.publish(vec![event.clone()]).await.unwrap();
+        bus.publish(vec![event, user_event("u1", 2, "UserUpdated")])
+            .await
+            .unwrap();
+
+        let mut stream = bus.jetstream().get_stream(DEFAULT_STREAM).await.unwrap();
+        assert_eq!(stream.info().await.unwrap().state.messages, 2);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs nats-server; run with `make test-nats`"]
+    async fn test_subscribe_points_to_consumer() {
+        let server = NatsServer::spawn().await;
+        let mut bus = server.bus().await;
+        let (handler, _) = CountingHandler::new(&["UserCreated"], 0);
+        let err = bus.subscribe(Box::new(handler)).await.unwrap_err();
+        assert!(err.to_string().contains("NatsConsumer"));
+    }
+}
@@ -0,0 +1,38 @@
+-- Event log, Postgres dialect. Same columns and meaning as the SQLite
+-- `events` table after `2026-04-26-000001_widen_event_int_columns`, with
+-- native types: UUID ids, JSONB payload, and a millisecond `timestamp`.
+--
+-- `id` is the global position. `UNIQUE (aggregate_id, sequence)` is the
+-- optimistic concurrency guard: of two writers racing for the same
+-- sequence, the second fails with a unique violation, which
+-- `PostgresEventStore` reports as a concurrency conflict.
+--
+-- BIGSERIAL values are allocated at insert time but become visible at
+-- commit, so concurrent transactions could expose a lower id after a
+-- higher one and a checkpointed reader would skip it. Appends therefore
+-- take a transaction-scoped advisory lock before reading the stream head;
+-- ids commit in order.
+
+CREATE TABLE events (
+    id               BIGSERIAL PRIMARY KEY,
+    event_id         UUID   NOT NULL UNIQUE,
+    aggregate_type   TEXT   NOT NULL,
+    aggregate_id     TEXT   NOT NULL,
+    sequence         BIGINT NOT NULL,
+    event_type       TEXT   NOT NULL,
+    payload          JSONB  NOT NULL,
+    timestamp        BIGINT NOT NULL,
+    actor_id         TEXT   NOT NULL,
+    actor_session_id TEXT,
+    source_ip        TEXT,
+    user_agent       TEXT,
+    timestamp_utc_us BIGINT NOT NULL,
+    causation_id     UUID,
+    correlation_id   UUID   NOT NULL,
+    UNIQUE (aggregate_id, sequence)
+);
+
+CREATE INDEX idx_events_type           ON events (event_type);
+CREATE INDEX idx_events_timestamp      ON events (timestamp);
+CREATE INDEX idx_events_actor_id       ON events (actor_id);
+CREATE INDEX idx_events_correlation_id ON events (correlation_id);
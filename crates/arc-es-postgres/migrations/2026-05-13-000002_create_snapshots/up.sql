@@ -0,0 +1,12 @@
+-- Aggregate snapshots, one row per aggregate, replaced on every save. A
+-- cache, not a source of truth: truncating this table is always safe. See
+-- the SQLite migration `2026-05-09-000001_create_snapshots`.
+
+CREATE TABLE snapshots (
+    aggregate_id   TEXT    NOT NULL PRIMARY KEY,
+    aggregate_type TEXT    NOT NULL,
+    version        BIGINT  NOT NULL,
+    schema_version INTEGER NOT NULL,
+    state          JSONB   NOT NULL,
+    taken_at_us    BIGINT  NOT NULL
+);
@@ -0,0 +1,16 @@
+-- Read-model projection table for the User aggregate, in the standard
+-- projection shape `(id, version, data)` with `data` as JSONB.
+--
+-- `PostgresReadModelStore` upserts with
+--   `INSERT … ON CONFLICT (id) DO UPDATE … WHERE users_view.version < excluded.version`
+-- so duplicate delivery and out-of-order replay converge to the same row.
+-- The unique expression index keeps login-by-email unambiguous and serves
+-- `find_by("users_view", "email", …)`.
+
+CREATE TABLE users_view (
+    id      TEXT   NOT NULL PRIMARY KEY,
+    version BIGINT NOT NULL,
+    data    JSONB  NOT NULL
+);
+
+CREATE UNIQUE INDEX idx_users_view_email ON users_view ((data ->> 'email'));
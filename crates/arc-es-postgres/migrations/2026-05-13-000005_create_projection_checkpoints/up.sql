@@ -0,0 +1,8 @@
+-- Projection checkpoints: the last global event position (`events.id`) each
+-- projection has applied. Positions only move forward.
+
+CREATE TABLE projection_checkpoints (
+    projection    TEXT   NOT NULL PRIMARY KEY,
+    position      BIGINT NOT NULL,
+    updated_at_us BIGINT NOT NULL
+);
@@ -0,0 +1,4 @@
+-- Payload schema version per event, same as the SQLite migration of the
+-- same name. Existing rows are version 1.
+
+ALTER TABLE events ADD COLUMN schema_version INTEGER NOT NULL DEFAULT 1;
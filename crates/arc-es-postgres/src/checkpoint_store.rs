@@ -0,0 +1,139 @@
+//! Postgres-backed [`CheckpointStore`] for projection positions.
+//!
+//! One row per projection in `projection_checkpoints`, saved with an upsert
+//! gated on `position < excluded.position` so a checkpoint never rewinds.
+
+use crate::now_us;
+use arc_core::event::GlobalPosition;
+use arc_core::projection::{CheckpointStore, ProjectionError, ProjectionResult};
+use async_trait::async_trait;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::r2d2::{self, ConnectionManager};
+use diesel::sql_types::{BigInt, Text};
+use std::sync::Arc;
+
+mod schema {
+    diesel::table! {
+        projection_checkpoints (projection) {
+            projection -> Text,
+            position -> BigInt,
+            updated_at_us -> BigInt,
+        }
+    }
+}
+
+use schema::projection_checkpoints;
+
+type Pool = r2d2::Pool<ConnectionManager<PgConnection>>;
+
+/// Durable projection checkpoints backed by Postgres.
+#[derive(Clone)]
+pub struct PostgresCheckpointStore {
+    pool: Arc<Pool>,
+}
+
+impl PostgresCheckpointStore {
+    pub async fn new(database_url: &str) -> ProjectionResult<Self> {
+        let manager = ConnectionManager::<PgConnection>::new(database_url);
+        let pool = tokio::task::spawn_blocking(move || Pool::builder().max_size(10).build(manager))
+            .await
+            .map_err(|e| ProjectionError::other(format!("Task join error: {e}")))?
+            .map_err(|e| ProjectionError::other(format!("Failed to build checkpoint pool: {e}")))?;
+        Ok(Self::with_pool(pool))
+    }
+
+    pub fn with_pool(pool: Pool) -> Self {
+        Self {
+            pool: Arc::new(pool),
+        }
+    }
+
+    /// Run `f` on a pooled connection off the async runtime, mapping every
+    /// failure to a checkpoint error for `projection`.
+    async fn run<F, T>(&self, projection: &str, f: F) -> ProjectionResult<T>
+    where
+        F: FnOnce(&mut PgConnection) -> QueryResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.pool.clone();
+        let name = projection.to_string();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| {
+                ProjectionError::checkpoint_failed(&name, format!("Failed to get connection: {e}"))
+            })?;
+            f(&mut conn).map_err(|e| ProjectionError::checkpoint_failed(&name, e.to_string()))
+        })
+        .await
+        .map_err(|e| ProjectionError::other(format!("Task join error: {e}")))?
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for PostgresCheckpointStore {
+    async fn load(&self, projection: &str) -> ProjectionResult<Option<GlobalPosition>> {
+        let name = projection.to_string();
+        let position: Option<i64> = self
+            .run(projection, move |conn| {
+                projection_checkpoints::table
+                    .find(name)
+                    .select(projection_checkpoints::position)
+                    .first(conn)
+                    .optional()
+            })
+            .await?;
+        Ok(position.map(GlobalPosition))
+    }
+
+    async fn save(&self, projection: &str, position: GlobalPosition) -> ProjectionResult<()> {
+        let name = projection.to_string();
+        self.run(projection, move |conn| {
+            diesel::sql_query(
+                "INSERT INTO projection_checkpoints (projection, position, updated_at_us) \
+                 VALUES ($1, $2, $3) \
+                 ON CONFLICT (projection) DO UPDATE \
+                 SET position = excluded.position, updated_at_us = excluded.updated_at_us \
+                 WHERE projection_checkpoints.position < excluded.position",
+            )
+            .bind::<Text, _>(name)
+            .bind::<BigInt, _>(position.value())
+            .bind::<BigInt, _>(now_us())
+            .execute(conn)
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn reset(&self, projection: &str) -> ProjectionResult<()> {
+        let name = projection.to_string();
+        self.run(projection, move |conn| {
+            diesel::delete(projection_checkpoints::table.find(name)).execute(conn)
+        })
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TestDatabase;
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_save_only_moves_forward_until_reset() {
+        let db = TestDatabase::create();
+        let store = PostgresCheckpointStore::with_pool(db.pool());
+        assert_eq!(store.load("users").await.unwrap(), None);
+
+        store.save("users", GlobalPosition(10)).await.unwrap();
+        store.save("users", GlobalPosition(4)).await.unwrap();
+        assert_eq!(store.load("users").await.unwrap(), Some(GlobalPosition(10)));
+        assert_eq!(store.load("orders").await.unwrap(), None);
+
+        store.reset("users").await.unwrap();
+        assert_eq!(store.load("users").await.unwrap(), None);
+        store.save("users", GlobalPosition(2)).await.unwrap();
+        assert_eq!(store.load("users").await.unwrap(), Some(GlobalPosition(2)));
+    }
+}
@@ -0,0 +1,894 @@
+//! # Arc ES Postgres
+//!
+//! PostgreSQL implementations of the `arc-core` storage traits:
+//!
+//! - [`PostgresEventStore`] — [`EventStore`] over the `events` and
+//!   `snapshots` tables.
+//! - [`PostgresReadModelStore`] — version-gated projection rows with a
+//!   JSONB `data` column.
+//! - [`PostgresSessionStore`] — the JWT session registry.
+//! - [`PostgresCheckpointStore`] — projection checkpoints.
+//! - [`PostgresEventListener`] — `LISTEN`s for appends so tailing readers
+//!   wake up on new events instead of polling.
+//!
+//! Semantics match `arc-es-sqlite`: the same columns, the same version gate,
+//! the same errors. The schema lives in this crate's `migrations/`
+//! directory (Postgres dialect) and is applied with [`run_migrations`].
+//!
+//! ## Appends
+//!
+//! `append` runs in one transaction that first takes a transaction-scoped
+//! advisory lock ([`APPEND_LOCK_KEY`]). Appends are therefore serialized,
+//! which keeps `events.id` commit order equal to allocation order: a reader
+//! resuming from a checkpoint can never miss an event that committed late
+//! with a lower id. `UNIQUE (aggregate_id, sequence)` remains the
+//! concurrency guard of last resort; a unique violation on it is reported
+//! as [`EventStoreError::ConcurrencyConflict`].
+//!
+//! Each append ends with a `pg_notify` on [`EVENTS_CHANNEL`] carrying its
+//! last position, delivered to listeners when the transaction commits.
+//!
+//! The integrity chain and the transactional outbox are SQLite-only for now.
+
+use arc_core::audit::AuditMetadata;
+use arc_core::event::{Event, GlobalPosition};
+use arc_core::event_store::{
+    validate_audit_batch, EventStore, EventStoreError, EventStoreResult, VersionCheck,
+};
+use arc_core::snapshot::Snapshot;
+use async_trait::async_trait;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::r2d2::{self, ConnectionManager};
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use diesel::sql_types::{BigInt, Text};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub mod session;
+pub use session::PostgresSessionStore;
+
+pub mod read_model_store;
+pub use read_model_store::PostgresReadModelStore;
+
+pub mod checkpoint_store;
+pub use checkpoint_store::PostgresCheckpointStore;
+
+pub mod listener;
+pub use listener::PostgresEventListener;
+
+/// Postgres schema for every store in this crate.
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/// Channel notified with the last appended position after every append.
+pub const EVENTS_CHANNEL: &str = "arc_events";
+
+/// Key of the transaction-scoped advisory lock that serializes appends.
+pub const APPEND_LOCK_KEY: i64 = 0x6172_635f_6576_7473; // "arc_evts"
+
+/// Constraint behind the optimistic concurrency guard.
+const SEQUENCE_CONSTRAINT: &str = "events_aggregate_id_sequence_key";
+
+/// Microseconds since UNIX epoch, for bookkeeping columns.
+pub(crate) fn now_us() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_micros() as i64)
+        .unwrap_or(0)
+}
+
+/// Apply every pending migration in [`MIGRATIONS`] to `database_url`.
+pub async fn run_migrations(database_url: &str) -> EventStoreResult<()> {
+    let database_url = database_url.to_string();
+    tokio::task::spawn_blocking(move || {
+        let mut conn = PgConnection::establish(&database_url)
+            .map_err(|e| EventStoreError::database(format!("Failed to connect: {}", e)))?;
+        conn.run_pending_migrations(MIGRATIONS)
+            .map_err(|e| EventStoreError::database(format!("Failed to run migrations: {}", e)))?;
+        Ok(())
+    })
+    .await
+    .map_err(|e| EventStoreError::other(format!("Task join error: {}", e)))?
+}
+
+mod schema {
+    diesel::table! {
+        events (id) {
+            id -> BigInt,
+            event_id -> Uuid,
+            aggregate_type -> Text,
+            aggregate_id -> Text,
+            sequence -> BigInt,
+            event_type -> Text,
+            payload -> Jsonb,
+            timestamp -> BigInt,
+            actor_id -> Text,
+            actor_session_id -> Nullable<Text>,
+            source_ip -> Nullable<Text>,
+            user_agent -> Nullable<Text>,
+            timestamp_utc_us -> BigInt,
+            causation_id -> Nullable<Uuid>,
+            correlation_id -> Uuid,
+            schema_version -> Integer,
+        }
+    }
+
+    diesel::table! {
+        snapshots (aggregate_id) {
+            aggregate_id -> Text,
+            aggregate_type -> Text,
+            version -> BigInt,
+            schema_version -> Integer,
+            state -> Jsonb,
+            taken_at_us -> BigInt,
+        }
+    }
+}
+
+use schema::{events, snapshots};
+
+/// Database row used for inserting events.
+#[derive(Debug, Insertable, Clone)]
+#[diesel(table_name = events)]
+struct NewEventRecord {
+    pub event_id: Uuid,
+    pub aggregate_type: String,
+    pub aggregate_id: String,
+    pub sequence: i64,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub timestamp: i64,
+    pub actor_id: String,
+    pub actor_session_id: Option<String>,
+    pub source_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub timestamp_utc_us: i64,
+    pub causation_id: Option<Uuid>,
+    pub correlation_id: Uuid,
+    pub schema_version: i32,
+}
+
+#[derive(Debug, Queryable, Clone)]
+struct EventRecord {
+    pub id: i64,
+    pub event_id: Uuid,
+    pub aggregate_type: String,
+    pub aggregate_id: String,
+    pub sequence: i64,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub timestamp: i64,
+    pub actor_id: String,
+    pub actor_session_id: Option<String>,
+    pub source_ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub timestamp_utc_us: i64,
+    pub causation_id: Option<Uuid>,
+    pub correlation_id: Uuid,
+    pub schema_version: i32,
+}
+
+impl NewEventRecord {
+    fn from_event(event: &Event) -> Result<Self, EventStoreError> {
+        let timestamp = i64::try_from(event.timestamp).map_err(|_| {
+            EventStoreError::serialization(format!(
+                "Event timestamp {} out of range",
+                event.timestamp
+            ))
+        })?;
+        Ok(NewEventRecord {
+            event_id: event.event_id,
+            aggregate_type: event.aggregate_type.clone(),
+            aggregate_id: event.aggregate_id.clone(),
+            sequence: event.sequence,
+            event_type: event.event_type.clone(),
+            payload: event.payload.clone(),
+            timestamp,
+            actor_id: event.audit.actor_id.clone(),
+            actor_session_id: event.audit.actor_session_id.clone(),
+            source_ip: event.audit.source_ip.clone(),
+            user_agent: event.audit.user_agent.clone(),
+            timestamp_utc_us: event.audit.timestamp_utc_us,
+            causation_id: event.audit.causation_id,
+            correlation_id: event.audit.correlation_id,
+            schema_version: i32::try_from(event.schema_version).map_err(|_| {
+                EventStoreError::serialization(format!(
+                    "Event schema version {} out of range",
+                    event.schema_version
+                ))
+            })?,
+        })
+    }
+}
+
+impl EventRecord {
+    fn into_event(self) -> Event {
+        let audit = AuditMetadata {
+            actor_id: self.actor_id,
+            actor_session_id: self.actor_session_id,
+            source_ip: self.source_ip,
+            user_agent: self.user_agent,
+            timestamp_utc_us: self.timestamp_utc_us,
+            causation_id: self.causation_id,
+            correlation_id: self.correlation_id,
+        };
+
+        Event {
+            event_id: self.event_id,
+            aggregate_type: self.aggregate_type,
+            aggregate_id: self.aggregate_id,
+            sequence: self.sequence,
+            event_type: self.event_type,
+            payload: self.payload,
+            schema_version: self.schema_version.max(0) as u32,
+            audit,
+            timestamp: self.timestamp.max(0) as u64,
+            position: Some(GlobalPosition(self.id)),
+        }
+    }
+}
+
+/// Database row for the `snapshots` table (used for insert, update and load).
+#[derive(Debug, Insertable, Queryable, AsChangeset, Clone)]
+#[diesel(table_name = snapshots, primary_key(aggregate_id))]
+struct SnapshotRecord {
+    pub aggregate_id: String,
+    pub aggregate_type: String,
+    pub version: i64,
+    pub schema_version: i32,
+    pub state: serde_json::Value,
+    pub taken_at_us: i64,
+}
+
+impl SnapshotRecord {
+    fn from_snapshot(snapshot: &Snapshot) -> Result<Self, EventStoreError> {
+        Ok(SnapshotRecord {
+            aggregate_id: snapshot.aggregate_id.clone(),
+            aggregate_type: snapshot.aggregate_type.clone(),
+            version: snapshot.version,
+            schema_version: i32::try_from(snapshot.schema_version).map_err(|_| {
+                EventStoreError::serialization(format!(
+                    "Snapshot schema version {} out of range",
+                    snapshot.schema_version
+                ))
+            })?,
+            state: snapshot.state.clone(),
+            taken_at_us: snapshot.taken_at_us,
+        })
+    }
+
+    fn into_snapshot(self) -> Snapshot {
+        Snapshot {
+            aggregate_type: self.aggregate_type,
+            aggregate_id: self.aggregate_id,
+            version: self.version,
+            schema_version: self.schema_version.max(0) as u32,
+            state: self.state,
+            taken_at_us: self.taken_at_us,
+        }
+    }
+}
+
+type Pool = r2d2::Pool<ConnectionManager<PgConnection>>;
+
+fn db_err(e: DieselError) -> EventStoreError {
+    EventStoreError::database(e.to_string())
+}
+
+fn is_sequence_conflict(e: &DieselError) -> bool {
+    matches!(
+        e,
+        DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, info)
+            if info.constraint_name() == Some(SEQUENCE_CONSTRAINT)
+    )
+}
+
+fn current_version(conn: &mut PgConnection, aggregate_id: &str) -> EventStoreResult<i64> {
+    Ok(events::table
+        .filter(events::aggregate_id.eq(aggregate_id))
+        .select(diesel::dsl::max(events::sequence))
+        .first::<Option<i64>>(conn)
+        .map_err(db_err)?
+        .unwrap_or(0))
+}
+
+/// PostgreSQL implementation of EventStore.
+#[derive(Clone)]
+pub struct PostgresEventStore {
+    pool: Arc<Pool>,
+}
+
+impl PostgresEventStore {
+    pub async fn new(database_url: &str) -> EventStoreResult<Self> {
+        let manager = ConnectionManager::<PgConnection>::new(database_url);
+        let pool = tokio::task::spawn_blocking(move || Pool::builder().max_size(10).build(manager))
+            .await
+            .map_err(|e| EventStoreError::other(format!("Task join error: {}", e)))?
+            .map_err(|e| EventStoreError::database(format!("Failed to create pool: {}", e)))?;
+
+        Ok(Self::with_pool(pool))
+    }
+
+    pub fn with_pool(pool: Pool) -> Self {
+        PostgresEventStore {
+            pool: Arc::new(pool),
+        }
+    }
+
+    /// Run `f` on a pooled connection off the async runtime.
+    async fn with_conn<F, T>(&self, f: F) -> EventStoreResult<T>
+    where
+        F: FnOnce(&mut PgConnection) -> EventStoreResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| {
+                EventStoreError::database(format!("Failed to get connection: {}", e))
+            })?;
+            f(&mut conn)
+        })
+        .await
+        .map_err(|e| EventStoreError::other(format!("Task join error: {}", e)))?
+    }
+}
+
+/// The body of an append, run inside its transaction.
+fn append_in_transaction(
+    conn: &mut PgConnection,
+    aggregate_id: &str,
+    version_check: VersionCheck,
+    new_events: &[Event],
+) -> Result<Vec<Event>, AppendError> {
+    diesel::sql_query("SELECT pg_advisory_xact_lock($1)")
+        .bind::<BigInt, _>(APPEND_LOCK_KEY)
+        .execute(conn)?;
+
+    let current_version = current_version(conn, aggregate_id)?;
+
+    if let Some(expected) = version_check.version() {
+        if current_version != expected {
+            return Err(EventStoreError::ConcurrencyConflict {
+                aggregate_id: aggregate_id.to_string(),
+                expected,
+                actual: current_version,
+            }
+            .into());
+        }
+    }
+
+    for (expected_sequence, event) in (current_version + 1..).zip(new_events) {
+        if event.sequence != expected_sequence {
+            return Err(EventStoreError::InvalidSequence {
+                aggregate_id: aggregate_id.to_string(),
+                expected: expected_sequence,
+                actual: event.sequence,
+            }
+            .into());
+        }
+    }
+
+    let records = new_events
+        .iter()
+        .map(NewEventRecord::from_event)
+        .collect::<EventStoreResult<Vec<_>>>()?;
+    let ids: Vec<i64> = diesel::insert_into(events::table)
+        .values(&records)
+        .returning(events::id)
+        .get_results(conn)?;
+
+    if let Some(last) = ids.last() {
+        diesel::sql_query("SELECT pg_notify($1, $2)")
+            .bind::<Text, _>(EVENTS_CHANNEL)
+            .bind::<Text, _>(last.to_string())
+            .execute(conn)?;
+    }
+
+    Ok(new_events
+        .iter()
+        .zip(ids)
+        .map(|(event, id)| {
+            let mut stored = event.clone();
+            stored.position = Some(GlobalPosition(id));
+            stored
+        })
+        .collect())
+}
+
+/// Failure inside an append transaction. Keeps the raw Diesel error so a
+/// lost race on the sequence constraint can be told apart after rollback.
+enum AppendError {
+    Store(EventStoreError),
+    Diesel(DieselError),
+}
+
+impl From<EventStoreError> for AppendError {
+    fn from(e: EventStoreError) -> Self {
+        AppendError::Store(e)
+    }
+}
+
+impl From<DieselError> for AppendError {
+    fn from(e: DieselError) -> Self {
+        AppendError::Diesel(e)
+    }
+}
+
+#[async_trait]
+impl EventStore for PostgresEventStore {
+    async fn append(
+        &self,
+        aggregate_id: &str,
+        version_check: VersionCheck,
+        new_events: Vec<Event>,
+    ) -> EventStoreResult<()> {
+        self.append_returning(aggregate_id, version_check, new_events)
+            .await
+            .map(drop)
+    }
+
+    async fn append_returning(
+        &self,
+        aggregate_id: &str,
+        version_check: VersionCheck,
+        new_events: Vec<Event>,
+    ) -> EventStoreResult<Vec<Event>> {
+        if new_events.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Defense-in-depth: reject any event with invalid audit before touching the DB.
+        validate_audit_batch(aggregate_id, &new_events)?;
+
+        let aggregate_id = aggregate_id.to_string();
+
+        self.with_conn(move |conn| {
+            use diesel::connection::{AnsiTransactionManager, TransactionManager};
+
+            AnsiTransactionManager::begin_transaction(conn).map_err(db_err)?;
+
+            let result = append_in_transaction(conn, &aggregate_id, version_check, &new_events);
+
+            match result {
+                Ok(stored_events) => {
+                    AnsiTransactionManager::commit_transaction(conn).map_err(db_err)?;
+                    Ok(stored_events)
+                }
+                Err(e) => {
+                    let _ = AnsiTransactionManager::rollback_transaction(conn);
+                    match e {
+                        AppendError::Store(e) => Err(e),
+                        AppendError::Diesel(e) if is_sequence_conflict(&e) => {
+                            Err(EventStoreError::ConcurrencyConflict {
+                                expected: version_check.version().unwrap_or_default(),
+                                actual: current_version(conn, &aggregate_id)?,
+                                aggregate_id,
+                            })
+                        }
+                        AppendError::Diesel(e) => Err(db_err(e)),
+                    }
+                }
+            }
+        })
+        .await
+    }
+
+    async fn load(&self, aggregate_id: &str) -> EventStoreResult<Vec<Event>> {
+        self.load_from(aggregate_id, 1).await
+    }
+
+    async fn load_from(
+        &self,
+        aggregate_id: &str,
+        from_sequence: i64,
+    ) -> EventStoreResult<Vec<Event>> {
+        let aggregate_id = aggregate_id.to_string();
+        self.with_conn(move |conn| {
+            let records: Vec<EventRecord> = events::table
+                .filter(events::aggregate_id.eq(&aggregate_id))
+                .filter(events::sequence.ge(from_sequence))
+                .order(events::sequence.asc())
+                .load(conn)
+                .map_err(db_err)?;
+            Ok(records.into_iter().map(EventRecord::into_event).collect())
+        })
+        .await
+    }
+
+    async fn stream_all(&self, from_position: i64) -> EventStoreResult<Vec<Event>> {
+        self.stream_all_paged(GlobalPosition(from_position), usize::MAX)
+            .await
+    }
+
+    async fn stream_all_paged(
+        &self,
+        from: GlobalPosition,
+        limit: usize,
+    ) -> EventStoreResult<Vec<Event>> {
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        self.with_conn(move |conn| {
+            let records: Vec<EventRecord> = events::table
+                .filter(events::id.ge(from.value()))
+                .order(events::id.asc())
+                .limit(limit)
+                .load(conn)
+                .map_err(db_err)?;
+            Ok(records.into_iter().map(EventRecord::into_event).collect())
+        })
+        .await
+    }
+
+    async fn get_version(&self, aggregate_id: &str) -> EventStoreResult<i64> {
+        let aggregate_id = aggregate_id.to_string();
+        self.with_conn(move |conn| current_version(conn, &aggregate_id))
+            .await
+    }
+
+    async fn save_snapshot(&self, snapshot: &Snapshot) -> EventStoreResult<()> {
+        let record = SnapshotRecord::from_snapshot(snapshot)?;
+        self.with_conn(move |conn| {
+            diesel::insert_into(snapshots::table)
+                .values(&record)
+                .on_conflict(snapshots::aggregate_id)
+                .do_update()
+                .set(&record)
+                .execute(conn)
+                .map_err(db_err)?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn load_snapshot(&self, aggregate_id: &str) -> EventStoreResult<Option<Snapshot>> {
+        let aggregate_id = aggregate_id.to_string();
+        self.with_conn(move |conn| {
+            let record: Option<SnapshotRecord> = snapshots::table
+                .filter(snapshots::aggregate_id.eq(&aggregate_id))
+                .first(conn)
+                .optional()
+                .map_err(db_err)?;
+            Ok(record.map(SnapshotRecord::into_snapshot))
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A freshly migrated database, dropped again when the value is. Tests
+    /// that need one are `#[ignore]`d and need `ARC_POSTGRES_TEST_URL`
+    /// pointing at a server whose user may create databases
+    /// (`make test-postgres` starts a throwaway one).
+    pub(crate) struct TestDatabase {
+        admin_url: String,
+        name: String,
+        pub url: String,
+    }
+
+    impl TestDatabase {
+        pub fn create() -> Self {
+            let admin_url = std::env::var("ARC_POSTGRES_TEST_URL")
+                .expect("set ARC_POSTGRES_TEST_URL to run Postgres tests");
+            let name = format!("arc_test_{}", Uuid::new_v4().simple());
+            let (base, query) = match admin_url.split_once('?') {
+                Some((base, query)) => (base, format!("?{query}")),
+                None => (admin_url.as_str(), String::new()),
+            };
+            let (server, _) = base
+                .rsplit_once('/')
+                .expect("ARC_POSTGRES_TEST_URL must include a database");
+            let url = format!("{server}/{name}{query}");
+
+            let mut admin = PgConnection::establish(&admin_url).expect("connect to Postgres");
+            diesel::sql_query(format!("CREATE DATABASE {name}"))
+                .execute(&mut admin)
+                .expect("create test database");
+            let db = Self {
+                admin_url,
+                name,
+                url,
+            };
+            let mut conn = PgConnection::establish(&db.url).expect("connect to test database");
+            conn.run_pending_migrations(MIGRATIONS).expect("migrations");
+            db
+        }
+
+        pub fn pool(&self) -> Pool {
+            let manager = ConnectionManager::<PgConnection>::new(&self.url);
+            Pool::builder().max_size(4).build(manager).expect("pool")
+        }
+    }
+
+    impl Drop for TestDatabase {
+        fn drop(&mut self) {
+            if let Ok(mut admin) = PgConnection::establish(&self.admin_url) {
+                let _ = diesel::sql_query(format!(
+                    "DROP DATABASE IF EXISTS {} WITH (FORCE)",
+                    self.name
+                ))
+                .execute(&mut admin);
+            }
+        }
+    }
+
+    pub(crate) fn stamped_event(
+        agg_type: &str,
+        agg_id: &str,
+        sequence: i64,
+        event_type: &str,
+        payload: serde_json::Value,
+    ) -> Event {
+        Event::new(agg_type, agg_id, sequence, event_type, payload)
+            .with_audit(AuditMetadata::test_default())
+    }
+
+    fn setup() -> (TestDatabase, PostgresEventStore) {
+        let db = TestDatabase::create();
+        let store = PostgresEventStore::with_pool(db.pool());
+        (db, store)
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_append_and_load_roundtrips_event() {
+        let (_db, store) = setup();
+        let mut event = stamped_event(
+            "User",
+            "user-123",
+            1,
+            "UserCreated",
+            json!({ "name": "Alice", "tags": ["a", "b"] }),
+        );
+        event.schema_version = 2;
+
+        let stored = store
+            .append_returning("user-123", VersionCheck::New, vec![event.clone()])
+            .await
+            .unwrap();
+        let loaded = store.load("user-123").await.unwrap();
+
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0].event_id, event.event_id);
+        assert_eq!(loaded[0].payload, event.payload);
+        assert_eq!(loaded[0].audit, event.audit);
+        assert_eq!(loaded[0].timestamp, event.timestamp);
+        assert_eq!(loaded[0].schema_version, 2);
+        assert_eq!(loaded[0].position, stored[0].position);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_optimistic_concurrency_control() {
+        let (_db, store) = setup();
+        store
+            .append(
+                "user-789",
+                VersionCheck::New,
+                vec![stamped_event(
+                    "User",
+                    "user-789",
+                    1,
+                    "UserCreated",
+                    json!({}),
+                )],
+            )
+            .await
+            .unwrap();
+        let result = store
+            .append(
+                "user-789",
+                VersionCheck::New,
+                vec![stamped_event(
+                    "User",
+                    "user-789",
+                    1,
+                    "UserCreated",
+                    json!({}),
+                )],
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(EventStoreError::ConcurrencyConflict {
+                expected: 0,
+                actual: 1,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_invalid_sequence() {
+        let (_db, store) = setup();
+        let result = store
+            .append(
+                "user-999",
+                VersionCheck::New,
+                vec![stamped_event(
+                    "User",
+                    "user-999",
+                    5,
+                    "UserCreated",
+                    json!({}),
+                )],
+            )
+            .await;
+        assert!(matches!(
+            result,
+            Err(EventStoreError::InvalidSequence {
+                expected: 1,
+                actual: 5,
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_sequence_constraint_maps_to_concurrency_conflict() {
+        // A writer that bypasses the advisory lock can still lose the race
+        // on UNIQUE (aggregate_id, sequence); that must read as a conflict.
+        let (_db, store) = setup();
+        let first = stamped_event("User", "u1", 1, "UserCreated", json!({}));
+        let second = stamped_event("User", "u1", 1, "UserCreated", json!({}));
+        let err = store
+            .with_conn(move |conn| {
+                for event in [first, second] {
+                    if let Err(e) = diesel::insert_into(events::table)
+                        .values(NewEventRecord::from_event(&event)?)
+                        .execute(conn)
+                    {
+                        return Ok(Some(e));
+                    }
+                }
+                Ok(None)
+            })
+            .await
+            .unwrap()
+            .expect("second insert must violate the sequence constraint");
+        assert!(is_sequence_conflict(&err), "got {err:?}");
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_load_from_and_get_version() {
+        let (_db, store) = setup();
+        let events = (1..=4)
+            .map(|seq| stamped_event("Order", "order-1", seq, "ItemAdded", json!({})))
+            .collect();
+        store
+            .append("order-1", VersionCheck::New, events)
+            .await
+            .unwrap();
+
+        let loaded = store.load_from("order-1", 3).await.unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded[0].sequence, 3);
+        assert_eq!(store.get_version("order-1").await.unwrap(), 4);
+        assert_eq!(store.get_version("missing").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_stream_all_paged_resumes_from_last_position() {
+        let (_db, store) = setup();
+        for (agg, n) in [("a", 3), ("b", 2)] {
+            let events = (1..=n)
+                .map(|seq| stamped_event("User", agg, seq, "Touched", json!({})))
+                .collect();
+            store.append(agg, VersionCheck::New, events).await.unwrap();
+        }
+
+        let first = store
+            .stream_all_paged(GlobalPosition::START, 3)
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 3);
+        let next = first.last().unwrap().position.unwrap().next();
+        let rest = store.stream_all_paged(next, 3).await.unwrap();
+        assert_eq!(rest.len(), 2);
+        assert!(rest.iter().all(|e| e.aggregate_id == "b"));
+        assert_eq!(store.stream_all(1).await.unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_concurrent_appends_keep_positions_in_commit_order() {
+        let (_db, store) = setup();
+        let tasks: Vec<_> = (0..8)
+            .map(|i| {
+                let store = store.clone();
+                tokio::spawn(async move {
+                    let id = format!("agg-{i}");
+                    for seq in 1..=5 {
+                        let event = stamped_event("User", &id, seq, "Touched", json!({}));
+                        store
+                            .append(&id, VersionCheck::Expected(seq - 1), vec![event])
+                            .await
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        let all = store.stream_all(1).await.unwrap();
+        assert_eq!(all.len(), 40);
+        let positions: Vec<i64> = all.iter().map(|e| e.position.unwrap().value()).collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_racing_writers_on_one_aggregate_conflict() {
+        let (_db, store) = setup();
+        let tasks: Vec<_> = (0..4)
+            .map(|_| {
+                let store = store.clone();
+                tokio::spawn(async move {
+                    let event = stamped_event("User", "u1", 1, "UserCreated", json!({}));
+                    store.append("u1", VersionCheck::New, vec![event]).await
+                })
+            })
+            .collect();
+        let mut ok = 0;
+        for task in tasks {
+            match task.await.unwrap() {
+                Ok(()) => ok += 1,
+                Err(e) => assert!(
+                    matches!(e, EventStoreError::ConcurrencyConflict { .. }),
+                    "got {e:?}"
+                ),
+            }
+        }
+        assert_eq!(ok, 1);
+        assert_eq!(store.get_version("u1").await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_append_rejects_pending_audit() {
+        let (_db, store) = setup();
+        let event = Event::new("User", "u1", 1, "UserCreated", json!({}));
+        let err = store
+            .append("u1", VersionCheck::New, vec![event])
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, EventStoreError::InvalidAudit { .. }),
+            "got {err:?}"
+        );
+        assert_eq!(store.get_version("u1").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_snapshot_roundtrip_and_replace() {
+        let (_db, store) = setup();
+        assert!(store.load_snapshot("u1").await.unwrap().is_none());
+
+        let mut snapshot = Snapshot {
+            aggregate_type: "User".into(),
+            aggregate_id: "u1".into(),
+            version: 5,
+            schema_version: 1,
+            state: json!({ "name": "Alice" }),
+            taken_at_us: 1,
+        };
+        store.save_snapshot(&snapshot).await.unwrap();
+        snapshot.version = 10;
+        snapshot.state = json!({ "name": "Alice B" });
+        store.save_snapshot(&snapshot).await.unwrap();
+
+        let loaded = store.load_snapshot("u1").await.unwrap().unwrap();
+        assert_eq!(loaded.version, 10);
+        assert_eq!(loaded.state, json!({ "name": "Alice B" }));
+    }
+}
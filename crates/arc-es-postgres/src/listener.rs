@@ -0,0 +1,159 @@
+//! `LISTEN` on [`EVENTS_CHANNEL`] for new-event wakeups.
+//!
+//! [`PostgresEventStore`](crate::PostgresEventStore) notifies the channel
+//! with the last appended position when an append commits. A reader that
+//! tails the log (a worker catching projections up, a handler source) can
+//! wait on [`PostgresEventListener::wait`] instead of sleeping a fixed poll
+//! interval. Notifications are wakeups, not delivery: they are dropped while
+//! nobody listens and coalesce under load, so readers still read the log
+//! from their checkpoint.
+//!
+//! Diesel has no notification API, so the listener holds its own
+//! `tokio-postgres` connection.
+
+use crate::EVENTS_CHANNEL;
+use arc_core::event::GlobalPosition;
+use arc_core::event_store::{EventStoreError, EventStoreResult};
+use futures::StreamExt;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_postgres::{AsyncMessage, NoTls};
+
+/// A dedicated connection listening for appends.
+pub struct PostgresEventListener {
+    positions: watch::Receiver<Option<GlobalPosition>>,
+    task: JoinHandle<()>,
+    _client: tokio_postgres::Client,
+}
+
+impl PostgresEventListener {
+    /// Connect to `database_url` and `LISTEN` on [`EVENTS_CHANNEL`].
+    pub async fn connect(database_url: &str) -> EventStoreResult<Self> {
+        let (client, mut connection) = tokio_postgres::connect(database_url, NoTls)
+            .await
+            .map_err(|e| EventStoreError::database(format!("Failed to connect listener: {e}")))?;
+
+        let (sender, positions) = watch::channel(None);
+        let task = tokio::spawn(async move {
+            let mut messages = futures::stream::poll_fn(move |cx| connection.poll_message(cx));
+            while let Some(message) = messages.next().await {
+                match message {
+                    Ok(AsyncMessage::Notification(n)) if n.channel() == EVENTS_CHANNEL => {
+                        match n.payload().parse::<i64>() {
+                            Ok(position) => {
+                                sender.send_replace(Some(GlobalPosition(position)));
+                            }
+                            Err(_) => tracing::warn!(
+                                payload = n.payload(),
+                                "Ignoring malformed event notification"
+                            ),
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::error!(error = %e, "Event listener connection failed");
+                        break;
+                    }
+                }
+            }
+        });
+
+        client
+            .batch_execute(&format!("LISTEN {EVENTS_CHANNEL}"))
+            .await
+            .map_err(|e| EventStoreError::database(format!("Failed to LISTEN: {e}")))?;
+
+        Ok(Self {
+            positions,
+            task,
+            _client: client,
+        })
+    }
+
+    /// Resolve on the next append notification with the position it
+    /// reported. Notifications received since the previous call resolve
+    /// immediately (with the latest position). Fails once the connection is
+    /// lost; reconnect with [`connect`](Self::connect).
+    pub async fn wait(&mut self) -> EventStoreResult<GlobalPosition> {
+        loop {
+            self.positions.changed().await.map_err(|_| {
+                EventStoreError::database("Event listener connection closed".to_string())
+            })?;
+            if let Some(position) = *self.positions.borrow_and_update() {
+                return Ok(position);
+            }
+        }
+    }
+
+    /// Highest position seen so far, if any notification has arrived.
+    pub fn latest(&self) -> Option<GlobalPosition> {
+        *self.positions.borrow()
+    }
+}
+
+impl Drop for PostgresEventListener {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{stamped_event, TestDatabase};
+    use crate::PostgresEventStore;
+    use arc_core::event_store::{EventStore, VersionCheck};
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_append_wakes_listener_with_position() {
+        let db = TestDatabase::create();
+        let store = PostgresEventStore::with_pool(db.pool());
+        let mut listener = PostgresEventListener::connect(&db.url).await.unwrap();
+        assert_eq!(listener.latest(), None);
+
+        let stored = store
+            .append_returning(
+                "u1",
+                VersionCheck::New,
+                vec![
+                    stamped_event("User", "u1", 1, "UserCreated", json!({})),
+                    stamped_event("User", "u1", 2, "UserUpdated", json!({})),
+                ],
+            )
+            .await
+            .unwrap();
+
+        let position = tokio::time::timeout(Duration::from_secs(5), listener.wait())
+            .await
+            .expect("notification within 5s")
+            .unwrap();
+        assert_eq!(Some(position), stored[1].position);
+        assert_eq!(listener.latest(), Some(position));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_failed_append_does_not_notify() {
+        let db = TestDatabase::create();
+        let store = PostgresEventStore::with_pool(db.pool());
+        let mut listener = PostgresEventListener::connect(&db.url).await.unwrap();
+
+        let result = store
+            .append(
+                "u1",
+                VersionCheck::Expected(3),
+                vec![stamped_event("User", "u1", 4, "UserUpdated", json!({}))],
+            )
+            .await;
+        assert!(result.is_err());
+        assert!(
+            tokio::time::timeout(Duration::from_millis(300), listener.wait())
+                .await
+                .is_err(),
+            "rolled-back append must not notify"
+        );
+    }
+}
@@ -0,0 +1,535 @@
+//! # Postgres Read Model Store
+//!
+//! [`ReadModelStore`] over the framework's standard projection shape:
+//! `(id TEXT PK, version BIGINT, data JSONB)`. The `data` column holds the
+//! full row, so the store serves any projector without knowing its columns.
+//!
+//! ## Idempotency
+//!
+//! [`upsert`](ReadModelStore::upsert) translates to
+//!
+//! ```sql
+//! INSERT INTO {table} (id, version, data) VALUES ($1, $2, $3)
+//! ON CONFLICT (id) DO UPDATE
+//!    SET version = excluded.version, data = excluded.data
+//!  WHERE {table}.version < excluded.version
+//! ```
+//!
+//! the same gate as the SQLite store: replay and at-least-once delivery
+//! never regress a row. [`apply_batch`](ReadModelStore::apply_batch) runs
+//! the same statements in one transaction.
+//!
+//! ## Queries
+//!
+//! [`find_by`](ReadModelStore::find_by) compares strings with
+//! `data ->> '{field}'`, which matches the expression indexes migrations
+//! create (e.g. `idx_users_view_email`), and other primitives as JSONB.
+//! A `null` value matches both an explicit `null` and a missing field.
+//!
+//! Tables are created by migrations, never by the store.
+
+use arc_core::read_model_store::{
+    ReadModelError, ReadModelResult, ReadModelStore, Row, Upsert, WriteOp,
+};
+use async_trait::async_trait;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::r2d2::{self, ConnectionManager};
+use diesel::sql_types::{BigInt, Jsonb, Text};
+use std::sync::Arc;
+
+type Pool = r2d2::Pool<ConnectionManager<PgConnection>>;
+
+/// PostgreSQL implementation of [`ReadModelStore`].
+#[derive(Clone)]
+pub struct PostgresReadModelStore {
+    pool: Arc<Pool>,
+}
+
+impl PostgresReadModelStore {
+    /// Build a new store from a database URL. Creates a small r2d2 pool.
+    pub async fn new(database_url: &str) -> ReadModelResult<Self> {
+        let manager = ConnectionManager::<PgConnection>::new(database_url);
+        let pool = tokio::task::spawn_blocking(move || Pool::builder().max_size(10).build(manager))
+            .await
+            .map_err(|e| ReadModelError::other(format!("Task join error: {e}")))?
+            .map_err(|e| {
+                ReadModelError::other(format!("Failed to build read-model pool: {}", e))
+            })?;
+        Ok(Self::with_pool(pool))
+    }
+
+    /// Build a store from an existing pool, e.g. one shared with the event
+    /// store.
+    pub fn with_pool(pool: Pool) -> Self {
+        Self {
+            pool: Arc::new(pool),
+        }
+    }
+
+    /// Run `f` on a pooled connection off the async runtime. `fail` maps
+    /// connection and query errors to the operation's error kind.
+    async fn run<F, T>(&self, fail: fn(String) -> ReadModelError, f: F) -> ReadModelResult<T>
+    where
+        F: FnOnce(&mut PgConnection) -> QueryResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool
+                .get()
+                .map_err(|e| fail(format!("Failed to get connection: {e}")))?;
+            f(&mut conn).map_err(|e| fail(e.to_string()))
+        })
+        .await
+        .map_err(|e| ReadModelError::other(format!("Task join error: {e}")))?
+    }
+}
+
+#[derive(QueryableByName, Debug)]
+struct DataRow {
+    #[diesel(sql_type = Jsonb)]
+    data: Row,
+}
+
+/// Validate a table or column name before splicing it into SQL. Diesel's
+/// `sql_query` binds values, not identifiers; only `[A-Za-z0-9_]` passes.
+fn check_ident(label: &str, ident: &str) -> ReadModelResult<()> {
+    if ident.is_empty() {
+        return Err(ReadModelError::other(format!("{label} cannot be empty")));
+    }
+    if !ident.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(ReadModelError::other(format!(
+            "invalid {label} '{ident}': only [A-Za-z0-9_] permitted"
+        )));
+    }
+    Ok(())
+}
+
+fn extract_version(row: &Row) -> ReadModelResult<i64> {
+    row.get("version").and_then(|v| v.as_i64()).ok_or_else(|| {
+        ReadModelError::write_failed(
+            "Upsert.row missing required i64 field 'version' for version-gated upsert",
+        )
+    })
+}
+
+/// A [`WriteOp`] checked and ready to run on a connection.
+enum PreparedWrite {
+    Upsert {
+        table: String,
+        key: String,
+        version: i64,
+        row: Row,
+    },
+    Delete {
+        table: String,
+        key: String,
+    },
+}
+
+impl PreparedWrite {
+    fn new(op: WriteOp) -> ReadModelResult<Self> {
+        match op {
+            WriteOp::Upsert(op) => {
+                check_ident("table name", &op.table)?;
+                let version = extract_version(&op.row)?;
+                let Upsert { table, key, row } = op;
+                Ok(PreparedWrite::Upsert {
+                    table,
+                    key,
+                    version,
+                    row,
+                })
+            }
+            WriteOp::Delete { table, key } => {
+                check_ident("table name", &table)?;
+                Ok(PreparedWrite::Delete { table, key })
+            }
+        }
+    }
+
+    fn execute(self, conn: &mut PgConnection) -> QueryResult<()> {
+        match self {
+            PreparedWrite::Upsert {
+                table,
+                key,
+                version,
+                row,
+            } => diesel::sql_query(format!(
+                "INSERT INTO {table} (id, version, data) VALUES ($1, $2, $3) \
+                 ON CONFLICT (id) DO UPDATE SET version = excluded.version, data = excluded.data \
+                 WHERE {table}.version < excluded.version"
+            ))
+            .bind::<Text, _>(key)
+            .bind::<BigInt, _>(version)
+            .bind::<Jsonb, _>(row)
+            .execute(conn),
+            PreparedWrite::Delete { table, key } => {
+                diesel::sql_query(format!("DELETE FROM {table} WHERE id = $1"))
+                    .bind::<Text, _>(key)
+                    .execute(conn)
+            }
+        }
+        .map(drop)
+    }
+}
+
+fn write_failed(message: String) -> ReadModelError {
+    ReadModelError::write_failed(message)
+}
+
+fn query_failed(message: String) -> ReadModelError {
+    ReadModelError::query_failed(message)
+}
+
+#[async_trait]
+impl ReadModelStore for PostgresReadModelStore {
+    async fn upsert(&self, op: Upsert) -> ReadModelResult<()> {
+        let write = PreparedWrite::new(WriteOp::Upsert(op))?;
+        self.run(write_failed, move |conn| write.execute(conn))
+            .await
+    }
+
+    async fn delete(&self, table: &str, key: &str) -> ReadModelResult<()> {
+        let write = PreparedWrite::new(WriteOp::delete(table, key))?;
+        self.run(write_failed, move |conn| write.execute(conn))
+            .await
+    }
+
+    async fn apply_batch(&self, ops: Vec<WriteOp>) -> ReadModelResult<()> {
+        let writes = ops
+            .into_iter()
+            .map(PreparedWrite::new)
+            .collect::<ReadModelResult<Vec<_>>>()?;
+        if writes.is_empty() {
+            return Ok(());
+        }
+        self.run(write_failed, move |conn| {
+            conn.transaction(|conn| writes.into_iter().try_for_each(|write| write.execute(conn)))
+        })
+        .await
+    }
+
+    async fn get(&self, table: &str, key: &str) -> ReadModelResult<Option<Row>> {
+        check_ident("table name", table)?;
+        let sql = format!("SELECT data FROM {table} WHERE id = $1 LIMIT 1");
+        let key = key.to_string();
+
+        let rows: Vec<DataRow> = self
+            .run(query_failed, move |conn| {
+                diesel::sql_query(sql).bind::<Text, _>(key).load(conn)
+            })
+            .await?;
+        Ok(rows.into_iter().next().map(|r| r.data))
+    }
+
+    async fn find_by(
+        &self,
+        table: &str,
+        field: &str,
+        value: &serde_json::Value,
+    ) -> ReadModelResult<Vec<Row>> {
+        check_ident("table name", table)?;
+        check_ident("field name", field)?;
+        let select = format!("SELECT data FROM {table} WHERE");
+
+        // Primitive comparisons only, as in the SQLite store.
+        let rows: Vec<DataRow> = match value.clone() {
+            serde_json::Value::String(s) => {
+                let sql = format!("{select} data ->> '{field}' = $1");
+                self.run(query_failed, move |conn| {
+                    diesel::sql_query(sql).bind::<Text, _>(s).load(conn)
+                })
+                .await?
+            }
+            serde_json::Value::Null => {
+                let sql = format!("{select} COALESCE(data -> '{field}', 'null') = 'null'");
+                self.run(query_failed, move |conn| diesel::sql_query(sql).load(conn))
+                    .await?
+            }
+            value @ (serde_json::Value::Number(_) | serde_json::Value::Bool(_)) => {
+                let sql = format!("{select} data -> '{field}' = $1");
+                self.run(query_failed, move |conn| {
+                    diesel::sql_query(sql).bind::<Jsonb, _>(value).load(conn)
+                })
+                .await?
+            }
+            other => {
+                return Err(ReadModelError::query_failed(format!(
+                    "find_by only accepts primitive values, got: {other}"
+                )))
+            }
+        };
+        Ok(rows.into_iter().map(|r| r.data).collect())
+    }
+
+    async fn list(&self, table: &str) -> ReadModelResult<Vec<Row>> {
+        check_ident("table name", table)?;
+        let sql = format!("SELECT data FROM {table} ORDER BY id");
+
+        let rows: Vec<DataRow> = self
+            .run(query_failed, move |conn| diesel::sql_query(sql).load(conn))
+            .await?;
+        Ok(rows.into_iter().map(|r| r.data).collect())
+    }
+
+    async fn truncate(&self, table: &str) -> ReadModelResult<()> {
+        check_ident("table name", table)?;
+        let sql = format!("TRUNCATE {table}");
+
+        self.run(ReadModelError::schema_failed, move |conn| {
+            diesel::sql_query(sql).execute(conn)
+        })
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TestDatabase;
+    use serde_json::json;
+
+    fn setup() -> (TestDatabase, PostgresReadModelStore) {
+        let db = TestDatabase::create();
+        let store = PostgresReadModelStore::with_pool(db.pool());
+        (db, store)
+    }
+
+    fn user_row(id: &str, name: &str, email: &str, version: i64) -> Row {
+        json!({
+            "id": id,
+            "name": name,
+            "email": email,
+            "active": true,
+            "version": version,
+        })
+    }
+
+    async fn upsert(store: &PostgresReadModelStore, row: Row) -> ReadModelResult<()> {
+        let key = row["id"].as_str().unwrap().to_string();
+        store.upsert(Upsert::new("users_view", key, row)).await
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_upsert_inserts_then_reads() {
+        let (_db, store) = setup();
+        upsert(&store, user_row("u1", "Alice", "a@b.c", 1))
+            .await
+            .unwrap();
+
+        let got = store.get("users_view", "u1").await.unwrap().unwrap();
+        assert_eq!(got["name"], "Alice");
+        assert_eq!(got["version"], 1);
+        assert!(store.get("users_view", "u2").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_upsert_advances_only_on_higher_version() {
+        let (_db, store) = setup();
+        upsert(&store, user_row("u1", "Alice", "a@b.c", 1))
+            .await
+            .unwrap();
+        upsert(&store, user_row("u1", "Alice2", "a@b.c", 2))
+            .await
+            .unwrap();
+        // Replays of the same or an older version must not regress the row.
+        upsert(&store, user_row("u1", "Should not stick", "a@b.c", 2))
+            .await
+            .unwrap();
+        upsert(&store, user_row("u1", "Stale", "a@b.c", 1))
+            .await
+            .unwrap();
+
+        let got = store.get("users_view", "u1").await.unwrap().unwrap();
+        assert_eq!(got["name"], "Alice2");
+        assert_eq!(got["version"], 2);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_find_by_compares_primitives() {
+        let (_db, store) = setup();
+        upsert(&store, user_row("u1", "Alice", "a@b.c", 1))
+            .await
+            .unwrap();
+        let mut bob = user_row("u2", "Bob", "b@b.c", 3);
+        bob["active"] = json!(false);
+        bob["nickname"] = json!(null);
+        upsert(&store, bob).await.unwrap();
+
+        let by_email = store
+            .find_by("users_view", "email", &json!("b@b.c"))
+            .await
+            .unwrap();
+        assert_eq!(by_email.len(), 1);
+        assert_eq!(by_email[0]["id"], "u2");
+
+        let by_version = store
+            .find_by("users_view", "version", &json!(3))
+            .await
+            .unwrap();
+        assert_eq!(by_version.len(), 1);
+        let active = store
+            .find_by("users_view", "active", &json!(true))
+            .await
+            .unwrap();
+        assert_eq!(active[0]["id"], "u1");
+        // Explicit null and missing field both match null.
+        let no_nickname = store
+            .find_by("users_view", "nickname", &json!(null))
+            .await
+            .unwrap();
+        assert_eq!(no_nickname.len(), 2);
+
+        let err = store
+            .find_by("users_view", "email", &json!(["a@b.c"]))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ReadModelError::QueryFailed { .. }));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_find_by_email_uses_index() {
+        let (_db, store) = setup();
+        let plan: Vec<PlanRow> = store
+            .run(query_failed, |conn| {
+                diesel::sql_query("SET enable_seqscan = off").execute(conn)?;
+                diesel::sql_query(
+                    "EXPLAIN SELECT data FROM users_view WHERE data ->> 'email' = 'a@b.c'",
+                )
+                .load(conn)
+            })
+            .await
+            .unwrap();
+        assert!(
+            plan.iter().any(|r| r.plan.contains("idx_users_view_email")),
+            "email lookup did not use index; plan: {plan:?}"
+        );
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_query_falls_back_to_in_memory_evaluation() {
+        use arc_core::read_model_query::{Direction, Filter, Query};
+
+        let (_db, store) = setup();
+        upsert(&store, user_row("u1", "Carol", "c@b.c", 1))
+            .await
+            .unwrap();
+        upsert(&store, user_row("u2", "Alice", "a@b.c", 1))
+            .await
+            .unwrap();
+        let mut bob = user_row("u3", "Bob", "b@b.c", 1);
+        bob["active"] = json!(false);
+        upsert(&store, bob).await.unwrap();
+
+        let query = Query::new()
+            .with_filter(Filter::eq("active", true))
+            .with_order("name", Direction::Asc)
+            .with_limit(1);
+        let first = store.query("users_view", &query).await.unwrap();
+        assert_eq!(first.rows[0]["id"], "u2");
+        let next = first.next.unwrap();
+        let second = store
+            .query("users_view", &query.clone().with_cursor(next))
+            .await
+            .unwrap();
+        assert_eq!(second.rows[0]["id"], "u1");
+        assert_eq!(store.count("users_view", &query).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_apply_batch_is_one_transaction() {
+        let (_db, store) = setup();
+        upsert(&store, user_row("u1", "Alice", "a@b.c", 2))
+            .await
+            .unwrap();
+
+        store
+            .apply_batch(vec![
+                Upsert::new("users_view", "u2", user_row("u2", "Bob", "b@b.c", 1)).into(),
+                Upsert::new("users_view", "u1", user_row("u1", "Stale", "a@b.c", 1)).into(),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(store.list("users_view").await.unwrap().len(), 2);
+        let alice = store.get("users_view", "u1").await.unwrap().unwrap();
+        assert_eq!(alice["name"], "Alice");
+
+        // The collision on the last op undoes the delete before it.
+        let err = store
+            .apply_batch(vec![
+                WriteOp::delete("users_view", "u2"),
+                Upsert::new("users_view", "u3", user_row("u3", "Eve", "a@b.c", 1)).into(),
+            ])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ReadModelError::WriteFailed { .. }), "{err:?}");
+        assert!(store.get("users_view", "u2").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_delete_list_and_truncate() {
+        let (_db, store) = setup();
+        upsert(&store, user_row("u1", "Alice", "a@b.c", 1))
+            .await
+            .unwrap();
+        upsert(&store, user_row("u2", "Bob", "b@b.c", 1))
+            .await
+            .unwrap();
+
+        store.delete("users_view", "u1").await.unwrap();
+        assert!(store.get("users_view", "u1").await.unwrap().is_none());
+        assert_eq!(store.list("users_view").await.unwrap().len(), 1);
+
+        store.truncate("users_view").await.unwrap();
+        assert!(store.list("users_view").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_email_unique_constraint_rejects_collision() {
+        let (_db, store) = setup();
+        upsert(&store, user_row("u1", "Alice", "x@y.z", 1))
+            .await
+            .unwrap();
+        let err = upsert(&store, user_row("u2", "Bob", "x@y.z", 1))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ReadModelError::WriteFailed { ref message } if message.contains("idx_users_view_email")),
+            "expected unique constraint violation, got {err:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_table_name_validation_rejects_injection() {
+        // Rejected before any connection is made, so no database is needed.
+        let manager = ConnectionManager::<PgConnection>::new("postgres://unused/unused");
+        let store = PostgresReadModelStore::with_pool(
+            Pool::builder().min_idle(Some(0)).build_unchecked(manager),
+        );
+        let err = store
+            .get("users_view; DROP TABLE users_view", "u1")
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ReadModelError::Other { ref message } if message.contains("table name")),
+            "expected identifier rejection, got {err:?}"
+        );
+    }
+
+    #[derive(QueryableByName, Debug)]
+    struct PlanRow {
+        #[diesel(sql_type = Text, column_name = "QUERY PLAN")]
+        plan: String,
+    }
+}
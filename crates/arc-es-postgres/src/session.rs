@@ -0,0 +1,281 @@
+//! Postgres-backed [`SessionStore`] (HIPAA-4).
+//!
+//! Same table and semantics as `SqliteSessionStore`, with `jti` stored as a
+//! native `UUID`.
+
+use arc_core::session::{SessionRecord, SessionStore, SessionStoreError};
+use async_trait::async_trait;
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use diesel::r2d2::{self, ConnectionManager};
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod schema {
+    diesel::table! {
+        jwt_sessions (jti) {
+            jti -> Uuid,
+            actor_id -> Text,
+            created_at_us -> BigInt,
+            expires_at_us -> BigInt,
+            revoked_at_us -> Nullable<BigInt>,
+        }
+    }
+}
+
+use schema::jwt_sessions;
+
+#[derive(Debug, Insertable, Queryable, Clone)]
+#[diesel(table_name = jwt_sessions)]
+struct SessionRow {
+    jti: Uuid,
+    actor_id: String,
+    created_at_us: i64,
+    expires_at_us: i64,
+    revoked_at_us: Option<i64>,
+}
+
+impl From<SessionRecord> for SessionRow {
+    fn from(record: SessionRecord) -> Self {
+        Self {
+            jti: record.jti,
+            actor_id: record.actor_id,
+            created_at_us: record.created_at_us,
+            expires_at_us: record.expires_at_us,
+            revoked_at_us: record.revoked_at_us,
+        }
+    }
+}
+
+impl From<SessionRow> for SessionRecord {
+    fn from(row: SessionRow) -> Self {
+        Self {
+            jti: row.jti,
+            actor_id: row.actor_id,
+            created_at_us: row.created_at_us,
+            expires_at_us: row.expires_at_us,
+            revoked_at_us: row.revoked_at_us,
+        }
+    }
+}
+
+type Pool = r2d2::Pool<ConnectionManager<PgConnection>>;
+
+/// Durable JWT session store backed by Postgres.
+#[derive(Clone)]
+pub struct PostgresSessionStore {
+    pool: Arc<Pool>,
+}
+
+impl PostgresSessionStore {
+    pub async fn new(database_url: &str) -> Result<Self, SessionStoreError> {
+        let manager = ConnectionManager::<PgConnection>::new(database_url);
+        let pool = tokio::task::spawn_blocking(move || Pool::builder().max_size(10).build(manager))
+            .await
+            .map_err(|e| SessionStoreError::Sink(format!("join error: {e}")))?
+            .map_err(|e| SessionStoreError::Sink(format!("failed to create pool: {e}")))?;
+        Ok(Self::with_pool(pool))
+    }
+
+    pub fn with_pool(pool: Pool) -> Self {
+        Self {
+            pool: Arc::new(pool),
+        }
+    }
+
+    /// Run `f` on a pooled connection off the async runtime. Every failure
+    /// is a sink failure, so `is_valid` fails closed.
+    async fn run<F, T>(&self, f: F) -> Result<T, SessionStoreError>
+    where
+        F: FnOnce(&mut PgConnection) -> QueryResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool
+                .get()
+                .map_err(|e| SessionStoreError::Sink(format!("conn: {e}")))?;
+            f(&mut conn).map_err(|e| SessionStoreError::Sink(e.to_string()))
+        })
+        .await
+        .map_err(|e| SessionStoreError::Sink(format!("join error: {e}")))?
+    }
+}
+
+#[async_trait]
+impl SessionStore for PostgresSessionStore {
+    async fn record_session(&self, record: SessionRecord) -> Result<(), SessionStoreError> {
+        if record.actor_id.trim().is_empty() {
+            return Err(SessionStoreError::Validation("actor_id empty".into()));
+        }
+        if record.expires_at_us <= record.created_at_us {
+            return Err(SessionStoreError::Validation(
+                "expires_at_us must be > created_at_us".into(),
+            ));
+        }
+
+        let row = SessionRow::from(record);
+        self.run(move |conn| {
+            diesel::insert_into(jwt_sessions::table)
+                .values(&row)
+                .execute(conn)
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn is_valid(&self, jti: Uuid, now_us: i64) -> Result<bool, SessionStoreError> {
+        let row: Option<SessionRow> = self
+            .run(move |conn| jwt_sessions::table.find(jti).first(conn).optional())
+            .await?;
+        Ok(row.is_some_and(|r| SessionRecord::from(r).is_valid_at(now_us)))
+    }
+
+    async fn revoke(&self, jti: Uuid, now_us: i64) -> Result<(), SessionStoreError> {
+        let n = self
+            .run(move |conn| {
+                diesel::update(jwt_sessions::table.find(jti))
+                    .set(jwt_sessions::revoked_at_us.eq(Some(now_us)))
+                    .execute(conn)
+            })
+            .await?;
+        if n == 0 {
+            Err(SessionStoreError::NotFound(jti))
+        } else {
+            Ok(())
+        }
+    }
+
+    async fn revoke_all_for_actor(
+        &self,
+        actor_id: &str,
+        now_us: i64,
+    ) -> Result<usize, SessionStoreError> {
+        let key = actor_id.to_string();
+        self.run(move |conn| {
+            diesel::update(
+                jwt_sessions::table
+                    .filter(jwt_sessions::actor_id.eq(&key))
+                    .filter(jwt_sessions::revoked_at_us.is_null()),
+            )
+            .set(jwt_sessions::revoked_at_us.eq(Some(now_us)))
+            .execute(conn)
+        })
+        .await
+    }
+
+    async fn prune_expired(&self, now_us: i64) -> Result<usize, SessionStoreError> {
+        self.run(move |conn| {
+            diesel::delete(jwt_sessions::table.filter(jwt_sessions::expires_at_us.le(now_us)))
+                .execute(conn)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::TestDatabase;
+
+    fn setup() -> (TestDatabase, PostgresSessionStore) {
+        let db = TestDatabase::create();
+        let store = PostgresSessionStore::with_pool(db.pool());
+        (db, store)
+    }
+
+    fn record(jti: Uuid, actor: &str) -> SessionRecord {
+        let now = 1_700_000_000_000_000;
+        SessionRecord {
+            jti,
+            actor_id: actor.into(),
+            created_at_us: now,
+            expires_at_us: now + 24 * 3600 * 1_000_000,
+            revoked_at_us: None,
+        }
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_record_then_is_valid() {
+        let (_db, s) = setup();
+        let id = Uuid::new_v4();
+        s.record_session(record(id, "alice")).await.unwrap();
+        assert!(s.is_valid(id, 1_700_000_000_000_001).await.unwrap());
+        assert!(!s
+            .is_valid(Uuid::new_v4(), 1_700_000_000_000_001)
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_revoke_then_invalid() {
+        let (_db, s) = setup();
+        let id = Uuid::new_v4();
+        s.record_session(record(id, "alice")).await.unwrap();
+        s.revoke(id, 1_700_000_000_000_500).await.unwrap();
+        assert!(!s.is_valid(id, 1_700_000_000_000_600).await.unwrap());
+
+        let err = s.revoke(Uuid::new_v4(), 0).await.unwrap_err();
+        assert!(matches!(err, SessionStoreError::NotFound(_)));
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_revoke_all_for_actor() {
+        let (_db, s) = setup();
+        let a1 = Uuid::new_v4();
+        let a2 = Uuid::new_v4();
+        let b = Uuid::new_v4();
+        s.record_session(record(a1, "alice")).await.unwrap();
+        s.record_session(record(a2, "alice")).await.unwrap();
+        s.record_session(record(b, "bob")).await.unwrap();
+
+        let now = 1_700_000_000_000_500;
+        assert_eq!(s.revoke_all_for_actor("alice", now).await.unwrap(), 2);
+        assert!(!s.is_valid(a1, now + 1).await.unwrap());
+        assert!(!s.is_valid(a2, now + 1).await.unwrap());
+        assert!(s.is_valid(b, now + 1).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_prune_expired() {
+        let (_db, s) = setup();
+        let live = Uuid::new_v4();
+        let dead = Uuid::new_v4();
+        let now = 1_700_000_000_000_000;
+        s.record_session(SessionRecord {
+            expires_at_us: now + 1_000_000,
+            ..record(live, "x")
+        })
+        .await
+        .unwrap();
+        s.record_session(SessionRecord {
+            created_at_us: now - 2000,
+            expires_at_us: now - 1000,
+            ..record(dead, "x")
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(s.prune_expired(now).await.unwrap(), 1);
+        assert!(s.is_valid(live, now + 1).await.unwrap());
+        assert!(!s.is_valid(dead, now + 1).await.unwrap());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs Postgres; run with `make test-postgres`"]
+    async fn test_record_rejects_invalid_expiry() {
+        let (_db, s) = setup();
+        let err = s
+            .record_session(SessionRecord {
+                expires_at_us: 0,
+                ..record(Uuid::new_v4(), "alice")
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(err, SessionStoreError::Validation(_)));
+    }
+}
@@ -0,0 +1,169 @@
+//! SQLite-backed [`CheckpointStore`] for projection positions.
+//!
+//! One row per projection in `projection_checkpoints`. Saves are a single
+//! upsert gated on `position < excluded.position`, the same forward-only
+//! rule the read model store applies to row versions, so concurrent or
+//! late deliveries can never rewind a checkpoint.
+
+use crate::now_us;
+use arc_core::event::GlobalPosition;
+use arc_core::projection::{CheckpointStore, ProjectionError, ProjectionResult};
+use async_trait::async_trait;
+use diesel::prelude::*;
+use diesel::r2d2::{self, ConnectionManager};
+use diesel::sql_types::{BigInt, Text};
+use diesel::sqlite::SqliteConnection;
+use std::sync::Arc;
+
+mod schema {
+    diesel::table! {
+        projection_checkpoints (projection) {
+            projection -> Text,
+            position -> BigInt,
+            updated_at_us -> BigInt,
+        }
+    }
+}
+
+use schema::projection_checkpoints;
+
+type Pool = r2d2::Pool<ConnectionManager<SqliteConnection>>;
+
+/// Durable projection checkpoints backed by SQLite.
+#[derive(Clone)]
+pub struct SqliteCheckpointStore {
+    pool: Arc<Pool>,
+}
+
+impl SqliteCheckpointStore {
+    pub async fn new(database_url: &str) -> ProjectionResult<Self> {
+        let manager = ConnectionManager::<SqliteConnection>::new(database_url);
+        let pool = Pool::builder()
+            .max_size(10)
+            .build(manager)
+            .map_err(|e| ProjectionError::other(format!("Failed to build checkpoint pool: {e}")))?;
+        Ok(Self {
+            pool: Arc::new(pool),
+        })
+    }
+
+    pub fn with_pool(pool: Pool) -> Self {
+        Self {
+            pool: Arc::new(pool),
+        }
+    }
+
+    /// Run `f` on a pooled connection off the async runtime, mapping every
+    /// failure to a checkpoint error for `projection`.
+    async fn run<F, T>(&self, projection: &str, f: F) -> ProjectionResult<T>
+    where
+        F: FnOnce(&mut SqliteConnection) -> QueryResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.pool.clone();
+        let name = projection.to_string();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| {
+                ProjectionError::checkpoint_failed(&name, format!("Failed to get connection: {e}"))
+            })?;
+            f(&mut conn).map_err(|e| ProjectionError::checkpoint_failed(&name, e.to_string()))
+        })
+        .await
+        .map_err(|e| ProjectionError::other(format!("Task join error: {e}")))?
+    }
+}
+
+#[async_trait]
+impl CheckpointStore for SqliteCheckpointStore {
+    async fn load(&self, projection: &str) -> ProjectionResult<Option<GlobalPosition>> {
+        let name = projection.to_string();
+        let position: Option<i64> = self
+            .run(projection, move |conn| {
+                projection_checkpoints::table
+                    .filter(projection_checkpoints::projection.eq(name))
+                    .select(projection_checkpoints::position)
+                    .first(conn)
+                    .optional()
+            })
+            .await?;
+        Ok(position.map(GlobalPosition))
+    }
+
+    async fn save(&self, projection: &str, position: GlobalPosition) -> ProjectionResult<()> {
+        let name = projection.to_string();
+        self.run(projection, move |conn| {
+            diesel::sql_query(
+                "INSERT INTO projection_checkpoints (projection, position, updated_at_us) \
+                 VALUES (?, ?, ?) \
+                 ON CONFLICT(projection) DO UPDATE \
+                 SET position = excluded.position, updated_at_us = excluded.updated_at_us \
+                 WHERE projection_checkpoints.position < excluded.position",
+            )
+            .bind::<Text, _>(name)
+            .bind::<BigInt, _>(position.value())
+            .bind::<BigInt, _>(now_us())
+            .execute(conn)
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn reset(&self, projection: &str) -> ProjectionResult<()> {
+        let name = projection.to_string();
+        self.run(projection, move |conn| {
+            diesel::delete(
+                projection_checkpoints::table.filter(projection_checkpoints::projection.eq(name)),
+            )
+            .execute(conn)
+        })
+        .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+    const MIGRATIONS: EmbeddedMigrations = embed_migrations!("../../migrations");
+
+    fn setup_store() -> SqliteCheckpointStore {
+        let manager = ConnectionManager::<SqliteConnection>::new(":memory:");
+        let pool = Pool::builder().max_size(1).build(manager).expect("pool");
+        let mut conn = pool.get().unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+        drop(conn);
+        SqliteCheckpointStore::with_pool(pool)
+    }
+
+    #[tokio::test]
+    async fn test_missing_checkpoint_loads_none() {
+        let store = setup_store();
+        assert_eq!(store.load("users").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_save_only_moves_forward() {
+        let store = setup_store();
+        store.save("users", GlobalPosition(10)).await.unwrap();
+        store.save("users", GlobalPosition(4)).await.unwrap();
+        assert_eq!(store.load("users").await.unwrap(), Some(GlobalPosition(10)));
+
+        store.save("users", GlobalPosition(12)).await.unwrap();
+        assert_eq!(store.load("users").await.unwrap(), Some(GlobalPosition(12)));
+        assert_eq!(store.load("orders").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_reset_forgets_checkpoint() {
+        let store = setup_store();
+        store.save("users", GlobalPosition(10)).await.unwrap();
+        store.reset("users").await.unwrap();
+        assert_eq!(store.load("users").await.unwrap(), None);
+
+        // After a reset, a lower position is accepted again.
+        store.save("users", GlobalPosition(2)).await.unwrap();
+        assert_eq!(store.load("users").await.unwrap(), Some(GlobalPosition(2)));
+    }
+}
@@ -0,0 +1,402 @@
+//! SQLite-backed [`IdempotencyStore`] for command dispatch.
+//!
+//! One row per `(actor_id, idempotency_key)` in `command_idempotency`. The
+//! outcome columns stay `NULL` while the command runs; `event_ids` holds the
+//! produced event ids as a JSON array once it completes, and `fingerprint`
+//! the hash of the command that produced them. `begin` runs its
+//! read-then-reserve inside an `IMMEDIATE` transaction, so two connections
+//! racing on the same key cannot both be told to go ahead.
+
+use arc_core::idempotency::{
+    IdempotencyError, IdempotencyRecord, IdempotencyStatus, IdempotencyStore, IdempotentOutcome,
+};
+use async_trait::async_trait;
+use diesel::prelude::*;
+use diesel::r2d2::{self, ConnectionManager};
+use diesel::sqlite::SqliteConnection;
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod schema {
+    diesel::table! {
+        command_idempotency (actor_id, idempotency_key) {
+            actor_id -> Text,
+            idempotency_key -> Text,
+            created_at_us -> BigInt,
+            expires_at_us -> BigInt,
+            aggregate_type -> Nullable<Text>,
+            aggregate_id -> Nullable<Text>,
+            event_ids -> Nullable<Text>,
+            fingerprint -> Nullable<Text>,
+        }
+    }
+}
+
+use schema::command_idempotency;
+
+#[derive(Debug, Insertable, Queryable, Clone)]
+#[diesel(table_name = command_idempotency)]
+struct IdempotencyRow {
+    actor_id: String,
+    idempotency_key: String,
+    created_at_us: i64,
+    expires_at_us: i64,
+    aggregate_type: Option<String>,
+    aggregate_id: Option<String>,
+    event_ids: Option<String>,
+    fingerprint: Option<String>,
+}
+
+impl IdempotencyRow {
+    fn from_record(record: IdempotencyRecord) -> Result<Self, IdempotencyError> {
+        let (aggregate_type, aggregate_id, event_ids, fingerprint) = match record.outcome {
+            Some(o) => (
+                Some(o.aggregate_type),
+                Some(o.aggregate_id),
+                Some(encode_event_ids(&o.event_ids)?),
+                o.fingerprint,
+            ),
+            None => (None, None, None, None),
+        };
+        Ok(Self {
+            actor_id: record.actor_id,
+            idempotency_key: record.key,
+            created_at_us: record.created_at_us,
+            expires_at_us: record.expires_at_us,
+            aggregate_type,
+            aggregate_id,
+            event_ids,
+            fingerprint,
+        })
+    }
+
+    fn into_record(self) -> Result<IdempotencyRecord, IdempotencyError> {
+        let outcome = match (self.aggregate_type, self.aggregate_id, self.event_ids) {
+            (Some(aggregate_type), Some(aggregate_id), Some(event_ids)) => {
+                let event_ids: Vec<Uuid> = serde_json::from_str(&event_ids).map_err(|e| {
+                    IdempotencyError::Sink(format!("malformed event_ids in DB row: {e}"))
+                })?;
+                Some(IdempotentOutcome {
+                    aggregate_type,
+                    aggregate_id,
+                    event_ids,
+                    fingerprint: self.fingerprint,
+                })
+            }
+            _ => None,
+        };
+        Ok(IdempotencyRecord {
+            actor_id: self.actor_id,
+            key: self.idempotency_key,
+            created_at_us: self.created_at_us,
+            expires_at_us: self.expires_at_us,
+            outcome,
+        })
+    }
+}
+
+fn encode_event_ids(ids: &[Uuid]) -> Result<String, IdempotencyError> {
+    serde_json::to_string(ids).map_err(|e| IdempotencyError::Sink(e.to_string()))
+}
+
+type Pool = r2d2::Pool<ConnectionManager<SqliteConnection>>;
+
+/// Durable idempotency key store backed by SQLite.
+#[derive(Clone)]
+pub struct SqliteIdempotencyStore {
+    pool: Arc<Pool>,
+}
+
+impl SqliteIdempotencyStore {
+    pub async fn new(database_url: &str) -> Result<Self, IdempotencyError> {
+        let manager = ConnectionManager::<SqliteConnection>::new(database_url);
+        let pool = Pool::builder()
+            .max_size(10)
+            .build(manager)
+            .map_err(|e| IdempotencyError::Sink(format!("failed to create pool: {e}")))?;
+        Ok(Self {
+            pool: Arc::new(pool),
+        })
+    }
+
+    pub fn with_pool(pool: Pool) -> Self {
+        Self {
+            pool: Arc::new(pool),
+        }
+    }
+}
+
+async fn run_blocking<F, T>(f: F) -> Result<T, IdempotencyError>
+where
+    F: FnOnce() -> Result<T, IdempotencyError> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| IdempotencyError::Sink(format!("join error: {e}")))?
+}
+
+fn find(
+    conn: &mut SqliteConnection,
+    actor_id: &str,
+    key: &str,
+) -> Result<Option<IdempotencyRow>, diesel::result::Error> {
+    command_idempotency::table
+        .filter(command_idempotency::actor_id.eq(actor_id))
+        .filter(command_idempotency::idempotency_key.eq(key))
+        .first(conn)
+        .optional()
+}
+
+#[async_trait]
+impl IdempotencyStore for SqliteIdempotencyStore {
+    async fn begin(
+        &self,
+        record: IdempotencyRecord,
+    ) -> Result<IdempotencyStatus, IdempotencyError> {
+        record.validate()?;
+        let now_us = record.created_at_us;
+        let row = IdempotencyRow::from_record(record)?;
+        let pool = self.pool.clone();
+
+        run_blocking(move || {
+            let mut conn = pool
+                .get()
+                .map_err(|e| IdempotencyError::Sink(format!("conn: {e}")))?;
+            conn.immediate_transaction(|conn| {
+                if let Some(existing) = find(conn, &row.actor_id, &row.idempotency_key)? {
+                    let existing = existing
+                        .into_record()
+                        .map_err(|e| diesel::result::Error::DeserializationError(Box::new(e)))?;
+                    if existing.is_live_at(now_us) {
+                        return Ok(match existing.outcome {
+                            Some(outcome) => IdempotencyStatus::Completed(outcome),
+                            None => IdempotencyStatus::InFlight,
+                        });
+                    }
+                }
+                diesel::replace_into(command_idempotency::table)
+                    .values(&row)
+                    .execute(conn)?;
+                Ok(IdempotencyStatus::Started)
+            })
+            .map_err(|e: diesel::result::Error| IdempotencyError::Sink(e.to_string()))
+        })
+        .await
+    }
+
+    async fn lookup(
+        &self,
+        actor_id: &str,
+        key: &str,
+        now_us: i64,
+    ) -> Result<Option<IdempotentOutcome>, IdempotencyError> {
+        let (actor_id, key) = (actor_id.to_string(), key.to_string());
+        let pool = self.pool.clone();
+
+        run_blocking(move || {
+            let mut conn = pool
+                .get()
+                .map_err(|e| IdempotencyError::Sink(format!("conn: {e}")))?;
+            let row = find(&mut conn, &actor_id, &key)
+                .map_err(|e| IdempotencyError::Sink(e.to_string()))?;
+            Ok(match row {
+                Some(r) => {
+                    let rec = r.into_record()?;
+                    rec.outcome.filter(|_| rec.expires_at_us > now_us)
+                }
+                None => None,
+            })
+        })
+        .await
+    }
+
+    async fn complete(
+        &self,
+        actor_id: &str,
+        key: &str,
+        outcome: IdempotentOutcome,
+    ) -> Result<(), IdempotencyError> {
+        let (actor_id, key) = (actor_id.to_string(), key.to_string());
+        let event_ids = encode_event_ids(&outcome.event_ids)?;
+        let pool = self.pool.clone();
+
+        run_blocking(move || {
+            let mut conn = pool
+                .get()
+                .map_err(|e| IdempotencyError::Sink(format!("conn: {e}")))?;
+            let n = diesel::update(
+                command_idempotency::table
+                    .filter(command_idempotency::actor_id.eq(&actor_id))
+                    .filter(command_idempotency::idempotency_key.eq(&key)),
+            )
+            .set((
+                command_idempotency::aggregate_type.eq(Some(outcome.aggregate_type)),
+                command_idempotency::aggregate_id.eq(Some(outcome.aggregate_id)),
+                command_idempotency::event_ids.eq(Some(event_ids)),
+                command_idempotency::fingerprint.eq(outcome.fingerprint),
+            ))
+            .execute(&mut conn)
+            .map_err(|e| IdempotencyError::Sink(e.to_string()))?;
+            if n == 0 {
+                Err(IdempotencyError::Validation(format!(
+                    "no reservation for key '{key}'"
+                )))
+            } else {
+                Ok(())
+            }
+        })
+        .await
+    }
+
+    async fn release(&self, actor_id: &str, key: &str) -> Result<(), IdempotencyError> {
+        let (actor_id, key) = (actor_id.to_string(), key.to_string());
+        let pool = self.pool.clone();
+
+        run_blocking(move || {
+            let mut conn = pool
+                .get()
+                .map_err(|e| IdempotencyError::Sink(format!("conn: {e}")))?;
+            diesel::delete(
+                command_idempotency::table
+                    .filter(command_idempotency::actor_id.eq(&actor_id))
+                    .filter(command_idempotency::idempotency_key.eq(&key))
+                    .filter(command_idempotency::event_ids.is_null()),
+            )
+            .execute(&mut conn)
+            .map_err(|e| IdempotencyError::Sink(e.to_string()))?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn prune_expired(&self, now_us: i64) -> Result<usize, IdempotencyError> {
+        let pool = self.pool.clone();
+
+        run_blocking(move || {
+            let mut conn = pool
+                .get()
+                .map_err(|e| IdempotencyError::Sink(format!("conn: {e}")))?;
+            let n = diesel::delete(
+                command_idempotency::table.filter(command_idempotency::expires_at_us.le(now_us)),
+            )
+            .execute(&mut conn)
+            .map_err(|e| IdempotencyError::Sink(e.to_string()))?;
+            Ok(n)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arc_core::idempotency::IN_FLIGHT_LEASE_US;
+    use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+    const MIGRATIONS: EmbeddedMigrations = embed_migrations!("../../migrations");
+    const NOW: i64 = 1_700_000_000_000_000;
+    const DAY_US: i64 = 24 * 3600 * 1_000_000;
+
+    async fn setup_store() -> SqliteIdempotencyStore {
+        let manager = ConnectionManager::<SqliteConnection>::new(":memory:");
+        let pool = Pool::builder().max_size(1).build(manager).expect("pool");
+        let mut conn = pool.get().unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+        drop(conn);
+        SqliteIdempotencyStore::with_pool(pool)
+    }
+
+    fn outcome() -> IdempotentOutcome {
+        IdempotentOutcome {
+            aggregate_type: "User".into(),
+            aggregate_id: "u1".into(),
+            event_ids: vec![Uuid::new_v4(), Uuid::new_v4()],
+            fingerprint: Some("ab12".into()),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_begin_complete_then_replay() {
+        let s = setup_store().await;
+        let r = IdempotencyRecord::reserve("alice", "k1", NOW, DAY_US);
+        assert_eq!(
+            s.begin(r.clone()).await.unwrap(),
+            IdempotencyStatus::Started
+        );
+        assert_eq!(s.begin(r).await.unwrap(), IdempotencyStatus::InFlight);
+        assert_eq!(s.lookup("alice", "k1", NOW).await.unwrap(), None);
+
+        let done = outcome();
+        s.complete("alice", "k1", done.clone()).await.unwrap();
+        assert_eq!(
+            s.lookup("alice", "k1", NOW + 1).await.unwrap(),
+            Some(done.clone())
+        );
+        let again = IdempotencyRecord::reserve("alice", "k1", NOW + 1_000, DAY_US);
+        assert_eq!(
+            s.begin(again).await.unwrap(),
+            IdempotencyStatus::Completed(done)
+        );
+        assert_eq!(s.lookup("bob", "k1", NOW + 1).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_release_only_drops_reservations() {
+        let s = setup_store().await;
+        let r = IdempotencyRecord::reserve("alice", "k1", NOW, DAY_US);
+        s.begin(r.clone()).await.unwrap();
+        s.release("alice", "k1").await.unwrap();
+        assert_eq!(
+            s.begin(r.clone()).await.unwrap(),
+            IdempotencyStatus::Started
+        );
+
+        s.complete("alice", "k1", outcome()).await.unwrap();
+        s.release("alice", "k1").await.unwrap();
+        assert!(matches!(
+            s.begin(r).await.unwrap(),
+            IdempotencyStatus::Completed(_)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_abandoned_reservation_is_taken_over() {
+        let s = setup_store().await;
+        s.begin(IdempotencyRecord::reserve("alice", "k1", NOW, DAY_US))
+            .await
+            .unwrap();
+        let later = IdempotencyRecord::reserve("alice", "k1", NOW + IN_FLIGHT_LEASE_US, DAY_US);
+        assert_eq!(s.begin(later).await.unwrap(), IdempotencyStatus::Started);
+    }
+
+    #[tokio::test]
+    async fn test_complete_without_reservation_is_rejected() {
+        let s = setup_store().await;
+        let err = s.complete("alice", "nope", outcome()).await.unwrap_err();
+        assert!(matches!(err, IdempotencyError::Validation(_)));
+    }
+
+    #[tokio::test]
+    async fn test_prune_expired() {
+        let s = setup_store().await;
+        s.begin(IdempotencyRecord::reserve(
+            "a",
+            "old",
+            NOW - 2 * DAY_US,
+            DAY_US,
+        ))
+        .await
+        .unwrap();
+        s.begin(IdempotencyRecord::reserve("a", "new", NOW, DAY_US))
+            .await
+            .unwrap();
+        assert_eq!(s.prune_expired(NOW).await.unwrap(), 1);
+        assert_eq!(
+            s.begin(IdempotencyRecord::reserve("a", "new", NOW, DAY_US))
+                .await
+                .unwrap(),
+            IdempotencyStatus::InFlight
+        );
+    }
+}
@@ -7,12 +7,32 @@
 //! [`validate_audit_batch`](arc_core::event_store::validate_audit_batch)
 //! before any write — defense-in-depth against an upstream that forgot to
 //! stamp.
+//!
+//! Aggregate snapshots live in the `snapshots` table, one row per aggregate,
+//! replaced on every `save_snapshot`.
+//!
+//! ## Integrity chain (HIPAA-5, §164.312(c)(1))
+//!
+//! With [`SqliteEventStore::with_integrity_chain`] configured, `append` signs
+//! every row inside the append transaction, twice:
+//!
+//! - `signature` chains from the previous event of the same aggregate.
+//! - `global_signature` chains from the previous row in insertion order.
+//!
+//! Rows written without a chain (including everything before migration
+//! `2026-05-10-000001_add_event_signatures`) carry the genesis marker `''`.
+//! Verification tolerates genesis rows only as a leading prefix. Verifying
+//! loads are opt-in via [`SqliteEventStore::verify_on_load`] or
+//! [`SqliteEventStore::load_verified`], and fail with
+//! [`EventStoreError::IntegrityViolation`].
 
 use arc_core::audit::AuditMetadata;
-use arc_core::event::Event;
+use arc_core::event::{Event, GlobalPosition};
 use arc_core::event_store::{
     validate_audit_batch, EventStore, EventStoreError, EventStoreResult, VersionCheck,
 };
+use arc_core::integrity::{EventSignature, IntegrityChain, IntegrityError};
+use arc_core::snapshot::Snapshot;
 use async_trait::async_trait;
 use diesel::prelude::*;
 use diesel::r2d2::{self, ConnectionManager};
@@ -29,6 +49,28 @@ pub use session::SqliteSessionStore;
 pub mod read_model_store;
 pub use read_model_store::SqliteReadModelStore;
 
+pub mod checkpoint_store;
+pub use checkpoint_store::SqliteCheckpointStore;
+
+pub mod idempotency;
+pub use idempotency::SqliteIdempotencyStore;
+
+pub mod process_store;
+pub use process_store::SqliteProcessStore;
+
+pub mod schedule_store;
+pub use schedule_store::SqliteScheduleStore;
+
+mod outbox;
+
+/// Microseconds since UNIX epoch, for bookkeeping columns.
+pub(crate) fn now_us() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_micros() as i64)
+        .unwrap_or(0)
+}
+
 /// Database row used for inserting events.
 #[derive(Debug, Insertable, Clone)]
 #[diesel(table_name = events)]
@@ -47,12 +89,14 @@ struct NewEventRecord {
     pub timestamp_utc_us: i64,
     pub causation_id: Option<String>,
     pub correlation_id: String,
+    pub signature: String,
+    pub global_signature: String,
+    pub schema_version: i32,
 }
 
 #[derive(Debug, Queryable, Clone)]
 struct EventRecord {
-    #[allow(dead_code)]
-    pub id: Option<i32>,
+    pub id: Option<i64>,
     pub event_id: String,
     pub aggregate_type: String,
     pub aggregate_id: String,
@@ -67,6 +111,9 @@ struct EventRecord {
     pub timestamp_utc_us: i64,
     pub causation_id: Option<String>,
     pub correlation_id: String,
+    pub signature: String,
+    pub global_signature: String,
+    pub schema_version: i32,
 }
 
 impl NewEventRecord {
@@ -89,8 +136,24 @@ impl NewEventRecord {
             timestamp_utc_us: event.audit.timestamp_utc_us,
             causation_id: event.audit.causation_id.map(|u| u.to_string()),
             correlation_id: event.audit.correlation_id.to_string(),
+            signature: String::new(),
+            global_signature: String::new(),
+            schema_version: i32::try_from(event.schema_version).map_err(|_| {
+                EventStoreError::serialization(format!(
+                    "Event schema version {} out of range",
+                    event.schema_version
+                ))
+            })?,
         })
     }
+
+    /// The event exactly as it will read back from this row. Signatures are
+    /// computed over this form: the `timestamp` column only keeps seconds.
+    fn as_stored(&self, event: &Event) -> Event {
+        let mut stored = event.clone();
+        stored.timestamp = (self.timestamp as u64) * 1000;
+        stored
+    }
 }
 
 impl EventRecord {
@@ -129,8 +192,15 @@ impl EventRecord {
             sequence: self.sequence,
             event_type: self.event_type.clone(),
             payload,
+            schema_version: u32::try_from(self.schema_version).map_err(|_| {
+                EventStoreError::serialization(format!(
+                    "Invalid event schema version {}",
+                    self.schema_version
+                ))
+            })?,
             audit,
             timestamp: (self.timestamp as u64) * 1000,
+            position: self.id.map(GlobalPosition),
         })
     }
 }
@@ -138,7 +208,7 @@ impl EventRecord {
 mod schema {
     diesel::table! {
         events (id) {
-            id -> Nullable<Integer>,
+            id -> Nullable<BigInt>,
             event_id -> Text,
             aggregate_type -> Text,
             aggregate_id -> Text,
@@ -153,18 +223,130 @@ mod schema {
             timestamp_utc_us -> BigInt,
             causation_id -> Nullable<Text>,
             correlation_id -> Text,
+            signature -> Text,
+            global_signature -> Text,
+            schema_version -> Integer,
+        }
+    }
+
+    diesel::table! {
+        snapshots (aggregate_id) {
+            aggregate_id -> Text,
+            aggregate_type -> Text,
+            version -> BigInt,
+            schema_version -> Integer,
+            state -> Text,
+            taken_at_us -> BigInt,
         }
     }
+
+    diesel::table! {
+        event_outbox (position) {
+            position -> BigInt,
+            attempts -> Integer,
+            last_error -> Nullable<Text>,
+            created_at_us -> BigInt,
+            delivered_at_us -> Nullable<BigInt>,
+        }
+    }
+}
+
+use schema::{event_outbox, events, snapshots};
+
+/// Database row for the `snapshots` table (used for both insert and load).
+#[derive(Debug, Insertable, Queryable, Clone)]
+#[diesel(table_name = snapshots)]
+struct SnapshotRecord {
+    pub aggregate_id: String,
+    pub aggregate_type: String,
+    pub version: i64,
+    pub schema_version: i32,
+    pub state: String,
+    pub taken_at_us: i64,
 }
 
-use schema::events;
+impl SnapshotRecord {
+    fn from_snapshot(snapshot: &Snapshot) -> Result<Self, EventStoreError> {
+        Ok(SnapshotRecord {
+            aggregate_id: snapshot.aggregate_id.clone(),
+            aggregate_type: snapshot.aggregate_type.clone(),
+            version: snapshot.version,
+            schema_version: i32::try_from(snapshot.schema_version).map_err(|_| {
+                EventStoreError::serialization(format!(
+                    "Snapshot schema version {} out of range",
+                    snapshot.schema_version
+                ))
+            })?,
+            state: serde_json::to_string(&snapshot.state)
+                .map_err(|e| EventStoreError::serialization(e.to_string()))?,
+            taken_at_us: snapshot.taken_at_us,
+        })
+    }
+
+    fn to_snapshot(&self) -> Result<Snapshot, EventStoreError> {
+        let state: serde_json::Value = serde_json::from_str(&self.state)
+            .map_err(|e| EventStoreError::serialization(e.to_string()))?;
+        Ok(Snapshot {
+            aggregate_type: self.aggregate_type.clone(),
+            aggregate_id: self.aggregate_id.clone(),
+            version: self.version,
+            schema_version: self.schema_version as u32,
+            state,
+            taken_at_us: self.taken_at_us,
+        })
+    }
+}
 
 type Pool = r2d2::Pool<ConnectionManager<SqliteConnection>>;
 
+/// Recompute a chain over `rows` (in chain order), starting from `anchor`.
+///
+/// Leading genesis-marked rows are pre-chain legacy data and are skipped
+/// when `anchor` is itself genesis. Any genesis row after that point, or any
+/// signature mismatch, is reported as `BrokenAt`.
+fn verify_rows<'a>(
+    chain: &dyn IntegrityChain,
+    mut anchor: EventSignature,
+    rows: impl IntoIterator<Item = (&'a Event, &'a str)>,
+) -> Result<(), IntegrityError> {
+    let mut in_legacy_prefix = anchor.is_genesis();
+    for (event, claimed) in rows {
+        if claimed.is_empty() && in_legacy_prefix {
+            continue;
+        }
+        in_legacy_prefix = false;
+        let computed = chain.sign_event(&anchor, event)?;
+        if computed.as_str() != claimed {
+            return Err(IntegrityError::BrokenAt {
+                aggregate_id: event.aggregate_id.clone(),
+                sequence: event.sequence,
+            });
+        }
+        anchor = computed;
+    }
+    Ok(())
+}
+
+/// An event together with its stored chain signatures, as returned by
+/// [`SqliteEventStore::stream_all_signed`] for offline audits.
+#[derive(Debug, Clone)]
+pub struct SignedEvent {
+    /// Row id — the event's position in the global chain.
+    pub position: i64,
+    pub event: Event,
+    /// Per-aggregate chain signature (genesis for legacy rows).
+    pub signature: EventSignature,
+    /// Global chain signature (genesis for legacy rows).
+    pub global_signature: EventSignature,
+}
+
 /// SQLite implementation of EventStore.
 #[derive(Clone)]
 pub struct SqliteEventStore {
     pool: Arc<Pool>,
+    integrity: Option<Arc<dyn IntegrityChain>>,
+    verify_on_load: bool,
+    outbox: bool,
 }
 
 impl SqliteEventStore {
@@ -175,16 +357,197 @@ impl SqliteEventStore {
             .build(manager)
             .map_err(|e| EventStoreError::database(format!("Failed to create pool: {}", e)))?;
 
-        Ok(SqliteEventStore {
-            pool: Arc::new(pool),
-        })
+        Ok(Self::with_pool(pool))
     }
 
     pub fn with_pool(pool: Pool) -> Self {
         SqliteEventStore {
             pool: Arc::new(pool),
+            integrity: None,
+            verify_on_load: false,
+            outbox: false,
         }
     }
+
+    /// Queue every appended event in `event_outbox`, inside the append
+    /// transaction. Pair with `CommandBus::with_outbox` and an
+    /// `OutboxRelay` for at-least-once publishing.
+    pub fn with_outbox(mut self) -> Self {
+        self.outbox = true;
+        self
+    }
+
+    /// Sign every appended event with `chain`. Without a chain, rows are
+    /// written with the genesis marker and cannot be verified.
+    pub fn with_integrity_chain(mut self, chain: Arc<dyn IntegrityChain>) -> Self {
+        self.integrity = Some(chain);
+        self
+    }
+
+    /// Make `load` / `load_from` verify the per-aggregate chain before
+    /// returning. Has no effect unless an integrity chain is configured.
+    pub fn verify_on_load(mut self, enabled: bool) -> Self {
+        self.verify_on_load = enabled;
+        self
+    }
+
+    /// Load an aggregate's full stream and verify its per-aggregate chain.
+    ///
+    /// Returns [`EventStoreError::IntegrityViolation`] on the first
+    /// mismatch, and an error if no integrity chain is configured.
+    pub async fn load_verified(&self, aggregate_id: &str) -> EventStoreResult<Vec<Event>> {
+        self.load_checked(aggregate_id, 1, true).await
+    }
+
+    /// Verify the global chain across every row in insertion order.
+    ///
+    /// Catches what per-aggregate verification cannot: a whole aggregate's
+    /// rows deleted, or rows re-inserted out of order.
+    pub async fn verify_global_chain(&self) -> EventStoreResult<()> {
+        let chain = self.integrity.clone().ok_or_else(|| {
+            EventStoreError::other(
+                "Global verification requested but no integrity chain is configured",
+            )
+        })?;
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| {
+                EventStoreError::database(format!("Failed to get connection: {}", e))
+            })?;
+
+            let records: Vec<EventRecord> =
+                events::table
+                    .order(events::id.asc())
+                    .load(&mut conn)
+                    .map_err(|e| EventStoreError::database(e.to_string()))?;
+            let events: Vec<Event> = records
+                .iter()
+                .map(|r| r.to_event())
+                .collect::<EventStoreResult<_>>()?;
+
+            verify_rows(
+                chain.as_ref(),
+                EventSignature::genesis(),
+                events
+                    .iter()
+                    .zip(records.iter().map(|r| r.global_signature.as_str())),
+            )
+            .map_err(EventStoreError::integrity)
+        })
+        .await
+        .map_err(|e| EventStoreError::other(format!("Task join error: {}", e)))?
+    }
+
+    /// Like [`EventStore::stream_all_paged`], but keeps each row's position
+    /// and stored signatures so callers can recompute both chains themselves.
+    /// Returns at most `limit` rows strictly after `after_position`; pass the
+    /// last returned position to fetch the next page. Works without an
+    /// integrity chain configured.
+    pub async fn stream_all_signed(
+        &self,
+        after_position: i64,
+        limit: usize,
+    ) -> EventStoreResult<Vec<SignedEvent>> {
+        let pool = self.pool.clone();
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| {
+                EventStoreError::database(format!("Failed to get connection: {}", e))
+            })?;
+
+            let records: Vec<EventRecord> = events::table
+                .filter(events::id.gt(after_position))
+                .order(events::id.asc())
+                .limit(limit)
+                .load(&mut conn)
+                .map_err(|e| EventStoreError::database(e.to_string()))?;
+
+            records
+                .into_iter()
+                .map(|r| {
+                    Ok(SignedEvent {
+                        position: r.id.unwrap_or_default(),
+                        event: r.to_event()?,
+                        signature: EventSignature(r.signature),
+                        global_signature: EventSignature(r.global_signature),
+                    })
+                })
+                .collect()
+        })
+        .await
+        .map_err(|e| EventStoreError::other(format!("Task join error: {}", e)))?
+    }
+
+    async fn load_checked(
+        &self,
+        aggregate_id: &str,
+        from_sequence: i64,
+        verify: bool,
+    ) -> EventStoreResult<Vec<Event>> {
+        let chain = match (verify, &self.integrity) {
+            (false, _) => None,
+            (true, Some(chain)) => Some(chain.clone()),
+            (true, None) => {
+                return Err(EventStoreError::other(
+                    "Verifying load requested but no integrity chain is configured",
+                ))
+            }
+        };
+        let aggregate_id = aggregate_id.to_string();
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| {
+                EventStoreError::database(format!("Failed to get connection: {}", e))
+            })?;
+
+            // When verifying a tail, also fetch the row before it: its
+            // signature anchors the chain for the first returned event.
+            let query_from = if chain.is_some() {
+                (from_sequence - 1).max(1)
+            } else {
+                from_sequence
+            };
+            let records: Vec<EventRecord> = events::table
+                .filter(events::aggregate_id.eq(&aggregate_id))
+                .filter(events::sequence.ge(query_from))
+                .order(events::sequence.asc())
+                .load(&mut conn)
+                .map_err(|e| EventStoreError::database(e.to_string()))?;
+
+            let events: Vec<Event> = records
+                .iter()
+                .map(|r| r.to_event())
+                .collect::<EventStoreResult<_>>()?;
+
+            let Some(chain) = chain else {
+                return Ok(events);
+            };
+
+            let split = records
+                .iter()
+                .position(|r| r.sequence >= from_sequence)
+                .unwrap_or(records.len());
+            let anchor = match split {
+                0 => EventSignature::genesis(),
+                n => EventSignature(records[n - 1].signature.clone()),
+            };
+            verify_rows(
+                chain.as_ref(),
+                anchor,
+                events[split..]
+                    .iter()
+                    .zip(records[split..].iter().map(|r| r.signature.as_str())),
+            )
+            .map_err(EventStoreError::integrity)?;
+
+            Ok(events.into_iter().skip(split).collect())
+        })
+        .await
+        .map_err(|e| EventStoreError::other(format!("Task join error: {}", e)))?
+    }
 }
 
 #[async_trait]
@@ -195,8 +558,19 @@ impl EventStore for SqliteEventStore {
         version_check: VersionCheck,
         new_events: Vec<Event>,
     ) -> EventStoreResult<()> {
+        self.append_returning(aggregate_id, version_check, new_events)
+            .await
+            .map(drop)
+    }
+
+    async fn append_returning(
+        &self,
+        aggregate_id: &str,
+        version_check: VersionCheck,
+        new_events: Vec<Event>,
+    ) -> EventStoreResult<Vec<Event>> {
         if new_events.is_empty() {
-            return Ok(());
+            return Ok(Vec::new());
         }
 
         // Defense-in-depth: reject any event with invalid audit before touching the DB.
@@ -204,8 +578,10 @@ impl EventStore for SqliteEventStore {
 
         let aggregate_id = aggregate_id.to_string();
         let pool = self.pool.clone();
+        let integrity = self.integrity.clone();
+        let outbox = self.outbox;
 
-        tokio::task::spawn_blocking(move || -> EventStoreResult<()> {
+        tokio::task::spawn_blocking(move || -> EventStoreResult<Vec<Event>> {
             use diesel::connection::AnsiTransactionManager;
             use diesel::connection::TransactionManager;
 
@@ -216,7 +592,7 @@ impl EventStore for SqliteEventStore {
             AnsiTransactionManager::begin_transaction(&mut *conn)
                 .map_err(|e| EventStoreError::database(e.to_string()))?;
 
-            let result = (|| -> EventStoreResult<()> {
+            let result = (|| -> EventStoreResult<Vec<Event>> {
                 let current_version = events::table
                     .filter(events::aggregate_id.eq(&aggregate_id))
                     .select(diesel::dsl::max(events::sequence))
@@ -234,8 +610,7 @@ impl EventStore for SqliteEventStore {
                     }
                 }
 
-                let mut expected_sequence = current_version + 1;
-                for event in &new_events {
+                for (expected_sequence, event) in (current_version + 1..).zip(&new_events) {
                     if event.sequence != expected_sequence {
                         return Err(EventStoreError::InvalidSequence {
                             aggregate_id: aggregate_id.clone(),
@@ -243,25 +618,76 @@ impl EventStore for SqliteEventStore {
                             actual: event.sequence,
                         });
                     }
-                    expected_sequence += 1;
                 }
 
+                // Chain heads are read inside the transaction, so a
+                // concurrent writer cannot fork either chain.
+                let mut heads = match &integrity {
+                    Some(_) => {
+                        let aggregate_head: Option<String> = events::table
+                            .filter(events::aggregate_id.eq(&aggregate_id))
+                            .order(events::sequence.desc())
+                            .select(events::signature)
+                            .first(&mut *conn)
+                            .optional()
+                            .map_err(|e| EventStoreError::database(e.to_string()))?;
+                        let global_head: Option<String> = events::table
+                            .order(events::id.desc())
+                            .select(events::global_signature)
+                            .first(&mut *conn)
+                            .optional()
+                            .map_err(|e| EventStoreError::database(e.to_string()))?;
+                        Some((
+                            EventSignature(aggregate_head.unwrap_or_default()),
+                            EventSignature(global_head.unwrap_or_default()),
+                        ))
+                    }
+                    None => None,
+                };
+
+                let mut stored_events = Vec::with_capacity(new_events.len());
                 for event in &new_events {
-                    let record = NewEventRecord::from_event(event)?;
-                    diesel::insert_into(events::table)
+                    let mut record = NewEventRecord::from_event(event)?;
+                    let mut stored = record.as_stored(event);
+                    if let (Some(chain), Some((aggregate_head, global_head))) =
+                        (&integrity, heads.as_mut())
+                    {
+                        let sign_failed = |e: IntegrityError| EventStoreError::integrity(e);
+                        *aggregate_head = chain
+                            .sign_event(aggregate_head, &stored)
+                            .map_err(sign_failed)?;
+                        *global_head = chain
+                            .sign_event(global_head, &stored)
+                            .map_err(sign_failed)?;
+                        record.signature = aggregate_head.as_str().to_string();
+                        record.global_signature = global_head.as_str().to_string();
+                    }
+                    let id: Option<i64> = diesel::insert_into(events::table)
                         .values(&record)
-                        .execute(&mut *conn)
+                        .returning(events::id)
+                        .get_result(&mut *conn)
                         .map_err(|e| EventStoreError::database(e.to_string()))?;
+                    stored.position = id.map(GlobalPosition);
+                    if let (true, Some(position)) = (outbox, id) {
+                        diesel::insert_into(event_outbox::table)
+                            .values((
+                                event_outbox::position.eq(position),
+                                event_outbox::created_at_us.eq(now_us()),
+                            ))
+                            .execute(&mut *conn)
+                            .map_err(|e| EventStoreError::database(e.to_string()))?;
+                    }
+                    stored_events.push(stored);
                 }
 
-                Ok(())
+                Ok(stored_events)
             })();
 
             match result {
-                Ok(_) => {
+                Ok(stored_events) => {
                     AnsiTransactionManager::commit_transaction(&mut *conn)
                         .map_err(|e| EventStoreError::database(e.to_string()))?;
-                    Ok(())
+                    Ok(stored_events)
                 }
                 Err(e) => {
                     let _ = AnsiTransactionManager::rollback_transaction(&mut *conn);
@@ -282,8 +708,22 @@ impl EventStore for SqliteEventStore {
         aggregate_id: &str,
         from_sequence: i64,
     ) -> EventStoreResult<Vec<Event>> {
-        let aggregate_id = aggregate_id.to_string();
+        let verify = self.verify_on_load && self.integrity.is_some();
+        self.load_checked(aggregate_id, from_sequence, verify).await
+    }
+
+    async fn stream_all(&self, from_position: i64) -> EventStoreResult<Vec<Event>> {
+        self.stream_all_paged(GlobalPosition(from_position), usize::MAX)
+            .await
+    }
+
+    async fn stream_all_paged(
+        &self,
+        from: GlobalPosition,
+        limit: usize,
+    ) -> EventStoreResult<Vec<Event>> {
         let pool = self.pool.clone();
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
 
         tokio::task::spawn_blocking(move || {
             let mut conn = pool.get().map_err(|e| {
@@ -291,9 +731,9 @@ impl EventStore for SqliteEventStore {
             })?;
 
             let records: Vec<EventRecord> = events::table
-                .filter(events::aggregate_id.eq(&aggregate_id))
-                .filter(events::sequence.ge(from_sequence))
-                .order(events::sequence.asc())
+                .filter(events::id.ge(from.value()))
+                .order(events::id.asc())
+                .limit(limit)
                 .load(&mut conn)
                 .map_err(|e| EventStoreError::database(e.to_string()))?;
 
@@ -303,7 +743,8 @@ impl EventStore for SqliteEventStore {
         .map_err(|e| EventStoreError::other(format!("Task join error: {}", e)))?
     }
 
-    async fn stream_all(&self, from_position: i64) -> EventStoreResult<Vec<Event>> {
+    async fn get_version(&self, aggregate_id: &str) -> EventStoreResult<i64> {
+        let aggregate_id = aggregate_id.to_string();
         let pool = self.pool.clone();
 
         tokio::task::spawn_blocking(move || {
@@ -311,19 +752,40 @@ impl EventStore for SqliteEventStore {
                 EventStoreError::database(format!("Failed to get connection: {}", e))
             })?;
 
-            let records: Vec<EventRecord> = events::table
-                .filter(events::id.ge(from_position as i32))
-                .order(events::id.asc())
-                .load(&mut conn)
+            let version = events::table
+                .filter(events::aggregate_id.eq(&aggregate_id))
+                .select(diesel::dsl::max(events::sequence))
+                .first::<Option<i64>>(&mut conn)
+                .map_err(|e| EventStoreError::database(e.to_string()))?
+                .unwrap_or(0);
+
+            Ok(version)
+        })
+        .await
+        .map_err(|e| EventStoreError::other(format!("Task join error: {}", e)))?
+    }
+
+    async fn save_snapshot(&self, snapshot: &Snapshot) -> EventStoreResult<()> {
+        let record = SnapshotRecord::from_snapshot(snapshot)?;
+        let pool = self.pool.clone();
+
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| {
+                EventStoreError::database(format!("Failed to get connection: {}", e))
+            })?;
+
+            diesel::replace_into(snapshots::table)
+                .values(&record)
+                .execute(&mut conn)
                 .map_err(|e| EventStoreError::database(e.to_string()))?;
 
-            records.iter().map(|r| r.to_event()).collect()
+            Ok(())
         })
         .await
         .map_err(|e| EventStoreError::other(format!("Task join error: {}", e)))?
     }
 
-    async fn get_version(&self, aggregate_id: &str) -> EventStoreResult<i64> {
+    async fn load_snapshot(&self, aggregate_id: &str) -> EventStoreResult<Option<Snapshot>> {
         let aggregate_id = aggregate_id.to_string();
         let pool = self.pool.clone();
 
@@ -332,14 +794,13 @@ impl EventStore for SqliteEventStore {
                 EventStoreError::database(format!("Failed to get connection: {}", e))
             })?;
 
-            let version = events::table
-                .filter(events::aggregate_id.eq(&aggregate_id))
-                .select(diesel::dsl::max(events::sequence))
-                .first::<Option<i64>>(&mut conn)
-                .map_err(|e| EventStoreError::database(e.to_string()))?
-                .unwrap_or(0);
+            let record: Option<SnapshotRecord> = snapshots::table
+                .filter(snapshots::aggregate_id.eq(&aggregate_id))
+                .first(&mut conn)
+                .optional()
+                .map_err(|e| EventStoreError::database(e.to_string()))?;
 
-            Ok(version)
+            record.map(|r| r.to_snapshot()).transpose()
         })
         .await
         .map_err(|e| EventStoreError::other(format!("Task join error: {}", e)))?
@@ -565,6 +1026,38 @@ mod tests {
         assert_eq!(store.stream_all(0).await.unwrap().len(), 4);
     }
 
+    #[tokio::test]
+    async fn test_stream_all_paged_resumes_from_last_position() {
+        let store = setup_test_store().await;
+        for i in 1..=5 {
+            let id = format!("user-{i}");
+            store
+                .append(
+                    &id,
+                    VersionCheck::New,
+                    vec![stamped_event("User", &id, 1, "UserCreated", json!({}))],
+                )
+                .await
+                .unwrap();
+        }
+
+        let first = store
+            .stream_all_paged(GlobalPosition::START, 2)
+            .await
+            .unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].aggregate_id, "user-1");
+        let cursor = first[1].position.expect("loaded events carry a position");
+
+        let second = store.stream_all_paged(cursor.next(), 10).await.unwrap();
+        assert_eq!(second.len(), 3);
+        assert_eq!(second[0].aggregate_id, "user-3");
+        assert!(second[0].position > Some(cursor));
+
+        let loaded = store.load("user-3").await.unwrap();
+        assert_eq!(loaded[0].position, second[0].position);
+    }
+
     #[tokio::test]
     async fn test_empty_aggregate() {
         let store = setup_test_store().await;
@@ -785,6 +1278,20 @@ mod tests {
         assert_eq!(loaded[0].audit.timestamp_utc_us, 1_700_000_000_000_000);
         assert_eq!(loaded[0].audit.correlation_id, Uuid::nil());
         assert!(loaded[0].audit.causation_id.is_none());
+        assert_eq!(loaded[0].schema_version, 1);
+    }
+
+    #[tokio::test]
+    async fn test_schema_version_roundtrips() {
+        let store = setup_test_store().await;
+        let mut event = stamped_event("User", "u-v2", 1, "UserCreated", json!({}));
+        event.schema_version = 2;
+        store
+            .append("u-v2", VersionCheck::New, vec![event])
+            .await
+            .unwrap();
+        assert_eq!(store.load("u-v2").await.unwrap()[0].schema_version, 2);
+        assert_eq!(store.stream_all(0).await.unwrap()[0].schema_version, 2);
     }
 
     #[tokio::test]
@@ -925,4 +1432,254 @@ mod tests {
             assert_eq!(e.sequence, (i + 1) as i64);
         }
     }
+
+    #[tokio::test]
+    async fn test_snapshot_roundtrip_and_replace() {
+        let store = setup_test_store().await;
+        assert!(store.load_snapshot("snap-1").await.unwrap().is_none());
+
+        let first = Snapshot::new("User", "snap-1", 10, 1, json!({"n": 10}));
+        store.save_snapshot(&first).await.unwrap();
+        assert_eq!(store.load_snapshot("snap-1").await.unwrap(), Some(first));
+
+        let second = Snapshot::new("User", "snap-1", 20, 2, json!({"n": 20}));
+        store.save_snapshot(&second).await.unwrap();
+        let loaded = store.load_snapshot("snap-1").await.unwrap().unwrap();
+        assert_eq!(loaded, second);
+        assert!(store.load_snapshot("snap-2").await.unwrap().is_none());
+    }
+
+    fn test_chain() -> Arc<dyn IntegrityChain> {
+        Arc::new(
+            arc_core::integrity::HmacSha256Chain::new(b"012345678901234567890123456789AB".to_vec())
+                .unwrap(),
+        )
+    }
+
+    async fn setup_signed_store() -> SqliteEventStore {
+        setup_test_store().await.with_integrity_chain(test_chain())
+    }
+
+    fn exec_sql(store: &SqliteEventStore, sql: &str) {
+        let mut conn = store.pool.get().unwrap();
+        diesel::sql_query(sql).execute(&mut conn).unwrap();
+    }
+
+    async fn append_signed_stream(store: &SqliteEventStore, agg_id: &str, count: i64) {
+        for seq in 1..=count {
+            let check = if seq == 1 {
+                VersionCheck::New
+            } else {
+                VersionCheck::Expected(seq - 1)
+            };
+            store
+                .append(
+                    agg_id,
+                    check,
+                    vec![stamped_event(
+                        "User",
+                        agg_id,
+                        seq,
+                        "Touched",
+                        json!({ "n": seq }),
+                    )],
+                )
+                .await
+                .unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signed_append_verifies_per_aggregate_and_globally() {
+        let store = setup_signed_store().await;
+        append_signed_stream(&store, "sig-a", 3).await;
+        append_signed_stream(&store, "sig-b", 2).await;
+
+        let mut conn = store.pool.get().unwrap();
+        let sigs: Vec<(String, String)> = events::table
+            .order(events::id.asc())
+            .select((events::signature, events::global_signature))
+            .load(&mut conn)
+            .unwrap();
+        drop(conn);
+        assert_eq!(sigs.len(), 5);
+        assert!(sigs.iter().all(|(s, g)| s.len() == 64 && g.len() == 64));
+        // First row of each chain starts from genesis, so both match there;
+        // sig-b's first row chains differently per-aggregate vs globally.
+        assert_eq!(sigs[0].0, sigs[0].1);
+        assert_ne!(sigs[3].0, sigs[3].1);
+
+        assert_eq!(store.load_verified("sig-a").await.unwrap().len(), 3);
+        assert_eq!(store.load_verified("sig-b").await.unwrap().len(), 2);
+        store.verify_global_chain().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tampered_payload_fails_verifying_load() {
+        let store = setup_signed_store().await;
+        append_signed_stream(&store, "sig-t", 3).await;
+        exec_sql(
+            &store,
+            "UPDATE events SET payload = '{\"n\":99}' WHERE aggregate_id = 'sig-t' AND sequence = 2",
+        );
+
+        // Plain loads are unaffected unless verification is opted into.
+        assert_eq!(store.load("sig-t").await.unwrap().len(), 3);
+
+        let err = store.load_verified("sig-t").await.unwrap_err();
+        assert!(matches!(
+            err,
+            EventStoreError::IntegrityViolation {
+                source: IntegrityError::BrokenAt { sequence: 2, .. }
+            }
+        ));
+
+        let verifying = store.clone().verify_on_load(true);
+        assert!(matches!(
+            verifying.load("sig-t").await.unwrap_err(),
+            EventStoreError::IntegrityViolation { .. }
+        ));
+        assert!(matches!(
+            store.verify_global_chain().await.unwrap_err(),
+            EventStoreError::IntegrityViolation { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_upcast_reads_verify_the_stored_shape() {
+        use arc_core::event::UpcasterRegistry;
+        use arc_core::event_store::UpcastingEventStore;
+
+        let signed = setup_signed_store().await.verify_on_load(true);
+        append_signed_stream(&signed, "sig-u", 2).await;
+        let mut v2 = stamped_event("User", "sig-u", 3, "Touched", json!({ "count": 3 }));
+        v2.schema_version = 2;
+        signed
+            .append("sig-u", VersionCheck::Expected(2), vec![v2])
+            .await
+            .unwrap();
+
+        let store = UpcastingEventStore::new(
+            signed.clone(),
+            UpcasterRegistry::new().with_upcaster("Touched", 1, |mut p: serde_json::Value| {
+                Ok(json!({ "count": p["n"].take() }))
+            }),
+        );
+        let loaded = store.load("sig-u").await.unwrap();
+        assert!(loaded.iter().all(|e| e.schema_version == 2));
+        assert_eq!(loaded[0].payload, json!({ "count": 1 }));
+        assert_eq!(loaded[2].payload, json!({ "count": 3 }));
+
+        // The version is signed: rolling it back to re-run an upcaster breaks
+        // the chain.
+        exec_sql(
+            &signed,
+            "UPDATE events SET schema_version = 1 WHERE aggregate_id = 'sig-u' AND sequence = 3",
+        );
+        assert!(matches!(
+            store.load("sig-u").await.unwrap_err(),
+            EventStoreError::IntegrityViolation { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_verifying_load_from_anchors_on_previous_row() {
+        let store = setup_signed_store().await.verify_on_load(true);
+        append_signed_stream(&store, "sig-f", 4).await;
+
+        let tail = store.load_from("sig-f", 3).await.unwrap();
+        assert_eq!(
+            tail.iter().map(|e| e.sequence).collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+
+        exec_sql(
+            &store,
+            "UPDATE events SET event_type = 'Forged' WHERE aggregate_id = 'sig-f' AND sequence = 4",
+        );
+        assert!(matches!(
+            store.load_from("sig-f", 3).await.unwrap_err(),
+            EventStoreError::IntegrityViolation {
+                source: IntegrityError::BrokenAt { sequence: 4, .. }
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_legacy_genesis_prefix_is_accepted_but_not_after_signed_rows() {
+        // Rows written without a chain carry the genesis marker, like rows
+        // back-filled by the signatures migration.
+        let unsigned = setup_test_store().await;
+        append_signed_stream(&unsigned, "sig-l", 2).await;
+
+        let store = unsigned.clone().with_integrity_chain(test_chain());
+        store
+            .append(
+                "sig-l",
+                VersionCheck::Expected(2),
+                vec![stamped_event("User", "sig-l", 3, "Touched", json!({}))],
+            )
+            .await
+            .unwrap();
+        assert_eq!(store.load_verified("sig-l").await.unwrap().len(), 3);
+        store.verify_global_chain().await.unwrap();
+
+        store
+            .append(
+                "sig-l",
+                VersionCheck::Expected(3),
+                vec![stamped_event("User", "sig-l", 4, "Touched", json!({}))],
+            )
+            .await
+            .unwrap();
+
+        // Blanking a signed row's signature must not pass as legacy: the
+        // next signed row no longer chains.
+        exec_sql(
+            &store,
+            "UPDATE events SET signature = '', global_signature = '' \
+             WHERE aggregate_id = 'sig-l' AND sequence = 3",
+        );
+        assert!(matches!(
+            store.load_verified("sig-l").await.unwrap_err(),
+            EventStoreError::IntegrityViolation {
+                source: IntegrityError::BrokenAt { sequence: 4, .. }
+            }
+        ));
+        assert!(matches!(
+            store.verify_global_chain().await.unwrap_err(),
+            EventStoreError::IntegrityViolation {
+                source: IntegrityError::BrokenAt { sequence: 4, .. }
+            }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_verifying_load_without_chain_is_an_error() {
+        let store = setup_test_store().await;
+        assert!(matches!(
+            store.load_verified("nobody").await.unwrap_err(),
+            EventStoreError::Other { .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stream_all_signed_returns_positions_and_signatures() {
+        let store = setup_signed_store().await;
+        append_signed_stream(&store, "sig-s", 2).await;
+
+        let rows = store.stream_all_signed(0, 10).await.unwrap();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].position < rows[1].position);
+        assert_eq!(rows[1].event.sequence, 2);
+        assert!(!rows[1].signature.is_genesis());
+        assert!(!rows[1].global_signature.is_genesis());
+
+        let first = store.stream_all_signed(0, 1).await.unwrap();
+        assert_eq!(first.len(), 1);
+        let next = store.stream_all_signed(first[0].position, 1).await.unwrap();
+        assert_eq!(next[0].position, rows[1].position);
+        let done = store.stream_all_signed(rows[1].position, 1).await.unwrap();
+        assert!(done.is_empty());
+    }
 }
@@ -0,0 +1,246 @@
+//! [`Outbox`] over the `event_outbox` table written by
+//! [`SqliteEventStore::with_outbox`].
+//!
+//! Rows are keyed by the event's position (`events.id`), so a pending entry
+//! is joined back to its event with a primary-key lookup.
+
+use crate::schema::{event_outbox, events};
+use crate::{now_us, EventRecord, SqliteEventStore};
+use arc_core::event::GlobalPosition;
+use arc_core::event_store::{EventStoreError, EventStoreResult};
+use arc_core::outbox::{Outbox, OutboxEntry, OutboxStatus};
+use async_trait::async_trait;
+use diesel::prelude::*;
+use diesel::sqlite::SqliteConnection;
+use std::collections::HashMap;
+
+impl SqliteEventStore {
+    /// Run `f` on a pooled connection off the async runtime.
+    async fn with_conn<F, T>(&self, f: F) -> EventStoreResult<T>
+    where
+        F: FnOnce(&mut SqliteConnection) -> EventStoreResult<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().map_err(|e| {
+                EventStoreError::database(format!("Failed to get connection: {}", e))
+            })?;
+            f(&mut conn)
+        })
+        .await
+        .map_err(|e| EventStoreError::other(format!("Task join error: {}", e)))?
+    }
+}
+
+fn db_err(e: diesel::result::Error) -> EventStoreError {
+    EventStoreError::database(e.to_string())
+}
+
+#[async_trait]
+impl Outbox for SqliteEventStore {
+    async fn fetch_pending(&self, limit: usize) -> EventStoreResult<Vec<OutboxEntry>> {
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        self.with_conn(move |conn| {
+            let pending: Vec<(i64, i32, Option<String>)> = event_outbox::table
+                .filter(event_outbox::delivered_at_us.is_null())
+                .order(event_outbox::position.asc())
+                .limit(limit)
+                .select((
+                    event_outbox::position,
+                    event_outbox::attempts,
+                    event_outbox::last_error,
+                ))
+                .load(conn)
+                .map_err(db_err)?;
+
+            let positions: Vec<i64> = pending.iter().map(|(p, _, _)| *p).collect();
+            let mut records: HashMap<i64, EventRecord> = events::table
+                .filter(events::id.eq_any(&positions))
+                .load::<EventRecord>(conn)
+                .map_err(db_err)?
+                .into_iter()
+                .filter_map(|r| r.id.map(|id| (id, r)))
+                .collect();
+
+            pending
+                .into_iter()
+                .map(|(position, attempts, last_error)| {
+                    let record = records.remove(&position).ok_or_else(|| {
+                        EventStoreError::database(format!(
+                            "Outbox entry {} has no matching event",
+                            position
+                        ))
+                    })?;
+                    Ok(OutboxEntry {
+                        position: GlobalPosition(position),
+                        event: record.to_event()?,
+                        attempts: attempts.max(0) as u32,
+                        last_error,
+                    })
+                })
+                .collect()
+        })
+        .await
+    }
+
+    async fn mark_delivered(&self, positions: &[GlobalPosition]) -> EventStoreResult<()> {
+        if positions.is_empty() {
+            return Ok(());
+        }
+        let positions: Vec<i64> = positions.iter().map(|p| p.value()).collect();
+        self.with_conn(move |conn| {
+            diesel::update(
+                event_outbox::table
+                    .filter(event_outbox::position.eq_any(&positions))
+                    .filter(event_outbox::delivered_at_us.is_null()),
+            )
+            .set(event_outbox::delivered_at_us.eq(now_us()))
+            .execute(conn)
+            .map_err(db_err)?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn mark_failed(&self, position: GlobalPosition, error: &str) -> EventStoreResult<()> {
+        let error = error.to_string();
+        self.with_conn(move |conn| {
+            diesel::update(event_outbox::table.find(position.value()))
+                .set((
+                    event_outbox::attempts.eq(event_outbox::attempts + 1),
+                    event_outbox::last_error.eq(Some(error)),
+                ))
+                .execute(conn)
+                .map_err(db_err)?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn status(&self) -> EventStoreResult<OutboxStatus> {
+        self.with_conn(|conn| {
+            let pending = event_outbox::table.filter(event_outbox::delivered_at_us.is_null());
+            let count = |n: i64| n.max(0) as u64;
+            Ok(OutboxStatus {
+                pending: count(pending.count().get_result(conn).map_err(db_err)?),
+                failing: count(
+                    pending
+                        .filter(event_outbox::attempts.gt(0))
+                        .count()
+                        .get_result(conn)
+                        .map_err(db_err)?,
+                ),
+                delivered: count(
+                    event_outbox::table
+                        .filter(event_outbox::delivered_at_us.is_not_null())
+                        .count()
+                        .get_result(conn)
+                        .map_err(db_err)?,
+                ),
+                oldest_pending: pending
+                    .select(diesel::dsl::min(event_outbox::position))
+                    .first::<Option<i64>>(conn)
+                    .map_err(db_err)?
+                    .map(GlobalPosition),
+            })
+        })
+        .await
+    }
+
+    async fn prune_delivered(&self, delivered_before_us: i64) -> EventStoreResult<usize> {
+        self.with_conn(move |conn| {
+            diesel::delete(
+                event_outbox::table.filter(event_outbox::delivered_at_us.lt(delivered_before_us)),
+            )
+            .execute(conn)
+            .map_err(db_err)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arc_core::audit::AuditMetadata;
+    use arc_core::event::Event;
+    use arc_core::event_store::{EventStore, VersionCheck};
+    use diesel::r2d2::{ConnectionManager, Pool};
+    use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+    use serde_json::json;
+
+    const MIGRATIONS: EmbeddedMigrations = embed_migrations!("../../migrations");
+
+    fn setup_store() -> SqliteEventStore {
+        let manager = ConnectionManager::<SqliteConnection>::new(":memory:");
+        let pool = Pool::builder().max_size(1).build(manager).expect("pool");
+        let mut conn = pool.get().unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+        drop(conn);
+        SqliteEventStore::with_pool(pool).with_outbox()
+    }
+
+    async fn append(store: &SqliteEventStore, id: &str, count: i64) -> Vec<Event> {
+        let events = (1..=count)
+            .map(|seq| {
+                Event::new("User", id, seq, "Touched", json!({ "n": seq }))
+                    .with_audit(AuditMetadata::test_default())
+            })
+            .collect();
+        store
+            .append_returning(id, VersionCheck::New, events)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_append_queues_events_in_outbox() {
+        let store = setup_store();
+        let stored = append(&store, "u1", 2).await;
+
+        let pending = store.fetch_pending(10).await.unwrap();
+        assert_eq!(pending.len(), 2);
+        assert_eq!(Some(pending[0].position), stored[0].position);
+        assert_eq!(pending[0].event.event_id, stored[0].event_id);
+        assert_eq!(pending[1].event.position, stored[1].position);
+        assert_eq!(pending[0].attempts, 0);
+    }
+
+    #[tokio::test]
+    async fn test_outbox_disabled_writes_nothing() {
+        let store = setup_store();
+        let plain = SqliteEventStore {
+            outbox: false,
+            ..store.clone()
+        };
+        append(&plain, "u1", 1).await;
+        assert_eq!(store.status().await.unwrap(), OutboxStatus::default());
+    }
+
+    #[tokio::test]
+    async fn test_failed_then_delivered_bookkeeping() {
+        let store = setup_store();
+        let stored = append(&store, "u1", 3).await;
+        let first = stored[0].position.unwrap();
+
+        store.mark_failed(first, "bus down").await.unwrap();
+        let status = store.status().await.unwrap();
+        assert_eq!(status.pending, 3);
+        assert_eq!(status.failing, 1);
+        assert_eq!(status.oldest_pending, Some(first));
+        let pending = store.fetch_pending(1).await.unwrap();
+        assert_eq!(pending[0].attempts, 1);
+        assert_eq!(pending[0].last_error.as_deref(), Some("bus down"));
+
+        let all: Vec<_> = stored.iter().filter_map(|e| e.position).collect();
+        store.mark_delivered(&all).await.unwrap();
+        let status = store.status().await.unwrap();
+        assert_eq!(status.pending, 0);
+        assert_eq!(status.delivered, 3);
+        assert!(store.fetch_pending(10).await.unwrap().is_empty());
+
+        assert_eq!(store.prune_delivered(i64::MAX).await.unwrap(), 3);
+        assert_eq!(store.status().await.unwrap().delivered, 0);
+    }
+}
@@ -0,0 +1,301 @@
+//! SQLite-backed [`ProcessStore`] for process managers.
+//!
+//! One row per `(process_name, instance_id)` in `process_instances`. The
+//! manager's state and the handled event ids are stored as JSON text.
+//! `save` inserts version 1 and otherwise updates only the row still at
+//! `version - 1`, so of two racing steps on one instance exactly one wins and
+//! the other sees [`ProcessStoreError::Conflict`].
+
+use arc_core::process_manager::{ProcessInstance, ProcessStatus, ProcessStore, ProcessStoreError};
+use async_trait::async_trait;
+use diesel::prelude::*;
+use diesel::r2d2::{self, ConnectionManager};
+use diesel::sqlite::SqliteConnection;
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod schema {
+    diesel::table! {
+        process_instances (process_name, instance_id) {
+            process_name -> Text,
+            instance_id -> Text,
+            correlation_id -> Text,
+            status -> Text,
+            state -> Text,
+            version -> BigInt,
+            deadline_us -> Nullable<BigInt>,
+            handled_event_ids -> Text,
+            failure -> Nullable<Text>,
+            created_at_us -> BigInt,
+            updated_at_us -> BigInt,
+        }
+    }
+}
+
+use schema::process_instances;
+
+#[derive(Debug, Insertable, Queryable, AsChangeset, Clone)]
+#[diesel(table_name = process_instances, treat_none_as_null = true)]
+struct ProcessRow {
+    process_name: String,
+    instance_id: String,
+    correlation_id: String,
+    status: String,
+    state: String,
+    version: i64,
+    deadline_us: Option<i64>,
+    handled_event_ids: String,
+    failure: Option<String>,
+    created_at_us: i64,
+    updated_at_us: i64,
+}
+
+impl ProcessRow {
+    fn from_instance(instance: &ProcessInstance) -> Result<Self, ProcessStoreError> {
+        let encode = |e: serde_json::Error| ProcessStoreError::Sink(e.to_string());
+        Ok(Self {
+            process_name: instance.process_name.clone(),
+            instance_id: instance.instance_id.clone(),
+            correlation_id: instance.correlation_id.to_string(),
+            status: instance.status.as_str().to_string(),
+            state: serde_json::to_string(&instance.state).map_err(encode)?,
+            version: instance.version,
+            deadline_us: instance.deadline_us,
+            handled_event_ids: serde_json::to_string(&instance.handled_event_ids)
+                .map_err(encode)?,
+            failure: instance.failure.clone(),
+            created_at_us: instance.created_at_us,
+            updated_at_us: instance.updated_at_us,
+        })
+    }
+
+    fn into_instance(self) -> Result<ProcessInstance, ProcessStoreError> {
+        let malformed = |column: &str, e: String| {
+            ProcessStoreError::Sink(format!("malformed {column} in DB row: {e}"))
+        };
+        Ok(ProcessInstance {
+            correlation_id: Uuid::parse_str(&self.correlation_id)
+                .map_err(|e| malformed("correlation_id", e.to_string()))?,
+            status: ProcessStatus::parse(&self.status)
+                .ok_or_else(|| malformed("status", self.status.clone()))?,
+            state: serde_json::from_str(&self.state)
+                .map_err(|e| malformed("state", e.to_string()))?,
+            handled_event_ids: serde_json::from_str(&self.handled_event_ids)
+                .map_err(|e| malformed("handled_event_ids", e.to_string()))?,
+            process_name: self.process_name,
+            instance_id: self.instance_id,
+            version: self.version,
+            deadline_us: self.deadline_us,
+            failure: self.failure,
+            created_at_us: self.created_at_us,
+            updated_at_us: self.updated_at_us,
+        })
+    }
+}
+
+type Pool = r2d2::Pool<ConnectionManager<SqliteConnection>>;
+
+/// Durable process instance store backed by SQLite.
+#[derive(Clone)]
+pub struct SqliteProcessStore {
+    pool: Arc<Pool>,
+}
+
+impl SqliteProcessStore {
+    pub async fn new(database_url: &str) -> Result<Self, ProcessStoreError> {
+        let manager = ConnectionManager::<SqliteConnection>::new(database_url);
+        let pool = Pool::builder()
+            .max_size(10)
+            .build(manager)
+            .map_err(|e| ProcessStoreError::Sink(format!("failed to create pool: {e}")))?;
+        Ok(Self {
+            pool: Arc::new(pool),
+        })
+    }
+
+    pub fn with_pool(pool: Pool) -> Self {
+        Self {
+            pool: Arc::new(pool),
+        }
+    }
+}
+
+async fn run_blocking<F, T>(f: F) -> Result<T, ProcessStoreError>
+where
+    F: FnOnce() -> Result<T, ProcessStoreError> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| ProcessStoreError::Sink(format!("join error: {e}")))?
+}
+
+#[async_trait]
+impl ProcessStore for SqliteProcessStore {
+    async fn load(
+        &self,
+        process_name: &str,
+        instance_id: &str,
+    ) -> Result<Option<ProcessInstance>, ProcessStoreError> {
+        let (process_name, instance_id) = (process_name.to_string(), instance_id.to_string());
+        let pool = self.pool.clone();
+
+        run_blocking(move || {
+            let mut conn = pool
+                .get()
+                .map_err(|e| ProcessStoreError::Sink(format!("conn: {e}")))?;
+            let row: Option<ProcessRow> = process_instances::table
+                .filter(process_instances::process_name.eq(&process_name))
+                .filter(process_instances::instance_id.eq(&instance_id))
+                .first(&mut conn)
+                .optional()
+                .map_err(|e| ProcessStoreError::Sink(e.to_string()))?;
+            row.map(ProcessRow::into_instance).transpose()
+        })
+        .await
+    }
+
+    async fn save(&self, instance: &ProcessInstance) -> Result<(), ProcessStoreError> {
+        instance.validate()?;
+        let row = ProcessRow::from_instance(instance)?;
+        let pool = self.pool.clone();
+
+        run_blocking(move || {
+            let mut conn = pool
+                .get()
+                .map_err(|e| ProcessStoreError::Sink(format!("conn: {e}")))?;
+            let written = if row.version == 1 {
+                diesel::insert_into(process_instances::table)
+                    .values(&row)
+                    .on_conflict_do_nothing()
+                    .execute(&mut conn)
+            } else {
+                diesel::update(
+                    process_instances::table
+                        .filter(process_instances::process_name.eq(&row.process_name))
+                        .filter(process_instances::instance_id.eq(&row.instance_id))
+                        .filter(process_instances::version.eq(row.version - 1)),
+                )
+                .set(&row)
+                .execute(&mut conn)
+            }
+            .map_err(|e| ProcessStoreError::Sink(e.to_string()))?;
+            if written == 0 {
+                return Err(ProcessStoreError::conflict(
+                    row.process_name,
+                    row.instance_id,
+                ));
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    async fn due(
+        &self,
+        process_name: &str,
+        now_us: i64,
+        limit: usize,
+    ) -> Result<Vec<ProcessInstance>, ProcessStoreError> {
+        let process_name = process_name.to_string();
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let pool = self.pool.clone();
+
+        run_blocking(move || {
+            let mut conn = pool
+                .get()
+                .map_err(|e| ProcessStoreError::Sink(format!("conn: {e}")))?;
+            let rows: Vec<ProcessRow> = process_instances::table
+                .filter(process_instances::process_name.eq(&process_name))
+                .filter(process_instances::status.eq(ProcessStatus::Running.as_str()))
+                .filter(process_instances::deadline_us.le(now_us))
+                .order(process_instances::deadline_us.asc())
+                .limit(limit)
+                .load(&mut conn)
+                .map_err(|e| ProcessStoreError::Sink(e.to_string()))?;
+            rows.into_iter().map(ProcessRow::into_instance).collect()
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+    use serde_json::json;
+
+    const MIGRATIONS: EmbeddedMigrations = embed_migrations!("../../migrations");
+    const NOW: i64 = 1_700_000_000_000_000;
+
+    async fn setup_store() -> SqliteProcessStore {
+        let manager = ConnectionManager::<SqliteConnection>::new(":memory:");
+        let pool = Pool::builder().max_size(1).build(manager).expect("pool");
+        let mut conn = pool.get().unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+        drop(conn);
+        SqliteProcessStore::with_pool(pool)
+    }
+
+    fn instance(id: &str, deadline_us: Option<i64>) -> ProcessInstance {
+        let mut instance = ProcessInstance::start("onboarding", id, Uuid::new_v4(), NOW);
+        instance.version = 1;
+        instance.state = json!({ "user_id": id });
+        instance.deadline_us = deadline_us;
+        instance.handled_event_ids = vec![Uuid::new_v4()];
+        instance
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load_roundtrip() {
+        let s = setup_store().await;
+        let mut i = instance("u1", Some(NOW + 10));
+        s.save(&i).await.unwrap();
+        assert_eq!(s.load("onboarding", "u1").await.unwrap(), Some(i.clone()));
+        assert_eq!(s.load("other", "u1").await.unwrap(), None);
+
+        i.version = 2;
+        i.status = ProcessStatus::Compensated;
+        i.deadline_us = None;
+        i.failure = Some("process timed out".into());
+        s.save(&i).await.unwrap();
+        assert_eq!(s.load("onboarding", "u1").await.unwrap(), Some(i));
+    }
+
+    #[tokio::test]
+    async fn test_stale_saves_conflict() {
+        let s = setup_store().await;
+        let mut i = instance("u1", None);
+        s.save(&i).await.unwrap();
+        assert!(matches!(
+            s.save(&i).await.unwrap_err(),
+            ProcessStoreError::Conflict { .. }
+        ));
+
+        i.version = 3;
+        assert!(matches!(
+            s.save(&i).await.unwrap_err(),
+            ProcessStoreError::Conflict { .. }
+        ));
+        i.version = 2;
+        s.save(&i).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_due_returns_running_instances_past_their_deadline() {
+        let s = setup_store().await;
+        s.save(&instance("late", Some(NOW))).await.unwrap();
+        s.save(&instance("early", Some(NOW - 5))).await.unwrap();
+        s.save(&instance("future", Some(NOW + 5))).await.unwrap();
+        s.save(&instance("idle", None)).await.unwrap();
+        let mut done = instance("done", Some(NOW - 10));
+        done.status = ProcessStatus::Completed;
+        s.save(&done).await.unwrap();
+
+        let due = s.due("onboarding", NOW, 10).await.unwrap();
+        let ids: Vec<_> = due.iter().map(|i| i.instance_id.as_str()).collect();
+        assert_eq!(ids, ["early", "late"]);
+        assert_eq!(s.due("onboarding", NOW, 1).await.unwrap().len(), 1);
+        assert!(s.due("other", NOW, 10).await.unwrap().is_empty());
+    }
+}
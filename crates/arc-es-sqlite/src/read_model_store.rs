@@ -22,24 +22,48 @@
 //! delivery: applying an older event twice, or out-of-order, never regresses
 //! state.
 //!
+//! [`apply_batch`](ReadModelStore::apply_batch) runs the same statements in
+//! one `BEGIN IMMEDIATE` transaction. Besides atomicity that saves a commit
+//! (and its fsync) per row, which is what makes page-at-a-time rebuilds
+//! fast.
+//!
 //! ## Queries
 //!
 //! [`find_by`](ReadModelStore::find_by) uses `json_extract(data, '$.{field}')`
 //! to reach into the blob. Columns commonly queried (e.g. `email`) get
 //! expression indexes in the migration that creates the table.
 //!
+//! [`query`](ReadModelStore::query) and [`count`](ReadModelStore::count)
+//! translate a [`Query`] to one statement: each filter becomes a comparison
+//! on `json_extract(data, '$.{field}')`, the ordering an `ORDER BY` on the
+//! same expressions plus `id`, and a cursor a keyset condition. Field names
+//! pass `check_ident` and are spliced as literal paths, so filters and sorts
+//! can use the same expression indexes; values are always bound.
+//!
 //! ## Boundaries
 //!
-//! The store does **not** create tables — DDL lives in `migrations/`. That
-//! keeps the production write path away from connection-time mutations and
-//! lets `diesel migration run` control schema evolution.
+//! Reads and writes never touch DDL. Tables and indexes come either from
+//! `migrations/` or from a projector's declared
+//! [`ReadModelSchema`], which [`ensure_schema`](ReadModelStore::ensure_schema)
+//! applies once at startup: it creates the table in the standard shape and
+//! reconciles every `idx_{table}_*` index with the declaration, in one
+//! transaction. Indexes named otherwise are left to their migrations.
+//!
+//! [`swap_tables`](ReadModelStore::swap_tables) renames tables in one
+//! transaction for blue/green rebuilds. SQLite cannot rename an index, so
+//! the declared indexes of both tables are dropped and rebuilt on the new
+//! live table before commit; the retired table keeps none. Readers go on
+//! seeing the old table until the swap commits.
 
-use arc_core::read_model_store::{ReadModelError, ReadModelResult, ReadModelStore, Row, Upsert};
+use arc_core::read_model_query::{Direction, Page, Predicate, Query};
+use arc_core::read_model_store::{
+    ReadModelError, ReadModelResult, ReadModelSchema, ReadModelStore, Row, Upsert, WriteOp,
+};
 use async_trait::async_trait;
 use diesel::prelude::*;
 use diesel::r2d2::{self, ConnectionManager};
-use diesel::sql_types::{BigInt, Nullable, Text};
-use diesel::sqlite::SqliteConnection;
+use diesel::sql_types::{BigInt, Double, Nullable, Text};
+use diesel::sqlite::{Sqlite, SqliteConnection};
 use std::sync::Arc;
 
 type Pool = r2d2::Pool<ConnectionManager<SqliteConnection>>;
@@ -71,12 +95,58 @@ impl SqliteReadModelStore {
     }
 }
 
+impl SqliteReadModelStore {
+    /// Run a write on a pooled connection off the async runtime.
+    async fn write<F>(&self, f: F) -> ReadModelResult<()>
+    where
+        F: FnOnce(&mut SqliteConnection) -> QueryResult<()> + Send + 'static,
+    {
+        let pool = self.pool.clone();
+        tokio::task::spawn_blocking(move || -> ReadModelResult<()> {
+            let mut conn = pool.get().map_err(|e| {
+                ReadModelError::write_failed(format!("Failed to get connection: {e}"))
+            })?;
+            f(&mut conn).map_err(|e| ReadModelError::write_failed(e.to_string()))
+        })
+        .await
+        .map_err(|e| ReadModelError::other(format!("Task join error: {e}")))?
+    }
+}
+
 #[derive(QueryableByName, Debug)]
 struct DataRow {
     #[diesel(sql_type = Text)]
     data: String,
 }
 
+#[derive(QueryableByName, Debug)]
+struct KeyedDataRow {
+    #[diesel(sql_type = Text)]
+    id: String,
+    #[diesel(sql_type = Text)]
+    data: String,
+}
+
+#[derive(QueryableByName, Debug)]
+struct IndexRow {
+    #[diesel(sql_type = Text)]
+    name: String,
+    #[diesel(sql_type = Text)]
+    sql: String,
+}
+
+#[derive(QueryableByName, Debug)]
+struct TableRow {
+    #[diesel(sql_type = Text)]
+    name: String,
+}
+
+#[derive(QueryableByName, Debug)]
+struct CountRow {
+    #[diesel(sql_type = BigInt)]
+    n: i64,
+}
+
 /// Validate a table or column name against an allow-list of characters before
 /// splicing it into a SQL string. Diesel's `sql_query` does not bind
 /// identifiers, only values. This guards against caller-supplied identifiers
@@ -102,64 +172,298 @@ fn extract_version(row: &Row) -> ReadModelResult<i64> {
     })
 }
 
+/// A [`WriteOp`] checked and serialized, ready to run on a connection.
+enum PreparedWrite {
+    Upsert {
+        table: String,
+        key: String,
+        version: i64,
+        data: String,
+    },
+    Delete {
+        table: String,
+        key: String,
+    },
+}
+
+impl PreparedWrite {
+    fn new(op: WriteOp) -> ReadModelResult<Self> {
+        match op {
+            WriteOp::Upsert(op) => {
+                check_ident("table name", &op.table)?;
+                let version = extract_version(&op.row)?;
+                let data = serde_json::to_string(&op.row).map_err(|e| {
+                    ReadModelError::write_failed(format!("Failed to serialize row: {e}"))
+                })?;
+                Ok(PreparedWrite::Upsert {
+                    table: op.table,
+                    key: op.key,
+                    version,
+                    data,
+                })
+            }
+            WriteOp::Delete { table, key } => {
+                check_ident("table name", &table)?;
+                Ok(PreparedWrite::Delete { table, key })
+            }
+        }
+    }
+
+    fn execute(self, conn: &mut SqliteConnection) -> QueryResult<usize> {
+        match self {
+            PreparedWrite::Upsert {
+                table,
+                key,
+                version,
+                data,
+            } => diesel::sql_query(format!(
+                "INSERT INTO {table} (id, version, data) VALUES (?, ?, ?) \
+                 ON CONFLICT(id) DO UPDATE SET version = excluded.version, data = excluded.data \
+                 WHERE {table}.version < excluded.version"
+            ))
+            .bind::<Text, _>(key)
+            .bind::<BigInt, _>(version)
+            .bind::<Text, _>(data)
+            .execute(conn),
+            PreparedWrite::Delete { table, key } => {
+                diesel::sql_query(format!("DELETE FROM {table} WHERE id = ?"))
+                    .bind::<Text, _>(key)
+                    .execute(conn)
+            }
+        }
+    }
+}
+
 fn parse_data_row(raw: &str) -> ReadModelResult<Row> {
     serde_json::from_str(raw).map_err(|e| {
         ReadModelError::query_failed(format!("Failed to parse projection row JSON: {e}"))
     })
 }
 
-#[async_trait]
-impl ReadModelStore for SqliteReadModelStore {
-    async fn upsert(&self, op: Upsert) -> ReadModelResult<()> {
-        check_ident("table name", &op.table)?;
-        let version = extract_version(&op.row)?;
-        let data = serde_json::to_string(&op.row)
-            .map_err(|e| ReadModelError::write_failed(format!("Failed to serialize row: {e}")))?;
-        let pool = self.pool.clone();
-        let table = op.table.clone();
-        let key = op.key.clone();
+/// A value bound into a translated [`Query`], typed the way `json_extract`
+/// returns it so comparisons match the in-memory semantics.
+enum Bind {
+    Text(String),
+    Int(i64),
+    Float(f64),
+    Null,
+}
 
-        tokio::task::spawn_blocking(move || -> ReadModelResult<()> {
-            let mut conn = pool.get().map_err(|e| {
-                ReadModelError::write_failed(format!("Failed to get connection: {e}"))
-            })?;
+impl Bind {
+    fn from_json(value: &serde_json::Value) -> Self {
+        match value {
+            serde_json::Value::Null => Bind::Null,
+            serde_json::Value::Bool(b) => Bind::Int(i64::from(*b)),
+            serde_json::Value::Number(n) => match n.as_i64() {
+                Some(i) => Bind::Int(i),
+                None => Bind::Float(n.as_f64().unwrap_or(f64::NAN)),
+            },
+            serde_json::Value::String(s) => Bind::Text(s.clone()),
+            other => Bind::Text(other.to_string()),
+        }
+    }
+}
+
+/// `json_extract` of a top-level field. The path is spliced rather than
+/// bound so the planner can match expression indexes on it.
+fn field_expr(field: &str) -> ReadModelResult<String> {
+    check_ident("field name", field)?;
+    Ok(format!("json_extract(data, '$.{field}')"))
+}
+
+/// `WHERE` conditions for the query's filters, plus its cursor when
+/// `with_cursor` is set. Pushes the values to bind, in order.
+fn where_clause(
+    query: &Query,
+    with_cursor: bool,
+    binds: &mut Vec<Bind>,
+) -> ReadModelResult<String> {
+    let mut conditions = Vec::new();
+    for filter in &query.filters {
+        let x = field_expr(&filter.field)?;
+        let mut bind = |v: &serde_json::Value| binds.push(Bind::from_json(v));
+        conditions.push(match &filter.predicate {
+            Predicate::Eq(v) => {
+                bind(v);
+                format!("{x} IS ?")
+            }
+            Predicate::Ne(v) => {
+                bind(v);
+                format!("{x} IS NOT ?")
+            }
+            Predicate::Lt(v) => {
+                bind(v);
+                format!("{x} < ?")
+            }
+            Predicate::Gt(v) => {
+                bind(v);
+                format!("{x} > ?")
+            }
+            Predicate::In(vs) if vs.is_empty() => "0".to_string(),
+            Predicate::In(vs) => {
+                vs.iter().for_each(&mut bind);
+                format!("{x} IN ({})", vec!["?"; vs.len()].join(", "))
+            }
+            Predicate::Prefix(p) => {
+                binds.push(Bind::Int(p.chars().count() as i64));
+                binds.push(Bind::Text(p.clone()));
+                format!(
+                    "json_type(data, '$.{}') = 'text' AND substr({x}, 1, ?) = ?",
+                    filter.field
+                )
+            }
+        });
+    }
+
+    if let Some(after) = query.after.as_ref().filter(|_| with_cursor) {
+        // Keyset: rows equal to the cursor on the first i sort keys and
+        // strictly after it on key i, for every i, with `id` as the last key.
+        // SQLite sorts NULL first, so "after NULL" ascending is any non-null
+        // value and nothing is after a non-null value descending but NULL.
+        let mut alternatives = Vec::new();
+        for i in 0..=query.order.len() {
+            let mut parts = Vec::new();
+            for (o, v) in query.order.iter().zip(&after.values).take(i) {
+                parts.push(format!("{} IS ?", field_expr(&o.field)?));
+                binds.push(Bind::from_json(v));
+            }
+            match query.order.get(i) {
+                Some(o) => {
+                    let x = field_expr(&o.field)?;
+                    let v = &after.values[i];
+                    parts.push(match (o.direction, v.is_null()) {
+                        (Direction::Asc, true) => format!("{x} IS NOT NULL"),
+                        (Direction::Asc, false) => {
+                            binds.push(Bind::from_json(v));
+                            format!("{x} > ?")
+                        }
+                        (Direction::Desc, true) => "0".to_string(),
+                        (Direction::Desc, false) => {
+                            binds.push(Bind::from_json(v));
+                            format!("({x} < ? OR {x} IS NULL)")
+                        }
+                    });
+                }
+                None => {
+                    binds.push(Bind::Text(after.key.clone()));
+                    parts.push("id > ?".to_string());
+                }
+            }
+            alternatives.push(format!("({})", parts.join(" AND ")));
+        }
+        conditions.push(format!("({})", alternatives.join(" OR ")));
+    }
+
+    Ok(if conditions.is_empty() {
+        String::new()
+    } else {
+        format!(" WHERE {}", conditions.join(" AND "))
+    })
+}
 
+/// `CREATE INDEX` statements for the schema's indexes, by index name.
+fn index_statements(schema: &ReadModelSchema) -> ReadModelResult<Vec<(String, String)>> {
+    let table = &schema.table;
+    schema
+        .indexes
+        .iter()
+        .map(|index| {
+            let exprs = index
+                .fields
+                .iter()
+                .map(|f| field_expr(f))
+                .collect::<ReadModelResult<Vec<_>>>()?;
+            let name = index.name(table);
+            let unique = if index.unique { "UNIQUE " } else { "" };
             let sql = format!(
-                "INSERT INTO {table} (id, version, data) VALUES (?, ?, ?) \
-                 ON CONFLICT(id) DO UPDATE SET version = excluded.version, data = excluded.data \
-                 WHERE {table}.version < excluded.version"
+                "CREATE {unique}INDEX {name} ON {table}({})",
+                exprs.join(", ")
             );
-
-            diesel::sql_query(sql)
-                .bind::<Text, _>(key)
-                .bind::<BigInt, _>(version)
-                .bind::<Text, _>(data)
-                .execute(&mut *conn)
-                .map_err(|e| ReadModelError::write_failed(e.to_string()))?;
-            Ok(())
+            Ok((name, sql))
         })
-        .await
-        .map_err(|e| ReadModelError::other(format!("Task join error: {e}")))?
+        .collect()
+}
+
+/// Indexes on `table` with their DDL. Automatic indexes (the primary key)
+/// have no SQL and are left out.
+fn load_indexes(conn: &mut SqliteConnection, table: &str) -> QueryResult<Vec<IndexRow>> {
+    diesel::sql_query(
+        "SELECT name, sql FROM sqlite_master \
+         WHERE type = 'index' AND tbl_name = ? AND sql IS NOT NULL",
+    )
+    .bind::<Text, _>(table)
+    .load(conn)
+}
+
+/// Drop the `idx_{table}_*` indexes [`ensure_schema`] manages on `table`.
+///
+/// [`ensure_schema`]: ReadModelStore::ensure_schema
+fn drop_owned_indexes(conn: &mut SqliteConnection, table: &str) -> QueryResult<()> {
+    let owned = format!("idx_{table}_");
+    for index in load_indexes(conn, table)? {
+        if index.name.starts_with(&owned) {
+            diesel::sql_query(format!("DROP INDEX {}", index.name)).execute(conn)?;
+        }
+    }
+    Ok(())
+}
+
+/// `sqlite_master` keeps DDL as written; compare statements without
+/// whitespace or case so a migration's formatting is not a difference.
+fn same_ddl(a: &str, b: &str) -> bool {
+    let norm = |s: &str| {
+        s.chars()
+            .filter(|c| !c.is_whitespace())
+            .collect::<String>()
+            .to_ascii_lowercase()
+    };
+    norm(a) == norm(b)
+}
+
+fn load_bound<T>(conn: &mut SqliteConnection, sql: String, binds: Vec<Bind>) -> QueryResult<Vec<T>>
+where
+    T: QueryableByName<Sqlite> + 'static,
+{
+    let mut query = diesel::sql_query(sql).into_boxed::<Sqlite>();
+    for bind in binds {
+        query = match bind {
+            Bind::Text(s) => query.bind::<Text, _>(s),
+            Bind::Int(i) => query.bind::<BigInt, _>(i),
+            Bind::Float(f) => query.bind::<Double, _>(f),
+            Bind::Null => query.bind::<Nullable<Text>, _>(None::<String>),
+        };
+    }
+    query.load(conn)
+}
+
+#[async_trait]
+impl ReadModelStore for SqliteReadModelStore {
+    async fn upsert(&self, op: Upsert) -> ReadModelResult<()> {
+        let write = PreparedWrite::new(WriteOp::Upsert(op))?;
+        self.write(move |conn| write.execute(conn).map(drop)).await
     }
 
     async fn delete(&self, table: &str, key: &str) -> ReadModelResult<()> {
-        check_ident("table name", table)?;
-        let pool = self.pool.clone();
-        let table = table.to_string();
-        let key = key.to_string();
+        let write = PreparedWrite::new(WriteOp::delete(table, key))?;
+        self.write(move |conn| write.execute(conn).map(drop)).await
+    }
 
-        tokio::task::spawn_blocking(move || -> ReadModelResult<()> {
-            let mut conn = pool.get().map_err(|e| {
-                ReadModelError::write_failed(format!("Failed to get connection: {e}"))
-            })?;
-            diesel::sql_query(format!("DELETE FROM {table} WHERE id = ?"))
-                .bind::<Text, _>(key)
-                .execute(&mut *conn)
-                .map_err(|e| ReadModelError::write_failed(e.to_string()))?;
-            Ok(())
+    async fn apply_batch(&self, ops: Vec<WriteOp>) -> ReadModelResult<()> {
+        let writes = ops
+            .into_iter()
+            .map(PreparedWrite::new)
+            .collect::<ReadModelResult<Vec<_>>>()?;
+        if writes.is_empty() {
+            return Ok(());
+        }
+        self.write(move |conn| {
+            conn.immediate_transaction(|conn| {
+                writes
+                    .into_iter()
+                    .try_for_each(|write| write.execute(conn).map(drop))
+            })
         })
         .await
-        .map_err(|e| ReadModelError::other(format!("Task join error: {e}")))?
     }
 
     async fn get(&self, table: &str, key: &str) -> ReadModelResult<Option<Row>> {
@@ -252,6 +556,72 @@ impl ReadModelStore for SqliteReadModelStore {
         rows.iter().map(|r| parse_data_row(r)).collect()
     }
 
+    async fn query(&self, table: &str, query: &Query) -> ReadModelResult<Page> {
+        check_ident("table name", table)?;
+        query.validate()?;
+        let mut binds = Vec::new();
+        let mut sql = format!(
+            "SELECT id, data FROM {table}{}",
+            where_clause(query, true, &mut binds)?
+        );
+        let mut order = Vec::new();
+        for o in &query.order {
+            let direction = match o.direction {
+                Direction::Asc => "ASC",
+                Direction::Desc => "DESC",
+            };
+            order.push(format!("{} {direction}", field_expr(&o.field)?));
+        }
+        order.push("id ASC".to_string());
+        sql.push_str(&format!(" ORDER BY {} LIMIT ? OFFSET ?", order.join(", ")));
+        binds.push(Bind::Int(
+            query
+                .limit
+                .map_or(-1, |l| i64::try_from(l).unwrap_or(i64::MAX)),
+        ));
+        binds.push(Bind::Int(i64::try_from(query.offset).unwrap_or(i64::MAX)));
+        let pool = self.pool.clone();
+
+        let rows = tokio::task::spawn_blocking(move || -> ReadModelResult<Vec<KeyedDataRow>> {
+            let mut conn = pool.get().map_err(|e| {
+                ReadModelError::query_failed(format!("Failed to get connection: {e}"))
+            })?;
+            load_bound(&mut conn, sql, binds)
+                .map_err(|e| ReadModelError::query_failed(e.to_string()))
+        })
+        .await
+        .map_err(|e| ReadModelError::other(format!("Task join error: {e}")))??;
+
+        let hits = rows
+            .into_iter()
+            .map(|r| Ok((r.id, parse_data_row(&r.data)?)))
+            .collect::<ReadModelResult<Vec<_>>>()?;
+        Ok(query.page(hits))
+    }
+
+    async fn count(&self, table: &str, query: &Query) -> ReadModelResult<u64> {
+        check_ident("table name", table)?;
+        query.validate()?;
+        let mut binds = Vec::new();
+        let sql = format!(
+            "SELECT COUNT(*) AS n FROM {table}{}",
+            where_clause(query, false, &mut binds)?
+        );
+        let pool = self.pool.clone();
+
+        let rows = tokio::task::spawn_blocking(move || -> ReadModelResult<Vec<CountRow>> {
+            let mut conn = pool.get().map_err(|e| {
+                ReadModelError::query_failed(format!("Failed to get connection: {e}"))
+            })?;
+            load_bound(&mut conn, sql, binds)
+                .map_err(|e| ReadModelError::query_failed(e.to_string()))
+        })
+        .await
+        .map_err(|e| ReadModelError::other(format!("Task join error: {e}")))??;
+
+        Ok(rows.first().map_or(0, |r| r.n.max(0) as u64))
+    }
+
     async fn truncate(&self, table: &str) -> ReadModelResult<()> {
         check_ident("table name", table)?;
         let pool = self.pool.clone();
@@ -269,6 +639,123 @@ impl ReadModelStore for SqliteReadModelStore {
         .await
         .map_err(|e| ReadModelError::other(format!("Task join error: {e}")))?
     }
+
+    async fn ensure_schema(&self, schema: &ReadModelSchema) -> ReadModelResult<()> {
+        check_ident("table name", &schema.table)?;
+        schema.validate()?;
+        let wanted = index_statements(schema)?;
+        let pool = self.pool.clone();
+        let table = schema.table.clone();
+
+        tokio::task::spawn_blocking(move || -> ReadModelResult<()> {
+            let mut conn = pool.get().map_err(|e| {
+                ReadModelError::schema_failed(format!("Failed to get connection: {e}"))
+            })?;
+            conn.immediate_transaction(|conn| {
+                diesel::sql_query(format!(
+                    "CREATE TABLE IF NOT EXISTS {table} (\
+                     id TEXT NOT NULL PRIMARY KEY, \
+                     version BIGINT NOT NULL, \
+                     data TEXT NOT NULL)"
+                ))
+                .execute(conn)?;
+
+                let existing = load_indexes(conn, &table)?;
+
+                let owned = format!("idx_{table}_");
+                for index in existing.iter().filter(|i| i.name.starts_with(&owned)) {
+                    let current = wanted
+                        .iter()
+                        .any(|(name, sql)| *name == index.name && same_ddl(sql, &index.sql));
+                    if !current {
+                        tracing::info!(table = %table, index = %index.name, "Dropping read-model index");
+                        diesel::sql_query(format!("DROP INDEX {}", index.name)).execute(conn)?;
+                    }
+                }
+                for (name, sql) in &wanted {
+                    let present = existing
+                        .iter()
+                        .any(|i| i.name == *name && same_ddl(sql, &i.sql));
+                    if !present {
+                        tracing::info!(table = %table, index = %name, "Creating read-model index");
+                        diesel::sql_query(sql.as_str()).execute(conn)?;
+                    }
+                }
+                Ok(())
+            })
+            .map_err(|e: diesel::result::Error| ReadModelError::schema_failed(e.to_string()))
+        })
+        .await
+        .map_err(|e| ReadModelError::other(format!("Task join error: {e}")))?
+    }
+
+    fn supports_table_swap(&self) -> bool {
+        true
+    }
+
+    async fn swap_tables(
+        &self,
+        schema: &ReadModelSchema,
+        incoming: &str,
+        outgoing: &str,
+    ) -> ReadModelResult<()> {
+        let live = schema.table.clone();
+        for table in [live.as_str(), incoming, outgoing] {
+            check_ident("table name", table)?;
+        }
+        if incoming == live || outgoing == live || incoming == outgoing {
+            return Err(ReadModelError::schema_failed(format!(
+                "cannot swap {incoming} in for {live} leaving it as {outgoing}"
+            )));
+        }
+        schema.validate()?;
+        let wanted = index_statements(schema)?;
+        let pool = self.pool.clone();
+        let incoming = incoming.to_string();
+        let outgoing = outgoing.to_string();
+
+        tokio::task::spawn_blocking(move || -> ReadModelResult<()> {
+            let mut conn = pool.get().map_err(|e| {
+                ReadModelError::schema_failed(format!("Failed to get connection: {e}"))
+            })?;
+            let swapped = conn
+                .immediate_transaction(|conn| {
+                    let tables: Vec<TableRow> = diesel::sql_query(
+                        "SELECT name FROM sqlite_master WHERE type = 'table' AND name IN (?, ?)",
+                    )
+                    .bind::<Text, _>(&incoming)
+                    .bind::<Text, _>(&live)
+                    .load(conn)?;
+                    if !tables.iter().any(|t| t.name == incoming) {
+                        return Ok(false);
+                    }
+
+                    diesel::sql_query(format!("DROP TABLE IF EXISTS {outgoing}")).execute(conn)?;
+                    if tables.iter().any(|t| t.name == live) {
+                        drop_owned_indexes(conn, &live)?;
+                        diesel::sql_query(format!("ALTER TABLE {live} RENAME TO {outgoing}"))
+                            .execute(conn)?;
+                    }
+                    drop_owned_indexes(conn, &incoming)?;
+                    diesel::sql_query(format!("ALTER TABLE {incoming} RENAME TO {live}"))
+                        .execute(conn)?;
+                    for (_, sql) in &wanted {
+                        diesel::sql_query(sql.as_str()).execute(conn)?;
+                    }
+                    Ok(true)
+                })
+                .map_err(|e: diesel::result::Error| ReadModelError::schema_failed(e.to_string()))?;
+            if !swapped {
+                return Err(ReadModelError::schema_failed(format!(
+                    "no table {incoming} to swap in"
+                )));
+            }
+            tracing::info!(table = %live, from = %incoming, retired = %outgoing, "Swapped read-model table");
+            Ok(())
+        })
+        .await
+        .map_err(|e| ReadModelError::other(format!("Task join error: {e}")))?
+    }
 }
 
 #[cfg(test)]
@@ -461,6 +948,390 @@ mod tests {
         );
     }
 
+    async fn seed_query_rows(store: &SqliteReadModelStore) -> Vec<(String, Row)> {
+        let rows = vec![
+            json!({"id": "u1", "name": "Carol", "email": "c@x", "age": 41, "active": true, "version": 1}),
+            json!({"id": "u2", "name": "alice", "email": "a@x", "age": 29, "active": false, "version": 1}),
+            json!({"id": "u3", "name": "Bob", "email": "b@x", "age": 35.5, "active": true, "version": 1}),
+            json!({"id": "u4", "name": "Alina", "email": "d@x", "active": true, "version": 1}),
+            json!({"id": "u5", "name": "Bob", "email": "e@x", "age": 35, "active": true, "version": 1}),
+        ];
+        for row in &rows {
+            let key = row["id"].as_str().unwrap();
+            store
+                .upsert(Upsert::new("users_view", key, row.clone()))
+                .await
+                .unwrap();
+        }
+        rows.into_iter()
+            .map(|r| (r["id"].as_str().unwrap().to_string(), r))
+            .collect()
+    }
+
+    fn ids(page: &Page) -> Vec<String> {
+        page.rows
+            .iter()
+            .map(|r| r["id"].as_str().unwrap().to_string())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_query_agrees_with_in_memory_evaluation() {
+        use arc_core::read_model_query::Filter;
+
+        let store = setup().await;
+        let rows = seed_query_rows(&store).await;
+        let queries = [
+            Query::new(),
+            Query::new().with_filter(Filter::eq("active", true)),
+            Query::new().with_filter(Filter::ne("age", serde_json::Value::Null)),
+            Query::new().with_filter(Filter::eq("age", serde_json::Value::Null)),
+            Query::new().with_filter(Filter::lt("age", 35.5)),
+            Query::new().with_filter(Filter::gt("age", 35)),
+            Query::new().with_filter(Filter::one_of("name", ["Bob", "Alina"])),
+            Query::new().with_filter(Filter::one_of("name", Vec::<String>::new())),
+            Query::new().with_filter(Filter::prefix("name", "Al")),
+            Query::new().with_filter(Filter::prefix("age", "3")),
+            Query::new()
+                .with_filter(Filter::eq("active", true))
+                .with_order("name", Direction::Asc)
+                .with_order("age", Direction::Desc),
+            Query::new().with_order("age", Direction::Asc),
+            Query::new()
+                .with_order("age", Direction::Desc)
+                .with_limit(2)
+                .with_offset(1),
+        ];
+        for query in queries {
+            let expected = query.evaluate(rows.clone()).unwrap();
+            let got = store.query("users_view", &query).await.unwrap();
+            assert_eq!(ids(&got), ids(&expected), "{query:?}");
+            assert_eq!(got.next, expected.next, "{query:?}");
+            assert_eq!(
+                store.count("users_view", &query).await.unwrap(),
+                query.count_matching(rows.iter().map(|(_, r)| r)).unwrap(),
+                "{query:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_cursor_walks_every_row_once() {
+        let store = setup().await;
+        let rows = seed_query_rows(&store).await;
+        for direction in [Direction::Asc, Direction::Desc] {
+            let query = Query::new()
+                .with_order("age", direction)
+                .with_order("name", Direction::Asc)
+                .with_limit(2);
+            let mut seen = Vec::new();
+            let mut page = store.query("users_view", &query).await.unwrap();
+            loop {
+                seen.extend(ids(&page));
+                let Some(next) = page.next.clone() else {
+                    break;
+                };
+                page = store
+                    .query("users_view", &query.clone().with_cursor(next))
+                    .await
+                    .unwrap();
+            }
+            let all = Query::new()
+                .with_order("age", direction)
+                .with_order("name", Direction::Asc);
+            assert_eq!(
+                seen,
+                ids(&all.evaluate(rows.clone()).unwrap()),
+                "{direction:?}"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_rejects_unsafe_field_names() {
+        use arc_core::read_model_query::Filter;
+
+        let store = setup().await;
+        for query in [
+            Query::new().with_filter(Filter::eq("email') OR 1=1 --", "x")),
+            Query::new().with_order("name DESC; DROP TABLE users_view", Direction::Asc),
+        ] {
+            let err = store.query("users_view", &query).await.unwrap_err();
+            assert!(
+                matches!(err, ReadModelError::Other { ref message } if message.contains("field name")),
+                "expected identifier rejection, got {err:?}"
+            );
+        }
+    }
+
+    async fn indexes(store: &SqliteReadModelStore, table: &str) -> Vec<(String, String)> {
+        let pool = store.pool.clone();
+        let table = table.to_string();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = pool.get().unwrap();
+            let rows: Vec<IndexRow> = diesel::sql_query(
+                "SELECT name, sql FROM sqlite_master \
+                 WHERE type = 'index' AND tbl_name = ? AND sql IS NOT NULL ORDER BY name",
+            )
+            .bind::<Text, _>(table)
+            .load(&mut conn)
+            .unwrap();
+            rows.into_iter().map(|r| (r.name, r.sql)).collect()
+        })
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_ensure_schema_creates_table_and_reconciles_indexes() {
+        use arc_core::read_model_store::ReadModelIndex;
+
+        let store = setup().await;
+        let v1 = ReadModelSchema::new("orders_view")
+            .with_index(ReadModelIndex::on(["status"]))
+            .with_index(ReadModelIndex::on(["customer_id", "placed_at"]));
+        store.ensure_schema(&v1).await.unwrap();
+        // Idempotent.
+        store.ensure_schema(&v1).await.unwrap();
+        let names = |ix: Vec<(String, String)>| ix.into_iter().map(|(n, _)| n).collect::<Vec<_>>();
+        assert_eq!(
+            names(indexes(&store, "orders_view").await),
+            [
+                "idx_orders_view_customer_id_placed_at",
+                "idx_orders_view_status"
+            ]
+        );
+
+        store
+            .upsert(Upsert::new(
+                "orders_view",
+                "o1",
+                json!({"id": "o1", "status": "open", "version": 1}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(
+            store
+                .find_by("orders_view", "status", &json!("open"))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Hand-named indexes belong to migrations and survive reconciliation.
+        let pool = store.pool.clone();
+        tokio::task::spawn_blocking(move || {
+            diesel::sql_query("CREATE INDEX orders_by_version ON orders_view(version)")
+                .execute(&mut pool.get().unwrap())
+                .unwrap();
+        })
+        .await
+        .unwrap();
+
+        // `status` turns unique, the compound index goes away.
+        let v2 =
+            ReadModelSchema::new("orders_view").with_index(ReadModelIndex::unique_on(["status"]));
+        store.ensure_schema(&v2).await.unwrap();
+        let ix = indexes(&store, "orders_view").await;
+        assert_eq!(
+            names(ix.clone()),
+            ["idx_orders_view_status", "orders_by_version"]
+        );
+        assert!(ix[0].1.starts_with("CREATE UNIQUE INDEX"), "{ix:?}");
+        let err = store
+            .upsert(Upsert::new(
+                "orders_view",
+                "o2",
+                json!({"id": "o2", "status": "open", "version": 1}),
+            ))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ReadModelError::WriteFailed { .. }), "{err:?}");
+    }
+
+    #[tokio::test]
+    async fn test_ensure_schema_is_atomic_when_an_index_cannot_be_built() {
+        use arc_core::read_model_store::ReadModelIndex;
+
+        let store = setup().await;
+        for id in ["u1", "u2"] {
+            let row = user_row(id, "Same Name", &format!("{id}@b.c"), 1);
+            store
+                .upsert(Upsert::new("users_view", id, row))
+                .await
+                .unwrap();
+        }
+        let before = indexes(&store, "users_view").await;
+
+        // Duplicate names: the unique index fails, and the email index the
+        // schema dropped is restored with the rollback.
+        let schema =
+            ReadModelSchema::new("users_view").with_index(ReadModelIndex::unique_on(["name"]));
+        let err = store.ensure_schema(&schema).await.unwrap_err();
+        assert!(
+            matches!(err, ReadModelError::SchemaFailed { .. }),
+            "{err:?}"
+        );
+        assert_eq!(indexes(&store, "users_view").await, before);
+    }
+
+    #[tokio::test]
+    async fn test_ensure_schema_accepts_migration_written_index() {
+        // The migration's `idx_users_view_email` is formatted differently
+        // from the generated statement but is the same index; it is kept.
+        use arc_core::read_model_store::ReadModelIndex;
+
+        let store = setup().await;
+        let before = indexes(&store, "users_view").await;
+        let schema =
+            ReadModelSchema::new("users_view").with_index(ReadModelIndex::unique_on(["email"]));
+        store.ensure_schema(&schema).await.unwrap();
+        assert_eq!(indexes(&store, "users_view").await, before);
+    }
+
+    #[tokio::test]
+    async fn test_swap_tables_replaces_live_table_and_keeps_old() {
+        use arc_core::read_model_store::{ReadModelIndex, TableRedirect};
+
+        let store = setup().await;
+        store
+            .upsert(Upsert::new(
+                "users_view",
+                "u1",
+                user_row("u1", "Alice", "a@b.c", 1),
+            ))
+            .await
+            .unwrap();
+        let schema =
+            ReadModelSchema::new("users_view").with_index(ReadModelIndex::unique_on(["email"]));
+
+        // Build the replacement beside the live table, as a rebuild does.
+        let shadow = TableRedirect::new(Arc::new(store.clone()), "users_view", "users_view__next");
+        shadow.ensure_schema(&schema).await.unwrap();
+        shadow
+            .upsert(Upsert::new(
+                "users_view",
+                "u2",
+                user_row("u2", "Bob", "b@b.c", 1),
+            ))
+            .await
+            .unwrap();
+        assert!(store.get("users_view", "u2").await.unwrap().is_none());
+        assert_eq!(
+            indexes(&store, "users_view__next").await[0].0,
+            "idx_users_view__next_email"
+        );
+
+        store
+            .swap_tables(&schema, "users_view__next", "users_view__prev")
+            .await
+            .unwrap();
+        let ids = |rows: Vec<Row>| rows.iter().map(|r| r["id"].clone()).collect::<Vec<_>>();
+        assert_eq!(ids(store.list("users_view").await.unwrap()), [json!("u2")]);
+        assert_eq!(
+            ids(store.list("users_view__prev").await.unwrap()),
+            [json!("u1")]
+        );
+        let live = indexes(&store, "users_view").await;
+        assert_eq!(live.len(), 1);
+        assert_eq!(live[0].0, "idx_users_view_email");
+        assert!(indexes(&store, "users_view__prev").await.is_empty());
+        // The new live table is what `ensure_schema` would have built.
+        store.ensure_schema(&schema).await.unwrap();
+        assert_eq!(indexes(&store, "users_view").await, live);
+        let err = store
+            .upsert(Upsert::new(
+                "users_view",
+                "u3",
+                user_row("u3", "Eve", "b@b.c", 1),
+            ))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ReadModelError::WriteFailed { .. }), "{err:?}");
+
+        // The shadow is gone now: nothing to swap, nothing changes.
+        let err = store
+            .swap_tables(&schema, "users_view__next", "users_view__prev")
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, ReadModelError::SchemaFailed { .. }),
+            "{err:?}"
+        );
+        assert_eq!(ids(store.list("users_view").await.unwrap()), [json!("u2")]);
+
+        // Rolling back is the same swap the other way round.
+        store
+            .swap_tables(&schema, "users_view__prev", "users_view__next")
+            .await
+            .unwrap();
+        assert_eq!(ids(store.list("users_view").await.unwrap()), [json!("u1")]);
+        assert_eq!(
+            ids(store.list("users_view__next").await.unwrap()),
+            [json!("u2")]
+        );
+        assert_eq!(indexes(&store, "users_view").await, live);
+    }
+
+    #[tokio::test]
+    async fn test_apply_batch_commits_across_tables_or_not_at_all() {
+        let store = setup().await;
+        store
+            .ensure_schema(&ReadModelSchema::new("members_view"))
+            .await
+            .unwrap();
+        let member = |id: &str, user: &str| json!({"id": id, "user_id": user, "version": 1});
+        store
+            .upsert(Upsert::new(
+                "users_view",
+                "u1",
+                user_row("u1", "Alice", "a@b.c", 2),
+            ))
+            .await
+            .unwrap();
+
+        store
+            .apply_batch(vec![
+                Upsert::new("users_view", "u2", user_row("u2", "Bob", "b@b.c", 1)).into(),
+                Upsert::new("members_view", "m1", member("m1", "u2")).into(),
+                // Gated out inside the batch, as outside it.
+                Upsert::new("users_view", "u1", user_row("u1", "Stale", "a@b.c", 1)).into(),
+                WriteOp::delete("members_view", "missing"),
+            ])
+            .await
+            .unwrap();
+        assert_eq!(store.list("users_view").await.unwrap().len(), 2);
+        assert_eq!(store.list("members_view").await.unwrap().len(), 1);
+        let alice = store.get("users_view", "u1").await.unwrap().unwrap();
+        assert_eq!(alice["name"], "Alice");
+
+        // The email collision fails the batch after the member row was
+        // written; the transaction takes both back.
+        let err = store
+            .apply_batch(vec![
+                Upsert::new("members_view", "m2", member("m2", "u3")).into(),
+                WriteOp::delete("users_view", "u2"),
+                Upsert::new("users_view", "u3", user_row("u3", "Eve", "a@b.c", 1)).into(),
+            ])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ReadModelError::WriteFailed { .. }), "{err:?}");
+        assert!(store.get("members_view", "m2").await.unwrap().is_none());
+        assert!(store.get("users_view", "u2").await.unwrap().is_some());
+
+        // Invalid ops are rejected before anything runs.
+        let err = store
+            .apply_batch(vec![
+                Upsert::new("members_view", "m3", member("m3", "u1")).into(),
+                WriteOp::delete("members_view; DROP TABLE users_view", "x"),
+            ])
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ReadModelError::Other { .. }), "{err:?}");
+        assert!(store.get("members_view", "m3").await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_table_name_validation_rejects_injection() {
         let store = setup().await;
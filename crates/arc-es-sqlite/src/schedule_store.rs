@@ -0,0 +1,407 @@
+//! SQLite-backed [`ScheduleStore`] for the command scheduler.
+//!
+//! One row per job key in `scheduled_commands`; the command payload is
+//! stored as JSON text. `schedule` replaces the row under the key, and
+//! `record` updates only the row still holding the job's `job_id`, so a run
+//! that raced a reschedule cannot overwrite the new job.
+
+use arc_core::scheduler::{JobStatus, ScheduleStore, ScheduledJob, SchedulerError};
+use async_trait::async_trait;
+use diesel::prelude::*;
+use diesel::r2d2::{self, ConnectionManager};
+use diesel::sqlite::SqliteConnection;
+use std::sync::Arc;
+use uuid::Uuid;
+
+mod schema {
+    diesel::table! {
+        scheduled_commands (job_key) {
+            job_key -> Text,
+            job_id -> Text,
+            command_type -> Text,
+            payload -> Text,
+            scheduled_by -> Text,
+            correlation_id -> Text,
+            causation_id -> Nullable<Text>,
+            due_at_us -> BigInt,
+            status -> Text,
+            attempts -> Integer,
+            last_error -> Nullable<Text>,
+            created_at_us -> BigInt,
+            updated_at_us -> BigInt,
+        }
+    }
+}
+
+use schema::scheduled_commands;
+
+#[derive(Debug, Insertable, Queryable, AsChangeset, Clone)]
+#[diesel(table_name = scheduled_commands, treat_none_as_null = true)]
+struct JobRow {
+    job_key: String,
+    job_id: String,
+    command_type: String,
+    payload: String,
+    scheduled_by: String,
+    correlation_id: String,
+    causation_id: Option<String>,
+    due_at_us: i64,
+    status: String,
+    attempts: i32,
+    last_error: Option<String>,
+    created_at_us: i64,
+    updated_at_us: i64,
+}
+
+impl JobRow {
+    fn from_job(job: &ScheduledJob) -> Result<Self, SchedulerError> {
+        Ok(Self {
+            job_key: job.key.clone(),
+            job_id: job.job_id.to_string(),
+            command_type: job.command_type.clone(),
+            payload: serde_json::to_string(&job.payload)
+                .map_err(|e| SchedulerError::Sink(e.to_string()))?,
+            scheduled_by: job.scheduled_by.clone(),
+            correlation_id: job.correlation_id.to_string(),
+            causation_id: job.causation_id.map(|id| id.to_string()),
+            due_at_us: job.due_at_us,
+            status: job.status.as_str().to_string(),
+            attempts: i32::try_from(job.attempts).unwrap_or(i32::MAX),
+            last_error: job.last_error.clone(),
+            created_at_us: job.created_at_us,
+            updated_at_us: job.updated_at_us,
+        })
+    }
+
+    fn into_job(self) -> Result<ScheduledJob, SchedulerError> {
+        let malformed = |column: &str, e: String| {
+            SchedulerError::Sink(format!("malformed {column} in DB row: {e}"))
+        };
+        let uuid = |column: &str, s: &str| {
+            Uuid::parse_str(s).map_err(|e| malformed(column, e.to_string()))
+        };
+        Ok(ScheduledJob {
+            job_id: uuid("job_id", &self.job_id)?,
+            payload: serde_json::from_str(&self.payload)
+                .map_err(|e| malformed("payload", e.to_string()))?,
+            correlation_id: uuid("correlation_id", &self.correlation_id)?,
+            causation_id: self
+                .causation_id
+                .as_deref()
+                .map(|id| uuid("causation_id", id))
+                .transpose()?,
+            status: JobStatus::parse(&self.status)
+                .ok_or_else(|| malformed("status", self.status.clone()))?,
+            attempts: u32::try_from(self.attempts)
+                .map_err(|e| malformed("attempts", e.to_string()))?,
+            key: self.job_key,
+            command_type: self.command_type,
+            scheduled_by: self.scheduled_by,
+            due_at_us: self.due_at_us,
+            last_error: self.last_error,
+            created_at_us: self.created_at_us,
+            updated_at_us: self.updated_at_us,
+        })
+    }
+}
+
+type Pool = r2d2::Pool<ConnectionManager<SqliteConnection>>;
+
+/// Durable scheduled command store backed by SQLite.
+#[derive(Clone)]
+pub struct SqliteScheduleStore {
+    pool: Arc<Pool>,
+}
+
+impl SqliteScheduleStore {
+    pub async fn new(database_url: &str) -> Result<Self, SchedulerError> {
+        let manager = ConnectionManager::<SqliteConnection>::new(database_url);
+        let pool = Pool::builder()
+            .max_size(10)
+            .build(manager)
+            .map_err(|e| SchedulerError::Sink(format!("failed to create pool: {e}")))?;
+        Ok(Self {
+            pool: Arc::new(pool),
+        })
+    }
+
+    pub fn with_pool(pool: Pool) -> Self {
+        Self {
+            pool: Arc::new(pool),
+        }
+    }
+}
+
+async fn run_blocking<F, T>(f: F) -> Result<T, SchedulerError>
+where
+    F: FnOnce() -> Result<T, SchedulerError> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| SchedulerError::Sink(format!("join error: {e}")))?
+}
+
+#[async_trait]
+impl ScheduleStore for SqliteScheduleStore {
+    async fn schedule(&self, job: &ScheduledJob) -> Result<(), SchedulerError> {
+        job.validate()?;
+        let row = JobRow::from_job(job)?;
+        let pool = self.pool.clone();
+
+        run_blocking(move || {
+            let mut conn = pool
+                .get()
+                .map_err(|e| SchedulerError::Sink(format!("conn: {e}")))?;
+            diesel::replace_into(scheduled_commands::table)
+                .values(&row)
+                .execute(&mut conn)
+                .map_err(|e| SchedulerError::Sink(e.to_string()))?;
+            Ok(())
+        })
+        .await
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<ScheduledJob>, SchedulerError> {
+        let key = key.to_string();
+        let pool = self.pool.clone();
+
+        run_blocking(move || {
+            let mut conn = pool
+                .get()
+                .map_err(|e| SchedulerError::Sink(format!("conn: {e}")))?;
+            let row: Option<JobRow> = scheduled_commands::table
+                .find(&key)
+                .first(&mut conn)
+                .optional()
+                .map_err(|e| SchedulerError::Sink(e.to_string()))?;
+            row.map(JobRow::into_job).transpose()
+        })
+        .await
+    }
+
+    async fn cancel(&self, key: &str, now_us: i64) -> Result<bool, SchedulerError> {
+        let key = key.to_string();
+        let pool = self.pool.clone();
+
+        run_blocking(move || {
+            let mut conn = pool
+                .get()
+                .map_err(|e| SchedulerError::Sink(format!("conn: {e}")))?;
+            let updated = diesel::update(
+                scheduled_commands::table
+                    .filter(scheduled_commands::job_key.eq(&key))
+                    .filter(scheduled_commands::status.eq(JobStatus::Pending.as_str())),
+            )
+            .set((
+                scheduled_commands::status.eq(JobStatus::Cancelled.as_str()),
+                scheduled_commands::updated_at_us.eq(now_us),
+            ))
+            .execute(&mut conn)
+            .map_err(|e| SchedulerError::Sink(e.to_string()))?;
+            Ok(updated > 0)
+        })
+        .await
+    }
+
+    async fn claim_due(
+        &self,
+        now_us: i64,
+        stale_before_us: i64,
+        limit: usize,
+    ) -> Result<Vec<ScheduledJob>, SchedulerError> {
+        let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+        let pool = self.pool.clone();
+
+        run_blocking(move || {
+            let mut conn = pool
+                .get()
+                .map_err(|e| SchedulerError::Sink(format!("conn: {e}")))?;
+            let rows: Vec<JobRow> = scheduled_commands::table
+                .filter(
+                    scheduled_commands::status
+                        .eq(JobStatus::Pending.as_str())
+                        .and(scheduled_commands::due_at_us.le(now_us))
+                        .or(scheduled_commands::status
+                            .eq(JobStatus::Running.as_str())
+                            .and(scheduled_commands::updated_at_us.le(stale_before_us))),
+                )
+                .order(scheduled_commands::due_at_us.asc())
+                .limit(limit)
+                .load(&mut conn)
+                .map_err(|e| SchedulerError::Sink(e.to_string()))?;
+
+            let mut claimed = Vec::with_capacity(rows.len());
+            for mut row in rows {
+                // Conditional on the row being unchanged since it was read.
+                let updated = diesel::update(
+                    scheduled_commands::table
+                        .filter(scheduled_commands::job_key.eq(&row.job_key))
+                        .filter(scheduled_commands::job_id.eq(&row.job_id))
+                        .filter(scheduled_commands::status.eq(&row.status))
+                        .filter(scheduled_commands::updated_at_us.eq(row.updated_at_us)),
+                )
+                .set((
+                    scheduled_commands::status.eq(JobStatus::Running.as_str()),
+                    scheduled_commands::updated_at_us.eq(now_us),
+                ))
+                .execute(&mut conn)
+                .map_err(|e| SchedulerError::Sink(e.to_string()))?;
+                if updated == 1 {
+                    row.status = JobStatus::Running.as_str().to_string();
+                    row.updated_at_us = now_us;
+                    claimed.push(row.into_job()?);
+                }
+            }
+            Ok(claimed)
+        })
+        .await
+    }
+
+    async fn record(&self, job: &ScheduledJob) -> Result<bool, SchedulerError> {
+        let row = JobRow::from_job(job)?;
+        let pool = self.pool.clone();
+
+        run_blocking(move || {
+            let mut conn = pool
+                .get()
+                .map_err(|e| SchedulerError::Sink(format!("conn: {e}")))?;
+            let updated = diesel::update(
+                scheduled_commands::table
+                    .filter(scheduled_commands::job_key.eq(&row.job_key))
+                    .filter(scheduled_commands::job_id.eq(&row.job_id))
+                    .filter(scheduled_commands::status.eq(JobStatus::Running.as_str())),
+            )
+            .set(&row)
+            .execute(&mut conn)
+            .map_err(|e| SchedulerError::Sink(e.to_string()))?;
+            Ok(updated > 0)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+    use serde_json::json;
+
+    const MIGRATIONS: EmbeddedMigrations = embed_migrations!("../../migrations");
+    const NOW: i64 = 1_700_000_000_000_000;
+
+    async fn setup_store() -> SqliteScheduleStore {
+        let manager = ConnectionManager::<SqliteConnection>::new(":memory:");
+        let pool = Pool::builder().max_size(1).build(manager).expect("pool");
+        let mut conn = pool.get().unwrap();
+        conn.run_pending_migrations(MIGRATIONS).unwrap();
+        drop(conn);
+        SqliteScheduleStore::with_pool(pool)
+    }
+
+    fn job(key: &str, due_at_us: i64) -> ScheduledJob {
+        ScheduledJob {
+            key: key.into(),
+            job_id: Uuid::new_v4(),
+            command_type: "UserCommand".into(),
+            payload: json!({ "DeleteUser": { "id": key } }),
+            scheduled_by: "user-1".into(),
+            correlation_id: Uuid::new_v4(),
+            causation_id: Some(Uuid::new_v4()),
+            due_at_us,
+            status: JobStatus::Pending,
+            attempts: 0,
+            last_error: None,
+            created_at_us: NOW,
+            updated_at_us: NOW,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_schedule_replace_and_record() {
+        let s = setup_store().await;
+        let first = job("k", NOW);
+        s.schedule(&first).await.unwrap();
+        assert_eq!(s.get("k").await.unwrap(), Some(first.clone()));
+        assert_eq!(s.get("missing").await.unwrap(), None);
+
+        let mut second = job("k", NOW + 10);
+        second.causation_id = None;
+        s.schedule(&second).await.unwrap();
+        assert_eq!(s.get("k").await.unwrap(), Some(second.clone()));
+
+        // The replaced job's outcome is dropped.
+        assert!(!s.record(&first).await.unwrap());
+        // An unclaimed job's outcome is dropped too.
+        assert!(!s.record(&second).await.unwrap());
+
+        let mut second = s.claim_due(NOW + 10, 0, 10).await.unwrap().remove(0);
+        assert_eq!(second.status, JobStatus::Running);
+        second.status = JobStatus::Failed;
+        second.attempts = 1;
+        second.last_error = Some("rejected".into());
+        assert!(s.record(&second).await.unwrap());
+        assert_eq!(s.get("k").await.unwrap(), Some(second));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_only_pending_jobs() {
+        let s = setup_store().await;
+        s.schedule(&job("k", NOW)).await.unwrap();
+        assert!(s.cancel("k", NOW + 1).await.unwrap());
+        assert!(!s.cancel("k", NOW + 2).await.unwrap());
+        assert!(!s.cancel("missing", NOW).await.unwrap());
+
+        let cancelled = s.get("k").await.unwrap().unwrap();
+        assert_eq!(cancelled.status, JobStatus::Cancelled);
+        assert_eq!(cancelled.updated_at_us, NOW + 1);
+    }
+
+    #[tokio::test]
+    async fn test_claim_due_takes_pending_jobs_earliest_first() {
+        let s = setup_store().await;
+        s.schedule(&job("late", NOW)).await.unwrap();
+        s.schedule(&job("early", NOW - 5)).await.unwrap();
+        s.schedule(&job("future", NOW + 5)).await.unwrap();
+        let mut done = job("done", NOW - 10);
+        done.status = JobStatus::Done;
+        s.schedule(&done).await.unwrap();
+
+        let claimed = s.claim_due(NOW, 0, 1).await.unwrap();
+        let keys: Vec<_> = claimed.iter().map(|j| j.key.as_str()).collect();
+        assert_eq!(keys, ["early"]);
+        assert_eq!(claimed[0].status, JobStatus::Running);
+        assert_eq!(claimed[0].updated_at_us, NOW);
+
+        let claimed = s.claim_due(NOW, 0, 10).await.unwrap();
+        let keys: Vec<_> = claimed.iter().map(|j| j.key.as_str()).collect();
+        assert_eq!(keys, ["late"]);
+        assert!(s.claim_due(NOW, 0, 10).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_between_claim_and_record_is_refused() {
+        let s = setup_store().await;
+        s.schedule(&job("k", NOW)).await.unwrap();
+        let mut claimed = s.claim_due(NOW, 0, 10).await.unwrap().remove(0);
+
+        assert!(!s.cancel("k", NOW + 1).await.unwrap());
+        claimed.status = JobStatus::Done;
+        claimed.attempts = 1;
+        assert!(s.record(&claimed).await.unwrap());
+        assert_eq!(s.get("k").await.unwrap(), Some(claimed));
+    }
+
+    #[tokio::test]
+    async fn test_stale_claim_is_taken_over() {
+        let s = setup_store().await;
+        s.schedule(&job("k", NOW)).await.unwrap();
+        assert_eq!(s.claim_due(NOW, 0, 10).await.unwrap().len(), 1);
+
+        // Still fresh: claimed at NOW, only claims at or before NOW - 1 are stale.
+        assert!(s.claim_due(NOW + 5, NOW - 1, 10).await.unwrap().is_empty());
+        let taken = s.claim_due(NOW + 5, NOW, 10).await.unwrap();
+        assert_eq!(taken.len(), 1);
+        assert_eq!(taken[0].updated_at_us, NOW + 5);
+    }
+}
@@ -0,0 +1,231 @@
+//! Worker health state and the HTTP endpoint that reports it.
+//!
+//! `GET /health` answers `200` with a JSON body while the worker is healthy
+//! and `503` once it is stopping or any source has failed
+//! [`UNHEALTHY_AFTER_FAILURES`] polls in a row. Any other path is `404`.
+//! The server speaks just enough HTTP/1.1 for container healthchecks and
+//! load balancer probes.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+
+/// Consecutive failed polls after which a source marks the worker unhealthy.
+pub const UNHEALTHY_AFTER_FAILURES: u32 = 3;
+
+/// Largest request head the health endpoint reads.
+const MAX_REQUEST_BYTES: usize = 8 * 1024;
+
+/// Progress of one worker source.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct SourceStatus {
+    /// Events processed since the worker started
+    pub processed: u64,
+
+    /// Wall-clock time of the last successful poll, in microseconds
+    pub last_success_us: Option<i64>,
+
+    /// Error from the most recent failed poll, cleared on success
+    pub last_error: Option<String>,
+
+    /// Failed polls since the last success
+    pub consecutive_failures: u32,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct HealthState {
+    stopping: bool,
+    sources: BTreeMap<String, SourceStatus>,
+}
+
+/// Shared, cloneable view of a worker's health.
+#[derive(Clone, Default)]
+pub struct WorkerHealth {
+    state: Arc<Mutex<HealthState>>,
+}
+
+impl WorkerHealth {
+    pub(crate) fn register(&self, source: &str) {
+        self.lock().sources.entry(source.to_string()).or_default();
+    }
+
+    pub(crate) fn record_success(&self, source: &str, processed: usize) {
+        let mut state = self.lock();
+        let status = state.sources.entry(source.to_string()).or_default();
+        status.processed += processed as u64;
+        status.last_success_us = Some(now_us());
+        status.last_error = None;
+        status.consecutive_failures = 0;
+    }
+
+    pub(crate) fn record_failure(&self, source: &str, error: String) {
+        let mut state = self.lock();
+        let status = state.sources.entry(source.to_string()).or_default();
+        status.last_error = Some(error);
+        status.consecutive_failures += 1;
+    }
+
+    pub(crate) fn mark_stopping(&self) {
+        self.lock().stopping = true;
+    }
+
+    /// Status of `source`, if it is registered.
+    pub fn source(&self, source: &str) -> Option<SourceStatus> {
+        self.lock().sources.get(source).cloned()
+    }
+
+    /// `false` once the worker is stopping or any source has failed
+    /// [`UNHEALTHY_AFTER_FAILURES`] polls in a row.
+    pub fn is_healthy(&self) -> bool {
+        let state = self.lock();
+        !state.stopping
+            && state
+                .sources
+                .values()
+                .all(|s| s.consecutive_failures < UNHEALTHY_AFTER_FAILURES)
+    }
+
+    /// JSON body served by the health endpoint.
+    pub fn to_json(&self) -> serde_json::Value {
+        let healthy = self.is_healthy();
+        let state = self.lock();
+        serde_json::json!({
+            "status": if healthy { "ok" } else if state.stopping { "stopping" } else { "failing" },
+            "sources": state.sources,
+        })
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HealthState> {
+        // A poisoned lock only means a panic mid-update of plain counters.
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn now_us() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as i64)
+        .unwrap_or_default()
+}
+
+/// Serve `GET /health` on `listener` until `shutdown` turns `true`.
+pub async fn serve_health(
+    listener: TcpListener,
+    health: WorkerHealth,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    if let Ok(addr) = listener.local_addr() {
+        tracing::info!(%addr, "Worker health endpoint listening");
+    }
+    while !*shutdown.borrow() {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((stream, _)) => {
+                    let health = health.clone();
+                    tokio::spawn(async move {
+                        if let Err(e) = respond(stream, &health).await {
+                            tracing::debug!(error = %e, "Health request failed");
+                        }
+                    });
+                }
+                Err(e) => tracing::warn!(error = %e, "Health endpoint accept failed"),
+            },
+            changed = shutdown.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+}
+
+async fn respond(mut stream: TcpStream, health: &WorkerHealth) -> std::io::Result<()> {
+    let mut request = Vec::new();
+    let mut buf = [0u8; 1024];
+    while !request.windows(4).any(|w| w == b"\r\n\r\n") && request.len() < MAX_REQUEST_BYTES {
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        request.extend_from_slice(&buf[..n]);
+    }
+
+    let request_line = String::from_utf8_lossy(&request);
+    let mut parts = request_line.split_whitespace();
+    let (status, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/health")) if health.is_healthy() => ("200 OK", health.to_json()),
+        (Some("GET"), Some("/health")) => ("503 Service Unavailable", health.to_json()),
+        _ => ("404 Not Found", serde_json::json!({ "error": "not found" })),
+    };
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn get(addr: std::net::SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        stream
+            .write_all(format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n").as_bytes())
+            .await
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[test]
+    fn test_unhealthy_after_repeated_failures() {
+        let health = WorkerHealth::default();
+        health.register("projections");
+        for _ in 1..UNHEALTHY_AFTER_FAILURES {
+            health.record_failure("projections", "db locked".into());
+        }
+        assert!(health.is_healthy());
+
+        health.record_failure("projections", "db locked".into());
+        assert!(!health.is_healthy());
+
+        health.record_success("projections", 4);
+        assert!(health.is_healthy());
+        let status = health.source("projections").unwrap();
+        assert_eq!(status.processed, 4);
+        assert_eq!(status.last_error, None);
+    }
+
+    #[tokio::test]
+    async fn test_health_endpoint_reports_status() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let health = WorkerHealth::default();
+        health.record_success("projections", 2);
+        let (stop, stopped) = watch::channel(false);
+        let server = tokio::spawn(serve_health(listener, health.clone(), stopped));
+
+        let ok = get(addr, "/health").await;
+        assert!(ok.starts_with("HTTP/1.1 200 OK"));
+        assert!(ok.contains(r#""status":"ok""#));
+        assert!(ok.contains(r#""processed":2"#));
+
+        assert!(get(addr, "/other").await.starts_with("HTTP/1.1 404"));
+
+        health.mark_stopping();
+        let stopping = get(addr, "/health").await;
+        assert!(stopping.starts_with("HTTP/1.1 503"));
+        assert!(stopping.contains(r#""status":"stopping""#));
+
+        stop.send(true).unwrap();
+        server.await.unwrap();
+    }
+}
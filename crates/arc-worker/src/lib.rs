@@ -0,0 +1,243 @@
+//! # arc-worker
+//!
+//! Out-of-process runtime for projections and event handlers.
+//!
+//! A [`Worker`] polls a set of [`WorkerSource`]s until shutdown:
+//!
+//! - [`ProjectionSource`] catches every projection of a
+//!   [`ProjectionEngine`](arc_core::projection::ProjectionEngine) up from its
+//!   checkpoint, so read models stay current without the web process.
+//! - [`HandlerSource`] tails the event store for one [`EventHandler`] under
+//!   its own checkpoint (side effects such as email or webhooks).
+//!
+//! A source that fails is logged, recorded in [`WorkerHealth`] and retried
+//! on the next poll; it never stops the other sources. [`serve_health`]
+//! exposes that state over HTTP and [`shutdown_signal`] resolves on SIGTERM
+//! or Ctrl-C.
+//!
+//! ## Example
+//!
+//! ```rust,ignore
+//! let worker = Worker::new().with_source(ProjectionSource::new(engine));
+//! let (stop, stopped) = tokio::sync::watch::channel(false);
+//! let listener = tokio::net::TcpListener::bind("0.0.0.0:8081").await?;
+//! tokio::spawn(serve_health(listener, worker.health(), stopped.clone()));
+//! tokio::spawn(async move {
+//!     shutdown_signal().await;
+//!     let _ = stop.send(true);
+//! });
+//! worker.run(stopped).await;
+//! ```
+//!
+//! [`EventHandler`]: arc_core::event_bus::EventHandler
+
+pub mod health;
+pub mod source;
+
+pub use health::{serve_health, SourceStatus, WorkerHealth, UNHEALTHY_AFTER_FAILURES};
+pub use source::{HandlerSource, ProjectionSource, WorkerSource};
+
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Default pause between polls once every source is idle.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Polls [`WorkerSource`]s until shutdown.
+pub struct Worker {
+    sources: Vec<Arc<dyn WorkerSource>>,
+    poll_interval: Duration,
+    health: WorkerHealth,
+}
+
+impl Worker {
+    pub fn new() -> Self {
+        Self {
+            sources: Vec::new(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            health: WorkerHealth::default(),
+        }
+    }
+
+    /// Add a source. Sources are polled in the order they were added.
+    pub fn with_source(mut self, source: impl WorkerSource + 'static) -> Self {
+        self.health.register(source.name());
+        self.sources.push(Arc::new(source));
+        self
+    }
+
+    /// Pause between polls once every source is idle.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Handle on the worker's health state, e.g. for [`serve_health`].
+    pub fn health(&self) -> WorkerHealth {
+        self.health.clone()
+    }
+
+    /// Poll every source once. Returns how many events were processed in
+    /// total; failures are logged and recorded per source.
+    pub async fn poll_once(&self) -> usize {
+        let mut processed = 0;
+        for source in &self.sources {
+            match source.poll().await {
+                Ok(n) => {
+                    self.health.record_success(source.name(), n);
+                    processed += n;
+                }
+                Err(e) => {
+                    tracing::error!(source = source.name(), error = %e, "Worker source failed");
+                    self.health.record_failure(source.name(), e.to_string());
+                }
+            }
+        }
+        processed
+    }
+
+    /// Poll until `shutdown` turns `true` (or its sender is dropped).
+    ///
+    /// Polls back to back while sources report work and sleeps
+    /// `poll_interval` once they are idle. A poll in progress is finished
+    /// before the worker stops, so checkpoints reflect what was handled.
+    pub async fn run(&self, mut shutdown: watch::Receiver<bool>) {
+        tracing::info!(sources = self.sources.len(), "Worker started");
+        while !*shutdown.borrow() {
+            if self.poll_once().await > 0 {
+                continue;
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(self.poll_interval) => {}
+                changed = shutdown.changed() => {
+                    if changed.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+        self.health.mark_stopping();
+        tracing::info!("Worker stopped");
+    }
+}
+
+impl Default for Worker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Resolve on SIGTERM (container stop) or Ctrl-C.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!(error = %e, "Failed to listen for Ctrl-C");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to listen for SIGTERM");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Received Ctrl-C, shutting down"),
+        _ = terminate => tracing::info!("Received SIGTERM, shutting down"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Reports `work` events on its first polls, then idles; optionally fails.
+    struct StubSource {
+        name: &'static str,
+        work: AtomicUsize,
+        fail: bool,
+        polls: Arc<AtomicUsize>,
+    }
+
+    impl StubSource {
+        fn new(name: &'static str, work: usize, fail: bool) -> (Self, Arc<AtomicUsize>) {
+            let polls = Arc::new(AtomicUsize::new(0));
+            let source = Self {
+                name,
+                work: AtomicUsize::new(work),
+                fail,
+                polls: polls.clone(),
+            };
+            (source, polls)
+        }
+    }
+
+    #[async_trait]
+    impl WorkerSource for StubSource {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        async fn poll(&self) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+            self.polls.fetch_add(1, Ordering::SeqCst);
+            if self.fail {
+                return Err("store unavailable".into());
+            }
+            Ok(self.work.swap(0, Ordering::SeqCst))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failing_source_does_not_stop_others() {
+        let (broken, _) = StubSource::new("broken", 0, true);
+        let (healthy, healthy_polls) = StubSource::new("healthy", 3, false);
+        let worker = Worker::new().with_source(broken).with_source(healthy);
+
+        assert_eq!(worker.poll_once().await, 3);
+        assert_eq!(healthy_polls.load(Ordering::SeqCst), 1);
+
+        let health = worker.health();
+        assert_eq!(health.source("healthy").unwrap().processed, 3);
+        let broken = health.source("broken").unwrap();
+        assert_eq!(broken.consecutive_failures, 1);
+        assert_eq!(broken.last_error.as_deref(), Some("store unavailable"));
+    }
+
+    #[tokio::test]
+    async fn test_run_polls_until_shutdown() {
+        let (source, polls) = StubSource::new("projections", 2, false);
+        let worker = Arc::new(
+            Worker::new()
+                .with_source(source)
+                .with_poll_interval(Duration::from_millis(5)),
+        );
+        let (stop, stopped) = watch::channel(false);
+        let task = tokio::spawn({
+            let worker = worker.clone();
+            async move { worker.run(stopped).await }
+        });
+
+        while polls.load(Ordering::SeqCst) < 3 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        stop.send(true).unwrap();
+        task.await.unwrap();
+
+        assert!(!worker.health().is_healthy());
+        assert_eq!(worker.health().source("projections").unwrap().processed, 2);
+    }
+}
@@ -0,0 +1,288 @@
+//! Units of work a [`Worker`](crate::Worker) polls.
+
+use arc_core::event::GlobalPosition;
+use arc_core::event_bus::EventHandler;
+use arc_core::event_store::EventStore;
+use arc_core::projection::{
+    CheckpointStore, ProjectionEngine, ProjectionResult, DEFAULT_REBUILD_PAGE_SIZE,
+};
+use async_trait::async_trait;
+use futures::StreamExt;
+use std::sync::Arc;
+
+/// Something a worker drives: tails a log or a subscription and processes
+/// what is new.
+#[async_trait]
+pub trait WorkerSource: Send + Sync {
+    /// Name used in logs and health reports. Unique per worker.
+    fn name(&self) -> &str;
+
+    /// Process whatever is available and return how many events were
+    /// processed. Returning `0` lets the worker sleep before the next poll.
+    async fn poll(&self) -> Result<usize, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// Catches every projection of a [`ProjectionEngine`] up from its
+/// checkpoint. The engine needs a checkpoint store.
+pub struct ProjectionSource {
+    engine: Arc<ProjectionEngine>,
+}
+
+impl ProjectionSource {
+    pub fn new(engine: Arc<ProjectionEngine>) -> Self {
+        Self { engine }
+    }
+}
+
+#[async_trait]
+impl WorkerSource for ProjectionSource {
+    fn name(&self) -> &str {
+        "projections"
+    }
+
+    async fn poll(&self) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let mut read = 0;
+        for name in self.engine.projection_names() {
+            read += self.engine.catch_up(&name).await?;
+        }
+        Ok(read)
+    }
+}
+
+/// Tails the event store for one [`EventHandler`], tracking progress under
+/// `name` in a [`CheckpointStore`].
+///
+/// Events are handled in position order. A handler error stops the poll at
+/// that event, whose position is not saved, so it is retried on the next
+/// poll (at-least-once; handlers must be idempotent).
+pub struct HandlerSource {
+    name: String,
+    handler: Arc<dyn EventHandler>,
+    event_store: Arc<dyn EventStore>,
+    checkpoints: Arc<dyn CheckpointStore>,
+    page_size: usize,
+}
+
+impl HandlerSource {
+    pub fn new(
+        name: impl Into<String>,
+        handler: Arc<dyn EventHandler>,
+        event_store: Arc<dyn EventStore>,
+        checkpoints: Arc<dyn CheckpointStore>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            handler,
+            event_store,
+            checkpoints,
+            page_size: DEFAULT_REBUILD_PAGE_SIZE,
+        }
+    }
+
+    /// Events read per page (and between checkpoint saves). Clamped to at
+    /// least 1.
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    async fn save(&self, position: Option<GlobalPosition>) -> ProjectionResult<()> {
+        match position {
+            Some(position) => self.checkpoints.save(&self.name, position).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl WorkerSource for HandlerSource {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn poll(&self) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+        let from = self
+            .checkpoints
+            .load(&self.name)
+            .await?
+            .map(GlobalPosition::next)
+            .unwrap_or(GlobalPosition::START);
+        let handles = self.handler.handles();
+        let mut events = self.event_store.stream_all_from(from, self.page_size);
+        let mut read = 0usize;
+        let mut last = None;
+        while let Some(event) = events.next().await {
+            let event = event?;
+            if handles.contains(&event.event_type) {
+                if let Err(e) = self.handler.handle(&event).await {
+                    self.save(last).await?;
+                    return Err(format!(
+                        "{} failed on event {} ({}): {}",
+                        self.name, event.event_id, event.event_type, e
+                    )
+                    .into());
+                }
+            }
+            read += 1;
+            last = event.position.or(last);
+            if read.is_multiple_of(self.page_size) {
+                self.save(last).await?;
+            }
+        }
+        self.save(last).await?;
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arc_core::audit::AuditMetadata;
+    use arc_core::event::Event;
+    use arc_core::event_store::{InMemoryEventStore, VersionCheck};
+    use arc_core::projection::{InMemoryCheckpointStore, Projection};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::Mutex;
+
+    /// Records handled sequence numbers; fails once on `fail_on`.
+    struct RecordingHandler {
+        seen: Mutex<Vec<i64>>,
+        fail_on: Option<i64>,
+        failures: AtomicUsize,
+    }
+
+    impl RecordingHandler {
+        fn new(fail_on: Option<i64>) -> Arc<Self> {
+            Arc::new(Self {
+                seen: Mutex::new(Vec::new()),
+                fail_on,
+                failures: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl EventHandler for RecordingHandler {
+        fn handles(&self) -> Vec<String> {
+            vec!["Touched".to_string()]
+        }
+
+        async fn handle(
+            &self,
+            event: &Event,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            if Some(event.sequence) == self.fail_on
+                && self.failures.fetch_add(1, Ordering::SeqCst) == 0
+            {
+                return Err("smtp down".into());
+            }
+            self.seen.lock().await.push(event.sequence);
+            Ok(())
+        }
+    }
+
+    async fn store_with(n: i64) -> Arc<InMemoryEventStore> {
+        let store = Arc::new(InMemoryEventStore::new());
+        for seq in 1..=n {
+            let event = Event::new("User", "u1", seq, "Touched", json!({}))
+                .with_audit(AuditMetadata::test_default());
+            store
+                .append("u1", VersionCheck::Expected(seq - 1), vec![event])
+                .await
+                .unwrap();
+        }
+        store
+    }
+
+    #[tokio::test]
+    async fn test_handler_source_resumes_from_checkpoint() {
+        let store = store_with(3).await;
+        let checkpoints = Arc::new(InMemoryCheckpointStore::new());
+        let handler = RecordingHandler::new(None);
+        let source = HandlerSource::new(
+            "mailer",
+            handler.clone(),
+            store.clone(),
+            checkpoints.clone(),
+        )
+        .with_page_size(2);
+
+        assert_eq!(source.poll().await.unwrap(), 3);
+        assert_eq!(
+            checkpoints.load("mailer").await.unwrap(),
+            Some(GlobalPosition(3))
+        );
+        assert_eq!(source.poll().await.unwrap(), 0);
+
+        let event = Event::new("User", "u1", 4, "Touched", json!({}))
+            .with_audit(AuditMetadata::test_default());
+        store
+            .append("u1", VersionCheck::Expected(3), vec![event])
+            .await
+            .unwrap();
+        assert_eq!(source.poll().await.unwrap(), 1);
+        assert_eq!(*handler.seen.lock().await, vec![1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn test_handler_failure_is_retried_from_failed_event() {
+        let store = store_with(3).await;
+        let checkpoints = Arc::new(InMemoryCheckpointStore::new());
+        let handler = RecordingHandler::new(Some(2));
+        let source = HandlerSource::new("mailer", handler.clone(), store, checkpoints.clone());
+
+        let err = source.poll().await.unwrap_err();
+        assert!(err.to_string().contains("smtp down"));
+        assert_eq!(
+            checkpoints.load("mailer").await.unwrap(),
+            Some(GlobalPosition(1))
+        );
+
+        assert_eq!(source.poll().await.unwrap(), 2);
+        assert_eq!(*handler.seen.lock().await, vec![1, 2, 3]);
+    }
+
+    /// Counts handled events.
+    struct CountingProjection(Arc<AtomicUsize>);
+
+    #[async_trait]
+    impl Projection for CountingProjection {
+        fn name(&self) -> &str {
+            "touches"
+        }
+
+        fn handles(&self) -> Vec<String> {
+            vec!["Touched".to_string()]
+        }
+
+        async fn handle(&self, _event: &Event) -> ProjectionResult<()> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn clear(&self) -> ProjectionResult<()> {
+            self.0.store(0, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_projection_source_catches_up_engine() {
+        let store = store_with(2).await;
+        let checkpoints = Arc::new(InMemoryCheckpointStore::new());
+        let handled = Arc::new(AtomicUsize::new(0));
+        let mut engine = ProjectionEngine::new(Box::new(store.as_ref().clone()))
+            .with_checkpoint_store(checkpoints.clone());
+        engine.register(Box::new(CountingProjection(handled.clone())));
+        let source = ProjectionSource::new(Arc::new(engine));
+
+        assert_eq!(source.poll().await.unwrap(), 2);
+        assert_eq!(source.poll().await.unwrap(), 0);
+        assert_eq!(handled.load(Ordering::SeqCst), 2);
+        assert_eq!(
+            checkpoints.load("touches").await.unwrap(),
+            Some(GlobalPosition(2))
+        );
+    }
+}
@@ -0,0 +1,18 @@
+-- Aggregate snapshots: the latest serialized state per aggregate, written by
+-- `CommandBus` every `SnapshotPolicy::EveryNEvents(n)` events so dispatch can
+-- restore state and replay only the tail of the stream.
+--
+-- Snapshots are a cache, not a source of truth. One row per aggregate; saving
+-- replaces the previous row. `schema_version` is the aggregate's
+-- `snapshot_schema_version()` at write time — rows with a stale schema are
+-- ignored on load and overwritten by the next snapshot. Truncating this table
+-- is always safe.
+
+CREATE TABLE snapshots (
+    aggregate_id   TEXT    NOT NULL PRIMARY KEY,
+    aggregate_type TEXT    NOT NULL,
+    version        BIGINT  NOT NULL,
+    schema_version INTEGER NOT NULL,
+    state          TEXT    NOT NULL,
+    taken_at_us    BIGINT  NOT NULL
+);
@@ -0,0 +1,2 @@
+ALTER TABLE events DROP COLUMN global_signature;
+ALTER TABLE events DROP COLUMN signature;
@@ -0,0 +1,21 @@
+-- HIPAA-5 wiring: every event row carries two HMAC-SHA256 chain signatures.
+-- §164.312(c)(1).
+--
+--   signature        = HMAC(key, prev signature of the same aggregate || canonical(event))
+--   global_signature = HMAC(key, prev global_signature by id              || canonical(event))
+--
+-- The per-aggregate chain lets `load` verify one stream cheaply; the global
+-- chain additionally detects whole-aggregate deletion and cross-aggregate
+-- reordering. Both are computed by `SqliteEventStore::append` inside the
+-- append transaction.
+--
+-- Rows written before this migration cannot be signed in SQL (the key lives
+-- with the application), so they are back-filled with the genesis marker
+-- `''` (`EventSignature::genesis()`). Verification accepts genesis-marked rows
+-- only as a leading prefix of a chain — the first signed row chains from
+-- genesis, and a genesis row after any signed row is reported as tampering.
+
+ALTER TABLE events ADD COLUMN signature TEXT NOT NULL DEFAULT '';
+ALTER TABLE events ADD COLUMN global_signature TEXT NOT NULL DEFAULT '';
+
+UPDATE events SET signature = '', global_signature = '';
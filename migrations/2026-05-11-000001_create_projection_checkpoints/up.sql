@@ -0,0 +1,14 @@
+-- Projection checkpoints: the last global event position (`events.id`) each
+-- projection has applied. `ProjectionEngine` writes a row after every event
+-- it processes and after rebuilds; `ProjectionEngine::catch_up` resumes from
+-- it on startup so events appended but never published are not lost.
+--
+-- Positions only move forward: the store's upsert ignores a lower position.
+-- Deleting a row makes the next catch-up replay that projection from the
+-- start of the log, which idempotent projectors tolerate.
+
+CREATE TABLE projection_checkpoints (
+    projection    TEXT   NOT NULL PRIMARY KEY,
+    position      BIGINT NOT NULL,
+    updated_at_us BIGINT NOT NULL
+);
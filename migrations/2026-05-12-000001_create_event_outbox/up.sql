@@ -0,0 +1,20 @@
+-- Transactional outbox: one row per event, inserted by `SqliteEventStore`
+-- (when built `with_outbox()`) in the same transaction as the event itself,
+-- so every committed event is queued for delivery to the `EventBus`.
+--
+-- `position` is the event's `events.id`. `CommandBus` marks rows delivered
+-- after a successful inline publish; `OutboxRelay` redelivers the rest in
+-- position order, counting `attempts` and keeping `last_error` for
+-- operators. Delivered rows may be pruned; pending rows must not be.
+
+CREATE TABLE event_outbox (
+    position        BIGINT  NOT NULL PRIMARY KEY,
+    attempts        INTEGER NOT NULL DEFAULT 0,
+    last_error      TEXT,
+    created_at_us   BIGINT  NOT NULL,
+    delivered_at_us BIGINT
+);
+
+-- The relay only ever scans undelivered rows.
+CREATE INDEX idx_event_outbox_pending ON event_outbox (position)
+    WHERE delivered_at_us IS NULL;
@@ -0,0 +1,9 @@
+-- Payload schema version per event (`Event::schema_version`). Stored
+-- payloads are never rewritten; `UpcastingEventStore` migrates old shapes
+-- to the current one on read, choosing upcasters by
+-- `(event_type, schema_version)`.
+--
+-- Every existing row was written before versions existed, so it is
+-- version 1.
+
+ALTER TABLE events ADD COLUMN schema_version INTEGER NOT NULL DEFAULT 1;
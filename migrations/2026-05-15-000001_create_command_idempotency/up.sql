@@ -0,0 +1,16 @@
+-- Idempotency keys for command dispatch. A row is reserved (event_ids NULL)
+-- while the command runs and completed with the produced event ids, so a
+-- repeated request replays the original result instead of re-executing.
+
+CREATE TABLE command_idempotency (
+    actor_id TEXT NOT NULL,
+    idempotency_key TEXT NOT NULL,
+    created_at_us BIGINT NOT NULL,
+    expires_at_us BIGINT NOT NULL,
+    aggregate_type TEXT,
+    aggregate_id TEXT,
+    event_ids TEXT,
+    PRIMARY KEY (actor_id, idempotency_key)
+);
+
+CREATE INDEX idx_command_idempotency_expires_at ON command_idempotency(expires_at_us);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_process_instances_due;
+DROP TABLE IF EXISTS process_instances;
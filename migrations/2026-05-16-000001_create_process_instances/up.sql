@@ -0,0 +1,20 @@
+-- Durable state of process managers (sagas). One row per running or
+-- finished instance; `version` guards against concurrent steps and
+-- `deadline_us` is polled for timeouts while the instance is running.
+
+CREATE TABLE process_instances (
+    process_name TEXT NOT NULL,
+    instance_id TEXT NOT NULL,
+    correlation_id TEXT NOT NULL,
+    status TEXT NOT NULL,
+    state TEXT NOT NULL,
+    version BIGINT NOT NULL,
+    deadline_us BIGINT,
+    handled_event_ids TEXT NOT NULL,
+    failure TEXT,
+    created_at_us BIGINT NOT NULL,
+    updated_at_us BIGINT NOT NULL,
+    PRIMARY KEY (process_name, instance_id)
+);
+
+CREATE INDEX idx_process_instances_due ON process_instances(process_name, status, deadline_us);
@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS idx_scheduled_commands_due;
+DROP TABLE IF EXISTS scheduled_commands;
@@ -0,0 +1,21 @@
+-- Commands scheduled to be dispatched later. One row per key; scheduling
+-- under an existing key replaces the row with a new `job_id`. Pending rows
+-- are polled by `due_at_us`.
+
+CREATE TABLE scheduled_commands (
+    job_key TEXT PRIMARY KEY NOT NULL,
+    job_id TEXT NOT NULL,
+    command_type TEXT NOT NULL,
+    payload TEXT NOT NULL,
+    scheduled_by TEXT NOT NULL,
+    correlation_id TEXT NOT NULL,
+    causation_id TEXT,
+    due_at_us BIGINT NOT NULL,
+    status TEXT NOT NULL,
+    attempts INTEGER NOT NULL,
+    last_error TEXT,
+    created_at_us BIGINT NOT NULL,
+    updated_at_us BIGINT NOT NULL
+);
+
+CREATE INDEX idx_scheduled_commands_due ON scheduled_commands(status, due_at_us);
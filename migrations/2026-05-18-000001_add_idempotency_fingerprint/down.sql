@@ -0,0 +1 @@
+ALTER TABLE command_idempotency DROP COLUMN fingerprint;
@@ -0,0 +1,8 @@
+-- Hash of the command a completed idempotency key was used for
+-- (`Command::idempotency_fingerprint`). A repeat of the key with a different
+-- command is refused instead of replaying this one's result.
+--
+-- Rows written before fingerprints existed keep NULL and are only checked
+-- by aggregate type.
+
+ALTER TABLE command_idempotency ADD COLUMN fingerprint TEXT;
@@ -50,20 +50,34 @@ impl arc_core::aggregate::Command for ${ENTITY_PASCAL}Command {
 EOF
 
 cat > "$TARGET_DIR/events.rs" <<EOF
+use arc_core::domain_event::DomainEvent;
 use serde::{Deserialize, Serialize};
 
-#[allow(dead_code)]
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(tag = "type", rename_all = "snake_case")]
+/// Typed \`${ENTITY_PASCAL}\` event payloads. The variant name is the stored
+/// \`event_type\` and the variant's fields are the payload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
 pub enum ${ENTITY_PASCAL}DomainEvent {
     ${ENTITY_PASCAL}Created { id: String },
 }
+
+impl DomainEvent for ${ENTITY_PASCAL}DomainEvent {
+    const EVENT_TYPES: &'static [&'static str] = &["${ENTITY_PASCAL}Created"];
+
+    fn event_type(&self) -> &'static str {
+        match self {
+            Self::${ENTITY_PASCAL}Created { .. } => "${ENTITY_PASCAL}Created",
+        }
+    }
+}
 EOF
 
 cat > "$TARGET_DIR/aggregate.rs" <<EOF
 use crate::domain::${ENTITY_LOWER}::commands::${ENTITY_PASCAL}Command;
-use async_trait::async_trait;
+use crate::domain::${ENTITY_LOWER}::events::${ENTITY_PASCAL}DomainEvent;
+use arc_core::domain_event::DomainEventError;
 use arc_core::{aggregate::Aggregate, event::Event};
+use async_trait::async_trait;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -72,6 +86,8 @@ pub enum ${ENTITY_PASCAL}AggregateError {
     AlreadyExists,
     #[error("${ENTITY_LOWER} not found")]
     NotFound,
+    #[error("failed to encode ${ENTITY_LOWER} event: {0}")]
+    Encode(#[from] DomainEventError),
 }
 
 #[derive(Default)]
@@ -84,7 +100,7 @@ pub struct ${ENTITY_PASCAL}Aggregate {
 #[async_trait]
 impl Aggregate for ${ENTITY_PASCAL}Aggregate {
     type Command = ${ENTITY_PASCAL}Command;
-    type Event = ();
+    type Event = ${ENTITY_PASCAL}DomainEvent;
     type Error = ${ENTITY_PASCAL}AggregateError;
 
     fn aggregate_type() -> &'static str {
@@ -97,37 +113,66 @@ impl Aggregate for ${ENTITY_PASCAL}Aggregate {
 
     async fn handle(&self, cmd: Self::Command) -> Result<Vec<Event>, Self::Error> {
         match cmd {
-            ${ENTITY_PASCAL}Command::Create${ENTITY_PASCAL} { ref id } => {
+            ${ENTITY_PASCAL}Command::Create${ENTITY_PASCAL} { id } => {
                 if self.exists {
                     return Err(${ENTITY_PASCAL}AggregateError::AlreadyExists);
                 }
-                Ok(vec![Event::new(
-                    "${ENTITY_PASCAL}",
-                    id,
+                let event = ${ENTITY_PASCAL}DomainEvent::${ENTITY_PASCAL}Created { id: id.clone() };
+                Ok(vec![Event::from_domain(
+                    Self::aggregate_type(),
+                    &id,
                     self.version + 1,
-                    "${ENTITY_PASCAL}Created",
-                    serde_json::json!({ "id": id }),
-                )])
+                    &event,
+                )?])
             }
         }
     }
 
     fn apply(&mut self, event: &Event) {
         self.version = event.sequence;
-        match event.event_type.as_str() {
-            "${ENTITY_PASCAL}Created" => {
-                self.id = Some(event.payload["id"].as_str().unwrap().to_string());
-                self.exists = true;
-            }
-            _ => {}
+        if let Ok(${ENTITY_PASCAL}DomainEvent::${ENTITY_PASCAL}Created { id }) = event.decode() {
+            self.id = Some(id);
+            self.exists = true;
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arc_core::testing::AggregateFixture;
+
+    fn create() -> ${ENTITY_PASCAL}Command {
+        ${ENTITY_PASCAL}Command::Create${ENTITY_PASCAL} { id: "id-1".into() }
+    }
+
+    fn created() -> ${ENTITY_PASCAL}DomainEvent {
+        ${ENTITY_PASCAL}DomainEvent::${ENTITY_PASCAL}Created { id: "id-1".into() }
+    }
+
+    #[tokio::test]
+    async fn test_create_emits_created() {
+        AggregateFixture::<${ENTITY_PASCAL}Aggregate>::new("id-1")
+            .when(create())
+            .await
+            .then_expect_events([created()]);
+    }
+
+    #[tokio::test]
+    async fn test_cannot_create_twice() {
+        AggregateFixture::<${ENTITY_PASCAL}Aggregate>::new("id-1")
+            .given([created()])
+            .when(create())
+            .await
+            .then_expect_error("${ENTITY_LOWER} already exists");
+    }
+}
 EOF
 
 echo "Created $TARGET_DIR"
 echo
 echo "Next steps:"
 echo "  1. Add 'pub mod $ENTITY_LOWER;' to crates/arc-app/src/domain/mod.rs"
-echo "  2. Register a CommandBus<${ENTITY_PASCAL}Aggregate> in commands/serve.rs"
+echo "  2. Register ${ENTITY_PASCAL}Aggregate in helpers::es_stack::register_aggregates"
 echo "  3. Add controller routes that dispatch ${ENTITY_PASCAL}Command"
+echo "  4. Run: cargo test -p arc domain::$ENTITY_LOWER"